@@ -58,7 +58,7 @@ impl AirBuilder {
         &mut self,
         source_filename: &str,
         version: u64,
-        triple: &[&'static str],
+        triple: &[&str],
         data_layout: &str,
     ) -> Result<()> {
         self.file.items.push(AirItem::Module(AirModule::default()));
@@ -138,6 +138,8 @@ impl AirBuilder {
             ..Default::default()
         });
 
+        module.value_list.push(AirValue::Function(id));
+
         module.max_global_id += 1;
         Ok(id)
     }
@@ -0,0 +1,343 @@
+use anyhow::{Result, anyhow};
+
+use crate::{
+    spirv_builder::SpirVBuilder,
+    spirv_parser::{
+        SpirVConstantValue, SpirVDecorate, SpirVDecorateType, SpirVOp, SpirVType, SpirVVariableId,
+    },
+};
+
+use super::{AirToSpirV, ArgumentBufferArgument};
+
+/// Size and alignment of a type in a Metal buffer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MetalLayout {
+    pub size: u32,
+    pub alignment: u32,
+}
+
+impl MetalLayout {
+    /// Distance between two elements of an array.
+    pub fn stride(&self) -> u32 {
+        self.size.next_multiple_of(self.alignment)
+    }
+
+    /// A vector of `count` scalars. `float3` takes the space of a `float4`,
+    /// `packed_float3` is exactly three floats aligned like one.
+    pub fn vector(scalar_size: u32, count: u32, packed: bool) -> Self {
+        match packed {
+            true => Self {
+                size: scalar_size * count,
+                alignment: scalar_size,
+            },
+            false => {
+                let size = scalar_size * count.next_power_of_two();
+                Self {
+                    size,
+                    alignment: size,
+                }
+            }
+        }
+    }
+}
+
+impl AirToSpirV {
+    /// Layout of a Metal type name such as `float3`, `packed_half4`,
+    /// `simd::float3` or `float4x4`.
+    pub fn metal_type_name_layout(type_name: &str) -> Option<MetalLayout> {
+        let type_name = type_name.rsplit("::").next()?;
+
+        if let Some((columns, rows)) = Self::matrix_dimensions(type_name) {
+            let scalar_size = match type_name.starts_with("half") {
+                true => 2,
+                false => 4,
+            };
+            let column = MetalLayout::vector(scalar_size, rows, false);
+
+            return Some(MetalLayout {
+                size: column.size * columns,
+                alignment: column.alignment,
+            });
+        }
+
+        let (packed, type_name) = match type_name.strip_prefix("packed_") {
+            Some(type_name) => (true, type_name),
+            None => (false, type_name),
+        };
+        let (scalar, count) = match type_name.strip_suffix(['2', '3', '4']) {
+            Some(scalar) => (scalar, type_name[scalar.len()..].parse::<u32>().ok()?),
+            None => (type_name, 1),
+        };
+
+        let scalar_size = match scalar {
+            "bool" | "char" | "uchar" => 1,
+            "short" | "ushort" | "half" => 2,
+            "int" | "uint" | "float" => 4,
+            "long" | "ulong" => 8,
+            _ => return None,
+        };
+
+        Some(MetalLayout::vector(scalar_size, count, packed))
+    }
+
+    /// Layout of `ty` under Metal's rules, honoring `Offset` and
+    /// `ArrayStride` decorations it already has.
+    pub fn metal_layout(builder: &SpirVBuilder, ty: SpirVVariableId) -> Result<MetalLayout> {
+        let ty_value = builder
+            .module
+            .type_table
+            .get(&ty)
+            .ok_or(anyhow!("Type {:?} not found.", ty))?;
+
+        Ok(match ty_value.clone() {
+            SpirVType::Bool => MetalLayout {
+                size: 1,
+                alignment: 1,
+            },
+            SpirVType::Int(width, _) | SpirVType::Float(width) => MetalLayout {
+                size: width / 8,
+                alignment: width / 8,
+            },
+            SpirVType::Pointer(..) => MetalLayout {
+                size: 8,
+                alignment: 8,
+            },
+            SpirVType::Vector(scalar_ty, count) => {
+                MetalLayout::vector(Self::metal_layout(builder, scalar_ty)?.size, count, false)
+            }
+            SpirVType::Matrix(column_ty, count) => {
+                let column = Self::metal_layout(builder, column_ty)?;
+                MetalLayout {
+                    size: column.size * count,
+                    alignment: column.alignment,
+                }
+            }
+            SpirVType::Array(element_ty, length) => {
+                let element = Self::metal_layout(builder, element_ty)?;
+                let stride = Self::array_stride(builder, ty).unwrap_or(element.stride());
+                let length = match builder.module.constants_table[&length].value {
+                    SpirVConstantValue::UnsignedInteger(length) => length,
+                    SpirVConstantValue::SignedInteger(length) => length as u64,
+                    _ => 0,
+                };
+
+                MetalLayout {
+                    size: stride * length as u32,
+                    alignment: element.alignment,
+                }
+            }
+            SpirVType::RuntimeArray(element_ty) => MetalLayout {
+                size: 0,
+                alignment: Self::metal_layout(builder, element_ty)?.alignment,
+            },
+            SpirVType::Struct(members) => {
+                let offsets = Self::member_offsets(builder, ty);
+
+                let mut size = 0u32;
+                let mut alignment = 1;
+                for (index, member_ty) in members.into_iter().enumerate() {
+                    let member = Self::metal_layout(builder, member_ty)?;
+                    let offset = match offsets.get(index).copied().flatten() {
+                        Some(offset) => offset,
+                        None => size.next_multiple_of(member.alignment),
+                    };
+
+                    size = size.max(offset + member.size);
+                    alignment = alignment.max(member.alignment);
+                }
+
+                MetalLayout {
+                    size: size.next_multiple_of(alignment),
+                    alignment,
+                }
+            }
+            ty => return Err(anyhow!("{:?} has no Metal layout.", ty)),
+        })
+    }
+
+    /// Decorates `ty` and everything it contains with Metal's layout.
+    /// Struct members take their offsets from `members`, the buffer's
+    /// `air.struct_type_info`, when it describes them.
+    ///
+    /// Packed vectors can end up at offsets only `scalarBlockLayout`
+    /// allows, `check_extended_layout` rejects those when it's missing.
+    pub fn decorate_metal_layout(
+        builder: &mut SpirVBuilder,
+        ty: SpirVVariableId,
+        members: &[ArgumentBufferArgument],
+    ) -> Result<()> {
+        match builder.module.type_table[&ty].clone() {
+            SpirVType::Struct(member_types) => {
+                if !Self::member_offsets(builder, ty).is_empty() {
+                    return Ok(());
+                }
+
+                let members = match members.len() == member_types.len() {
+                    true => members,
+                    false => &[],
+                };
+
+                let mut offset = 0;
+                for (index, member_ty) in member_types.into_iter().enumerate() {
+                    Self::decorate_metal_layout(builder, member_ty, &[])?;
+                    let member = Self::metal_layout(builder, member_ty)?;
+
+                    offset = match members.get(index) {
+                        Some(argument) => argument.offset as u32,
+                        None => offset.next_multiple_of(member.alignment),
+                    };
+                    builder.set_member_decorate(ty, index, SpirVDecorateType::Offset(offset));
+                    offset += member.size;
+                }
+            }
+            SpirVType::Array(element_ty, _) | SpirVType::RuntimeArray(element_ty) => {
+                Self::decorate_metal_layout(builder, element_ty, &[])?;
+
+                if Self::array_stride(builder, ty).is_none() {
+                    let stride = Self::metal_layout(builder, element_ty)?.stride();
+                    builder.set_decorate(
+                        ty,
+                        SpirVDecorate {
+                            ty: SpirVDecorateType::ArrayStride(stride),
+                            member_decorates: vec![],
+                        },
+                    );
+                }
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    /// Checks the decorated layout of `ty` against Vulkan's extended
+    /// layout, what buffers are held to without `scalarBlockLayout`.
+    pub fn check_extended_layout(builder: &SpirVBuilder, ty: SpirVVariableId) -> Result<()> {
+        match builder.module.type_table[&ty].clone() {
+            SpirVType::Struct(member_types) => {
+                let offsets = Self::member_offsets(builder, ty);
+
+                for (index, member_ty) in member_types.into_iter().enumerate() {
+                    Self::check_extended_layout(builder, member_ty)?;
+
+                    let Some(offset) = offsets.get(index).copied().flatten() else {
+                        continue;
+                    };
+                    if offset % Self::extended_alignment(builder, member_ty)? != 0 {
+                        return Err(anyhow!(
+                            "Member {} of {:?} at offset {} needs `scalarBlockLayout`.",
+                            index,
+                            ty,
+                            offset
+                        ));
+                    }
+                }
+            }
+            SpirVType::Array(element_ty, _) | SpirVType::RuntimeArray(element_ty) => {
+                Self::check_extended_layout(builder, element_ty)?;
+
+                let alignment = Self::extended_alignment(builder, element_ty)?;
+                if let Some(stride) = Self::array_stride(builder, ty)
+                    && stride % alignment != 0
+                {
+                    return Err(anyhow!(
+                        "Array {:?} with stride {} needs `scalarBlockLayout`.",
+                        ty,
+                        stride
+                    ));
+                }
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    /// Base alignment of `ty` under Vulkan's extended layout. Unlike
+    /// Metal's, it doesn't depend on whether a vector is packed.
+    fn extended_alignment(builder: &SpirVBuilder, ty: SpirVVariableId) -> Result<u32> {
+        Ok(match builder.module.type_table[&ty].clone() {
+            SpirVType::Vector(scalar_ty, count) => {
+                let scalar = Self::extended_alignment(builder, scalar_ty)?;
+                match count {
+                    2 => scalar * 2,
+                    _ => scalar * 4,
+                }
+            }
+            SpirVType::Matrix(column_ty, _) => Self::extended_alignment(builder, column_ty)?,
+            SpirVType::Array(element_ty, _) | SpirVType::RuntimeArray(element_ty) => {
+                Self::extended_alignment(builder, element_ty)?
+            }
+            SpirVType::Struct(member_types) => {
+                let mut alignment = 1;
+                for member_ty in member_types {
+                    alignment = alignment.max(Self::extended_alignment(builder, member_ty)?);
+                }
+                alignment
+            }
+            _ => Self::metal_layout(builder, ty)?.alignment,
+        })
+    }
+
+    /// An array type decorated with `stride`. Arrays that only differ in
+    /// their stride, like `float3` and `packed_float3` buffers, get
+    /// separate types.
+    pub fn strided_array_type(
+        builder: &mut SpirVBuilder,
+        ty: SpirVType,
+        stride: u32,
+    ) -> SpirVVariableId {
+        let existing = builder
+            .module
+            .type_table
+            .iter()
+            .filter(|(_, x)| **x == ty)
+            .map(|(id, _)| *id)
+            .collect::<Vec<_>>();
+
+        if let Some(id) = existing
+            .iter()
+            .find(|x| Self::array_stride(builder, **x) == Some(stride))
+        {
+            return *id;
+        }
+
+        let id = match existing.is_empty() {
+            true => builder.new_type(ty),
+            false => builder.new_distinct_type(ty),
+        };
+        builder.set_decorate(
+            id,
+            SpirVDecorate {
+                ty: SpirVDecorateType::ArrayStride(stride),
+                member_decorates: vec![],
+            },
+        );
+
+        id
+    }
+
+    fn array_stride(builder: &SpirVBuilder, ty: SpirVVariableId) -> Option<u32> {
+        match builder.module.decorate_table.get(&ty)?.ty {
+            SpirVDecorateType::ArrayStride(stride) => Some(stride),
+            _ => None,
+        }
+    }
+
+    /// `Offset` of every member decorated so far, empty if there are none.
+    fn member_offsets(builder: &SpirVBuilder, ty: SpirVVariableId) -> Vec<Option<u32>> {
+        let mut result = vec![];
+        for i in &builder.module.operands {
+            if let SpirVOp::MemberDecorate(id, member, SpirVDecorateType::Offset(offset)) = i
+                && *id == ty
+            {
+                if result.len() <= *member {
+                    result.resize(*member + 1, None);
+                }
+                result[*member] = Some(*offset);
+            }
+        }
+
+        result
+    }
+}
@@ -0,0 +1,675 @@
+use std::collections::{HashMap, HashSet};
+
+use anyhow::{Result, anyhow};
+
+use crate::{
+    air_parser::{AirConstantValue, AirGetElementPtr, AirModule, AirValue, AirValueId},
+    llvm_bitcode::BinaryOpCode,
+    spirv_builder::SpirVBuilder,
+    spirv_parser::{
+        SpirVBinaryOp, SpirVCompositeConstruct, SpirVCompositeExtract, SpirVDecorateType,
+        SpirVLoad, SpirVMemoryOperands, SpirVOpCode, SpirVType, SpirVVariableId,
+    },
+};
+
+use super::AirToSpirV;
+
+/// Where the columns of a matrix are read from.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MatrixSource {
+    /// `extractvalue %aggregate, prefix..., column`
+    Extract {
+        aggregate: AirValueId,
+        prefix: Vec<u64>,
+    },
+    /// `load (getelementptr ..., column)`, kept without the column index.
+    Load(AirGetElementPtr),
+}
+
+/// A matrix product the Metal compiler expanded into column arithmetic.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MatrixProduct {
+    /// `matrix * vector`, the sum of each column times a splat of the
+    /// matching vector component.
+    Vector {
+        matrix: MatrixSource,
+        vector: AirValueId,
+        columns: u32,
+    },
+    /// `a * b`, an `insertvalue` chain of `a` times each column of `b`.
+    Matrix {
+        a: MatrixSource,
+        b: MatrixSource,
+        columns: u32,
+    },
+}
+
+impl AirToSpirV {
+    /// Columns and rows of a Metal matrix type, from names like `float4x4`,
+    /// `half3x2` or `struct.metal::matrix<float, 4, 4, void>`.
+    pub fn matrix_dimensions(name: &str) -> Option<(u32, u32)> {
+        let dimension = |x: &str| x.trim().parse::<u32>().ok().filter(|x| (2..=4).contains(x));
+
+        if let Some((_, parameters)) = name.split_once("matrix<") {
+            let mut parameters = parameters.trim_end_matches('>').split(',');
+            parameters.next()?;
+            return Some((
+                dimension(parameters.next()?)?,
+                dimension(parameters.next()?)?,
+            ));
+        }
+
+        let name = name.rsplit([':', '.']).next()?;
+        let (columns, rows) = name
+            .strip_prefix("float")
+            .or(name.strip_prefix("half"))?
+            .split_once('x')?;
+
+        Some((dimension(columns)?, dimension(rows)?))
+    }
+
+    /// `OpTypeMatrix` for an array of float vectors, how AIR stores a
+    /// matrix's columns.
+    pub fn matrix_type(builder: &mut SpirVBuilder, ty: SpirVVariableId) -> Option<SpirVVariableId> {
+        let (column_ty, length) = match builder.module.type_table.get(&ty)? {
+            SpirVType::Array(column_ty, length) => (*column_ty, *length),
+            _ => return None,
+        };
+
+        match builder.module.type_table.get(&column_ty)? {
+            SpirVType::Vector(scalar_ty, _) => {
+                if !matches!(builder.module.type_table[scalar_ty], SpirVType::Float(_)) {
+                    return None;
+                }
+            }
+            _ => return None,
+        }
+
+        let columns = Self::spirv_constant_to_literal(builder, length).ok()? as u32;
+        Some(builder.new_type(SpirVType::Matrix(column_ty, columns)))
+    }
+
+    /// Gives a matrix member of `struct_ty`, or an array of them, its
+    /// column-major layout.
+    pub fn decorate_matrix_member(
+        builder: &mut SpirVBuilder,
+        struct_ty: SpirVVariableId,
+        member: usize,
+        mut member_ty: SpirVVariableId,
+    ) -> Result<()> {
+        loop {
+            match builder.module.type_table[&member_ty] {
+                SpirVType::Array(element_ty, _) | SpirVType::RuntimeArray(element_ty) => {
+                    member_ty = element_ty
+                }
+                SpirVType::Matrix(column_ty, _) => {
+                    let stride = Self::size_of_vector(builder, column_ty)?.next_power_of_two();
+
+                    builder.set_member_decorate(struct_ty, member, SpirVDecorateType::ColMajor);
+                    builder.set_member_decorate(
+                        struct_ty,
+                        member,
+                        SpirVDecorateType::MatrixStride(stride),
+                    );
+                    return Ok(());
+                }
+                _ => return Ok(()),
+            }
+        }
+    }
+
+    fn size_of_vector(builder: &SpirVBuilder, vector_ty: SpirVVariableId) -> Result<u32> {
+        let scalar_ty = Self::scalar_type(builder, vector_ty);
+
+        match builder.module.type_table.get(&scalar_ty) {
+            Some(SpirVType::Float(width) | SpirVType::Int(width, _)) => {
+                Ok(width / 8 * Self::vector_size(builder, vector_ty))
+            }
+            ty => Err(anyhow!("Expected a numeric vector, found {:?}.", ty)),
+        }
+    }
+
+    /// Finds the matrix products in `body`. Returns the product computed by
+    /// each root value and the values only the products needed, which no
+    /// longer have to be translated.
+    pub fn matrix_products(
+        module: &AirModule,
+        body: &[AirValueId],
+    ) -> (HashMap<AirValueId, MatrixProduct>, HashSet<AirValueId>) {
+        let mut products = HashMap::new();
+        let mut members = HashSet::new();
+
+        // Partial sums look like smaller products, so start from the end.
+        for i in body.iter().rev() {
+            if members.contains(i) {
+                continue;
+            }
+
+            if let Some((product, product_members)) = Self::vector_product(module, *i) {
+                products.insert(*i, product);
+                members.extend(product_members);
+            }
+        }
+
+        for i in body.iter().rev() {
+            if members.contains(i) {
+                continue;
+            }
+
+            if let Some((product, product_members)) =
+                Self::matrix_matrix_product(module, *i, &products)
+            {
+                products.insert(*i, product);
+                members.extend(product_members);
+            }
+        }
+
+        // Members used outside of their pattern still get translated.
+        let uses = |i: AirValueId| match products.get(&i) {
+            Some(product) => Self::product_operands(product),
+            None => module.value_list[i.0 as usize].operands(),
+        };
+
+        let mut kept = HashSet::new();
+        let mut stack = body
+            .iter()
+            .filter(|i| !members.contains(i))
+            .flat_map(|i| uses(*i))
+            .collect::<Vec<_>>();
+        while let Some(i) = stack.pop() {
+            if members.contains(&i) && kept.insert(i) {
+                stack.extend(uses(i));
+            }
+        }
+
+        let skipped = members.difference(&kept).copied().collect();
+        (products, skipped)
+    }
+
+    fn product_operands(product: &MatrixProduct) -> Vec<AirValueId> {
+        let source_operands = |source: &MatrixSource| match source {
+            MatrixSource::Extract { aggregate, .. } => vec![*aggregate],
+            MatrixSource::Load(gep) => {
+                let mut operands = vec![gep.base_ptr_value];
+                operands.extend(&gep.indices);
+                operands
+            }
+        };
+
+        match product {
+            MatrixProduct::Vector { matrix, vector, .. } => {
+                let mut operands = source_operands(matrix);
+                operands.push(*vector);
+                operands
+            }
+            MatrixProduct::Matrix { a, b, .. } => {
+                let mut operands = source_operands(a);
+                operands.extend(source_operands(b));
+                operands
+            }
+        }
+    }
+
+    /// Matches `column0 * v.x + column1 * v.y + ...`, as `fadd`s of `fmul`s
+    /// or a chain of `fma`s.
+    fn vector_product(
+        module: &AirModule,
+        root: AirValueId,
+    ) -> Option<(MatrixProduct, Vec<AirValueId>)> {
+        let mut members = vec![];
+        let mut terms = vec![];
+        Self::product_terms(module, root, &mut terms, &mut members)?;
+
+        let (matrix, vector) = (terms[0].0.clone(), terms[0].2);
+        let mut columns = vec![false; terms.len()];
+        for (source, column, term_vector, component) in &terms {
+            if *source != matrix || *term_vector != vector || column != component {
+                return None;
+            }
+            *columns.get_mut(*column as usize)? = true;
+        }
+
+        if terms.len() < 2 || columns.contains(&false) {
+            return None;
+        }
+
+        members.retain(|i| *i != root);
+        Some((
+            MatrixProduct::Vector {
+                matrix,
+                vector,
+                columns: terms.len() as u32,
+            },
+            members,
+        ))
+    }
+
+    /// Collects `(matrix, column, vector, component)` for every term of a
+    /// sum.
+    fn product_terms(
+        module: &AirModule,
+        value: AirValueId,
+        terms: &mut Vec<(MatrixSource, u64, AirValueId, u64)>,
+        members: &mut Vec<AirValueId>,
+    ) -> Option<()> {
+        members.push(value);
+
+        match &module.value_list[value.0 as usize] {
+            AirValue::BinOp(bin_op) if bin_op.opcode == BinaryOpCode::ADD => {
+                Self::product_terms(module, bin_op.lhs, terms, members)?;
+                Self::product_terms(module, bin_op.rhs, terms, members)
+            }
+            AirValue::BinOp(bin_op) if bin_op.opcode == BinaryOpCode::MUL => {
+                terms.push(Self::product_term(module, bin_op.lhs, bin_op.rhs, members)?);
+                Some(())
+            }
+            AirValue::Call(call) => {
+                let name = match module.value_list[call.function.0 as usize] {
+                    AirValue::Function(signature) => {
+                        let signature = module.get_function_signature(signature)?;
+                        &module.string_table[signature.name.0 as usize].content
+                    }
+                    _ => return None,
+                };
+
+                let is_fma = ["air.fma.", "air.fast_fma.", "llvm.fma.", "llvm.fmuladd."]
+                    .iter()
+                    .any(|x| name.starts_with(x));
+                match call.arguments.as_slice() {
+                    [a, b, accumulator] if is_fma => {
+                        terms.push(Self::product_term(module, *a, *b, members)?);
+                        Self::product_terms(module, *accumulator, terms, members)
+                    }
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+
+    fn product_term(
+        module: &AirModule,
+        a: AirValueId,
+        b: AirValueId,
+        members: &mut Vec<AirValueId>,
+    ) -> Option<(MatrixSource, u64, AirValueId, u64)> {
+        let ((source, column, column_members), (vector, component, splat_members)) =
+            match (Self::matrix_column(module, a), Self::splat(module, b)) {
+                (Some(column), Some(splat)) => (column, splat),
+                _ => (Self::matrix_column(module, b)?, Self::splat(module, a)?),
+            };
+
+        members.extend(column_members);
+        members.extend(splat_members);
+        Some((source, column, vector, component))
+    }
+
+    /// Matches a value read from one column of a matrix.
+    fn matrix_column(
+        module: &AirModule,
+        value: AirValueId,
+    ) -> Option<(MatrixSource, u64, Vec<AirValueId>)> {
+        match &module.value_list[value.0 as usize] {
+            AirValue::ExtractVal(extract_val) => {
+                let (column, prefix) = extract_val.indices.split_last()?;
+
+                Some((
+                    MatrixSource::Extract {
+                        aggregate: extract_val.aggregate,
+                        prefix: prefix.to_vec(),
+                    },
+                    *column,
+                    vec![value],
+                ))
+            }
+            AirValue::Load(load) => match &module.value_list[load.op.0 as usize] {
+                // The first index steps over the pointer, it never names
+                // the column.
+                AirValue::GetElementPtr(gep) if gep.indices.len() >= 2 => {
+                    let mut gep = gep.clone();
+                    let column = Self::get_air_constant_integer(module, gep.indices.pop()?)?;
+
+                    Some((MatrixSource::Load(gep), column, vec![value, load.op]))
+                }
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// Matches a vector with every component set to `vector[component]`.
+    fn splat(module: &AirModule, value: AirValueId) -> Option<(AirValueId, u64, Vec<AirValueId>)> {
+        let shuffle_vec = match &module.value_list[value.0 as usize] {
+            AirValue::ShuffleVec(shuffle_vec) => shuffle_vec,
+            _ => return None,
+        };
+
+        if !Self::is_undefined(module, shuffle_vec.vec2) && shuffle_vec.vec2 != shuffle_vec.vec1 {
+            return None;
+        }
+
+        let component = Self::splat_mask(module, shuffle_vec.mask)?;
+
+        // `insertelement undef, (extractelement %v, c), k` then a splat of
+        // component `k`.
+        if let AirValue::InsertElt(insert_elt) = &module.value_list[shuffle_vec.vec1.0 as usize]
+            && Self::get_air_constant_integer(module, insert_elt.index) == Some(component)
+            && let AirValue::ExtractElt(extract_elt) =
+                &module.value_list[insert_elt.value.0 as usize]
+            && let Some(component) = Self::get_air_constant_integer(module, extract_elt.index)
+        {
+            return Some((
+                extract_elt.vector,
+                component,
+                vec![value, shuffle_vec.vec1, insert_elt.value],
+            ));
+        }
+
+        Some((shuffle_vec.vec1, component, vec![value]))
+    }
+
+    /// The component a shuffle mask repeats, if it repeats a single one.
+    fn splat_mask(module: &AirModule, mask: AirValueId) -> Option<u64> {
+        let constant = match &module.value_list[mask.0 as usize] {
+            AirValue::Constant(constant) => module.constants.get(constant)?,
+            _ => return None,
+        };
+
+        let elements = match &constant.value {
+            AirConstantValue::Null => return Some(0),
+            AirConstantValue::Aggregate(elements) => elements
+                .iter()
+                .filter(|i| !Self::is_undefined(module, **i))
+                .map(|i| Self::get_air_constant_integer(module, *i))
+                .collect::<Option<Vec<_>>>()?,
+            AirConstantValue::Array(elements) => elements
+                .iter()
+                .filter_map(|i| match i {
+                    AirConstantValue::Undefined | AirConstantValue::Poison => None,
+                    AirConstantValue::Integer(i) => Some(Some(*i)),
+                    AirConstantValue::Null => Some(Some(0)),
+                    _ => Some(None),
+                })
+                .collect::<Option<Vec<_>>>()?,
+            _ => return None,
+        };
+
+        let component = *elements.first()?;
+        elements
+            .iter()
+            .all(|i| *i == component)
+            .then_some(component)
+    }
+
+    fn is_undefined(module: &AirModule, value: AirValueId) -> bool {
+        match &module.value_list[value.0 as usize] {
+            AirValue::Constant(constant) => matches!(
+                module.constants.get(constant).map(|x| &x.value),
+                Some(AirConstantValue::Undefined | AirConstantValue::Poison)
+            ),
+            _ => false,
+        }
+    }
+
+    /// Matches an `insertvalue` chain building `a * b` one column at a
+    /// time, each column being `a * b[j]`.
+    fn matrix_matrix_product(
+        module: &AirModule,
+        root: AirValueId,
+        products: &HashMap<AirValueId, MatrixProduct>,
+    ) -> Option<(MatrixProduct, Vec<AirValueId>)> {
+        let mut members = vec![];
+        let mut columns = vec![];
+
+        let mut value = root;
+        while !Self::is_undefined(module, value) {
+            let insert_val = match &module.value_list[value.0 as usize] {
+                AirValue::InsertVal(insert_val) => insert_val,
+                _ => return None,
+            };
+
+            members.push(value);
+            columns.push((insert_val.insert_value_idx, insert_val.value2));
+            value = insert_val.value1;
+        }
+
+        let mut a = None;
+        let mut b = None;
+        let mut found = vec![false; columns.len()];
+        for (column, product) in &columns {
+            let (matrix, vector) = match products.get(product)? {
+                MatrixProduct::Vector { matrix, vector, .. } => (matrix, *vector),
+                _ => return None,
+            };
+            let (source, b_column, b_members) = Self::matrix_column(module, vector)?;
+
+            if b_column != *column || *a.get_or_insert(matrix) != matrix {
+                return None;
+            }
+            if *b.get_or_insert(source.clone()) != source {
+                return None;
+            }
+            *found.get_mut(*column as usize)? = true;
+
+            members.push(*product);
+            members.extend(b_members);
+        }
+
+        if columns.len() < 2 || found.contains(&false) {
+            return None;
+        }
+
+        members.retain(|i| *i != root);
+        Some((
+            MatrixProduct::Matrix {
+                a: a?.clone(),
+                b: b?,
+                columns: columns.len() as u32,
+            },
+            members,
+        ))
+    }
+
+    pub fn emit_matrix_product(
+        builder: &mut SpirVBuilder,
+        module: &AirModule,
+        product: &MatrixProduct,
+        value_list: &HashMap<AirValueId, SpirVVariableId>,
+    ) -> Result<SpirVVariableId> {
+        Ok(match product {
+            MatrixProduct::Vector {
+                matrix,
+                vector,
+                columns,
+            } => {
+                let matrix = Self::matrix_value(builder, module, matrix, value_list)?;
+                let vector = Self::spirv_value(value_list, *vector)?;
+
+                Self::matrix_times_vector(builder, matrix, vector, *columns)?
+            }
+            MatrixProduct::Matrix { a, b, columns } => {
+                let a = Self::matrix_value(builder, module, a, value_list)?;
+                let b = Self::matrix_value(builder, module, b, value_list)?;
+
+                let a_ty = builder.find_operand_type_id(a)?;
+                let b_ty = builder.find_operand_type_id(b)?;
+                let a_columns = Self::spirv_get_element_type(builder, a_ty, 0)?;
+                let b_columns = Self::spirv_get_element_type(builder, b_ty, 0)?;
+
+                let (a_matrix, b_matrix) = (
+                    builder.module.type_table[&a_ty].clone(),
+                    builder.module.type_table[&b_ty].clone(),
+                );
+                if let (SpirVType::Matrix(_, a_count), SpirVType::Matrix(_, b_count)) =
+                    (a_matrix, b_matrix)
+                    && b_count == *columns
+                    && builder.module.type_table[&b_columns]
+                        == SpirVType::Vector(Self::scalar_type(builder, a_columns), a_count)
+                {
+                    let type_id = builder.new_type(SpirVType::Matrix(a_columns, *columns));
+                    return Ok(builder.new_binary_op(
+                        SpirVOpCode::MatrixTimesMatrix,
+                        SpirVBinaryOp {
+                            type_id,
+                            operand1: a,
+                            operand2: b,
+                        },
+                    ));
+                }
+
+                let a_count = Self::vector_size(builder, b_columns);
+                let elements = (0..*columns)
+                    .map(|column| {
+                        let vector = builder.new_composite_extract(SpirVCompositeExtract {
+                            type_id: b_columns,
+                            composite_id: b,
+                            indices: vec![column],
+                        });
+                        Self::matrix_times_vector(builder, a, vector, a_count)
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+
+                let first = elements
+                    .first()
+                    .ok_or(anyhow!("A matrix product needs a column."))?;
+                let column_ty = builder.find_operand_type_id(*first)?;
+                let type_id = builder.new_type(SpirVType::Matrix(column_ty, *columns));
+                builder.new_composite_construct(SpirVCompositeConstruct { type_id, elements })
+            }
+        })
+    }
+
+    /// The matrix a product reads, as one value.
+    fn matrix_value(
+        builder: &mut SpirVBuilder,
+        module: &AirModule,
+        source: &MatrixSource,
+        value_list: &HashMap<AirValueId, SpirVVariableId>,
+    ) -> Result<SpirVVariableId> {
+        Ok(match source {
+            MatrixSource::Extract { aggregate, prefix } => {
+                let composite_id = Self::spirv_value(value_list, *aggregate)?;
+                if prefix.is_empty() {
+                    return Ok(composite_id);
+                }
+
+                let mut type_id = builder.find_operand_type_id(composite_id)?;
+                for i in prefix {
+                    type_id = Self::spirv_get_element_type(builder, type_id, *i as usize)?;
+                }
+
+                builder.new_composite_extract(SpirVCompositeExtract {
+                    type_id,
+                    composite_id,
+                    indices: prefix.iter().map(|x| *x as u32).collect(),
+                })
+            }
+            MatrixSource::Load(gep) => {
+                let pointer_id = Self::get_element_ptr(builder, module, gep, value_list)?;
+                let pointer_ty = builder.find_operand_type_id(pointer_id)?;
+                let type_id = Self::spirv_get_element_type(builder, pointer_ty, 0)?;
+
+                builder.new_load(SpirVLoad {
+                    type_id,
+                    pointer_id,
+                    memory_operands: SpirVMemoryOperands::None,
+                })
+            }
+        })
+    }
+
+    /// `OpMatrixTimesVector` over the first `columns` columns, or dot
+    /// products with the matrix's rows when it isn't an `OpTypeMatrix`
+    /// of that size.
+    fn matrix_times_vector(
+        builder: &mut SpirVBuilder,
+        matrix: SpirVVariableId,
+        vector: SpirVVariableId,
+        columns: u32,
+    ) -> Result<SpirVVariableId> {
+        let matrix_ty = builder.find_operand_type_id(matrix)?;
+        let vector_ty = builder.find_operand_type_id(vector)?;
+        let column_ty = Self::spirv_get_element_type(builder, matrix_ty, 0)?;
+
+        if builder.module.type_table[&matrix_ty] == SpirVType::Matrix(column_ty, columns)
+            && Self::vector_size(builder, vector_ty) == columns
+        {
+            return Ok(builder.new_binary_op(
+                SpirVOpCode::MatrixTimesVector,
+                SpirVBinaryOp {
+                    type_id: column_ty,
+                    operand1: matrix,
+                    operand2: vector,
+                },
+            ));
+        }
+
+        let scalar_ty = Self::scalar_type(builder, column_ty);
+        let row_ty = builder.new_type(SpirVType::Vector(scalar_ty, columns));
+
+        let vector = match Self::vector_size(builder, vector_ty) == columns {
+            true => vector,
+            false => {
+                let elements = (0..columns)
+                    .map(|i| {
+                        builder.new_composite_extract(SpirVCompositeExtract {
+                            type_id: scalar_ty,
+                            composite_id: vector,
+                            indices: vec![i],
+                        })
+                    })
+                    .collect();
+                builder.new_composite_construct(SpirVCompositeConstruct {
+                    type_id: row_ty,
+                    elements,
+                })
+            }
+        };
+
+        let elements = (0..Self::vector_size(builder, column_ty))
+            .map(|row| {
+                let elements = (0..columns)
+                    .map(|column| {
+                        builder.new_composite_extract(SpirVCompositeExtract {
+                            type_id: scalar_ty,
+                            composite_id: matrix,
+                            indices: vec![column, row],
+                        })
+                    })
+                    .collect();
+                let row = builder.new_composite_construct(SpirVCompositeConstruct {
+                    type_id: row_ty,
+                    elements,
+                });
+
+                builder.new_binary_op(
+                    SpirVOpCode::Dot,
+                    SpirVBinaryOp {
+                        type_id: scalar_ty,
+                        operand1: row,
+                        operand2: vector,
+                    },
+                )
+            })
+            .collect();
+
+        Ok(builder.new_composite_construct(SpirVCompositeConstruct {
+            type_id: column_ty,
+            elements,
+        }))
+    }
+
+    fn scalar_type(builder: &SpirVBuilder, vector_ty: SpirVVariableId) -> SpirVVariableId {
+        match builder.module.type_table[&vector_ty] {
+            SpirVType::Vector(scalar_ty, _) => scalar_ty,
+            _ => vector_ty,
+        }
+    }
+
+    fn vector_size(builder: &SpirVBuilder, vector_ty: SpirVVariableId) -> u32 {
+        match builder.module.type_table[&vector_ty] {
+            SpirVType::Vector(_, size) => size,
+            _ => 1,
+        }
+    }
+}
@@ -1,33 +1,182 @@
-use std::collections::HashMap;
+pub mod layout;
+pub mod matrix;
+
+pub use layout::*;
+pub use matrix::*;
+
+use std::{
+    collections::{HashMap, HashSet},
+    panic::AssertUnwindSafe,
+    path::Path,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc,
+    },
+};
 
 use anyhow::{Result, anyhow};
 
 use crate::{
     air_parser::{
-        AirConstant, AirConstantId, AirConstantValue, AirFile, AirFunctionSignatureId,
-        AirGlobalVariableId, AirItem, AirMetadataConstant, AirMetadataNamedNode, AirModule,
-        AirType, AirValue, AirValueId, AirVectorType,
+        AirConstant, AirConstantId, AirConstantValue, AirDebugInfo, AirFile, AirFunctionSignature,
+        AirFunctionSignatureId, AirGetElementPtr, AirGlobalVariableId, AirItem,
+        AirMetadataConstant, AirModule, AirType, AirValue, AirValueId, AirVectorType,
     },
+    llvm_bitcode::{BinaryOpCode, CastOpCode, debug},
     spirv_builder::SpirVBuilder,
     spirv_parser::{
-        SpirVAccessChain, SpirVAddressingModel, SpirVBitCast, SpirVBuiltIn, SpirVCapability,
-        SpirVCompositeExtract, SpirVCompositeInsert, SpirVConstant, SpirVConstantComposite,
-        SpirVConstantValue, SpirVDecorate, SpirVDecorateType, SpirVExecutionModel, SpirVLoad,
-        SpirVMemoryModel, SpirVMemoryOperands, SpirVSource, SpirVSourceLanguage, SpirVStorageClass,
-        SpirVStore, SpirVType, SpirVVariableId, SpirVVectorShuffle,
+        SpirVAccessChain, SpirVAddressingModel, SpirVBinaryOp, SpirVBitCast, SpirVBuiltIn,
+        SpirVCapability, SpirVCompositeExtract, SpirVCompositeInsert, SpirVConstant,
+        SpirVConstantComposite, SpirVConstantValue, SpirVDecorate, SpirVDecorateType, SpirVDim,
+        SpirVExecutionMode, SpirVExecutionModel, SpirVImageType, SpirVLine, SpirVLoad,
+        SpirVMemoryModel, SpirVMemoryOperands, SpirVOpCode, SpirVSource, SpirVSourceLanguage,
+        SpirVStorageClass, SpirVStore, SpirVType, SpirVUnaryOp, SpirVVariableId,
+        SpirVVectorInsertDynamic, SpirVVectorShuffle,
     },
 };
 
+#[derive(Debug, Clone)]
+pub struct AirToSpirVOptions {
+    /// SPIR-V version written to the header, as (major, minor).
+    pub spirv_version: (u8, u8),
+    pub environment: VulkanEnvironment,
+    pub descriptor_bindings: DescriptorBindingStrategy,
+    /// Negate `position.y`, Metal's clip space is Y-up while Vulkan's is Y-down.
+    pub flip_y: bool,
+    pub depth_range: DepthRange,
+    /// Emit `OpString`/`OpLine` from the module's `DILocation`s so
+    /// validation messages point back at the Metal source.
+    pub debug_info: bool,
+    pub emit_names: bool,
+    /// Metal picks the threadgroup size when dispatching, Vulkan when the
+    /// pipeline is made, so kernels are translated for this size.
+    pub threads_per_threadgroup: [u32; 3],
+    /// The device has `scalarBlockLayout`. Without it, buffers Metal lays
+    /// out tighter than Vulkan's extended layout allows are rejected.
+    pub scalar_block_layout: bool,
+}
+
+impl Default for AirToSpirVOptions {
+    fn default() -> Self {
+        Self {
+            spirv_version: (1, 0),
+            environment: VulkanEnvironment::default(),
+            descriptor_bindings: DescriptorBindingStrategy::default(),
+            flip_y: false,
+            depth_range: DepthRange::default(),
+            debug_info: false,
+            emit_names: true,
+            threads_per_threadgroup: [1, 1, 1],
+            scalar_block_layout: true,
+        }
+    }
+}
+
+impl AirToSpirVOptions {
+    /// Maps a Metal `[[buffer(n)]]`, `[[texture(n)]]` or `[[sampler(n)]]`
+    /// index to a (set, binding) pair.
+    pub fn descriptor_binding(&self, kind: ShaderResourceKind, index: u32) -> (u32, u32) {
+        match self.descriptor_bindings {
+            DescriptorBindingStrategy::Flat => (
+                0,
+                match kind {
+                    ShaderResourceKind::Buffer => index,
+                    ShaderResourceKind::Texture => MAX_BUFFER_BINDINGS + index,
+                    ShaderResourceKind::Sampler => {
+                        MAX_BUFFER_BINDINGS + MAX_TEXTURE_BINDINGS + index
+                    }
+                },
+            ),
+            DescriptorBindingStrategy::SetPerResourceKind => (kind as u32, index),
+        }
+    }
+
+    /// Descriptor set for the argument buffer at `[[buffer(n)]]`, placed
+    /// after the sets `descriptor_bindings` already uses. Its members are
+    /// bound at their `[[id(n)]]`.
+    pub fn argument_buffer_set(&self, buffer_index: u32) -> u32 {
+        match self.descriptor_bindings {
+            DescriptorBindingStrategy::Flat => 1 + buffer_index,
+            DescriptorBindingStrategy::SetPerResourceKind => 3 + buffer_index,
+        }
+    }
+}
+
+/// Named metadata listing each stage's entry points.
+pub const ENTRY_POINT_STAGES: [(&str, SpirVExecutionModel); 3] = [
+    ("air.vertex", SpirVExecutionModel::Vertex),
+    ("air.fragment", SpirVExecutionModel::Fragment),
+    ("air.kernel", SpirVExecutionModel::GLCompute),
+];
+
+/// Metal's per-stage argument table sizes.
+pub const MAX_BUFFER_BINDINGS: u32 = 31;
+pub const MAX_TEXTURE_BINDINGS: u32 = 128;
+pub const MAX_SAMPLER_BINDINGS: u32 = 16;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum VulkanEnvironment {
+    #[default]
+    Vulkan1_0,
+    Vulkan1_1,
+    Vulkan1_2,
+    Vulkan1_3,
+}
+
+impl VulkanEnvironment {
+    pub fn max_spirv_version(&self) -> (u8, u8) {
+        match self {
+            Self::Vulkan1_0 => (1, 0),
+            Self::Vulkan1_1 => (1, 3),
+            Self::Vulkan1_2 => (1, 5),
+            Self::Vulkan1_3 => (1, 6),
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum DescriptorBindingStrategy {
+    /// Everything in set 0: buffers, then textures, then samplers.
+    #[default]
+    Flat,
+    /// Set 0 for buffers, 1 for textures and 2 for samplers, the binding
+    /// is the Metal index.
+    SetPerResourceKind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u32)]
+pub enum ShaderResourceKind {
+    Buffer = 0,
+    Texture = 1,
+    Sampler = 2,
+}
+
+/// Clip space depth range of the target, Metal uses [0, 1].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum DepthRange {
+    #[default]
+    ZeroToOne,
+    /// Remaps `z` to `2z - w`.
+    NegativeOneToOne,
+}
+
 pub struct AirToSpirV {
     pub input: AirFile,
     pub output: SpirVBuilder,
+    pub options: AirToSpirVOptions,
 }
 
 impl AirToSpirV {
     pub fn new(input: AirFile) -> Self {
+        Self::new_with_options(input, AirToSpirVOptions::default())
+    }
+
+    pub fn new_with_options(input: AirFile, options: AirToSpirVOptions) -> Self {
         Self {
             input,
             output: SpirVBuilder::default(),
+            options,
         }
     }
 
@@ -35,8 +184,8 @@ impl AirToSpirV {
         builder: &mut SpirVBuilder,
         module: &AirModule,
         value: &AirType,
-    ) -> SpirVVariableId {
-        match value {
+    ) -> Result<SpirVVariableId> {
+        Ok(match value {
             AirType::Void => builder.new_type(SpirVType::Void),
             AirType::Integer(width) => builder.new_type(SpirVType::Int(*width as u32, false)),
             AirType::Float => builder.new_type(SpirVType::Float(32)),
@@ -45,33 +194,54 @@ impl AirToSpirV {
                     builder,
                     module,
                     &module.types[function_ty.return_type.0 as usize],
-                );
+                )?;
                 let args = function_ty
                     .param_types
                     .iter()
                     .map(|ty| Self::parse_air_type(builder, module, &module.types[ty.0 as usize]))
-                    .collect::<Vec<_>>();
+                    .collect::<Result<Vec<_>>>()?;
 
                 builder.new_type(SpirVType::Function(return_ty, args))
             }
             AirType::Struct(struct_ty) => {
-                let elements = struct_ty
+                let mut elements = struct_ty
                     .elements
                     .iter()
                     .map(|ty| {
-                        (
+                        Ok((
                             String::new(),
-                            Self::parse_air_type(builder, module, &module.types[ty.0 as usize]),
-                        )
+                            Self::parse_air_type(builder, module, &module.types[ty.0 as usize])?,
+                        ))
                     })
-                    .collect::<Vec<_>>();
+                    .collect::<Result<Vec<_>>>()?;
+
+                // `metal::matrix` wraps the array of its columns.
+                if Self::matrix_dimensions(&struct_ty.name).is_some()
+                    && let [(_, columns_ty)] = elements.as_mut_slice()
+                    && let Some(matrix_ty) = Self::matrix_type(builder, *columns_ty)
+                {
+                    *columns_ty = matrix_ty;
+                }
+
+                let type_count = builder.module.type_table.len();
+                let struct_id =
+                    builder.new_struct_type(&struct_ty.name, struct_ty.name.is_empty(), elements);
+
+                if builder.module.type_table.len() != type_count
+                    && let SpirVType::Struct(members) =
+                        builder.module.type_table[&struct_id].clone()
+                {
+                    for (member, member_ty) in members.into_iter().enumerate() {
+                        Self::decorate_matrix_member(builder, struct_id, member, member_ty)?;
+                    }
+                }
 
-                builder.new_struct_type(&struct_ty.name, struct_ty.name.is_empty(), elements)
+                struct_id
             }
             AirType::Array(array_ty) => {
                 let ty = &module.types[array_ty.element_type.0 as usize];
 
-                let element_ty = Self::parse_air_type(builder, module, ty);
+                let element_ty = Self::parse_air_type(builder, module, ty)?;
                 let u32_ty = builder.new_type(SpirVType::Int(32, false));
                 let length = builder.new_constant(SpirVConstant {
                     type_id: u32_ty,
@@ -83,11 +253,41 @@ impl AirToSpirV {
             AirType::Vector(vector_ty) => {
                 let ty = &module.types[vector_ty.element_type.0 as usize];
 
-                let element_ty = Self::parse_air_type(builder, module, ty);
+                let element_ty = Self::parse_air_type(builder, module, ty)?;
 
                 builder.new_type(SpirVType::Vector(element_ty, vector_ty.size as u32))
             }
-            _ => todo!("{:?}", value),
+            AirType::Pointer(address_space, ty) => {
+                let pointee_ty =
+                    Self::parse_air_type(builder, module, &module.types[ty.0 as usize])?;
+
+                builder.new_type(SpirVType::Pointer(
+                    Self::address_space_storage_class(*address_space)?,
+                    pointee_ty,
+                ))
+            }
+            _ => return Err(anyhow!("{:?} has no SPIR-V equivalent.", value)),
+        })
+    }
+
+    /// Metal address spaces: 0 is `thread`, 1 `device`, 2 `constant` and
+    /// 3 `threadgroup`.
+    pub fn address_space_storage_class(address_space: u64) -> Result<SpirVStorageClass> {
+        match address_space {
+            0 => Ok(SpirVStorageClass::Function),
+            1 | 2 => Ok(SpirVStorageClass::StorageBuffer),
+            3 => Ok(SpirVStorageClass::Workgroup),
+            _ => Err(anyhow!("Unknown address space {}.", address_space)),
+        }
+    }
+
+    /// `StorageBuffer` needs SPIR-V 1.3, older versions use `Uniform` with
+    /// a `BufferBlock` decorated struct.
+    pub fn buffer_storage_class(options: &AirToSpirVOptions) -> SpirVStorageClass {
+        if options.spirv_version >= (1, 3) {
+            SpirVStorageClass::StorageBuffer
+        } else {
+            SpirVStorageClass::Uniform
         }
     }
 
@@ -95,28 +295,24 @@ impl AirToSpirV {
         builder: &mut SpirVBuilder,
         module: &AirModule,
         type_id: SpirVVariableId,
-        constant: Option<AirConstant>,
-        constant_value: Option<AirConstantValue>,
-    ) -> SpirVVariableId {
-        let const_val = match constant {
-            Some(c) => c.value,
-            None => match constant_value {
-                Some(cv) => cv,
-                None => panic!("No Constant or Constant Value Found."),
-            },
-        };
-
-        match const_val {
+        value: &AirConstantValue,
+    ) -> Result<SpirVVariableId> {
+        Ok(match value {
             AirConstantValue::Integer(value) => builder.new_constant(SpirVConstant {
                 type_id,
-                value: SpirVConstantValue::UnsignedInteger(value),
+                value: SpirVConstantValue::UnsignedInteger(*value),
             }),
             AirConstantValue::Float32(value) => builder.new_constant(SpirVConstant {
                 type_id,
-                value: SpirVConstantValue::Float32(value),
+                value: SpirVConstantValue::Float32(*value),
             }),
             AirConstantValue::Undefined | AirConstantValue::Null | AirConstantValue::Poison => {
-                let ty = builder.module.type_table.get(&type_id).unwrap().clone();
+                let ty = builder
+                    .module
+                    .type_table
+                    .get(&type_id)
+                    .ok_or(anyhow!("{:?} isn't a type.", type_id))?
+                    .clone();
                 match ty {
                     SpirVType::Vector(ty_id, size) => {
                         let null_const = builder.new_constant(SpirVConstant {
@@ -129,13 +325,20 @@ impl AirToSpirV {
                             values: vec![null_const; size as usize],
                         })
                     }
-                    SpirVType::Array(ty_id, size) => {
-                        let null_const = builder.new_constant(SpirVConstant {
-                            type_id: ty_id,
-                            value: SpirVConstantValue::Null,
-                        });
-
-                        let length = Self::spirv_constant_to_literal(builder, size) as usize;
+                    SpirVType::Array(ty_id, _) | SpirVType::Matrix(ty_id, _) => {
+                        let length = match ty {
+                            SpirVType::Array(_, length) => {
+                                Self::spirv_constant_to_literal(builder, length)? as usize
+                            }
+                            SpirVType::Matrix(_, columns) => columns as usize,
+                            _ => 0,
+                        };
+                        let null_const = Self::parse_air_constant(
+                            builder,
+                            module,
+                            ty_id,
+                            &AirConstantValue::Null,
+                        )?;
 
                         builder.new_constant_composite(SpirVConstantComposite {
                             type_id: type_id,
@@ -149,9 +352,8 @@ impl AirToSpirV {
                                 builder,
                                 module,
                                 i,
-                                None,
-                                Some(AirConstantValue::Null),
-                            ));
+                                &AirConstantValue::Null,
+                            )?);
                         }
 
                         builder.new_constant_composite(SpirVConstantComposite {
@@ -166,334 +368,599 @@ impl AirToSpirV {
                 }
             }
             AirConstantValue::Array(elements) => {
-                let values = elements
-                    .iter()
-                    .map(|value| {
-                        let element_ty = Self::spirv_get_element_type(builder, type_id, 0);
-                        Self::parse_air_constant(
-                            builder,
-                            module,
-                            element_ty,
-                            None,
-                            Some(value.clone()),
-                        )
-                    })
-                    .collect();
+                let mut values = vec![];
+                for value in elements {
+                    let element_ty = Self::spirv_get_element_type(builder, type_id, 0)?;
+                    values.push(Self::parse_air_constant(
+                        builder, module, element_ty, value,
+                    )?);
+                }
 
                 builder.new_constant_composite(SpirVConstantComposite { type_id, values })
             }
             AirConstantValue::Aggregate(elements) => {
-                let mut count = 0;
-                let values = elements
-                    .iter()
-                    .map(|value| {
-                        let element_ty = Self::spirv_get_element_type(builder, type_id, count);
-                        count += 1;
-                        match module.value_list.get(value.0 as usize) {
-                            Some(value) => Self::parse_air_constant(
-                                builder,
-                                module,
-                                element_ty,
-                                match value {
-                                    AirValue::Constant(constant) => {
-                                        Some(module.constants.get(&constant).unwrap().clone())
-                                    }
-                                    _ => None,
-                                },
-                                None,
-                            ),
-                            None => Self::parse_air_constant(
-                                builder,
-                                module,
-                                element_ty,
-                                None,
-                                Some(AirConstantValue::Poison),
-                            ),
+                let mut values = vec![];
+                for (count, value) in elements.iter().enumerate() {
+                    let element_ty = Self::spirv_get_element_type(builder, type_id, count)?;
+                    let value = match module.value_list.get(value.0 as usize) {
+                        Some(AirValue::Constant(constant)) => {
+                            &module
+                                .constants
+                                .get(constant)
+                                .ok_or(anyhow!("{:?} not found.", constant))?
+                                .value
                         }
-                    })
-                    .collect();
+                        Some(value) => {
+                            return Err(anyhow!("Expected Constant, found {:?}.", value));
+                        }
+                        None => &AirConstantValue::Poison,
+                    };
+
+                    values.push(Self::parse_air_constant(
+                        builder, module, element_ty, value,
+                    )?);
+                }
 
                 builder.new_constant_composite(SpirVConstantComposite { type_id, values })
             }
-            _ => todo!("{:?}", const_val),
-        }
+            value => return Err(anyhow!("Constant {:?} isn't supported.", value)),
+        })
     }
 
     pub fn spirv_get_element_type(
         builder: &mut SpirVBuilder,
         id: SpirVVariableId,
         index: usize,
-    ) -> SpirVVariableId {
-        match builder.module.type_table.get(&id).unwrap() {
-            SpirVType::Vector(type_id, _)
-            | SpirVType::Array(type_id, _)
-            | SpirVType::Pointer(_, type_id) => *type_id,
-            SpirVType::Struct(types) => types[index],
-            _ => id,
-        }
+    ) -> Result<SpirVVariableId> {
+        Ok(
+            match builder
+                .module
+                .type_table
+                .get(&id)
+                .ok_or(anyhow!("{:?} isn't a type.", id))?
+            {
+                SpirVType::Vector(type_id, _)
+                | SpirVType::Matrix(type_id, _)
+                | SpirVType::Array(type_id, _)
+                | SpirVType::Pointer(_, type_id) => *type_id,
+                SpirVType::Struct(types) => {
+                    *types
+                        .get(index)
+                        .ok_or(anyhow!("{:?} has no member {}.", id, index))?
+                }
+                _ => id,
+            },
+        )
     }
 
     pub fn start(&mut self) -> Result<()> {
-        let mut module: Option<AirModule> = None;
+        let module = self
+            .input
+            .items
+            .iter()
+            .find_map(|i| match i {
+                AirItem::Module(m) => Some(m),
+                _ => None,
+            })
+            .ok_or(anyhow!("Module not found."))?;
+
+        self.output = Self::translate(module, &self.options, None)?;
 
-        for i in &self.input.items {
-            match i {
-                AirItem::Module(m) => module = Some(m.clone()),
-                _ => continue,
+        Ok(())
+    }
+
+    /// Names of the module's entry points, in the order they're declared.
+    pub fn entry_points(module: &AirModule) -> Vec<String> {
+        Self::entry_point_nodes(module)
+            .into_iter()
+            .filter_map(|(_, entry)| {
+                let signature = Self::entry_point_signature(module, entry).ok()?;
+                Some(
+                    module.string_table[signature.name.0 as usize]
+                        .content
+                        .clone(),
+                )
+            })
+            .collect()
+    }
+
+    /// The metadata node of every entry point, holding its function, outputs
+    /// and inputs, with the stage it belongs to.
+    pub fn entry_point_nodes(module: &AirModule) -> Vec<(SpirVExecutionModel, &Vec<Option<u64>>)> {
+        let mut result = vec![];
+
+        for i in &module.metadata_named_nodes {
+            let Some((_, stage)) = ENTRY_POINT_STAGES.iter().find(|(name, _)| *name == i.name)
+            else {
+                continue;
+            };
+
+            for operand in &i.operands {
+                if let Some(AirMetadataConstant::Node(entry)) =
+                    module.metadata_constants.get(operand)
+                {
+                    result.push((*stage, entry));
+                }
             }
         }
 
-        if module.is_none() {
-            return Err(anyhow!("Module not found."));
+        result
+    }
+
+    pub fn entry_point_signature<'a>(
+        module: &'a AirModule,
+        entry: &[Option<u64>],
+    ) -> Result<&'a AirFunctionSignature> {
+        let metadata = module.get_metadata(entry.first().copied().flatten());
+        match metadata {
+            Some(AirMetadataConstant::Value(AirValue::Function(function))) => module
+                .get_function_signature(*function)
+                .ok_or(anyhow!("Function {:?} not found.", function)),
+            _ => Err(anyhow!("Expected Function, found {:?}.", metadata)),
+        }
+    }
+
+    /// Outputs (`index` 1) or inputs (`index` 2) of an entry point.
+    pub fn entry_point_variables(
+        module: &AirModule,
+        entry: &[Option<u64>],
+        index: usize,
+    ) -> Result<VertexFunctionInfo> {
+        let metadata = module.get_metadata(entry.get(index).copied().flatten());
+        match metadata {
+            Some(AirMetadataConstant::Node(variables)) => {
+                Self::parse_vertex_info(module, variables.clone())
+            }
+            _ => Err(anyhow!("Expected Node Group, found {:?}.", metadata)),
         }
+    }
 
-        let module = module.unwrap();
+    /// Translates `module` without modifying it. With `entry_point` only that
+    /// entry point ends up in the output.
+    pub fn translate(
+        module: &AirModule,
+        options: &AirToSpirVOptions,
+        entry_point: Option<&str>,
+    ) -> Result<SpirVBuilder> {
         let mut builder = SpirVBuilder::new();
 
-        builder.set_version(1, 0);
+        if options.spirv_version > options.environment.max_spirv_version() {
+            return Err(anyhow!(
+                "SPIR-V {:?} isn't supported by {:?}.",
+                options.spirv_version,
+                options.environment
+            ));
+        }
+
+        builder.set_version(options.spirv_version.0, options.spirv_version.1);
         builder.add_capability(SpirVCapability::Shader);
         builder.add_memory_model(SpirVAddressingModel::Logical, SpirVMemoryModel::Glsl450);
+        // There's no source language for Metal.
         builder.add_source(SpirVSource {
-            source_language: SpirVSourceLanguage::Glsl,
-            version: 450,
+            source_language: SpirVSourceLanguage::Unknown,
+            version: 0,
         });
-        builder.new_source_extension("GL_GOOGLE_cpp_style_line_directive");
-        builder.new_source_extension("GL_GOOGLE_include_directive");
         builder.new_extended_instruction_import("GLSL.std.450");
 
+        let debug_files = if options.debug_info {
+            Self::parse_debug_files(&mut builder, module)
+        } else {
+            HashMap::new()
+        };
+
         let mut constants: HashMap<AirConstantId, SpirVVariableId> = HashMap::new();
-        dbg!(&module.constants.len());
-        for (id, constant) in &module.constants {
+        debug(&format!("AIR constants: {}", module.constants.len()));
+        // `HashMap` order changes between runs, the output mustn't.
+        let mut module_constants = module.constants.iter().collect::<Vec<_>>();
+        module_constants.sort_by_key(|(id, _)| **id);
+        for (id, constant) in module_constants {
             let ty =
-                Self::parse_air_type(&mut builder, &module, &module.types[constant.ty.0 as usize]);
-            let constant =
-                Self::parse_air_constant(&mut builder, &module, ty, Some(constant.clone()), None);
+                Self::parse_air_type(&mut builder, module, &module.types[constant.ty.0 as usize])?;
+            let constant = Self::parse_air_constant(&mut builder, module, ty, &constant.value)?;
             constants.insert(*id, constant);
         }
-        dbg!(&constants.len());
+        debug(&format!("SPIR-V constants: {}", constants.len()));
 
         let mut global_variables: HashMap<AirGlobalVariableId, SpirVVariableId> = HashMap::new();
-        for (id, global_var) in &module.global_variables {
+        let mut module_global_variables = module.global_variables.iter().collect::<Vec<_>>();
+        module_global_variables.sort_by_key(|(id, _)| **id);
+        for (id, global_var) in module_global_variables {
             let ty = Self::parse_air_type(
                 &mut builder,
-                &module,
+                module,
                 &module.types[global_var.type_id.0 as usize],
-            );
+            )?;
+            let pointer_ty = builder.new_type(SpirVType::Pointer(SpirVStorageClass::Private, ty));
             global_variables.insert(
                 *id,
                 builder.new_variable(
                     &module.string_table[global_var.name.0 as usize].content,
-                    ty,
+                    pointer_ty,
                     SpirVStorageClass::Private,
                     constants.get(&global_var.initializer).cloned(),
                 ),
             );
         }
 
-        let mut entry_points: HashMap<AirFunctionSignatureId, SpirVVariableId> = HashMap::new();
-        let mut air_vertex: Option<AirMetadataNamedNode> = None;
+        let mut found_entry_point = false;
+        for (stage, entry) in Self::entry_point_nodes(module) {
+            let function_signature = Self::entry_point_signature(module, entry)?;
 
-        for i in &module.metadata_named_nodes {
-            if i.name == "air.vertex" {
-                air_vertex = Some(i.clone());
+            let name = &module.string_table[function_signature.name.0 as usize].content;
+            if entry_point.is_some_and(|x| x != name) {
+                continue;
             }
-        }
+            found_entry_point = true;
 
-        match air_vertex {
-            Some(air_vertex) => {
-                for i in air_vertex.operands {
-                    let entry = match &module.metadata_constants[&i] {
-                        AirMetadataConstant::Node(entry) => entry,
-                        _ => panic!("Expected Node, found {:?}", module.metadata_constants[&i]),
-                    };
+            let mut vertex_info_output = Self::entry_point_variables(module, entry, 1)?;
+            vertex_info_output.merge(Self::entry_point_variables(module, entry, 2)?);
 
-                    let function_signature = match module.metadata_constants.get(&entry[0]).unwrap()
-                    {
-                        AirMetadataConstant::Value(value) => match value {
-                            AirValue::Function(function) => {
-                                module.get_function_signature(*function).unwrap()
-                            }
-                            _ => panic!("Expected Function, found {:?}", value),
-                        },
-                        _ => {
-                            panic!(
-                                "Expected Value, found {:?}",
-                                module.metadata_constants[&entry[0]]
-                            )
+            let air_function_type = Self::parse_air_type(
+                &mut builder,
+                module,
+                &AirType::Function(function_signature.ty.clone()),
+            )?;
+
+            let mut location_count = 0;
+            let mut variable_count = 0;
+            let mut spirv_inputs = vec![];
+            let mut spirv_outputs = vec![];
+            let mut air_arguments = vec![];
+            let mut argument_buffers = HashMap::new();
+            let mut argument_descriptors: Vec<SpirVVariableId> = vec![];
+            match &builder.module.type_table[&air_function_type].clone() {
+                SpirVType::Function(output, inputs) => {
+                    // Kernels return nothing.
+                    if !matches!(builder.module.type_table[output], SpirVType::Void) {
+                        spirv_outputs.extend(Self::parse_entry_point_variable(
+                            &mut builder,
+                            &vec![*output],
+                            &vertex_info_output,
+                            &mut variable_count,
+                            &mut location_count,
+                            options,
+                        )?);
+                    }
+
+                    for (index, input) in inputs.iter().enumerate() {
+                        let info = vertex_info_output
+                            .variables
+                            .get(variable_count)
+                            .ok_or(anyhow!("`{}` has no metadata for input {}.", name, index))?;
+                        if let ShaderVariableType::Input(ShaderInputType::ArgumentBuffer) = info.ty
+                        {
+                            let argument_buffer = Self::declare_argument_buffer(
+                                &mut builder,
+                                &Self::argument_buffer_layout(name, info, options),
+                                options,
+                            )?;
+                            argument_descriptors.extend(
+                                argument_buffer
+                                    .descriptors
+                                    .iter()
+                                    .flatten()
+                                    .chain(&argument_buffer.data),
+                            );
+                            argument_buffers
+                                .insert(function_signature.ty.param_values[index], argument_buffer);
+
+                            variable_count += 1;
+                            continue;
                         }
-                    };
 
-                    let entry_point_outputs =
-                        match module.metadata_constants.get(&entry[1]).unwrap() {
-                            AirMetadataConstant::Node(entry_point_outputs) => entry_point_outputs,
-                            _ => {
-                                panic!(
-                                    "Expected Node Group, found {:?}",
-                                    module.metadata_constants[&entry[1]]
-                                )
-                            }
-                        };
+                        spirv_inputs.extend(Self::parse_entry_point_variable(
+                            &mut builder,
+                            &vec![*input],
+                            &vertex_info_output,
+                            &mut variable_count,
+                            &mut location_count,
+                            options,
+                        )?);
+                    }
+                }
+                ty => return Err(anyhow!("Expected Function, found {:?}.", ty)),
+            }
 
-                    let entry_point_inputs = match module.metadata_constants.get(&entry[2]).unwrap()
-                    {
-                        AirMetadataConstant::Node(entry_point_inputs) => entry_point_inputs,
-                        _ => {
-                            panic!(
-                                "Expected Node Group, found {:?}",
-                                module.metadata_constants[&entry[2]]
-                            )
-                        }
-                    };
+            air_arguments.extend(function_signature.ty.param_values.clone());
 
-                    let mut vertex_info_output =
-                        Self::parse_vertex_info(&module, entry_point_outputs.clone());
+            let function = Self::parse_air_function(
+                &mut builder,
+                module,
+                function_signature.global_id,
+                &FunctionContext {
+                    arguments: &air_arguments,
+                    outputs: &spirv_outputs,
+                    inputs: &spirv_inputs,
+                    argument_buffers: &argument_buffers,
+                    global_variables: &global_variables,
+                    constants: &constants,
+                    debug_files: &debug_files,
+                    options,
+                },
+            )?;
+
+            let mut spirv_arguments = spirv_outputs.clone();
+            spirv_arguments.extend(spirv_inputs);
+            spirv_arguments.extend(argument_descriptors);
+
+            // Only variables are part of the interface, and before SPIR-V
+            // 1.4 only Input and Output ones.
+            let mut interface = vec![];
+            for i in spirv_arguments {
+                let pointer_ty = builder.find_operand_type_id(i)?;
+                let is_interface = match builder.module.type_table[&pointer_ty] {
+                    SpirVType::Pointer(SpirVStorageClass::Input | SpirVStorageClass::Output, _) => {
+                        true
+                    }
+                    SpirVType::Pointer(..) => options.spirv_version >= (1, 4),
+                    _ => false,
+                };
 
-                    vertex_info_output
-                        .merge(Self::parse_vertex_info(&module, entry_point_inputs.clone()));
+                if is_interface {
+                    interface.push(i);
+                }
+            }
 
-                    let air_function_type = Self::parse_air_type(
-                        &mut builder,
-                        &module,
-                        &AirType::Function(function_signature.ty.clone()),
-                    );
+            builder.new_entry_point(name, function, stage, interface);
 
-                    let mut location_count = 0;
-                    let mut variable_count = 0;
-                    let mut spirv_inputs = vec![];
-                    let mut spirv_outputs = vec![];
-                    let mut air_arguments = vec![];
-                    match &builder.module.type_table[&air_function_type].clone() {
-                        SpirVType::Function(output, inputs) => {
-                            let output = vec![*output];
-                            spirv_outputs.extend(Self::parse_entry_point_variable(
-                                &mut builder,
-                                &output,
-                                &vertex_info_output,
-                                &mut location_count,
-                                &mut variable_count,
-                            ));
+            match stage {
+                SpirVExecutionModel::Fragment => {
+                    builder.new_execution_mode(function, SpirVExecutionMode::OriginUpperLeft);
 
-                            spirv_inputs.extend(Self::parse_entry_point_variable(
-                                &mut builder,
-                                inputs,
-                                &vertex_info_output,
-                                &mut location_count,
-                                &mut variable_count,
-                            ));
-                        }
-                        _ => panic!(
-                            "Expected Function, found {:?}",
-                            &builder.module.type_table[&air_function_type]
-                        ),
+                    if vertex_info_output.variables.iter().any(|x| {
+                        matches!(x.ty, ShaderVariableType::Output(ShaderOutputType::Depth))
+                    }) {
+                        builder.new_execution_mode(function, SpirVExecutionMode::DepthReplacing);
                     }
+                }
+                SpirVExecutionModel::GLCompute => {
+                    let [x, y, z] = options.threads_per_threadgroup;
+                    builder.new_execution_mode(function, SpirVExecutionMode::LocalSize(x, y, z));
+                }
+                _ => {}
+            }
+        }
 
-                    air_arguments.extend(function_signature.ty.param_values.clone());
-
-                    let function = Self::parse_air_function(
-                        &mut builder,
-                        &module,
-                        function_signature.global_id,
-                        &air_arguments,
-                        &spirv_outputs,
-                        &spirv_inputs,
-                        &global_variables,
-                        &constants,
-                    );
+        if let Some(entry_point) = entry_point
+            && !found_entry_point
+        {
+            return Err(anyhow!("Entry point `{}` not found.", entry_point));
+        }
 
-                    let mut spirv_arguments = spirv_outputs.clone();
-                    spirv_arguments.extend(spirv_inputs);
+        if !options.emit_names {
+            builder.strip_names();
+        }
 
-                    entry_points.insert(
-                        function_signature.global_id,
-                        builder.new_entry_point(
-                            &module.string_table[function_signature.name.0 as usize].content,
-                            function,
-                            SpirVExecutionModel::Vertex,
-                            spirv_arguments,
-                        ),
-                    );
-                }
-            }
-            None => {}
+        Ok(builder)
+    }
+
+    /// Translates every entry point of `module` into its own SPIR-V module,
+    /// spread over `threads` worker threads (`0` uses the available
+    /// parallelism). A failing or panicking entry point only affects its own
+    /// result, which keeps the order of `entry_points`.
+    pub fn translate_entry_points(
+        module: &AirModule,
+        options: &AirToSpirVOptions,
+        threads: usize,
+    ) -> Vec<EntryPointTranslation> {
+        let names = Self::entry_points(module);
+        let threads = match threads {
+            0 => std::thread::available_parallelism().map_or(1, |x| x.get()),
+            threads => threads,
         }
+        .min(names.len());
+
+        let next = AtomicUsize::new(0);
+        let (sender, receiver) = mpsc::channel();
+
+        // Scoped, so the workers borrow the module rather than a copy of it.
+        std::thread::scope(|scope| {
+            for _ in 0..threads {
+                let sender = sender.clone();
+                let (names, next) = (&names, &next);
+
+                scope.spawn(move || {
+                    loop {
+                        let index = next.fetch_add(1, Ordering::Relaxed);
+                        let Some(name) = names.get(index) else {
+                            break;
+                        };
 
-        self.output = builder;
+                        let result = std::panic::catch_unwind(AssertUnwindSafe(|| {
+                            Self::translate(module, options, Some(name))
+                        }))
+                        .unwrap_or_else(|payload| {
+                            let message = payload
+                                .downcast_ref::<&str>()
+                                .map(|x| x.to_string())
+                                .or_else(|| payload.downcast_ref::<String>().cloned())
+                                .unwrap_or("Unknown panic.".to_string());
+
+                            Err(anyhow!("Translation of `{}` panicked: {}", name, message))
+                        });
 
-        Ok(())
+                        if sender.send((index, result)).is_err() {
+                            break;
+                        }
+                    }
+                });
+            }
+        });
+        drop(sender);
+
+        let mut results = receiver.iter().collect::<Vec<_>>();
+        results.sort_by_key(|(index, _)| *index);
+
+        results
+            .into_iter()
+            .map(|(index, result)| EntryPointTranslation {
+                name: names[index].clone(),
+                result,
+            })
+            .collect()
     }
 
-    pub fn vec_mask_to_literal_array(air_mask: AirValueId, module: &AirModule) -> Vec<u32> {
-        let vec_value = module.value_list.get(air_mask.0 as usize).unwrap();
-        let vec_constant = match vec_value {
-            AirValue::Constant(constant) => {
-                let constant = module.constants.get(constant).unwrap();
-                match &constant.value {
-                    AirConstantValue::Aggregate(agg) => agg
-                        .iter()
-                        .map(|i| match module.value_list.get(i.0 as usize).unwrap() {
-                            AirValue::Constant(c) => &module.constants.get(&c).unwrap().value,
-                            _ => panic!("Expected Constant. Found {:?}", i),
-                        })
-                        .collect::<Vec<_>>(),
-                    AirConstantValue::Array(arr) => arr.iter().map(|x| x).collect::<Vec<_>>(),
-                    _ => panic!("Expected Aggregate or Array. Found {:?}", &constant.value),
-                }
+    pub fn vec_mask_to_literal_array(air_mask: AirValueId, module: &AirModule) -> Result<Vec<u32>> {
+        let constant = |value_id: AirValueId| -> Result<&AirConstant> {
+            match module.value_list.get(value_id.0 as usize) {
+                Some(AirValue::Constant(constant)) => module
+                    .constants
+                    .get(constant)
+                    .ok_or(anyhow!("{:?} not found.", constant)),
+                value => Err(anyhow!("Expected Constant, found {:?}.", value)),
             }
-            _ => panic!("Expected Constant. Found {:?}", vec_value),
+        };
+
+        let mask = constant(air_mask)?;
+        let vec_constant = match &mask.value {
+            AirConstantValue::Aggregate(agg) => agg
+                .iter()
+                .map(|i| Ok(&constant(*i)?.value))
+                .collect::<Result<Vec<_>>>()?,
+            AirConstantValue::Array(arr) => arr.iter().collect::<Vec<_>>(),
+            // `zeroinitializer`
+            AirConstantValue::Null => match module.types.get(mask.ty.0 as usize) {
+                Some(AirType::Vector(vector_ty)) => return Ok(vec![0; vector_ty.size as usize]),
+                ty => return Err(anyhow!("Expected Vector, found {:?}.", ty)),
+            },
+            value => return Err(anyhow!("Expected Aggregate or Array, found {:?}.", value)),
         };
 
         let mut result = vec![];
         for i in vec_constant {
             match i {
                 AirConstantValue::Integer(int) => result.push(*int as u32),
-                AirConstantValue::Null | AirConstantValue::Undefined => result.push(0),
-                _ => panic!("Expected Integer. Found {:?}", i),
+                AirConstantValue::Null | AirConstantValue::Undefined | AirConstantValue::Poison => {
+                    result.push(0)
+                }
+                _ => return Err(anyhow!("Expected Integer, found {:?}.", i)),
             }
         }
 
-        result
+        Ok(result)
+    }
+
+    /// The SPIR-V id of an AIR value that's already been translated.
+    pub fn spirv_value(
+        value_list: &HashMap<AirValueId, SpirVVariableId>,
+        value_id: AirValueId,
+    ) -> Result<SpirVVariableId> {
+        value_list
+            .get(&value_id)
+            .copied()
+            .ok_or(anyhow!("{:?} is used before it's defined.", value_id))
+    }
+
+    /// `OpAccessChain` for an LLVM `getelementptr`.
+    pub fn get_element_ptr(
+        builder: &mut SpirVBuilder,
+        module: &AirModule,
+        air_gep: &AirGetElementPtr,
+        value_list: &HashMap<AirValueId, SpirVVariableId>,
+    ) -> Result<SpirVVariableId> {
+        let spirv_base = Self::spirv_value(value_list, air_gep.base_ptr_value)?;
+        let base_pointer_ty = builder.find_operand_type_id(spirv_base)?;
+        let (storage_class, base_ty) = match builder.module.type_table[&base_pointer_ty] {
+            SpirVType::Pointer(storage_class, ty) => (storage_class, ty),
+            ref ty => return Err(anyhow!("Expected Pointer, found {:?}.", ty)),
+        };
+
+        // The first LLVM index steps over the pointer itself. Buffers
+        // are wrapped in a block, so it indexes their runtime array,
+        // everything else has to be indexed at 0.
+        let mut air_indices = air_gep.indices.clone();
+        let mut spirv_indices = vec![];
+        if Self::get_buffer_block_type(builder, base_ty).is_some() {
+            let u32_ty = builder.new_type(SpirVType::Int(32, false));
+            spirv_indices.push(builder.new_constant(SpirVConstant {
+                type_id: u32_ty,
+                value: SpirVConstantValue::UnsignedInteger(0),
+            }));
+        } else if !air_indices.is_empty() {
+            let first = air_indices.remove(0);
+            if Self::get_air_constant_integer(module, first) != Some(0) {
+                return Err(anyhow!(
+                    "Pointer arithmetic on {:?} isn't supported.",
+                    air_gep.base_ptr_value
+                ));
+            }
+        }
+
+        let mut element_ty = base_ty;
+        for i in &air_indices {
+            spirv_indices.push(Self::spirv_value(value_list, *i)?);
+        }
+        for index in &spirv_indices {
+            // Struct members have to be picked by a constant.
+            let member = match builder.module.type_table[&element_ty] {
+                SpirVType::Struct(_) => Self::spirv_constant_to_literal(builder, *index)? as usize,
+                _ => 0,
+            };
+            element_ty = match builder.module.type_table[&element_ty].clone() {
+                SpirVType::RuntimeArray(ty) => ty,
+                _ => Self::spirv_get_element_type(builder, element_ty, member)?,
+            };
+        }
+
+        let pointer_ty = builder.new_type(SpirVType::Pointer(storage_class, element_ty));
+
+        Ok(builder.new_access_chain(SpirVAccessChain {
+            type_id: pointer_ty,
+            base_id: spirv_base,
+            indices: spirv_indices,
+        }))
     }
 
-    pub fn get_air_type_from_value<'a>(module: &'a AirModule, value_id: AirValueId) -> &'a AirType {
-        let get_air_ty = module.value_list.get(value_id.0 as usize).unwrap();
+    pub fn get_air_type_from_value(module: &AirModule, value_id: AirValueId) -> Result<&AirType> {
+        let value = module
+            .value_list
+            .get(value_id.0 as usize)
+            .ok_or(anyhow!("{:?} not found.", value_id))?;
+        let vector_type = |value_id: AirValueId| -> Result<AirVectorType> {
+            match Self::get_air_type_from_value(module, value_id)? {
+                AirType::Vector(vector) => Ok(vector.clone()),
+                ty => Err(anyhow!("Expected Vector, found {:?}.", ty)),
+            }
+        };
 
-        match get_air_ty {
+        Ok(match value {
             AirValue::Constant(constant) => {
-                let constant = module.constants.get(constant).unwrap();
-                let air_ty = module.types.get(constant.ty.0 as usize).unwrap();
-                air_ty
+                let constant = module
+                    .constants
+                    .get(constant)
+                    .ok_or(anyhow!("{:?} not found.", constant))?;
+                module
+                    .types
+                    .get(constant.ty.0 as usize)
+                    .ok_or(anyhow!("{:?} not found.", constant.ty))?
             }
             AirValue::Load(load) => &load.ty,
+            // The mask's length and the inputs' element type.
             AirValue::ShuffleVec(shuffle_vec) => {
-                let size = {
-                    let mask = Self::get_air_type_from_value(module, shuffle_vec.mask);
-                    match mask {
-                        AirType::Vector(v) => v.size,
-                        _ => panic!("Expected Vector, Found: {:?}", mask),
-                    }
-                };
-
-                let element_type = {
-                    let vec1 = Self::get_air_type_from_value(module, shuffle_vec.vec1);
-                    match vec1 {
-                        AirType::Vector(v) => v.element_type,
-                        _ => panic!("Expected Vector, Found: {:?}", vec1),
-                    }
-                };
-
-                for i in &module.types {
-                    if *i == AirType::Vector(AirVectorType { size, element_type }) {
-                        return i;
-                    }
-                }
+                let ty = AirType::Vector(AirVectorType {
+                    size: vector_type(shuffle_vec.mask)?.size,
+                    element_type: vector_type(shuffle_vec.vec1)?.element_type,
+                });
 
-                todo!()
+                module
+                    .types
+                    .iter()
+                    .find(|x| **x == ty)
+                    .ok_or(anyhow!("The module has no {:?}.", ty))?
             }
             AirValue::InsertVal(air_insert_val) => {
-                Self::get_air_type_from_value(module, air_insert_val.value1)
+                Self::get_air_type_from_value(module, air_insert_val.value1)?
             }
-            _ => todo!("{:?}", get_air_ty),
-        }
+            AirValue::InsertElt(air_insert_elt) => {
+                Self::get_air_type_from_value(module, air_insert_elt.vector)?
+            }
+            AirValue::Cast(air_cast) => &air_cast.cast_to_type,
+            AirValue::Argument(argument) => module
+                .types
+                .get(argument.type_id.0 as usize)
+                .ok_or(anyhow!("{:?} not found.", argument.type_id))?,
+            value => return Err(anyhow!("The type of {:?} isn't known.", value)),
+        })
     }
 
     pub fn parse_air_value(
@@ -501,56 +968,146 @@ impl AirToSpirV {
         module: &AirModule,
         value_id: AirValueId,
         value_list: &HashMap<AirValueId, SpirVVariableId>,
-        spirv_entry_point_outputs: &Vec<SpirVVariableId>,
-    ) -> SpirVVariableId {
-        let value = module.value_list.get(value_id.0 as usize).unwrap();
-
-        match value {
+        spirv_entry_point_outputs: &[SpirVVariableId],
+        position_fixups: Option<PositionFixups>,
+        options: &AirToSpirVOptions,
+    ) -> Result<SpirVVariableId> {
+        let value = module
+            .value_list
+            .get(value_id.0 as usize)
+            .ok_or(anyhow!("{:?} not found.", value_id))?;
+
+        Ok(match value {
             AirValue::Cast(air_cast) => {
-                let to_type = Self::parse_air_type(builder, module, &air_cast.cast_to_type);
+                let to_type = Self::parse_air_type(builder, module, &air_cast.cast_to_type)?;
+                let operand = Self::spirv_value(value_list, air_cast.value)?;
+
+                let op_code = match air_cast.cast_code {
+                    CastOpCode::BITCAST => {
+                        return Ok(builder.new_bit_cast(SpirVBitCast {
+                            variable: operand,
+                            to_type,
+                        }));
+                    }
+                    CastOpCode::TRUNC | CastOpCode::ZEXT => SpirVOpCode::UConvert,
+                    CastOpCode::SEXT => SpirVOpCode::SConvert,
+                    CastOpCode::FPTOUI => SpirVOpCode::ConvertFToU,
+                    CastOpCode::FPTOSI => SpirVOpCode::ConvertFToS,
+                    CastOpCode::UITOFP => SpirVOpCode::ConvertUToF,
+                    CastOpCode::SITOFP => SpirVOpCode::ConvertSToF,
+                    CastOpCode::FPTRUNC | CastOpCode::FPEXT => SpirVOpCode::FConvert,
+                    ref cast_code => {
+                        return Err(anyhow!("{:?} casts aren't supported.", cast_code));
+                    }
+                };
 
-                return builder.new_bit_cast(SpirVBitCast {
-                    variable: *value_list.get(&air_cast.value).unwrap(),
-                    to_type,
-                });
+                builder.new_unary_op(
+                    op_code,
+                    SpirVUnaryOp {
+                        type_id: to_type,
+                        operand,
+                    },
+                )
             }
             AirValue::GetElementPtr(air_gep) => {
-                let element_ty = Self::parse_air_type(builder, module, &air_gep.ty);
-                let pointer_ty =
-                    builder.new_type(SpirVType::Pointer(SpirVStorageClass::Private, element_ty));
-
-                let spirv_base = *value_list.get(&air_gep.base_ptr_value).unwrap();
-                let mut spirv_indices = vec![];
-                for i in &air_gep.indices {
-                    spirv_indices.push(*value_list.get(&i).unwrap());
+                Self::get_element_ptr(builder, module, air_gep, value_list)?
+            }
+            AirValue::BinOp(air_bin_op) => {
+                let operand1 = Self::spirv_value(value_list, air_bin_op.lhs)?;
+                let operand2 = Self::spirv_value(value_list, air_bin_op.rhs)?;
+                let type_id = builder.find_operand_type_id(operand1)?;
+
+                let mut scalar_ty = type_id;
+                while let SpirVType::Vector(element_ty, _) = builder.module.type_table[&scalar_ty] {
+                    scalar_ty = element_ty;
                 }
+                let is_float = matches!(builder.module.type_table[&scalar_ty], SpirVType::Float(_));
+
+                let op_code = match (air_bin_op.opcode, is_float) {
+                    (BinaryOpCode::ADD, true) => SpirVOpCode::FAdd,
+                    (BinaryOpCode::SUB, true) => SpirVOpCode::FSub,
+                    (BinaryOpCode::MUL, true) => SpirVOpCode::FMul,
+                    (BinaryOpCode::SDIV, true) => SpirVOpCode::FDiv,
+                    (BinaryOpCode::SREM, true) => SpirVOpCode::FRem,
+                    (BinaryOpCode::ADD, false) => SpirVOpCode::IAdd,
+                    (BinaryOpCode::SUB, false) => SpirVOpCode::ISub,
+                    (BinaryOpCode::MUL, false) => SpirVOpCode::IMul,
+                    (BinaryOpCode::UDIV, false) => SpirVOpCode::UDiv,
+                    (BinaryOpCode::SDIV, false) => SpirVOpCode::SDiv,
+                    (BinaryOpCode::UREM, false) => SpirVOpCode::UMod,
+                    (BinaryOpCode::SREM, false) => SpirVOpCode::SRem,
+                    (BinaryOpCode::SHL, false) => SpirVOpCode::ShiftLeftLogical,
+                    (BinaryOpCode::LSHR, false) => SpirVOpCode::ShiftRightLogical,
+                    (BinaryOpCode::ASHR, false) => SpirVOpCode::ShiftRightArithmetic,
+                    (BinaryOpCode::AND, false) => SpirVOpCode::BitwiseAnd,
+                    (BinaryOpCode::OR, false) => SpirVOpCode::BitwiseOr,
+                    (BinaryOpCode::XOR, false) => SpirVOpCode::BitwiseXor,
+                    (opcode, _) => {
+                        return Err(anyhow!(
+                            "{:?} on {:?} isn't supported.",
+                            opcode,
+                            builder.module.type_table[&scalar_ty]
+                        ));
+                    }
+                };
 
-                return builder.new_access_chain(SpirVAccessChain {
-                    type_id: pointer_ty,
-                    base_id: spirv_base,
-                    indices: spirv_indices,
-                });
+                builder.new_binary_op(
+                    op_code,
+                    SpirVBinaryOp {
+                        type_id,
+                        operand1,
+                        operand2,
+                    },
+                )
             }
             AirValue::Load(air_load) => {
-                let operand = value_list.get(&air_load.op).unwrap();
+                let mut pointer_id = Self::spirv_value(value_list, air_load.op)?;
+
+                let mut load_ty = Self::parse_air_type(builder, module, &air_load.ty)?;
+
+                let pointer_ty = builder.find_operand_type_id(pointer_id)?;
+                if let SpirVType::Pointer(storage_class, mut pointee_ty) =
+                    builder.module.type_table[&pointer_ty]
+                {
+                    // Loading straight from a buffer reads its first element.
+                    if let Some(element_ty) = Self::get_buffer_block_type(builder, pointee_ty) {
+                        let u32_ty = builder.new_type(SpirVType::Int(32, false));
+                        let index_zero = builder.new_constant(SpirVConstant {
+                            type_id: u32_ty,
+                            value: SpirVConstantValue::UnsignedInteger(0),
+                        });
+                        let element_pointer_ty =
+                            builder.new_type(SpirVType::Pointer(storage_class, element_ty));
+
+                        pointer_id = builder.new_access_chain(SpirVAccessChain {
+                            type_id: element_pointer_ty,
+                            base_id: pointer_id,
+                            indices: vec![index_zero, index_zero],
+                        });
+                        pointee_ty = element_ty;
+                    }
 
-                let load_ty = Self::parse_air_type(builder, module, &air_load.ty);
+                    // Matrices are arrays of columns in AIR.
+                    if let SpirVType::Matrix(..) = builder.module.type_table[&pointee_ty] {
+                        load_ty = pointee_ty;
+                    }
+                }
 
-                return builder.new_load(SpirVLoad {
+                builder.new_load(SpirVLoad {
                     type_id: load_ty,
-                    pointer_id: *operand,
+                    pointer_id,
                     memory_operands: SpirVMemoryOperands::None,
-                });
+                })
             }
             AirValue::ShuffleVec(air_shuffle_vec) => {
-                let vec_type = Self::get_air_type_from_value(module, value_id);
+                let vec_type = Self::get_air_type_from_value(module, value_id)?;
 
-                let vec1 = *value_list.get(&air_shuffle_vec.vec1).unwrap();
-                let vec2 = *value_list.get(&air_shuffle_vec.vec2).unwrap();
+                let vec1 = Self::spirv_value(value_list, air_shuffle_vec.vec1)?;
+                let vec2 = Self::spirv_value(value_list, air_shuffle_vec.vec2)?;
 
-                let mask = Self::vec_mask_to_literal_array(air_shuffle_vec.mask, module);
+                let mask = Self::vec_mask_to_literal_array(air_shuffle_vec.mask, module)?;
 
-                let vec_type = Self::parse_air_type(builder, module, vec_type);
+                let vec_type = Self::parse_air_type(builder, module, vec_type)?;
                 builder.new_vector_shuffle(SpirVVectorShuffle {
                     vec_type,
                     vec1,
@@ -558,12 +1115,26 @@ impl AirToSpirV {
                     mask,
                 })
             }
+            AirValue::ExtractVal(air_extract_val) => {
+                let composite_id = Self::spirv_value(value_list, air_extract_val.aggregate)?;
+
+                let mut type_id = builder.find_operand_type_id(composite_id)?;
+                for i in &air_extract_val.indices {
+                    type_id = Self::spirv_get_element_type(builder, type_id, *i as usize)?;
+                }
+
+                builder.new_composite_extract(SpirVCompositeExtract {
+                    type_id,
+                    composite_id,
+                    indices: air_extract_val.indices.iter().map(|x| *x as u32).collect(),
+                })
+            }
             AirValue::InsertVal(air_insert_val) => {
-                let result_type = Self::get_air_type_from_value(module, air_insert_val.value1);
-                let result_type = Self::parse_air_type(builder, module, result_type);
+                let result_type = Self::get_air_type_from_value(module, air_insert_val.value1)?;
+                let result_type = Self::parse_air_type(builder, module, result_type)?;
 
-                let value1 = *value_list.get(&air_insert_val.value1).unwrap();
-                let value2 = *value_list.get(&air_insert_val.value2).unwrap();
+                let value1 = Self::spirv_value(value_list, air_insert_val.value1)?;
+                let value2 = Self::spirv_value(value_list, air_insert_val.value2)?;
 
                 builder.new_composite_insert(SpirVCompositeInsert {
                     type_id: result_type,
@@ -572,85 +1143,141 @@ impl AirToSpirV {
                     indices: vec![air_insert_val.insert_value_idx as u32],
                 })
             }
+            AirValue::ExtractElt(air_extract_elt) => {
+                let vector = Self::spirv_value(value_list, air_extract_elt.vector)?;
+                let vector_ty = builder.find_operand_type_id(vector)?;
+                let type_id = Self::spirv_get_element_type(builder, vector_ty, 0)?;
+
+                match Self::get_air_constant_integer(module, air_extract_elt.index) {
+                    Some(index) => builder.new_composite_extract(SpirVCompositeExtract {
+                        type_id,
+                        composite_id: vector,
+                        indices: vec![index as u32],
+                    }),
+                    None => builder.new_binary_op(
+                        SpirVOpCode::VectorExtractDynamic,
+                        SpirVBinaryOp {
+                            type_id,
+                            operand1: vector,
+                            operand2: Self::spirv_value(value_list, air_extract_elt.index)?,
+                        },
+                    ),
+                }
+            }
+            AirValue::InsertElt(air_insert_elt) => {
+                let result_type = Self::get_air_type_from_value(module, air_insert_elt.vector)?;
+                let result_type = Self::parse_air_type(builder, module, result_type)?;
+
+                let vector = Self::spirv_value(value_list, air_insert_elt.vector)?;
+                let component = Self::spirv_value(value_list, air_insert_elt.value)?;
+
+                match Self::get_air_constant_integer(module, air_insert_elt.index) {
+                    Some(index) => builder.new_composite_insert(SpirVCompositeInsert {
+                        type_id: result_type,
+                        object_id: component,
+                        composite_id: vector,
+                        indices: vec![index as u32],
+                    }),
+                    None => builder.new_vector_insert_dynamic(SpirVVectorInsertDynamic {
+                        type_id: result_type,
+                        vector,
+                        component,
+                        index: Self::spirv_value(value_list, air_insert_elt.index)?,
+                    }),
+                }
+            }
             AirValue::Return(air_return) => {
+                let return_value = match air_return.value {
+                    Some(value) => value,
+                    None => return Ok(builder.new_return(None)),
+                };
+
                 if spirv_entry_point_outputs.len() == 0 {
-                    return builder.new_return(Some(*value_list.get(&air_return.value).unwrap()));
+                    let value = Self::spirv_value(value_list, return_value)?;
+                    return Ok(builder.new_return(Some(value)));
                 }
 
-                let get_return_ty = Self::get_air_type_from_value(module, air_return.value);
-                let spirv_return_ty = Self::parse_air_type(builder, module, get_return_ty);
-                let spirv_return_pointer_ty = builder.new_type(SpirVType::Pointer(
-                    SpirVStorageClass::Private,
-                    spirv_return_ty,
-                ));
+                let get_return_ty = Self::get_air_type_from_value(module, return_value)?;
+                let spirv_return_ty = Self::parse_air_type(builder, module, get_return_ty)?;
+                let spirv_return_value = Self::spirv_value(value_list, return_value)?;
 
-                let load = builder.new_load(SpirVLoad {
-                    type_id: spirv_return_ty,
-                    pointer_id: spirv_return_pointer_ty,
-                    memory_operands: SpirVMemoryOperands::None,
-                });
-
-                let index_zero_ty = builder.new_type(SpirVType::Int(32, true));
-                let index_zero = builder.new_constant(SpirVConstant {
-                    type_id: index_zero_ty,
-                    value: SpirVConstantValue::SignedInteger(0),
-                });
+                // Several outputs come back as a struct, one per member.
+                let is_struct = matches!(get_return_ty, AirType::Struct(_));
 
-                let mut count = 0;
-                for i in spirv_entry_point_outputs {
-                    let spirv_pointer_ty = builder.find_operand_type_id(*i);
+                for (count, i) in spirv_entry_point_outputs.iter().enumerate() {
+                    let spirv_pointer_ty = builder.find_operand_type_id(*i)?;
                     let spirv_value_ty = builder.find_pointer_type(spirv_pointer_ty);
 
-                    let spirv_value = builder.new_composite_extract(SpirVCompositeExtract {
-                        type_id: spirv_value_ty,
-                        composite_id: load,
-                        indices: vec![count],
-                    });
+                    let mut spirv_value = if is_struct {
+                        let member_ty =
+                            Self::spirv_get_element_type(builder, spirv_return_ty, count)?;
+                        builder.new_composite_extract(SpirVCompositeExtract {
+                            type_id: member_ty,
+                            composite_id: spirv_return_value,
+                            indices: vec![count as u32],
+                        })
+                    } else {
+                        spirv_return_value
+                    };
 
-                    let access = builder.new_access_chain(SpirVAccessChain {
-                        type_id: spirv_pointer_ty,
-                        base_id: *i,
-                        indices: vec![index_zero],
-                    });
+                    let mut pointer = *i;
+                    if let Some(fixups) = position_fixups.filter(|x| x.block_ty == spirv_value_ty) {
+                        spirv_value =
+                            Self::apply_position_fixups(builder, options, spirv_value, &fixups);
+
+                        pointer = builder.new_access_chain(SpirVAccessChain {
+                            type_id: fixups.pointer_ty,
+                            base_id: *i,
+                            indices: vec![fixups.index_zero],
+                        });
+                    }
 
                     builder.new_store(SpirVStore {
-                        pointer_id: access,
+                        pointer_id: pointer,
                         object_id: spirv_value,
                         memory_operands: SpirVMemoryOperands::None,
                     });
-
-                    count += 1;
                 }
 
                 builder.new_return(None)
             }
-            _ => todo!("{:?}", value),
-        }
+            _ => return Err(anyhow!("{:?} isn't supported.", value)),
+        })
     }
 
     pub fn parse_air_function(
         builder: &mut SpirVBuilder,
         module: &AirModule,
         air_signature: AirFunctionSignatureId,
-        air_entry_points: &Vec<AirValueId>,
-        spirv_entry_point_outputs: &Vec<SpirVVariableId>,
-        spirv_entry_point_inputs: &Vec<SpirVVariableId>,
-        global_variables: &HashMap<AirGlobalVariableId, SpirVVariableId>,
-        constants: &HashMap<AirConstantId, SpirVVariableId>,
-    ) -> SpirVVariableId {
+        context: &FunctionContext,
+    ) -> Result<SpirVVariableId> {
+        let FunctionContext {
+            arguments: air_entry_points,
+            outputs: spirv_entry_point_outputs,
+            inputs: spirv_entry_point_inputs,
+            argument_buffers,
+            global_variables,
+            constants,
+            debug_files,
+            options,
+        } = *context;
+
         let mut air_function_body = None;
         for i in &module.function_bodies {
             if i.signature == air_signature {
                 air_function_body = Some(i);
             }
         }
-        let air_signature = module.get_function_signature(air_signature).unwrap();
+        let air_signature = module
+            .get_function_signature(air_signature)
+            .ok_or(anyhow!("Function {:?} not found.", air_signature))?;
+        let name = &module.string_table[air_signature.name.0 as usize].content;
 
         let return_type = Self::parse_air_type(
             builder,
             module,
             &module.types[air_signature.ty.return_type.0 as usize],
-        );
+        )?;
 
         let void_ty = builder.new_type(SpirVType::Void);
         let function_type =
@@ -674,16 +1301,46 @@ impl AirToSpirV {
             )
         };
 
-        let air_function_body = air_function_body.unwrap();
+        let air_function_body = air_function_body.ok_or(anyhow!("`{}` has no body.", name))?;
 
         builder.new_basic_block();
 
         let mut value_list: HashMap<AirValueId, SpirVVariableId> = HashMap::new();
 
-        let mut count = 0;
+        // Input variables are loaded once up front, buffers stay pointers.
+        // Argument buffers have no variable of their own, see
+        // `parse_argument_buffer_access`.
+        let mut spirv_inputs = spirv_entry_point_inputs.iter();
         for i in air_entry_points {
-            value_list.insert(*i, spirv_entry_point_inputs[count]);
-            count += 1;
+            if argument_buffers.contains_key(i) {
+                continue;
+            }
+
+            let spirv_input =
+                *spirv_inputs
+                    .next()
+                    .ok_or(anyhow!("`{}` has no input for {:?}.", name, i))?;
+            let pointer_ty = builder.find_operand_type_id(spirv_input)?;
+
+            let (value, ty) = match builder.module.type_table[&pointer_ty] {
+                SpirVType::Pointer(SpirVStorageClass::Input, ty) => (
+                    builder.new_load(SpirVLoad {
+                        type_id: ty,
+                        pointer_id: spirv_input,
+                        memory_operands: SpirVMemoryOperands::None,
+                    }),
+                    ty,
+                ),
+                SpirVType::Pointer(..) => {
+                    value_list.insert(*i, spirv_input);
+                    continue;
+                }
+                // `threads_per_threadgroup` is a constant.
+                _ => (spirv_input, pointer_ty),
+            };
+
+            let value = Self::narrow_built_in(builder, module, *i, value, ty)?;
+            value_list.insert(*i, value);
         }
 
         for (id, spirv_value) in global_variables {
@@ -698,9 +1355,65 @@ impl AirToSpirV {
             }
         }
 
+        let (matrix_products, matrix_values) =
+            Self::matrix_products(module, &air_function_body.contents);
+
+        let mut position_fixups = None;
+        for x in spirv_entry_point_outputs {
+            let pointer_ty = builder.find_operand_type_id(*x)?;
+            let block_ty = builder.find_pointer_type(pointer_ty);
+            position_fixups = PositionFixups::new(builder, options, block_ty);
+
+            if position_fixups.is_some() {
+                break;
+            }
+        }
+
+        let mut member_pointers = HashSet::new();
+        let mut current_line: Option<SpirVLine> = None;
         for i in &air_function_body.contents {
-            let value =
-                Self::parse_air_value(builder, module, *i, &value_list, spirv_entry_point_outputs);
+            if matrix_values.contains(i) {
+                continue;
+            }
+
+            if !debug_files.is_empty() {
+                let line = Self::parse_debug_location(module, *i, debug_files);
+                if line != current_line {
+                    match &line {
+                        Some(line) => builder.new_line(line.clone()),
+                        None => builder.new_no_line(),
+                    }
+                    current_line = line;
+                }
+            }
+
+            if let Some(value) = Self::parse_argument_buffer_access(
+                builder,
+                module,
+                *i,
+                &value_list,
+                argument_buffers,
+                &mut member_pointers,
+            )? {
+                value_list.insert(*i, value);
+                continue;
+            }
+
+            if let Some(product) = matrix_products.get(i) {
+                let value = Self::emit_matrix_product(builder, module, product, &value_list)?;
+                value_list.insert(*i, value);
+                continue;
+            }
+
+            let value = Self::parse_air_value(
+                builder,
+                module,
+                *i,
+                &value_list,
+                spirv_entry_point_outputs,
+                position_fixups,
+                options,
+            )?;
 
             value_list.insert(*i, value);
         }
@@ -708,10 +1421,241 @@ impl AirToSpirV {
         builder.end_function(func)
     }
 
-    pub fn spirv_constant_to_literal(builder: &mut SpirVBuilder, id: SpirVVariableId) -> u64 {
-        match builder.module.constants_table.get(&id).unwrap().value {
-            SpirVConstantValue::UnsignedInteger(int) => int,
-            _ => todo!(),
+    /// Compute built-ins are `uint3`, Metal also takes them as `uint` or
+    /// `uint2`. Anything else comes back as is.
+    pub fn narrow_built_in(
+        builder: &mut SpirVBuilder,
+        module: &AirModule,
+        argument: AirValueId,
+        value: SpirVVariableId,
+        ty: SpirVVariableId,
+    ) -> Result<SpirVVariableId> {
+        let expected = Self::parse_air_type(
+            builder,
+            module,
+            Self::get_air_type_from_value(module, argument)?,
+        )?;
+        if expected == ty {
+            return Ok(value);
+        }
+
+        Ok(
+            match (
+                &builder.module.type_table[&ty],
+                &builder.module.type_table[&expected],
+            ) {
+                (SpirVType::Vector(element, 3), SpirVType::Vector(expected_element, size))
+                    if element == expected_element =>
+                {
+                    builder.new_vector_shuffle(SpirVVectorShuffle {
+                        vec_type: expected,
+                        vec1: value,
+                        vec2: value,
+                        mask: (0..*size).collect(),
+                    })
+                }
+                (SpirVType::Vector(element, 3), _) if *element == expected => builder
+                    .new_composite_extract(SpirVCompositeExtract {
+                        type_id: expected,
+                        composite_id: value,
+                        indices: vec![0],
+                    }),
+                (ty, expected) => {
+                    return Err(anyhow!("{:?} can't be passed as {:?}.", ty, expected));
+                }
+            },
+        )
+    }
+
+    /// Returns the element type if `ty` is the block wrapping a buffer's
+    /// runtime array.
+    pub fn get_buffer_block_type(
+        builder: &SpirVBuilder,
+        ty: SpirVVariableId,
+    ) -> Option<SpirVVariableId> {
+        match builder.module.type_table.get(&ty)? {
+            SpirVType::Struct(members) if members.len() == 1 => {
+                match builder.module.type_table.get(&members[0])? {
+                    SpirVType::RuntimeArray(element_ty) => Some(*element_ty),
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+
+    pub fn get_air_constant_integer(module: &AirModule, value_id: AirValueId) -> Option<u64> {
+        match module.value_list.get(value_id.0 as usize)? {
+            AirValue::Constant(constant) => match module.constants.get(constant)?.value {
+                AirConstantValue::Integer(value) => Some(value),
+                AirConstantValue::Null => Some(0),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// Returns the `float4` member type if `ty` is the `BuiltIn Position` block.
+    pub fn get_position_type(
+        builder: &SpirVBuilder,
+        ty: SpirVVariableId,
+    ) -> Option<SpirVVariableId> {
+        let decorate = builder.module.decorate_table.get(&ty)?;
+        if !matches!(
+            decorate.member_decorates.first(),
+            Some(SpirVDecorateType::BuiltIn(SpirVBuiltIn::Position))
+        ) {
+            return None;
+        }
+
+        match builder.module.type_table.get(&ty)? {
+            SpirVType::Struct(members) => members.first().copied(),
+            _ => None,
+        }
+    }
+
+    pub fn apply_position_fixups(
+        builder: &mut SpirVBuilder,
+        options: &AirToSpirVOptions,
+        position: SpirVVariableId,
+        fixups: &PositionFixups,
+    ) -> SpirVVariableId {
+        let (float_ty, position_ty) = (fixups.float_ty, fixups.position_ty);
+        let mut position = position;
+
+        if options.flip_y {
+            let y = builder.new_composite_extract(SpirVCompositeExtract {
+                type_id: float_ty,
+                composite_id: position,
+                indices: vec![1],
+            });
+            let y = builder.new_unary_op(
+                SpirVOpCode::FNegate,
+                SpirVUnaryOp {
+                    type_id: float_ty,
+                    operand: y,
+                },
+            );
+
+            position = builder.new_composite_insert(SpirVCompositeInsert {
+                type_id: position_ty,
+                object_id: y,
+                composite_id: position,
+                indices: vec![1],
+            });
+        }
+
+        if let Some(two) = fixups.two {
+            let z = builder.new_composite_extract(SpirVCompositeExtract {
+                type_id: float_ty,
+                composite_id: position,
+                indices: vec![2],
+            });
+            let w = builder.new_composite_extract(SpirVCompositeExtract {
+                type_id: float_ty,
+                composite_id: position,
+                indices: vec![3],
+            });
+            let z = builder.new_binary_op(
+                SpirVOpCode::FMul,
+                SpirVBinaryOp {
+                    type_id: float_ty,
+                    operand1: z,
+                    operand2: two,
+                },
+            );
+            let z = builder.new_binary_op(
+                SpirVOpCode::FSub,
+                SpirVBinaryOp {
+                    type_id: float_ty,
+                    operand1: z,
+                    operand2: w,
+                },
+            );
+
+            position = builder.new_composite_insert(SpirVCompositeInsert {
+                type_id: position_ty,
+                object_id: z,
+                composite_id: position,
+                indices: vec![2],
+            });
+        }
+
+        position
+    }
+
+    /// Emits an `OpString` with the full path of every `DIFile`, keyed
+    /// by its metadata id.
+    pub fn parse_debug_files(
+        builder: &mut SpirVBuilder,
+        module: &AirModule,
+    ) -> HashMap<u64, SpirVVariableId> {
+        let mut ids = module
+            .metadata_constants
+            .keys()
+            .copied()
+            .collect::<Vec<_>>();
+        ids.sort();
+
+        let mut result = HashMap::new();
+        for id in ids {
+            let file = match module.get_debug_info(id) {
+                Some(AirDebugInfo::File(file)) => file,
+                _ => continue,
+            };
+
+            let filename = module
+                .get_metadata_string(file.filename)
+                .unwrap_or_default();
+            let path = match module.get_metadata_string(file.directory) {
+                Some(directory) if !directory.is_empty() => Path::new(&directory)
+                    .join(&filename)
+                    .to_string_lossy()
+                    .to_string(),
+                _ => filename,
+            };
+
+            result.insert(id, builder.new_string(&path));
+        }
+
+        result
+    }
+
+    pub fn get_debug_scope_file(module: &AirModule, scope: u64) -> Option<u64> {
+        match module.get_debug_info(scope)? {
+            AirDebugInfo::File(_) => Some(scope),
+            AirDebugInfo::CompileUnit(unit) => unit.file,
+            AirDebugInfo::Subprogram(subprogram) => subprogram.file,
+            AirDebugInfo::LexicalBlock(block) => block.file,
+            AirDebugInfo::LexicalBlockFile(block) => block.file,
+            _ => None,
+        }
+    }
+
+    pub fn parse_debug_location(
+        module: &AirModule,
+        value_id: AirValueId,
+        debug_files: &HashMap<u64, SpirVVariableId>,
+    ) -> Option<SpirVLine> {
+        let location = module.debug_locations.get(&value_id)?;
+        let file = Self::get_debug_scope_file(module, location.scope)?;
+
+        Some(SpirVLine {
+            file: *debug_files.get(&file)?,
+            line: location.line as u32,
+            column: location.column as u32,
+        })
+    }
+
+    pub fn spirv_constant_to_literal(
+        builder: &mut SpirVBuilder,
+        id: SpirVVariableId,
+    ) -> Result<u64> {
+        match builder.module.constants_table.get(&id).map(|x| &x.value) {
+            Some(SpirVConstantValue::UnsignedInteger(int)) => Ok(*int),
+            Some(SpirVConstantValue::SignedInteger(int)) if *int >= 0 => Ok(*int as u64),
+            Some(SpirVConstantValue::Null) => Ok(0),
+            value => Err(anyhow!("Expected an integer constant, found {:?}.", value)),
         }
     }
 
@@ -720,8 +1664,9 @@ impl AirToSpirV {
         location: &mut u32,
         current_ty: SpirVVariableId,
         element_info: &ShaderVariable,
-    ) -> SpirVVariableId {
-        match &element_info.ty {
+        options: &AirToSpirVOptions,
+    ) -> Result<SpirVVariableId> {
+        Ok(match &element_info.ty {
             ShaderVariableType::Output(output) => match output {
                 ShaderOutputType::VertexOutput => {
                     let output_pointer =
@@ -788,6 +1733,51 @@ impl AirToSpirV {
 
                     builder.new_variable("VertexOutput", pointer, SpirVStorageClass::Output, None)
                 }
+                ShaderOutputType::RenderTarget => {
+                    let output_pointer =
+                        builder.new_type(SpirVType::Pointer(SpirVStorageClass::Output, current_ty));
+
+                    let output_var = builder.new_variable(
+                        &element_info.name.clone(),
+                        output_pointer,
+                        SpirVStorageClass::Output,
+                        None,
+                    );
+
+                    // `[[color(n)]]` is the attachment's location.
+                    builder.set_decorate(
+                        output_var,
+                        SpirVDecorate {
+                            ty: SpirVDecorateType::Location(
+                                element_info.location.unwrap_or(0) as u32
+                            ),
+                            member_decorates: vec![],
+                        },
+                    );
+
+                    output_var
+                }
+                ShaderOutputType::Depth => {
+                    let output_pointer =
+                        builder.new_type(SpirVType::Pointer(SpirVStorageClass::Output, current_ty));
+
+                    let depth = builder.new_variable(
+                        &element_info.name.clone(),
+                        output_pointer,
+                        SpirVStorageClass::Output,
+                        None,
+                    );
+
+                    builder.set_decorate(
+                        depth,
+                        SpirVDecorate {
+                            ty: SpirVDecorateType::BuiltIn(SpirVBuiltIn::FragDepth),
+                            member_decorates: vec![],
+                        },
+                    );
+
+                    depth
+                }
             },
             ShaderVariableType::Input(input) => match input {
                 ShaderInputType::VertexInput => {
@@ -801,7 +1791,10 @@ impl AirToSpirV {
                         None,
                     );
 
-                    let location_ty = SpirVDecorateType::Location(*location);
+                    // Attributes sit at their `[[attribute(n)]]`, which is
+                    // what the pipeline's vertex input state refers to.
+                    let location_ty =
+                        SpirVDecorateType::Location(element_info.buffer_index.unwrap_or(*location));
                     *location += 1;
 
                     builder.set_decorate(
@@ -828,63 +1821,707 @@ impl AirToSpirV {
                     builder.set_decorate(
                         vertex_id,
                         SpirVDecorate {
-                            ty: SpirVDecorateType::BuiltIn(SpirVBuiltIn::VertexId),
+                            ty: SpirVDecorateType::BuiltIn(SpirVBuiltIn::VertexIndex),
                             member_decorates: vec![],
                         },
                     );
 
                     vertex_id
                 }
+                ShaderInputType::FragmentInput => {
+                    let input_pointer =
+                        builder.new_type(SpirVType::Pointer(SpirVStorageClass::Input, current_ty));
+
+                    let input_var = builder.new_variable(
+                        &element_info.name.clone(),
+                        input_pointer,
+                        SpirVStorageClass::Input,
+                        None,
+                    );
+
+                    // `user(locnN)` matches the vertex output at location N.
+                    let location_ty = SpirVDecorateType::Location(
+                        element_info.location.map_or(*location, |x| x as u32),
+                    );
+                    *location += 1;
+
+                    builder.set_decorate(
+                        input_var,
+                        SpirVDecorate {
+                            ty: location_ty,
+                            member_decorates: vec![],
+                        },
+                    );
+
+                    let interpolation = match element_info.interpolation {
+                        ShaderInterpolation::Perspective => None,
+                        ShaderInterpolation::NoPerspective => {
+                            Some(SpirVDecorateType::NoPerspective)
+                        }
+                        ShaderInterpolation::Flat => Some(SpirVDecorateType::Flat),
+                    };
+                    if let Some(interpolation) = interpolation {
+                        builder.set_decorate(
+                            input_var,
+                            SpirVDecorate {
+                                ty: interpolation,
+                                member_decorates: vec![],
+                            },
+                        );
+                    }
+
+                    input_var
+                }
+                ShaderInputType::BuiltIn(SpirVBuiltIn::WorkgroupSize) => {
+                    // Vulkan only allows `WorkgroupSize` on a constant, which
+                    // is what the kernel was translated for anyway.
+                    let u32_ty = builder.new_type(SpirVType::Int(32, false));
+                    let vector_ty = builder.new_type(SpirVType::Vector(u32_ty, 3));
+                    let values = options
+                        .threads_per_threadgroup
+                        .map(|x| {
+                            builder.new_constant(SpirVConstant {
+                                type_id: u32_ty,
+                                value: SpirVConstantValue::UnsignedInteger(x as u64),
+                            })
+                        })
+                        .to_vec();
+
+                    builder.new_constant_composite(SpirVConstantComposite {
+                        type_id: vector_ty,
+                        values,
+                    })
+                }
+                ShaderInputType::BuiltIn(built_in) => {
+                    // Metal takes the compute ones as `uint`, `uint2` or
+                    // `uint3`, `parse_air_function` narrows them.
+                    let ty = match built_in {
+                        SpirVBuiltIn::NumWorkgroups
+                        | SpirVBuiltIn::WorkgroupId
+                        | SpirVBuiltIn::LocalInvocationId
+                        | SpirVBuiltIn::GlobalInvocationId => {
+                            let u32_ty = builder.new_type(SpirVType::Int(32, false));
+                            builder.new_type(SpirVType::Vector(u32_ty, 3))
+                        }
+                        SpirVBuiltIn::LocalInvocationIndex => {
+                            builder.new_type(SpirVType::Int(32, false))
+                        }
+                        _ => current_ty,
+                    };
+                    let input_pointer =
+                        builder.new_type(SpirVType::Pointer(SpirVStorageClass::Input, ty));
+
+                    let input_var = builder.new_variable(
+                        &element_info.name.clone(),
+                        input_pointer,
+                        SpirVStorageClass::Input,
+                        None,
+                    );
+
+                    builder.set_decorate(
+                        input_var,
+                        SpirVDecorate {
+                            ty: SpirVDecorateType::BuiltIn(*built_in),
+                            member_decorates: vec![],
+                        },
+                    );
+
+                    input_var
+                }
+                ShaderInputType::Buffer => {
+                    let mut element_ty = Self::spirv_get_element_type(builder, current_ty, 0)?;
+                    if Self::matrix_dimensions(&element_info.type_name).is_some()
+                        && let Some(matrix_ty) = Self::matrix_type(builder, element_ty)
+                    {
+                        element_ty = matrix_ty;
+                    }
+
+                    let stride = element_info
+                        .arg_type_size
+                        .map(|x| x as u32)
+                        .or(Self::metal_type_name_layout(&element_info.type_name)
+                            .map(|x| x.stride()));
+                    let block_ty = Self::buffer_block_type(
+                        builder,
+                        &element_info.name,
+                        element_ty,
+                        stride,
+                        &element_info.arguments,
+                        options,
+                    )?;
+
+                    let (set, binding) = options.descriptor_binding(
+                        ShaderResourceKind::Buffer,
+                        element_info.buffer_index.unwrap_or(0),
+                    );
+
+                    Self::new_descriptor_variable(
+                        builder,
+                        &element_info.name,
+                        block_ty,
+                        Self::buffer_storage_class(options),
+                        (set, binding),
+                        element_info.read_only,
+                    )
+                }
+                ShaderInputType::Texture | ShaderInputType::Sampler => {
+                    let (kind, ty) = match input {
+                        ShaderInputType::Texture => (
+                            ShaderResourceKind::Texture,
+                            Self::texture_image_type(builder, &element_info.type_name)?,
+                        ),
+                        _ => (
+                            ShaderResourceKind::Sampler,
+                            builder.new_type(SpirVType::Sampler),
+                        ),
+                    };
+
+                    Self::new_descriptor_variable(
+                        builder,
+                        &element_info.name,
+                        ty,
+                        SpirVStorageClass::UniformConstant,
+                        options.descriptor_binding(kind, element_info.buffer_index.unwrap_or(0)),
+                        false,
+                    )
+                }
+                ShaderInputType::ArgumentBuffer => {
+                    return Err(anyhow!(
+                        "Argument buffers are declared by `declare_argument_buffer`."
+                    ));
+                }
             },
-            _ => todo!("{:?}", element_info),
+            ShaderVariableType::Unknown => {
+                return Err(anyhow!("`{}` has no known type.", element_info.name));
+            }
+        })
+    }
+
+    /// Block around a runtime array of `element_ty`, what a buffer's
+    /// descriptor points to. `stride` is the element size Metal reported,
+    /// `members` its `air.struct_type_info`.
+    pub fn buffer_block_type(
+        builder: &mut SpirVBuilder,
+        name: &str,
+        element_ty: SpirVVariableId,
+        stride: Option<u32>,
+        members: &[ArgumentBufferArgument],
+        options: &AirToSpirVOptions,
+    ) -> Result<SpirVVariableId> {
+        Self::decorate_metal_layout(builder, element_ty, members)?;
+
+        let stride = stride.unwrap_or(Self::metal_layout(builder, element_ty)?.stride());
+        let array_ty =
+            Self::strided_array_type(builder, SpirVType::RuntimeArray(element_ty), stride);
+        if !options.scalar_block_layout {
+            Self::check_extended_layout(builder, array_ty)?;
+        }
+
+        let block_ty = builder.new_struct_type(
+            &format!("{}_block", name),
+            false,
+            vec![(String::new(), array_ty)],
+        );
+        builder.set_decorate(
+            block_ty,
+            SpirVDecorate {
+                ty: Self::buffer_block_decoration(options),
+                member_decorates: vec![SpirVDecorateType::Offset(0)],
+            },
+        );
+        Self::decorate_matrix_member(builder, block_ty, 0, array_ty)?;
+
+        Ok(block_ty)
+    }
+
+    /// Decoration of the structs buffer descriptors point to.
+    pub fn buffer_block_decoration(options: &AirToSpirVOptions) -> SpirVDecorateType {
+        match Self::buffer_storage_class(options) {
+            SpirVStorageClass::StorageBuffer => SpirVDecorateType::Block,
+            _ => SpirVDecorateType::BufferBlock,
         }
     }
 
+    /// `OpTypeImage` for a Metal texture type such as `texture2d<float, sample>`
+    /// or `depth2d_array<float>`.
+    pub fn texture_image_type(
+        builder: &mut SpirVBuilder,
+        type_name: &str,
+    ) -> Result<SpirVVariableId> {
+        let (base, parameters) = type_name
+            .trim_end_matches('>')
+            .split_once('<')
+            .unwrap_or((type_name, "float"));
+        let mut parameters = parameters.split(',').map(|x| x.trim());
+
+        let sampled_type = match parameters.next().unwrap_or("float") {
+            "float" | "half" => builder.new_type(SpirVType::Float(32)),
+            "int" | "short" => builder.new_type(SpirVType::Int(32, true)),
+            "uint" | "ushort" => builder.new_type(SpirVType::Int(32, false)),
+            element => {
+                return Err(anyhow!(
+                    "Texture element type `{}` isn't supported.",
+                    element
+                ));
+            }
+        };
+
+        let (depth, shape) = match base.strip_prefix("depth") {
+            Some(shape) => (1, shape),
+            None => (0, base.strip_prefix("texture").unwrap_or(base)),
+        };
+
+        let dim = if shape.starts_with("1d") {
+            SpirVDim::Dim1D
+        } else if shape.starts_with("2d") {
+            SpirVDim::Dim2D
+        } else if shape.starts_with("3d") {
+            SpirVDim::Dim3D
+        } else if shape.starts_with("cube") {
+            SpirVDim::Cube
+        } else if shape.starts_with("_buffer") {
+            SpirVDim::Buffer
+        } else {
+            return Err(anyhow!("Texture type `{}` isn't supported.", type_name));
+        };
+
+        Ok(builder.new_type(SpirVType::Image(SpirVImageType {
+            sampled_type,
+            dim,
+            depth,
+            arrayed: shape.contains("_array"),
+            multisampled: shape.contains("_ms"),
+            sampled: match parameters.next() {
+                Some("write" | "read_write") => 2,
+                _ => 1,
+            },
+            format: 0,
+        })))
+    }
+
+    /// Scalar and vector types by their Metal name, for values behind
+    /// opaque pointers.
+    pub fn metal_type_name_to_spirv(
+        builder: &mut SpirVBuilder,
+        type_name: &str,
+    ) -> Result<SpirVVariableId> {
+        let type_name = type_name.strip_prefix("packed_").unwrap_or(type_name);
+        let (scalar, size) = match type_name.strip_suffix(['2', '3', '4']) {
+            Some(scalar) => (scalar, type_name[scalar.len()..].parse::<u32>().ok()),
+            None => (type_name, None),
+        };
+
+        let scalar = match scalar {
+            "float" => builder.new_type(SpirVType::Float(32)),
+            "int" => builder.new_type(SpirVType::Int(32, true)),
+            "uint" => builder.new_type(SpirVType::Int(32, false)),
+            "short" => builder.new_type(SpirVType::Int(16, true)),
+            "ushort" => builder.new_type(SpirVType::Int(16, false)),
+            "char" => builder.new_type(SpirVType::Int(8, true)),
+            "uchar" => builder.new_type(SpirVType::Int(8, false)),
+            _ => return Err(anyhow!("Metal type `{}` isn't supported.", type_name)),
+        };
+
+        Ok(match size {
+            Some(size) => builder.new_type(SpirVType::Vector(scalar, size)),
+            None => scalar,
+        })
+    }
+
+    pub fn new_descriptor_variable(
+        builder: &mut SpirVBuilder,
+        name: &str,
+        ty: SpirVVariableId,
+        storage_class: SpirVStorageClass,
+        (set, binding): (u32, u32),
+        read_only: bool,
+    ) -> SpirVVariableId {
+        let pointer = builder.new_type(SpirVType::Pointer(storage_class, ty));
+        let variable = builder.new_variable(name, pointer, storage_class, None);
+
+        for ty in [
+            SpirVDecorateType::DescriptorSet(set),
+            SpirVDecorateType::Binding(binding),
+        ] {
+            builder.set_decorate(
+                variable,
+                SpirVDecorate {
+                    ty,
+                    member_decorates: vec![],
+                },
+            );
+        }
+
+        if read_only {
+            builder.set_decorate(
+                variable,
+                SpirVDecorate {
+                    ty: SpirVDecorateType::NonWritable,
+                    member_decorates: vec![],
+                },
+            );
+        }
+
+        variable
+    }
+
+    /// Declares a descriptor for every member of an argument buffer that
+    /// isn't plain data, arrays become arrays of descriptors. The result
+    /// follows `layout.arguments`.
+    pub fn declare_argument_buffer(
+        builder: &mut SpirVBuilder,
+        layout: &ArgumentBufferLayout,
+        options: &AirToSpirVOptions,
+    ) -> Result<ArgumentBufferDescriptors> {
+        let mut result = vec![];
+
+        for argument in &layout.arguments {
+            let name = format!("{}.{}", layout.name, argument.name);
+            let (ty, storage_class, read_only) = match argument.kind {
+                // Lives in the data block instead.
+                ArgumentKind::Data => {
+                    result.push(None);
+                    continue;
+                }
+                ArgumentKind::Buffer => {
+                    let element_ty = Self::metal_type_name_to_spirv(builder, &argument.type_name)?;
+                    let stride = argument
+                        .element_size
+                        .map(|x| x as u32)
+                        .or(Self::metal_type_name_layout(&argument.type_name).map(|x| x.stride()));
+
+                    (
+                        Self::buffer_block_type(builder, &name, element_ty, stride, &[], options)?,
+                        Self::buffer_storage_class(options),
+                        argument.read_only,
+                    )
+                }
+                ArgumentKind::Texture => (
+                    Self::texture_image_type(builder, &argument.type_name)?,
+                    SpirVStorageClass::UniformConstant,
+                    false,
+                ),
+                ArgumentKind::Sampler => (
+                    builder.new_type(SpirVType::Sampler),
+                    SpirVStorageClass::UniformConstant,
+                    false,
+                ),
+            };
+
+            let ty = if argument.array_length > 1 {
+                let length = Self::u32_constant(builder, argument.array_length);
+
+                builder.new_type(SpirVType::Array(ty, length))
+            } else {
+                ty
+            };
+
+            let id = argument
+                .id
+                .ok_or(anyhow!("`{}` has no `[[id(n)]]`.", name))?;
+            result.push(Some(Self::new_descriptor_variable(
+                builder,
+                &name,
+                ty,
+                storage_class,
+                (layout.set, id),
+                read_only,
+            )));
+        }
+
+        Ok(ArgumentBufferDescriptors {
+            layout: layout.clone(),
+            descriptors: result,
+            data: Self::declare_argument_buffer_data(builder, layout, options)?,
+        })
+    }
+
+    /// Plain data members live in the argument buffer itself, which is
+    /// bound as a buffer at `layout.data_binding()`. Members keep the
+    /// offsets Metal gave them.
+    pub fn declare_argument_buffer_data(
+        builder: &mut SpirVBuilder,
+        layout: &ArgumentBufferLayout,
+        options: &AirToSpirVOptions,
+    ) -> Result<Option<SpirVVariableId>> {
+        if !layout.has_data() {
+            return Ok(None);
+        }
+
+        let mut members = vec![];
+        let mut offsets = vec![];
+        for argument in layout
+            .arguments
+            .iter()
+            .filter(|x| x.kind == ArgumentKind::Data)
+        {
+            let ty = Self::metal_type_name_to_spirv(builder, &argument.type_name)?;
+            let ty = if argument.array_length > 1 {
+                let length = Self::u32_constant(builder, argument.array_length);
+                let stride = argument.size / argument.array_length as u64;
+
+                Self::strided_array_type(builder, SpirVType::Array(ty, length), stride as u32)
+            } else {
+                ty
+            };
+
+            members.push((argument.name.clone(), ty));
+            offsets.push(SpirVDecorateType::Offset(argument.offset as u32));
+        }
+
+        let block_ty = builder.new_struct_type(&format!("{}_data", layout.name), false, members);
+        builder.set_decorate(
+            block_ty,
+            SpirVDecorate {
+                ty: Self::buffer_block_decoration(options),
+                member_decorates: offsets,
+            },
+        );
+        if !options.scalar_block_layout {
+            Self::check_extended_layout(builder, block_ty)?;
+        }
+
+        // Nothing writes to argument buffers yet.
+        Ok(Some(Self::new_descriptor_variable(
+            builder,
+            &format!("{}.data", layout.name),
+            block_ty,
+            Self::buffer_storage_class(options),
+            (layout.set, layout.data_binding()),
+            true,
+        )))
+    }
+
+    fn u32_constant(builder: &mut SpirVBuilder, value: u32) -> SpirVVariableId {
+        let u32_ty = builder.new_type(SpirVType::Int(32, false));
+
+        builder.new_constant(SpirVConstant {
+            type_id: u32_ty,
+            value: SpirVConstantValue::UnsignedInteger(value as u64),
+        })
+    }
+
+    /// Argument buffer members are bound to their own descriptors. A pointer
+    /// to a member resolves to the descriptor, and loading it gives that same
+    /// pointer back, the way buffer arguments stay pointers. Plain data
+    /// resolves to its member of the data block and loads like any pointer.
+    pub fn parse_argument_buffer_access(
+        builder: &mut SpirVBuilder,
+        module: &AirModule,
+        value_id: AirValueId,
+        value_list: &HashMap<AirValueId, SpirVVariableId>,
+        argument_buffers: &HashMap<AirValueId, ArgumentBufferDescriptors>,
+        member_pointers: &mut HashSet<AirValueId>,
+    ) -> Result<Option<SpirVVariableId>> {
+        let (argument_buffer, index, element, load_ty) = match &module.value_list
+            [value_id.0 as usize]
+        {
+            AirValue::Load(air_load) if member_pointers.contains(&air_load.op) => {
+                return Ok(Some(Self::spirv_value(value_list, air_load.op)?));
+            }
+            // A load straight from the argument buffer reads its first member.
+            AirValue::Load(air_load) => {
+                let Some(argument_buffer) = argument_buffers.get(&air_load.op) else {
+                    return Ok(None);
+                };
+                let (index, element) = argument_buffer.member_element_at(builder, 0)?;
+
+                (argument_buffer, index, element, Some(&air_load.ty))
+            }
+            AirValue::GetElementPtr(air_gep) => {
+                let Some(argument_buffer) = argument_buffers.get(&air_gep.base_ptr_value) else {
+                    return Ok(None);
+                };
+
+                match &air_gep.ty {
+                    // Field index, then the element for arrays or the
+                    // component for vectors.
+                    AirType::Struct(_) => {
+                        if air_gep.indices.len() > 3 {
+                            return Err(anyhow!(
+                                "Argument buffer access through {} indices isn't supported.",
+                                air_gep.indices.len()
+                            ));
+                        }
+
+                        let index = air_gep
+                            .indices
+                            .get(1)
+                            .and_then(|x| Self::get_air_constant_integer(module, *x))
+                            .ok_or(anyhow!(
+                                "Argument buffer members have to be picked by a constant."
+                            ))?;
+                        let element = match air_gep.indices.get(2) {
+                            Some(x) => Some(Self::spirv_value(value_list, *x)?),
+                            None => None,
+                        };
+
+                        (argument_buffer, index as usize, element, None)
+                    }
+                    // Byte offsets and friends.
+                    ty => {
+                        let element_size = match ty {
+                            AirType::Integer(width) => width / 8,
+                            AirType::Pointer(..) => 8,
+                            _ => {
+                                return Err(anyhow!(
+                                    "Argument buffer access through {:?} isn't supported.",
+                                    ty
+                                ));
+                            }
+                        };
+                        let offset = air_gep
+                            .indices
+                            .first()
+                            .and_then(|x| Self::get_air_constant_integer(module, *x))
+                            .ok_or(anyhow!("Argument buffer offsets have to be constants."))?
+                            * element_size;
+                        let (index, element) =
+                            argument_buffer.member_element_at(builder, offset)?;
+
+                        (argument_buffer, index, element, None)
+                    }
+                }
+            }
+            _ => return Ok(None),
+        };
+
+        let data_member = argument_buffer.data_member(index);
+        let (base, mut indices) = match data_member {
+            Some(member) => {
+                let data = argument_buffer.data.ok_or(anyhow!(
+                    "`{}` has no data block.",
+                    argument_buffer.layout.name
+                ))?;
+
+                (data, vec![Self::u32_constant(builder, member)])
+            }
+            None => (argument_buffer.descriptor(index)?, vec![]),
+        };
+        indices.extend(element);
+
+        let pointer = if indices.is_empty() {
+            base
+        } else {
+            let pointer_ty = builder.find_operand_type_id(base)?;
+            let SpirVType::Pointer(storage_class, mut ty) = builder.module.type_table[&pointer_ty]
+            else {
+                return Err(anyhow!("Argument buffer members have to be pointers."));
+            };
+            for i in 0..indices.len() {
+                let member = match data_member {
+                    Some(member) if i == 0 => member as usize,
+                    _ => 0,
+                };
+                ty = Self::spirv_get_element_type(builder, ty, member)?;
+            }
+            let element_pointer_ty = builder.new_type(SpirVType::Pointer(storage_class, ty));
+
+            builder.new_access_chain(SpirVAccessChain {
+                type_id: element_pointer_ty,
+                base_id: base,
+                indices,
+            })
+        };
+
+        Ok(Some(match (data_member, load_ty) {
+            (Some(_), Some(load_ty)) => {
+                let type_id = Self::parse_air_type(builder, module, load_ty)?;
+
+                builder.new_load(SpirVLoad {
+                    type_id,
+                    pointer_id: pointer,
+                    memory_operands: SpirVMemoryOperands::None,
+                })
+            }
+            (None, None) => {
+                member_pointers.insert(value_id);
+                pointer
+            }
+            _ => pointer,
+        }))
+    }
+
+    pub fn argument_buffer_layout(
+        function: &str,
+        variable: &ShaderVariable,
+        options: &AirToSpirVOptions,
+    ) -> ArgumentBufferLayout {
+        let buffer_index = variable.buffer_index.unwrap_or(0);
+
+        ArgumentBufferLayout {
+            function: function.to_string(),
+            name: variable.name.clone(),
+            buffer_index,
+            set: options.argument_buffer_set(buffer_index),
+            encoded_length: variable.arg_type_size.unwrap_or(0),
+            arguments: variable.arguments.clone(),
+        }
+    }
+
+    /// Layouts of every entry point's argument buffers, without translating
+    /// anything.
+    pub fn argument_buffers(
+        module: &AirModule,
+        options: &AirToSpirVOptions,
+    ) -> Result<Vec<ArgumentBufferLayout>> {
+        let mut result = vec![];
+
+        for (_, entry) in Self::entry_point_nodes(module) {
+            let signature = Self::entry_point_signature(module, entry)?;
+            let name = &module.string_table[signature.name.0 as usize].content;
+
+            for variable in Self::entry_point_variables(module, entry, 2)?.variables {
+                if let ShaderVariableType::Input(ShaderInputType::ArgumentBuffer) = variable.ty {
+                    result.push(Self::argument_buffer_layout(name, &variable, options));
+                }
+            }
+        }
+
+        Ok(result)
+    }
+
     pub fn parse_entry_point_variable(
         builder: &mut SpirVBuilder,
         inputs: &Vec<SpirVVariableId>,
         info: &VertexFunctionInfo,
         variable_count: &mut usize,
         location: &mut u32,
-    ) -> Vec<SpirVVariableId> {
+        options: &AirToSpirVOptions,
+    ) -> Result<Vec<SpirVVariableId>> {
         let mut result: Vec<SpirVVariableId> = vec![];
         for i in inputs {
-            let input = builder.module.type_table.get(&i).unwrap().clone();
-            match input {
-                SpirVType::Struct(elements) => {
-                    for i in elements {
-                        let element_info = &info.variables[*variable_count];
-                        result.push(Self::shader_variable_to_spirv_variable(
-                            builder,
-                            location,
-                            i,
-                            element_info,
-                        ));
-                        *variable_count += 1;
-                    }
-                }
-                _ => {
-                    let element_info = &info.variables[*variable_count];
-                    result.push(Self::shader_variable_to_spirv_variable(
-                        builder,
-                        location,
-                        *i,
-                        element_info,
-                    ));
-                    *variable_count += 1;
-                }
+            // Several outputs come back as a struct, one variable per member.
+            let elements = match &builder.module.type_table[i] {
+                SpirVType::Struct(elements) => elements.clone(),
+                _ => vec![*i],
+            };
+
+            for i in elements {
+                let element_info = info.variables.get(*variable_count).ok_or(anyhow!(
+                    "No metadata for entry point variable {}.",
+                    variable_count
+                ))?;
+                result.push(Self::shader_variable_to_spirv_variable(
+                    builder,
+                    location,
+                    i,
+                    element_info,
+                    options,
+                )?);
+                *variable_count += 1;
             }
         }
 
-        result
+        Ok(result)
     }
 
     pub fn parse_metadata_value(
-        properties: &Vec<u64>,
+        properties: &[Option<u64>],
         module: &AirModule,
-        value_name: &mut String,
+        variable: &mut ShaderVariable,
         start_at: usize,
-    ) {
+    ) -> Result<()> {
         let mut count = start_at;
 
         loop {
@@ -892,45 +2529,157 @@ impl AirToSpirV {
                 break;
             }
 
-            let variable_string = module.get_metadata_string(properties[count]).unwrap();
+            let variable_string = module
+                .get_metadata_string(properties[count])
+                .ok_or(anyhow!(
+                    "Expected String, found {:?}.",
+                    module.get_metadata(properties[count])
+                ))?;
 
+            // `user(locnN)` is shared by a vertex output and the fragment
+            // input reading it, `generated(...)` names unnamed ones.
             // TODO: Handle user defined locations.
-            if !variable_string.starts_with("user") {
+            if let Some(location) = variable_string.strip_prefix("user(locn") {
+                variable.location = location.trim_end_matches(')').parse().ok();
+            } else if !variable_string.starts_with("user")
+                && !variable_string.starts_with("generated(")
+            {
                 match variable_string.as_str() {
                     "air.arg_type_name" => {
-                        // Skip, since we already have the AIR/LLVM Type.
-                        // There's no need to do string parsing or anything of the sort.
+                        // Only argument buffer members need it, their AIR type
+                        // is hidden behind an opaque pointer.
+                        count += 1;
+                        variable.type_name = Self::metadata_string(module, properties.get(count))?;
+                    }
+                    "air.struct_type_info" => {
+                        count += 1;
+                        variable.arguments =
+                            Self::parse_argument_buffer_members(module, properties[count])?;
+                    }
+                    "air.argument_buffer_id" => {
                         count += 1;
+                        variable.buffer_index = module
+                            .get_metadata_integer(properties[count])
+                            .map(|index| index as u32);
                     }
                     "air.arg_name" => {
                         count += 1;
-                        *value_name = module.get_metadata_string(properties[count]).unwrap();
+                        variable.name = Self::metadata_string(module, properties.get(count))?;
+                    }
+                    "air.location_index" => {
+                        // The index, then how many slots it takes.
+                        variable.buffer_index = module
+                            .get_metadata_integer(properties[count + 1])
+                            .map(|index| index as u32);
+                        count += 2;
+                    }
+                    "air.arg_type_size" => {
+                        count += 1;
+                        variable.arg_type_size = module.get_metadata_integer(properties[count]);
                     }
-                    _ => {
-                        todo!("{:?}", variable_string)
+                    "air.address_space"
+                    | "air.arg_type_align_size"
+                    | "air.buffer_size"
+                    | "air.depth_qualifier" => count += 1,
+                    "air.read" | "air.sample" => variable.read_only = true,
+                    "air.read_write" | "air.write" => variable.read_only = false,
+                    "air.perspective" => variable.interpolation = ShaderInterpolation::Perspective,
+                    "air.no_perspective" => {
+                        variable.interpolation = ShaderInterpolation::NoPerspective
                     }
+                    "air.flat" => variable.interpolation = ShaderInterpolation::Flat,
+                    // Inputs are sampled at the pixel center, and the
+                    // centroid isn't mapped yet.
+                    "air.center" | "air.centroid" => {}
+                    "air.arg_unused" => {}
+                    _ => return Err(anyhow!("`{}` isn't supported.", variable_string)),
                 }
             }
 
             count += 1;
         }
+
+        Ok(())
+    }
+
+    fn metadata_string(module: &AirModule, id: Option<&Option<u64>>) -> Result<String> {
+        let id = id.copied().flatten();
+        module.get_metadata_string(id).ok_or(anyhow!(
+            "Expected String, found {:?}.",
+            module.get_metadata(id)
+        ))
     }
 
+    /// Reads `air.struct_type_info`: offset, size, array length, type name
+    /// and name of every member, followed by `air.indirect_argument` and
+    /// its properties for anything that isn't plain data.
+    pub fn parse_argument_buffer_members(
+        module: &AirModule,
+        struct_type_info: Option<u64>,
+    ) -> Result<Vec<ArgumentBufferArgument>> {
+        let properties = match module.get_metadata(struct_type_info) {
+            Some(AirMetadataConstant::Node(properties)) => properties,
+            metadata => return Err(anyhow!("Expected Node Group, found {:?}.", metadata)),
+        };
+        let integer = |index: usize| {
+            module
+                .get_metadata_integer(properties[index])
+                .ok_or(anyhow!(
+                    "Expected Integer, found {:?}.",
+                    module.get_metadata(properties[index])
+                ))
+        };
+
+        let mut result = vec![];
+        let mut count = 0;
+        while count + 5 <= properties.len() {
+            let mut argument = ArgumentBufferArgument {
+                offset: integer(count)?,
+                size: integer(count + 1)?,
+                array_length: integer(count + 2)?.max(1) as u32,
+                type_name: Self::metadata_string(module, properties.get(count + 3))?,
+                name: Self::metadata_string(module, properties.get(count + 4))?,
+                ..Default::default()
+            };
+            count += 5;
+
+            if properties
+                .get(count)
+                .and_then(|x| module.get_metadata_string(*x))
+                == Some("air.indirect_argument".to_string())
+            {
+                let info = Self::parse_vertex_info(
+                    module,
+                    vec![properties.get(count + 1).copied().flatten()],
+                )?;
+                let info = &info.variables[0];
+                count += 2;
+
+                argument.kind = info.argument_kind();
+                argument.id = info.buffer_index;
+                argument.type_name = info.type_name.clone();
+                argument.element_size = info.arg_type_size;
+                argument.read_only = info.read_only;
+            }
+
+            result.push(argument);
+        }
+
+        Ok(result)
+    }
+
+    /// Reads the metadata of an entry point's outputs or inputs. Outputs
+    /// start with their kind, inputs with their argument index.
     pub fn parse_vertex_info(
         module: &AirModule,
-        vertex_values_info: Vec<u64>,
-    ) -> VertexFunctionInfo {
+        vertex_values_info: Vec<Option<u64>>,
+    ) -> Result<VertexFunctionInfo> {
         let mut variables: Vec<ShaderVariable> = vec![];
         let mut location_id = 0;
         for i in vertex_values_info {
-            let vertex_properties = match module.metadata_constants.get(&i).unwrap() {
-                AirMetadataConstant::Node(vertex_properties) => vertex_properties,
-                _ => {
-                    panic!(
-                        "Expected Node Group, found {:?}",
-                        module.metadata_constants[&i]
-                    )
-                }
+            let vertex_properties = match module.get_metadata(i) {
+                Some(AirMetadataConstant::Node(vertex_properties)) => vertex_properties,
+                metadata => return Err(anyhow!("Expected Node Group, found {:?}.", metadata)),
             };
 
             // TODO: Make a more elegant solution if a string isn't found.
@@ -939,9 +2688,12 @@ impl AirToSpirV {
                 Some(v) => v,
                 None => {
                     starts_at_two = true;
-                    module.get_metadata_string(vertex_properties[1]).unwrap()
+                    Self::metadata_string(module, vertex_properties.get(1))?
                 }
             };
+            let mut start_at = if starts_at_two { 2 } else { 1 };
+
+            let built_in = |built_in| ShaderVariableType::Input(ShaderInputType::BuiltIn(built_in));
 
             let mut vertex_variable = ShaderVariable::default();
             let mut variable_location_id = None;
@@ -953,35 +2705,109 @@ impl AirToSpirV {
                         ShaderOutputType::VertexOutput
                     })
                 }
+                // A fragment function takes it as an input.
+                "air.position" if starts_at_two => {
+                    vertex_variable.ty = built_in(SpirVBuiltIn::FragCoord)
+                }
                 "air.position" => {
                     vertex_variable.ty = ShaderVariableType::Output(ShaderOutputType::Position)
                 }
+                "air.render_target" => {
+                    // The color index, then the index for dual-source blending.
+                    vertex_variable.ty = ShaderVariableType::Output(ShaderOutputType::RenderTarget);
+                    variable_location_id = module
+                        .get_metadata_integer(vertex_properties.get(start_at).copied().flatten());
+                    start_at += 2;
+                }
+                "air.depth" => {
+                    vertex_variable.ty = ShaderVariableType::Output(ShaderOutputType::Depth)
+                }
                 "air.vertex_id" => {
                     vertex_variable.ty = ShaderVariableType::Input(ShaderInputType::VertexID)
                 }
-                _ => todo!("{:?}", variable_name),
+                "air.vertex_input" => {
+                    vertex_variable.ty = ShaderVariableType::Input(ShaderInputType::VertexInput)
+                }
+                "air.fragment_input" => {
+                    vertex_variable.ty = ShaderVariableType::Input(ShaderInputType::FragmentInput)
+                }
+                "air.front_facing" => vertex_variable.ty = built_in(SpirVBuiltIn::FrontFacing),
+                "air.point_coord" => vertex_variable.ty = built_in(SpirVBuiltIn::PointCoord),
+                "air.thread_position_in_grid" => {
+                    vertex_variable.ty = built_in(SpirVBuiltIn::GlobalInvocationId)
+                }
+                "air.thread_position_in_threadgroup" => {
+                    vertex_variable.ty = built_in(SpirVBuiltIn::LocalInvocationId)
+                }
+                "air.thread_index_in_threadgroup" => {
+                    vertex_variable.ty = built_in(SpirVBuiltIn::LocalInvocationIndex)
+                }
+                "air.threadgroup_position_in_grid" => {
+                    vertex_variable.ty = built_in(SpirVBuiltIn::WorkgroupId)
+                }
+                "air.threadgroups_per_grid" => {
+                    vertex_variable.ty = built_in(SpirVBuiltIn::NumWorkgroups)
+                }
+                "air.threads_per_threadgroup" => {
+                    vertex_variable.ty = built_in(SpirVBuiltIn::WorkgroupSize)
+                }
+                "air.buffer" => {
+                    vertex_variable.ty = ShaderVariableType::Input(ShaderInputType::Buffer)
+                }
+                "air.texture" => {
+                    vertex_variable.ty = ShaderVariableType::Input(ShaderInputType::Texture)
+                }
+                "air.sampler" => {
+                    vertex_variable.ty = ShaderVariableType::Input(ShaderInputType::Sampler)
+                }
+                "air.indirect_buffer" => {
+                    vertex_variable.ty = ShaderVariableType::Input(ShaderInputType::ArgumentBuffer)
+                }
+                _ => return Err(anyhow!("`{}` isn't supported.", variable_name)),
             }
 
             vertex_variable.location = variable_location_id;
 
-            Self::parse_metadata_value(
-                vertex_properties,
-                module,
-                &mut vertex_variable.name,
-                if starts_at_two { 2 } else { 1 },
-            );
+            Self::parse_metadata_value(vertex_properties, module, &mut vertex_variable, start_at)?;
             variables.push(vertex_variable);
         }
 
-        VertexFunctionInfo { variables }
+        Ok(VertexFunctionInfo { variables })
     }
 }
 
+/// Result of one entry point from `AirToSpirV::translate_entry_points`.
+pub struct EntryPointTranslation {
+    pub name: String,
+    pub result: Result<SpirVBuilder>,
+}
+
 #[derive(Debug, Default, Clone)]
 pub struct ShaderVariable {
     pub ty: ShaderVariableType,
     pub name: String,
     pub location: Option<u64>,
+    /// `[[buffer(n)]]`, `[[texture(n)]]` or `[[sampler(n)]]` index, or
+    /// `[[id(n)]]` for argument buffer members.
+    pub buffer_index: Option<u32>,
+    pub arg_type_size: Option<u64>,
+    pub read_only: bool,
+    /// Metal type name, e.g. `float4` or `texture2d<float, sample>`.
+    pub type_name: String,
+    /// Members of an argument buffer.
+    pub arguments: Vec<ArgumentBufferArgument>,
+    pub interpolation: ShaderInterpolation,
+}
+
+impl ShaderVariable {
+    pub fn argument_kind(&self) -> ArgumentKind {
+        match self.ty {
+            ShaderVariableType::Input(ShaderInputType::Buffer) => ArgumentKind::Buffer,
+            ShaderVariableType::Input(ShaderInputType::Texture) => ArgumentKind::Texture,
+            ShaderVariableType::Input(ShaderInputType::Sampler) => ArgumentKind::Sampler,
+            _ => ArgumentKind::Data,
+        }
+    }
 }
 
 #[derive(Debug, Default, Clone)]
@@ -997,6 +2823,9 @@ pub enum ShaderOutputType {
     #[default]
     VertexOutput,
     Position,
+    /// `[[color(n)]]`, `n` is the variable's location.
+    RenderTarget,
+    Depth,
 }
 
 #[derive(Debug, Default, Clone)]
@@ -1004,6 +2833,238 @@ pub enum ShaderInputType {
     #[default]
     VertexInput,
     VertexID,
+    /// `[[stage_in]]` members of a fragment function.
+    FragmentInput,
+    BuiltIn(SpirVBuiltIn),
+    Buffer,
+    Texture,
+    Sampler,
+    ArgumentBuffer,
+}
+
+/// How a fragment input is interpolated.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ShaderInterpolation {
+    #[default]
+    Perspective,
+    NoPerspective,
+    Flat,
+}
+
+/// An argument buffer parameter and the descriptors of its members, `None`
+/// for plain data.
+#[derive(Debug, Clone)]
+pub struct ArgumentBufferDescriptors {
+    pub layout: ArgumentBufferLayout,
+    pub descriptors: Vec<Option<SpirVVariableId>>,
+    /// Block over the plain data members, if there are any.
+    pub data: Option<SpirVVariableId>,
+}
+
+impl ArgumentBufferDescriptors {
+    pub fn member_at(&self, offset: u64) -> Option<usize> {
+        self.layout
+            .arguments
+            .iter()
+            .position(|x| (x.offset..x.offset + x.size.max(1)).contains(&offset))
+    }
+
+    /// The member at `offset` and, for arrays, the element there as a
+    /// constant.
+    pub fn member_element_at(
+        &self,
+        builder: &mut SpirVBuilder,
+        offset: u64,
+    ) -> Result<(usize, Option<SpirVVariableId>)> {
+        let index = self.member_at(offset).ok_or(anyhow!(
+            "`{}` has no member at offset {}.",
+            self.layout.name,
+            offset
+        ))?;
+        let argument = &self.layout.arguments[index];
+
+        if argument.array_length <= 1 {
+            if offset != argument.offset {
+                return Err(anyhow!(
+                    "Access into the middle of `{}.{}` isn't supported.",
+                    self.layout.name,
+                    argument.name
+                ));
+            }
+
+            return Ok((index, None));
+        }
+
+        let stride = argument.size / argument.array_length as u64;
+        let element =
+            AirToSpirV::u32_constant(builder, ((offset - argument.offset) / stride) as u32);
+
+        Ok((index, Some(element)))
+    }
+
+    /// Index of a plain data member in the data block.
+    pub fn data_member(&self, index: usize) -> Option<u32> {
+        match self.layout.arguments.get(index)?.kind {
+            ArgumentKind::Data => Some(
+                self.layout.arguments[..index]
+                    .iter()
+                    .filter(|x| x.kind == ArgumentKind::Data)
+                    .count() as u32,
+            ),
+            _ => None,
+        }
+    }
+
+    pub fn descriptor(&self, index: usize) -> Result<SpirVVariableId> {
+        self.descriptors
+            .get(index)
+            .copied()
+            .flatten()
+            .ok_or(anyhow!(
+                "`{}` has no descriptor for member {}.",
+                self.layout.name,
+                index
+            ))
+    }
+}
+
+/// Where the members of an argument buffer end up, so they can be written
+/// to the right descriptors.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ArgumentBufferLayout {
+    pub function: String,
+    pub name: String,
+    /// `[[buffer(n)]]` index of the argument buffer.
+    pub buffer_index: u32,
+    pub set: u32,
+    /// Size of the argument buffer's struct.
+    pub encoded_length: u64,
+    pub arguments: Vec<ArgumentBufferArgument>,
+}
+
+impl ArgumentBufferLayout {
+    /// Looks a member up by its `[[id(n)]]`.
+    pub fn argument(&self, id: u32) -> Option<&ArgumentBufferArgument> {
+        self.arguments.iter().find(|x| x.id == Some(id))
+    }
+
+    pub fn has_data(&self) -> bool {
+        self.arguments.iter().any(|x| x.kind == ArgumentKind::Data)
+    }
+
+    /// Binding of the argument buffer itself, which holds the plain data
+    /// members. It comes after every `[[id(n)]]` the members use.
+    pub fn data_binding(&self) -> u32 {
+        self.arguments
+            .iter()
+            .filter_map(|x| Some(x.id? + x.array_length.max(1)))
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ArgumentBufferArgument {
+    pub name: String,
+    /// `[[id(n)]]`, which is also the binding. `None` for plain data.
+    pub id: Option<u32>,
+    pub kind: ArgumentKind,
+    pub offset: u64,
+    pub size: u64,
+    pub array_length: u32,
+    pub type_name: String,
+    /// Size of what a buffer points to, its array stride.
+    pub element_size: Option<u64>,
+    pub read_only: bool,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum ArgumentKind {
+    /// Stored inline in the argument buffer, has no descriptor.
+    #[default]
+    Data = 0,
+    Buffer = 1,
+    Texture = 2,
+    Sampler = 3,
+}
+
+impl ArgumentKind {
+    pub fn from_u8(v: u8) -> Result<Self> {
+        Ok(match v {
+            0 => Self::Data,
+            1 => Self::Buffer,
+            2 => Self::Texture,
+            3 => Self::Sampler,
+            _ => return Err(anyhow!("Unknown argument kind {}.", v)),
+        })
+    }
+}
+
+/// What `AirToSpirV::parse_air_function` translates an entry point with.
+#[derive(Clone, Copy)]
+pub struct FunctionContext<'a> {
+    /// The AIR function's parameters.
+    pub arguments: &'a [AirValueId],
+    /// Entry point variables, each AIR parameter that isn't an argument
+    /// buffer has an input.
+    pub outputs: &'a [SpirVVariableId],
+    pub inputs: &'a [SpirVVariableId],
+    pub argument_buffers: &'a HashMap<AirValueId, ArgumentBufferDescriptors>,
+    pub global_variables: &'a HashMap<AirGlobalVariableId, SpirVVariableId>,
+    pub constants: &'a HashMap<AirConstantId, SpirVVariableId>,
+    pub debug_files: &'a HashMap<u64, SpirVVariableId>,
+    pub options: &'a AirToSpirVOptions,
+}
+
+/// What the `Position` fixups need, made once per function rather than on
+/// every store to it.
+#[derive(Debug, Clone, Copy)]
+pub struct PositionFixups {
+    /// The `BuiltIn Position` block.
+    pub block_ty: SpirVVariableId,
+    pub position_ty: SpirVVariableId,
+    pub float_ty: SpirVVariableId,
+    pub pointer_ty: SpirVVariableId,
+    pub index_zero: SpirVVariableId,
+    /// Only for `DepthRange::NegativeOneToOne`.
+    pub two: Option<SpirVVariableId>,
+}
+
+impl PositionFixups {
+    /// `None` unless `block_ty` is the `BuiltIn Position` block.
+    pub fn new(
+        builder: &mut SpirVBuilder,
+        options: &AirToSpirVOptions,
+        block_ty: SpirVVariableId,
+    ) -> Option<Self> {
+        let position_ty = AirToSpirV::get_position_type(builder, block_ty)?;
+        let float_ty = AirToSpirV::spirv_get_element_type(builder, position_ty, 0).ok()?;
+
+        let u32_ty = builder.new_type(SpirVType::Int(32, false));
+        let index_zero = builder.new_constant(SpirVConstant {
+            type_id: u32_ty,
+            value: SpirVConstantValue::UnsignedInteger(0),
+        });
+        let pointer_ty =
+            builder.new_type(SpirVType::Pointer(SpirVStorageClass::Output, position_ty));
+
+        let two = (options.depth_range == DepthRange::NegativeOneToOne).then(|| {
+            builder.new_constant(SpirVConstant {
+                type_id: float_ty,
+                value: SpirVConstantValue::Float32(2.0),
+            })
+        });
+
+        Some(Self {
+            block_ty,
+            position_ty,
+            float_ty,
+            pointer_ty,
+            index_zero,
+            two,
+        })
+    }
 }
 
 #[derive(Debug, Default, Clone)]
@@ -0,0 +1,112 @@
+use crate::spirv_interpreter::{mask, sign_extend};
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub enum AirInterpreterValue {
+    #[default]
+    Undefined,
+    /// Raw bits, masked to `width`. Signedness is up to the instruction.
+    Integer {
+        value: u64,
+        width: u64,
+    },
+    Float32(f32),
+    /// Vectors, arrays and structs.
+    Composite(Vec<AirInterpreterValue>),
+    Pointer(AirPointer),
+}
+
+impl AirInterpreterValue {
+    pub fn integer(value: u64, width: u64) -> Self {
+        Self::Integer {
+            value: mask(value, width as u32),
+            width,
+        }
+    }
+
+    pub fn as_u64(&self) -> Option<u64> {
+        match self {
+            Self::Integer { value, .. } => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            Self::Integer { value, width } => Some(sign_extend(*value, *width as u32)),
+            _ => None,
+        }
+    }
+
+    pub fn as_f32(&self) -> Option<f32> {
+        match self {
+            Self::Float32(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn elements(&self) -> Option<&[AirInterpreterValue]> {
+        match self {
+            Self::Composite(elements) => Some(elements),
+            _ => None,
+        }
+    }
+
+    /// Components of a float vector, or a float scalar as a single component.
+    pub fn as_f32_vec(&self) -> Option<Vec<f32>> {
+        match self {
+            Self::Composite(elements) => elements.iter().map(|i| i.as_f32()).collect(),
+            _ => self.as_f32().map(|value| vec![value]),
+        }
+    }
+}
+
+impl From<u32> for AirInterpreterValue {
+    fn from(value: u32) -> Self {
+        Self::integer(value as u64, 32)
+    }
+}
+
+impl From<i32> for AirInterpreterValue {
+    fn from(value: i32) -> Self {
+        Self::integer(value as u64, 32)
+    }
+}
+
+impl From<f32> for AirInterpreterValue {
+    fn from(value: f32) -> Self {
+        Self::Float32(value)
+    }
+}
+
+impl<T: Into<AirInterpreterValue>> From<Vec<T>> for AirInterpreterValue {
+    fn from(value: Vec<T>) -> Self {
+        Self::Composite(value.into_iter().map(|i| i.into()).collect())
+    }
+}
+
+impl<T: Into<AirInterpreterValue>, const N: usize> From<[T; N]> for AirInterpreterValue {
+    fn from(value: [T; N]) -> Self {
+        Self::Composite(value.into_iter().map(|i| i.into()).collect())
+    }
+}
+
+/// A byte address inside one of the interpreter's memory regions.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct AirPointer {
+    pub region: usize,
+    pub offset: u64,
+}
+
+/// An argument passed to the function being run.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AirArgument {
+    Value(AirInterpreterValue),
+    /// Backing memory for a pointer argument, laid out the way Metal does.
+    Buffer(Vec<u8>),
+}
+
+impl<T: Into<AirInterpreterValue>> From<T> for AirArgument {
+    fn from(value: T) -> Self {
+        Self::Value(value.into())
+    }
+}
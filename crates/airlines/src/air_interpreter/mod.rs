@@ -0,0 +1,933 @@
+use std::collections::HashMap;
+
+use anyhow::{Result, anyhow};
+
+use crate::{
+    air_parser::{
+        AirArrayType, AirBinOp, AirCast, AirConstant, AirConstantValue, AirFile,
+        AirFunctionSignatureId, AirGetElementPtr, AirGlobalVariableId, AirItem, AirModule,
+        AirStructType, AirType, AirTypeId, AirValue, AirValueId, AirVectorType,
+    },
+    llvm_bitcode::{BinaryOpCode, CastOpCode},
+};
+
+pub mod items;
+pub use items::*;
+
+/// Runs AIR functions on the CPU, straight from the parsed module.
+///
+/// Memory is a list of byte regions laid out the way Metal lays them out:
+/// one per global variable, and one per buffer argument of the current run.
+#[derive(Debug, Default)]
+pub struct AirInterpreter {
+    pub module: AirModule,
+    memory: Vec<Vec<u8>>,
+    globals: HashMap<AirGlobalVariableId, usize>,
+}
+
+impl AirInterpreter {
+    pub fn new(input: AirFile) -> Result<Self> {
+        let module = input
+            .items
+            .into_iter()
+            .find_map(|i| match i {
+                AirItem::Module(module) => Some(module),
+                _ => None,
+            })
+            .ok_or(anyhow!("Module not found."))?;
+
+        let mut interpreter = Self {
+            module,
+            ..Default::default()
+        };
+
+        let mut globals = interpreter
+            .module
+            .global_variables
+            .iter()
+            .map(|(id, global)| (*id, global.clone()))
+            .collect::<Vec<_>>();
+        globals.sort_by_key(|(id, _)| *id);
+
+        for (id, global) in globals {
+            let ty = interpreter.ty(global.type_id)?;
+            let mut bytes = vec![0; interpreter.size_of(ty)? as usize];
+
+            if let Some(constant) = interpreter.module.constants.get(&global.initializer) {
+                let value = interpreter.constant_value(constant)?;
+                interpreter.write_bytes(ty, &value, &mut bytes, 0)?;
+            }
+
+            interpreter.globals.insert(id, interpreter.memory.len());
+            interpreter.memory.push(bytes);
+        }
+
+        Ok(interpreter)
+    }
+
+    /// Calls `function` and returns what it returned, `Undefined` for `void`.
+    pub fn run(
+        &mut self,
+        function: &str,
+        arguments: Vec<AirArgument>,
+    ) -> Result<AirInterpreterValue> {
+        // Buffers from the previous run go away, globals stay.
+        self.memory.truncate(self.globals.len());
+
+        let signature = self
+            .module
+            .function_signatures
+            .iter()
+            .find(|i| self.module.string_table[i.name.0 as usize].content == function)
+            .map(|i| i.global_id)
+            .ok_or(anyhow!("Function `{}` not found.", function))?;
+
+        let mut values = vec![];
+        for i in arguments {
+            values.push(match i {
+                AirArgument::Value(value) => value,
+                AirArgument::Buffer(bytes) => {
+                    self.memory.push(bytes);
+                    AirInterpreterValue::Pointer(AirPointer {
+                        region: self.memory.len() - 1,
+                        offset: 0,
+                    })
+                }
+            });
+        }
+
+        self.call(signature, values)
+    }
+
+    fn call(
+        &self,
+        signature: AirFunctionSignatureId,
+        arguments: Vec<AirInterpreterValue>,
+    ) -> Result<AirInterpreterValue> {
+        let function = self
+            .module
+            .get_function_signature(signature)
+            .ok_or(anyhow!("Function {:?} not found.", signature))?;
+        let name = &self.module.string_table[function.name.0 as usize].content;
+
+        if function.is_proto {
+            return intrinsic(name, &arguments);
+        }
+
+        let body = self
+            .module
+            .function_bodies
+            .iter()
+            .find(|i| i.signature == signature)
+            .ok_or(anyhow!("Function `{}` has no body.", name))?;
+
+        if arguments.len() != function.ty.param_values.len() {
+            return Err(anyhow!(
+                "Function `{}` takes {} arguments, got {}.",
+                name,
+                function.ty.param_values.len(),
+                arguments.len()
+            ));
+        }
+
+        let mut values: HashMap<AirValueId, AirInterpreterValue> = function
+            .ty
+            .param_values
+            .iter()
+            .copied()
+            .zip(arguments)
+            .collect();
+
+        for i in &body.contents {
+            let result = match &self.module.value_list[i.0 as usize] {
+                AirValue::Return(air_return) => {
+                    return match air_return.value {
+                        Some(value) => self.value(&values, value),
+                        None => Ok(AirInterpreterValue::Undefined),
+                    };
+                }
+                value => self.execute(&values, value)?,
+            };
+
+            values.insert(*i, result);
+        }
+
+        Err(anyhow!("Function `{}` doesn't return.", name))
+    }
+
+    fn execute(
+        &self,
+        values: &HashMap<AirValueId, AirInterpreterValue>,
+        value: &AirValue,
+    ) -> Result<AirInterpreterValue> {
+        match value {
+            AirValue::Cast(air_cast) => self.cast(self.value(values, air_cast.value)?, air_cast),
+            AirValue::BinOp(air_bin_op) => self.bin_op(values, air_bin_op),
+            AirValue::GetElementPtr(air_gep) => self.get_element_ptr(values, air_gep),
+            AirValue::Load(air_load) => match self.value(values, air_load.op)? {
+                AirInterpreterValue::Pointer(pointer) => {
+                    let bytes = self
+                        .memory
+                        .get(pointer.region)
+                        .ok_or(anyhow!("Invalid memory region {}.", pointer.region))?;
+                    self.read_bytes(&air_load.ty, bytes, pointer.offset)
+                }
+                value => Err(anyhow!(
+                    "Expected a pointer to load from, found {:?}.",
+                    value
+                )),
+            },
+            AirValue::ShuffleVec(air_shuffle_vec) => {
+                let vec1 = self.value(values, air_shuffle_vec.vec1)?;
+                let vec2 = self.value(values, air_shuffle_vec.vec2)?;
+                let mask = self.value(values, air_shuffle_vec.mask)?;
+
+                let vec1 = vec1.elements().unwrap_or_default();
+                let vec2 = vec2.elements().unwrap_or_default();
+                let mask = mask
+                    .elements()
+                    .ok_or(anyhow!("Expected a vector shuffle mask."))?;
+
+                // Undefined mask elements pick an undefined component.
+                let count = vec1.len().max(vec2.len());
+                let mut result = vec![];
+                for i in mask {
+                    result.push(match i.as_u64() {
+                        Some(index) if (index as usize) < count => {
+                            vec1.get(index as usize).cloned().unwrap_or_default()
+                        }
+                        Some(index) => vec2
+                            .get(index as usize - count)
+                            .cloned()
+                            .unwrap_or_default(),
+                        None => AirInterpreterValue::Undefined,
+                    });
+                }
+
+                Ok(AirInterpreterValue::Composite(result))
+            }
+            AirValue::ExtractVal(air_extract_val) => {
+                let mut value = self.value(values, air_extract_val.aggregate)?;
+                for index in &air_extract_val.indices {
+                    value = extract(&value, *index)?;
+                }
+                Ok(value)
+            }
+            AirValue::InsertVal(air_insert_val) => {
+                let mut composite = self.value(values, air_insert_val.value1)?;
+                let value = self.value(values, air_insert_val.value2)?;
+
+                insert(&mut composite, air_insert_val.insert_value_idx, value)?;
+                Ok(composite)
+            }
+            AirValue::ExtractElt(air_extract_elt) => {
+                let vector = self.value(values, air_extract_elt.vector)?;
+                let index = self
+                    .value(values, air_extract_elt.index)?
+                    .as_u64()
+                    .ok_or(anyhow!("Expected an integer index."))?;
+
+                extract(&vector, index)
+            }
+            AirValue::InsertElt(air_insert_elt) => {
+                let mut vector = self.value(values, air_insert_elt.vector)?;
+                let value = self.value(values, air_insert_elt.value)?;
+                let index = self
+                    .value(values, air_insert_elt.index)?
+                    .as_u64()
+                    .ok_or(anyhow!("Expected an integer index."))?;
+
+                insert(&mut vector, index, value)?;
+                Ok(vector)
+            }
+            AirValue::Call(air_call) => {
+                let signature = match self.module.value_list.get(air_call.function.0 as usize) {
+                    Some(AirValue::Function(signature)) => *signature,
+                    value => return Err(anyhow!("Indirect call to {:?}.", value)),
+                };
+
+                let arguments = air_call
+                    .arguments
+                    .iter()
+                    .map(|i| self.value(values, *i))
+                    .collect::<Result<Vec<_>>>()?;
+
+                self.call(signature, arguments)
+            }
+            _ => Err(anyhow!("{:?} is not supported yet.", value)),
+        }
+    }
+
+    fn value(
+        &self,
+        values: &HashMap<AirValueId, AirInterpreterValue>,
+        id: AirValueId,
+    ) -> Result<AirInterpreterValue> {
+        if let Some(value) = values.get(&id) {
+            return Ok(value.clone());
+        }
+
+        match self.module.value_list.get(id.0 as usize) {
+            Some(AirValue::Constant(constant)) => self.constant_value(
+                self.module
+                    .constants
+                    .get(constant)
+                    .ok_or(anyhow!("Constant {:?} not found.", constant))?,
+            ),
+            Some(AirValue::GlobalVariable(global)) => {
+                Ok(AirInterpreterValue::Pointer(AirPointer {
+                    region: *self
+                        .globals
+                        .get(global)
+                        .ok_or(anyhow!("Global variable {:?} not found.", global))?,
+                    offset: 0,
+                }))
+            }
+            value => Err(anyhow!("Value {:?} is not available: {:?}.", id, value)),
+        }
+    }
+
+    fn ty(&self, id: AirTypeId) -> Result<&AirType> {
+        self.module
+            .types
+            .get(id.0 as usize)
+            .ok_or(anyhow!("Type {:?} not found.", id))
+    }
+
+    fn constant_value(&self, constant: &AirConstant) -> Result<AirInterpreterValue> {
+        self.constant_to_value(&constant.value, constant.ty)
+    }
+
+    fn constant_to_value(
+        &self,
+        value: &AirConstantValue,
+        ty: AirTypeId,
+    ) -> Result<AirInterpreterValue> {
+        let air_ty = self.ty(ty)?;
+
+        match value {
+            AirConstantValue::Null => self.zero_value(air_ty),
+            // Aggregates keep their shape so single members can be inserted.
+            AirConstantValue::Undefined | AirConstantValue::Poison => {
+                let count = match air_ty {
+                    AirType::Array(array) => array.size as usize,
+                    AirType::Vector(vector) => vector.size as usize,
+                    AirType::Struct(structure) => structure.elements.len(),
+                    _ => return Ok(AirInterpreterValue::Undefined),
+                };
+                Ok(AirInterpreterValue::Composite(vec![
+                    AirInterpreterValue::Undefined;
+                    count
+                ]))
+            }
+            AirConstantValue::Integer(value) => match air_ty {
+                AirType::Integer(width) => Ok(AirInterpreterValue::integer(*value, *width)),
+                AirType::Float => Ok(AirInterpreterValue::Float32(f32::from_bits(*value as u32))),
+                _ => Err(anyhow!("Integer constant of type {:?}.", air_ty)),
+            },
+            AirConstantValue::Float32(value) => Ok(AirInterpreterValue::Float32(*value)),
+            AirConstantValue::Aggregate(elements) => Ok(AirInterpreterValue::Composite(
+                elements
+                    .iter()
+                    .map(|i| self.value(&HashMap::new(), *i))
+                    .collect::<Result<_>>()?,
+            )),
+            AirConstantValue::Array(elements) => {
+                let mut result = vec![];
+                for (count, i) in elements.iter().enumerate() {
+                    result.push(self.constant_to_value(i, element_type(air_ty, count)?)?);
+                }
+                Ok(AirInterpreterValue::Composite(result))
+            }
+            _ => Err(anyhow!("Constant {:?} is not supported yet.", value)),
+        }
+    }
+
+    fn zero_value(&self, ty: &AirType) -> Result<AirInterpreterValue> {
+        match ty {
+            AirType::Integer(width) => Ok(AirInterpreterValue::integer(0, *width)),
+            AirType::Float => Ok(AirInterpreterValue::Float32(0.0)),
+            AirType::Array(array) => Ok(AirInterpreterValue::Composite(vec![
+                self.zero_value(
+                    self.ty(array.element_type)?
+                )?;
+                array.size as usize
+            ])),
+            AirType::Vector(vector) => Ok(AirInterpreterValue::Composite(vec![
+                self.zero_value(
+                    self.ty(vector.element_type)?
+                )?;
+                vector.size as usize
+            ])),
+            AirType::Struct(structure) => Ok(AirInterpreterValue::Composite(
+                structure
+                    .elements
+                    .iter()
+                    .map(|i| self.zero_value(self.ty(*i)?))
+                    .collect::<Result<_>>()?,
+            )),
+            _ => Err(anyhow!("{:?} has no zero value.", ty)),
+        }
+    }
+
+    /// Allocation size, vectors are padded to a power of two like `float3`.
+    fn size_of(&self, ty: &AirType) -> Result<u64> {
+        match ty {
+            AirType::Integer(width) => Ok(width.div_ceil(8).max(1).next_power_of_two()),
+            AirType::Float => Ok(4),
+            AirType::Pointer(..) => Ok(8),
+            AirType::Vector(vector) => {
+                let element_size = self.size_of(self.ty(vector.element_type)?)?;
+                Ok((vector.size * element_size).next_power_of_two())
+            }
+            AirType::Array(array) => Ok(array.size * self.size_of(self.ty(array.element_type)?)?),
+            AirType::Struct(structure) => {
+                let size = self.member_offset(structure, structure.elements.len())?;
+                Ok(size.next_multiple_of(self.align_of(ty)?))
+            }
+            _ => Err(anyhow!("{:?} has no size.", ty)),
+        }
+    }
+
+    fn align_of(&self, ty: &AirType) -> Result<u64> {
+        match ty {
+            AirType::Array(array) => self.align_of(self.ty(array.element_type)?),
+            AirType::Struct(structure) if structure.is_packed => Ok(1),
+            AirType::Struct(structure) => {
+                let mut result = 1;
+                for i in &structure.elements {
+                    result = result.max(self.align_of(self.ty(*i)?)?);
+                }
+                Ok(result)
+            }
+            _ => self.size_of(ty),
+        }
+    }
+
+    /// Offset of `member`, or the unpadded size when it's one past the end.
+    fn member_offset(&self, ty: &AirStructType, member: usize) -> Result<u64> {
+        let mut offset: u64 = 0;
+        for (count, i) in ty.elements.iter().enumerate() {
+            let element = self.ty(*i)?;
+            if !ty.is_packed {
+                offset = offset.next_multiple_of(self.align_of(element)?);
+            }
+            if count == member {
+                return Ok(offset);
+            }
+            offset += self.size_of(element)?;
+        }
+
+        match member == ty.elements.len() {
+            true => Ok(offset),
+            false => Err(anyhow!("{:?} has no member {}.", ty, member)),
+        }
+    }
+
+    /// Stride between the elements of an array or vector.
+    fn element_stride(&self, ty: &AirType) -> Result<u64> {
+        self.size_of(self.ty(element_type(ty, 0)?)?)
+    }
+
+    fn read_bytes(&self, ty: &AirType, bytes: &[u8], offset: u64) -> Result<AirInterpreterValue> {
+        let read = |size: u64| -> Result<u64> {
+            let start = offset as usize;
+            let data = bytes.get(start..start + size as usize).ok_or(anyhow!(
+                "Read of {} bytes at {} is out of bounds.",
+                size,
+                offset
+            ))?;
+
+            let mut result = [0; 8];
+            result[..data.len()].copy_from_slice(data);
+            Ok(u64::from_le_bytes(result))
+        };
+
+        match ty {
+            AirType::Integer(width) => Ok(AirInterpreterValue::integer(
+                read(self.size_of(ty)?)?,
+                *width,
+            )),
+            AirType::Float => Ok(AirInterpreterValue::Float32(
+                f32::from_bits(read(4)? as u32),
+            )),
+            AirType::Array(AirArrayType { size, .. })
+            | AirType::Vector(AirVectorType { size, .. }) => {
+                let element = self.ty(element_type(ty, 0)?)?;
+                let stride = self.element_stride(ty)?;
+
+                let mut result = vec![];
+                for i in 0..*size {
+                    result.push(self.read_bytes(element, bytes, offset + i * stride)?);
+                }
+                Ok(AirInterpreterValue::Composite(result))
+            }
+            AirType::Struct(structure) => {
+                let mut result = vec![];
+                for (count, i) in structure.elements.iter().enumerate() {
+                    let member_offset = self.member_offset(structure, count)?;
+                    result.push(self.read_bytes(self.ty(*i)?, bytes, offset + member_offset)?);
+                }
+                Ok(AirInterpreterValue::Composite(result))
+            }
+            _ => Err(anyhow!("Can't read {:?} from memory.", ty)),
+        }
+    }
+
+    fn write_bytes(
+        &self,
+        ty: &AirType,
+        value: &AirInterpreterValue,
+        bytes: &mut [u8],
+        offset: u64,
+    ) -> Result<()> {
+        let mut write = |data: &[u8]| -> Result<()> {
+            let start = offset as usize;
+            bytes
+                .get_mut(start..start + data.len())
+                .ok_or(anyhow!("Write at {} is out of bounds.", offset))?
+                .copy_from_slice(data);
+            Ok(())
+        };
+
+        match (ty, value) {
+            // Whatever was there stays.
+            (_, AirInterpreterValue::Undefined) => Ok(()),
+            (AirType::Integer(_), AirInterpreterValue::Integer { value, .. }) => {
+                write(&value.to_le_bytes()[..self.size_of(ty)? as usize])
+            }
+            (AirType::Float, AirInterpreterValue::Float32(value)) => write(&value.to_le_bytes()),
+            (AirType::Array(_) | AirType::Vector(_), AirInterpreterValue::Composite(elements)) => {
+                let element = self.ty(element_type(ty, 0)?)?;
+                let stride = self.element_stride(ty)?;
+
+                for (count, i) in elements.iter().enumerate() {
+                    self.write_bytes(element, i, bytes, offset + count as u64 * stride)?;
+                }
+                Ok(())
+            }
+            (AirType::Struct(structure), AirInterpreterValue::Composite(elements)) => {
+                for (count, i) in elements.iter().enumerate() {
+                    let member = self.ty(element_type(ty, count)?)?;
+                    let member_offset = self.member_offset(structure, count)?;
+                    self.write_bytes(member, i, bytes, offset + member_offset)?;
+                }
+                Ok(())
+            }
+            _ => Err(anyhow!("Can't write {:?} as {:?}.", value, ty)),
+        }
+    }
+
+    fn get_element_ptr(
+        &self,
+        values: &HashMap<AirValueId, AirInterpreterValue>,
+        air_gep: &AirGetElementPtr,
+    ) -> Result<AirInterpreterValue> {
+        let mut pointer = match self.value(values, air_gep.base_ptr_value)? {
+            AirInterpreterValue::Pointer(pointer) => pointer,
+            value => return Err(anyhow!("Expected a pointer, found {:?}.", value)),
+        };
+
+        // The first index steps over whole objects, the rest walk into them.
+        let mut ty = &air_gep.ty;
+        for (count, i) in air_gep.indices.iter().enumerate() {
+            let index = self
+                .value(values, *i)?
+                .as_i64()
+                .ok_or(anyhow!("Expected an integer index."))?;
+
+            let offset = match (count, ty) {
+                (0, _) => index * self.size_of(ty)? as i64,
+                (_, AirType::Struct(structure)) => {
+                    let offset = self.member_offset(structure, index as usize)?;
+                    ty = self.ty(element_type(ty, index as usize)?)?;
+                    offset as i64
+                }
+                (_, AirType::Array(_) | AirType::Vector(_)) => {
+                    let offset = index * self.element_stride(ty)? as i64;
+                    ty = self.ty(element_type(ty, 0)?)?;
+                    offset
+                }
+                _ => return Err(anyhow!("Can't index into {:?}.", ty)),
+            };
+
+            pointer.offset = pointer.offset.wrapping_add_signed(offset);
+        }
+
+        Ok(AirInterpreterValue::Pointer(pointer))
+    }
+
+    fn bin_op(
+        &self,
+        values: &HashMap<AirValueId, AirInterpreterValue>,
+        air_bin_op: &AirBinOp,
+    ) -> Result<AirInterpreterValue> {
+        let lhs = self.value(values, air_bin_op.lhs)?;
+        let rhs = self.value(values, air_bin_op.rhs)?;
+
+        componentwise_n(&[lhs, rhs], &|i| {
+            let (a, b) = (&i[0], &i[1]);
+
+            if let (Some(a), Some(b)) = (a.as_f32(), b.as_f32()) {
+                return Ok(AirInterpreterValue::Float32(match air_bin_op.opcode {
+                    BinaryOpCode::ADD => a + b,
+                    BinaryOpCode::SUB => a - b,
+                    BinaryOpCode::MUL => a * b,
+                    BinaryOpCode::SDIV => a / b,
+                    BinaryOpCode::SREM => a % b,
+                    opcode => return Err(anyhow!("{:?} on floats.", opcode)),
+                }));
+            }
+
+            // Undefined components stay undefined.
+            let (
+                AirInterpreterValue::Integer { value: x, width },
+                AirInterpreterValue::Integer { value: y, .. },
+            ) = (a, b)
+            else {
+                return Ok(AirInterpreterValue::Undefined);
+            };
+            let (x, y, width) = (*x, *y, *width);
+            let (sx, sy) = (a.as_i64().unwrap(), b.as_i64().unwrap());
+            let division_by_zero = || anyhow!("Division by zero.");
+
+            let result = match air_bin_op.opcode {
+                BinaryOpCode::ADD => x.wrapping_add(y),
+                BinaryOpCode::SUB => x.wrapping_sub(y),
+                BinaryOpCode::MUL => x.wrapping_mul(y),
+                BinaryOpCode::UDIV => x.checked_div(y).ok_or_else(division_by_zero)?,
+                BinaryOpCode::SDIV => sx.checked_div(sy).ok_or_else(division_by_zero)? as u64,
+                BinaryOpCode::UREM => x.checked_rem(y).ok_or_else(division_by_zero)?,
+                BinaryOpCode::SREM => sx.checked_rem(sy).ok_or_else(division_by_zero)? as u64,
+                BinaryOpCode::SHL => x.wrapping_shl(y as u32),
+                BinaryOpCode::LSHR => x.wrapping_shr(y as u32),
+                BinaryOpCode::ASHR => sx.wrapping_shr(y as u32) as u64,
+                BinaryOpCode::AND => x & y,
+                BinaryOpCode::OR => x | y,
+                BinaryOpCode::XOR => x ^ y,
+            };
+
+            Ok(AirInterpreterValue::integer(result, width))
+        })
+    }
+
+    fn cast(&self, value: AirInterpreterValue, air_cast: &AirCast) -> Result<AirInterpreterValue> {
+        let to_type = &air_cast.cast_to_type;
+
+        match air_cast.cast_code {
+            CastOpCode::BITCAST | CastOpCode::ADDRSPACECAST
+                if matches!(value, AirInterpreterValue::Pointer(_)) =>
+            {
+                return Ok(value);
+            }
+            CastOpCode::BITCAST => {
+                let mut bytes = vec![];
+                value_to_bytes(&value, &mut bytes)?;
+                return self.read_bytes(to_type, &bytes, 0);
+            }
+            _ => {}
+        }
+
+        let to_scalar = match to_type {
+            AirType::Vector(vector) => self.ty(vector.element_type)?,
+            ty => ty,
+        };
+        let width = match to_scalar {
+            AirType::Integer(width) => Some(*width),
+            _ => None,
+        };
+
+        componentwise(&value, &|i| {
+            let unsupported =
+                || anyhow!("Can't {:?} {:?} to {:?}.", air_cast.cast_code, i, to_type);
+
+            Ok(match (&air_cast.cast_code, width) {
+                (_, _) if *i == AirInterpreterValue::Undefined => AirInterpreterValue::Undefined,
+                (CastOpCode::TRUNC | CastOpCode::ZEXT, Some(width)) => {
+                    AirInterpreterValue::integer(i.as_u64().ok_or_else(unsupported)?, width)
+                }
+                (CastOpCode::SEXT, Some(width)) => {
+                    AirInterpreterValue::integer(i.as_i64().ok_or_else(unsupported)? as u64, width)
+                }
+                (CastOpCode::FPTOUI, Some(width)) => {
+                    AirInterpreterValue::integer(i.as_f32().ok_or_else(unsupported)? as u64, width)
+                }
+                (CastOpCode::FPTOSI, Some(width)) => AirInterpreterValue::integer(
+                    i.as_f32().ok_or_else(unsupported)? as i64 as u64,
+                    width,
+                ),
+                (CastOpCode::UITOFP, None) => {
+                    AirInterpreterValue::Float32(i.as_u64().ok_or_else(unsupported)? as f32)
+                }
+                (CastOpCode::SITOFP, None) => {
+                    AirInterpreterValue::Float32(i.as_i64().ok_or_else(unsupported)? as f32)
+                }
+                (CastOpCode::FPTRUNC | CastOpCode::FPEXT, None) => {
+                    AirInterpreterValue::Float32(i.as_f32().ok_or_else(unsupported)?)
+                }
+                _ => return Err(unsupported()),
+            })
+        })
+    }
+}
+
+fn element_type(ty: &AirType, index: usize) -> Result<AirTypeId> {
+    match ty {
+        AirType::Array(array) => Ok(array.element_type),
+        AirType::Vector(vector) => Ok(vector.element_type),
+        AirType::Struct(structure) => structure.elements.get(index).copied().ok_or(anyhow!(
+            "{:?} has no member {}.",
+            ty,
+            index
+        )),
+        _ => Err(anyhow!("{:?} has no elements.", ty)),
+    }
+}
+
+fn extract(composite: &AirInterpreterValue, index: u64) -> Result<AirInterpreterValue> {
+    match composite {
+        AirInterpreterValue::Composite(elements) => elements
+            .get(index as usize)
+            .cloned()
+            .ok_or(anyhow!("Index {} is out of bounds.", index)),
+        _ => Err(anyhow!("Can't extract from {:?}.", composite)),
+    }
+}
+
+fn insert(
+    composite: &mut AirInterpreterValue,
+    index: u64,
+    value: AirInterpreterValue,
+) -> Result<()> {
+    match composite {
+        AirInterpreterValue::Composite(elements) => {
+            let element = elements
+                .get_mut(index as usize)
+                .ok_or(anyhow!("Index {} is out of bounds.", index))?;
+            *element = value;
+            Ok(())
+        }
+        _ => Err(anyhow!("Can't insert into {:?}.", composite)),
+    }
+}
+
+/// Packs scalars back to back, the way a bitcast reinterprets them.
+fn value_to_bytes(value: &AirInterpreterValue, bytes: &mut Vec<u8>) -> Result<()> {
+    match value {
+        AirInterpreterValue::Integer { value, width } => {
+            bytes.extend(&value.to_le_bytes()[..width.div_ceil(8) as usize])
+        }
+        AirInterpreterValue::Float32(value) => bytes.extend(value.to_le_bytes()),
+        AirInterpreterValue::Composite(elements) => {
+            for i in elements {
+                value_to_bytes(i, bytes)?;
+            }
+        }
+        _ => return Err(anyhow!("Can't bitcast {:?}.", value)),
+    }
+
+    Ok(())
+}
+
+fn componentwise(
+    value: &AirInterpreterValue,
+    f: &dyn Fn(&AirInterpreterValue) -> Result<AirInterpreterValue>,
+) -> Result<AirInterpreterValue> {
+    match value {
+        AirInterpreterValue::Composite(values) => Ok(AirInterpreterValue::Composite(
+            values.iter().map(f).collect::<Result<_>>()?,
+        )),
+        value => f(value),
+    }
+}
+
+/// Applies `f` per component. Scalar operands are reused for every component.
+fn componentwise_n(
+    values: &[AirInterpreterValue],
+    f: &dyn Fn(&[AirInterpreterValue]) -> Result<AirInterpreterValue>,
+) -> Result<AirInterpreterValue> {
+    let count = values.iter().find_map(|i| i.elements().map(|i| i.len()));
+
+    match count {
+        None => f(values),
+        Some(count) => {
+            let mut result = vec![];
+            for index in 0..count {
+                let operands = values
+                    .iter()
+                    .map(|i| match i {
+                        AirInterpreterValue::Composite(elements) => {
+                            elements.get(index).cloned().unwrap_or_default()
+                        }
+                        scalar => scalar.clone(),
+                    })
+                    .collect::<Vec<_>>();
+                result.push(f(&operands)?);
+            }
+            Ok(AirInterpreterValue::Composite(result))
+        }
+    }
+}
+
+/// Evaluates `air.*` and `llvm.*` declarations by name, e.g.
+/// `air.fast_clamp.v3f32` or `air.max.s.i32`.
+fn intrinsic(name: &str, operands: &[AirInterpreterValue]) -> Result<AirInterpreterValue> {
+    let mut parts = name.split('.');
+    let op = match (parts.next(), parts.next()) {
+        (Some("air" | "llvm"), Some(op)) => op,
+        _ => return Err(anyhow!("Function `{}` has no body.", name)),
+    };
+    let op = op
+        .trim_start_matches("fast_")
+        .trim_start_matches("precise_");
+    let suffixes = parts.collect::<Vec<_>>();
+
+    let vector = |index: usize| {
+        operands
+            .get(index)
+            .and_then(|i| i.as_f32_vec())
+            .ok_or(anyhow!("`{}` expects float operands.", name))
+    };
+
+    // Geometric functions work on whole vectors.
+    match op {
+        "dot" => {
+            let (a, b) = (vector(0)?, vector(1)?);
+            return Ok(AirInterpreterValue::Float32(
+                a.iter().zip(&b).map(|(a, b)| a * b).sum(),
+            ));
+        }
+        "length" => return Ok(AirInterpreterValue::Float32(length(&vector(0)?))),
+        "distance" => {
+            let (a, b) = (vector(0)?, vector(1)?);
+            let difference = a.iter().zip(&b).map(|(a, b)| a - b).collect::<Vec<_>>();
+            return Ok(AirInterpreterValue::Float32(length(&difference)));
+        }
+        "normalize" => {
+            let a = vector(0)?;
+            let length = length(&a);
+            return Ok(a.iter().map(|i| i / length).collect::<Vec<_>>().into());
+        }
+        "cross" => {
+            let (a, b) = match (vector(0)?.as_slice(), vector(1)?.as_slice()) {
+                (&[a0, a1, a2], &[b0, b1, b2]) => ([a0, a1, a2], [b0, b1, b2]),
+                _ => return Err(anyhow!("`{}` needs two 3 component vectors.", name)),
+            };
+            return Ok([
+                a[1] * b[2] - a[2] * b[1],
+                a[2] * b[0] - a[0] * b[2],
+                a[0] * b[1] - a[1] * b[0],
+            ]
+            .into());
+        }
+        _ => {}
+    }
+
+    let is_float = match operands.first() {
+        Some(AirInterpreterValue::Composite(elements)) => {
+            matches!(elements.first(), Some(AirInterpreterValue::Float32(_)))
+        }
+        Some(value) => matches!(value, AirInterpreterValue::Float32(_)),
+        None => false,
+    };
+
+    if !is_float {
+        // `air.min.s.i32` and `llvm.smin.i32` both mean a signed minimum.
+        let signed = suffixes.contains(&"s")
+            || matches!(op, "smin" | "smax")
+            || (op == "abs" && !suffixes.contains(&"u"));
+        let op = op
+            .strip_prefix(['s', 'u'])
+            .filter(|i| matches!(*i, "min" | "max"))
+            .unwrap_or(op);
+
+        return componentwise_n(operands, &|i| {
+            let width = match i.first() {
+                Some(AirInterpreterValue::Integer { width, .. }) => *width,
+                _ => return Err(anyhow!("`{}` expects integer operands.", name)),
+            };
+            let x = i
+                .iter()
+                .map(|j| match signed {
+                    true => j.as_i64().map(i128::from),
+                    false => j.as_u64().map(i128::from),
+                })
+                .collect::<Option<Vec<_>>>()
+                .ok_or(anyhow!("`{}` expects integer operands.", name))?;
+
+            let result = match (op, x.as_slice()) {
+                ("min", [a, b]) => *a.min(b),
+                ("max", [a, b]) => *a.max(b),
+                // `llvm.abs` carries an extra `is_int_min_poison` flag.
+                ("abs", [a, ..]) => a.abs(),
+                ("clamp", [a, low, high]) => *a.max(low).min(high),
+                _ => return Err(anyhow!("Intrinsic `{}` is not supported yet.", name)),
+            };
+
+            Ok(AirInterpreterValue::integer(result as u64, width))
+        });
+    }
+
+    componentwise_n(operands, &|i| {
+        let x = i
+            .iter()
+            .map(|j| j.as_f32())
+            .collect::<Option<Vec<_>>>()
+            .ok_or(anyhow!("`{}` expects float operands.", name))?;
+
+        let result = match (op, x.as_slice()) {
+            ("sqrt", [a]) => a.sqrt(),
+            ("rsqrt", [a]) => 1.0 / a.sqrt(),
+            ("fabs" | "abs", [a]) => a.abs(),
+            ("floor", [a]) => a.floor(),
+            ("ceil", [a]) => a.ceil(),
+            ("trunc", [a]) => a.trunc(),
+            ("round", [a]) => a.round(),
+            ("rint" | "roundeven", [a]) => a.round_ties_even(),
+            // Metal keeps `fract` below 1.
+            ("fract", [a]) => (a - a.floor()).min(1.0 - f32::EPSILON / 2.0),
+            ("sin", [a]) => a.sin(),
+            ("cos", [a]) => a.cos(),
+            ("tan", [a]) => a.tan(),
+            ("asin", [a]) => a.asin(),
+            ("acos", [a]) => a.acos(),
+            ("atan", [a]) => a.atan(),
+            ("sinh", [a]) => a.sinh(),
+            ("cosh", [a]) => a.cosh(),
+            ("tanh", [a]) => a.tanh(),
+            ("exp", [a]) => a.exp(),
+            ("exp2", [a]) => a.exp2(),
+            ("exp10", [a]) => 10.0_f32.powf(*a),
+            ("log", [a]) => a.ln(),
+            ("log2", [a]) => a.log2(),
+            ("log10", [a]) => a.log10(),
+            ("saturate", [a]) => a.clamp(0.0, 1.0),
+            ("sign", [a]) => match a {
+                a if *a > 0.0 => 1.0,
+                a if *a < 0.0 => -1.0,
+                _ => 0.0,
+            },
+            ("pow" | "powr", [a, b]) => a.powf(*b),
+            ("fmin" | "minnum" | "min", [a, b]) => a.min(*b),
+            ("fmax" | "maxnum" | "max", [a, b]) => a.max(*b),
+            ("fmod", [a, b]) => a % b,
+            ("atan2", [a, b]) => a.atan2(*b),
+            ("copysign", [a, b]) => a.copysign(*b),
+            ("step", [edge, a]) => (a >= edge) as u32 as f32,
+            ("fma" | "fmuladd", [a, b, c]) => a.mul_add(*b, *c),
+            ("clamp", [a, low, high]) => a.max(*low).min(*high),
+            ("mix", [a, b, t]) => a + (b - a) * t,
+            ("smoothstep", [edge0, edge1, a]) => {
+                let t = ((a - edge0) / (edge1 - edge0)).clamp(0.0, 1.0);
+                t * t * (3.0 - 2.0 * t)
+            }
+            _ => return Err(anyhow!("Intrinsic `{}` is not supported yet.", name)),
+        };
+
+        Ok(AirInterpreterValue::Float32(result))
+    })
+}
+
+fn length(value: &[f32]) -> f32 {
+    value.iter().map(|i| i * i).sum::<f32>().sqrt()
+}
@@ -2,7 +2,7 @@ use std::collections::HashMap;
 
 use anyhow::{Result, anyhow};
 
-use crate::llvm_bitcode::{AttributeKindCode, CastOpCode, Fields, GEPNoWrapFlags};
+use crate::llvm_bitcode::{AttributeKindCode, BinaryOpCode, CastOpCode, Fields, GEPNoWrapFlags};
 
 #[derive(Debug, Default, Clone)]
 pub struct AirFile {
@@ -100,24 +100,24 @@ pub enum LinkageCode {
 }
 
 impl LinkageCode {
-    pub fn from_u64(v: u64) -> Self {
+    pub fn from_u64(v: u64) -> Result<Self> {
         match v {
-            0 => Self::EXTERNAL,
-            1 => Self::WEAK,
-            2 => Self::APPENDING,
-            3 => Self::INTERNAL,
-            4 => Self::LINK_ONCE,
-            5 => Self::DLL_IMPORT,
-            6 => Self::DLL_EXPORT,
-            7 => Self::EXTERN_WEAK,
-            8 => Self::COMMON,
-            9 => Self::PRIVATE,
-            10 => Self::WEAK_ODR,
-            11 => Self::LINK_ONCE_ODR,
-            12 => Self::AVAILABLE_EXTERNALLY,
-            13 => Self::DEPRECATED1,
-            14 => Self::DEPRECATED2,
-            _ => unimplemented!(),
+            0 => Ok(Self::EXTERNAL),
+            1 => Ok(Self::WEAK),
+            2 => Ok(Self::APPENDING),
+            3 => Ok(Self::INTERNAL),
+            4 => Ok(Self::LINK_ONCE),
+            5 => Ok(Self::DLL_IMPORT),
+            6 => Ok(Self::DLL_EXPORT),
+            7 => Ok(Self::EXTERN_WEAK),
+            8 => Ok(Self::COMMON),
+            9 => Ok(Self::PRIVATE),
+            10 => Ok(Self::WEAK_ODR),
+            11 => Ok(Self::LINK_ONCE_ODR),
+            12 => Ok(Self::AVAILABLE_EXTERNALLY),
+            13 => Ok(Self::DEPRECATED1),
+            14 => Ok(Self::DEPRECATED2),
+            _ => Err(anyhow!("'{:?}' is not a valid linkage.", v)),
         }
     }
 }
@@ -133,12 +133,12 @@ pub enum VisibilityCode {
 }
 
 impl VisibilityCode {
-    pub fn from_u64(v: u64) -> Self {
+    pub fn from_u64(v: u64) -> Result<Self> {
         match v {
-            0 => Self::DEFAULT,
-            1 => Self::HIDDEN,
-            2 => Self::PROTECTED,
-            _ => unimplemented!(),
+            0 => Ok(Self::DEFAULT),
+            1 => Ok(Self::HIDDEN),
+            2 => Ok(Self::PROTECTED),
+            _ => Err(anyhow!("'{:?}' is not a valid visibility.", v)),
         }
     }
 }
@@ -156,14 +156,14 @@ pub enum ThreadLocalCode {
 }
 
 impl ThreadLocalCode {
-    pub fn from_u64(v: u64) -> Self {
+    pub fn from_u64(v: u64) -> Result<Self> {
         match v {
-            0 => Self::NOT_THREAD_LOCAL,
-            1 => Self::THREAD_LOCAL,
-            2 => Self::LOCAL_DYNAMIC,
-            3 => Self::INITIAL_EXEC,
-            4 => Self::LOCAL_EXEC,
-            _ => unimplemented!(),
+            0 => Ok(Self::NOT_THREAD_LOCAL),
+            1 => Ok(Self::THREAD_LOCAL),
+            2 => Ok(Self::LOCAL_DYNAMIC),
+            3 => Ok(Self::INITIAL_EXEC),
+            4 => Ok(Self::LOCAL_EXEC),
+            _ => Err(anyhow!("'{:?}' is not a valid thread local mode.", v)),
         }
     }
 }
@@ -179,12 +179,12 @@ pub enum UnnamedAddrCode {
 }
 
 impl UnnamedAddrCode {
-    pub fn from_u64(v: u64) -> Self {
+    pub fn from_u64(v: u64) -> Result<Self> {
         match v {
-            0 => Self::NOT_UNNAMED_ADDR,
-            1 => Self::UNNAMED_ADDR,
-            2 => Self::LOCAL_UNNAMED_ADDR,
-            _ => unimplemented!(),
+            0 => Ok(Self::NOT_UNNAMED_ADDR),
+            1 => Ok(Self::UNNAMED_ADDR),
+            2 => Ok(Self::LOCAL_UNNAMED_ADDR),
+            _ => Err(anyhow!("'{:?}' is not a valid unnamed_addr kind.", v)),
         }
     }
 }
@@ -200,12 +200,12 @@ pub enum DllStorageClassCode {
 }
 
 impl DllStorageClassCode {
-    pub fn from_u64(v: u64) -> Self {
+    pub fn from_u64(v: u64) -> Result<Self> {
         match v {
-            0 => Self::DEFAULT,
-            1 => Self::DLL_IMPORT,
-            2 => Self::DLL_EXPORT,
-            _ => unimplemented!(),
+            0 => Ok(Self::DEFAULT),
+            1 => Ok(Self::DLL_IMPORT),
+            2 => Ok(Self::DLL_EXPORT),
+            _ => Err(anyhow!("'{:?}' is not a valid DLL storage class.", v)),
         }
     }
 }
@@ -220,11 +220,11 @@ pub enum PreemptionSpecifierCode {
 }
 
 impl PreemptionSpecifierCode {
-    pub fn from_u64(v: u64) -> Self {
+    pub fn from_u64(v: u64) -> Result<Self> {
         match v {
-            0 => Self::DSO_PREEMPTABLE,
-            1 => Self::DSO_LOCAL,
-            _ => unimplemented!(),
+            0 => Ok(Self::DSO_PREEMPTABLE),
+            1 => Ok(Self::DSO_LOCAL),
+            _ => Err(anyhow!("'{:?}' is not a valid preemption specifier.", v)),
         }
     }
 }
@@ -253,25 +253,25 @@ pub enum CallingConventionCode {
 }
 
 impl CallingConventionCode {
-    pub fn from_u64(v: u64) -> Self {
+    pub fn from_u64(v: u64) -> Result<Self> {
         match v {
-            0 => Self::C,
-            8 => Self::FAST,
-            9 => Self::COLD,
-            13 => Self::ANY_REG,
-            14 => Self::PRESERVE_MOST,
-            15 => Self::PRESERVE_ALL,
-            16 => Self::SWIFT,
-            17 => Self::CXX_FAST_TLS,
-            18 => Self::TAIL,
-            19 => Self::CFGUARD_CHECK,
-            20 => Self::SWIFT_TAIL,
-            64 => Self::X86_STDCALL,
-            65 => Self::X86_FASTCALL,
-            66 => Self::ARM_APCS,
-            67 => Self::ARM_AAPCS,
-            68 => Self::ARM_AAPCS_VFP,
-            _ => unimplemented!(),
+            0 => Ok(Self::C),
+            8 => Ok(Self::FAST),
+            9 => Ok(Self::COLD),
+            13 => Ok(Self::ANY_REG),
+            14 => Ok(Self::PRESERVE_MOST),
+            15 => Ok(Self::PRESERVE_ALL),
+            16 => Ok(Self::SWIFT),
+            17 => Ok(Self::CXX_FAST_TLS),
+            18 => Ok(Self::TAIL),
+            19 => Ok(Self::CFGUARD_CHECK),
+            20 => Ok(Self::SWIFT_TAIL),
+            64 => Ok(Self::X86_STDCALL),
+            65 => Ok(Self::X86_FASTCALL),
+            66 => Ok(Self::ARM_APCS),
+            67 => Ok(Self::ARM_AAPCS),
+            68 => Ok(Self::ARM_AAPCS_VFP),
+            _ => Err(anyhow!("'{:?}' is not a valid calling convention.", v)),
         }
     }
 }
@@ -295,6 +295,7 @@ pub struct AirFunctionSignature {
     pub prefix_data_index: u64,
     pub personality_fn_index: u64,
     pub preemption_specifier: PreemptionSpecifierCode,
+    pub body_offset: u64,
 }
 
 #[derive(Debug, Default, Clone)]
@@ -302,7 +303,6 @@ pub struct AirFunctionSignature {
 pub enum UndiscoveredData {
     #[default]
     NONE,
-    VSTOFFSET(u64),
     INDEX_OFFSET(u64),
 }
 
@@ -318,8 +318,90 @@ pub enum AirMetadataConstant {
     None,
     Value(AirValue),
     Pointer(u64),
-    Node(Vec<u64>),
+    /// `None` for null operands.
+    Node(Vec<Option<u64>>),
     String(String),
+    DebugInfo(AirDebugInfo),
+}
+
+#[derive(Debug, Clone)]
+pub enum AirDebugInfo {
+    File(AirDIFile),
+    CompileUnit(AirDICompileUnit),
+    Subprogram(AirDISubprogram),
+    SubroutineType(AirDISubroutineType),
+    LexicalBlock(AirDILexicalBlock),
+    LexicalBlockFile(AirDILexicalBlockFile),
+    Location(AirDILocation),
+    /// Nodes that only matter to a debugger (types, variables, ...),
+    /// kept as the raw record.
+    Other(u64, Vec<u64>),
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct AirDIFile {
+    pub filename: Option<u64>,
+    pub directory: Option<u64>,
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct AirDICompileUnit {
+    pub source_language: u64,
+    pub file: Option<u64>,
+    pub producer: Option<u64>,
+    pub is_optimized: bool,
+    pub emission_kind: u64,
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct AirDISubprogram {
+    pub scope: Option<u64>,
+    pub name: Option<u64>,
+    pub linkage_name: Option<u64>,
+    pub file: Option<u64>,
+    pub line: u64,
+    pub ty: Option<u64>,
+    pub scope_line: u64,
+    pub sp_flags: u64,
+    pub flags: u64,
+    pub unit: Option<u64>,
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct AirDISubroutineType {
+    pub flags: u64,
+    pub types: Option<u64>,
+    pub calling_convention: u64,
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct AirDILexicalBlock {
+    pub scope: Option<u64>,
+    pub file: Option<u64>,
+    pub line: u64,
+    pub column: u64,
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct AirDILexicalBlockFile {
+    pub scope: Option<u64>,
+    pub file: Option<u64>,
+    pub discriminator: u64,
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct AirDILocation {
+    pub line: u64,
+    pub column: u64,
+    pub scope: u64,
+    pub inlined_at: Option<u64>,
+    pub is_implicit_code: bool,
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct AirMetadataAttachment {
+    pub kind: u64,
+    pub node: u64,
 }
 
 #[derive(Debug, Default, Clone, PartialEq)]
@@ -331,17 +413,84 @@ pub enum AirValue {
     Function(AirFunctionSignatureId),
     Argument(AirLocal),
     Cast(AirCast),
+    BinOp(AirBinOp),
     GetElementPtr(AirGetElementPtr),
     Load(AirLoad),
     ShuffleVec(AirShuffleVec),
+    ExtractVal(AirExtractVal),
     InsertVal(AirInsertVal),
+    ExtractElt(AirExtractElt),
     InsertElt(AirInsertElt),
+    Call(AirCall),
     Return(AirReturn),
+    Branch(AirBranch),
+    Phi(AirPhi),
+    /// Stands for a value referenced before its definition, the value
+    /// number it will get is kept until it gets parsed.
+    Placeholder(u64),
+}
+
+impl AirValue {
+    /// Values used by an instruction, in operand order.
+    pub fn operands(&self) -> Vec<AirValueId> {
+        match self {
+            AirValue::Cast(cast) => vec![cast.value],
+            AirValue::BinOp(bin_op) => vec![bin_op.lhs, bin_op.rhs],
+            AirValue::GetElementPtr(gep) => {
+                let mut operands = vec![gep.base_ptr_value];
+                operands.extend(&gep.indices);
+                operands
+            }
+            AirValue::Load(load) => vec![load.op],
+            AirValue::ShuffleVec(shuffle_vec) => {
+                vec![shuffle_vec.vec1, shuffle_vec.vec2, shuffle_vec.mask]
+            }
+            AirValue::ExtractVal(extract_val) => vec![extract_val.aggregate],
+            AirValue::InsertVal(insert_val) => vec![insert_val.value1, insert_val.value2],
+            AirValue::ExtractElt(extract_elt) => vec![extract_elt.vector, extract_elt.index],
+            AirValue::InsertElt(insert_elt) => {
+                vec![insert_elt.vector, insert_elt.value, insert_elt.index]
+            }
+            AirValue::Call(call) => {
+                let mut operands = vec![call.function];
+                operands.extend(&call.arguments);
+                operands
+            }
+            AirValue::Return(ret) => ret.value.into_iter().collect(),
+            AirValue::Branch(branch) => branch.condition.into_iter().collect(),
+            AirValue::Phi(phi) => phi.incoming.iter().map(|x| x.0).collect(),
+            _ => vec![],
+        }
+    }
 }
 
 #[derive(Debug, Default, Clone, PartialEq)]
 pub struct AirReturn {
-    pub value: AirValueId,
+    pub value: Option<AirValueId>,
+}
+
+/// Blocks are their index in the function, like `block_names`.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct AirBranch {
+    pub true_block: u64,
+    /// Only conditional branches have these.
+    pub false_block: Option<u64>,
+    pub condition: Option<AirValueId>,
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct AirPhi {
+    pub ty: AirType,
+    /// The value and the block it comes from.
+    pub incoming: Vec<(AirValueId, u64)>,
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct AirCall {
+    pub function: AirValueId,
+    pub arguments: Vec<AirValueId>,
+    /// Return type of the callee.
+    pub ty: AirType,
 }
 
 #[derive(Debug, Default, Clone, PartialEq)]
@@ -351,6 +500,18 @@ pub struct AirInsertVal {
     pub insert_value_idx: u64,
 }
 
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct AirExtractVal {
+    pub aggregate: AirValueId,
+    pub indices: Vec<u64>,
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct AirExtractElt {
+    pub vector: AirValueId,
+    pub index: AirValueId,
+}
+
 #[derive(Debug, Default, Clone, PartialEq)]
 pub struct AirInsertElt {
     pub vector: AirValueId,
@@ -381,6 +542,13 @@ pub struct AirGetElementPtr {
     pub indices: Vec<AirValueId>,
 }
 
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct AirBinOp {
+    pub opcode: BinaryOpCode,
+    pub lhs: AirValueId,
+    pub rhs: AirValueId,
+}
+
 #[derive(Debug, Default, Clone, PartialEq)]
 pub struct AirCast {
     pub value: AirValueId,
@@ -392,6 +560,7 @@ pub struct AirCast {
 pub struct AirFunctionBody {
     pub signature: AirFunctionSignatureId,
     pub contents: Vec<AirValueId>,
+    pub block_names: HashMap<u64, String>,
 }
 
 #[derive(Debug, Default, Clone, PartialEq)]
@@ -450,16 +619,44 @@ pub struct AirModule {
     pub operand_bundle_tags: Vec<String>,
     pub sync_scope_names: Vec<String>,
     pub max_global_id: u64,
+    pub vst_offset: u64,
+    pub value_names: HashMap<AirValueId, String>,
+    pub metadata_attachments: HashMap<AirValueId, Vec<AirMetadataAttachment>>,
+    pub debug_locations: HashMap<AirValueId, AirDILocation>,
 }
 
 impl AirModule {
-    pub fn get_metadata_string(&self, id: u64) -> Option<String> {
-        match self.metadata_constants.get(&id).unwrap() {
+    /// Takes node operands as they are, null ones have no constant.
+    pub fn get_metadata(&self, id: Option<u64>) -> Option<&AirMetadataConstant> {
+        self.metadata_constants.get(&id?)
+    }
+
+    pub fn get_metadata_string(&self, id: Option<u64>) -> Option<String> {
+        match self.metadata_constants.get(&id?)? {
             AirMetadataConstant::String(string) => Some(string.clone()),
             _ => None,
         }
     }
 
+    pub fn get_metadata_integer(&self, id: Option<u64>) -> Option<u64> {
+        match self.metadata_constants.get(&id?)? {
+            AirMetadataConstant::Value(AirValue::Constant(constant)) => {
+                match self.constants.get(constant)?.value {
+                    AirConstantValue::Integer(value) => Some(value),
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+
+    pub fn get_debug_info(&self, id: u64) -> Option<&AirDebugInfo> {
+        match self.metadata_constants.get(&id) {
+            Some(AirMetadataConstant::DebugInfo(debug_info)) => Some(debug_info),
+            _ => None,
+        }
+    }
+
     pub fn get_function_signature(
         &self,
         id: AirFunctionSignatureId,
@@ -485,7 +682,7 @@ impl AirModule {
         }
     }
 
-    pub fn parse_global_variable(&mut self, fields: Fields) {
+    pub fn parse_global_variable(&mut self, fields: Fields) -> Result<()> {
         let string_offset = fields[0];
         let string_size = fields[1];
 
@@ -501,7 +698,7 @@ impl AirModule {
 
         let initializer_id = fields[4];
 
-        let linkage = LinkageCode::from_u64(fields[5]);
+        let linkage = LinkageCode::from_u64(fields[5])?;
         let alignment = match fields[7].checked_sub(1) {
             Some(result) => 2_u64.pow(result as u32),
             None => 0,
@@ -509,16 +706,16 @@ impl AirModule {
         // TODO: Parse section (fields[7]) correctly.
         let section_index = fields[7];
 
-        let visibility = VisibilityCode::from_u64(fields[8]);
-        let thread_local = ThreadLocalCode::from_u64(fields[9]);
-        let unnamed_addr = UnnamedAddrCode::from_u64(fields[10]);
-        let dll_storage_class = DllStorageClassCode::from_u64(fields[11]);
+        let visibility = VisibilityCode::from_u64(fields[8])?;
+        let thread_local = ThreadLocalCode::from_u64(fields[9])?;
+        let unnamed_addr = UnnamedAddrCode::from_u64(fields[10])?;
+        let dll_storage_class = DllStorageClassCode::from_u64(fields[11])?;
 
         // TODO: Parse comdat (fields[12]) correctly.
         let comdat = fields[12];
 
         let attribute_index = fields[13];
-        let preemption_specifier = PreemptionSpecifierCode::from_u64(fields[14]);
+        let preemption_specifier = PreemptionSpecifierCode::from_u64(fields[14])?;
 
         let result = AirGlobalVariable {
             name,
@@ -546,6 +743,8 @@ impl AirModule {
             .insert(AirGlobalVariableId(self.max_global_id), result);
 
         self.max_global_id += 1;
+
+        Ok(())
     }
 
     pub fn parse_function_signature(&mut self, fields: Fields) -> Result<()> {
@@ -563,9 +762,9 @@ impl AirModule {
             AirType::Function(f) => f,
             _ => return Err(anyhow!("Function type not found.")),
         };
-        let calling_convention = CallingConventionCode::from_u64(fields[3]);
+        let calling_convention = CallingConventionCode::from_u64(fields[3])?;
         let is_proto = fields[4] != 0;
-        let linkage = LinkageCode::from_u64(fields[5]);
+        let linkage = LinkageCode::from_u64(fields[5])?;
 
         let attr_entry = match self.entry_table.get(&fields[6]) {
             Some(entry) => Some(entry.clone()),
@@ -579,11 +778,11 @@ impl AirModule {
 
         // TODO: Parse section (fields[8]) correctly.
         let section_index = fields[8];
-        let visibility = VisibilityCode::from_u64(fields[9]);
+        let visibility = VisibilityCode::from_u64(fields[9])?;
 
         // TODO: Parse gc (fields[10]) correctly.
         let gc_index = fields[10];
-        let unnamed_addr = UnnamedAddrCode::from_u64(fields[11]);
+        let unnamed_addr = UnnamedAddrCode::from_u64(fields[11])?;
 
         // TODO: Parse prologue_data (fields[12]) correctly.
         let prologue_data_index = fields[12];
@@ -597,7 +796,7 @@ impl AirModule {
         // TODO: Parse personality_fn (fields[15]) correctly.
         let personality_fn_index = fields[15];
 
-        let preemption_specifier = PreemptionSpecifierCode::from_u64(fields[16]);
+        let preemption_specifier = PreemptionSpecifierCode::from_u64(fields[16])?;
 
         self.value_list
             .push(AirValue::Function(AirFunctionSignatureId(
@@ -622,6 +821,7 @@ impl AirModule {
             prefix_data_index,
             personality_fn_index,
             preemption_specifier,
+            body_offset: 0,
         });
 
         self.max_global_id += 1;
@@ -681,6 +881,8 @@ pub enum AirType {
     Struct(AirStructType),
     Function(AirFunctionType),
     Metadata,
+    /// Basic blocks, only for operands of branches.
+    Label,
 }
 
 impl AirType {
@@ -7,23 +7,40 @@ pub use items::*;
 use anyhow::{Result, anyhow};
 
 use crate::llvm_bitcode::{
-    AttributeCode, AttributeKindCode, BitCursor, Bitstream, Block, BlockID, CastOpCode,
-    ConstantsCode, Fields, FunctionCodes, GEPNoWrapFlags, IdentificationCode, MetadataCodes,
-    ModuleCode, Record, Signature, StreamEntry, TypeCode,
+    AttributeCode, AttributeKindCode, BinaryOpCode, BitCursor, Bitstream, Block, BlockID,
+    CastOpCode, ConstantsCode, Fields, FunctionCodes, GEPNoWrapFlags, IdentificationCode,
+    MetadataCodes, ModuleCode, Record, Signature, StreamEntry, TypeCode, ValueSymtabCodes,
 };
 
 pub struct Parser {
     pub signature: Option<Signature>,
     pub bitstream: Bitstream,
+    value_numbers: Vec<AirValueId>,
+    forward_references: HashMap<u64, AirValueId>,
+    global_value_count: Option<usize>,
+    /// Skip function blocks, see `start_lazy`.
+    lazy: bool,
+    /// Function offsets in the value symbol table are relative to this.
+    bitcode_start_bit: usize,
+    module_abbrev_width: u64,
+    module_value_symtab_read: bool,
 }
 
 impl Parser {
     pub fn new(inner: Vec<u8>) -> Result<Self> {
         let (signature, bitstream) = Bitstream::from(inner)?;
+        let bitcode_start_bit = bitstream.tell_bit();
 
         Ok(Self {
             signature,
             bitstream,
+            value_numbers: vec![],
+            forward_references: HashMap::new(),
+            global_value_count: None,
+            lazy: false,
+            bitcode_start_bit,
+            module_abbrev_width: 0,
+            module_value_symtab_read: false,
         })
     }
 
@@ -52,7 +69,7 @@ impl Parser {
                         // assume that its a blob.
                         result.push(Self::parse_blob(record.fields));
                     }
-                    _ => todo!(),
+                    _ => return Err(anyhow!("Unexpected sub-block in the blob block.")),
                 },
                 None => return Ok(result),
             }
@@ -86,7 +103,7 @@ impl Parser {
                         }
                         None => return Err(anyhow!("Module not found.")),
                     },
-                    _ => todo!(),
+                    _ => return Err(anyhow!("Unexpected sub-block in the string table block.")),
                 },
                 None => return Ok(AirStringTable { strings }),
             }
@@ -96,23 +113,26 @@ impl Parser {
     }
 
     pub fn parse_block(&mut self, b: Block, module: &mut Option<AirModule>) -> Result<AirItem> {
-        match BlockID::from_u64(b.block_id) {
+        match BlockID::from_u64(b.block_id)? {
             BlockID::IDENTIFICATION => Ok(AirItem::IdentificationBlock(
                 self.parse_identification_block()?,
             )),
-            BlockID::MODULE => Ok(AirItem::Module(self.parse_module()?)),
+            BlockID::MODULE => {
+                self.module_abbrev_width = b.abbrev_width;
+                Ok(AirItem::Module(self.parse_module()?))
+            }
             BlockID::SYMTAB => Ok(AirItem::SymTabBlock(AirSymTabBlock {
                 blobs: self.parse_blob_vec()?,
             })),
             BlockID::STRTAB => Ok(AirItem::StringTable(
                 self.parse_string_table_contents(module)?,
             )),
-            _ => todo!("{:?} not implemented yet.", b),
+            _ => Err(anyhow!("{:?} blocks aren't supported yet.", b)),
         }
     }
 
     pub fn parse_module_record(&mut self, record: Record, result: &mut AirModule) -> Result<()> {
-        match ModuleCode::from_u64(record.code) {
+        match ModuleCode::from_u64(record.code)? {
             ModuleCode::VERSION => {
                 result.version = record.fields[0];
                 result.use_relative_ids = result.version >= 1;
@@ -122,12 +142,10 @@ impl Parser {
             ModuleCode::SOURCE_FILENAME => {
                 result.source_filename = Self::parse_string(record.fields)
             }
-            ModuleCode::GLOBALVAR => result.parse_global_variable(record.fields),
+            ModuleCode::GLOBALVAR => result.parse_global_variable(record.fields)?,
             ModuleCode::FUNCTION => result.parse_function_signature(record.fields)?,
-            ModuleCode::VSTOFFSET => result
-                .undiscovered_data
-                .push(UndiscoveredData::VSTOFFSET(record.fields[0])),
-            _ => todo!("{:?} | {:?}", ModuleCode::from_u64(record.code), record),
+            ModuleCode::VSTOFFSET => result.vst_offset = record.fields[0],
+            code => return Err(anyhow!("{:?} records aren't supported yet.", code)),
         }
 
         Ok(())
@@ -144,7 +162,7 @@ impl Parser {
                     StreamEntry::EndBlock | StreamEntry::EndOfStream => {
                         return Ok(result);
                     }
-                    StreamEntry::Record(record) => match TypeCode::from_u64(record.code) {
+                    StreamEntry::Record(record) => match TypeCode::from_u64(record.code)? {
                         TypeCode::NUMENTRY => {}
                         TypeCode::FLOAT => result.push(AirType::Float),
                         TypeCode::VECTOR => result.push(AirType::Vector(AirVectorType {
@@ -196,10 +214,11 @@ impl Parser {
                             }));
                         }
                         TypeCode::METADATA => result.push(AirType::Metadata),
+                        TypeCode::LABEL => result.push(AirType::Label),
                         TypeCode::VOID => result.push(AirType::Void),
-                        _ => todo!("{:?}", TypeCode::from_u64(record.code)),
+                        code => return Err(anyhow!("{:?} types aren't supported yet.", code)),
                     },
-                    _ => todo!(),
+                    _ => return Err(anyhow!("Unexpected sub-block in the type block.")),
                 },
                 None => return Ok(result),
             }
@@ -214,7 +233,7 @@ impl Parser {
         }
 
         let mut result = AirAttribute::default();
-        match AttributeCode::from_u64(record.code) {
+        match AttributeCode::from_u64(record.code)? {
             AttributeCode::GRP_CODE_ENTRY => {
                 result.id = record.fields[0];
                 result.paramidx = record.fields[1];
@@ -224,7 +243,7 @@ impl Parser {
                     match record.fields[count] {
                         0 => {
                             count += 1;
-                            let property = AttributeKindCode::from_u64(record.fields[count]);
+                            let property = AttributeKindCode::from_u64(record.fields[count])?;
                             result
                                 .properties
                                 .push(AirAttrProperties::WellKnown(property));
@@ -256,7 +275,9 @@ impl Parser {
                                 .properties
                                 .push(AirAttrProperties::WithStringValue(first, second));
                         }
-                        _ => todo!("{:?}", record.fields[count]),
+                        kind => {
+                            return Err(anyhow!("Attribute kind {} isn't supported yet.", kind));
+                        }
                     }
 
                     count += 1;
@@ -264,7 +285,7 @@ impl Parser {
 
                 return Ok(result);
             }
-            _ => todo!(),
+            code => Err(anyhow!("{:?} isn't an attribute group entry.", code)),
         }
     }
 
@@ -301,7 +322,11 @@ impl Parser {
                         result.insert(property.id, property);
                     }
 
-                    _ => todo!(),
+                    _ => {
+                        return Err(anyhow!(
+                            "Unexpected sub-block in the attribute group block."
+                        ));
+                    }
                 },
                 None => return Ok(result),
             }
@@ -311,7 +336,7 @@ impl Parser {
     }
 
     pub fn parse_entry(&mut self, record: Record, module: &mut AirModule) -> Result<AirAttrEntry> {
-        match AttributeCode::from_u64(record.code) {
+        match AttributeCode::from_u64(record.code)? {
             AttributeCode::ENTRY => {
                 let mut result: Vec<AirAttribute> = vec![];
 
@@ -321,7 +346,7 @@ impl Parser {
 
                 return Ok(AirAttrEntry { groups: result });
             }
-            _ => todo!(),
+            code => Err(anyhow!("{:?} records aren't supported yet.", code)),
         }
     }
 
@@ -343,7 +368,7 @@ impl Parser {
                         result.insert(result.len() as u64 + 1, entry);
                     }
 
-                    _ => todo!(),
+                    _ => return Err(anyhow!("Unexpected sub-block in the attribute block.")),
                 },
                 None => return Ok(result),
             }
@@ -356,22 +381,16 @@ impl Parser {
         result: &mut AirModule,
         ty: AirTypeId,
         value: u64,
-    ) -> AirConstantValue {
+    ) -> Result<AirConstantValue> {
         match result.types[ty.0 as usize] {
             AirType::Float => {
                 let result = f32::from_le_bytes((value as u32).to_le_bytes());
-                return AirConstantValue::Float32(result);
-            }
-            AirType::Integer(_) => {
-                return AirConstantValue::Integer(value);
-            }
-            AirType::Array(_) => {
-                return result.constants[&AirConstantId(value)].value.clone();
+                Ok(AirConstantValue::Float32(result))
             }
-            AirType::Pointer(_, _) => {
-                return AirConstantValue::Pointer(value);
-            }
-            _ => todo!("{:?}", ty),
+            AirType::Integer(_) => Ok(AirConstantValue::Integer(value)),
+            AirType::Array(_) => Ok(result.constants[&AirConstantId(value)].value.clone()),
+            AirType::Pointer(_, _) => Ok(AirConstantValue::Pointer(value)),
+            ref ty => Err(anyhow!("Constants of type {:?} aren't supported yet.", ty)),
         }
     }
 
@@ -384,7 +403,7 @@ impl Parser {
         let element_type = match result.types[array_ty.0 as usize].clone() {
             AirType::Vector(v) => v.element_type,
             AirType::Array(a) => a.element_type,
-            _ => todo!(),
+            ty => return Err(anyhow!("{:?} isn't an array or vector type.", ty)),
         };
 
         let mut contents: Vec<AirConstantValue> = vec![];
@@ -393,7 +412,7 @@ impl Parser {
                 result,
                 element_type,
                 i,
-            ));
+            )?);
         }
 
         Ok(AirConstantValue::Array(contents))
@@ -422,7 +441,7 @@ impl Parser {
             match content {
                 Some(ucontent) => match ucontent? {
                     StreamEntry::EndBlock | StreamEntry::EndOfStream => break,
-                    StreamEntry::Record(record) => match ConstantsCode::from_u64(record.code) {
+                    StreamEntry::Record(record) => match ConstantsCode::from_u64(record.code)? {
                         ConstantsCode::SETTYPE => {
                             current_type = AirTypeId(record.fields[0]);
 
@@ -547,10 +566,10 @@ impl Parser {
                                 AirValue::Constant(AirConstantId(module.max_constants_id)),
                             );
                         }
-                        _ => todo!("{:#?}", ConstantsCode::from_u64(record.code)),
+                        code => return Err(anyhow!("{:?} constants aren't supported yet.", code)),
                     },
 
-                    _ => todo!(),
+                    _ => return Err(anyhow!("Unexpected sub-block in the constants block.")),
                 },
                 None => break,
             }
@@ -570,7 +589,7 @@ impl Parser {
             match content {
                 Some(content) => match content? {
                     StreamEntry::EndBlock | StreamEntry::EndOfStream => return Ok(()),
-                    StreamEntry::Record(record) => match MetadataCodes::from_u64(record.code) {
+                    StreamEntry::Record(record) => match MetadataCodes::from_u64(record.code)? {
                         MetadataCodes::KIND => {
                             let _ = result.metadata_kind_table.insert(
                                 record.fields[0],
@@ -580,9 +599,9 @@ impl Parser {
                                 },
                             );
                         }
-                        _ => todo!(),
+                        code => return Err(anyhow!("{:?} records aren't supported yet.", code)),
                     },
-                    _ => todo!(),
+                    _ => return Err(anyhow!("Unexpected sub-block in the metadata kind block.")),
                 },
                 None => return Ok(()),
             }
@@ -626,26 +645,92 @@ impl Parser {
         Ok(result)
     }
 
-    pub fn parse_value_symtab(&mut self, _result: &mut AirModule) -> Result<()> {
+    /// Module-level values keep their value number as id, so anything
+    /// outside of the current function maps to itself.
+    pub fn get_value_id(&self, value_no: u64) -> AirValueId {
+        match self.value_numbers.get(value_no as usize) {
+            Some(id) => *id,
+            None => AirValueId(value_no),
+        }
+    }
+
+    /// Returns the names of the basic blocks, which only function-level
+    /// tables have.
+    pub fn parse_value_symtab(&mut self, result: &mut AirModule) -> Result<HashMap<u64, String>> {
         let mut content = self.bitstream.next();
+        let mut block_names: HashMap<u64, String> = HashMap::new();
 
         loop {
             match content {
                 Some(content) => match content? {
-                    StreamEntry::EndBlock | StreamEntry::EndOfStream => return Ok(()),
-                    StreamEntry::Record(_record) => {
-                        // TODO: Parse Value SymTab.
+                    StreamEntry::EndBlock | StreamEntry::EndOfStream => return Ok(block_names),
+                    StreamEntry::Record(record) => match ValueSymtabCodes::from_u64(record.code)? {
+                        ValueSymtabCodes::ENTRY => {
+                            let id = self.get_value_id(record.fields[0]);
+                            let name = Self::parse_string(record.fields[1..].to_vec());
+
+                            result.value_names.insert(id, name);
+                        }
+                        ValueSymtabCodes::BBENTRY => {
+                            let name = Self::parse_string(record.fields[1..].to_vec());
+
+                            block_names.insert(record.fields[0], name);
+                        }
+                        ValueSymtabCodes::FNENTRY => {
+                            let id = self.get_value_id(record.fields[0]);
+
+                            let signature = match result.value_list.get(id.0 as usize) {
+                                Some(AirValue::Function(signature)) => *signature,
+                                _ => return Err(anyhow!("Invalid function entry {:?}.", id)),
+                            };
+
+                            for i in &mut result.function_signatures {
+                                if i.global_id == signature {
+                                    i.body_offset = record.fields[1];
+                                }
+                            }
+
+                            // Names are only here when there is no string table.
+                            if record.fields.len() > 2 {
+                                let name = Self::parse_string(record.fields[2..].to_vec());
+                                result.value_names.insert(id, name);
+                            }
+                        }
+                        ValueSymtabCodes::COMBINED_ENTRY => {
+                            return Err(anyhow!("Combined entries are only used by summaries."));
+                        }
+                    },
+                    _ => {
+                        return Err(anyhow!(
+                            "Unexpected sub-block in the value symbol table block."
+                        ));
                     }
-                    _ => todo!(),
                 },
-                None => return Ok(()),
+                None => return Ok(block_names),
             }
 
             content = self.bitstream.next();
         }
     }
 
-    pub fn parse_metadata_attachment(&mut self, _result: &mut AirModule) -> Result<()> {
+    pub fn parse_attachment_pairs(fields: &[u64]) -> Vec<AirMetadataAttachment> {
+        fields
+            .chunks(2)
+            .map(|x| AirMetadataAttachment {
+                kind: x[0],
+                node: x[1],
+            })
+            .collect()
+    }
+
+    /// `contents` are the instructions of the function the block is in,
+    /// attachments refer to them by their index.
+    pub fn parse_metadata_attachment(
+        &mut self,
+        result: &mut AirModule,
+        function: AirValueId,
+        contents: &[AirValueId],
+    ) -> Result<()> {
         let mut content = self.bitstream.next();
 
         loop {
@@ -654,15 +739,38 @@ impl Parser {
                     StreamEntry::EndBlock | StreamEntry::EndOfStream => return Ok(()),
                     StreamEntry::Record(record) => {
                         if !matches!(
-                            MetadataCodes::from_u64(record.code),
+                            MetadataCodes::from_u64(record.code)?,
                             MetadataCodes::ATTACHMENT
                         ) {
                             return Err(anyhow!("Only accepts Attachments, for now..."));
                         }
 
-                        // TODO: Parse Metadata Attachments.
+                        // An odd length means the first field is an instruction,
+                        // otherwise they're attached to the function itself.
+                        let (id, pairs) = match record.fields.len() % 2 {
+                            1 => match contents.get(record.fields[0] as usize) {
+                                Some(id) => (*id, &record.fields[1..]),
+                                None => {
+                                    return Err(anyhow!(
+                                        "Attachment to unknown instruction {}.",
+                                        record.fields[0]
+                                    ));
+                                }
+                            },
+                            _ => (function, &record.fields[..]),
+                        };
+
+                        result
+                            .metadata_attachments
+                            .entry(id)
+                            .or_default()
+                            .extend(Self::parse_attachment_pairs(pairs));
+                    }
+                    _ => {
+                        return Err(anyhow!(
+                            "Unexpected sub-block in the metadata attachment block."
+                        ));
                     }
-                    _ => todo!(),
                 },
                 None => return Ok(()),
             }
@@ -679,7 +787,7 @@ impl Parser {
             match content {
                 Some(content) => match content? {
                     StreamEntry::EndBlock | StreamEntry::EndOfStream => return Ok(()),
-                    StreamEntry::Record(record) => match MetadataCodes::from_u64(record.code) {
+                    StreamEntry::Record(record) => match MetadataCodes::from_u64(record.code)? {
                         MetadataCodes::STRINGS => {
                             let strings = Self::parse_metadata_strings(record.fields)?;
                             for i in strings {
@@ -706,7 +814,7 @@ impl Parser {
                             let _ = result.metadata_constants.insert(
                                 next_metadata_no,
                                 AirMetadataConstant::Node(
-                                    record.fields.iter().map(|x| x - 1).collect(),
+                                    record.fields.iter().map(|x| x.checked_sub(1)).collect(),
                                 ),
                             );
                             next_metadata_no += 1;
@@ -724,11 +832,62 @@ impl Parser {
                             current_name = Self::parse_string(record.fields);
                         }
                         MetadataCodes::DISTINCT_NODE => {
-                            // Skip. We don't need this data (for now).
+                            let _ = result.metadata_constants.insert(
+                                next_metadata_no,
+                                AirMetadataConstant::Node(
+                                    record.fields.iter().map(|x| x.checked_sub(1)).collect(),
+                                ),
+                            );
+                            next_metadata_no += 1;
+                        }
+                        MetadataCodes::GLOBAL_DECL_ATTACHMENT => {
+                            result
+                                .metadata_attachments
+                                .entry(AirValueId(record.fields[0]))
+                                .or_default()
+                                .extend(Self::parse_attachment_pairs(&record.fields[1..]));
                         }
-                        _ => todo!("{:?}", MetadataCodes::from_u64(record.code)),
+                        MetadataCodes::LOCATION
+                        | MetadataCodes::GENERIC_DEBUG
+                        | MetadataCodes::SUBRANGE
+                        | MetadataCodes::ENUMERATOR
+                        | MetadataCodes::BASIC_TYPE
+                        | MetadataCodes::FILE
+                        | MetadataCodes::DERIVED_TYPE
+                        | MetadataCodes::COMPOSITE_TYPE
+                        | MetadataCodes::SUBROUTINE_TYPE
+                        | MetadataCodes::COMPILE_UNIT
+                        | MetadataCodes::SUBPROGRAM
+                        | MetadataCodes::LEXICAL_BLOCK
+                        | MetadataCodes::LEXICAL_BLOCK_FILE
+                        | MetadataCodes::NAMESPACE
+                        | MetadataCodes::TEMPLATE_TYPE
+                        | MetadataCodes::TEMPLATE_VALUE
+                        | MetadataCodes::GLOBAL_VAR
+                        | MetadataCodes::LOCAL_VAR
+                        | MetadataCodes::EXPRESSION
+                        | MetadataCodes::OBJC_PROPERTY
+                        | MetadataCodes::IMPORTED_ENTITY
+                        | MetadataCodes::MODULE
+                        | MetadataCodes::MACRO
+                        | MetadataCodes::MACRO_FILE
+                        | MetadataCodes::GLOBAL_VAR_EXPR
+                        | MetadataCodes::LABEL
+                        | MetadataCodes::STRING_TYPE
+                        | MetadataCodes::COMMON_BLOCK
+                        | MetadataCodes::GENERIC_SUBRANGE
+                        | MetadataCodes::ASSIGN_ID => {
+                            let debug_info = Self::parse_debug_info(record.code, &record.fields)?;
+
+                            let _ = result.metadata_constants.insert(
+                                next_metadata_no,
+                                AirMetadataConstant::DebugInfo(debug_info),
+                            );
+                            next_metadata_no += 1;
+                        }
+                        code => return Err(anyhow!("{:?} metadata isn't supported yet.", code)),
                     },
-                    _ => todo!(),
+                    _ => return Err(anyhow!("Unexpected sub-block in the metadata block.")),
                 },
                 None => return Ok(()),
             }
@@ -737,6 +896,79 @@ impl Parser {
         }
     }
 
+    /// Most debug info operands are stored one above their id, zero being null.
+    pub fn get_metadata_ref(field: u64) -> Option<u64> {
+        field.checked_sub(1)
+    }
+
+    /// Builds the debug info node of a metadata record, `fields` uses the
+    /// layout LLVM writes them with.
+    pub fn parse_debug_info(code: u64, fields: &[u64]) -> Result<AirDebugInfo> {
+        let field = |i: usize| fields.get(i).copied().unwrap_or(0);
+        let reference = |i: usize| Self::get_metadata_ref(field(i));
+
+        Ok(match MetadataCodes::from_u64(code)? {
+            MetadataCodes::LOCATION => AirDebugInfo::Location(AirDILocation {
+                line: field(1),
+                column: field(2),
+                scope: field(3),
+                inlined_at: reference(4),
+                is_implicit_code: field(5) != 0,
+            }),
+            MetadataCodes::FILE => AirDebugInfo::File(AirDIFile {
+                filename: reference(1),
+                directory: reference(2),
+            }),
+            MetadataCodes::COMPILE_UNIT => AirDebugInfo::CompileUnit(AirDICompileUnit {
+                source_language: field(1),
+                file: reference(2),
+                producer: reference(3),
+                is_optimized: field(4) != 0,
+                emission_kind: field(8),
+            }),
+            MetadataCodes::SUBPROGRAM => {
+                // Older records keep the flags in a different layout.
+                if field(0) & 0b100 == 0 {
+                    return Err(anyhow!(
+                        "Subprograms without SP flags are not supported yet."
+                    ));
+                }
+
+                AirDebugInfo::Subprogram(AirDISubprogram {
+                    scope: reference(1),
+                    name: reference(2),
+                    linkage_name: reference(3),
+                    file: reference(4),
+                    line: field(5),
+                    ty: reference(6),
+                    scope_line: field(7),
+                    sp_flags: field(9),
+                    flags: field(11),
+                    unit: reference(12),
+                })
+            }
+            MetadataCodes::SUBROUTINE_TYPE => AirDebugInfo::SubroutineType(AirDISubroutineType {
+                flags: field(1),
+                types: reference(2),
+                calling_convention: field(3),
+            }),
+            MetadataCodes::LEXICAL_BLOCK => AirDebugInfo::LexicalBlock(AirDILexicalBlock {
+                scope: reference(1),
+                file: reference(2),
+                line: field(3),
+                column: field(4),
+            }),
+            MetadataCodes::LEXICAL_BLOCK_FILE => {
+                AirDebugInfo::LexicalBlockFile(AirDILexicalBlockFile {
+                    scope: reference(1),
+                    file: reference(2),
+                    discriminator: field(3),
+                })
+            }
+            _ => AirDebugInfo::Other(code, fields.to_vec()),
+        })
+    }
+
     pub fn parse_operand_bundle_tags(&mut self, result: &mut AirModule) -> Result<()> {
         let mut content = self.bitstream.next();
 
@@ -755,7 +987,11 @@ impl Parser {
                             .operand_bundle_tags
                             .push(Self::parse_string(record.fields));
                     }
-                    _ => todo!(),
+                    _ => {
+                        return Err(anyhow!(
+                            "Unexpected sub-block in the operand bundle tags block."
+                        ));
+                    }
                 },
                 None => return Ok(()),
             }
@@ -782,7 +1018,11 @@ impl Parser {
                             .sync_scope_names
                             .push(Self::parse_string(record.fields));
                     }
-                    _ => todo!(),
+                    _ => {
+                        return Err(anyhow!(
+                            "Unexpected sub-block in the sync scope names block."
+                        ));
+                    }
                 },
                 None => return Ok(()),
             }
@@ -791,24 +1031,128 @@ impl Parser {
         }
     }
 
-    pub fn get_value(
+    pub fn get_value_no(&self, result: &AirModule, field: u64) -> u64 {
+        let next_value_no = self.value_numbers.len() as u64;
+
+        // Relative ids are 32-bit, forward references wrap around.
+        match result.use_relative_ids {
+            true => (next_value_no as u32).wrapping_sub(field as u32) as u64,
+            false => field,
+        }
+    }
+
+    pub fn get_value(&mut self, result: &mut AirModule, field: u64) -> AirValueId {
+        let value_no = self.get_value_no(result, field);
+        self.get_numbered_value(result, value_no)
+    }
+
+    /// `PHI` operands are signed, so that values from later blocks can be
+    /// relative too.
+    pub fn get_signed_value(&mut self, result: &mut AirModule, field: u64) -> AirValueId {
+        let delta = match field & 1 {
+            0 => (field >> 1) as i64,
+            _ => -((field >> 1) as i64),
+        };
+
+        let value_no = match result.use_relative_ids {
+            true => (self.value_numbers.len() as i64 - delta) as u64,
+            false => delta as u64,
+        };
+
+        self.get_numbered_value(result, value_no)
+    }
+
+    pub fn get_numbered_value(&mut self, result: &mut AirModule, value_no: u64) -> AirValueId {
+        if let Some(id) = self.value_numbers.get(value_no as usize) {
+            return *id;
+        }
+
+        if let Some(id) = self.forward_references.get(&value_no) {
+            return *id;
+        }
+
+        result.value_list.push(AirValue::Placeholder(value_no));
+        let id = AirValueId(result.value_list.len() as u64 - 1);
+
+        self.forward_references.insert(value_no, id);
+
+        id
+    }
+
+    /// Reads an operand and skips the type that follows it when
+    /// it's a forward reference.
+    pub fn get_value_type_pair(
+        &mut self,
+        result: &mut AirModule,
+        fields: &Fields,
+        slot: &mut usize,
+    ) -> Result<AirValueId> {
+        let field = *fields
+            .get(*slot)
+            .ok_or(anyhow!("Missing operand {}.", *slot))?;
+        *slot += 1;
+
+        if self.get_value_no(result, field) >= self.value_numbers.len() as u64 {
+            *slot += 1;
+        }
+
+        Ok(self.get_value(result, field))
+    }
+
+    pub fn push_instruction(
         &mut self,
         result: &mut AirModule,
-        field: u64,
-        next_value_no: usize,
+        value: AirValue,
+        has_value_no: bool,
     ) -> AirValueId {
-        match result.use_relative_ids {
-            true => AirValueId(next_value_no as u64 - field),
-            false => AirValueId(field),
+        if !has_value_no {
+            result.value_list.push(value);
+            return AirValueId(result.value_list.len() as u64 - 1);
         }
+
+        let value_no = self.value_numbers.len() as u64;
+
+        let id = match self.forward_references.remove(&value_no) {
+            Some(id) => {
+                result.value_list[id.0 as usize] = value;
+                id
+            }
+            None => {
+                result.value_list.push(value);
+                AirValueId(result.value_list.len() as u64 - 1)
+            }
+        };
+
+        self.value_numbers.push(id);
+
+        id
     }
 
     pub fn parse_function_body(&mut self, result: &mut AirModule, _block: Block) -> Result<()> {
         let mut content = self.bitstream.next();
 
-        let id = result.current_function_local_id as usize;
+        // Every function numbers its values right after the module-level ones.
+        let global_value_count = *self
+            .global_value_count
+            .get_or_insert(result.value_list.len());
+        self.value_numbers = (0..global_value_count as u64).map(AirValueId).collect();
+        self.forward_references.clear();
+
+        // Bodies only exist for definitions, in the order they were declared.
+        let mut id = result.current_function_local_id as usize;
+        while result
+            .function_signatures
+            .get(id)
+            .is_some_and(|x| x.is_proto)
+        {
+            id += 1;
+        }
+        result.current_function_local_id = id as u64;
+
         let function_signature = &mut result.function_signatures[id];
+        let signature = function_signature.global_id;
         let mut contents: Vec<AirValueId> = vec![];
+        let mut block_names: HashMap<u64, String> = HashMap::new();
 
         let mut count = 0;
         for i in &function_signature.ty.param_types {
@@ -817,15 +1161,14 @@ impl Parser {
                 type_id: *i,
                 value: None,
             }));
-            function_signature
-                .ty
-                .param_values
-                .push(AirValueId(result.value_list.len() as u64 - 1));
+            let value_id = AirValueId(result.value_list.len() as u64 - 1);
+            function_signature.ty.param_values.push(value_id);
+            self.value_numbers.push(value_id);
             count += 1;
         }
 
-        let mut next_value_no = result.value_list.len();
         let mut function_body_id = 0;
+        let mut last_location: Option<AirDILocation> = None;
 
         loop {
             match content {
@@ -833,7 +1176,7 @@ impl Parser {
                     StreamEntry::EndBlock | StreamEntry::EndOfStream => {
                         break;
                     }
-                    StreamEntry::Record(record) => match FunctionCodes::from_u64(record.code) {
+                    StreamEntry::Record(record) => match FunctionCodes::from_u64(record.code)? {
                         FunctionCodes::DECLAREBLOCKS => {
                             if record.fields[0] == 0 {
                                 return Err(anyhow!("Invalid Declare Block value."));
@@ -846,9 +1189,11 @@ impl Parser {
                                 .resize(function_body_id, AirFunctionBody::default());
                         }
                         FunctionCodes::INST_CAST => {
-                            let value = self.get_value(result, record.fields[0], next_value_no);
-                            let cast_to_type = result.types[record.fields[1] as usize].clone();
-                            let cast_code = CastOpCode::from_u64(record.fields[2]);
+                            let mut slot = 0;
+                            let value =
+                                self.get_value_type_pair(result, &record.fields, &mut slot)?;
+                            let cast_to_type = result.types[record.fields[slot] as usize].clone();
+                            let cast_code = CastOpCode::from_u64(record.fields[slot + 1])?;
 
                             let cast = AirValue::Cast(AirCast {
                                 value,
@@ -856,25 +1201,34 @@ impl Parser {
                                 cast_code,
                             });
 
-                            result.value_list.push(cast);
-                            contents.push(AirValueId(result.value_list.len() as u64 - 1));
+                            contents.push(self.push_instruction(result, cast, true));
+                        }
+                        FunctionCodes::INST_BINOP => {
+                            let mut slot = 0;
+                            let lhs =
+                                self.get_value_type_pair(result, &record.fields, &mut slot)?;
+                            let rhs = self.get_value(result, record.fields[slot]);
+                            let opcode = BinaryOpCode::from_u64(record.fields[slot + 1])?;
 
-                            next_value_no += 1;
+                            let bin_op = AirValue::BinOp(AirBinOp { opcode, lhs, rhs });
+
+                            contents.push(self.push_instruction(result, bin_op, true));
                         }
                         FunctionCodes::INST_GEP => {
                             let no_wrap_flags = GEPNoWrapFlags::from_u64(record.fields[0]);
                             let ty = result.types[record.fields[1] as usize].clone();
 
+                            let mut slot = 2;
                             let base_ptr_value =
-                                self.get_value(result, record.fields[2], next_value_no);
+                                self.get_value_type_pair(result, &record.fields, &mut slot)?;
 
                             let mut indices: Vec<AirValueId> = vec![];
-                            for i in 3..record.fields.len() {
-                                indices.push(self.get_value(
+                            while slot < record.fields.len() {
+                                indices.push(self.get_value_type_pair(
                                     result,
-                                    record.fields[i],
-                                    next_value_no,
-                                ));
+                                    &record.fields,
+                                    &mut slot,
+                                )?);
                             }
 
                             let gep = AirValue::GetElementPtr(AirGetElementPtr {
@@ -884,96 +1238,282 @@ impl Parser {
                                 indices,
                             });
 
-                            result.value_list.push(gep);
-                            contents.push(AirValueId(result.value_list.len() as u64 - 1));
-
-                            next_value_no += 1;
+                            contents.push(self.push_instruction(result, gep, true));
                         }
                         FunctionCodes::INST_LOAD => {
-                            let op = self.get_value(result, record.fields[0], next_value_no);
-                            let ty = result.types[record.fields[1] as usize].clone();
+                            let mut slot = 0;
+                            let op = self.get_value_type_pair(result, &record.fields, &mut slot)?;
+                            let ty = result.types[record.fields[slot] as usize].clone();
 
-                            let alignment = match record.fields[2].checked_sub(1) {
+                            let alignment = match record.fields[slot + 1].checked_sub(1) {
                                 Some(result) => 2_u64.pow(result as u32),
                                 None => 0,
                             };
 
-                            let vol = record.fields[3];
+                            let vol = record.fields[slot + 2];
 
-                            result.value_list.push(AirValue::Load(AirLoad {
+                            let load = AirValue::Load(AirLoad {
                                 op,
                                 ty,
                                 alignment,
                                 vol,
-                            }));
-                            contents.push(AirValueId(result.value_list.len() as u64 - 1));
+                            });
 
-                            next_value_no += 1;
+                            contents.push(self.push_instruction(result, load, true));
                         }
                         FunctionCodes::INST_SHUFFLEVEC => {
-                            let vec1 = self.get_value(result, record.fields[0], next_value_no);
-                            let vec2 = self.get_value(result, record.fields[1], next_value_no);
-                            let mask = self.get_value(result, record.fields[2], next_value_no);
-
-                            result.value_list.push(AirValue::ShuffleVec(AirShuffleVec {
-                                vec1,
-                                vec2,
-                                mask,
-                            }));
-                            contents.push(AirValueId(result.value_list.len() as u64 - 1));
+                            let mut slot = 0;
+                            let vec1 =
+                                self.get_value_type_pair(result, &record.fields, &mut slot)?;
+                            let vec2 = self.get_value(result, record.fields[slot]);
+                            slot += 1;
+                            let mask =
+                                self.get_value_type_pair(result, &record.fields, &mut slot)?;
+
+                            let shuffle_vec =
+                                AirValue::ShuffleVec(AirShuffleVec { vec1, vec2, mask });
+
+                            contents.push(self.push_instruction(result, shuffle_vec, true));
+                        }
+                        FunctionCodes::INST_EXTRACTVAL => {
+                            let mut slot = 0;
+                            let aggregate =
+                                self.get_value_type_pair(result, &record.fields, &mut slot)?;
+                            let indices = record.fields[slot..].to_vec();
 
-                            next_value_no += 1;
+                            let extract_val =
+                                AirValue::ExtractVal(AirExtractVal { aggregate, indices });
+
+                            contents.push(self.push_instruction(result, extract_val, true));
                         }
                         FunctionCodes::INST_INSERTVAL => {
-                            let value1 = self.get_value(result, record.fields[0], next_value_no);
-                            let value2 = self.get_value(result, record.fields[1], next_value_no);
-                            let insert_value_idx = record.fields[2];
-
-                            result.value_list.push(AirValue::InsertVal(AirInsertVal {
+                            let mut slot = 0;
+                            let value1 =
+                                self.get_value_type_pair(result, &record.fields, &mut slot)?;
+                            let value2 =
+                                self.get_value_type_pair(result, &record.fields, &mut slot)?;
+                            let insert_value_idx = record.fields[slot];
+
+                            let insert_val = AirValue::InsertVal(AirInsertVal {
                                 value1,
                                 value2,
                                 insert_value_idx,
-                            }));
-                            contents.push(AirValueId(result.value_list.len() as u64 - 1));
+                            });
 
-                            next_value_no += 1;
+                            contents.push(self.push_instruction(result, insert_val, true));
                         }
                         FunctionCodes::INST_RET => {
-                            let value = self.get_value(result, record.fields[0], next_value_no);
+                            let mut slot = 0;
+                            let value = match record.fields.is_empty() {
+                                true => None,
+                                false => Some(self.get_value_type_pair(
+                                    result,
+                                    &record.fields,
+                                    &mut slot,
+                                )?),
+                            };
 
-                            result
-                                .value_list
-                                .push(AirValue::Return(AirReturn { value }));
-                            contents.push(AirValueId(result.value_list.len() as u64 - 1));
+                            let ret = AirValue::Return(AirReturn { value });
 
-                            next_value_no += 1;
+                            contents.push(self.push_instruction(result, ret, false));
                         }
-                        FunctionCodes::INST_INSERTELT => {
-                            let vector = self.get_value(result, record.fields[0], next_value_no);
-                            let value = self.get_value(result, record.fields[1], next_value_no);
-                            let index = self.get_value(result, record.fields[2], next_value_no);
+                        FunctionCodes::INST_BR => {
+                            let true_block = *record
+                                .fields
+                                .first()
+                                .ok_or(anyhow!("Branch without a destination."))?;
+
+                            let branch = match record.fields[1..] {
+                                [] => AirBranch {
+                                    true_block,
+                                    ..Default::default()
+                                },
+                                [false_block, condition] => AirBranch {
+                                    true_block,
+                                    false_block: Some(false_block),
+                                    condition: Some(self.get_value(result, condition)),
+                                },
+                                _ => return Err(anyhow!("Invalid branch {:?}.", record.fields)),
+                            };
+
+                            contents.push(self.push_instruction(
+                                result,
+                                AirValue::Branch(branch),
+                                false,
+                            ));
+                        }
+                        FunctionCodes::INST_PHI => {
+                            let ty = result
+                                .types
+                                .get(record.fields[0] as usize)
+                                .ok_or(anyhow!("Invalid phi type {}.", record.fields[0]))?
+                                .clone();
+
+                            // An odd number of operands ends with fast math flags.
+                            let incoming = record.fields[1..]
+                                .chunks_exact(2)
+                                .map(|x| (self.get_signed_value(result, x[0]), x[1]))
+                                .collect();
+
+                            let phi = AirValue::Phi(AirPhi { ty, incoming });
+
+                            contents.push(self.push_instruction(result, phi, true));
+                        }
+                        FunctionCodes::INST_EXTRACTELT => {
+                            let mut slot = 0;
+                            let vector =
+                                self.get_value_type_pair(result, &record.fields, &mut slot)?;
+                            let index =
+                                self.get_value_type_pair(result, &record.fields, &mut slot)?;
 
-                            result.value_list.push(AirValue::InsertElt(AirInsertElt {
+                            let extract_elt = AirValue::ExtractElt(AirExtractElt { vector, index });
+
+                            contents.push(self.push_instruction(result, extract_elt, true));
+                        }
+                        FunctionCodes::INST_INSERTELT => {
+                            let mut slot = 0;
+                            let vector =
+                                self.get_value_type_pair(result, &record.fields, &mut slot)?;
+                            let value = self.get_value(result, record.fields[slot]);
+                            slot += 1;
+                            let index =
+                                self.get_value_type_pair(result, &record.fields, &mut slot)?;
+
+                            let insert_elt = AirValue::InsertElt(AirInsertElt {
                                 vector,
                                 value,
                                 index,
-                            }));
+                            });
 
-                            contents.push(AirValueId(result.value_list.len() as u64 - 1));
+                            contents.push(self.push_instruction(result, insert_elt, true));
+                        }
+                        FunctionCodes::INST_CALL => {
+                            let calling_convention = record.fields[1];
+                            let mut slot = 2;
+
+                            // Bit 17 marks fast math flags, bit 15 an explicit
+                            // function type.
+                            if (calling_convention >> 17) & 1 != 0 {
+                                slot += 1;
+                            }
+
+                            let explicit_type = match (calling_convention >> 15) & 1 != 0 {
+                                true => {
+                                    slot += 1;
+                                    Some(record.fields[slot - 1])
+                                }
+                                false => None,
+                            };
+
+                            let function =
+                                self.get_value_type_pair(result, &record.fields, &mut slot)?;
+
+                            let function_type = match explicit_type {
+                                Some(ty) => result.types[ty as usize].clone(),
+                                None => match &result.value_list[function.0 as usize] {
+                                    AirValue::Function(signature) => AirType::Function(
+                                        result
+                                            .get_function_signature(*signature)
+                                            .ok_or(anyhow!("Function {:?} not found.", signature))?
+                                            .ty
+                                            .clone(),
+                                    ),
+                                    _ => {
+                                        return Err(anyhow!(
+                                            "Indirect calls need an explicit function type."
+                                        ));
+                                    }
+                                },
+                            };
+                            let function_type = match function_type {
+                                AirType::Function(function_type) => function_type,
+                                _ => return Err(anyhow!("Expected a function type for a call.")),
+                            };
+
+                            let mut arguments = vec![];
+                            for _ in &function_type.param_types {
+                                let field = *record
+                                    .fields
+                                    .get(slot)
+                                    .ok_or(anyhow!("Missing call argument {}.", slot))?;
+                                arguments.push(self.get_value(result, field));
+                                slot += 1;
+                            }
+                            while slot < record.fields.len() {
+                                arguments.push(self.get_value_type_pair(
+                                    result,
+                                    &record.fields,
+                                    &mut slot,
+                                )?);
+                            }
+
+                            let ty = result.types[function_type.return_type.0 as usize].clone();
+                            let has_value_no = ty != AirType::Void;
+
+                            let call = AirValue::Call(AirCall {
+                                function,
+                                arguments,
+                                ty,
+                            });
+
+                            contents.push(self.push_instruction(result, call, has_value_no));
+                        }
+                        FunctionCodes::DEBUG_LOC => {
+                            let location = AirDILocation {
+                                line: record.fields[0],
+                                column: record.fields[1],
+                                scope: Self::get_metadata_ref(record.fields[2])
+                                    .ok_or(anyhow!("Debug location without a scope."))?,
+                                inlined_at: Self::get_metadata_ref(record.fields[3]),
+                                is_implicit_code: record.fields.get(4).is_some_and(|x| *x != 0),
+                            };
+
+                            if let Some(id) = contents.last() {
+                                result.debug_locations.insert(*id, location.clone());
+                            }
+
+                            last_location = Some(location);
+                        }
+                        FunctionCodes::DEBUG_LOC_AGAIN => match (contents.last(), &last_location) {
+                            (Some(id), Some(location)) => {
+                                result.debug_locations.insert(*id, location.clone());
+                            }
+                            _ => return Err(anyhow!("No debug location to repeat.")),
+                        },
+                        code => {
+                            return Err(anyhow!("{:?} instructions aren't supported yet.", code));
                         }
-                        _ => todo!("{:?}", FunctionCodes::from_u64(record.code)),
                     },
                     StreamEntry::SubBlock(sub_block) => {
-                        match BlockID::from_u64(sub_block.block_id) {
+                        match BlockID::from_u64(sub_block.block_id)? {
                             BlockID::CONSTANTS => {
+                                let begin = result.value_list.len() as u64;
                                 self.parse_constants(result)?;
-                                next_value_no = result.value_list.len();
+                                self.value_numbers.extend(
+                                    (begin..result.value_list.len() as u64).map(AirValueId),
+                                );
                             }
                             BlockID::METADATA => self.parse_metadata_block(result)?,
                             BlockID::METADATA_ATTACHMENT => {
-                                self.parse_metadata_attachment(result)?;
+                                let signature = result.function_signatures[id].global_id;
+                                let function = result
+                                    .value_list
+                                    .iter()
+                                    .position(|x| *x == AirValue::Function(signature))
+                                    .map(|x| AirValueId(x as u64))
+                                    .ok_or(anyhow!("Function {:?} has no value.", signature))?;
+
+                                self.parse_metadata_attachment(result, function, &contents)?;
+                            }
+                            BlockID::VALUE_SYMTAB => {
+                                block_names = self.parse_value_symtab(result)?;
+                            }
+                            block => {
+                                return Err(anyhow!(
+                                    "{:?} blocks aren't supported in function bodies.",
+                                    block
+                                ));
                             }
-                            _ => todo!("{:?}", BlockID::from_u64(sub_block.block_id)),
                         }
                     }
                 },
@@ -982,38 +1522,111 @@ impl Parser {
             content = self.bitstream.next();
         }
 
+        if let Some(value_no) = self.forward_references.keys().min() {
+            return Err(anyhow!(
+                "Value number {} is referenced but never defined.",
+                value_no
+            ));
+        }
+
         result.current_function_local_id += 1;
 
         result.function_bodies[function_body_id - 1] = AirFunctionBody {
-            signature: result.function_signatures[function_body_id - 1].global_id,
+            signature,
             contents,
+            block_names,
         };
 
         Ok(())
     }
 
+    /// Global variables record their initializer as a value id plus one,
+    /// swap it for the constant once the module constants are known.
+    pub fn resolve_global_initializers(result: &mut AirModule) {
+        for global_variable in result.global_variables.values_mut() {
+            let Some(value_id) = global_variable.initializer.0.checked_sub(1) else {
+                continue;
+            };
+
+            global_variable.initializer = match result.value_list.get(value_id as usize) {
+                Some(AirValue::Constant(constant)) => *constant,
+                _ => AirConstantId(0),
+            };
+        }
+    }
+
     pub fn parse_module_sub_block(
         &mut self,
         sub_block: Block,
         result: &mut AirModule,
     ) -> Result<()> {
-        match BlockID::from_u64(sub_block.block_id) {
+        match BlockID::from_u64(sub_block.block_id)? {
             BlockID::TYPE_NEW => result.types = self.parse_type_entries()?,
             BlockID::PARAMATTR_GROUP => result.attributes = self.parse_attribute_group()?,
             BlockID::PARAMATTR => result.entry_table = self.parse_entry_table(result)?,
-            BlockID::CONSTANTS => self.parse_constants(result)?,
+            BlockID::CONSTANTS => {
+                self.parse_constants(result)?;
+                Self::resolve_global_initializers(result);
+            }
             BlockID::METADATA_KIND => self.parse_metadata_kind_block(result)?,
             BlockID::METADATA => self.parse_metadata_block(result)?,
             BlockID::OPERAND_BUNDLE_TAGS => self.parse_operand_bundle_tags(result)?,
             BlockID::SYNC_SCOPE_NAMES => self.parse_sync_scope_names(result)?,
-            BlockID::FUNCTION => self.parse_function_body(result, sub_block)?,
-            BlockID::VALUE_SYMTAB => self.parse_value_symtab(result)?,
-            _ => todo!("{:?}", BlockID::from_u64(sub_block.block_id)),
+            BlockID::FUNCTION if self.lazy => {
+                self.parse_module_value_symtab(result)?;
+                self.global_value_count
+                    .get_or_insert(result.value_list.len());
+                self.bitstream.skip_block(&sub_block)?;
+            }
+            BlockID::FUNCTION => {
+                self.parse_module_value_symtab(result)?;
+                self.parse_function_body(result, sub_block)?;
+            }
+            // Already read through `VSTOFFSET`.
+            BlockID::VALUE_SYMTAB if self.module_value_symtab_read => {
+                self.bitstream.skip_block(&sub_block)?;
+            }
+            BlockID::VALUE_SYMTAB => {
+                self.parse_value_symtab(result)?;
+                self.module_value_symtab_read = true;
+            }
+            block => return Err(anyhow!("{:?} blocks aren't supported in modules.", block)),
         }
 
         Ok(())
     }
 
+    /// With a `VSTOFFSET` the module-level table comes after the function
+    /// blocks, it's read before the first of them so that they already have
+    /// their names and offsets.
+    pub fn parse_module_value_symtab(&mut self, result: &mut AirModule) -> Result<()> {
+        if self.module_value_symtab_read || result.vst_offset == 0 {
+            return Ok(());
+        }
+
+        // Same as the function offsets, 32-bit words plus one.
+        let bit_position = self.bitcode_start_bit + (result.vst_offset as usize - 1) * 32;
+
+        let position = self.bitstream.tell_bit();
+        let block = self
+            .bitstream
+            .jump_to_block(bit_position, self.module_abbrev_width)?;
+
+        if BlockID::from_u64(block.block_id)? != BlockID::VALUE_SYMTAB {
+            return Err(anyhow!(
+                "VSTOFFSET {} doesn't point at a value symbol table.",
+                result.vst_offset
+            ));
+        }
+
+        // Only module-level values exist at this point.
+        self.value_numbers.clear();
+        self.parse_value_symtab(result)?;
+        self.module_value_symtab_read = true;
+
+        self.bitstream.seek_bit(position)
+    }
+
     pub fn parse_module(&mut self) -> Result<AirModule> {
         let mut content = self.bitstream.next();
         let mut result = AirModule::default();
@@ -1059,7 +1672,7 @@ impl Parser {
                         return Ok(result);
                     }
                     StreamEntry::Record(record) => {
-                        match IdentificationCode::from_u64(record.code) {
+                        match IdentificationCode::from_u64(record.code)? {
                             IdentificationCode::STRING => {
                                 result.string = Self::parse_string(record.fields);
                             }
@@ -1068,7 +1681,7 @@ impl Parser {
                             }
                         }
                     }
-                    _ => todo!(),
+                    _ => return Err(anyhow!("Unexpected sub-block in the identification block.")),
                 },
                 None => return Ok(result),
             }
@@ -1084,7 +1697,7 @@ impl Parser {
                     let block = self.parse_block(b, current_module)?;
                     Ok(Some(block))
                 }
-                _ => todo!(),
+                _ => Err(anyhow!("Expected a block at the top level.")),
             },
             None => Ok(None),
         }
@@ -1115,4 +1728,125 @@ impl Parser {
 
         Ok(AirFile { items })
     }
+
+    /// Like `start`, but function bodies are skipped and only decoded once
+    /// `LazyAirFile::get_function` asks for them.
+    pub fn start_lazy(mut self) -> Result<LazyAirFile> {
+        self.lazy = true;
+        let file = self.start()?;
+
+        Ok(LazyAirFile { parser: self, file })
+    }
+
+    /// Decodes the body of the function at `index` in `function_signatures`
+    /// from the offset the value symbol table gave it.
+    pub fn parse_function_at(&mut self, result: &mut AirModule, index: usize) -> Result<()> {
+        let offset = match result.function_signatures.get(index) {
+            Some(signature) if signature.body_offset != 0 => signature.body_offset,
+            _ => return Err(anyhow!("Function {} has no body offset.", index)),
+        };
+
+        // The offset is in 32-bit words, plus one.
+        let bit_position = self.bitcode_start_bit + (offset as usize - 1) * 32;
+
+        let position = self.bitstream.tell_bit();
+        let block = self
+            .bitstream
+            .jump_to_block(bit_position, self.module_abbrev_width)?;
+
+        if BlockID::from_u64(block.block_id)? != BlockID::FUNCTION {
+            return Err(anyhow!(
+                "Function {} doesn't point at a function block.",
+                index
+            ));
+        }
+
+        result.current_function_local_id = index as u64;
+        self.parse_function_body(result, block)?;
+
+        self.bitstream.seek_bit(position)
+    }
+}
+
+/// An `AirFile` read with `Parser::start_lazy`. Everything but the function
+/// bodies is there from the start.
+pub struct LazyAirFile {
+    parser: Parser,
+    pub file: AirFile,
+}
+
+impl LazyAirFile {
+    pub fn module(&self) -> Result<&AirModule> {
+        self.file
+            .items
+            .iter()
+            .find_map(|x| match x {
+                AirItem::Module(module) => Some(module),
+                _ => None,
+            })
+            .ok_or(anyhow!("Module not found."))
+    }
+
+    fn module_mut(file: &mut AirFile) -> Result<&mut AirModule> {
+        file.items
+            .iter_mut()
+            .find_map(|x| match x {
+                AirItem::Module(module) => Some(module),
+                _ => None,
+            })
+            .ok_or(anyhow!("Module not found."))
+    }
+
+    /// Decodes the body of `name` the first time it's asked for. Declarations
+    /// and unknown names have none.
+    pub fn get_function(&mut self, name: &str) -> Result<Option<&AirFunctionBody>> {
+        let module = Self::module_mut(&mut self.file)?;
+
+        let Some(index) = module
+            .function_signatures
+            .iter()
+            .position(|x| module.string_table[x.name.0 as usize].content == name)
+        else {
+            return Ok(None);
+        };
+
+        let signature = &module.function_signatures[index];
+        if signature.is_proto {
+            return Ok(None);
+        }
+
+        let global_id = signature.global_id;
+        if Self::find_body(module, global_id).is_none() {
+            self.parser.parse_function_at(module, index)?;
+        }
+
+        Ok(Self::find_body(module, global_id))
+    }
+
+    /// `DECLAREBLOCKS` pads the bodies with empty ones, skip those.
+    fn find_body(
+        module: &AirModule,
+        signature: AirFunctionSignatureId,
+    ) -> Option<&AirFunctionBody> {
+        module
+            .function_bodies
+            .iter()
+            .find(|x| x.signature == signature && !x.contents.is_empty())
+    }
+
+    /// Decodes whatever is left and returns the complete file.
+    pub fn into_air_file(mut self) -> Result<AirFile> {
+        let module = Self::module_mut(&mut self.file)?;
+
+        for index in 0..module.function_signatures.len() {
+            let signature = &module.function_signatures[index];
+            let global_id = signature.global_id;
+
+            if !signature.is_proto && Self::find_body(module, global_id).is_none() {
+                self.parser.parse_function_at(module, index)?;
+            }
+        }
+
+        Ok(self.file)
+    }
 }
@@ -0,0 +1,278 @@
+use anyhow::{Result, anyhow};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Token {
+    Word(String),
+    Number(String),
+    String(String),
+    CString(Vec<u8>),
+    LocalIdent(String),
+    GlobalIdent(String),
+    AttrGroupRef(u64),
+    MetadataRef(u64),
+    MetadataName(String),
+    MetadataString(String),
+    Exclaim,
+    Equal,
+    Comma,
+    Colon,
+    Star,
+    Pipe,
+    Ellipsis,
+    LParen,
+    RParen,
+    LBracket,
+    RBracket,
+    LBrace,
+    RBrace,
+    Less,
+    Greater,
+}
+
+#[derive(Debug, Clone)]
+pub struct SpannedToken {
+    pub token: Token,
+    pub line: usize,
+}
+
+pub struct Lexer<'a> {
+    input: &'a [u8],
+    position: usize,
+    line: usize,
+}
+
+impl<'a> Lexer<'a> {
+    pub fn new(input: &'a str) -> Self {
+        Self {
+            input: input.as_bytes(),
+            position: 0,
+            line: 1,
+        }
+    }
+
+    pub fn tokenize(mut self) -> Result<Vec<SpannedToken>> {
+        let mut result: Vec<SpannedToken> = vec![];
+
+        loop {
+            self.skip_whitespace_and_comments();
+            let line = self.line;
+
+            match self.next_token()? {
+                Some(token) => result.push(SpannedToken { token, line }),
+                None => return Ok(result),
+            }
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.input.get(self.position).copied()
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<u8> {
+        self.input.get(self.position + offset).copied()
+    }
+
+    fn is_identifier_char(c: u8) -> bool {
+        c.is_ascii_alphanumeric() || matches!(c, b'_' | b'.' | b'$' | b'-')
+    }
+
+    fn is_word_char(c: u8) -> bool {
+        c.is_ascii_alphanumeric() || matches!(c, b'_' | b'.' | b'$')
+    }
+
+    fn skip_whitespace_and_comments(&mut self) {
+        while let Some(c) = self.peek() {
+            match c {
+                b'\n' => {
+                    self.line += 1;
+                    self.position += 1;
+                }
+                b';' => {
+                    while let Some(c) = self.peek() {
+                        if c == b'\n' {
+                            break;
+                        }
+                        self.position += 1;
+                    }
+                }
+                c if c.is_ascii_whitespace() => self.position += 1,
+                _ => break,
+            }
+        }
+    }
+
+    fn read_while(&mut self, predicate: fn(u8) -> bool) -> String {
+        let begin = self.position;
+
+        while let Some(c) = self.peek() {
+            if !predicate(c) {
+                break;
+            }
+            self.position += 1;
+        }
+
+        String::from_utf8_lossy(&self.input[begin..self.position]).to_string()
+    }
+
+    fn read_quoted(&mut self) -> Result<Vec<u8>> {
+        // Skip the opening quote.
+        self.position += 1;
+
+        let mut result: Vec<u8> = vec![];
+        loop {
+            match self.peek() {
+                Some(b'"') => {
+                    self.position += 1;
+                    return Ok(result);
+                }
+                Some(b'\\') => {
+                    if self.peek_at(1) == Some(b'\\') {
+                        result.push(b'\\');
+                        self.position += 2;
+                        continue;
+                    }
+
+                    let hex = self
+                        .input
+                        .get(self.position + 1..self.position + 3)
+                        .ok_or(anyhow!("Line {}: Unterminated escape sequence.", self.line))?;
+                    let hex = std::str::from_utf8(hex)?;
+
+                    result.push(u8::from_str_radix(hex, 16).map_err(|_| {
+                        anyhow!("Line {}: Invalid escape sequence `\\{}`.", self.line, hex)
+                    })?);
+                    self.position += 3;
+                }
+                Some(c) => {
+                    if c == b'\n' {
+                        self.line += 1;
+                    }
+                    result.push(c);
+                    self.position += 1;
+                }
+                None => return Err(anyhow!("Line {}: Unterminated string.", self.line)),
+            }
+        }
+    }
+
+    fn read_quoted_string(&mut self) -> Result<String> {
+        Ok(String::from_utf8_lossy(&self.read_quoted()?).to_string())
+    }
+
+    fn read_number(&mut self) -> String {
+        let begin = self.position;
+
+        if self.peek() == Some(b'-') {
+            self.position += 1;
+        }
+
+        let is_hex = self.peek() == Some(b'0') && self.peek_at(1) == Some(b'x');
+
+        while let Some(c) = self.peek() {
+            let is_exponent_sign = !is_hex
+                && matches!(c, b'+' | b'-')
+                && matches!(self.input[self.position - 1], b'e' | b'E');
+
+            if !(c.is_ascii_alphanumeric() || c == b'.' || is_exponent_sign) {
+                break;
+            }
+            self.position += 1;
+        }
+
+        String::from_utf8_lossy(&self.input[begin..self.position]).to_string()
+    }
+
+    fn read_identifier(&mut self) -> Result<String> {
+        match self.peek() {
+            Some(b'"') => self.read_quoted_string(),
+            _ => Ok(self.read_while(Self::is_identifier_char)),
+        }
+    }
+
+    fn next_token(&mut self) -> Result<Option<Token>> {
+        self.skip_whitespace_and_comments();
+
+        let c = match self.peek() {
+            Some(c) => c,
+            None => return Ok(None),
+        };
+
+        let single = match c {
+            b'=' => Some(Token::Equal),
+            b',' => Some(Token::Comma),
+            b':' => Some(Token::Colon),
+            b'*' => Some(Token::Star),
+            b'|' => Some(Token::Pipe),
+            b'(' => Some(Token::LParen),
+            b')' => Some(Token::RParen),
+            b'[' => Some(Token::LBracket),
+            b']' => Some(Token::RBracket),
+            b'{' => Some(Token::LBrace),
+            b'}' => Some(Token::RBrace),
+            b'<' => Some(Token::Less),
+            b'>' => Some(Token::Greater),
+            _ => None,
+        };
+
+        if let Some(token) = single {
+            self.position += 1;
+            return Ok(Some(token));
+        }
+
+        let token = match c {
+            b'.' if self.input[self.position..].starts_with(b"...") => {
+                self.position += 3;
+                Token::Ellipsis
+            }
+            b'"' => Token::String(self.read_quoted_string()?),
+            b'%' => {
+                self.position += 1;
+                Token::LocalIdent(self.read_identifier()?)
+            }
+            b'@' => {
+                self.position += 1;
+                Token::GlobalIdent(self.read_identifier()?)
+            }
+            b'#' => {
+                self.position += 1;
+                let id = self.read_while(|c| c.is_ascii_digit());
+                Token::AttrGroupRef(id.parse().map_err(|_| {
+                    anyhow!("Line {}: Invalid attribute group reference.", self.line)
+                })?)
+            }
+            b'!' => {
+                self.position += 1;
+                match self.peek() {
+                    Some(b'"') => Token::MetadataString(self.read_quoted_string()?),
+                    Some(c) if c.is_ascii_digit() => {
+                        let id = self.read_while(|c| c.is_ascii_digit());
+                        Token::MetadataRef(id.parse()?)
+                    }
+                    Some(c) if Self::is_identifier_char(c) || c == b'\\' => Token::MetadataName(
+                        self.read_while(|c| Self::is_identifier_char(c) || c == b'\\'),
+                    ),
+                    _ => Token::Exclaim,
+                }
+            }
+            b'c' if self.peek_at(1) == Some(b'"') => {
+                self.position += 1;
+                Token::CString(self.read_quoted()?)
+            }
+            c if c.is_ascii_digit()
+                || (c == b'-' && self.peek_at(1).is_some_and(|c| c.is_ascii_digit())) =>
+            {
+                Token::Number(self.read_number())
+            }
+            c if Self::is_word_char(c) => Token::Word(self.read_while(Self::is_word_char)),
+            _ => {
+                return Err(anyhow!(
+                    "Line {}: Unexpected character `{}`.",
+                    self.line,
+                    c as char
+                ));
+            }
+        };
+
+        Ok(Some(token))
+    }
+}
@@ -0,0 +1,1968 @@
+pub mod lexer;
+
+use std::collections::HashMap;
+
+pub use lexer::*;
+
+use anyhow::{Result, anyhow};
+
+use crate::{
+    air_builder::AirBuilder,
+    air_parser::*,
+    llvm_bitcode::{AttributeKindCode, BinaryOpCode, CastOpCode, GEPNoWrapFlags, MetadataCodes},
+};
+
+#[derive(Debug, Default)]
+struct ModuleLayout {
+    headers: Vec<usize>,
+    attribute_groups: Vec<usize>,
+    global_variables: Vec<usize>,
+    functions: Vec<usize>,
+    metadata: Vec<usize>,
+    max_metadata_id: Option<u64>,
+}
+
+#[derive(Debug, Default)]
+struct GlobalProperties {
+    is_declaration: bool,
+    linkage: LinkageCode,
+    preemption_specifier: PreemptionSpecifierCode,
+    visibility: VisibilityCode,
+    dll_storage_class: DllStorageClassCode,
+    thread_local: ThreadLocalCode,
+    unnamed_addr: UnnamedAddrCode,
+    calling_convention: CallingConventionCode,
+}
+
+#[derive(Debug)]
+struct PendingFunctionBody {
+    signature: AirFunctionSignatureId,
+    param_names: Vec<Option<String>>,
+    position: usize,
+}
+
+pub struct Parser {
+    tokens: Vec<SpannedToken>,
+    position: usize,
+    builder: AirBuilder,
+    named_types: HashMap<String, usize>,
+    resolved_named_types: HashMap<String, AirTypeId>,
+    attribute_groups: HashMap<u64, Vec<AirAttrProperties>>,
+    globals: HashMap<String, AirValueId>,
+    locals: HashMap<String, AirValueId>,
+    constant_cache: HashMap<(AirTypeId, String), AirValueId>,
+    metadata_strings: HashMap<String, u64>,
+    next_metadata_id: u64,
+    pending_attachments: Vec<AirMetadataAttachment>,
+}
+
+impl Parser {
+    pub fn new(input: &str) -> Result<Self> {
+        Ok(Self {
+            tokens: Lexer::new(input).tokenize()?,
+            position: 0,
+            builder: AirBuilder::new(),
+            named_types: HashMap::new(),
+            resolved_named_types: HashMap::new(),
+            attribute_groups: HashMap::new(),
+            globals: HashMap::new(),
+            locals: HashMap::new(),
+            constant_cache: HashMap::new(),
+            metadata_strings: HashMap::new(),
+            next_metadata_id: 0,
+            pending_attachments: vec![],
+        })
+    }
+
+    fn module(&mut self) -> Result<&mut AirModule> {
+        self.builder.get_current_module()
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.peek_at(0)
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<&Token> {
+        self.tokens.get(self.position + offset).map(|x| &x.token)
+    }
+
+    fn peek_is_word(&self, word: &str) -> bool {
+        matches!(self.peek(), Some(Token::Word(w)) if w == word)
+    }
+
+    fn line(&self) -> usize {
+        match self.tokens.get(self.position) {
+            Some(token) => token.line,
+            None => self.tokens.last().map(|x| x.line).unwrap_or(0),
+        }
+    }
+
+    fn error(&self, message: String) -> anyhow::Error {
+        anyhow!("Line {}: {}", self.line(), message)
+    }
+
+    fn next(&mut self) -> Result<Token> {
+        match self.tokens.get(self.position) {
+            Some(token) => {
+                self.position += 1;
+                Ok(token.token.clone())
+            }
+            None => Err(self.error("Unexpected end of file.".to_string())),
+        }
+    }
+
+    fn eat(&mut self, token: &Token) -> bool {
+        if self.peek() == Some(token) {
+            self.position += 1;
+            return true;
+        }
+
+        false
+    }
+
+    fn eat_word(&mut self, word: &str) -> bool {
+        if self.peek_is_word(word) {
+            self.position += 1;
+            return true;
+        }
+
+        false
+    }
+
+    fn expect(&mut self, token: Token) -> Result<()> {
+        match self.next()? {
+            t if t == token => Ok(()),
+            t => {
+                self.position -= 1;
+                Err(self.error(format!("Expected {:?}, found {:?}.", token, t)))
+            }
+        }
+    }
+
+    fn expect_word(&mut self) -> Result<String> {
+        match self.next()? {
+            Token::Word(word) => Ok(word),
+            t => {
+                self.position -= 1;
+                Err(self.error(format!("Expected a keyword, found {:?}.", t)))
+            }
+        }
+    }
+
+    fn expect_keyword(&mut self, keyword: &str) -> Result<()> {
+        match self.eat_word(keyword) {
+            true => Ok(()),
+            false => Err(self.error(format!("Expected `{}`, found {:?}.", keyword, self.peek()))),
+        }
+    }
+
+    fn expect_string(&mut self) -> Result<String> {
+        match self.next()? {
+            Token::String(string) => Ok(string),
+            t => {
+                self.position -= 1;
+                Err(self.error(format!("Expected a string, found {:?}.", t)))
+            }
+        }
+    }
+
+    fn expect_integer(&mut self) -> Result<u64> {
+        match self.next()? {
+            Token::Number(number) => Self::parse_integer(&number)
+                .ok_or_else(|| self.error(format!("Invalid integer `{}`.", number))),
+            t => {
+                self.position -= 1;
+                Err(self.error(format!("Expected an integer, found {:?}.", t)))
+            }
+        }
+    }
+
+    /// Skips a bracketed group, the current token must be the one opening it.
+    fn skip_balanced(&mut self) -> Result<()> {
+        let mut depth = 0;
+
+        loop {
+            match self.next()? {
+                Token::LParen | Token::LBracket | Token::LBrace | Token::Less => depth += 1,
+                Token::RParen | Token::RBracket | Token::RBrace | Token::Greater => depth -= 1,
+                _ => {}
+            }
+
+            if depth <= 0 {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Unnamed values get a number instead, those have no symbol.
+    fn is_numbered(name: &str) -> bool {
+        name.bytes().all(|c| c.is_ascii_digit())
+    }
+
+    pub fn parse_integer(text: &str) -> Option<u64> {
+        text.parse::<i128>().ok().map(|x| x as u64)
+    }
+
+    pub fn parse_float(text: &str) -> Option<f32> {
+        match text.strip_prefix("0x") {
+            // Hexadecimal floats are always printed with the bits of a double,
+            // no matter the type they belong to.
+            Some(hex) => u64::from_str_radix(hex, 16)
+                .ok()
+                .map(|bits| f64::from_bits(bits) as f32),
+            None => text.parse::<f32>().ok(),
+        }
+    }
+
+    fn scan_top_level(&mut self) -> ModuleLayout {
+        let mut result = ModuleLayout::default();
+        let mut depth = 0;
+
+        for i in 0..self.tokens.len() {
+            let token = &self.tokens[i].token;
+            let next = self.tokens.get(i + 1).map(|x| &x.token);
+
+            match token {
+                Token::LParen | Token::LBracket | Token::LBrace | Token::Less => depth += 1,
+                Token::RParen | Token::RBracket | Token::RBrace | Token::Greater => depth -= 1,
+                _ => {}
+            }
+
+            if depth != 0 {
+                continue;
+            }
+
+            // Anything else (comdats, module level asm, ...) carries nothing
+            // the AIR model keeps track of.
+            match token {
+                Token::Word(word) => match word.as_str() {
+                    "source_filename" | "target" => result.headers.push(i),
+                    "define" | "declare" => result.functions.push(i),
+                    "attributes" => result.attribute_groups.push(i),
+                    _ => {}
+                },
+                Token::GlobalIdent(_) if next == Some(&Token::Equal) => {
+                    result.global_variables.push(i)
+                }
+                Token::LocalIdent(name) if next == Some(&Token::Equal) => {
+                    if matches!(self.tokens.get(i + 2).map(|x| &x.token), Some(Token::Word(w)) if w == "type")
+                    {
+                        self.named_types.insert(name.clone(), i + 3);
+                    }
+                }
+                Token::MetadataRef(id) if next == Some(&Token::Equal) => {
+                    result.max_metadata_id = Some(result.max_metadata_id.unwrap_or(0).max(*id));
+                    result.metadata.push(i);
+                }
+                Token::MetadataName(_) if next == Some(&Token::Equal) => result.metadata.push(i),
+                _ => {}
+            }
+        }
+
+        result
+    }
+
+    fn parse_header(&mut self) -> Result<()> {
+        match self.expect_word()?.as_str() {
+            "source_filename" => {
+                self.expect(Token::Equal)?;
+                let source_filename = self.expect_string()?;
+                self.module()?.source_filename = source_filename;
+            }
+            "target" => {
+                let kind = self.expect_word()?;
+                self.expect(Token::Equal)?;
+                let value = self.expect_string()?;
+
+                match kind.as_str() {
+                    "datalayout" => self.module()?.data_layout = value,
+                    "triple" => self.module()?.triple = value,
+                    _ => return Err(self.error(format!("Unknown target property `{}`.", kind))),
+                }
+            }
+            _ => unreachable!(),
+        }
+
+        Ok(())
+    }
+
+    fn intern_type(&mut self, ty: AirType) -> Result<AirTypeId> {
+        let module = self.module()?;
+
+        match module.types.iter().position(|x| *x == ty) {
+            Some(id) => Ok(AirTypeId(id as u64)),
+            None => self.builder.new_type(ty),
+        }
+    }
+
+    fn get_type(&mut self, id: AirTypeId) -> Result<AirType> {
+        Ok(self.module()?.types[id.0 as usize].clone())
+    }
+
+    fn opaque_pointer_type(&mut self, address_space: u64) -> Result<AirTypeId> {
+        // Opaque pointers have no pointee, `i8` stands in for it the same way
+        // it did back when every untyped pointer was spelled `i8*`.
+        let pointee = self.intern_type(AirType::Integer(8))?;
+        self.intern_type(AirType::Pointer(address_space, pointee))
+    }
+
+    fn parse_address_space(&mut self) -> Result<u64> {
+        if !self.eat_word("addrspace") {
+            return Ok(0);
+        }
+
+        self.expect(Token::LParen)?;
+        let address_space = self.expect_integer()?;
+        self.expect(Token::RParen)?;
+
+        Ok(address_space)
+    }
+
+    /// Parses `{ ... }` or `<{ ... }>`, returning if the struct is packed
+    /// and its element types.
+    fn parse_struct_elements(&mut self) -> Result<(bool, Vec<AirTypeId>)> {
+        let is_packed = self.eat(&Token::Less);
+        self.expect(Token::LBrace)?;
+
+        let mut elements: Vec<AirTypeId> = vec![];
+        if !self.eat(&Token::RBrace) {
+            loop {
+                elements.push(self.parse_type()?);
+
+                if !self.eat(&Token::Comma) {
+                    break;
+                }
+            }
+
+            self.expect(Token::RBrace)?;
+        }
+
+        if is_packed {
+            self.expect(Token::Greater)?;
+        }
+
+        Ok((is_packed, elements))
+    }
+
+    fn resolve_named_type(&mut self, name: &str) -> Result<AirTypeId> {
+        if let Some(id) = self.resolved_named_types.get(name) {
+            return Ok(*id);
+        }
+
+        let position = match self.named_types.get(name) {
+            Some(position) => *position,
+            None => return Err(self.error(format!("Unknown type `%{}`.", name))),
+        };
+
+        let return_position = self.position;
+        self.position = position;
+
+        let (is_packed, elements) = match self.eat_word("opaque") {
+            true => (false, vec![]),
+            false => self.parse_struct_elements()?,
+        };
+
+        self.position = return_position;
+
+        let id = self.intern_type(AirType::Struct(AirStructType {
+            name: name.to_string(),
+            is_packed,
+            elements,
+        }))?;
+
+        self.resolved_named_types.insert(name.to_string(), id);
+
+        Ok(id)
+    }
+
+    pub fn parse_type(&mut self) -> Result<AirTypeId> {
+        let mut result = match self.next()? {
+            Token::Word(word) => match word.as_str() {
+                "void" => self.intern_type(AirType::Void)?,
+                "float" => self.intern_type(AirType::Float)?,
+                "metadata" => self.intern_type(AirType::Metadata)?,
+                "ptr" => {
+                    let address_space = self.parse_address_space()?;
+                    self.opaque_pointer_type(address_space)?
+                }
+                _ => match word.strip_prefix('i').and_then(|x| x.parse::<u64>().ok()) {
+                    Some(width) => self.intern_type(AirType::Integer(width))?,
+                    None => {
+                        self.position -= 1;
+                        return Err(self.error(format!("Type `{}` is not supported yet.", word)));
+                    }
+                },
+            },
+            Token::LBracket => {
+                let size = self.expect_integer()?;
+                self.expect_keyword("x")?;
+                let element_type = self.parse_type()?;
+                self.expect(Token::RBracket)?;
+
+                self.intern_type(AirType::Array(AirArrayType { size, element_type }))?
+            }
+            Token::Less if self.peek() != Some(&Token::LBrace) => {
+                let size = self.expect_integer()?;
+                self.expect_keyword("x")?;
+                let element_type = self.parse_type()?;
+                self.expect(Token::Greater)?;
+
+                self.intern_type(AirType::Vector(AirVectorType { size, element_type }))?
+            }
+            Token::Less | Token::LBrace => {
+                self.position -= 1;
+                let (is_packed, elements) = self.parse_struct_elements()?;
+
+                self.intern_type(AirType::Struct(AirStructType {
+                    name: String::new(),
+                    is_packed,
+                    elements,
+                }))?
+            }
+            Token::LocalIdent(name) => self.resolve_named_type(&name)?,
+            t => {
+                self.position -= 1;
+                return Err(self.error(format!("Expected a type, found {:?}.", t)));
+            }
+        };
+
+        // Typed pointers, as found in IR from before opaque pointers.
+        loop {
+            if self.eat(&Token::Star) {
+                result = self.intern_type(AirType::Pointer(0, result))?;
+            } else if self.peek_is_word("addrspace") && self.peek_at(4) == Some(&Token::Star) {
+                let address_space = self.parse_address_space()?;
+                self.expect(Token::Star)?;
+                result = self.intern_type(AirType::Pointer(address_space, result))?;
+            } else {
+                return Ok(result);
+            }
+        }
+    }
+
+    fn intern_constant(&mut self, ty: AirTypeId, value: AirConstantValue) -> Result<AirValueId> {
+        // Keyed by the `Debug` form so `-0.0` and `0.0` stay distinct.
+        let key = (ty, format!("{:?}", value));
+
+        if let Some(id) = self.constant_cache.get(&key) {
+            return Ok(*id);
+        }
+
+        self.builder.new_constant(AirConstant { ty, value })?;
+        let id = AirValueId(self.module()?.value_list.len() as u64 - 1);
+
+        self.constant_cache.insert(key, id);
+
+        Ok(id)
+    }
+
+    fn parse_constant_elements(
+        &mut self,
+        close: Token,
+    ) -> Result<Vec<(AirTypeId, AirConstantValue)>> {
+        let mut result = vec![];
+
+        if self.eat(&close) {
+            return Ok(result);
+        }
+
+        loop {
+            let ty = self.parse_type()?;
+            result.push((ty, self.parse_constant(ty)?));
+
+            if !self.eat(&Token::Comma) {
+                break;
+            }
+        }
+
+        self.expect(close)?;
+
+        Ok(result)
+    }
+
+    fn aggregate_constant(
+        &mut self,
+        elements: Vec<(AirTypeId, AirConstantValue)>,
+        is_struct: bool,
+    ) -> Result<AirConstantValue> {
+        // Flat scalar sequences are stored inline like a `CST_CODE_DATA` record,
+        // everything else references its elements through the value list like
+        // a `CST_CODE_AGGREGATE` one.
+        let is_data = !is_struct
+            && !elements.is_empty()
+            && elements.iter().all(|(_, value)| {
+                matches!(
+                    value,
+                    AirConstantValue::Integer(_) | AirConstantValue::Float32(_)
+                )
+            });
+
+        if is_data {
+            return Ok(AirConstantValue::Array(
+                elements.into_iter().map(|(_, value)| value).collect(),
+            ));
+        }
+
+        let mut values: Vec<AirValueId> = vec![];
+        for (ty, value) in elements {
+            values.push(self.intern_constant(ty, value)?);
+        }
+
+        Ok(AirConstantValue::Aggregate(values))
+    }
+
+    pub fn parse_constant(&mut self, ty: AirTypeId) -> Result<AirConstantValue> {
+        let air_type = self.get_type(ty)?;
+
+        match self.next()? {
+            Token::Number(number) => match air_type {
+                AirType::Integer(_) => Self::parse_integer(&number)
+                    .map(AirConstantValue::Integer)
+                    .ok_or_else(|| self.error(format!("Invalid integer `{}`.", number))),
+                AirType::Float => Self::parse_float(&number)
+                    .map(AirConstantValue::Float32)
+                    .ok_or_else(|| self.error(format!("Invalid float `{}`.", number))),
+                _ => Err(self.error(format!("`{}` is not a valid {:?}.", number, air_type))),
+            },
+            Token::Word(word) => match word.as_str() {
+                "true" => Ok(AirConstantValue::Integer(1)),
+                "false" => Ok(AirConstantValue::Integer(0)),
+                "null" | "zeroinitializer" => Ok(AirConstantValue::Null),
+                "undef" => Ok(AirConstantValue::Undefined),
+                "poison" => Ok(AirConstantValue::Poison),
+                "splat" => {
+                    let size = match air_type {
+                        AirType::Vector(vector) => vector.size,
+                        _ => return Err(self.error("`splat` requires a vector type.".to_string())),
+                    };
+
+                    self.expect(Token::LParen)?;
+                    let element_type = self.parse_type()?;
+                    let element = self.parse_constant(element_type)?;
+                    self.expect(Token::RParen)?;
+
+                    self.aggregate_constant(vec![(element_type, element); size as usize], false)
+                }
+                _ => {
+                    self.position -= 1;
+                    Err(self.error(format!("Constant `{}` is not supported yet.", word)))
+                }
+            },
+            Token::Less if self.eat(&Token::LBrace) => {
+                let elements = self.parse_constant_elements(Token::RBrace)?;
+                self.expect(Token::Greater)?;
+                self.aggregate_constant(elements, true)
+            }
+            Token::Less => {
+                let elements = self.parse_constant_elements(Token::Greater)?;
+                self.aggregate_constant(elements, false)
+            }
+            Token::LBracket => {
+                let elements = self.parse_constant_elements(Token::RBracket)?;
+                self.aggregate_constant(elements, false)
+            }
+            Token::LBrace => {
+                let elements = self.parse_constant_elements(Token::RBrace)?;
+                self.aggregate_constant(elements, true)
+            }
+            Token::CString(bytes) => Ok(AirConstantValue::Array(
+                bytes
+                    .iter()
+                    .map(|x| AirConstantValue::Integer(*x as u64))
+                    .collect(),
+            )),
+            Token::GlobalIdent(name) => match self.globals.get(&name) {
+                Some(id) => Ok(AirConstantValue::Pointer(id.0)),
+                None => Err(self.error(format!("Use of undefined value `@{}`.", name))),
+            },
+            t => {
+                self.position -= 1;
+                Err(self.error(format!("Expected a constant, found {:?}.", t)))
+            }
+        }
+    }
+
+    pub fn parse_value(&mut self, ty: AirTypeId) -> Result<AirValueId> {
+        match self.peek().cloned() {
+            Some(Token::LocalIdent(name)) => {
+                let value = self.locals.get(&name).copied();
+                match value {
+                    Some(value) => {
+                        self.position += 1;
+                        Ok(value)
+                    }
+                    None => Err(self.error(format!("Use of undefined value `%{}`.", name))),
+                }
+            }
+            Some(Token::GlobalIdent(name)) => {
+                let value = self.globals.get(&name).copied();
+                match value {
+                    Some(value) => {
+                        self.position += 1;
+                        Ok(value)
+                    }
+                    None => Err(self.error(format!("Use of undefined value `@{}`.", name))),
+                }
+            }
+            _ => {
+                let value = self.parse_constant(ty)?;
+                self.intern_constant(ty, value)
+            }
+        }
+    }
+
+    pub fn parse_typed_value(&mut self) -> Result<(AirTypeId, AirValueId)> {
+        let ty = self.parse_type()?;
+        let value = self.parse_value(ty)?;
+
+        Ok((ty, value))
+    }
+
+    fn parse_memory_effects(&mut self) -> Result<u64> {
+        // Two bits per location (none, read, write, readwrite), ordered as
+        // argument memory, inaccessible memory and everything else.
+        let mut result = 0;
+
+        self.expect(Token::LParen)?;
+
+        loop {
+            let first = self.expect_word()?;
+            let (location, access) = match self.eat(&Token::Colon) {
+                true => (Some(first), self.expect_word()?),
+                false => (None, first),
+            };
+
+            let access = match access.as_str() {
+                "none" => 0,
+                "read" => 1,
+                "write" => 2,
+                "readwrite" => 3,
+                _ => return Err(self.error(format!("Unknown memory access `{}`.", access))),
+            };
+
+            match location.as_deref() {
+                None => result = access * 0b010101,
+                Some(location) => {
+                    let shift = match location {
+                        "argmem" => 0,
+                        "inaccessiblemem" => 2,
+                        _ => 4,
+                    };
+                    result = (result & !(0b11 << shift)) | (access << shift);
+                }
+            }
+
+            if !self.eat(&Token::Comma) {
+                break;
+            }
+        }
+
+        self.expect(Token::RParen)?;
+
+        Ok(result)
+    }
+
+    fn parse_attribute(&mut self, kind: AttributeKindCode) -> Result<AirAttrProperties> {
+        if self.eat(&Token::Equal) {
+            return Ok(AirAttrProperties::WithIntValue(
+                kind,
+                self.expect_integer()?,
+            ));
+        }
+
+        if kind == AttributeKindCode::ALIGNMENT && matches!(self.peek(), Some(Token::Number(_))) {
+            return Ok(AirAttrProperties::WithIntValue(
+                kind,
+                self.expect_integer()?,
+            ));
+        }
+
+        if self.peek() != Some(&Token::LParen) {
+            return Ok(AirAttrProperties::WellKnown(kind));
+        }
+
+        if kind == AttributeKindCode::MEMORY {
+            return Ok(AirAttrProperties::WithIntValue(
+                kind,
+                self.parse_memory_effects()?,
+            ));
+        }
+
+        if matches!(self.peek_at(1), Some(Token::Number(_)))
+            && self.peek_at(2) == Some(&Token::RParen)
+        {
+            self.expect(Token::LParen)?;
+            let value = self.expect_integer()?;
+            self.expect(Token::RParen)?;
+
+            return Ok(AirAttrProperties::WithIntValue(kind, value));
+        }
+
+        // TODO: Keep the arguments of type and list attributes, like `byval(<ty>)`.
+        self.skip_balanced()?;
+
+        Ok(AirAttrProperties::WellKnown(kind))
+    }
+
+    fn parse_string_attribute(&mut self) -> Result<AirAttrProperties> {
+        let key = self.expect_string()?;
+
+        match self.eat(&Token::Equal) {
+            true => Ok(AirAttrProperties::WithStringValue(
+                key,
+                self.expect_string()?,
+            )),
+            false => Ok(AirAttrProperties::StringAttribute(key)),
+        }
+    }
+
+    /// Parses the attributes in front of a parameter or return type.
+    fn parse_parameter_attributes(&mut self) -> Result<Vec<AirAttrProperties>> {
+        let mut result = vec![];
+
+        loop {
+            match self.peek() {
+                Some(Token::Word(word)) => match AttributeKindCode::from_name(word) {
+                    Some(kind) => {
+                        self.position += 1;
+                        result.push(self.parse_attribute(kind)?);
+                    }
+                    None => return Ok(result),
+                },
+                Some(Token::String(_)) => result.push(self.parse_string_attribute()?),
+                _ => return Ok(result),
+            }
+        }
+    }
+
+    fn parse_attribute_group_definition(&mut self) -> Result<()> {
+        self.expect_keyword("attributes")?;
+
+        let id = match self.next()? {
+            Token::AttrGroupRef(id) => id,
+            t => return Err(self.error(format!("Expected an attribute group, found {:?}.", t))),
+        };
+
+        self.expect(Token::Equal)?;
+        self.expect(Token::LBrace)?;
+
+        let mut properties = vec![];
+        while !self.eat(&Token::RBrace) {
+            match self.peek().cloned() {
+                Some(Token::Word(word)) => match AttributeKindCode::from_name(&word) {
+                    Some(kind) => {
+                        self.position += 1;
+                        properties.push(self.parse_attribute(kind)?);
+                    }
+                    None => return Err(self.error(format!("Unknown attribute `{}`.", word))),
+                },
+                Some(Token::String(_)) => properties.push(self.parse_string_attribute()?),
+                t => return Err(self.error(format!("Expected an attribute, found {:?}.", t))),
+            }
+        }
+
+        self.attribute_groups.insert(id, properties);
+
+        Ok(())
+    }
+
+    fn parse_global_properties(&mut self) -> Result<GlobalProperties> {
+        let mut result = GlobalProperties::default();
+
+        loop {
+            let word = match self.peek() {
+                Some(Token::Word(word)) => word.clone(),
+                _ => return Ok(result),
+            };
+
+            match word.as_str() {
+                "private" => result.linkage = LinkageCode::PRIVATE,
+                "internal" => result.linkage = LinkageCode::INTERNAL,
+                "available_externally" => result.linkage = LinkageCode::AVAILABLE_EXTERNALLY,
+                "linkonce" => result.linkage = LinkageCode::LINK_ONCE,
+                "weak" => result.linkage = LinkageCode::WEAK,
+                "common" => result.linkage = LinkageCode::COMMON,
+                "appending" => result.linkage = LinkageCode::APPENDING,
+                "linkonce_odr" => result.linkage = LinkageCode::LINK_ONCE_ODR,
+                "weak_odr" => result.linkage = LinkageCode::WEAK_ODR,
+                "external" => {
+                    result.linkage = LinkageCode::EXTERNAL;
+                    result.is_declaration = true;
+                }
+                "extern_weak" => {
+                    result.linkage = LinkageCode::EXTERN_WEAK;
+                    result.is_declaration = true;
+                }
+                "dso_local" => result.preemption_specifier = PreemptionSpecifierCode::DSO_LOCAL,
+                "dso_preemptable" => {
+                    result.preemption_specifier = PreemptionSpecifierCode::DSO_PREEMPTABLE
+                }
+                "default" => result.visibility = VisibilityCode::DEFAULT,
+                "hidden" => result.visibility = VisibilityCode::HIDDEN,
+                "protected" => result.visibility = VisibilityCode::PROTECTED,
+                "dllimport" => result.dll_storage_class = DllStorageClassCode::DLL_IMPORT,
+                "dllexport" => result.dll_storage_class = DllStorageClassCode::DLL_EXPORT,
+                "thread_local" => {
+                    self.position += 1;
+                    result.thread_local = ThreadLocalCode::THREAD_LOCAL;
+
+                    if self.eat(&Token::LParen) {
+                        result.thread_local = match self.expect_word()?.as_str() {
+                            "localdynamic" => ThreadLocalCode::LOCAL_DYNAMIC,
+                            "initialexec" => ThreadLocalCode::INITIAL_EXEC,
+                            "localexec" => ThreadLocalCode::LOCAL_EXEC,
+                            model => {
+                                return Err(self.error(format!("Unknown TLS model `{}`.", model)));
+                            }
+                        };
+                        self.expect(Token::RParen)?;
+                    }
+                    continue;
+                }
+                "unnamed_addr" => result.unnamed_addr = UnnamedAddrCode::UNNAMED_ADDR,
+                "local_unnamed_addr" => result.unnamed_addr = UnnamedAddrCode::LOCAL_UNNAMED_ADDR,
+                // TODO: Keep the address space of global variables.
+                "addrspace" => {
+                    self.parse_address_space()?;
+                    continue;
+                }
+                "externally_initialized" => {}
+                "ccc" => result.calling_convention = CallingConventionCode::C,
+                "fastcc" => result.calling_convention = CallingConventionCode::FAST,
+                "coldcc" => result.calling_convention = CallingConventionCode::COLD,
+                "cc" => {
+                    self.position += 1;
+                    result.calling_convention =
+                        CallingConventionCode::from_u64(self.expect_integer()?)?;
+                    continue;
+                }
+                _ => return Ok(result),
+            }
+
+            self.position += 1;
+        }
+    }
+
+    fn parse_metadata_attachment(&mut self) -> Result<AirMetadataAttachment> {
+        let name = match self.next()? {
+            Token::MetadataName(name) => name,
+            t => return Err(self.error(format!("Expected a metadata kind, found {:?}.", t))),
+        };
+
+        let metadata_kind_table = &mut self.module()?.metadata_kind_table;
+        let kind = match metadata_kind_table.values().find(|x| x.name == name) {
+            Some(kind) => kind.id,
+            None => {
+                let id = metadata_kind_table.len() as u64;
+                metadata_kind_table.insert(id, AirMetadataKind { id, name });
+                id
+            }
+        };
+
+        let node = self.parse_metadata_operand()?;
+
+        Ok(AirMetadataAttachment { kind, node })
+    }
+
+    /// Locations are kept apart from the rest, just like the bitcode does.
+    fn attach_metadata(
+        &mut self,
+        id: AirValueId,
+        attachments: Vec<AirMetadataAttachment>,
+    ) -> Result<()> {
+        let module = self.module()?;
+
+        for attachment in attachments {
+            match module.get_debug_info(attachment.node) {
+                Some(AirDebugInfo::Location(location)) => {
+                    let location = location.clone();
+                    module.debug_locations.insert(id, location);
+                }
+                _ => module
+                    .metadata_attachments
+                    .entry(id)
+                    .or_default()
+                    .push(attachment),
+            }
+        }
+
+        Ok(())
+    }
+
+    fn parse_global_variable_trailer(&mut self, id: AirGlobalVariableId) -> Result<()> {
+        while self.peek() == Some(&Token::Comma) {
+            self.position += 1;
+
+            if matches!(self.peek(), Some(Token::MetadataName(_))) {
+                let attachment = self.parse_metadata_attachment()?;
+                let value_id = self.global_variable_value(id)?;
+                self.attach_metadata(value_id, vec![attachment])?;
+                continue;
+            }
+
+            match self.expect_word()?.as_str() {
+                "align" => {
+                    let alignment = self.expect_integer()?;
+                    self.module()?
+                        .global_variables
+                        .get_mut(&id)
+                        .unwrap()
+                        .alignment = alignment;
+                }
+                // TODO: Parse section, partition and code model correctly.
+                "section" | "partition" | "code_model" => {
+                    self.expect_string()?;
+                }
+                "comdat" => {
+                    if self.peek() == Some(&Token::LParen) {
+                        self.skip_balanced()?;
+                    }
+                }
+                word => {
+                    self.position -= 1;
+                    return Err(self.error(format!("Unknown global variable property `{}`.", word)));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Declares a global variable, returning the position of its
+    /// initializer if it has one.
+    fn parse_global_variable(&mut self) -> Result<Option<(AirGlobalVariableId, usize)>> {
+        let name = match self.next()? {
+            Token::GlobalIdent(name) => name,
+            _ => unreachable!(),
+        };
+
+        self.expect(Token::Equal)?;
+        let properties = self.parse_global_properties()?;
+
+        let is_const = match self.expect_word()?.as_str() {
+            "constant" => true,
+            "global" => false,
+            word => {
+                self.position -= 1;
+                return Err(self.error(format!("`{}` is not supported yet.", word)));
+            }
+        };
+
+        let ty = self.parse_type()?;
+
+        // The initializer is resolved once every global value has been
+        // declared, constant 0 marks it as missing meanwhile.
+        let id = self
+            .builder
+            .new_global_variable(&name, ty, AirConstantId(0))?;
+
+        let module = self.module()?;
+        let value_id = AirValueId(module.value_list.len() as u64 - 1);
+
+        let global_variable = module.global_variables.get_mut(&id).unwrap();
+        global_variable.is_const = is_const;
+        global_variable.linkage = properties.linkage;
+        global_variable.visibility = properties.visibility;
+        global_variable.thread_local = properties.thread_local;
+        global_variable.unnamed_addr = properties.unnamed_addr;
+        global_variable.dll_storage_class = properties.dll_storage_class;
+        global_variable.preemption_specifier = properties.preemption_specifier;
+
+        self.globals.insert(name, value_id);
+
+        if properties.is_declaration {
+            self.parse_global_variable_trailer(id)?;
+            return Ok(None);
+        }
+
+        Ok(Some((id, self.position)))
+    }
+
+    fn parse_global_initializer(&mut self, id: AirGlobalVariableId) -> Result<()> {
+        let ty = self.module()?.global_variables[&id].type_id;
+        let value = self.parse_value(ty)?;
+
+        let initializer = match self.module()?.value_list[value.0 as usize] {
+            AirValue::Constant(constant) => constant,
+            _ => return Err(self.error("Initializers must be constants.".to_string())),
+        };
+
+        self.module()?
+            .global_variables
+            .get_mut(&id)
+            .unwrap()
+            .initializer = initializer;
+
+        self.parse_global_variable_trailer(id)
+    }
+
+    fn global_variable_value(&mut self, id: AirGlobalVariableId) -> Result<AirValueId> {
+        match self
+            .module()?
+            .value_list
+            .iter()
+            .position(|x| *x == AirValue::GlobalVariable(id))
+        {
+            Some(value) => Ok(AirValueId(value as u64)),
+            None => Err(self.error(format!("Global variable {:?} has no value.", id))),
+        }
+    }
+
+    fn new_attribute_group(
+        &mut self,
+        paramidx: u64,
+        properties: Vec<AirAttrProperties>,
+    ) -> Result<AirAttribute> {
+        let module = self.module()?;
+        let result = AirAttribute {
+            id: module.attributes.len() as u64 + 1,
+            paramidx,
+            properties,
+        };
+
+        module.attributes.insert(result.id, result.clone());
+
+        Ok(result)
+    }
+
+    fn parse_function_signature(&mut self) -> Result<Option<PendingFunctionBody>> {
+        let is_proto = match self.expect_word()?.as_str() {
+            "define" => false,
+            "declare" => true,
+            _ => unreachable!(),
+        };
+
+        let properties = self.parse_global_properties()?;
+        let return_attributes = self.parse_parameter_attributes()?;
+        let return_type = self.parse_type()?;
+
+        let name = match self.next()? {
+            Token::GlobalIdent(name) => name,
+            t => return Err(self.error(format!("Expected a function name, found {:?}.", t))),
+        };
+
+        let mut vararg = 0;
+        let mut param_types: Vec<AirTypeId> = vec![];
+        let mut param_attributes: Vec<Vec<AirAttrProperties>> = vec![];
+        let mut param_names: Vec<Option<String>> = vec![];
+
+        self.expect(Token::LParen)?;
+        if !self.eat(&Token::RParen) {
+            loop {
+                if self.eat(&Token::Ellipsis) {
+                    vararg = 1;
+                } else {
+                    param_types.push(self.parse_type()?);
+                    param_attributes.push(self.parse_parameter_attributes()?);
+                    param_names.push(match self.peek().cloned() {
+                        Some(Token::LocalIdent(name)) => {
+                            self.position += 1;
+                            Some(name)
+                        }
+                        _ => None,
+                    });
+                }
+
+                if !self.eat(&Token::Comma) {
+                    break;
+                }
+            }
+
+            self.expect(Token::RParen)?;
+        }
+
+        let mut unnamed_addr = properties.unnamed_addr;
+        let mut alignment = 0;
+        let mut function_attributes: Vec<AirAttrProperties> = vec![];
+        let mut attachments: Vec<AirMetadataAttachment> = vec![];
+
+        loop {
+            match self.peek().cloned() {
+                Some(Token::Word(word)) => match word.as_str() {
+                    "unnamed_addr" => {
+                        self.position += 1;
+                        unnamed_addr = UnnamedAddrCode::UNNAMED_ADDR;
+                    }
+                    "local_unnamed_addr" => {
+                        self.position += 1;
+                        unnamed_addr = UnnamedAddrCode::LOCAL_UNNAMED_ADDR;
+                    }
+                    "addrspace" => {
+                        self.parse_address_space()?;
+                    }
+                    "align" => {
+                        self.position += 1;
+                        alignment = self.expect_integer()?;
+                    }
+                    // TODO: Parse section, partition and gc correctly.
+                    "section" | "partition" | "gc" => {
+                        self.position += 1;
+                        self.expect_string()?;
+                    }
+                    "comdat" => {
+                        self.position += 1;
+                        if self.peek() == Some(&Token::LParen) {
+                            self.skip_balanced()?;
+                        }
+                    }
+                    _ => match AttributeKindCode::from_name(&word) {
+                        Some(kind) => {
+                            self.position += 1;
+                            function_attributes.push(self.parse_attribute(kind)?);
+                        }
+                        None => break,
+                    },
+                },
+                Some(Token::AttrGroupRef(id)) => {
+                    self.position += 1;
+                    match self.attribute_groups.get(&id) {
+                        Some(group) => function_attributes.extend(group.iter().cloned()),
+                        None => {
+                            return Err(self.error(format!("Unknown attribute group `#{}`.", id)));
+                        }
+                    }
+                }
+                Some(Token::String(_)) => function_attributes.push(self.parse_string_attribute()?),
+                Some(Token::MetadataName(_)) if self.peek_at(1) != Some(&Token::Equal) => {
+                    attachments.push(self.parse_metadata_attachment()?);
+                }
+                _ => break,
+            }
+        }
+
+        let ty = AirFunctionType {
+            vararg,
+            return_type,
+            param_types: param_types.clone(),
+            param_values: vec![],
+        };
+
+        self.intern_type(AirType::Function(ty.clone()))?;
+
+        let mut groups: Vec<AirAttribute> = vec![];
+        if !function_attributes.is_empty() {
+            groups.push(self.new_attribute_group(u32::MAX as u64, function_attributes)?);
+        }
+        if !return_attributes.is_empty() {
+            groups.push(self.new_attribute_group(0, return_attributes)?);
+        }
+        for (i, properties) in param_attributes.into_iter().enumerate() {
+            if !properties.is_empty() {
+                groups.push(self.new_attribute_group(i as u64 + 1, properties)?);
+            }
+        }
+
+        let attr_entry = match groups.is_empty() {
+            true => None,
+            false => {
+                let entry = AirAttrEntry { groups };
+                let module = self.module()?;
+                let id = module.entry_table.len() as u64 + 1;
+                module.entry_table.insert(id, entry.clone());
+                Some(entry)
+            }
+        };
+
+        let signature = self.builder.new_function_signature(&name, ty)?;
+
+        let module = self.module()?;
+        let value_id = AirValueId(module.value_list.len() as u64 - 1);
+
+        let function_signature = module.function_signatures.last_mut().unwrap();
+        function_signature.calling_convention = properties.calling_convention;
+        function_signature.is_proto = is_proto;
+        function_signature.linkage = properties.linkage;
+        function_signature.attr_entry = attr_entry;
+        function_signature.alignment = alignment;
+        function_signature.visibility = properties.visibility;
+        function_signature.unnamed_addr = unnamed_addr;
+        function_signature.preemption_specifier = properties.preemption_specifier;
+
+        self.globals.insert(name, value_id);
+        self.attach_metadata(value_id, attachments)?;
+
+        match is_proto {
+            true => Ok(None),
+            false => Ok(Some(PendingFunctionBody {
+                signature,
+                param_names,
+                position: self.position,
+            })),
+        }
+    }
+
+    fn new_metadata(&mut self, constant: AirMetadataConstant) -> Result<u64> {
+        let id = self.next_metadata_id;
+        self.next_metadata_id += 1;
+
+        self.module()?.metadata_constants.insert(id, constant);
+
+        Ok(id)
+    }
+
+    fn intern_metadata_string(&mut self, string: String) -> Result<u64> {
+        if let Some(id) = self.metadata_strings.get(&string) {
+            return Ok(*id);
+        }
+
+        let id = self.new_metadata(AirMetadataConstant::String(string.clone()))?;
+        self.metadata_strings.insert(string, id);
+
+        Ok(id)
+    }
+
+    /// Parses the `key: value` list of a specialized node, metadata
+    /// operands are stored one above their id like the bitcode does.
+    fn parse_debug_info_fields(&mut self) -> Result<HashMap<String, u64>> {
+        let mut result: HashMap<String, u64> = HashMap::new();
+
+        self.expect(Token::LParen)?;
+        if self.eat(&Token::RParen) {
+            return Ok(result);
+        }
+
+        loop {
+            let key = self.expect_word()?;
+            self.expect(Token::Colon)?;
+
+            let value = match self.peek().cloned() {
+                Some(Token::Number(_)) => self.expect_integer()?,
+                Some(Token::String(string)) => {
+                    self.position += 1;
+                    self.intern_metadata_string(string)? + 1
+                }
+                Some(Token::Word(word)) if word == "true" || word == "false" => {
+                    self.position += 1;
+                    (word == "true") as u64
+                }
+                Some(Token::Word(word)) if word == "null" => {
+                    self.position += 1;
+                    0
+                }
+                Some(Token::Word(_)) => {
+                    let mut flags = 0;
+
+                    loop {
+                        let name = self.expect_word()?;
+                        flags |= match debug_info_constant(&name) {
+                            Some(value) => value,
+                            None => {
+                                self.position -= 1;
+                                return Err(self.error(format!(
+                                    "Debug info constant `{}` is not supported yet.",
+                                    name
+                                )));
+                            }
+                        };
+
+                        if !self.eat(&Token::Pipe) {
+                            break;
+                        }
+                    }
+
+                    flags
+                }
+                _ => self.parse_nullable_metadata_operand()?.map_or(0, |x| x + 1),
+            };
+
+            result.insert(key, value);
+
+            if !self.eat(&Token::Comma) {
+                break;
+            }
+        }
+
+        self.expect(Token::RParen)?;
+
+        Ok(result)
+    }
+
+    fn parse_specialized_node(&mut self, name: &str) -> Result<AirMetadataConstant> {
+        let fields = self.parse_debug_info_fields()?;
+        let field = |key: &str| fields.get(key).copied().unwrap_or(0);
+
+        // Laid out the same way as their bitcode records.
+        let (code, record) = match name {
+            "DILocation" => (
+                MetadataCodes::LOCATION,
+                vec![
+                    0,
+                    field("line"),
+                    field("column"),
+                    field("scope").saturating_sub(1),
+                    field("inlinedAt"),
+                    field("isImplicitCode"),
+                ],
+            ),
+            "DIFile" => (
+                MetadataCodes::FILE,
+                vec![0, field("filename"), field("directory")],
+            ),
+            "DICompileUnit" => (
+                MetadataCodes::COMPILE_UNIT,
+                vec![
+                    1,
+                    field("language"),
+                    field("file"),
+                    field("producer"),
+                    field("isOptimized"),
+                    field("flags"),
+                    field("runtimeVersion"),
+                    field("splitDebugFilename"),
+                    field("emissionKind"),
+                ],
+            ),
+            "DISubprogram" => (
+                MetadataCodes::SUBPROGRAM,
+                vec![
+                    0b100,
+                    field("scope"),
+                    field("name"),
+                    field("linkageName"),
+                    field("file"),
+                    field("line"),
+                    field("type"),
+                    field("scopeLine"),
+                    field("containingType"),
+                    field("spFlags"),
+                    field("virtualIndex"),
+                    field("flags"),
+                    field("unit"),
+                ],
+            ),
+            "DISubroutineType" => (
+                MetadataCodes::SUBROUTINE_TYPE,
+                vec![0, field("flags"), field("types"), field("cc")],
+            ),
+            "DILexicalBlock" => (
+                MetadataCodes::LEXICAL_BLOCK,
+                vec![
+                    0,
+                    field("scope"),
+                    field("file"),
+                    field("line"),
+                    field("column"),
+                ],
+            ),
+            "DILexicalBlockFile" => (
+                MetadataCodes::LEXICAL_BLOCK_FILE,
+                vec![0, field("scope"), field("file"), field("discriminator")],
+            ),
+            // TODO: Parse the rest of the specialized nodes (`!DIBasicType(...)`, ...).
+            _ => return Ok(AirMetadataConstant::None),
+        };
+
+        Ok(AirMetadataConstant::DebugInfo(
+            crate::air_parser::Parser::parse_debug_info(code as u64, &record)?,
+        ))
+    }
+
+    fn parse_metadata_operands(&mut self) -> Result<Vec<Option<u64>>> {
+        let mut result: Vec<Option<u64>> = vec![];
+
+        self.expect(Token::LBrace)?;
+        if self.eat(&Token::RBrace) {
+            return Ok(result);
+        }
+
+        loop {
+            result.push(self.parse_nullable_metadata_operand()?);
+
+            if !self.eat(&Token::Comma) {
+                break;
+            }
+        }
+
+        self.expect(Token::RBrace)?;
+
+        Ok(result)
+    }
+
+    /// Node operands can be `null`, same as in bitcode.
+    fn parse_nullable_metadata_operand(&mut self) -> Result<Option<u64>> {
+        match self.peek() {
+            Some(Token::Word(word)) if word == "null" => {
+                self.position += 1;
+                Ok(None)
+            }
+            _ => Ok(Some(self.parse_metadata_operand()?)),
+        }
+    }
+
+    fn parse_metadata_operand(&mut self) -> Result<u64> {
+        match self.peek().cloned() {
+            Some(Token::MetadataRef(id)) => {
+                self.position += 1;
+                Ok(id)
+            }
+            Some(Token::MetadataString(string)) => {
+                self.position += 1;
+                self.intern_metadata_string(string)
+            }
+            Some(Token::Word(word)) if word == "null" => {
+                self.position += 1;
+                self.new_metadata(AirMetadataConstant::None)
+            }
+            Some(Token::Exclaim) => {
+                self.position += 1;
+                let operands = self.parse_metadata_operands()?;
+                self.new_metadata(AirMetadataConstant::Node(operands))
+            }
+            Some(Token::MetadataName(name)) => {
+                self.position += 1;
+                let constant = self.parse_specialized_node(&name)?;
+                self.new_metadata(constant)
+            }
+            _ => {
+                let (_, value) = self.parse_typed_value()?;
+                let value = self.module()?.value_list[value.0 as usize].clone();
+
+                self.new_metadata(AirMetadataConstant::Value(value))
+            }
+        }
+    }
+
+    fn parse_metadata_definition(&mut self) -> Result<()> {
+        match self.next()? {
+            Token::MetadataRef(id) => {
+                self.expect(Token::Equal)?;
+                self.eat_word("distinct");
+
+                let constant = match self.next()? {
+                    Token::Exclaim => AirMetadataConstant::Node(self.parse_metadata_operands()?),
+                    Token::MetadataName(name) => self.parse_specialized_node(&name)?,
+                    t => {
+                        return Err(self.error(format!("Expected a metadata node, found {:?}.", t)));
+                    }
+                };
+
+                self.module()?.metadata_constants.insert(id, constant);
+            }
+            Token::MetadataName(name) => {
+                self.expect(Token::Equal)?;
+                self.expect(Token::Exclaim)?;
+                self.expect(Token::LBrace)?;
+
+                let mut operands: Vec<u64> = vec![];
+                if !self.eat(&Token::RBrace) {
+                    loop {
+                        match self.next()? {
+                            Token::MetadataRef(id) => operands.push(id),
+                            t => {
+                                return Err(self.error(format!(
+                                    "Named metadata can only reference nodes, found {:?}.",
+                                    t
+                                )));
+                            }
+                        }
+
+                        if !self.eat(&Token::Comma) {
+                            break;
+                        }
+                    }
+
+                    self.expect(Token::RBrace)?;
+                }
+
+                self.module()?
+                    .metadata_named_nodes
+                    .push(AirMetadataNamedNode { name, operands });
+            }
+            _ => unreachable!(),
+        }
+
+        Ok(())
+    }
+
+    fn parse_alignment(&mut self) -> Result<u64> {
+        if self.peek() == Some(&Token::Comma)
+            && matches!(self.peek_at(1), Some(Token::Word(w)) if w == "align")
+        {
+            self.position += 2;
+            return self.expect_integer();
+        }
+
+        Ok(0)
+    }
+
+    fn skip_flags(&mut self, flags: &[&str]) {
+        while flags.iter().any(|flag| self.peek_is_word(flag)) {
+            self.position += 1;
+        }
+    }
+
+    fn parse_cast(&mut self, cast_code: CastOpCode) -> Result<AirValue> {
+        self.skip_flags(&["nneg", "nuw", "nsw"]);
+
+        let (_, value) = self.parse_typed_value()?;
+        self.expect_keyword("to")?;
+        let cast_to_type = self.parse_type()?;
+
+        Ok(AirValue::Cast(AirCast {
+            value,
+            cast_to_type: self.get_type(cast_to_type)?,
+            cast_code,
+        }))
+    }
+
+    fn parse_bin_op(&mut self, opcode: BinaryOpCode) -> Result<AirValue> {
+        self.skip_flags(&[
+            "nuw", "nsw", "exact", "disjoint", "fast", "nnan", "ninf", "nsz", "arcp", "contract",
+            "afn", "reassoc",
+        ]);
+
+        let (ty, lhs) = self.parse_typed_value()?;
+        self.expect(Token::Comma)?;
+        let rhs = self.parse_value(ty)?;
+
+        Ok(AirValue::BinOp(AirBinOp { opcode, lhs, rhs }))
+    }
+
+    fn parse_get_element_ptr(&mut self) -> Result<AirValue> {
+        let mut no_wrap_flags = GEPNoWrapFlags::default();
+
+        loop {
+            if self.eat_word("inbounds") {
+                no_wrap_flags |= GEPNoWrapFlags::InBoundsFlag;
+            } else if self.eat_word("nusw") {
+                no_wrap_flags |= GEPNoWrapFlags::NUSWFlag;
+            } else if self.eat_word("nuw") {
+                no_wrap_flags |= GEPNoWrapFlags::NUWFlag;
+            } else if self.eat_word("inrange") {
+                self.skip_balanced()?;
+            } else {
+                break;
+            }
+        }
+
+        let ty = self.parse_type()?;
+        self.expect(Token::Comma)?;
+        let (_, base_ptr_value) = self.parse_typed_value()?;
+
+        let mut indices: Vec<AirValueId> = vec![];
+        while self.peek() == Some(&Token::Comma)
+            && !matches!(self.peek_at(1), Some(Token::MetadataName(_)))
+        {
+            self.position += 1;
+            self.eat_word("inrange");
+
+            let (_, index) = self.parse_typed_value()?;
+            indices.push(index);
+        }
+
+        Ok(AirValue::GetElementPtr(AirGetElementPtr {
+            no_wrap_flags,
+            ty: self.get_type(ty)?,
+            base_ptr_value,
+            indices,
+        }))
+    }
+
+    fn parse_load(&mut self) -> Result<AirValue> {
+        if self.peek_is_word("atomic") {
+            return Err(self.error("Atomic loads are not supported yet.".to_string()));
+        }
+
+        let vol = self.eat_word("volatile") as u64;
+
+        let ty = self.parse_type()?;
+        self.expect(Token::Comma)?;
+        let (_, op) = self.parse_typed_value()?;
+        let alignment = self.parse_alignment()?;
+
+        Ok(AirValue::Load(AirLoad {
+            op,
+            ty: self.get_type(ty)?,
+            alignment,
+            vol,
+        }))
+    }
+
+    fn parse_shuffle_vec(&mut self) -> Result<AirValue> {
+        let (_, vec1) = self.parse_typed_value()?;
+        self.expect(Token::Comma)?;
+        let (_, vec2) = self.parse_typed_value()?;
+        self.expect(Token::Comma)?;
+        let (_, mask) = self.parse_typed_value()?;
+
+        Ok(AirValue::ShuffleVec(AirShuffleVec { vec1, vec2, mask }))
+    }
+
+    fn parse_extract_val(&mut self) -> Result<AirValue> {
+        let (_, aggregate) = self.parse_typed_value()?;
+
+        let mut indices = vec![];
+        while self.peek() == Some(&Token::Comma)
+            && matches!(self.peek_at(1), Some(Token::Number(_)))
+        {
+            self.position += 1;
+            indices.push(self.expect_integer()?);
+        }
+
+        Ok(AirValue::ExtractVal(AirExtractVal { aggregate, indices }))
+    }
+
+    fn parse_insert_val(&mut self) -> Result<AirValue> {
+        let (_, value1) = self.parse_typed_value()?;
+        self.expect(Token::Comma)?;
+        let (_, value2) = self.parse_typed_value()?;
+        self.expect(Token::Comma)?;
+        let insert_value_idx = self.expect_integer()?;
+
+        if self.peek() == Some(&Token::Comma) && matches!(self.peek_at(1), Some(Token::Number(_))) {
+            return Err(
+                self.error("Nested `insertvalue` indices are not supported yet.".to_string())
+            );
+        }
+
+        Ok(AirValue::InsertVal(AirInsertVal {
+            value1,
+            value2,
+            insert_value_idx,
+        }))
+    }
+
+    fn parse_extract_elt(&mut self) -> Result<AirValue> {
+        let (_, vector) = self.parse_typed_value()?;
+        self.expect(Token::Comma)?;
+        let (_, index) = self.parse_typed_value()?;
+
+        Ok(AirValue::ExtractElt(AirExtractElt { vector, index }))
+    }
+
+    fn parse_insert_elt(&mut self) -> Result<AirValue> {
+        let (_, vector) = self.parse_typed_value()?;
+        self.expect(Token::Comma)?;
+        let (_, value) = self.parse_typed_value()?;
+        self.expect(Token::Comma)?;
+        let (_, index) = self.parse_typed_value()?;
+
+        Ok(AirValue::InsertElt(AirInsertElt {
+            vector,
+            value,
+            index,
+        }))
+    }
+
+    fn parse_call(&mut self) -> Result<AirValue> {
+        self.skip_flags(&[
+            "fast", "nnan", "ninf", "nsz", "arcp", "contract", "afn", "reassoc",
+        ]);
+        self.skip_flags(&["ccc", "fastcc", "coldcc"]);
+        self.parse_parameter_attributes()?;
+
+        let ty = self.parse_type()?;
+        let callee_ty = self.opaque_pointer_type(0)?;
+        let function = self.parse_value(callee_ty)?;
+
+        let mut arguments = vec![];
+        self.expect(Token::LParen)?;
+        if !self.eat(&Token::RParen) {
+            loop {
+                let ty = self.parse_type()?;
+                self.parse_parameter_attributes()?;
+                arguments.push(self.parse_value(ty)?);
+
+                if !self.eat(&Token::Comma) {
+                    break;
+                }
+            }
+            self.expect(Token::RParen)?;
+        }
+
+        while matches!(self.peek(), Some(Token::AttrGroupRef(_))) {
+            self.position += 1;
+        }
+
+        Ok(AirValue::Call(AirCall {
+            function,
+            arguments,
+            ty: self.get_type(ty)?,
+        }))
+    }
+
+    fn parse_return(&mut self) -> Result<AirValue> {
+        if self.eat_word("void") {
+            return Ok(AirValue::Return(AirReturn { value: None }));
+        }
+
+        let (_, value) = self.parse_typed_value()?;
+
+        Ok(AirValue::Return(AirReturn { value: Some(value) }))
+    }
+
+    pub fn parse_instruction(&mut self) -> Result<AirValue> {
+        let opcode = self.expect_word()?;
+
+        let result = match opcode.as_str() {
+            "trunc" => self.parse_cast(CastOpCode::TRUNC)?,
+            "zext" => self.parse_cast(CastOpCode::ZEXT)?,
+            "sext" => self.parse_cast(CastOpCode::SEXT)?,
+            "fptoui" => self.parse_cast(CastOpCode::FPTOUI)?,
+            "fptosi" => self.parse_cast(CastOpCode::FPTOSI)?,
+            "uitofp" => self.parse_cast(CastOpCode::UITOFP)?,
+            "sitofp" => self.parse_cast(CastOpCode::SITOFP)?,
+            "fptrunc" => self.parse_cast(CastOpCode::FPTRUNC)?,
+            "fpext" => self.parse_cast(CastOpCode::FPEXT)?,
+            "ptrtoint" => self.parse_cast(CastOpCode::PTRTOINT)?,
+            "inttoptr" => self.parse_cast(CastOpCode::INTTOPTR)?,
+            "bitcast" => self.parse_cast(CastOpCode::BITCAST)?,
+            "addrspacecast" => self.parse_cast(CastOpCode::ADDRSPACECAST)?,
+            "add" | "fadd" => self.parse_bin_op(BinaryOpCode::ADD)?,
+            "sub" | "fsub" => self.parse_bin_op(BinaryOpCode::SUB)?,
+            "mul" | "fmul" => self.parse_bin_op(BinaryOpCode::MUL)?,
+            "udiv" => self.parse_bin_op(BinaryOpCode::UDIV)?,
+            "sdiv" | "fdiv" => self.parse_bin_op(BinaryOpCode::SDIV)?,
+            "urem" => self.parse_bin_op(BinaryOpCode::UREM)?,
+            "srem" | "frem" => self.parse_bin_op(BinaryOpCode::SREM)?,
+            "shl" => self.parse_bin_op(BinaryOpCode::SHL)?,
+            "lshr" => self.parse_bin_op(BinaryOpCode::LSHR)?,
+            "ashr" => self.parse_bin_op(BinaryOpCode::ASHR)?,
+            "and" => self.parse_bin_op(BinaryOpCode::AND)?,
+            "or" => self.parse_bin_op(BinaryOpCode::OR)?,
+            "xor" => self.parse_bin_op(BinaryOpCode::XOR)?,
+            "getelementptr" => self.parse_get_element_ptr()?,
+            "load" => self.parse_load()?,
+            "shufflevector" => self.parse_shuffle_vec()?,
+            "extractvalue" => self.parse_extract_val()?,
+            "insertvalue" => self.parse_insert_val()?,
+            "extractelement" => self.parse_extract_elt()?,
+            "insertelement" => self.parse_insert_elt()?,
+            "call" => self.parse_call()?,
+            "tail" | "musttail" | "notail" => {
+                self.expect_keyword("call")?;
+                self.parse_call()?
+            }
+            "ret" => self.parse_return()?,
+            _ => {
+                self.position -= 1;
+                return Err(self.error(format!("Instruction `{}` is not supported yet.", opcode)));
+            }
+        };
+
+        while self.peek() == Some(&Token::Comma)
+            && matches!(self.peek_at(1), Some(Token::MetadataName(_)))
+        {
+            self.position += 1;
+
+            let attachment = self.parse_metadata_attachment()?;
+            self.pending_attachments.push(attachment);
+        }
+
+        Ok(result)
+    }
+
+    fn parse_function_body(&mut self, pending: PendingFunctionBody) -> Result<()> {
+        self.position = pending.position;
+        self.locals.clear();
+
+        let module = self.builder.get_current_module()?;
+        let signature = module
+            .function_signatures
+            .iter_mut()
+            .find(|x| x.global_id == pending.signature)
+            .unwrap();
+
+        for (i, ty) in signature.ty.param_types.iter().enumerate() {
+            module.value_list.push(AirValue::Argument(AirLocal {
+                id: i as u64,
+                type_id: *ty,
+                value: None,
+            }));
+
+            let value_id = AirValueId(module.value_list.len() as u64 - 1);
+            signature.ty.param_values.push(value_id);
+
+            if let Some(name) = &pending.param_names[i] {
+                if !Self::is_numbered(name) {
+                    module.value_names.insert(value_id, name.clone());
+                }
+                self.locals.insert(name.clone(), value_id);
+            }
+        }
+
+        self.expect(Token::LBrace)?;
+
+        let mut contents: Vec<AirValueId> = vec![];
+        let mut block_names: HashMap<u64, String> = HashMap::new();
+        let mut block_id = 0;
+        loop {
+            match (self.peek(), self.peek_at(1)) {
+                (Some(Token::RBrace), _) => {
+                    self.position += 1;
+                    break;
+                }
+                // TODO: Keep track of basic blocks once branches are supported.
+                (Some(Token::Word(label) | Token::Number(label)), Some(Token::Colon)) => {
+                    // A label in front of the first instruction names the entry block.
+                    if !contents.is_empty() {
+                        block_id += 1;
+                    }
+
+                    if !Self::is_numbered(label) {
+                        block_names.insert(block_id, label.clone());
+                    }
+
+                    self.position += 2;
+                }
+                (Some(Token::LocalIdent(name)), Some(Token::Equal)) => {
+                    let name = name.clone();
+                    self.position += 2;
+
+                    let value = self.parse_instruction()?;
+                    let module = self.module()?;
+                    module.value_list.push(value);
+
+                    let value_id = AirValueId(module.value_list.len() as u64 - 1);
+                    contents.push(value_id);
+
+                    if !Self::is_numbered(&name) {
+                        module.value_names.insert(value_id, name.clone());
+                    }
+                    self.locals.insert(name, value_id);
+                }
+                (Some(_), _) => {
+                    let value = self.parse_instruction()?;
+                    let module = self.module()?;
+                    module.value_list.push(value);
+
+                    contents.push(AirValueId(module.value_list.len() as u64 - 1));
+                }
+                (None, _) => return Err(self.error("Unterminated function body.".to_string())),
+            }
+
+            if let Some(id) = contents.last() {
+                let attachments = std::mem::take(&mut self.pending_attachments);
+                self.attach_metadata(*id, attachments)?;
+            }
+        }
+
+        self.module()?.function_bodies.push(AirFunctionBody {
+            signature: pending.signature,
+            contents,
+            block_names,
+        });
+
+        Ok(())
+    }
+
+    pub fn start(&mut self) -> Result<AirFile> {
+        let layout = self.scan_top_level();
+
+        self.builder.begin_module("", 2, &[], "")?;
+
+        // Constant 0 means "no initializer" for global variables, the same
+        // way the bitcode encodes it.
+        self.module()?.max_constants_id = 1;
+
+        // Numbered nodes keep the id they have in the source, inline operands
+        // are numbered after them.
+        self.next_metadata_id = layout.max_metadata_id.map(|x| x + 1).unwrap_or(0);
+
+        for i in layout.headers {
+            self.position = i;
+            self.parse_header()?;
+        }
+
+        for i in layout.attribute_groups {
+            self.position = i;
+            self.parse_attribute_group_definition()?;
+        }
+
+        // Global values come first in the value list, so every one of them is
+        // declared before parsing anything that may create a constant.
+        let mut initializers = vec![];
+        for i in layout.global_variables {
+            self.position = i;
+            if let Some(initializer) = self.parse_global_variable()? {
+                initializers.push(initializer);
+            }
+        }
+
+        let mut bodies = vec![];
+        for i in layout.functions {
+            self.position = i;
+            if let Some(body) = self.parse_function_signature()? {
+                bodies.push(body);
+            }
+        }
+
+        for (id, position) in initializers {
+            self.position = position;
+            self.parse_global_initializer(id)?;
+        }
+
+        for i in layout.metadata {
+            self.position = i;
+            self.parse_metadata_definition()?;
+        }
+
+        for body in bodies {
+            self.parse_function_body(body)?;
+        }
+
+        Ok(std::mem::take(&mut self.builder.file))
+    }
+}
+
+/// Named constants used by specialized nodes, in the textual IR.
+fn debug_info_constant(name: &str) -> Option<u64> {
+    Some(match name {
+        "DW_LANG_C89" => 0x1,
+        "DW_LANG_C" => 0x2,
+        "DW_LANG_C_plus_plus" => 0x4,
+        "DW_LANG_C99" => 0xc,
+        "DW_LANG_ObjC" => 0x10,
+        "DW_LANG_ObjC_plus_plus" => 0x11,
+        "DW_LANG_C_plus_plus_03" => 0x19,
+        "DW_LANG_C_plus_plus_11" => 0x1a,
+        "DW_LANG_C11" => 0x1d,
+        "DW_LANG_C_plus_plus_14" => 0x21,
+        "DW_LANG_C_plus_plus_17" => 0x2a,
+        "DW_LANG_C_plus_plus_20" => 0x2b,
+        "DW_LANG_C17" => 0x2c,
+        "DW_CC_normal" => 0x1,
+        "DW_CC_program" => 0x2,
+        "DW_CC_nocall" => 0x3,
+        "NoDebug" => 0,
+        "FullDebug" => 1,
+        "LineTablesOnly" => 2,
+        "DebugDirectivesOnly" => 3,
+        "Default" => 0,
+        "GNU" => 1,
+        "None" => 2,
+        "Apple" => 3,
+        "DIFlagZero" => 0,
+        "DIFlagPrivate" => 1,
+        "DIFlagProtected" => 2,
+        "DIFlagPublic" => 3,
+        "DIFlagFwdDecl" => 1 << 2,
+        "DIFlagAppleBlock" => 1 << 3,
+        "DIFlagVirtual" => 1 << 5,
+        "DIFlagArtificial" => 1 << 6,
+        "DIFlagExplicit" => 1 << 7,
+        "DIFlagPrototyped" => 1 << 8,
+        "DIFlagObjcClassComplete" => 1 << 9,
+        "DIFlagObjectPointer" => 1 << 10,
+        "DIFlagVector" => 1 << 11,
+        "DIFlagStaticMember" => 1 << 12,
+        "DIFlagLValueReference" => 1 << 13,
+        "DIFlagRValueReference" => 1 << 14,
+        "DIFlagNoReturn" => 1 << 20,
+        "DIFlagThunk" => 1 << 25,
+        "DIFlagAllCallsDescribed" => 1 << 29,
+        "DISPFlagZero" => 0,
+        "DISPFlagVirtual" => 1,
+        "DISPFlagPureVirtual" => 2,
+        "DISPFlagLocalToUnit" => 1 << 2,
+        "DISPFlagDefinition" => 1 << 3,
+        "DISPFlagOptimized" => 1 << 4,
+        "DISPFlagPure" => 1 << 5,
+        "DISPFlagElemental" => 1 << 6,
+        "DISPFlagRecursive" => 1 << 7,
+        "DISPFlagMainSubprogram" => 1 << 8,
+        "DISPFlagDeleted" => 1 << 9,
+        "DISPFlagObjCDirect" => 1 << 11,
+        _ => return None,
+    })
+}
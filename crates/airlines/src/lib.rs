@@ -1,26 +1,140 @@
 pub mod air_builder;
 pub mod air_codegen;
+pub mod air_interpreter;
 pub mod air_parser;
+pub mod air_text_parser;
 pub mod llvm_bitcode;
 pub mod metal_lib;
 pub mod spirv_builder;
 pub mod spirv_codegen;
+pub mod spirv_interpreter;
 pub mod spirv_parser;
+pub mod spirv_reflection;
+pub mod translation_cache;
 
 #[cfg(test)]
 mod tests {
     use anyhow::Result;
 
     use crate::{
-        air_codegen::AirToSpirV, metal_lib::MTLLibraryParser, spirv_codegen::air::SpirVToAir,
+        air_codegen::{
+            AirToSpirV, AirToSpirVOptions, ArgumentKind, DepthRange, DescriptorBindingStrategy,
+            MAX_BUFFER_BINDINGS, ShaderResourceKind, VulkanEnvironment,
+        },
+        air_interpreter::{AirArgument, AirInterpreter},
+        air_parser::{
+            AirBranch, AirDebugInfo, AirFile, AirItem, AirMetadataConstant, AirModule, AirPhi,
+            AirType, AirTypeId, AirValue, AirValueId,
+        },
+        metal_lib::{MTLLibraryFunctionType, MTLLibraryParser},
+        spirv_builder::SpirVBuilder,
+        spirv_codegen::air::SpirVToAir,
+        spirv_interpreter::{SpirVInterface, SpirVInterpreter},
+        spirv_parser::{
+            SpirVBuiltIn, SpirVDecorateType, SpirVExecutionMode, SpirVExecutionModel, SpirVModule,
+            SpirVOp, SpirVOpCode, SpirVStorageClass, SpirVType, SpirVVariableId,
+        },
+        spirv_reflection::SpirVReflection,
+        translation_cache::{
+            CachedTranslation, TranslationCache, TranslationKey, TranslationReflection,
+        },
     };
 
     use super::llvm_bitcode::*;
 
+    fn parse_air_text(path: &str) -> Result<AirFile> {
+        super::air_text_parser::Parser::new(&std::fs::read_to_string(path)?)?.start()
+    }
+
+    fn air_module(air: &AirFile) -> &AirModule {
+        air.items
+            .iter()
+            .find_map(|i| match i {
+                AirItem::Module(m) => Some(m),
+                _ => None,
+            })
+            .unwrap()
+    }
+
+    fn parse_spirv_words(words: &[u32]) -> Result<SpirVModule> {
+        super::spirv_parser::Parser::new(words.iter().flat_map(|x| x.to_le_bytes()).collect())
+            .start()
+    }
+
     #[test]
     fn read_metal_lib() -> Result<()> {
-        let metal_lib =
-            MTLLibraryParser::default().read(&std::fs::read("test-files/test.metallib")?)?;
+        let content = std::fs::read("test-files/test.metallib")?;
+        let mut metal_lib = MTLLibraryParser::default();
+        metal_lib.read(&content)?;
+
+        let signature = &metal_lib.signature;
+        assert_eq!(signature.file_size, content.len() as u64);
+        assert!(signature.bitcode_offset + signature.bitcode_size <= signature.file_size);
+        assert!(metal_lib.shader.air.is_some());
+
+        Ok(())
+    }
+
+    #[test]
+    fn read_metal_lib_functions() -> Result<()> {
+        let mut metal_lib = MTLLibraryParser::default();
+        metal_lib.read(&std::fs::read("test-files/test.metallib")?)?;
+
+        assert_eq!(metal_lib.functions.len(), 1);
+        assert_eq!(metal_lib.functions[0].name, "main0");
+        assert_eq!(
+            metal_lib.functions[0].function_type,
+            MTLLibraryFunctionType::Vertex
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn translation_cache() -> Result<()> {
+        let content = std::fs::read("test-files/test.metallib")?;
+        let cache = TranslationCache::new(std::env::temp_dir().join(format!(
+            "airlines-translation-cache-test-{}",
+            std::process::id()
+        )))?;
+
+        let options = AirToSpirVOptions::default();
+
+        let mut miss = MTLLibraryParser::default();
+        miss.read_header(&content)?;
+        let translated = miss.to_spirv_binary_cached(&cache, &options)?;
+        assert!(miss.shader.air.is_some());
+        assert_eq!(translated.reflection.functions[0].name, "main0");
+
+        // A hit doesn't need the bitcode at all.
+        let mut hit = MTLLibraryParser::default();
+        hit.read_header(&content)?;
+        assert_eq!(hit.to_spirv_binary_cached(&cache, &options)?, translated);
+        assert!(hit.shader.air.is_none());
+
+        // Different options are a different entry.
+        let flipped = AirToSpirVOptions {
+            flip_y: true,
+            ..Default::default()
+        };
+        assert_ne!(
+            TranslationKey::new(&hit, &options)?,
+            TranslationKey::new(&hit, &flipped)?
+        );
+        assert!(cache.get(&TranslationKey::new(&hit, &flipped)?).is_none());
+
+        // Corrupt entries are dropped.
+        let key = TranslationKey::new(&hit, &options)?;
+        std::fs::write(cache.directory().join(key.file_name()), b"ALSC")?;
+        assert!(cache.get(&key).is_none());
+        assert!(!cache.directory().join(key.file_name()).exists());
+
+        // Entries bigger than the limit aren't kept.
+        let small = cache.clone().with_max_size(16);
+        small.insert(&key, &translated)?;
+        assert!(small.get(&key).is_none());
+
+        std::fs::remove_dir_all(cache.directory())?;
 
         Ok(())
     }
@@ -43,6 +157,346 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn air_parser_lazy() -> Result<()> {
+        let content = std::fs::read("test-files/lazy.air")?;
+
+        let mut eager = super::air_parser::Parser::new(content.clone())?;
+        let eager = AirInterpreter::new(eager.start()?)?;
+
+        let mut lazy = super::air_parser::Parser::new(content)?.start_lazy()?;
+        assert!(lazy.module()?.function_bodies.is_empty());
+
+        // Out of order, and past a declaration.
+        for name in ["splat", "length"] {
+            let contents = lazy.get_function(name)?.unwrap().contents.clone();
+            let lazy_module = lazy.module()?;
+            let lazy_values = contents
+                .iter()
+                .map(|x| std::mem::discriminant(&lazy_module.value_list[x.0 as usize]))
+                .collect::<Vec<_>>();
+
+            let eager_signature = eager
+                .module
+                .function_signatures
+                .iter()
+                .find(|x| eager.module.string_table[x.name.0 as usize].content == name)
+                .unwrap()
+                .global_id;
+            let eager_body = eager
+                .module
+                .function_bodies
+                .iter()
+                .find(|x| x.signature == eager_signature)
+                .unwrap();
+
+            let eager_values = eager_body
+                .contents
+                .iter()
+                .map(|x| std::mem::discriminant(&eager.module.value_list[x.0 as usize]))
+                .collect::<Vec<_>>();
+
+            assert_eq!(lazy_values, eager_values);
+        }
+        assert!(lazy.get_function("air.fast_sqrt.f32")?.is_none());
+
+        let mut interpreter = AirInterpreter::new(lazy.into_air_file()?)?;
+        let result = interpreter.run("length", vec![[3.0_f32, 4.0].into()])?;
+        assert_eq!(result.as_f32(), Some(5.0));
+
+        let result = interpreter.run("splat", vec![2.0_f32.into()])?;
+        assert_eq!(result.as_f32_vec(), Some(vec![2.0, 2.0]));
+
+        Ok(())
+    }
+
+    #[test]
+    fn air_parser_value_names() -> Result<()> {
+        let air =
+            super::air_parser::Parser::new(std::fs::read("test-files/names.air")?)?.start()?;
+        let module = air_module(&air);
+
+        // The function offset comes from the module-level table at `VSTOFFSET`.
+        assert_ne!(module.vst_offset, 0);
+        let [signature] = &module.function_signatures[..] else {
+            panic!(
+                "Expected one function, found {:?}",
+                module.function_signatures
+            );
+        };
+        assert_ne!(signature.body_offset, 0);
+
+        // `DECLAREBLOCKS` pads the bodies with empty ones.
+        let body = module
+            .function_bodies
+            .iter()
+            .find(|x| !x.contents.is_empty())
+            .unwrap();
+        assert_eq!(body.signature, signature.global_id);
+        let mut block_names = body.block_names.iter().collect::<Vec<_>>();
+        block_names.sort();
+        assert_eq!(
+            block_names,
+            [
+                (&0, &"entry".to_string()),
+                (&1, &"exit".to_string()),
+                (&2, &"body".to_string()),
+                (&3, &"negate".to_string()),
+            ]
+        );
+
+        let value = |name: &str| {
+            module
+                .value_names
+                .iter()
+                .find(|x| x.1 == name)
+                .map(|x| *x.0)
+                .unwrap()
+        };
+        assert_eq!(signature.ty.param_values, [value("value"), value("flip")]);
+
+        let instruction = |name: &str| &module.value_list[value(name).0 as usize];
+        assert_eq!(
+            instruction("picked"),
+            &AirValue::Phi(AirPhi {
+                ty: AirType::Float,
+                incoming: vec![(value("doubled"), 2), (value("negated"), 3)],
+            })
+        );
+
+        // `doubled` is used before it's defined, the placeholder got replaced.
+        assert!(matches!(
+            instruction("sum"),
+            AirValue::BinOp(x) if x.lhs == value("picked") && x.rhs == value("doubled")
+        ));
+        assert!(matches!(instruction("doubled"), AirValue::BinOp(x) if x.lhs == value("value")));
+        assert!(
+            module
+                .value_list
+                .iter()
+                .all(|x| !matches!(x, AirValue::Placeholder(_)))
+        );
+
+        let branches = body
+            .contents
+            .iter()
+            .filter_map(|x| match &module.value_list[x.0 as usize] {
+                AirValue::Branch(branch) => Some(branch.clone()),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            branches,
+            [
+                AirBranch {
+                    true_block: 2,
+                    ..Default::default()
+                },
+                AirBranch {
+                    true_block: 3,
+                    false_block: Some(1),
+                    condition: Some(value("flip")),
+                },
+                AirBranch {
+                    true_block: 1,
+                    ..Default::default()
+                },
+            ]
+        );
+
+        // Lazily, the names are there before any body is.
+        let mut lazy =
+            super::air_parser::Parser::new(std::fs::read("test-files/names.air")?)?.start_lazy()?;
+        assert!(
+            lazy.module()?
+                .function_bodies
+                .iter()
+                .all(|x| x.contents.is_empty())
+        );
+        assert!(lazy.get_function("scale")?.is_some());
+
+        // A file without a module is an error rather than a panic.
+        lazy.file.items.clear();
+        assert!(lazy.module().is_err());
+        assert!(lazy.get_function("scale").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn air_text_parser() -> Result<()> {
+        let air = parse_air_text("test-files/code.air.ll")?;
+        let module = air_module(&air);
+
+        assert_eq!(module.triple, "air64-apple-macosx15.0.0");
+        assert_eq!(module.source_filename, "code.metal");
+        assert_eq!(module.global_variables.len(), 2);
+
+        let [signature] = &module.function_signatures[..] else {
+            panic!(
+                "Expected one function, found {:?}",
+                module.function_signatures
+            );
+        };
+        assert_eq!(
+            module.string_table[signature.name.0 as usize].content,
+            "vertexMain"
+        );
+        assert!(!signature.is_proto);
+
+        let ty = |id: AirTypeId| &module.types[id.0 as usize];
+        let vector = |x: &AirType| match x {
+            AirType::Vector(vector) => (vector.size, ty(vector.element_type).clone()),
+            _ => panic!("Expected Vector, found {:?}", x),
+        };
+        assert_eq!(
+            signature
+                .ty
+                .param_types
+                .iter()
+                .map(|x| ty(*x))
+                .collect::<Vec<_>>(),
+            [&AirType::Integer(32)]
+        );
+        let AirType::Struct(output) = ty(signature.ty.return_type) else {
+            panic!("Expected Struct, found {:?}", ty(signature.ty.return_type));
+        };
+        assert!(output.is_packed);
+        assert_eq!(
+            output
+                .elements
+                .iter()
+                .map(|x| vector(ty(*x)))
+                .collect::<Vec<_>>(),
+            [(3, AirType::Float), (4, AirType::Float)]
+        );
+
+        let [body] = &module.function_bodies[..] else {
+            panic!("Expected one body, found {:?}", module.function_bodies);
+        };
+        assert_eq!(body.signature, signature.global_id);
+        assert_eq!(body.contents.len(), 10);
+
+        let named_nodes = module
+            .metadata_named_nodes
+            .iter()
+            .map(|x| x.name.as_str())
+            .collect::<Vec<_>>();
+        assert!(named_nodes.contains(&"air.vertex"));
+
+        Ok(())
+    }
+
+    /// Checks `debug.air(.ll)`, which both parsers must read the same way.
+    fn assert_debug_info(air: &AirFile) {
+        let module = air_module(air);
+
+        let dbg = module
+            .metadata_kind_table
+            .values()
+            .find(|x| x.name == "dbg")
+            .unwrap()
+            .id;
+        let signature = module
+            .function_signatures
+            .iter()
+            .find(|x| !x.is_proto)
+            .unwrap();
+        let function = module
+            .value_list
+            .iter()
+            .position(|x| *x == AirValue::Function(signature.global_id))
+            .map(|x| AirValueId(x as u64))
+            .unwrap();
+
+        let subprogram_id = module.metadata_attachments[&function]
+            .iter()
+            .find(|x| x.kind == dbg)
+            .unwrap()
+            .node;
+        let Some(AirDebugInfo::Subprogram(subprogram)) = module.get_debug_info(subprogram_id)
+        else {
+            panic!(
+                "Expected Subprogram, found {:?}",
+                module.get_debug_info(subprogram_id)
+            );
+        };
+        assert_eq!(
+            module.get_metadata_string(subprogram.name).as_deref(),
+            Some("vertexMain")
+        );
+        assert_eq!((subprogram.line, subprogram.scope_line), (12, 12));
+
+        let Some(AirDebugInfo::File(file)) = subprogram.file.and_then(|x| module.get_debug_info(x))
+        else {
+            panic!("Expected File, found {:?}", subprogram.file);
+        };
+        assert_eq!(
+            module.get_metadata_string(file.filename).as_deref(),
+            Some("code.metal")
+        );
+
+        let body = module
+            .function_bodies
+            .iter()
+            .find(|x| x.signature == signature.global_id && !x.contents.is_empty())
+            .unwrap();
+        let locations = body
+            .contents
+            .iter()
+            .map(|x| {
+                let location = &module.debug_locations[x];
+                assert_eq!(location.scope, subprogram_id);
+                (location.line, location.column)
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            locations,
+            [
+                (14, 25),
+                (15, 16),
+                (15, 16),
+                (14, 25),
+                (14, 25),
+                (14, 18),
+                (14, 18),
+                (13, 20),
+                (13, 20),
+                (17, 2),
+            ]
+        );
+
+        // `distinct !{!26, null}`
+        let node = module
+            .metadata_named_nodes
+            .iter()
+            .find(|x| x.name == "test.distinct_null")
+            .unwrap()
+            .operands[0];
+        let Some(AirMetadataConstant::Node(operands)) = module.get_metadata(Some(node)) else {
+            panic!("Expected Node, found {:?}", module.get_metadata(Some(node)));
+        };
+        assert!(matches!(
+            operands[..],
+            [Some(x), None] if matches!(module.get_debug_info(x), Some(AirDebugInfo::File(_)))
+        ));
+    }
+
+    #[test]
+    fn air_text_debug_info() -> Result<()> {
+        assert_debug_info(&parse_air_text("test-files/debug.air.ll")?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn air_debug_info() -> Result<()> {
+        let mut parser = super::air_parser::Parser::new(std::fs::read("test-files/debug.air")?)?;
+        assert_debug_info(&parser.start()?);
+
+        Ok(())
+    }
+
     #[test]
     fn spirv_to_air() -> Result<()> {
         let mut input = super::spirv_parser::Parser::new(std::fs::read("test-files/test.spv")?);
@@ -60,13 +514,1047 @@ mod tests {
         let mut conversion = AirToSpirV::new(dbg!(input.start()?));
         conversion.start()?;
 
-        let assembly = conversion.output.assemble_to_bytes();
+        let assembly = conversion.output.assemble_to_bytes()?;
 
         std::fs::write("result.spv", assembly)?;
 
         Ok(())
     }
 
+    #[test]
+    fn air_text_to_spirv() -> Result<()> {
+        let mut conversion = AirToSpirV::new(parse_air_text("test-files/code.air.ll")?);
+        conversion.start()?;
+
+        let spirv = parse_spirv_words(&conversion.output.assemble()?)?;
+
+        let entry_points = spirv
+            .entry_point_table
+            .values()
+            .map(|x| (x.name.as_str(), x.execution_model))
+            .collect::<Vec<_>>();
+        assert_eq!(entry_points, [("vertexMain", SpirVExecutionModel::Vertex)]);
+
+        let mut built_ins = vec![];
+        let mut locations = vec![];
+        for op in &spirv.operands {
+            match op {
+                SpirVOp::Decorate(_, SpirVDecorateType::BuiltIn(built_in))
+                | SpirVOp::MemberDecorate(_, _, SpirVDecorateType::BuiltIn(built_in)) => {
+                    built_ins.push(*built_in)
+                }
+                SpirVOp::Decorate(_, SpirVDecorateType::Location(location)) => {
+                    locations.push(*location)
+                }
+                _ => {}
+            }
+        }
+        // `Position` is the first member of the per-vertex block.
+        assert!(built_ins.contains(&SpirVBuiltIn::Position));
+        assert!(built_ins.contains(&SpirVBuiltIn::VertexIndex));
+        assert_eq!(locations, [0]);
+
+        let mut interpreter = SpirVInterpreter::new(spirv)?;
+        interpreter.set_input(SpirVInterface::BuiltIn(SpirVBuiltIn::VertexIndex), 1);
+        interpreter.run("vertexMain")?;
+        assert_eq!(
+            interpreter
+                .output(SpirVInterface::BuiltIn(SpirVBuiltIn::Position))
+                .and_then(|i| i.as_f32_vec()),
+            Some(vec![0.5, 0.5, 0.0, 1.0])
+        );
+        assert_eq!(
+            interpreter
+                .output(SpirVInterface::Location(0))
+                .and_then(|i| i.as_f32_vec()),
+            Some(vec![0.0, 1.0, 0.0])
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn spirv_reflection() -> Result<()> {
+        let air = parse_air_text("test-files/code.air.ll")?;
+        let words = AirToSpirV::translate(air_module(&air), &AirToSpirVOptions::default(), None)?
+            .assemble()?;
+        let reflection = SpirVReflection::new(&parse_spirv_words(&words)?);
+
+        assert_eq!(reflection.entry_points.len(), 1);
+        assert_eq!(reflection.entry_points[0].name, "vertexMain");
+        assert_eq!(
+            reflection.entry_points[0].execution_model,
+            SpirVExecutionModel::Vertex
+        );
+
+        let interface = reflection.entry_points[0]
+            .interface
+            .iter()
+            .map(|x| {
+                (
+                    x.name.as_str(),
+                    x.storage_class,
+                    x.type_name.as_str(),
+                    x.location,
+                    x.built_in,
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            interface,
+            [
+                (
+                    "fragColor",
+                    SpirVStorageClass::Output,
+                    "float3",
+                    Some(0),
+                    None
+                ),
+                (
+                    "VertexOutput",
+                    SpirVStorageClass::Output,
+                    "mtlPosition",
+                    None,
+                    Some(SpirVBuiltIn::Position)
+                ),
+                (
+                    "vertexID",
+                    SpirVStorageClass::Input,
+                    "uint",
+                    None,
+                    Some(SpirVBuiltIn::VertexIndex)
+                ),
+            ]
+        );
+        assert!(reflection.resources.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn air_to_spirv_unsupported() -> Result<()> {
+        // Indexing past the global is pointer arithmetic, which has no
+        // logical addressing equivalent.
+        let source = std::fs::read_to_string("test-files/code.air.ll")?
+            .replace("@_ZL5color, i64 0, i64 %2", "@_ZL5color, i64 1, i64 %2");
+        let air = super::air_text_parser::Parser::new(&source)?.start()?;
+
+        let error = AirToSpirV::translate(air_module(&air), &AirToSpirVOptions::default(), None)
+            .err()
+            .unwrap();
+        assert!(error.to_string().contains("Pointer arithmetic"));
+
+        // Types without a SPIR-V equivalent are errors, not panics.
+        let source = std::fs::read_to_string("test-files/argument_buffer.air.ll")?
+            .replace("!\"float\", !\"scale\"", "!\"bool\", !\"scale\"");
+        let air = super::air_text_parser::Parser::new(&source)?.start()?;
+
+        let error = AirToSpirV::translate(air_module(&air), &AirToSpirVOptions::default(), None)
+            .err()
+            .unwrap();
+        assert!(
+            error
+                .to_string()
+                .contains("Metal type `bool` isn't supported.")
+        );
+
+        let mut builder = SpirVBuilder::new();
+        assert!(AirToSpirV::texture_image_type(&mut builder, "texture2d<bool>").is_err());
+        assert!(AirToSpirV::texture_image_type(&mut builder, "texture4d<float>").is_err());
+        assert!(
+            AirToSpirV::spirv_get_element_type(&mut builder, SpirVVariableId(1000), 0).is_err()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn air_to_spirv_entry_points() -> Result<()> {
+        let air = parse_air_text("test-files/entry_points.air.ll")?;
+        let module = air_module(&air);
+        let options = AirToSpirVOptions::default();
+
+        assert_eq!(
+            AirToSpirV::entry_points(module),
+            ["vertexMain", "vertexCopy", "vertexInstanced"]
+        );
+        assert!(AirToSpirV::translate(module, &options, Some("missing")).is_err());
+
+        let expected = AirToSpirV::translate(module, &options, Some("vertexMain"))?.assemble()?;
+
+        let results = AirToSpirV::translate_entry_points(module, &options, 0);
+        let names = results.iter().map(|x| x.name.as_str()).collect::<Vec<_>>();
+        assert_eq!(names, ["vertexMain", "vertexCopy", "vertexInstanced"]);
+
+        // `air.instance_id` isn't supported yet, which mustn't fail the others.
+        let [main, copy, instanced] = &results[..] else {
+            unreachable!()
+        };
+        assert_eq!(main.result.as_ref().unwrap().assemble()?, expected);
+        assert!(copy.result.is_ok());
+        assert!(instanced.result.is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn air_to_spirv_stages() -> Result<()> {
+        let air = parse_air_text("test-files/stages.air.ll")?;
+        let module = air_module(&air);
+        let options = AirToSpirVOptions {
+            threads_per_threadgroup: [4, 2, 1],
+            ..Default::default()
+        };
+
+        assert_eq!(
+            AirToSpirV::entry_points(module),
+            ["fragmentMain", "kernelMain"]
+        );
+
+        let words = AirToSpirV::translate(module, &options, None)?.assemble()?;
+        let spirv = parse_spirv_words(&words)?;
+
+        let mut execution_models = vec![];
+        let mut execution_modes = vec![];
+        for op in &spirv.operands {
+            match op {
+                SpirVOp::EntryPoint(entry_point) => {
+                    execution_models.push((entry_point.name.as_str(), entry_point.execution_model))
+                }
+                SpirVOp::ExecutionMode(_, mode) => execution_modes.push(mode.clone()),
+                _ => {}
+            }
+        }
+        assert_eq!(
+            execution_models,
+            [
+                ("fragmentMain", SpirVExecutionModel::Fragment),
+                ("kernelMain", SpirVExecutionModel::GLCompute)
+            ]
+        );
+        assert_eq!(
+            execution_modes,
+            [
+                SpirVExecutionMode::OriginUpperLeft,
+                SpirVExecutionMode::LocalSize(4, 2, 1)
+            ]
+        );
+
+        let reflection = SpirVReflection::new(&spirv);
+        let interface = reflection.entry_points[0]
+            .interface
+            .iter()
+            .map(|i| (i.name.as_str(), i.location, i.built_in))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            interface,
+            [
+                ("", Some(0), None),
+                ("position", None, Some(SpirVBuiltIn::FragCoord)),
+                ("color", Some(1), None),
+            ]
+        );
+
+        // The color comes from `user(locn1)`, the alpha from the depth.
+        let mut interpreter = SpirVInterpreter::new(spirv)?;
+        interpreter.set_input(
+            SpirVInterface::BuiltIn(SpirVBuiltIn::FragCoord),
+            [8.5f32, 4.5, 0.25, 1.0],
+        );
+        interpreter.set_input(SpirVInterface::Location(1), [0.1f32, 0.2, 0.3]);
+        interpreter.run("fragmentMain")?;
+        assert_eq!(
+            interpreter
+                .output(SpirVInterface::Location(0))
+                .and_then(|i| i.as_f32_vec()),
+            Some(vec![0.1, 0.2, 0.3, 0.25])
+        );
+
+        // `uint2` and `uint` built-ins are narrowed from `uint3`.
+        interpreter.dispatch("kernelMain", [2, 1, 1])?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn air_vertex_input_to_spirv() -> Result<()> {
+        let air = parse_air_text("test-files/vertex_input.air.ll")?;
+        let module = air_module(&air);
+
+        let words =
+            AirToSpirV::translate(module, &AirToSpirVOptions::default(), None)?.assemble()?;
+        let spirv = parse_spirv_words(&words)?;
+
+        let mut names = std::collections::HashMap::new();
+        let mut locations = vec![];
+        for op in &spirv.operands {
+            match op {
+                SpirVOp::Name(id, name) => {
+                    names.insert(*id, name.as_str());
+                }
+                SpirVOp::Decorate(id, SpirVDecorateType::Location(location)) => {
+                    locations.push((*id, *location))
+                }
+                _ => {}
+            }
+        }
+
+        // Attributes are at their `[[attribute(n)]]`, outputs keep counting
+        // from zero.
+        let locations = locations
+            .into_iter()
+            .map(|(id, location)| (names[&id], location))
+            .collect::<Vec<_>>();
+        assert_eq!(locations, [("fragColor", 0), ("color", 2), ("position", 0)]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn air_argument_buffer_to_spirv() -> Result<()> {
+        let air = parse_air_text("test-files/argument_buffer.air.ll")?;
+        let module = air_module(&air);
+        let options = AirToSpirVOptions {
+            spirv_version: (1, 4),
+            environment: VulkanEnvironment::Vulkan1_2,
+            ..Default::default()
+        };
+
+        let layouts = AirToSpirV::argument_buffers(module, &options)?;
+        assert_eq!(layouts.len(), 1);
+
+        let layout = &layouts[0];
+        assert_eq!(layout.function, "vertexMain");
+        assert_eq!(layout.name, "material");
+        assert_eq!((layout.buffer_index, layout.set), (0, 1));
+        assert_eq!(layout.encoded_length, 40);
+
+        let arguments = layout
+            .arguments
+            .iter()
+            .map(|x| (x.name.as_str(), x.id, x.kind, x.offset, x.array_length))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            arguments,
+            [
+                ("albedo", Some(0), ArgumentKind::Texture, 0, 1),
+                ("albedoSampler", Some(1), ArgumentKind::Sampler, 8, 1),
+                ("colors", Some(2), ArgumentKind::Buffer, 16, 2),
+                ("scale", None, ArgumentKind::Data, 32, 1),
+            ]
+        );
+        assert_eq!(layout.argument(2).unwrap().type_name, "float4");
+
+        let words = AirToSpirV::translate(module, &options, None)?.assemble()?;
+        let spirv = parse_spirv_words(&words)?;
+
+        let reflection = TranslationReflection::new(&[], &spirv);
+        let resources = reflection
+            .resources
+            .iter()
+            .map(|x| (x.name.as_str(), x.set, x.binding))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            resources,
+            [
+                ("material.albedo", 1, 0),
+                ("material.albedoSampler", 1, 1),
+                ("material.colors", 1, 2),
+                ("material.data", 1, 4),
+            ]
+        );
+        assert_eq!(layout.data_binding(), 4);
+
+        // The layouts survive the translation cache.
+        let cache = TranslationCache::new(std::env::temp_dir().join(format!(
+            "airlines-argument-buffer-test-{}",
+            std::process::id()
+        )))?;
+        let key = TranslationKey(1);
+        let translation = CachedTranslation {
+            words,
+            reflection: TranslationReflection {
+                argument_buffers: layouts,
+                ..reflection
+            },
+        };
+        cache.insert(&key, &translation)?;
+        assert_eq!(cache.get(&key), Some(translation));
+        std::fs::remove_dir_all(cache.directory())?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn air_debug_info_to_spirv() -> Result<()> {
+        let air = parse_air_text("test-files/debug.air.ll")?;
+        let module = air_module(&air);
+
+        let translate = |debug_info| -> Result<SpirVModule> {
+            let options = AirToSpirVOptions {
+                debug_info,
+                ..Default::default()
+            };
+            parse_spirv_words(&AirToSpirV::translate(module, &options, None)?.assemble()?)
+        };
+        let line_ops = |spirv: &SpirVModule| {
+            spirv
+                .functions_table
+                .values()
+                .flat_map(|x| &x.instructions)
+                .filter_map(|x| match x {
+                    SpirVOp::Block(_, block) => Some(&block.instructions),
+                    _ => None,
+                })
+                .flatten()
+                .filter_map(|x| match x {
+                    SpirVOp::Line(line) => Some(line.clone()),
+                    _ => None,
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let spirv = translate(true)?;
+        assert_eq!(
+            spirv
+                .operands
+                .iter()
+                .filter(|x| matches!(x, SpirVOp::Source(_)))
+                .count(),
+            1
+        );
+
+        let strings = spirv
+            .operands
+            .iter()
+            .filter_map(|x| match x {
+                SpirVOp::String(id, string) => Some((*id, string.as_str())),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        let [(file, path)] = strings[..] else {
+            panic!("Expected one OpString, found {:?}", strings);
+        };
+        assert!(path.ends_with("/crates/airlines/test-files/code.metal"));
+
+        let lines = line_ops(&spirv);
+        assert!(lines.iter().all(|x| x.file == file));
+        let mut lines = lines.iter().map(|x| (x.line, x.column)).collect::<Vec<_>>();
+        lines.dedup();
+        assert_eq!(
+            lines,
+            [(14, 25), (15, 16), (14, 25), (14, 18), (13, 20), (17, 2)]
+        );
+
+        // Nothing without the option.
+        let spirv = translate(false)?;
+        assert!(
+            !spirv
+                .operands
+                .iter()
+                .any(|x| matches!(x, SpirVOp::String(..)))
+        );
+        assert!(line_ops(&spirv).is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn air_to_spirv_options() -> Result<()> {
+        let air = parse_air_text("test-files/code.air.ll")?;
+        let module = air_module(&air);
+
+        let options = AirToSpirVOptions {
+            spirv_version: (1, 3),
+            environment: VulkanEnvironment::Vulkan1_1,
+            flip_y: true,
+            depth_range: DepthRange::NegativeOneToOne,
+            emit_names: false,
+            ..Default::default()
+        };
+        let spirv = parse_spirv_words(&AirToSpirV::translate(module, &options, None)?.assemble()?)?;
+
+        assert_eq!(spirv.signature.version, (1, 3));
+        assert!(
+            !spirv
+                .operands
+                .iter()
+                .any(|x| matches!(x, SpirVOp::Name(..) | SpirVOp::MemberName(..)))
+        );
+        assert_eq!(
+            spirv
+                .entry_point_table
+                .values()
+                .map(|x| x.name.as_str())
+                .collect::<Vec<_>>(),
+            ["vertexMain"]
+        );
+
+        // `Position` is (0.5, 0.5, 0, 1) for the second vertex, Y gets
+        // flipped and Z goes from [0, 1] to [-1, 1].
+        let mut interpreter = SpirVInterpreter::new(spirv)?;
+        interpreter.set_input(SpirVInterface::BuiltIn(SpirVBuiltIn::VertexIndex), 1);
+        interpreter.run("vertexMain")?;
+        assert_eq!(
+            interpreter
+                .output(SpirVInterface::BuiltIn(SpirVBuiltIn::Position))
+                .and_then(|i| i.as_f32_vec()),
+            Some(vec![0.5, -0.5, -1.0, 1.0])
+        );
+
+        // Versions the environment can't load are rejected.
+        let options = AirToSpirVOptions {
+            spirv_version: (1, 4),
+            environment: VulkanEnvironment::Vulkan1_1,
+            ..Default::default()
+        };
+        assert!(AirToSpirV::translate(module, &options, None).is_err());
+
+        // Argument buffers go after the sets the strategy uses.
+        let air = parse_air_text("test-files/argument_buffer.air.ll")?;
+        let options = AirToSpirVOptions {
+            spirv_version: (1, 4),
+            environment: VulkanEnvironment::Vulkan1_2,
+            descriptor_bindings: DescriptorBindingStrategy::SetPerResourceKind,
+            ..Default::default()
+        };
+        let spirv = parse_spirv_words(
+            &AirToSpirV::translate(air_module(&air), &options, None)?.assemble()?,
+        )?;
+        let sets = TranslationReflection::new(&[], &spirv)
+            .resources
+            .iter()
+            .map(|x| x.set)
+            .collect::<Vec<_>>();
+        assert_eq!(sets, [3, 3, 3, 3]);
+        assert_eq!(
+            options.descriptor_binding(ShaderResourceKind::Texture, 2),
+            (1, 2)
+        );
+        assert_eq!(
+            AirToSpirVOptions::default().descriptor_binding(ShaderResourceKind::Texture, 2),
+            (0, MAX_BUFFER_BINDINGS + 2)
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn spirv_interpreter() -> Result<()> {
+        let mut parser = super::spirv_parser::Parser::new(std::fs::read("test-files/test.spv")?);
+        let mut interpreter = SpirVInterpreter::new(parser.start()?)?;
+
+        let positions = [
+            [0.0, -0.5, 0.0, 1.0],
+            [0.5, 0.5, 0.0, 1.0],
+            [-0.5, 0.5, 0.0, 1.0],
+        ];
+        let colors = [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]];
+
+        for vertex_index in 0..3 {
+            interpreter.set_input(
+                SpirVInterface::BuiltIn(SpirVBuiltIn::VertexIndex),
+                vertex_index as u32,
+            );
+            interpreter.run("main")?;
+
+            let position = interpreter.output(SpirVInterface::BuiltIn(SpirVBuiltIn::Position));
+            let color = interpreter.output(SpirVInterface::Location(0));
+
+            assert_eq!(
+                position.and_then(|i| i.as_f32_vec()),
+                Some(positions[vertex_index].to_vec())
+            );
+            assert_eq!(
+                color.and_then(|i| i.as_f32_vec()),
+                Some(colors[vertex_index].to_vec())
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Assembles `(op code, operands)` pairs into a SPIR-V 1.3 module.
+    fn assemble_spirv(ops: &[(SpirVOpCode, &[u32])]) -> Vec<u32> {
+        let mut words = vec![0x07230203, 0x00010300, 0, 64, 0];
+        for (op_code, operands) in ops {
+            words.push(((operands.len() as u32 + 1) << 16) | *op_code as u32);
+            words.extend(*operands);
+        }
+
+        words
+    }
+
+    #[test]
+    fn spirv_interpreter_fragment() -> Result<()> {
+        // "main\0"
+        let main = [0x6e69616d, 0];
+        let words = assemble_spirv(&[
+            (SpirVOpCode::Capability, &[1]),
+            (SpirVOpCode::MemoryModel, &[0, 1]),
+            (SpirVOpCode::EntryPoint, &[4, 1, main[0], main[1], 2, 3]),
+            (SpirVOpCode::ExecutionMode, &[1, 7]),
+            (SpirVOpCode::Decorate, &[2, 30, 0]),
+            (SpirVOpCode::Decorate, &[3, 30, 0]),
+            (SpirVOpCode::TypeVoid, &[4]),
+            (SpirVOpCode::TypeFunction, &[5, 4]),
+            (SpirVOpCode::TypeFloat, &[6, 32]),
+            (SpirVOpCode::TypeBool, &[7]),
+            (SpirVOpCode::TypePointer, &[8, 1, 6]),
+            (SpirVOpCode::TypePointer, &[9, 3, 6]),
+            (SpirVOpCode::Variable, &[8, 2, 1]),
+            (SpirVOpCode::Variable, &[9, 3, 3]),
+            (SpirVOpCode::Constant, &[6, 10, 0]),
+            (SpirVOpCode::Function, &[4, 1, 0, 5]),
+            (SpirVOpCode::Label, &[11]),
+            (SpirVOpCode::Load, &[6, 12, 2]),
+            (SpirVOpCode::Store, &[3, 12]),
+            (SpirVOpCode::FOrdLessThan, &[7, 13, 12, 10]),
+            (SpirVOpCode::SelectionMerge, &[15, 0]),
+            (SpirVOpCode::BranchConditional, &[13, 14, 15]),
+            (SpirVOpCode::Label, &[14]),
+            (SpirVOpCode::Kill, &[]),
+            (SpirVOpCode::Label, &[15]),
+            (SpirVOpCode::Return, &[]),
+            (SpirVOpCode::FunctionEnd, &[]),
+        ]);
+
+        let mut interpreter = SpirVInterpreter::new(parse_spirv_words(&words)?)?;
+
+        interpreter.set_input(SpirVInterface::Location(0), 0.5f32);
+        interpreter.run("main")?;
+        assert!(!interpreter.discarded());
+        assert_eq!(
+            interpreter
+                .output(SpirVInterface::Location(0))
+                .and_then(|i| i.as_f32()),
+            Some(0.5)
+        );
+
+        interpreter.set_input(SpirVInterface::Location(0), -1.0f32);
+        interpreter.run("main")?;
+        assert!(interpreter.discarded());
+
+        Ok(())
+    }
+
+    #[test]
+    fn spirv_interpreter_compute() -> Result<()> {
+        // Every invocation squares its index into threadgroup memory, then
+        // after the barrier copies its neighbour's square to the buffer.
+        let main = [0x6e69616d, 0];
+        let words = assemble_spirv(&[
+            (SpirVOpCode::Capability, &[1]),
+            (SpirVOpCode::MemoryModel, &[0, 1]),
+            (SpirVOpCode::EntryPoint, &[5, 1, main[0], main[1], 2]),
+            (SpirVOpCode::ExecutionMode, &[1, 17, 4, 1, 1]),
+            (SpirVOpCode::Decorate, &[2, 11, 29]),
+            (SpirVOpCode::Decorate, &[13, 6, 4]),
+            (SpirVOpCode::MemberDecorate, &[14, 0, 35, 0]),
+            (SpirVOpCode::Decorate, &[14, 2]),
+            (SpirVOpCode::Decorate, &[4, 34, 0]),
+            (SpirVOpCode::Decorate, &[4, 33, 0]),
+            (SpirVOpCode::TypeVoid, &[5]),
+            (SpirVOpCode::TypeFunction, &[6, 5]),
+            (SpirVOpCode::TypeInt, &[7, 32, 0]),
+            (SpirVOpCode::Constant, &[7, 8, 0]),
+            (SpirVOpCode::Constant, &[7, 9, 1]),
+            (SpirVOpCode::Constant, &[7, 10, 2]),
+            (SpirVOpCode::Constant, &[7, 11, 4]),
+            (SpirVOpCode::Constant, &[7, 12, 0x108]),
+            (SpirVOpCode::TypeArray, &[13, 7, 11]),
+            (SpirVOpCode::TypeStruct, &[14, 13]),
+            (SpirVOpCode::TypePointer, &[15, 12, 14]),
+            (SpirVOpCode::TypePointer, &[16, 12, 7]),
+            (SpirVOpCode::TypePointer, &[17, 4, 13]),
+            (SpirVOpCode::TypePointer, &[18, 4, 7]),
+            (SpirVOpCode::TypePointer, &[19, 1, 7]),
+            (SpirVOpCode::Variable, &[19, 2, 1]),
+            (SpirVOpCode::Variable, &[17, 3, 4]),
+            (SpirVOpCode::Variable, &[15, 4, 12]),
+            (SpirVOpCode::Function, &[5, 1, 0, 6]),
+            (SpirVOpCode::Label, &[20]),
+            (SpirVOpCode::Load, &[7, 21, 2]),
+            (SpirVOpCode::AccessChain, &[18, 22, 3, 21]),
+            (SpirVOpCode::IMul, &[7, 23, 21, 21]),
+            (SpirVOpCode::Store, &[22, 23]),
+            (SpirVOpCode::ControlBarrier, &[10, 10, 12]),
+            (SpirVOpCode::IAdd, &[7, 24, 21, 9]),
+            (SpirVOpCode::UMod, &[7, 25, 24, 11]),
+            (SpirVOpCode::AccessChain, &[18, 26, 3, 25]),
+            (SpirVOpCode::Load, &[7, 27, 26]),
+            (SpirVOpCode::AccessChain, &[16, 28, 4, 8, 21]),
+            (SpirVOpCode::Store, &[28, 27]),
+            (SpirVOpCode::Return, &[]),
+            (SpirVOpCode::FunctionEnd, &[]),
+        ]);
+
+        let mut interpreter = SpirVInterpreter::new(parse_spirv_words(&words)?)?;
+        interpreter.bind_buffer(0, 0, vec![0; 16]);
+        interpreter.dispatch("main", [1, 1, 1])?;
+
+        let squares = interpreter
+            .buffer(0, 0)
+            .unwrap()
+            .chunks(4)
+            .map(|x| u32::from_le_bytes(x.try_into().unwrap()))
+            .collect::<Vec<_>>();
+        assert_eq!(squares, vec![1, 4, 9, 0]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn air_to_spirv_interpreter() -> Result<()> {
+        let mut input =
+            super::air_parser::Parser::new(std::fs::read("../../examples/hello/Shaders.air")?)?;
+
+        let mut conversion = AirToSpirV::new(input.start()?);
+        conversion.start()?;
+
+        let mut parser = super::spirv_parser::Parser::new(conversion.output.assemble_to_bytes()?);
+        let mut interpreter = SpirVInterpreter::new(parser.start()?)?;
+
+        // `constant float3*` has a 16 byte stride.
+        let positions = [[0.0, 0.5, 0.0], [-0.5, -0.5, 0.0], [0.5, -0.5, 0.0]];
+        let mut buffer = vec![];
+        for i in positions {
+            for j in [i[0], i[1], i[2], 0.0f32] {
+                buffer.extend(j.to_le_bytes());
+            }
+        }
+        interpreter.bind_buffer(0, 0, buffer);
+
+        for (vertex_id, position) in positions.iter().enumerate() {
+            interpreter.set_input(
+                SpirVInterface::BuiltIn(SpirVBuiltIn::VertexIndex),
+                vertex_id as u32,
+            );
+            interpreter.run("vertex_shader")?;
+
+            assert_eq!(
+                interpreter
+                    .output(SpirVInterface::BuiltIn(SpirVBuiltIn::Position))
+                    .and_then(|i| i.as_f32_vec()),
+                Some(vec![position[0], position[1], position[2], 1.0])
+            );
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn air_interpreter() -> Result<()> {
+        let mut parser = super::air_text_parser::Parser::new(&std::fs::read_to_string(
+            "test-files/intrinsics.air.ll",
+        )?)?;
+        let mut interpreter = AirInterpreter::new(parser.start()?)?;
+
+        let result = interpreter.run(
+            "lighting",
+            vec![[0.0f32, 3.0, 4.0].into(), [0.0f32, 0.0, 1.0].into()],
+        )?;
+
+        assert!((result.as_f32().unwrap() - 0.8).abs() < 1e-6);
+
+        Ok(())
+    }
+
+    #[test]
+    fn air_interpreter_differential() -> Result<()> {
+        // Runs every vertex through the AIR and through its SPIR-V translation.
+        let check = |path: &str,
+                     function: &str,
+                     buffer: Option<Vec<u8>>,
+                     outputs: &[SpirVInterface]|
+         -> Result<()> {
+            let air = super::air_parser::Parser::new(std::fs::read(path)?)?.start()?;
+
+            let mut conversion = AirToSpirV::new(air.clone());
+            conversion.start()?;
+
+            let mut parser =
+                super::spirv_parser::Parser::new(conversion.output.assemble_to_bytes()?);
+            let mut spirv_interpreter = SpirVInterpreter::new(parser.start()?)?;
+            let mut air_interpreter = AirInterpreter::new(air)?;
+
+            if let Some(buffer) = &buffer {
+                spirv_interpreter.bind_buffer(0, 0, buffer.clone());
+            }
+
+            for vertex_id in 0..3u32 {
+                let mut arguments = vec![AirArgument::from(vertex_id)];
+                if let Some(buffer) = &buffer {
+                    arguments.push(AirArgument::Buffer(buffer.clone()));
+                }
+                let result = air_interpreter.run(function, arguments)?;
+
+                spirv_interpreter.set_input(
+                    SpirVInterface::BuiltIn(SpirVBuiltIn::VertexIndex),
+                    vertex_id,
+                );
+                spirv_interpreter.run(function)?;
+
+                let results = match outputs.len() {
+                    1 => vec![result],
+                    _ => result.elements().unwrap_or_default().to_vec(),
+                };
+                for (air_output, spirv_output) in results.iter().zip(outputs) {
+                    let expected = air_output.as_f32_vec();
+                    assert!(expected.is_some());
+                    assert_eq!(
+                        expected,
+                        spirv_interpreter
+                            .output(*spirv_output)
+                            .and_then(|i| i.as_f32_vec())
+                    );
+                }
+            }
+
+            Ok(())
+        };
+
+        check(
+            "test-files/test.air",
+            "main0",
+            None,
+            &[
+                SpirVInterface::Location(0),
+                SpirVInterface::BuiltIn(SpirVBuiltIn::Position),
+            ],
+        )?;
+
+        let mut buffer = vec![];
+        for i in [
+            0.0f32, 0.5, 0.0, 0.0, -0.5, -0.5, 0.0, 0.0, 0.5, -0.5, 0.0, 0.0,
+        ] {
+            buffer.extend(i.to_le_bytes());
+        }
+        check(
+            "../../examples/hello/Shaders.air",
+            "vertex_shader",
+            Some(buffer),
+            &[SpirVInterface::BuiltIn(SpirVBuiltIn::Position)],
+        )?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn air_matrix_to_spirv() -> Result<()> {
+        let air = parse_air_text("test-files/matrix.air.ll")?;
+        let module = air_module(&air);
+        let options = AirToSpirVOptions::default();
+
+        let words = AirToSpirV::translate(module, &options, None)?.assemble()?;
+        let spirv = parse_spirv_words(&words)?;
+
+        assert!(
+            spirv
+                .type_table
+                .values()
+                .any(|x| matches!(x, SpirVType::Matrix(_, 4)))
+        );
+        let binary_ops = spirv
+            .functions_table
+            .values()
+            .flat_map(|x| &x.instructions)
+            .filter_map(|x| match x {
+                SpirVOp::Block(_, block) => Some(&block.instructions),
+                _ => None,
+            })
+            .flatten()
+            .filter_map(|x| match x {
+                SpirVOp::Binary(_, op_code, _) => Some(*op_code),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert!(binary_ops.contains(&SpirVOpCode::MatrixTimesMatrix));
+        assert!(binary_ops.contains(&SpirVOpCode::MatrixTimesVector));
+        // The columns were folded into the products.
+        assert!(!binary_ops.contains(&SpirVOpCode::FMul));
+
+        let positions = [
+            [0.0f32, 0.5, 0.0, 1.0],
+            [-0.5, -0.5, 0.0, 1.0],
+            [0.5, -0.5, 0.0, 1.0],
+        ];
+        let view_projection = [
+            [1.0f32, 0.0, 0.0, 0.0],
+            [0.0, 2.0, 0.0, 0.0],
+            [0.0, 0.0, 0.5, 1.0],
+            [0.0, 0.0, 0.25, 0.0],
+        ];
+        let model = [
+            [0.0f32, 1.0, 0.0, 0.0],
+            [-1.0, 0.0, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0],
+            [0.25, -0.5, 3.0, 1.0],
+        ];
+        let to_bytes = |x: &[[f32; 4]]| {
+            x.iter()
+                .flatten()
+                .flat_map(|i| i.to_le_bytes())
+                .collect::<Vec<_>>()
+        };
+        let (positions, uniforms) = (
+            to_bytes(&positions),
+            to_bytes(&[view_projection, model].concat()),
+        );
+
+        let mut spirv_interpreter = SpirVInterpreter::new(spirv)?;
+        for (index, buffer) in [(0, &positions), (1, &uniforms)] {
+            let (set, binding) = options.descriptor_binding(ShaderResourceKind::Buffer, index);
+            spirv_interpreter.bind_buffer(set, binding, buffer.clone());
+        }
+        let mut air_interpreter = AirInterpreter::new(air)?;
+
+        for vertex_id in 0..3u32 {
+            let result = air_interpreter.run(
+                "vertexMain",
+                vec![
+                    AirArgument::from(vertex_id),
+                    AirArgument::Buffer(positions.clone()),
+                    AirArgument::Buffer(uniforms.clone()),
+                ],
+            )?;
+
+            spirv_interpreter.set_input(
+                SpirVInterface::BuiltIn(SpirVBuiltIn::VertexIndex),
+                vertex_id,
+            );
+            spirv_interpreter.run("vertexMain")?;
+
+            let expected = result.elements().and_then(|x| x[0].as_f32_vec());
+            assert!(expected.is_some());
+            assert_eq!(
+                expected,
+                spirv_interpreter
+                    .output(SpirVInterface::BuiltIn(SpirVBuiltIn::Position))
+                    .and_then(|i| i.as_f32_vec())
+            );
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn air_buffer_layout_to_spirv() -> Result<()> {
+        let air = parse_air_text("test-files/layout.air.ll")?;
+        let module = air_module(&air);
+        let options = AirToSpirVOptions::default();
+
+        let words = AirToSpirV::translate(module, &options, None)?.assemble()?;
+        let spirv = parse_spirv_words(&words)?;
+
+        // `packed_float3 position; float scale; float3 color; float2 uv;`
+        let vertex = spirv
+            .name_table
+            .iter()
+            .find(|(_, x)| x.name == "struct.Vertex")
+            .map(|(id, _)| *id)
+            .unwrap();
+        let offsets = spirv
+            .operands
+            .iter()
+            .filter_map(|x| match x {
+                SpirVOp::MemberDecorate(id, _, SpirVDecorateType::Offset(offset))
+                    if *id == vertex =>
+                {
+                    Some(*offset)
+                }
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(offsets, [0, 12, 16, 32]);
+
+        // Everything above fits Vulkan's extended layout. A `packed_float3`
+        // in an argument buffer is a vector at an offset only
+        // `scalarBlockLayout` allows.
+        let packed_options = AirToSpirVOptions {
+            scalar_block_layout: false,
+            ..Default::default()
+        };
+        AirToSpirV::translate(module, &packed_options, None)?;
+
+        let source = std::fs::read_to_string("test-files/argument_buffer.air.ll")?.replace(
+            "i32 32, i32 4, i32 0, !\"float\", !\"scale\"",
+            "i32 36, i32 12, i32 0, !\"packed_float3\", !\"scale\"",
+        );
+        let packed_air = super::air_text_parser::Parser::new(&source)?.start()?;
+
+        AirToSpirV::translate(air_module(&packed_air), &options, None)?;
+        let error = AirToSpirV::translate(air_module(&packed_air), &packed_options, None)
+            .err()
+            .unwrap();
+        assert!(
+            error
+                .to_string()
+                .contains("offset 36 needs `scalarBlockLayout`")
+        );
+
+        let mut vertices = vec![];
+        let mut offsets = vec![];
+        for i in 0..3 {
+            let i = i as f32;
+            for j in [
+                i,
+                i + 1.0,
+                i + 2.0,
+                2.0,
+                0.5,
+                i * 10.0,
+                0.0,
+                0.0,
+                0.25,
+                i,
+                0.0,
+                0.0,
+            ] {
+                vertices.extend(j.to_le_bytes());
+            }
+            for j in [-1.0, 0.0, i] {
+                offsets.extend(f32::to_le_bytes(j));
+            }
+        }
+
+        let mut spirv_interpreter = SpirVInterpreter::new(spirv)?;
+        for (index, buffer) in [(0, &vertices), (1, &offsets)] {
+            let (set, binding) = options.descriptor_binding(ShaderResourceKind::Buffer, index);
+            spirv_interpreter.bind_buffer(set, binding, buffer.clone());
+        }
+        let mut air_interpreter = AirInterpreter::new(air)?;
+
+        for vertex_id in 0..3u32 {
+            let result = air_interpreter.run(
+                "vertexMain",
+                vec![
+                    AirArgument::from(vertex_id),
+                    AirArgument::Buffer(vertices.clone()),
+                    AirArgument::Buffer(offsets.clone()),
+                ],
+            )?;
+
+            spirv_interpreter.set_input(
+                SpirVInterface::BuiltIn(SpirVBuiltIn::VertexIndex),
+                vertex_id,
+            );
+            spirv_interpreter.run("vertexMain")?;
+
+            let i = vertex_id as f32;
+            let expected = Some(vec![i * 2.0 + i, i * 10.0, i, 1.0]);
+            assert_eq!(result.elements().and_then(|x| x[0].as_f32_vec()), expected);
+            assert_eq!(
+                spirv_interpreter
+                    .output(SpirVInterface::BuiltIn(SpirVBuiltIn::Position))
+                    .and_then(|i| i.as_f32_vec()),
+                expected
+            );
+        }
+
+        Ok(())
+    }
+
     #[test]
     fn air_bitstream() -> Result<()> {
         let (_signature, parser) = Bitstream::from(std::fs::read("test-files/test.air")?)?;
@@ -98,4 +1586,120 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn air_bitstream_blockinfo() -> Result<()> {
+        #[derive(Default)]
+        struct Writer {
+            bits: Vec<bool>,
+        }
+
+        impl Writer {
+            fn emit(&mut self, value: u64, width: usize) {
+                for i in 0..width {
+                    self.bits.push((value >> i) & 1 == 1);
+                }
+            }
+
+            fn emit_vbr(&mut self, mut value: u64, width: usize) {
+                let threshold = 1 << (width - 1);
+                while value >= threshold {
+                    self.emit((value & (threshold - 1)) | threshold, width);
+                    value >>= width - 1;
+                }
+                self.emit(value, width);
+            }
+
+            fn align32(&mut self) {
+                while !self.bits.len().is_multiple_of(32) {
+                    self.bits.push(false);
+                }
+            }
+
+            fn enter_block(&mut self, block_id: u64) -> usize {
+                self.emit(1, 2);
+                self.emit_vbr(block_id, 8);
+                self.emit_vbr(2, 4);
+                self.align32();
+                self.emit(0, 32);
+                self.bits.len()
+            }
+
+            fn end_block(&mut self, start: usize) {
+                self.emit(0, 2);
+                self.align32();
+
+                let words = (self.bits.len() - start) / 32;
+                for i in 0..32 {
+                    self.bits[start - 32 + i] = (words >> i) & 1 == 1;
+                }
+            }
+
+            fn record(&mut self, code: u64, fields: &[u64]) {
+                self.emit(3, 2);
+                self.emit_vbr(code, 6);
+                self.emit_vbr(fields.len() as u64, 6);
+                for i in fields {
+                    self.emit_vbr(*i, 6);
+                }
+            }
+
+            fn bytes(&self) -> Vec<u8> {
+                self.bits
+                    .chunks(8)
+                    .map(|x| (0..8).fold(0, |byte, i| byte | ((x[i] as u8) << i)))
+                    .collect()
+            }
+        }
+
+        let chars = |string: &str| string.bytes().map(|x| x as u64).collect::<Vec<_>>();
+
+        let mut writer = Writer::default();
+        writer.emit(BITCODE_MAGIC as u64, 32);
+
+        let blockinfo = writer.enter_block(0);
+        writer.record(1, &[8]);
+        writer.record(2, &chars("MY_MODULE"));
+        writer.record(3, &[[1].as_slice(), &chars("MY_VERSION")].concat());
+        writer.end_block(blockinfo);
+
+        let module = writer.enter_block(8);
+        writer.record(1, &[2]);
+        writer.end_block(module);
+
+        let raw = writer.bytes();
+
+        // Trailing bytes after the wrapped bitcode aren't part of it.
+        let mut wrapped = vec![];
+        for i in [BITCODE_WRAPPER_MAGIC, 0, 20, raw.len() as u32, 0] {
+            wrapped.extend(i.to_le_bytes());
+        }
+        wrapped.extend(&raw);
+        wrapped.extend([0; 4]);
+
+        for content in [raw, wrapped] {
+            let (_signature, mut bitstream) = Bitstream::from(content)?;
+
+            let mut entries = vec![];
+            for entry in bitstream.by_ref() {
+                entries.push(match entry? {
+                    StreamEntry::SubBlock(block) => format!("BLOCK {}", block.block_id),
+                    StreamEntry::Record(record) => {
+                        format!("RECORD {} {:?}", record.code, record.fields)
+                    }
+                    StreamEntry::EndBlock => "END".to_string(),
+                    StreamEntry::EndOfStream => break,
+                });
+            }
+
+            // BLOCKINFO and its records stay out of the stream.
+            assert_eq!(entries, ["BLOCK 8", "RECORD 1 [2]", "END"]);
+            assert_eq!(bitstream.block_name(8), Some("MY_MODULE"));
+            assert_eq!(bitstream.record_name(8, 1), Some("MY_VERSION"));
+            assert_eq!(bitstream.block_name(12), Some("FUNCTION_BLOCK"));
+            assert_eq!(bitstream.record_name(8, 2), None);
+        }
+
+        Ok(())
+    }
 }
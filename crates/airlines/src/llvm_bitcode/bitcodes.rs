@@ -125,32 +125,59 @@ pub enum BlockID {
 }
 
 impl BlockID {
-    pub fn from_u64(v: u64) -> Self {
+    pub fn from_u64(v: u64) -> Result<Self> {
         match v {
-            FIRST_APPLICATION_BLOCKID => Self::MODULE,
-            9 => Self::PARAMATTR,
-            10 => Self::PARAMATTR_GROUP,
-            11 => Self::CONSTANTS,
-            12 => Self::FUNCTION,
-            13 => Self::IDENTIFICATION,
-            14 => Self::VALUE_SYMTAB,
-            15 => Self::METADATA,
-            16 => Self::METADATA_ATTACHMENT,
-            17 => Self::TYPE_NEW,
-            18 => Self::USELIST,
-            19 => Self::MODULE_STRTAB,
-            20 => Self::GLOBALVAL_SUMMARY,
-            21 => Self::OPERAND_BUNDLE_TAGS,
-            22 => Self::METADATA_KIND,
-            23 => Self::STRTAB,
-            24 => Self::FULL_LTO_GLOBALVAL_SUMMARY,
-            25 => Self::SYMTAB,
-            26 => Self::SYNC_SCOPE_NAMES,
-            _ => unimplemented!(),
+            FIRST_APPLICATION_BLOCKID => Ok(Self::MODULE),
+            9 => Ok(Self::PARAMATTR),
+            10 => Ok(Self::PARAMATTR_GROUP),
+            11 => Ok(Self::CONSTANTS),
+            12 => Ok(Self::FUNCTION),
+            13 => Ok(Self::IDENTIFICATION),
+            14 => Ok(Self::VALUE_SYMTAB),
+            15 => Ok(Self::METADATA),
+            16 => Ok(Self::METADATA_ATTACHMENT),
+            17 => Ok(Self::TYPE_NEW),
+            18 => Ok(Self::USELIST),
+            19 => Ok(Self::MODULE_STRTAB),
+            20 => Ok(Self::GLOBALVAL_SUMMARY),
+            21 => Ok(Self::OPERAND_BUNDLE_TAGS),
+            22 => Ok(Self::METADATA_KIND),
+            23 => Ok(Self::STRTAB),
+            24 => Ok(Self::FULL_LTO_GLOBALVAL_SUMMARY),
+            25 => Ok(Self::SYMTAB),
+            26 => Ok(Self::SYNC_SCOPE_NAMES),
+            _ => Err(anyhow!("'{:?}' is not a valid block ID.", v)),
         }
     }
 }
 
+/// The names `llvm-bcanalyzer` uses for the standard blocks.
+pub fn known_block_name(block_id: u64) -> Option<&'static str> {
+    Some(match block_id {
+        0 => "BLOCKINFO_BLOCK",
+        FIRST_APPLICATION_BLOCKID => "MODULE_BLOCK",
+        9 => "PARAMATTR_BLOCK",
+        10 => "PARAMATTR_GROUP_BLOCK_ID",
+        11 => "CONSTANTS_BLOCK",
+        12 => "FUNCTION_BLOCK",
+        13 => "IDENTIFICATION_BLOCK_ID",
+        14 => "VALUE_SYMTAB",
+        15 => "METADATA_BLOCK",
+        16 => "METADATA_ATTACHMENT",
+        17 => "TYPE_BLOCK_ID",
+        18 => "USELIST_BLOCK",
+        19 => "MODULE_STRTAB_BLOCK",
+        20 => "GLOBALVAL_SUMMARY_BLOCK",
+        21 => "OPERAND_BUNDLE_TAGS_BLOCK",
+        22 => "METADATA_KIND_BLOCK",
+        23 => "STRTAB_BLOCK",
+        24 => "FULL_LTO_GLOBALVAL_SUMMARY_BLOCK",
+        25 => "SYMTAB_BLOCK",
+        26 => "SYNC_SCOPE_NAMES_BLOCK",
+        _ => return None,
+    })
+}
+
 #[derive(Debug, PartialEq, Eq)]
 #[repr(u64)]
 #[allow(non_camel_case_types)]
@@ -160,11 +187,11 @@ pub enum IdentificationCode {
 }
 
 impl IdentificationCode {
-    pub fn from_u64(v: u64) -> Self {
+    pub fn from_u64(v: u64) -> Result<Self> {
         match v {
-            1 => Self::STRING,
-            2 => Self::EPOCH,
-            _ => unimplemented!(),
+            1 => Ok(Self::STRING),
+            2 => Ok(Self::EPOCH),
+            _ => Err(anyhow!("'{:?}' is not a valid identification code.", v)),
         }
     }
 }
@@ -204,27 +231,27 @@ pub enum ModuleCode {
 }
 
 impl ModuleCode {
-    pub fn from_u64(v: u64) -> Self {
+    pub fn from_u64(v: u64) -> Result<Self> {
         match v {
-            1 => Self::VERSION,
-            2 => Self::TRIPLE,
-            3 => Self::DATALAYOUT,
-            4 => Self::ASM,
-            5 => Self::SECTIONNAME,
-            6 => Self::DEPLIB,
-            7 => Self::GLOBALVAR,
-            8 => Self::FUNCTION,
-            9 => Self::ALIAS_OLD,
+            1 => Ok(Self::VERSION),
+            2 => Ok(Self::TRIPLE),
+            3 => Ok(Self::DATALAYOUT),
+            4 => Ok(Self::ASM),
+            5 => Ok(Self::SECTIONNAME),
+            6 => Ok(Self::DEPLIB),
+            7 => Ok(Self::GLOBALVAR),
+            8 => Ok(Self::FUNCTION),
+            9 => Ok(Self::ALIAS_OLD),
             // 10 => doesn't exist afaik.
-            11 => Self::GCNAME,
-            12 => Self::COMDAT,
-            13 => Self::VSTOFFSET,
-            14 => Self::ALIAS,
-            15 => Self::METADATA_VALUS_UNUSED,
-            16 => Self::SOURCE_FILENAME,
-            17 => Self::HASH,
-            18 => Self::IFUNC,
-            _ => unimplemented!(),
+            11 => Ok(Self::GCNAME),
+            12 => Ok(Self::COMDAT),
+            13 => Ok(Self::VSTOFFSET),
+            14 => Ok(Self::ALIAS),
+            15 => Ok(Self::METADATA_VALUS_UNUSED),
+            16 => Ok(Self::SOURCE_FILENAME),
+            17 => Ok(Self::HASH),
+            18 => Ok(Self::IFUNC),
+            _ => Err(anyhow!("'{:?}' is not a valid module code.", v)),
         }
     }
 }
@@ -262,35 +289,35 @@ pub enum TypeCode {
 }
 
 impl TypeCode {
-    pub fn from_u64(v: u64) -> Self {
+    pub fn from_u64(v: u64) -> Result<Self> {
         match v {
-            1 => Self::NUMENTRY,
-            2 => Self::VOID,
-            3 => Self::FLOAT,
-            4 => Self::DOUBLE,
-            5 => Self::LABEL,
-            6 => Self::OPAQUE,
-            7 => Self::INTEGER,
-            8 => Self::POINTER,
-            9 => Self::FUNCTION_OLD,
-            10 => Self::HALF,
-            11 => Self::ARRAY,
-            12 => Self::VECTOR,
-            13 => Self::X86_FP80,
-            14 => Self::FP128,
-            15 => Self::PPC_FP128,
-            16 => Self::METADATA,
-            17 => Self::X86_MMX,
-            18 => Self::STRUCT_ANON,
-            19 => Self::STRUCT_NAME,
-            20 => Self::STRUCT_NAMED,
-            21 => Self::FUNCTION,
-            22 => Self::TOKEN,
-            23 => Self::BFLOAT,
-            24 => Self::X86_AMX,
-            25 => Self::OPAQUE_POINTER,
-            26 => Self::TARGET_TYPE,
-            _ => unimplemented!(),
+            1 => Ok(Self::NUMENTRY),
+            2 => Ok(Self::VOID),
+            3 => Ok(Self::FLOAT),
+            4 => Ok(Self::DOUBLE),
+            5 => Ok(Self::LABEL),
+            6 => Ok(Self::OPAQUE),
+            7 => Ok(Self::INTEGER),
+            8 => Ok(Self::POINTER),
+            9 => Ok(Self::FUNCTION_OLD),
+            10 => Ok(Self::HALF),
+            11 => Ok(Self::ARRAY),
+            12 => Ok(Self::VECTOR),
+            13 => Ok(Self::X86_FP80),
+            14 => Ok(Self::FP128),
+            15 => Ok(Self::PPC_FP128),
+            16 => Ok(Self::METADATA),
+            17 => Ok(Self::X86_MMX),
+            18 => Ok(Self::STRUCT_ANON),
+            19 => Ok(Self::STRUCT_NAME),
+            20 => Ok(Self::STRUCT_NAMED),
+            21 => Ok(Self::FUNCTION),
+            22 => Ok(Self::TOKEN),
+            23 => Ok(Self::BFLOAT),
+            24 => Ok(Self::X86_AMX),
+            25 => Ok(Self::OPAQUE_POINTER),
+            26 => Ok(Self::TARGET_TYPE),
+            _ => Err(anyhow!("'{:?}' is not a valid type code.", v)),
         }
     }
 }
@@ -405,112 +432,222 @@ pub enum AttributeKindCode {
 }
 
 impl AttributeKindCode {
-    pub fn from_u64(v: u64) -> Self {
+    pub fn from_u64(v: u64) -> Result<Self> {
         match v {
-            1 => Self::ALIGNMENT,
-            2 => Self::ALWAYS_INLINE,
-            3 => Self::BY_VAL,
-            4 => Self::INLINE_HINT,
-            5 => Self::IN_REG,
-            6 => Self::MIN_SIZE,
-            7 => Self::NAKED,
-            8 => Self::NEST,
-            9 => Self::NO_ALIAS,
-            10 => Self::NO_BUILTIN,
-            11 => Self::NO_CAPTURE,
-            12 => Self::NO_DUPLICATE,
-            13 => Self::NO_IMPLICIT_FLOAT,
-            14 => Self::NO_INLINE,
-            15 => Self::NON_LAZY_BIND,
-            16 => Self::NO_RED_ZONE,
-            17 => Self::NO_RETURN,
-            18 => Self::NO_UNWIND,
-            19 => Self::OPTIMIZE_FOR_SIZE,
-            20 => Self::READ_NONE,
-            21 => Self::READ_ONLY,
-            22 => Self::RETURNED,
-            23 => Self::RETURNS_TWICE,
-            24 => Self::S_EXT,
-            25 => Self::STACK_ALIGNMENT,
-            26 => Self::STACK_PROTECT,
-            27 => Self::STACK_PROTECT_REQ,
-            28 => Self::STACK_PROTECT_STRONG,
-            29 => Self::STRUCT_RET,
-            30 => Self::SANITIZE_ADDRESS,
-            31 => Self::SANITIZE_THREAD,
-            32 => Self::SANITIZE_MEMORY,
-            33 => Self::UW_TABLE,
-            34 => Self::Z_EXT,
-            35 => Self::BUILTIN,
-            36 => Self::COLD,
-            37 => Self::OPTIMIZE_NONE,
-            38 => Self::IN_ALLOCA,
-            39 => Self::NON_NULL,
-            40 => Self::JUMP_TABLE,
-            41 => Self::DEREFERENCEABLE,
-            42 => Self::DEREFERENCEABLE_OR_NULL,
-            43 => Self::CONVERGENT,
-            44 => Self::SAFESTACK,
-            45 => Self::ARGMEMONLY,
-            46 => Self::SWIFT_SELF,
-            47 => Self::SWIFT_ERROR,
-            48 => Self::NO_RECURSE,
-            49 => Self::INACCESSIBLEMEM_ONLY,
-            50 => Self::INACCESSIBLEMEM_OR_ARGMEMONLY,
-            51 => Self::ALLOC_SIZE,
-            52 => Self::WRITEONLY,
-            53 => Self::SPECULATABLE,
-            54 => Self::STRICT_FP,
-            55 => Self::SANITIZE_HWADDRESS,
-            56 => Self::NOCF_CHECK,
-            57 => Self::OPT_FOR_FUZZING,
-            58 => Self::SHADOWCALLSTACK,
-            59 => Self::SPECULATIVE_LOAD_HARDENING,
-            60 => Self::IMMARG,
-            61 => Self::WILLRETURN,
-            62 => Self::NOFREE,
-            63 => Self::NOSYNC,
-            64 => Self::SANITIZE_MEMTAG,
-            65 => Self::PREALLOCATED,
-            66 => Self::NO_MERGE,
-            67 => Self::NULL_POINTER_IS_VALID,
-            68 => Self::NOUNDEF,
-            69 => Self::BYREF,
-            70 => Self::MUSTPROGRESS,
-            71 => Self::NO_CALLBACK,
-            72 => Self::HOT,
-            73 => Self::NO_PROFILE,
-            74 => Self::VSCALE_RANGE,
-            75 => Self::SWIFT_ASYNC,
-            76 => Self::NO_SANITIZE_COVERAGE,
-            77 => Self::ELEMENTTYPE,
-            78 => Self::DISABLE_SANITIZER_INSTRUMENTATION,
-            79 => Self::NO_SANITIZE_BOUNDS,
-            80 => Self::ALLOC_ALIGN,
-            81 => Self::ALLOCATED_POINTER,
-            82 => Self::ALLOC_KIND,
-            83 => Self::PRESPLIT_COROUTINE,
-            84 => Self::FNRETTHUNK_EXTERN,
-            85 => Self::SKIP_PROFILE,
-            86 => Self::MEMORY,
-            87 => Self::NOFPCLASS,
-            88 => Self::OPTIMIZE_FOR_DEBUGGING,
-            89 => Self::WRITABLE,
-            90 => Self::CORO_ONLY_DESTROY_WHEN_COMPLETE,
-            91 => Self::DEAD_ON_UNWIND,
-            92 => Self::RANGE,
-            93 => Self::SANITIZE_NUMERICAL_STABILITY,
-            94 => Self::INITIALIZES,
-            95 => Self::HYBRID_PATCHABLE,
-            96 => Self::SANITIZE_REALTIME,
-            97 => Self::SANITIZE_REALTIME_BLOCKING,
-            98 => Self::CORO_ELIDE_SAFE,
-            99 => Self::NO_EXT,
-            100 => Self::NO_DIVERGENCE_SOURCE,
-            101 => Self::SANITIZE_TYPE,
-            102 => Self::CAPTURES,
-            103 => Self::DEAD_ON_RETURN,
-            _ => unimplemented!(),
+            1 => Ok(Self::ALIGNMENT),
+            2 => Ok(Self::ALWAYS_INLINE),
+            3 => Ok(Self::BY_VAL),
+            4 => Ok(Self::INLINE_HINT),
+            5 => Ok(Self::IN_REG),
+            6 => Ok(Self::MIN_SIZE),
+            7 => Ok(Self::NAKED),
+            8 => Ok(Self::NEST),
+            9 => Ok(Self::NO_ALIAS),
+            10 => Ok(Self::NO_BUILTIN),
+            11 => Ok(Self::NO_CAPTURE),
+            12 => Ok(Self::NO_DUPLICATE),
+            13 => Ok(Self::NO_IMPLICIT_FLOAT),
+            14 => Ok(Self::NO_INLINE),
+            15 => Ok(Self::NON_LAZY_BIND),
+            16 => Ok(Self::NO_RED_ZONE),
+            17 => Ok(Self::NO_RETURN),
+            18 => Ok(Self::NO_UNWIND),
+            19 => Ok(Self::OPTIMIZE_FOR_SIZE),
+            20 => Ok(Self::READ_NONE),
+            21 => Ok(Self::READ_ONLY),
+            22 => Ok(Self::RETURNED),
+            23 => Ok(Self::RETURNS_TWICE),
+            24 => Ok(Self::S_EXT),
+            25 => Ok(Self::STACK_ALIGNMENT),
+            26 => Ok(Self::STACK_PROTECT),
+            27 => Ok(Self::STACK_PROTECT_REQ),
+            28 => Ok(Self::STACK_PROTECT_STRONG),
+            29 => Ok(Self::STRUCT_RET),
+            30 => Ok(Self::SANITIZE_ADDRESS),
+            31 => Ok(Self::SANITIZE_THREAD),
+            32 => Ok(Self::SANITIZE_MEMORY),
+            33 => Ok(Self::UW_TABLE),
+            34 => Ok(Self::Z_EXT),
+            35 => Ok(Self::BUILTIN),
+            36 => Ok(Self::COLD),
+            37 => Ok(Self::OPTIMIZE_NONE),
+            38 => Ok(Self::IN_ALLOCA),
+            39 => Ok(Self::NON_NULL),
+            40 => Ok(Self::JUMP_TABLE),
+            41 => Ok(Self::DEREFERENCEABLE),
+            42 => Ok(Self::DEREFERENCEABLE_OR_NULL),
+            43 => Ok(Self::CONVERGENT),
+            44 => Ok(Self::SAFESTACK),
+            45 => Ok(Self::ARGMEMONLY),
+            46 => Ok(Self::SWIFT_SELF),
+            47 => Ok(Self::SWIFT_ERROR),
+            48 => Ok(Self::NO_RECURSE),
+            49 => Ok(Self::INACCESSIBLEMEM_ONLY),
+            50 => Ok(Self::INACCESSIBLEMEM_OR_ARGMEMONLY),
+            51 => Ok(Self::ALLOC_SIZE),
+            52 => Ok(Self::WRITEONLY),
+            53 => Ok(Self::SPECULATABLE),
+            54 => Ok(Self::STRICT_FP),
+            55 => Ok(Self::SANITIZE_HWADDRESS),
+            56 => Ok(Self::NOCF_CHECK),
+            57 => Ok(Self::OPT_FOR_FUZZING),
+            58 => Ok(Self::SHADOWCALLSTACK),
+            59 => Ok(Self::SPECULATIVE_LOAD_HARDENING),
+            60 => Ok(Self::IMMARG),
+            61 => Ok(Self::WILLRETURN),
+            62 => Ok(Self::NOFREE),
+            63 => Ok(Self::NOSYNC),
+            64 => Ok(Self::SANITIZE_MEMTAG),
+            65 => Ok(Self::PREALLOCATED),
+            66 => Ok(Self::NO_MERGE),
+            67 => Ok(Self::NULL_POINTER_IS_VALID),
+            68 => Ok(Self::NOUNDEF),
+            69 => Ok(Self::BYREF),
+            70 => Ok(Self::MUSTPROGRESS),
+            71 => Ok(Self::NO_CALLBACK),
+            72 => Ok(Self::HOT),
+            73 => Ok(Self::NO_PROFILE),
+            74 => Ok(Self::VSCALE_RANGE),
+            75 => Ok(Self::SWIFT_ASYNC),
+            76 => Ok(Self::NO_SANITIZE_COVERAGE),
+            77 => Ok(Self::ELEMENTTYPE),
+            78 => Ok(Self::DISABLE_SANITIZER_INSTRUMENTATION),
+            79 => Ok(Self::NO_SANITIZE_BOUNDS),
+            80 => Ok(Self::ALLOC_ALIGN),
+            81 => Ok(Self::ALLOCATED_POINTER),
+            82 => Ok(Self::ALLOC_KIND),
+            83 => Ok(Self::PRESPLIT_COROUTINE),
+            84 => Ok(Self::FNRETTHUNK_EXTERN),
+            85 => Ok(Self::SKIP_PROFILE),
+            86 => Ok(Self::MEMORY),
+            87 => Ok(Self::NOFPCLASS),
+            88 => Ok(Self::OPTIMIZE_FOR_DEBUGGING),
+            89 => Ok(Self::WRITABLE),
+            90 => Ok(Self::CORO_ONLY_DESTROY_WHEN_COMPLETE),
+            91 => Ok(Self::DEAD_ON_UNWIND),
+            92 => Ok(Self::RANGE),
+            93 => Ok(Self::SANITIZE_NUMERICAL_STABILITY),
+            94 => Ok(Self::INITIALIZES),
+            95 => Ok(Self::HYBRID_PATCHABLE),
+            96 => Ok(Self::SANITIZE_REALTIME),
+            97 => Ok(Self::SANITIZE_REALTIME_BLOCKING),
+            98 => Ok(Self::CORO_ELIDE_SAFE),
+            99 => Ok(Self::NO_EXT),
+            100 => Ok(Self::NO_DIVERGENCE_SOURCE),
+            101 => Ok(Self::SANITIZE_TYPE),
+            102 => Ok(Self::CAPTURES),
+            103 => Ok(Self::DEAD_ON_RETURN),
+            _ => Err(anyhow!("'{:?}' is not a valid attribute kind.", v)),
+        }
+    }
+
+    /// Maps the keyword used by the textual IR to its attribute kind.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "align" => Some(Self::ALIGNMENT),
+            "alwaysinline" => Some(Self::ALWAYS_INLINE),
+            "byval" => Some(Self::BY_VAL),
+            "inlinehint" => Some(Self::INLINE_HINT),
+            "inreg" => Some(Self::IN_REG),
+            "minsize" => Some(Self::MIN_SIZE),
+            "naked" => Some(Self::NAKED),
+            "nest" => Some(Self::NEST),
+            "noalias" => Some(Self::NO_ALIAS),
+            "nobuiltin" => Some(Self::NO_BUILTIN),
+            "nocapture" => Some(Self::NO_CAPTURE),
+            "noduplicate" => Some(Self::NO_DUPLICATE),
+            "noimplicitfloat" => Some(Self::NO_IMPLICIT_FLOAT),
+            "noinline" => Some(Self::NO_INLINE),
+            "nonlazybind" => Some(Self::NON_LAZY_BIND),
+            "noredzone" => Some(Self::NO_RED_ZONE),
+            "noreturn" => Some(Self::NO_RETURN),
+            "nounwind" => Some(Self::NO_UNWIND),
+            "optsize" => Some(Self::OPTIMIZE_FOR_SIZE),
+            "readnone" => Some(Self::READ_NONE),
+            "readonly" => Some(Self::READ_ONLY),
+            "returned" => Some(Self::RETURNED),
+            "returns_twice" => Some(Self::RETURNS_TWICE),
+            "signext" => Some(Self::S_EXT),
+            "alignstack" => Some(Self::STACK_ALIGNMENT),
+            "ssp" => Some(Self::STACK_PROTECT),
+            "sspreq" => Some(Self::STACK_PROTECT_REQ),
+            "sspstrong" => Some(Self::STACK_PROTECT_STRONG),
+            "sret" => Some(Self::STRUCT_RET),
+            "sanitize_address" => Some(Self::SANITIZE_ADDRESS),
+            "sanitize_thread" => Some(Self::SANITIZE_THREAD),
+            "sanitize_memory" => Some(Self::SANITIZE_MEMORY),
+            "uwtable" => Some(Self::UW_TABLE),
+            "zeroext" => Some(Self::Z_EXT),
+            "builtin" => Some(Self::BUILTIN),
+            "cold" => Some(Self::COLD),
+            "optnone" => Some(Self::OPTIMIZE_NONE),
+            "inalloca" => Some(Self::IN_ALLOCA),
+            "nonnull" => Some(Self::NON_NULL),
+            "jumptable" => Some(Self::JUMP_TABLE),
+            "dereferenceable" => Some(Self::DEREFERENCEABLE),
+            "dereferenceable_or_null" => Some(Self::DEREFERENCEABLE_OR_NULL),
+            "convergent" => Some(Self::CONVERGENT),
+            "safestack" => Some(Self::SAFESTACK),
+            "argmemonly" => Some(Self::ARGMEMONLY),
+            "swiftself" => Some(Self::SWIFT_SELF),
+            "swifterror" => Some(Self::SWIFT_ERROR),
+            "norecurse" => Some(Self::NO_RECURSE),
+            "inaccessiblememonly" => Some(Self::INACCESSIBLEMEM_ONLY),
+            "inaccessiblemem_or_argmemonly" => Some(Self::INACCESSIBLEMEM_OR_ARGMEMONLY),
+            "allocsize" => Some(Self::ALLOC_SIZE),
+            "writeonly" => Some(Self::WRITEONLY),
+            "speculatable" => Some(Self::SPECULATABLE),
+            "strictfp" => Some(Self::STRICT_FP),
+            "sanitize_hwaddress" => Some(Self::SANITIZE_HWADDRESS),
+            "nocf_check" => Some(Self::NOCF_CHECK),
+            "optforfuzzing" => Some(Self::OPT_FOR_FUZZING),
+            "shadowcallstack" => Some(Self::SHADOWCALLSTACK),
+            "speculative_load_hardening" => Some(Self::SPECULATIVE_LOAD_HARDENING),
+            "immarg" => Some(Self::IMMARG),
+            "willreturn" => Some(Self::WILLRETURN),
+            "nofree" => Some(Self::NOFREE),
+            "nosync" => Some(Self::NOSYNC),
+            "sanitize_memtag" => Some(Self::SANITIZE_MEMTAG),
+            "preallocated" => Some(Self::PREALLOCATED),
+            "nomerge" => Some(Self::NO_MERGE),
+            "null_pointer_is_valid" => Some(Self::NULL_POINTER_IS_VALID),
+            "noundef" => Some(Self::NOUNDEF),
+            "byref" => Some(Self::BYREF),
+            "mustprogress" => Some(Self::MUSTPROGRESS),
+            "nocallback" => Some(Self::NO_CALLBACK),
+            "hot" => Some(Self::HOT),
+            "noprofile" => Some(Self::NO_PROFILE),
+            "vscale_range" => Some(Self::VSCALE_RANGE),
+            "swiftasync" => Some(Self::SWIFT_ASYNC),
+            "nosanitize_coverage" => Some(Self::NO_SANITIZE_COVERAGE),
+            "elementtype" => Some(Self::ELEMENTTYPE),
+            "disable_sanitizer_instrumentation" => Some(Self::DISABLE_SANITIZER_INSTRUMENTATION),
+            "nosanitize_bounds" => Some(Self::NO_SANITIZE_BOUNDS),
+            "allocalign" => Some(Self::ALLOC_ALIGN),
+            "allocptr" => Some(Self::ALLOCATED_POINTER),
+            "allockind" => Some(Self::ALLOC_KIND),
+            "presplitcoroutine" => Some(Self::PRESPLIT_COROUTINE),
+            "fn_ret_thunk_extern" => Some(Self::FNRETTHUNK_EXTERN),
+            "skipprofile" => Some(Self::SKIP_PROFILE),
+            "memory" => Some(Self::MEMORY),
+            "nofpclass" => Some(Self::NOFPCLASS),
+            "optdebug" => Some(Self::OPTIMIZE_FOR_DEBUGGING),
+            "writable" => Some(Self::WRITABLE),
+            "coro_only_destroy_when_complete" => Some(Self::CORO_ONLY_DESTROY_WHEN_COMPLETE),
+            "dead_on_unwind" => Some(Self::DEAD_ON_UNWIND),
+            "range" => Some(Self::RANGE),
+            "sanitize_numerical_stability" => Some(Self::SANITIZE_NUMERICAL_STABILITY),
+            "initializes" => Some(Self::INITIALIZES),
+            "hybrid_patchable" => Some(Self::HYBRID_PATCHABLE),
+            "sanitize_realtime" => Some(Self::SANITIZE_REALTIME),
+            "sanitize_realtime_blocking" => Some(Self::SANITIZE_REALTIME_BLOCKING),
+            "coro_elide_safe" => Some(Self::CORO_ELIDE_SAFE),
+            "noext" => Some(Self::NO_EXT),
+            "nodivergencesource" => Some(Self::NO_DIVERGENCE_SOURCE),
+            "sanitize_type" => Some(Self::SANITIZE_TYPE),
+            "captures" => Some(Self::CAPTURES),
+            "dead_on_return" => Some(Self::DEAD_ON_RETURN),
+            _ => None,
         }
     }
 }
@@ -525,12 +662,12 @@ pub enum AttributeCode {
 }
 
 impl AttributeCode {
-    pub fn from_u64(v: u64) -> Self {
+    pub fn from_u64(v: u64) -> Result<Self> {
         match v {
-            1 => Self::ENTRY_OLD,
-            2 => Self::ENTRY,
-            3 => Self::GRP_CODE_ENTRY,
-            _ => unimplemented!(),
+            1 => Ok(Self::ENTRY_OLD),
+            2 => Ok(Self::ENTRY),
+            3 => Ok(Self::GRP_CODE_ENTRY),
+            _ => Err(anyhow!("'{:?}' is not a valid attribute code.", v)),
         }
     }
 }
@@ -582,42 +719,42 @@ pub enum ConstantsCode {
 }
 
 impl ConstantsCode {
-    pub fn from_u64(v: u64) -> Self {
+    pub fn from_u64(v: u64) -> Result<Self> {
         match v {
-            1 => Self::SETTYPE,
-            2 => Self::NULL,
-            3 => Self::UNDEF,
-            4 => Self::INTEGER,
-            5 => Self::WIDE_INTEGER,
-            6 => Self::FLOAT,
-            7 => Self::AGGREGATE,
-            8 => Self::STRING,
-            9 => Self::CSTRING,
-            10 => Self::CE_BINOP,
-            11 => Self::CE_CAST,
-            12 => Self::CE_GEP_OLD,
-            13 => Self::CE_SELECT,
-            14 => Self::CE_EXTRACTELT,
-            15 => Self::CE_INSERTELT,
-            16 => Self::CE_SHUFFLEVEC,
-            17 => Self::CE_CMP,
-            18 => Self::INLINEASM_OLD,
-            19 => Self::CE_SHUFVEC_EX,
-            20 => Self::CE_INBOUNDS_GEP,
-            21 => Self::BLOCKADDRESS,
-            22 => Self::DATA,
-            23 => Self::INLINEASM_OLD2,
-            24 => Self::CE_GEP_WITH_INRANGE_INDEX_OLD,
-            25 => Self::CE_UNOP,
-            26 => Self::POISON,
-            27 => Self::DSO_LOCAL_EQUIVALENT,
-            28 => Self::INLINEASM_OLD3,
-            29 => Self::NO_CFI_VALUE,
-            30 => Self::INLINEASM,
-            31 => Self::CE_GEP_WITH_INRANGE,
-            32 => Self::CE_GEP,
-            33 => Self::PTRAUTH,
-            _ => unimplemented!(),
+            1 => Ok(Self::SETTYPE),
+            2 => Ok(Self::NULL),
+            3 => Ok(Self::UNDEF),
+            4 => Ok(Self::INTEGER),
+            5 => Ok(Self::WIDE_INTEGER),
+            6 => Ok(Self::FLOAT),
+            7 => Ok(Self::AGGREGATE),
+            8 => Ok(Self::STRING),
+            9 => Ok(Self::CSTRING),
+            10 => Ok(Self::CE_BINOP),
+            11 => Ok(Self::CE_CAST),
+            12 => Ok(Self::CE_GEP_OLD),
+            13 => Ok(Self::CE_SELECT),
+            14 => Ok(Self::CE_EXTRACTELT),
+            15 => Ok(Self::CE_INSERTELT),
+            16 => Ok(Self::CE_SHUFFLEVEC),
+            17 => Ok(Self::CE_CMP),
+            18 => Ok(Self::INLINEASM_OLD),
+            19 => Ok(Self::CE_SHUFVEC_EX),
+            20 => Ok(Self::CE_INBOUNDS_GEP),
+            21 => Ok(Self::BLOCKADDRESS),
+            22 => Ok(Self::DATA),
+            23 => Ok(Self::INLINEASM_OLD2),
+            24 => Ok(Self::CE_GEP_WITH_INRANGE_INDEX_OLD),
+            25 => Ok(Self::CE_UNOP),
+            26 => Ok(Self::POISON),
+            27 => Ok(Self::DSO_LOCAL_EQUIVALENT),
+            28 => Ok(Self::INLINEASM_OLD3),
+            29 => Ok(Self::NO_CFI_VALUE),
+            30 => Ok(Self::INLINEASM),
+            31 => Ok(Self::CE_GEP_WITH_INRANGE),
+            32 => Ok(Self::CE_GEP),
+            33 => Ok(Self::PTRAUTH),
+            _ => Err(anyhow!("'{:?}' is not a valid constants code.", v)),
         }
     }
 }
@@ -679,56 +816,78 @@ pub enum MetadataCodes {
 }
 
 impl MetadataCodes {
-    pub fn from_u64(v: u64) -> Self {
+    pub fn from_u64(v: u64) -> Result<Self> {
         match v {
-            1 => Self::STRING_OLD,
-            2 => Self::VALUE,
-            3 => Self::NODE,
-            4 => Self::NAME,
-            5 => Self::DISTINCT_NODE,
-            6 => Self::KIND,
-            7 => Self::LOCATION,
-            8 => Self::OLD_NODE,
-            9 => Self::OLD_FN_NODE,
-            10 => Self::NAMED_NODE,
-            11 => Self::ATTACHMENT,
-            12 => Self::GENERIC_DEBUG,
-            13 => Self::SUBRANGE,
-            14 => Self::ENUMERATOR,
-            15 => Self::BASIC_TYPE,
-            16 => Self::FILE,
-            17 => Self::DERIVED_TYPE,
-            18 => Self::COMPOSITE_TYPE,
-            19 => Self::SUBROUTINE_TYPE,
-            20 => Self::COMPILE_UNIT,
-            21 => Self::SUBPROGRAM,
-            22 => Self::LEXICAL_BLOCK,
-            23 => Self::LEXICAL_BLOCK_FILE,
-            24 => Self::NAMESPACE,
-            25 => Self::TEMPLATE_TYPE,
-            26 => Self::TEMPLATE_VALUE,
-            27 => Self::GLOBAL_VAR,
-            28 => Self::LOCAL_VAR,
-            29 => Self::EXPRESSION,
-            30 => Self::OBJC_PROPERTY,
-            31 => Self::IMPORTED_ENTITY,
-            32 => Self::MODULE,
-            33 => Self::MACRO,
-            34 => Self::MACRO_FILE,
-            35 => Self::STRINGS,
-            36 => Self::GLOBAL_DECL_ATTACHMENT,
-            37 => Self::GLOBAL_VAR_EXPR,
-            38 => Self::INDEX_OFFSET,
-            39 => Self::INDEX,
-            40 => Self::LABEL,
-            41 => Self::STRING_TYPE,
-            42 => Self::FORTRAN_RESERVED_1,
-            43 => Self::FORTRAN_RESERVED_2,
-            44 => Self::COMMON_BLOCK,
-            45 => Self::GENERIC_SUBRANGE,
-            46 => Self::ARG_LIST,
-            47 => Self::ASSIGN_ID,
-            _ => unimplemented!(),
+            1 => Ok(Self::STRING_OLD),
+            2 => Ok(Self::VALUE),
+            3 => Ok(Self::NODE),
+            4 => Ok(Self::NAME),
+            5 => Ok(Self::DISTINCT_NODE),
+            6 => Ok(Self::KIND),
+            7 => Ok(Self::LOCATION),
+            8 => Ok(Self::OLD_NODE),
+            9 => Ok(Self::OLD_FN_NODE),
+            10 => Ok(Self::NAMED_NODE),
+            11 => Ok(Self::ATTACHMENT),
+            12 => Ok(Self::GENERIC_DEBUG),
+            13 => Ok(Self::SUBRANGE),
+            14 => Ok(Self::ENUMERATOR),
+            15 => Ok(Self::BASIC_TYPE),
+            16 => Ok(Self::FILE),
+            17 => Ok(Self::DERIVED_TYPE),
+            18 => Ok(Self::COMPOSITE_TYPE),
+            19 => Ok(Self::SUBROUTINE_TYPE),
+            20 => Ok(Self::COMPILE_UNIT),
+            21 => Ok(Self::SUBPROGRAM),
+            22 => Ok(Self::LEXICAL_BLOCK),
+            23 => Ok(Self::LEXICAL_BLOCK_FILE),
+            24 => Ok(Self::NAMESPACE),
+            25 => Ok(Self::TEMPLATE_TYPE),
+            26 => Ok(Self::TEMPLATE_VALUE),
+            27 => Ok(Self::GLOBAL_VAR),
+            28 => Ok(Self::LOCAL_VAR),
+            29 => Ok(Self::EXPRESSION),
+            30 => Ok(Self::OBJC_PROPERTY),
+            31 => Ok(Self::IMPORTED_ENTITY),
+            32 => Ok(Self::MODULE),
+            33 => Ok(Self::MACRO),
+            34 => Ok(Self::MACRO_FILE),
+            35 => Ok(Self::STRINGS),
+            36 => Ok(Self::GLOBAL_DECL_ATTACHMENT),
+            37 => Ok(Self::GLOBAL_VAR_EXPR),
+            38 => Ok(Self::INDEX_OFFSET),
+            39 => Ok(Self::INDEX),
+            40 => Ok(Self::LABEL),
+            41 => Ok(Self::STRING_TYPE),
+            42 => Ok(Self::FORTRAN_RESERVED_1),
+            43 => Ok(Self::FORTRAN_RESERVED_2),
+            44 => Ok(Self::COMMON_BLOCK),
+            45 => Ok(Self::GENERIC_SUBRANGE),
+            46 => Ok(Self::ARG_LIST),
+            47 => Ok(Self::ASSIGN_ID),
+            _ => Err(anyhow!("'{:?}' is not a valid metadata code.", v)),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+#[repr(u64)]
+#[allow(non_camel_case_types)]
+pub enum ValueSymtabCodes {
+    ENTRY = 1,          // VST_ENTRY:          [valueid, namechar x N]
+    BBENTRY = 2,        // VST_BBENTRY:        [bbid, namechar x N]
+    FNENTRY = 3,        // VST_FNENTRY:        [valueid, offset, namechar x N]
+    COMBINED_ENTRY = 5, // VST_COMBINED_ENTRY: [valueid, refguid]
+}
+
+impl ValueSymtabCodes {
+    pub fn from_u64(v: u64) -> Result<Self> {
+        match v {
+            1 => Ok(Self::ENTRY),
+            2 => Ok(Self::BBENTRY),
+            3 => Ok(Self::FNENTRY),
+            5 => Ok(Self::COMBINED_ENTRY),
+            _ => Err(anyhow!("'{:?}' is not a valid value symbol table code.", v)),
         }
     }
 }
@@ -829,74 +988,74 @@ pub enum FunctionCodes {
 }
 
 impl FunctionCodes {
-    pub fn from_u64(v: u64) -> Self {
+    pub fn from_u64(v: u64) -> Result<Self> {
         match v {
-            1 => Self::DECLAREBLOCKS,
-            2 => Self::INST_BINOP,
-            3 => Self::INST_CAST,
-            4 => Self::INST_GEP_OLD,
-            5 => Self::INST_SELECT,
-            6 => Self::INST_EXTRACTELT,
-            7 => Self::INST_INSERTELT,
-            8 => Self::INST_SHUFFLEVEC,
-            9 => Self::INST_CMP,
-            10 => Self::INST_RET,
-            11 => Self::INST_BR,
-            12 => Self::INST_SWITCH,
-            13 => Self::INST_INVOKE,
+            1 => Ok(Self::DECLAREBLOCKS),
+            2 => Ok(Self::INST_BINOP),
+            3 => Ok(Self::INST_CAST),
+            4 => Ok(Self::INST_GEP_OLD),
+            5 => Ok(Self::INST_SELECT),
+            6 => Ok(Self::INST_EXTRACTELT),
+            7 => Ok(Self::INST_INSERTELT),
+            8 => Ok(Self::INST_SHUFFLEVEC),
+            9 => Ok(Self::INST_CMP),
+            10 => Ok(Self::INST_RET),
+            11 => Ok(Self::INST_BR),
+            12 => Ok(Self::INST_SWITCH),
+            13 => Ok(Self::INST_INVOKE),
             // 14 is Unused.
-            15 => Self::INST_UNREACHABLE,
-            16 => Self::INST_PHI,
+            15 => Ok(Self::INST_UNREACHABLE),
+            16 => Ok(Self::INST_PHI),
             // 17 is Unused.
             // 18 is Unused.
-            19 => Self::INST_ALLOCA,
-            20 => Self::INST_LOAD,
+            19 => Ok(Self::INST_ALLOCA),
+            20 => Ok(Self::INST_LOAD),
             // 21 is Unused.
             // 22 is Unused.
-            23 => Self::INST_VAARG,
-            24 => Self::INST_STORE_OLD,
+            23 => Ok(Self::INST_VAARG),
+            24 => Ok(Self::INST_STORE_OLD),
             // 25 is Unused.
-            26 => Self::INST_EXTRACTVAL,
-            27 => Self::INST_INSERTVAL,
-            28 => Self::INST_CMP2,
-            29 => Self::INST_VSELECT,
-            30 => Self::INST_INBOUNDS_GEP_OLD,
-            31 => Self::INST_INDIRECTBR,
+            26 => Ok(Self::INST_EXTRACTVAL),
+            27 => Ok(Self::INST_INSERTVAL),
+            28 => Ok(Self::INST_CMP2),
+            29 => Ok(Self::INST_VSELECT),
+            30 => Ok(Self::INST_INBOUNDS_GEP_OLD),
+            31 => Ok(Self::INST_INDIRECTBR),
             // 32 is Unused.
-            33 => Self::DEBUG_LOC_AGAIN,
-            34 => Self::INST_CALL,
-            35 => Self::DEBUG_LOC,
-            36 => Self::INST_FENCE,
-            37 => Self::INST_CMPXCHG_OLD,
-            38 => Self::INST_ATOMICRMW_OLD,
-            39 => Self::INST_RESUME,
-            40 => Self::INST_LANDINGPAD_OLD,
-            41 => Self::INST_LOADATOMIC,
-            42 => Self::INST_STOREATOMIC_OLD,
-            43 => Self::INST_GEP,
-            44 => Self::INST_STORE,
-            45 => Self::INST_STOREATOMIC,
-            46 => Self::INST_CMPXCHG,
-            47 => Self::INST_LANDINGPAD,
-            48 => Self::INST_CLEANUPRET,
-            49 => Self::INST_CATCHRET,
-            50 => Self::INST_CATCHPAD,
-            51 => Self::INST_CLEANUPPAD,
-            52 => Self::INST_CATCHSWITCH,
+            33 => Ok(Self::DEBUG_LOC_AGAIN),
+            34 => Ok(Self::INST_CALL),
+            35 => Ok(Self::DEBUG_LOC),
+            36 => Ok(Self::INST_FENCE),
+            37 => Ok(Self::INST_CMPXCHG_OLD),
+            38 => Ok(Self::INST_ATOMICRMW_OLD),
+            39 => Ok(Self::INST_RESUME),
+            40 => Ok(Self::INST_LANDINGPAD_OLD),
+            41 => Ok(Self::INST_LOADATOMIC),
+            42 => Ok(Self::INST_STOREATOMIC_OLD),
+            43 => Ok(Self::INST_GEP),
+            44 => Ok(Self::INST_STORE),
+            45 => Ok(Self::INST_STOREATOMIC),
+            46 => Ok(Self::INST_CMPXCHG),
+            47 => Ok(Self::INST_LANDINGPAD),
+            48 => Ok(Self::INST_CLEANUPRET),
+            49 => Ok(Self::INST_CATCHRET),
+            50 => Ok(Self::INST_CATCHPAD),
+            51 => Ok(Self::INST_CLEANUPPAD),
+            52 => Ok(Self::INST_CATCHSWITCH),
             // 53 is Unused.
             // 54 is Unused.
-            55 => Self::OPERAND_BUNDLE,
-            56 => Self::INST_UNOP,
-            57 => Self::INST_CALLBR,
-            58 => Self::INST_FREEZE,
-            59 => Self::INST_ATOMICRMW,
-            60 => Self::BLOCKADDR_USERS,
-            61 => Self::DEBUG_RECORD_VALUE,
-            62 => Self::DEBUG_RECORD_DECLARE,
-            63 => Self::DEBUG_RECORD_ASSIGN,
-            64 => Self::DEBUG_RECORD_VALUE_SIMPLE,
-            65 => Self::DEBUG_RECORD_LABEL,
-            _ => unimplemented!(),
+            55 => Ok(Self::OPERAND_BUNDLE),
+            56 => Ok(Self::INST_UNOP),
+            57 => Ok(Self::INST_CALLBR),
+            58 => Ok(Self::INST_FREEZE),
+            59 => Ok(Self::INST_ATOMICRMW),
+            60 => Ok(Self::BLOCKADDR_USERS),
+            61 => Ok(Self::DEBUG_RECORD_VALUE),
+            62 => Ok(Self::DEBUG_RECORD_DECLARE),
+            63 => Ok(Self::DEBUG_RECORD_ASSIGN),
+            64 => Ok(Self::DEBUG_RECORD_VALUE_SIMPLE),
+            65 => Ok(Self::DEBUG_RECORD_LABEL),
+            _ => Err(anyhow!("'{:?}' is not a valid function code.", v)),
         }
     }
 }
@@ -922,22 +1081,65 @@ pub enum CastOpCode {
 }
 
 impl CastOpCode {
-    pub fn from_u64(v: u64) -> Self {
+    pub fn from_u64(v: u64) -> Result<Self> {
+        match v {
+            0 => Ok(Self::TRUNC),
+            1 => Ok(Self::ZEXT),
+            2 => Ok(Self::SEXT),
+            3 => Ok(Self::FPTOUI),
+            4 => Ok(Self::FPTOSI),
+            5 => Ok(Self::UITOFP),
+            6 => Ok(Self::SITOFP),
+            7 => Ok(Self::FPTRUNC),
+            8 => Ok(Self::FPEXT),
+            9 => Ok(Self::PTRTOINT),
+            10 => Ok(Self::INTTOPTR),
+            11 => Ok(Self::BITCAST),
+            12 => Ok(Self::ADDRSPACECAST),
+            _ => Err(anyhow!("'{:?}' is not a valid cast opcode.", v)),
+        }
+    }
+}
+
+/// Float and integer instructions share codes, `fadd` is `ADD` on a float
+/// type and `fdiv`/`frem` are `SDIV`/`SREM`.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+#[repr(u64)]
+#[allow(non_camel_case_types)]
+pub enum BinaryOpCode {
+    #[default]
+    ADD = 0,
+    SUB = 1,
+    MUL = 2,
+    UDIV = 3,
+    SDIV = 4,
+    UREM = 5,
+    SREM = 6,
+    SHL = 7,
+    LSHR = 8,
+    ASHR = 9,
+    AND = 10,
+    OR = 11,
+    XOR = 12,
+}
+
+impl BinaryOpCode {
+    pub fn from_u64(v: u64) -> Result<Self> {
         match v {
-            0 => Self::TRUNC,
-            1 => Self::ZEXT,
-            2 => Self::SEXT,
-            3 => Self::FPTOUI,
-            4 => Self::FPTOSI,
-            5 => Self::UITOFP,
-            6 => Self::SITOFP,
-            7 => Self::FPTRUNC,
-            8 => Self::FPEXT,
-            9 => Self::PTRTOINT,
-            10 => Self::INTTOPTR,
-            11 => Self::BITCAST,
-            12 => Self::ADDRSPACECAST,
-            _ => unimplemented!(),
+            0 => Ok(Self::ADD),
+            1 => Ok(Self::SUB),
+            2 => Ok(Self::MUL),
+            3 => Ok(Self::UDIV),
+            4 => Ok(Self::SDIV),
+            5 => Ok(Self::UREM),
+            6 => Ok(Self::SREM),
+            7 => Ok(Self::SHL),
+            8 => Ok(Self::LSHR),
+            9 => Ok(Self::ASHR),
+            10 => Ok(Self::AND),
+            11 => Ok(Self::OR),
+            12 => Ok(Self::XOR),
+            _ => Err(anyhow!("'{:?}' is not a valid binary opcode.", v)),
         }
     }
 }
@@ -97,7 +97,6 @@ impl BitCursor {
     /// or would like to do a relative (start or end) seek, use the [`Seek`](std::io::Seek)
     /// implementation.
     pub fn seek_bit(&mut self, pos: usize) -> Result<()> {
-        println!();
         debug(&format!("seek_bit: seeking to {}", pos));
 
         // Get the byte corresponding to this bit.
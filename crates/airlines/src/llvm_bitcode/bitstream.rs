@@ -1,7 +1,7 @@
 use anyhow::{Result, anyhow};
 use std::io::{Seek, SeekFrom};
 
-use super::{BitCursor, StreamEntry, StreamParser, debug};
+use super::{BitCursor, Block, StreamEntry, StreamParser, debug, known_block_name};
 
 #[derive(Debug)]
 pub struct Signature {
@@ -14,6 +14,9 @@ pub struct Signature {
 
 pub const BITCODE_WRAPPER_MAGIC: u32 = 0x0b17c0de;
 
+/// `BC` followed by `0xC0DE`, read as a little-endian word.
+pub const BITCODE_MAGIC: u32 = 0xdec04342;
+
 #[derive(Debug)]
 pub struct Bitstream {
     pub magic: u32,
@@ -26,8 +29,13 @@ impl Bitstream {
             return Err(anyhow!("Bad Container: input is not 4-byte aligned."));
         }
 
+        let magic = cursor.read(32)? as u32;
+        if magic != BITCODE_MAGIC {
+            return Err(anyhow!("Bad Container: not LLVM bitcode."));
+        }
+
         Ok(Self {
-            magic: cursor.read(32)? as u32,
+            magic,
             parser: StreamParser::new(cursor),
         })
     }
@@ -50,8 +58,11 @@ impl Bitstream {
                 cpu_type: cursor.read(32)? as u32,
             };
 
-            let actual_length = (signature.size as usize) + 20;
-            let mut cur = BitCursor::new_with_len(inner, actual_length)?;
+            // The bitcode is `size` bytes at `offset`, anything after it
+            // (Apple tools pad the wrapper) isn't part of the stream.
+            let actual_length = signature.offset as usize + signature.size as usize;
+            let mut cur = BitCursor::new_with_len(inner, actual_length)
+                .map_err(|_| anyhow!("Bad Wrapper: bitcode goes past the end of the input."))?;
 
             cur.seek(SeekFrom::Start(signature.offset.into()))?;
             return Ok((Some(signature), Self::from_cursor(cur)?));
@@ -69,6 +80,34 @@ impl Bitstream {
     pub fn advance(&mut self) -> Result<StreamEntry> {
         self.parser.advance()
     }
+
+    pub fn skip_block(&mut self, block: &Block) -> Result<()> {
+        self.parser.skip_block(block)
+    }
+
+    pub fn jump_to_block(&mut self, bit_position: usize, abbrev_id_width: u64) -> Result<Block> {
+        self.parser.jump_to_block(bit_position, abbrev_id_width)
+    }
+
+    pub fn tell_bit(&self) -> usize {
+        self.parser.tell_bit()
+    }
+
+    /// The name BLOCKINFO gave the block, or LLVM's name for it.
+    pub fn block_name(&self, block_id: u64) -> Option<&str> {
+        self.parser
+            .block_name(block_id)
+            .or(known_block_name(block_id))
+    }
+
+    /// The name BLOCKINFO gave the record, if any.
+    pub fn record_name(&self, block_id: u64, code: u64) -> Option<&str> {
+        self.parser.record_name(block_id, code)
+    }
+
+    pub fn seek_bit(&mut self, bit_position: usize) -> Result<()> {
+        self.parser.seek_bit(bit_position)
+    }
 }
 
 impl Iterator for Bitstream {
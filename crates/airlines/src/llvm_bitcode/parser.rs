@@ -14,6 +14,10 @@ pub struct StreamParser {
     cursor: BitCursor,
     scopes: Vec<Scope>,
     blockinfo: HashMap<u64, Vec<Abbrev>>,
+    /// Names given by BLOCKNAME, by block ID.
+    block_names: HashMap<u64, String>,
+    /// Names given by SETRECORDNAME, by block ID and record code.
+    record_names: HashMap<(u64, u64), String>,
 }
 
 impl StreamParser {
@@ -22,9 +26,19 @@ impl StreamParser {
             cursor,
             scopes: vec![Scope::default()],
             blockinfo: Default::default(),
+            block_names: Default::default(),
+            record_names: Default::default(),
         }
     }
 
+    pub fn block_name(&self, block_id: u64) -> Option<&str> {
+        self.block_names.get(&block_id).map(|x| x.as_str())
+    }
+
+    pub fn record_name(&self, block_id: u64, code: u64) -> Option<&str> {
+        self.record_names.get(&(block_id, code)).map(|x| x.as_str())
+    }
+
     fn scope(&self) -> &Scope {
         #[allow(clippy::unwrap_used)]
         self.scopes.last().unwrap()
@@ -119,23 +133,56 @@ impl StreamParser {
 
         let record = Record::from_unabbrev(code, fields);
         if self.scope().is_blockinfo() {
-            let code = BlockInfoCode::from_u64(record.code)?;
-            match code {
-                BlockInfoCode::SETBID => {
-                    let block_id = record.fields[0];
-                    debug(&format!("SETBID: BLOCKINFO block ID is now {}", block_id));
-                    self.scope_mut().set_blockinfo_block_id(block_id)?;
-                }
-                BlockInfoCode::BLOCKNAME => debug("Skipping BLOCKNAME code in BLOCKINFO..."),
-                BlockInfoCode::SETRECORDNAME => {
-                    debug(&format!("Skipping SETRECORDNAME code in BLOCKINFO..."))
-                }
-            }
+            self.parse_blockinfo_record(record)?;
+            return Ok(None);
         }
 
         Ok(Some(StreamEntry::Record(record)))
     }
 
+    /// BLOCKINFO records describe other blocks, they're never handed out.
+    fn parse_blockinfo_record(&mut self, record: Record) -> Result<()> {
+        let name = |fields: &[u64]| fields.iter().map(|x| *x as u8 as char).collect::<String>();
+
+        // Unknown codes are ignored, like LLVM does.
+        let Ok(code) = BlockInfoCode::from_u64(record.code) else {
+            return Ok(());
+        };
+
+        if code == BlockInfoCode::SETBID {
+            let block_id = *record
+                .fields
+                .first()
+                .ok_or(anyhow!("SETBID without a block ID."))?;
+            debug(&format!("SETBID: BLOCKINFO block ID is now {}", block_id));
+
+            return self.scope_mut().set_blockinfo_block_id(block_id);
+        }
+
+        let block_id = self
+            .scope()
+            .blockinfo_block_id()
+            .ok_or(anyhow!("{:?} in BLOCKINFO, but no preceding SETBID.", code))?;
+
+        match code {
+            BlockInfoCode::BLOCKNAME => {
+                self.block_names.insert(block_id, name(&record.fields));
+            }
+            BlockInfoCode::SETRECORDNAME => {
+                let (record_code, record_name) = record
+                    .fields
+                    .split_first()
+                    .ok_or(anyhow!("SETRECORDNAME without a record code."))?;
+
+                self.record_names
+                    .insert((block_id, *record_code), name(record_name));
+            }
+            BlockInfoCode::SETBID => unreachable!(),
+        }
+
+        Ok(())
+    }
+
     pub fn define_abbrev(&mut self) -> Result<()> {
         let abbrev = Abbrev::new(&mut self.cursor)?;
         debug(&format!("New Abbrev: {:?}", abbrev));
@@ -191,11 +238,46 @@ impl StreamParser {
 
         Ok(Some(StreamEntry::SubBlock(Block {
             block_id,
-            bit_pos: self.cursor.position_in_bits() as u64,
+            bit_pos: self.cursor.tell_bit() as u64,
             len: block_len,
+            abbrev_width: new_width,
         })))
     }
 
+    /// Jumps past the rest of a block that was just entered, as if its
+    /// `END_BLOCK` had been read.
+    pub fn skip_block(&mut self, block: &Block) -> Result<()> {
+        self.cursor
+            .seek_bit((block.bit_pos + block.len * 8) as usize)?;
+        self.scopes.pop();
+
+        Ok(())
+    }
+
+    /// Enters the block starting at `bit_position`, `abbrev_id_width` being
+    /// the width used by its parent.
+    pub fn jump_to_block(&mut self, bit_position: usize, abbrev_id_width: u64) -> Result<Block> {
+        self.cursor.seek_bit(bit_position)?;
+
+        let id: AbbrevId = self.cursor.read(abbrev_id_width as usize)?.into();
+        if !matches!(id, AbbrevId::Reserved(ReservedAbbrevId::ENTER_SUBBLOCK)) {
+            return Err(anyhow!("No block at bit position {}.", bit_position));
+        }
+
+        match self.enter_block()? {
+            Some(StreamEntry::SubBlock(block)) => Ok(block),
+            _ => Err(anyhow!("Can't jump to a BLOCKINFO block.")),
+        }
+    }
+
+    pub fn tell_bit(&self) -> usize {
+        self.cursor.tell_bit()
+    }
+
+    pub fn seek_bit(&mut self, bit_position: usize) -> Result<()> {
+        self.cursor.seek_bit(bit_position)
+    }
+
     pub fn exit_block(&mut self) -> Result<Option<StreamEntry>> {
         self.cursor.align32();
 
@@ -3,8 +3,11 @@ pub type Fields = Vec<u64>;
 #[derive(Debug)]
 pub struct Block {
     pub block_id: u64,
+    /// Where the block's contents start, right after its length.
     pub bit_pos: u64,
+    /// Length of the contents in bytes, including the `END_BLOCK`.
     pub len: u64,
+    pub abbrev_width: u64,
 }
 
 #[derive(Clone, Debug)]
@@ -0,0 +1,692 @@
+use std::{
+    fmt::Write as _,
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+
+use airlines::{
+    air_codegen::{
+        AirToSpirV, AirToSpirVOptions, DepthRange, DescriptorBindingStrategy, VulkanEnvironment,
+    },
+    air_parser::AirFile,
+    llvm_bitcode::{BITCODE_WRAPPER_MAGIC, Bitstream, StreamEntry},
+    metal_lib::MTLLibraryParser,
+    spirv_builder::SpirVBuilder,
+    spirv_parser::SpirVModule,
+    spirv_reflection::{ReflectedVariable, SpirVReflection},
+};
+use anyhow::{Context, Result, anyhow};
+
+const USAGE: &str = "\
+Usage: airlines <command> <input> [options]
+
+Commands:
+  inspect <file.metallib>     Print the library header and function list
+  dump-bitstream <input>      Print the block/record tree of AIR bitcode
+  air2spv <input>             Translate AIR to SPIR-V
+  disasm <input>              Print SPIR-V assembly
+  reflect <input>             Print entry points and resources as JSON
+
+AIR inputs can be .metallib, .air bitcode or .ll text. `disasm` and
+`reflect` also take SPIR-V, anything else gets translated first.
+
+Options:
+  -o, --output <file>         Write here instead of stdout (air2spv defaults
+                              to the input with a .spv extension)
+  --spirv-version <x.y>       SPIR-V version to emit (default 1.0)
+  --vulkan <1.0|1.1|1.2|1.3>  Target Vulkan environment (default 1.0)
+  --flip-y                    Negate position.y
+  --depth-range <range>       zero-to-one (default) or negative-one-to-one
+  --set-per-resource-kind     Buffers, textures and samplers in sets 0, 1, 2
+  --debug-info                Emit OpLine from the AIR debug locations
+  --strip-names               Don't emit OpName/OpMemberName
+  -h, --help                  Print this message
+
+Exit codes: 0 on success, 1 on errors and 2 on bad usage.";
+
+/// Bad command line, reported with the usage text.
+#[derive(Debug)]
+struct UsageError(String);
+
+impl std::fmt::Display for UsageError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for UsageError {}
+
+fn usage_error(message: String) -> anyhow::Error {
+    anyhow::Error::new(UsageError(message))
+}
+
+const COMMANDS: &[&str] = &["inspect", "dump-bitstream", "air2spv", "disasm", "reflect"];
+
+#[derive(Debug, Default)]
+struct CommandLine {
+    command: String,
+    input: PathBuf,
+    output: Option<PathBuf>,
+    options: AirToSpirVOptions,
+    /// Whether any translation option was given.
+    translates: bool,
+}
+
+impl CommandLine {
+    fn parse(arguments: &[String]) -> Result<Self> {
+        let mut arguments = arguments.iter();
+        let mut result = Self {
+            command: arguments
+                .next()
+                .ok_or(usage_error("Missing command.".to_string()))?
+                .clone(),
+            ..Default::default()
+        };
+
+        if !COMMANDS.contains(&result.command.as_str()) {
+            return Err(usage_error(format!(
+                "Unknown command `{}`.",
+                result.command
+            )));
+        }
+
+        let mut input = None;
+        while let Some(argument) = arguments.next() {
+            let mut value = |name: &str| {
+                arguments
+                    .next()
+                    .cloned()
+                    .ok_or(usage_error(format!("`{}` needs a value.", name)))
+            };
+
+            match argument.as_str() {
+                "-o" | "--output" => result.output = Some(PathBuf::from(value(argument)?)),
+                "--spirv-version" => {
+                    let version = value(argument)?;
+                    result.options.spirv_version = version
+                        .split_once('.')
+                        .and_then(|(major, minor)| Some((major.parse().ok()?, minor.parse().ok()?)))
+                        .ok_or(usage_error(format!(
+                            "Invalid SPIR-V version `{}`.",
+                            version
+                        )))?;
+                    result.translates = true;
+                }
+                "--vulkan" => {
+                    result.options.environment = match value(argument)?.as_str() {
+                        "1.0" => VulkanEnvironment::Vulkan1_0,
+                        "1.1" => VulkanEnvironment::Vulkan1_1,
+                        "1.2" => VulkanEnvironment::Vulkan1_2,
+                        "1.3" => VulkanEnvironment::Vulkan1_3,
+                        version => {
+                            return Err(usage_error(format!(
+                                "Invalid Vulkan version `{}`.",
+                                version
+                            )));
+                        }
+                    };
+                    result.translates = true;
+                }
+                "--depth-range" => {
+                    result.options.depth_range = match value(argument)?.as_str() {
+                        "zero-to-one" => DepthRange::ZeroToOne,
+                        "negative-one-to-one" => DepthRange::NegativeOneToOne,
+                        range => {
+                            return Err(usage_error(format!("Invalid depth range `{}`.", range)));
+                        }
+                    };
+                    result.translates = true;
+                }
+                "--flip-y" => {
+                    result.options.flip_y = true;
+                    result.translates = true;
+                }
+                "--set-per-resource-kind" => {
+                    result.options.descriptor_bindings =
+                        DescriptorBindingStrategy::SetPerResourceKind;
+                    result.translates = true;
+                }
+                "--debug-info" => {
+                    result.options.debug_info = true;
+                    result.translates = true;
+                }
+                "--strip-names" => {
+                    result.options.emit_names = false;
+                    result.translates = true;
+                }
+                option if option.starts_with('-') => {
+                    return Err(usage_error(format!("Unknown option `{}`.", option)));
+                }
+                path => match input {
+                    None => input = Some(PathBuf::from(path)),
+                    Some(_) => return Err(usage_error(format!("Unexpected argument `{}`.", path))),
+                },
+            }
+        }
+
+        result.input = input.ok_or(usage_error(format!(
+            "`{}` needs an input file.",
+            result.command
+        )))?;
+
+        Ok(result)
+    }
+}
+
+enum Input {
+    MetalLibrary(Box<MTLLibraryParser>),
+    Bitcode(Vec<u8>),
+    Text(String),
+    SpirV(Vec<u8>),
+}
+
+const SPIRV_MAGIC: u32 = 0x07230203;
+
+impl Input {
+    fn read(path: &Path) -> Result<Self> {
+        let content =
+            std::fs::read(path).with_context(|| format!("Can't read `{}`", path.display()))?;
+
+        let magic = content
+            .get(..4)
+            .map(|x| u32::from_le_bytes([x[0], x[1], x[2], x[3]]));
+
+        if content.starts_with(b"MTLB") {
+            let mut parser = MTLLibraryParser::default();
+            parser
+                .read(&content)
+                .with_context(|| format!("`{}` is not a valid Metal library", path.display()))?;
+            return Ok(Self::MetalLibrary(Box::new(parser)));
+        }
+
+        if magic == Some(SPIRV_MAGIC) {
+            return Ok(Self::SpirV(content));
+        }
+
+        if content.starts_with(b"BC\xC0\xDE") || magic == Some(BITCODE_WRAPPER_MAGIC) {
+            return Ok(Self::Bitcode(content));
+        }
+
+        match String::from_utf8(content) {
+            Ok(text) => Ok(Self::Text(text)),
+            Err(_) => Err(anyhow!(
+                "`{}` is not a Metal library, AIR or SPIR-V.",
+                path.display()
+            )),
+        }
+    }
+
+    fn bitcode(&self) -> Result<Vec<u8>> {
+        match self {
+            Self::MetalLibrary(parser) => {
+                let signature = &parser.signature;
+                Ok(parser.content[signature.bitcode_offset as usize
+                    ..(signature.bitcode_offset + signature.bitcode_size) as usize]
+                    .to_vec())
+            }
+            Self::Bitcode(content) => Ok(content.clone()),
+            _ => Err(anyhow!("Expected AIR bitcode or a Metal library.")),
+        }
+    }
+
+    fn air(self) -> Result<AirFile> {
+        match self {
+            Self::MetalLibrary(parser) => parser
+                .shader
+                .air
+                .ok_or(anyhow!("The Metal library has no AIR.")),
+            Self::Bitcode(content) => airlines::air_parser::Parser::new(content)?.start(),
+            Self::Text(text) => airlines::air_text_parser::Parser::new(&text)?.start(),
+            Self::SpirV(_) => Err(anyhow!("Expected AIR, found SPIR-V.")),
+        }
+    }
+
+    fn spirv(self, options: &AirToSpirVOptions) -> Result<SpirVBuilder> {
+        match self {
+            Self::SpirV(content) => Ok(SpirVBuilder::from_module(
+                airlines::spirv_parser::Parser::new(content).start()?,
+            )),
+            input => {
+                let mut conversion = AirToSpirV::new_with_options(input.air()?, options.clone());
+                conversion.start()?;
+                Ok(conversion.output)
+            }
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    let arguments = std::env::args().skip(1).collect::<Vec<_>>();
+
+    if arguments.is_empty() || arguments.iter().any(|x| x == "-h" || x == "--help") {
+        println!("{}", USAGE);
+        return match arguments.is_empty() {
+            true => ExitCode::from(2),
+            false => ExitCode::SUCCESS,
+        };
+    }
+
+    match run(&arguments) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) if error.is::<UsageError>() => {
+            eprintln!("airlines: {}\n\n{}", error, USAGE);
+            ExitCode::from(2)
+        }
+        Err(error) => {
+            eprintln!("airlines: error: {:#}", error);
+            ExitCode::from(1)
+        }
+    }
+}
+
+fn run(arguments: &[String]) -> Result<()> {
+    let command_line = CommandLine::parse(arguments)?;
+
+    let translates = matches!(
+        command_line.command.as_str(),
+        "air2spv" | "disasm" | "reflect"
+    );
+    if command_line.translates && !translates {
+        return Err(usage_error(format!(
+            "`{}` takes no translation options.",
+            command_line.command
+        )));
+    }
+
+    let input = Input::read(&command_line.input)?;
+
+    match command_line.command.as_str() {
+        "inspect" => match input {
+            Input::MetalLibrary(parser) => write_text(&command_line, &inspect(&parser)),
+            _ => Err(anyhow!(
+                "`{}` is not a Metal library.",
+                command_line.input.display()
+            )),
+        },
+        "dump-bitstream" => write_text(&command_line, &dump_bitstream(input.bitcode()?)?),
+        "air2spv" => {
+            let builder = input.spirv(&command_line.options)?;
+            let output = command_line
+                .output
+                .clone()
+                .unwrap_or(command_line.input.with_extension("spv"));
+
+            std::fs::write(&output, builder.assemble_to_bytes()?)
+                .with_context(|| format!("Can't write `{}`", output.display()))
+        }
+        "disasm" => {
+            let builder = input.spirv(&command_line.options)?;
+            write_text(&command_line, &(builder.to_string() + "\n"))
+        }
+        "reflect" => {
+            let builder = input.spirv(&command_line.options)?;
+            write_text(&command_line, &reflect(&builder.module))
+        }
+        _ => unreachable!(),
+    }
+}
+
+fn write_text(command_line: &CommandLine, text: &str) -> Result<()> {
+    match &command_line.output {
+        Some(path) => {
+            std::fs::write(path, text).with_context(|| format!("Can't write `{}`", path.display()))
+        }
+        None => {
+            print!("{}", text);
+            Ok(())
+        }
+    }
+}
+
+fn inspect(parser: &MTLLibraryParser) -> String {
+    let signature = &parser.signature;
+    let mut result = String::new();
+
+    let _ = writeln!(result, "Platform: {:?}", signature.target_platform);
+    let _ = writeln!(
+        result,
+        "Version: {}.{}",
+        signature.version.0, signature.version.1
+    );
+    let _ = writeln!(result, "Library type: {:?}", signature.library_type);
+    let _ = writeln!(
+        result,
+        "Target OS: {:?} {}.{}",
+        signature.target_os.ty, signature.target_os.major, signature.target_os.minor
+    );
+    let _ = writeln!(result, "File size: {}", signature.file_size);
+
+    for (name, offset, size) in [
+        (
+            "Function list",
+            signature.function_list_offset,
+            signature.function_list_size,
+        ),
+        (
+            "Public metadata",
+            signature.public_metadata_offset,
+            signature.public_metadata_size,
+        ),
+        (
+            "Private metadata",
+            signature.private_metadata_offset,
+            signature.private_metadata_size,
+        ),
+        ("Bitcode", signature.bitcode_offset, signature.bitcode_size),
+    ] {
+        let _ = writeln!(result, "{}: offset {}, size {}", name, offset, size);
+    }
+
+    let _ = writeln!(result, "\nFunctions ({}):", parser.functions.len());
+    for i in &parser.functions {
+        let _ = writeln!(
+            result,
+            "  {} ({:?}): bitcode offset {}, size {}",
+            i.name, i.function_type, i.bitcode_offset, i.bitcode_size
+        );
+    }
+
+    result
+}
+
+fn dump_bitstream(bitcode: Vec<u8>) -> Result<String> {
+    let (_signature, mut bitstream) = Bitstream::from(bitcode)?;
+
+    let mut result = String::new();
+    let mut blocks: Vec<u64> = vec![];
+    while let Some(entry) = bitstream.next() {
+        let indent = "\t".repeat(blocks.len());
+
+        match entry? {
+            StreamEntry::SubBlock(block) => {
+                let _ = match bitstream.block_name(block.block_id) {
+                    Some(name) => {
+                        writeln!(result, "{}BLOCK {} {} {{", indent, block.block_id, name)
+                    }
+                    None => writeln!(result, "{}BLOCK {} {{", indent, block.block_id),
+                };
+                blocks.push(block.block_id);
+            }
+            StreamEntry::EndBlock => {
+                blocks.pop();
+                let _ = writeln!(result, "{}}}", "\t".repeat(blocks.len()));
+            }
+            StreamEntry::Record(record) => {
+                let name = blocks
+                    .last()
+                    .and_then(|block_id| bitstream.record_name(*block_id, record.code));
+
+                let _ = match name {
+                    Some(name) => writeln!(
+                        result,
+                        "{}RECORD {{ code: {} ({}), fields: {:?} }}",
+                        indent, record.code, name, record.fields
+                    ),
+                    None => writeln!(
+                        result,
+                        "{}RECORD {{ code: {}, fields: {:?} }}",
+                        indent, record.code, record.fields
+                    ),
+                };
+            }
+            StreamEntry::EndOfStream => break,
+        }
+    }
+
+    Ok(result)
+}
+
+fn reflect(module: &SpirVModule) -> String {
+    let reflection = SpirVReflection::new(module);
+
+    let variable_json = |variable: &ReflectedVariable, indent: &str| -> String {
+        let mut fields = vec![
+            format!("\"id\": {}", variable.id.0),
+            format!("\"name\": {}", json_string(&variable.name)),
+            format!("\"storage_class\": \"{:?}\"", variable.storage_class),
+            format!("\"type\": {}", json_string(&variable.type_name)),
+        ];
+        if let Some(location) = variable.location {
+            fields.push(format!("\"location\": {}", location));
+        }
+        if let Some(built_in) = variable.built_in {
+            fields.push(format!("\"builtin\": \"{:?}\"", built_in));
+        }
+        if let Some(set) = variable.set {
+            fields.push(format!("\"set\": {}", set));
+        }
+        if let Some(binding) = variable.binding {
+            fields.push(format!("\"binding\": {}", binding));
+        }
+        if variable.read_only {
+            fields.push("\"read_only\": true".to_string());
+        }
+
+        format!("{}{{ {} }}", indent, fields.join(", "))
+    };
+
+    let entry_points = reflection
+        .entry_points
+        .iter()
+        .map(|i| {
+            let interface = i
+                .interface
+                .iter()
+                .map(|variable| variable_json(variable, "        "))
+                .collect::<Vec<_>>();
+
+            format!(
+                "    {{\n      \"name\": {},\n      \"execution_model\": \"{:?}\",\n      \"interface\": [{}]\n    }}",
+                json_string(&i.name),
+                i.execution_model,
+                json_list(&interface, "      "),
+            )
+        })
+        .collect::<Vec<_>>();
+
+    let resources = reflection
+        .resources
+        .iter()
+        .map(|variable| variable_json(variable, "    "))
+        .collect::<Vec<_>>();
+
+    format!(
+        "{{\n  \"entry_points\": [{}],\n  \"resources\": [{}]\n}}\n",
+        json_list(&entry_points, "  "),
+        json_list(&resources, "  ")
+    )
+}
+
+fn json_list(items: &[String], indent: &str) -> String {
+    match items.is_empty() {
+        true => String::new(),
+        false => format!("\n{}\n{}", items.join(",\n"), indent),
+    }
+}
+
+fn json_string(value: &str) -> String {
+    let mut result = String::from("\"");
+    for i in value.chars() {
+        match i {
+            '"' => result += "\\\"",
+            '\\' => result += "\\\\",
+            '\n' => result += "\\n",
+            '\t' => result += "\\t",
+            i if (i as u32) < 0x20 => {
+                let _ = write!(result, "\\u{:04x}", i as u32);
+            }
+            i => result.push(i),
+        }
+    }
+    result.push('"');
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn arguments(arguments: &[&str]) -> Vec<String> {
+        arguments.iter().map(|x| x.to_string()).collect()
+    }
+
+    #[test]
+    fn command_line() -> Result<()> {
+        let command_line = CommandLine::parse(&arguments(&[
+            "air2spv",
+            "Shaders.air",
+            "--spirv-version",
+            "1.3",
+            "--vulkan",
+            "1.1",
+            "--depth-range",
+            "negative-one-to-one",
+            "--flip-y",
+            "--set-per-resource-kind",
+            "--debug-info",
+            "--strip-names",
+            "-o",
+            "out.spv",
+        ]))?;
+
+        assert_eq!(command_line.command, "air2spv");
+        assert_eq!(command_line.input, PathBuf::from("Shaders.air"));
+        assert_eq!(command_line.output, Some(PathBuf::from("out.spv")));
+        assert!(command_line.translates);
+
+        let options = &command_line.options;
+        assert_eq!(options.spirv_version, (1, 3));
+        assert_eq!(options.environment, VulkanEnvironment::Vulkan1_1);
+        assert_eq!(options.depth_range, DepthRange::NegativeOneToOne);
+        assert!(options.flip_y);
+        assert_eq!(
+            options.descriptor_bindings,
+            DescriptorBindingStrategy::SetPerResourceKind
+        );
+        assert!(options.debug_info);
+        assert!(!options.emit_names);
+
+        for bad in [
+            &[][..],
+            &["spv2air", "Shaders.spv"],
+            &["air2spv"],
+            &["air2spv", "a.air", "b.air"],
+            &["air2spv", "a.air", "--spirv-version", "1"],
+            &["air2spv", "a.air", "--vulkan", "2.0"],
+            &["air2spv", "a.air", "--depth-range"],
+            &["air2spv", "a.air", "--unknown"],
+        ] {
+            let error = CommandLine::parse(&arguments(bad)).err().unwrap();
+            assert!(error.is::<UsageError>(), "{:?}: {}", bad, error);
+        }
+
+        // Only the translating commands take translation options.
+        let error = run(&arguments(&[
+            "inspect",
+            "test-files/test.metallib",
+            "--flip-y",
+        ]))
+        .err()
+        .unwrap();
+        assert!(error.is::<UsageError>());
+
+        Ok(())
+    }
+
+    #[test]
+    fn inspect_metal_library() -> Result<()> {
+        let Input::MetalLibrary(parser) = Input::read(Path::new("test-files/test.metallib"))?
+        else {
+            panic!("Expected a Metal library.");
+        };
+
+        let text = inspect(&parser);
+        assert!(text.contains("Functions (1):"));
+        assert!(text.contains("main0 (Vertex)"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn translate_commands() -> Result<()> {
+        let directory =
+            std::env::temp_dir().join(format!("airlines-cli-test-{}", std::process::id()));
+        std::fs::create_dir_all(&directory)?;
+        let output = |name: &str| directory.join(name).to_string_lossy().to_string();
+
+        run(&arguments(&[
+            "air2spv",
+            "test-files/code.air.ll",
+            "--vulkan",
+            "1.1",
+            "--spirv-version",
+            "1.3",
+            "-o",
+            &output("code.spv"),
+        ]))?;
+        let spirv =
+            airlines::spirv_parser::Parser::new(std::fs::read(output("code.spv"))?).start()?;
+        assert_eq!(spirv.signature.version, (1, 3));
+        assert_eq!(
+            spirv
+                .entry_point_table
+                .values()
+                .map(|x| x.name.as_str())
+                .collect::<Vec<_>>(),
+            ["vertexMain"]
+        );
+
+        // SPIR-V input is read as is.
+        run(&arguments(&[
+            "disasm",
+            &output("code.spv"),
+            "-o",
+            &output("code.spvasm"),
+        ]))?;
+        let assembly = std::fs::read_to_string(output("code.spvasm"))?;
+        assert!(assembly.starts_with("OpCapability Shader\n"));
+        assert!(assembly.contains("\"vertexID\""));
+
+        run(&arguments(&[
+            "reflect",
+            "test-files/argument_buffer.air.ll",
+            "-o",
+            &output("reflect.json"),
+        ]))?;
+        let json = std::fs::read_to_string(output("reflect.json"))?;
+        assert!(json.contains("\"name\": \"vertexMain\""));
+        assert!(json.contains(
+            "{ \"id\": 37, \"name\": \"vertexID\", \"storage_class\": \"Input\", \"type\": \"uint\", \"builtin\": \"VertexIndex\" }"
+        ));
+        assert!(json.contains("\"name\": \"material.albedoSampler\""));
+        assert!(json.contains("\"set\": 1, \"binding\": 1"));
+        assert!(json.contains("\"type\": \"material_data\", \"set\": 1, \"binding\": 4"));
+
+        std::fs::remove_dir_all(&directory)?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn unsupported_input() -> Result<()> {
+        let path = std::env::temp_dir().join(format!(
+            "airlines-cli-unsupported-{}.spv",
+            std::process::id()
+        ));
+
+        // A module made of one instruction with an opcode SPIR-V doesn't have.
+        let words = [SPIRV_MAGIC, 0x10000, 0, 1, 0, (1 << 16) | 0xFFFF];
+        std::fs::write(&path, words.map(u32::to_le_bytes).concat())?;
+
+        let error = run(&arguments(&["disasm", &path.to_string_lossy()]));
+        std::fs::remove_file(&path)?;
+        assert!(error.is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn json_strings() {
+        assert_eq!(json_string("a\"b\\c\nd\u{1}"), "\"a\\\"b\\\\c\\nd\\u0001\"");
+    }
+}
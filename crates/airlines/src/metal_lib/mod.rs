@@ -2,7 +2,13 @@ pub mod reader;
 
 use anyhow::{Result, anyhow};
 
-use crate::{air_codegen::AirToSpirV, air_parser::AirFile};
+use crate::{
+    air_codegen::{AirToSpirV, AirToSpirVOptions, ArgumentBufferLayout, EntryPointTranslation},
+    air_parser::{AirFile, AirItem, AirModule},
+    translation_cache::{
+        CachedTranslation, TranslationCache, TranslationKey, TranslationReflection,
+    },
+};
 
 #[derive(Debug, Clone, Default, Copy, PartialEq, Eq, Hash)]
 #[repr(u8)]
@@ -91,6 +97,48 @@ impl MTLLibraryType {
     }
 }
 
+#[derive(Debug, Clone, Default, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum MTLLibraryFunctionType {
+    #[default]
+    Vertex,
+    Fragment,
+    Kernel,
+    Unqualified,
+    Visible,
+    Extern,
+    Intersection,
+}
+
+impl MTLLibraryFunctionType {
+    pub fn from_u8(v: u8) -> Result<Self> {
+        Ok(match v {
+            0 => Self::Vertex,
+            1 => Self::Fragment,
+            2 => Self::Kernel,
+            3 => Self::Unqualified,
+            4 => Self::Visible,
+            5 => Self::Extern,
+            6 => Self::Intersection,
+            _ => return Err(anyhow!("Invalid Metal Function Type.")),
+        })
+    }
+}
+
+/// An entry of the library's function list. Offsets are relative to the
+/// start of their section.
+#[derive(Debug, Clone, Default)]
+pub struct MTLLibraryFunction {
+    pub name: String,
+    pub function_type: MTLLibraryFunctionType,
+    /// SHA-256 of the function, when the library has one.
+    pub hash: Option<[u8; 32]>,
+    pub public_metadata_offset: u64,
+    pub private_metadata_offset: u64,
+    pub bitcode_offset: u64,
+    pub bitcode_size: u64,
+}
+
 #[derive(Debug, Clone, Default)]
 pub struct MTLLibrarySignature {
     pub target_platform: MTLLibraryPlatform,
@@ -123,16 +171,91 @@ impl RMLShader {
 pub struct MTLLibraryParser {
     pub content: Vec<u8>,
     pub signature: MTLLibrarySignature,
+    pub functions: Vec<MTLLibraryFunction>,
     pub shader: RMLShader,
     position: usize,
 }
 
 impl MTLLibraryParser {
-    pub fn to_spirv_binary(&self) -> Vec<u32> {
-        let mut air_to_spirv = AirToSpirV::new(self.shader.air.clone().unwrap());
+    pub fn to_spirv_binary(&self, options: &AirToSpirVOptions) -> Result<Vec<u32>> {
+        AirToSpirV::translate(self.air_module()?, options, None)?.assemble()
+    }
+
+    /// Looks the library up in `cache` first. The bitcode is only parsed and
+    /// translated on a miss, so a library read with `read_header` is enough.
+    pub fn to_spirv_binary_cached(
+        &mut self,
+        cache: &TranslationCache,
+        options: &AirToSpirVOptions,
+    ) -> Result<CachedTranslation> {
+        let key = TranslationKey::new(self, options)?;
+        if let Some(translation) = cache.get(&key) {
+            return Ok(translation);
+        }
+
+        if self.shader.air.is_none() {
+            self.read_bitcode()?;
+        }
+
+        let module = self.air_module()?;
+        let output = AirToSpirV::translate(module, options, None)?;
+
+        let translation = CachedTranslation {
+            words: output.assemble()?,
+            reflection: TranslationReflection {
+                argument_buffers: AirToSpirV::argument_buffers(module, options)?,
+                ..TranslationReflection::new(&self.functions, &output.module)
+            },
+        };
+
+        // A cache that can't be written to only costs a translation next launch.
+        let _ = cache.insert(&key, &translation);
 
-        air_to_spirv.start().unwrap();
+        Ok(translation)
+    }
+
+    /// Translates each entry point of the library on its own, see
+    /// `AirToSpirV::translate_entry_points`.
+    pub fn to_spirv_entry_points(
+        &mut self,
+        options: &AirToSpirVOptions,
+        threads: usize,
+    ) -> Result<Vec<EntryPointTranslation>> {
+        if self.shader.air.is_none() {
+            self.read_bitcode()?;
+        }
+
+        Ok(AirToSpirV::translate_entry_points(
+            self.air_module()?,
+            options,
+            threads,
+        ))
+    }
+
+    /// Layouts of the library's argument buffers, for filling them through
+    /// an `MTLArgumentEncoder`.
+    pub fn argument_buffers(
+        &mut self,
+        options: &AirToSpirVOptions,
+    ) -> Result<Vec<ArgumentBufferLayout>> {
+        if self.shader.air.is_none() {
+            self.read_bitcode()?;
+        }
+
+        AirToSpirV::argument_buffers(self.air_module()?, options)
+    }
 
-        air_to_spirv.output.assemble()
+    fn air_module(&self) -> Result<&AirModule> {
+        self.shader
+            .air
+            .as_ref()
+            .ok_or(anyhow!("Bitcode not read."))?
+            .items
+            .iter()
+            .find_map(|i| match i {
+                AirItem::Module(m) => Some(m),
+                _ => None,
+            })
+            .ok_or(anyhow!("Module not found."))
     }
 }
@@ -3,27 +3,45 @@ use anyhow::{Result, anyhow};
 use crate::{
     air_parser,
     metal_lib::{
-        MTLLibraryParser, MTLLibraryPlatform, MTLLibrarySignature, MTLLibraryTargetOS,
-        MTLLibraryTargetOSType, MTLLibraryType,
+        MTLLibraryFunction, MTLLibraryFunctionType, MTLLibraryParser, MTLLibraryPlatform,
+        MTLLibrarySignature, MTLLibraryTargetOS, MTLLibraryTargetOSType, MTLLibraryType,
     },
 };
 
 impl MTLLibraryParser {
     pub fn read(&mut self, content: &[u8]) -> Result<&mut Self> {
+        self.read_header(content)?;
+        self.read_bitcode()?;
+
+        Ok(self)
+    }
+
+    /// Reads the signature and function list, leaving the bitcode unparsed.
+    pub fn read_header(&mut self, content: &[u8]) -> Result<&mut Self> {
         self.content = content.to_vec();
+        self.position = 0;
 
         self.signature = self.read_signature()?;
+        self.functions = self.read_function_list()?;
 
+        Ok(self)
+    }
+
+    pub fn read_bitcode(&mut self) -> Result<()> {
         self.shader = super::RMLShader::from_air_file(
-            air_parser::Parser::new(
-                content[self.signature.bitcode_offset as usize
-                    ..(self.signature.bitcode_offset + self.signature.bitcode_size) as usize]
-                    .to_vec(),
-            )?
-            .start()?,
+            air_parser::Parser::new(self.bitcode()?.to_vec())?.start()?,
         );
 
-        Ok(self)
+        Ok(())
+    }
+
+    pub fn bitcode(&self) -> Result<&[u8]> {
+        let start = self.signature.bitcode_offset as usize;
+        let end = start + self.signature.bitcode_size as usize;
+
+        self.content
+            .get(start..end)
+            .ok_or(anyhow!("Bitcode section out of bounds."))
     }
 
     pub fn read_signature(&mut self) -> Result<MTLLibrarySignature> {
@@ -90,6 +108,83 @@ impl MTLLibraryParser {
         })
     }
 
+    /// Each entry is a size followed by tags (a 4 character name, a
+    /// 16-bit length and the data) up to `ENDT`.
+    pub fn read_function_list(&mut self) -> Result<Vec<MTLLibraryFunction>> {
+        self.jump_to(self.signature.function_list_offset as usize)?;
+
+        let count = self.advance_u32()?;
+
+        let mut result = vec![];
+        for _ in 0..count {
+            let entry_start = self.position;
+            let entry_size = self.advance_u32()? as usize;
+
+            let mut function = MTLLibraryFunction::default();
+            loop {
+                let tag = [
+                    self.advance()?,
+                    self.advance()?,
+                    self.advance()?,
+                    self.advance()?,
+                ];
+
+                if &tag == b"ENDT" {
+                    break;
+                }
+
+                let tag_size = u16::from_le_bytes([self.advance()?, self.advance()?]) as usize;
+                let tag_start = self.position;
+
+                match &tag {
+                    b"NAME" => {
+                        let mut name = vec![];
+                        for _ in 0..tag_size {
+                            name.push(self.advance()?);
+                        }
+                        if let Some(end) = name.iter().position(|x| *x == 0) {
+                            name.truncate(end);
+                        }
+                        function.name = String::from_utf8(name)?;
+                    }
+                    b"TYPE" => {
+                        function.function_type = MTLLibraryFunctionType::from_u8(self.advance()?)?
+                    }
+                    b"HASH" => {
+                        let mut hash = [0; 32];
+                        for byte in hash.iter_mut().take(tag_size) {
+                            *byte = self.advance()?;
+                        }
+                        function.hash = Some(hash);
+                    }
+                    b"OFFT" => {
+                        function.public_metadata_offset = self.advance_u64()?;
+                        function.private_metadata_offset = self.advance_u64()?;
+                        function.bitcode_offset = self.advance_u64()?;
+                    }
+                    b"MDSZ" => function.bitcode_size = self.advance_u64()?,
+                    _ => {}
+                }
+
+                self.jump_to(tag_start + tag_size)?;
+            }
+
+            self.jump_to(entry_start + entry_size)?;
+            result.push(function);
+        }
+
+        Ok(result)
+    }
+
+    pub fn advance_u32(&mut self) -> Result<u32> {
+        Ok(u32::from_le_bytes([
+            self.advance()?,
+            self.advance()?,
+            self.advance()?,
+            self.advance()?,
+        ]))
+    }
+
     pub fn advance_u64(&mut self) -> Result<u64> {
         Ok(u64::from_le_bytes([
             self.advance()?,
@@ -114,7 +209,7 @@ impl MTLLibraryParser {
     pub fn advance(&mut self) -> Result<u8> {
         self.position += 1;
 
-        if self.position - 1 > self.content.len() {
+        if self.position > self.content.len() {
             return Err(anyhow!("Position out of bounds."));
         }
 
@@ -1,10 +1,13 @@
+use anyhow::{Result, anyhow};
+
 use crate::spirv_parser::{
-    FunctionControl, SpirVAccessChain, SpirVAddressingModel, SpirVAlloca, SpirVBitCast, SpirVBlock,
-    SpirVCapability, SpirVCompositeExtract, SpirVCompositeInsert, SpirVConstant,
-    SpirVConstantComposite, SpirVConstantValue, SpirVDecorate, SpirVDecorateType, SpirVEntryPoint,
-    SpirVExecutionModel, SpirVFunction, SpirVLoad, SpirVMemoryModel, SpirVModule, SpirVName,
-    SpirVOp, SpirVOpCode, SpirVSource, SpirVStorageClass, SpirVStore, SpirVType, SpirVVariableId,
-    SpirVVectorShuffle,
+    FunctionControl, SpirVAccessChain, SpirVAddressingModel, SpirVAlloca, SpirVBinaryOp,
+    SpirVBitCast, SpirVBlock, SpirVCapability, SpirVCompositeConstruct, SpirVCompositeExtract,
+    SpirVCompositeInsert, SpirVConstant, SpirVConstantComposite, SpirVConstantValue, SpirVDecorate,
+    SpirVDecorateType, SpirVEntryPoint, SpirVExecutionMode, SpirVExecutionModel, SpirVFunction,
+    SpirVLine, SpirVLoad, SpirVMemoryModel, SpirVModule, SpirVName, SpirVOp, SpirVOpCode,
+    SpirVSource, SpirVStorageClass, SpirVStore, SpirVType, SpirVUnaryOp, SpirVVariableId,
+    SpirVVectorInsertDynamic, SpirVVectorShuffle,
 };
 
 #[derive(Debug, Default, Clone)]
@@ -23,6 +26,15 @@ impl SpirVBuilder {
         }
     }
 
+    /// Wraps a parsed module, new ids continue after its bound.
+    pub fn from_module(module: SpirVModule) -> Self {
+        Self {
+            current_variable_id: module.signature.bound.max(1),
+            module,
+            block_list: vec![],
+        }
+    }
+
     pub fn id_check<'a>(i: &'a SpirVOp, id: SpirVVariableId) -> Option<&'a SpirVOp> {
         match i {
             SpirVOp::Type(nid, ..)
@@ -37,6 +49,9 @@ impl SpirVBuilder {
             | SpirVOp::CompositeConstruct(nid, ..)
             | SpirVOp::Function(nid, ..)
             | SpirVOp::BitCast(nid, ..)
+            | SpirVOp::Unary(nid, ..)
+            | SpirVOp::Binary(nid, ..)
+            | SpirVOp::VectorInsertDynamic(nid, ..)
             | SpirVOp::VectorShuffle(nid, ..) => {
                 if *nid == id {
                     return Some(i);
@@ -62,6 +77,9 @@ impl SpirVBuilder {
             | SpirVOp::CompositeConstruct(nid, ..)
             | SpirVOp::Function(nid, ..)
             | SpirVOp::BitCast(nid, ..)
+            | SpirVOp::Unary(nid, ..)
+            | SpirVOp::Binary(nid, ..)
+            | SpirVOp::VectorInsertDynamic(nid, ..)
             | SpirVOp::VectorShuffle(nid, ..) => {
                 if *nid == id {
                     return Some(i);
@@ -73,11 +91,11 @@ impl SpirVBuilder {
         }
     }
 
-    pub fn find_operand_with_id<'a>(&'a self, id: SpirVVariableId) -> &'a SpirVOp {
+    pub fn find_operand_with_id(&self, id: SpirVVariableId) -> Result<&SpirVOp> {
         for i in &self.block_list {
             for j in &i.instructions {
                 match Self::id_check(j, id) {
-                    Some(s) => return s,
+                    Some(s) => return Ok(s),
                     None => {}
                 }
             }
@@ -85,19 +103,19 @@ impl SpirVBuilder {
 
         for i in &self.module.operands {
             match Self::id_check(i, id) {
-                Some(s) => return s,
+                Some(s) => return Ok(s),
                 None => {}
             }
         }
 
-        panic!("ID {:?} not found.", id)
+        Err(anyhow!("ID {:?} not found.", id))
     }
 
-    pub fn find_mut_operand_with_id<'a>(&'a mut self, id: SpirVVariableId) -> &'a mut SpirVOp {
+    pub fn find_mut_operand_with_id(&mut self, id: SpirVVariableId) -> Result<&mut SpirVOp> {
         for i in &mut self.block_list {
             for j in &mut i.instructions {
                 match Self::id_mut_check(j, id) {
-                    Some(s) => return s,
+                    Some(s) => return Ok(s),
                     None => {}
                 }
             }
@@ -105,21 +123,33 @@ impl SpirVBuilder {
 
         for i in &mut self.module.operands {
             match Self::id_mut_check(i, id) {
-                Some(s) => return s,
+                Some(s) => return Ok(s),
                 None => {}
             }
         }
 
-        panic!("ID {:?} not found.", id)
+        Err(anyhow!("ID {:?} not found.", id))
     }
 
-    pub fn find_operand_type_id<'a>(&'a self, id: SpirVVariableId) -> SpirVVariableId {
-        let find_id = self.find_operand_with_id(id);
+    pub fn find_operand_type_id(&self, id: SpirVVariableId) -> Result<SpirVVariableId> {
+        let find_id = self.find_operand_with_id(id)?;
 
-        match find_id {
+        Ok(match find_id {
             SpirVOp::Alloca(_, alloca) => alloca.type_id,
-            _ => todo!(),
-        }
+            SpirVOp::AccessChain(_, access_chain) => access_chain.type_id,
+            SpirVOp::Constant(_, constant) => constant.type_id,
+            SpirVOp::ConstantComposite(_, composite) => composite.type_id,
+            SpirVOp::Load(_, load) => load.type_id,
+            SpirVOp::CompositeExtract(_, extract) => extract.type_id,
+            SpirVOp::CompositeInsert(_, insert) => insert.type_id,
+            SpirVOp::CompositeConstruct(_, construct) => construct.type_id,
+            SpirVOp::BitCast(_, cast) => cast.to_type,
+            SpirVOp::Unary(_, _, operation) => operation.type_id,
+            SpirVOp::Binary(_, _, operation) => operation.type_id,
+            SpirVOp::VectorInsertDynamic(_, insert) => insert.type_id,
+            SpirVOp::VectorShuffle(_, shuffle) => shuffle.vec_type,
+            op => return Err(anyhow!("{:?} has no result type.", op)),
+        })
     }
 
     pub fn find_pointer_type<'a>(&'a self, id: SpirVVariableId) -> SpirVVariableId {
@@ -170,12 +200,29 @@ impl SpirVBuilder {
             .push(SpirVOp::ExtendedInstructionImport(id, import_name));
     }
 
+    /// Drops every `OpName`/`OpMemberName` from the module.
+    pub fn strip_names(&mut self) {
+        self.module
+            .operands
+            .retain(|op| !matches!(op, SpirVOp::Name(..) | SpirVOp::MemberName(..)));
+    }
+
     pub fn new_source_extension(&mut self, extension_name: &str) {
         self.module
             .operands
             .push(SpirVOp::SourceExtension(extension_name.to_string()));
     }
 
+    pub fn new_string(&mut self, content: &str) -> SpirVVariableId {
+        let id = self.new_id();
+
+        self.module
+            .operands
+            .push(SpirVOp::String(id, content.to_string()));
+
+        id
+    }
+
     pub fn new_type(&mut self, ty: SpirVType) -> SpirVVariableId {
         let var = SpirVVariableId(self.current_variable_id);
 
@@ -193,6 +240,19 @@ impl SpirVBuilder {
         var
     }
 
+    /// Declares `ty` even if an identical type exists, for arrays that only
+    /// differ in their decorations.
+    pub fn new_distinct_type(&mut self, ty: SpirVType) -> SpirVVariableId {
+        let var = SpirVVariableId(self.current_variable_id);
+
+        self.module.type_table.insert(var, ty.clone());
+        self.module.operands.push(SpirVOp::Type(var, ty));
+
+        self.current_variable_id += 1;
+
+        var
+    }
+
     pub fn new_entry_point(
         &mut self,
         name: &str,
@@ -220,6 +280,13 @@ impl SpirVBuilder {
         var
     }
 
+    /// `entry_point` is the entry point's function.
+    pub fn new_execution_mode(&mut self, entry_point: SpirVVariableId, mode: SpirVExecutionMode) {
+        self.module
+            .operands
+            .push(SpirVOp::ExecutionMode(entry_point, mode));
+    }
+
     pub fn new_variable(
         &mut self,
         name: &str,
@@ -346,6 +413,18 @@ impl SpirVBuilder {
         }
     }
 
+    /// Decorations on top of the one `set_decorate` gives each member.
+    pub fn set_member_decorate(
+        &mut self,
+        struct_id: SpirVVariableId,
+        member: usize,
+        decorate: SpirVDecorateType,
+    ) {
+        self.module
+            .operands
+            .push(SpirVOp::MemberDecorate(struct_id, member, decorate));
+    }
+
     pub fn new_constant_composite(&mut self, composite: SpirVConstantComposite) -> SpirVVariableId {
         let var = SpirVVariableId(self.current_variable_id);
 
@@ -414,6 +493,53 @@ impl SpirVBuilder {
         id
     }
 
+    pub fn new_unary_op(
+        &mut self,
+        op_code: SpirVOpCode,
+        operation: SpirVUnaryOp,
+    ) -> SpirVVariableId {
+        let id = self.new_id();
+
+        let current_block = self.block_list.last_mut().unwrap();
+
+        current_block
+            .instructions
+            .push(SpirVOp::Unary(id, op_code, operation));
+
+        id
+    }
+
+    pub fn new_binary_op(
+        &mut self,
+        op_code: SpirVOpCode,
+        operation: SpirVBinaryOp,
+    ) -> SpirVVariableId {
+        let id = self.new_id();
+
+        let current_block = self.block_list.last_mut().unwrap();
+
+        current_block
+            .instructions
+            .push(SpirVOp::Binary(id, op_code, operation));
+
+        id
+    }
+
+    pub fn new_vector_insert_dynamic(
+        &mut self,
+        insert: SpirVVectorInsertDynamic,
+    ) -> SpirVVariableId {
+        let id = self.new_id();
+
+        let current_block = self.block_list.last_mut().unwrap();
+
+        current_block
+            .instructions
+            .push(SpirVOp::VectorInsertDynamic(id, insert));
+
+        id
+    }
+
     pub fn new_access_chain(&mut self, access_chain: SpirVAccessChain) -> SpirVVariableId {
         let id = self.new_id();
 
@@ -473,6 +599,21 @@ impl SpirVBuilder {
         id
     }
 
+    pub fn new_composite_construct(
+        &mut self,
+        composite_construct: SpirVCompositeConstruct,
+    ) -> SpirVVariableId {
+        let id = self.new_id();
+
+        let current_block = self.block_list.last_mut().unwrap();
+
+        current_block
+            .instructions
+            .push(SpirVOp::CompositeConstruct(id, composite_construct));
+
+        id
+    }
+
     pub fn new_composite_insert(
         &mut self,
         composite_insert: SpirVCompositeInsert,
@@ -488,6 +629,18 @@ impl SpirVBuilder {
         id
     }
 
+    pub fn new_line(&mut self, line: SpirVLine) {
+        let current_block = self.block_list.last_mut().unwrap();
+
+        current_block.instructions.push(SpirVOp::Line(line));
+    }
+
+    pub fn new_no_line(&mut self) {
+        let current_block = self.block_list.last_mut().unwrap();
+
+        current_block.instructions.push(SpirVOp::NoLine);
+    }
+
     pub fn new_return(&mut self, value: Option<SpirVVariableId>) -> SpirVVariableId {
         let id = self.new_id();
 
@@ -507,7 +660,7 @@ impl SpirVBuilder {
         SpirVVariableId(id)
     }
 
-    pub fn end_function(&mut self, func: SpirVVariableId) -> SpirVVariableId {
+    pub fn end_function(&mut self, func: SpirVVariableId) -> Result<SpirVVariableId> {
         self.block_list
             .last_mut()
             .unwrap()
@@ -524,38 +677,39 @@ impl SpirVBuilder {
             .instructions
             .push(SpirVOp::Block(id, block.clone()));
 
-        let func_operand = self.find_mut_operand_with_id(func);
+        let func_operand = self.find_mut_operand_with_id(func)?;
         match func_operand {
             SpirVOp::Function(_, function) => function.instructions.push(SpirVOp::Block(id, block)),
-            _ => todo!(),
+            _ => return Err(anyhow!("{:?} isn't a function.", func)),
         }
 
         self.block_list.clear();
 
-        func
+        Ok(func)
     }
 
-    pub fn assemble(&self) -> Vec<u32> {
+    pub fn assemble(&self) -> Result<Vec<u32>> {
         let mut result: Vec<u32> = vec![];
 
         // Magic Number.
         result.push(0x7230203);
 
-        // SPIR-V Version (1.0).
-        result.push(u32::from_le_bytes([0_u8, 0_u8, 1_u8, 0_u8]));
+        // SPIR-V Version.
+        let (major, minor) = self.module.signature.version;
+        result.push(u32::from_le_bytes([0_u8, minor, major, 0_u8]));
 
         // Generator, Bound and Schema.
         result.extend(vec![0, self.current_variable_id + 1, 0]);
 
         for i in &self.module.operands {
-            result.extend(self.assemble_operand(i));
+            result.extend(self.assemble_operand(i)?);
         }
 
-        result
+        Ok(result)
     }
 
-    pub fn assemble_operand(&self, op: &SpirVOp) -> Vec<u32> {
-        match op {
+    pub fn assemble_operand(&self, op: &SpirVOp) -> Result<Vec<u32>> {
+        Ok(match op {
             SpirVOp::Capability(capability) => {
                 vec![
                     Self::new_opcode(2, SpirVOpCode::Capability),
@@ -587,6 +741,14 @@ impl SpirVBuilder {
                         *size,
                     ]
                 }
+                SpirVType::Matrix(column_type, column_count) => {
+                    vec![
+                        Self::new_opcode(4, SpirVOpCode::TypeMatrix),
+                        id.0,
+                        column_type.0,
+                        *column_count,
+                    ]
+                }
                 SpirVType::Array(type_id, size) => {
                     vec![
                         Self::new_opcode(4, SpirVOpCode::TypeArray),
@@ -616,15 +778,77 @@ impl SpirVBuilder {
                 SpirVType::Void => {
                     vec![Self::new_opcode(2, SpirVOpCode::TypeVoid), id.0]
                 }
-                _ => todo!("{:?}", ty),
+                SpirVType::Bool => {
+                    vec![Self::new_opcode(2, SpirVOpCode::TypeBool), id.0]
+                }
+                SpirVType::RuntimeArray(type_id) => {
+                    vec![
+                        Self::new_opcode(3, SpirVOpCode::TypeRuntimeArray),
+                        id.0,
+                        type_id.0,
+                    ]
+                }
+                SpirVType::Image(image) => {
+                    vec![
+                        Self::new_opcode(9, SpirVOpCode::TypeImage),
+                        id.0,
+                        image.sampled_type.0,
+                        image.dim as u32,
+                        image.depth,
+                        image.arrayed as u32,
+                        image.multisampled as u32,
+                        image.sampled,
+                        image.format,
+                    ]
+                }
+                SpirVType::Sampler => {
+                    vec![Self::new_opcode(2, SpirVOpCode::TypeSampler), id.0]
+                }
+                _ => return Err(anyhow!("Can't assemble {:?} types yet.", ty)),
             },
+            SpirVOp::Constant(
+                id,
+                SpirVConstant {
+                    type_id,
+                    value: SpirVConstantValue::Bool(value),
+                },
+            ) => {
+                vec![
+                    Self::new_opcode(
+                        3,
+                        if *value {
+                            SpirVOpCode::ConstantTrue
+                        } else {
+                            SpirVOpCode::ConstantFalse
+                        },
+                    ),
+                    type_id.0,
+                    id.0,
+                ]
+            }
             SpirVOp::Constant(id, constant) => {
+                let is_wide = matches!(
+                    self.module.type_table.get(&constant.type_id),
+                    Some(SpirVType::Int(64, _))
+                );
                 let constant_words =
                     match constant.value {
+                        SpirVConstantValue::SignedInteger(int) if is_wide => {
+                            let ib = int.to_le_bytes();
+
+                            vec![
+                                u32::from_le_bytes([ib[0], ib[1], ib[2], ib[3]]),
+                                u32::from_le_bytes([ib[4], ib[5], ib[6], ib[7]]),
+                            ]
+                        }
+                        SpirVConstantValue::UnsignedInteger(int) if is_wide => {
+                            vec![int as u32, (int >> 32) as u32]
+                        }
                         SpirVConstantValue::SignedInteger(int) => {
                             vec![u32::from_le_bytes((int as i32).to_le_bytes())]
                         }
                         SpirVConstantValue::UnsignedInteger(int) => vec![int as u32],
+                        SpirVConstantValue::Bool(_) => unreachable!(),
                         SpirVConstantValue::Float32(float) => {
                             vec![u32::from_le_bytes(float.to_le_bytes())]
                         }
@@ -754,7 +978,7 @@ impl SpirVBuilder {
                 ];
 
                 for i in &function.instructions {
-                    result.extend(self.assemble_operand(i));
+                    result.extend(self.assemble_operand(i)?);
                 }
 
                 result
@@ -763,7 +987,7 @@ impl SpirVBuilder {
                 let mut result = vec![Self::new_opcode(2, SpirVOpCode::Label), id.0];
 
                 for i in &block.instructions {
-                    result.extend(self.assemble_operand(i));
+                    result.extend(self.assemble_operand(i)?);
                 }
 
                 result
@@ -776,6 +1000,33 @@ impl SpirVBuilder {
                     bit_cast.variable.0,
                 ]
             }
+            SpirVOp::Unary(id, op_code, operation) => {
+                vec![
+                    Self::new_opcode(4, *op_code),
+                    operation.type_id.0,
+                    id.0,
+                    operation.operand.0,
+                ]
+            }
+            SpirVOp::Binary(id, op_code, operation) => {
+                vec![
+                    Self::new_opcode(5, *op_code),
+                    operation.type_id.0,
+                    id.0,
+                    operation.operand1.0,
+                    operation.operand2.0,
+                ]
+            }
+            SpirVOp::VectorInsertDynamic(id, insert) => {
+                vec![
+                    Self::new_opcode(6, SpirVOpCode::VectorInsertDynamic),
+                    insert.type_id.0,
+                    id.0,
+                    insert.vector.0,
+                    insert.component.0,
+                    insert.index.0,
+                ]
+            }
             SpirVOp::AccessChain(id, access_chain) => {
                 let mut result = vec![
                     Self::new_opcode(
@@ -853,6 +1104,20 @@ impl SpirVBuilder {
 
                 result
             }
+            SpirVOp::CompositeConstruct(id, composite_construct) => {
+                let mut result = vec![
+                    Self::new_opcode(
+                        3 + composite_construct.elements.len() as u32,
+                        SpirVOpCode::CompositeConstruct,
+                    ),
+                    composite_construct.type_id.0,
+                    id.0,
+                ];
+
+                result.extend(composite_construct.elements.iter().map(|value| value.0));
+
+                result
+            }
             SpirVOp::Store(store) => {
                 vec![
                     Self::new_opcode(4, SpirVOpCode::Store),
@@ -886,6 +1151,25 @@ impl SpirVBuilder {
 
                 result
             }
+            SpirVOp::ExecutionMode(entry_point, mode) => {
+                let (mode, literals) = match mode {
+                    SpirVExecutionMode::OriginUpperLeft => (7, vec![]),
+                    SpirVExecutionMode::OriginLowerLeft => (8, vec![]),
+                    SpirVExecutionMode::DepthReplacing => (12, vec![]),
+                    SpirVExecutionMode::LocalSize(x, y, z) => (17, vec![*x, *y, *z]),
+                    SpirVExecutionMode::Other(mode, literals) => (*mode, literals.clone()),
+                };
+
+                let mut result = vec![
+                    Self::new_opcode(3 + literals.len() as u32, SpirVOpCode::ExecutionMode),
+                    entry_point.0,
+                    mode,
+                ];
+
+                result.extend(literals);
+
+                result
+            }
             SpirVOp::Source(source) => {
                 vec![
                     Self::new_opcode(3, SpirVOpCode::Source),
@@ -927,8 +1211,29 @@ impl SpirVBuilder {
 
                 result
             }
-            _ => todo!("{:?}", op),
-        }
+            SpirVOp::String(id, content) => {
+                let content = Self::string_to_spirv_name(content);
+
+                let mut result = vec![
+                    Self::new_opcode(2 + content.len() as u32, SpirVOpCode::String),
+                    id.0,
+                ];
+
+                result.extend(content);
+
+                result
+            }
+            SpirVOp::Line(line) => {
+                vec![
+                    Self::new_opcode(4, SpirVOpCode::Line),
+                    line.file.0,
+                    line.line,
+                    line.column,
+                ]
+            }
+            SpirVOp::NoLine => vec![Self::new_opcode(1, SpirVOpCode::NoLine)],
+            _ => return Err(anyhow!("Can't assemble {:?} yet.", op)),
+        })
     }
 
     pub fn new_opcode(word_count: u32, op_code: SpirVOpCode) -> u32 {
@@ -946,7 +1251,18 @@ impl SpirVBuilder {
         match decorate_type {
             SpirVDecorateType::Block => vec![2],
             SpirVDecorateType::BuiltIn(builtin) => vec![11, *builtin as u32],
+            SpirVDecorateType::BufferBlock => vec![3],
+            SpirVDecorateType::RowMajor => vec![4],
+            SpirVDecorateType::ColMajor => vec![5],
+            SpirVDecorateType::ArrayStride(stride) => vec![6, *stride],
+            SpirVDecorateType::MatrixStride(stride) => vec![7, *stride],
+            SpirVDecorateType::NoPerspective => vec![13],
+            SpirVDecorateType::Flat => vec![14],
+            SpirVDecorateType::NonWritable => vec![24],
             SpirVDecorateType::Location(location) => vec![30, *location],
+            SpirVDecorateType::Binding(binding) => vec![33, *binding],
+            SpirVDecorateType::DescriptorSet(set) => vec![34, *set],
+            SpirVDecorateType::Offset(offset) => vec![35, *offset],
         }
     }
 
@@ -974,8 +1290,8 @@ impl SpirVBuilder {
         result
     }
 
-    pub fn assemble_to_bytes(&self) -> Vec<u8> {
-        let assemble = self.assemble();
+    pub fn assemble_to_bytes(&self) -> Result<Vec<u8>> {
+        let assemble = self.assemble()?;
 
         let mut result = vec![];
 
@@ -983,7 +1299,7 @@ impl SpirVBuilder {
             result.extend_from_slice(&i.to_le_bytes());
         }
 
-        result
+        Ok(result)
     }
 
     pub fn to_string(&self) -> String {
@@ -1005,6 +1321,9 @@ impl SpirVBuilder {
                 SpirVOp::SourceExtension(extension_name) => {
                     result += &format!("OpSourceExtension \"{}\"", extension_name)
                 }
+                SpirVOp::String(id, content) => {
+                    result += &format!("%{:?} = OpString \"{}\"", id.0, content)
+                }
                 SpirVOp::Type(id, ty) => {
                     result += &format!("%{:?} = ", id.0);
                     match ty {
@@ -1012,6 +1331,10 @@ impl SpirVBuilder {
                         SpirVType::Vector(type_id, size) => {
                             result += &format!("OpTypeVector %{:?} {:?}", type_id.0, size)
                         }
+                        SpirVType::Matrix(column_type, column_count) => {
+                            result +=
+                                &format!("OpTypeMatrix %{:?} {:?}", column_type.0, column_count)
+                        }
                         SpirVType::Int(width, signedness) => {
                             result += &format!("OpTypeInt {:?} {:?}", width, signedness)
                         }
@@ -1033,7 +1356,29 @@ impl SpirVBuilder {
                                 &format!("OpTypePointer {:?} %{:?}", storage_class, pointer_id.0)
                         }
                         SpirVType::Void => result += "OpTypeVoid",
-                        _ => todo!("{:?}", ty),
+                        SpirVType::Bool => result += "OpTypeBool",
+                        SpirVType::RuntimeArray(type_id) => {
+                            result += &format!("OpTypeRuntimeArray %{:?}", type_id.0)
+                        }
+                        SpirVType::Struct(members) => {
+                            result += "OpTypeStruct";
+                            for i in members {
+                                result += &format!(" %{:?}", i.0);
+                            }
+                        }
+                        SpirVType::Image(image) => {
+                            result += &format!(
+                                "OpTypeImage %{:?} {:?} {:?} {:?} {:?} {:?} {:?}",
+                                image.sampled_type.0,
+                                image.dim,
+                                image.depth,
+                                image.arrayed as u32,
+                                image.multisampled as u32,
+                                image.sampled,
+                                image.format
+                            )
+                        }
+                        SpirVType::Sampler => result += "OpTypeSampler",
                     }
                 }
                 SpirVOp::Constant(id, constant) => {
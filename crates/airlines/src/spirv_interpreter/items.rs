@@ -0,0 +1,186 @@
+use crate::spirv_parser::{SpirVBuiltIn, SpirVVariableId};
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub enum SpirVValue {
+    #[default]
+    Undefined,
+    Bool(bool),
+    /// Raw bits, masked to `width`. Signedness is up to the instruction.
+    Integer {
+        value: u64,
+        width: u32,
+    },
+    Float32(f32),
+    Float64(f64),
+    /// Vectors, arrays and structs.
+    Composite(Vec<SpirVValue>),
+    Pointer(SpirVPointer),
+}
+
+impl SpirVValue {
+    pub fn integer(value: u64, width: u32) -> Self {
+        Self::Integer {
+            value: mask(value, width),
+            width,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Self::Bool(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn as_u64(&self) -> Option<u64> {
+        match self {
+            Self::Integer { value, .. } => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            Self::Integer { value, width } => Some(sign_extend(*value, *width)),
+            _ => None,
+        }
+    }
+
+    pub fn as_u32(&self) -> Option<u32> {
+        self.as_u64().map(|value| value as u32)
+    }
+
+    pub fn as_i32(&self) -> Option<i32> {
+        self.as_i64().map(|value| value as i32)
+    }
+
+    pub fn as_f32(&self) -> Option<f32> {
+        match self {
+            Self::Float32(value) => Some(*value),
+            Self::Float64(value) => Some(*value as f32),
+            _ => None,
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Self::Float32(value) => Some(*value as f64),
+            Self::Float64(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn elements(&self) -> Option<&[SpirVValue]> {
+        match self {
+            Self::Composite(elements) => Some(elements),
+            _ => None,
+        }
+    }
+
+    /// Components of a float vector, or a float scalar as a single component.
+    pub fn as_f32_vec(&self) -> Option<Vec<f32>> {
+        match self {
+            Self::Composite(elements) => elements.iter().map(|i| i.as_f32()).collect(),
+            _ => self.as_f32().map(|value| vec![value]),
+        }
+    }
+}
+
+impl From<bool> for SpirVValue {
+    fn from(value: bool) -> Self {
+        Self::Bool(value)
+    }
+}
+
+impl From<u32> for SpirVValue {
+    fn from(value: u32) -> Self {
+        Self::integer(value as u64, 32)
+    }
+}
+
+impl From<i32> for SpirVValue {
+    fn from(value: i32) -> Self {
+        Self::integer(value as u64, 32)
+    }
+}
+
+impl From<u64> for SpirVValue {
+    fn from(value: u64) -> Self {
+        Self::integer(value, 64)
+    }
+}
+
+impl From<i64> for SpirVValue {
+    fn from(value: i64) -> Self {
+        Self::integer(value as u64, 64)
+    }
+}
+
+impl From<f32> for SpirVValue {
+    fn from(value: f32) -> Self {
+        Self::Float32(value)
+    }
+}
+
+impl From<f64> for SpirVValue {
+    fn from(value: f64) -> Self {
+        Self::Float64(value)
+    }
+}
+
+impl<T: Into<SpirVValue>> From<Vec<T>> for SpirVValue {
+    fn from(value: Vec<T>) -> Self {
+        Self::Composite(value.into_iter().map(|i| i.into()).collect())
+    }
+}
+
+impl<T: Into<SpirVValue>, const N: usize> From<[T; N]> for SpirVValue {
+    fn from(value: [T; N]) -> Self {
+        Self::Composite(value.into_iter().map(|i| i.into()).collect())
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct SpirVPointer {
+    pub base: SpirVPointerBase,
+    /// Indices walked by the access chains that made this pointer.
+    pub indices: Vec<u32>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpirVPointerBase {
+    /// A module-level `OpVariable`.
+    Global(SpirVVariableId),
+    /// A `Function` storage variable, with the depth of the frame that owns it.
+    Local(usize, SpirVVariableId),
+}
+
+impl Default for SpirVPointerBase {
+    fn default() -> Self {
+        Self::Global(SpirVVariableId::default())
+    }
+}
+
+/// Where an entry point reads its inputs from and writes its outputs to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SpirVInterface {
+    Location(u32),
+    BuiltIn(SpirVBuiltIn),
+}
+
+pub fn mask(value: u64, width: u32) -> u64 {
+    if width >= 64 {
+        value
+    } else {
+        value & ((1 << width) - 1)
+    }
+}
+
+pub fn sign_extend(value: u64, width: u32) -> i64 {
+    if width >= 64 {
+        value as i64
+    } else {
+        let shift = 64 - width;
+        ((value << shift) as i64) >> shift
+    }
+}
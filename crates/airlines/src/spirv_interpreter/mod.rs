@@ -0,0 +1,1712 @@
+use std::collections::HashMap;
+
+use anyhow::{Result, anyhow};
+
+use crate::spirv_parser::{
+    SpirVAlloca, SpirVBuiltIn, SpirVConstant, SpirVConstantValue, SpirVDecorateType,
+    SpirVEntryPoint, SpirVExecutionMode, SpirVModule, SpirVOp, SpirVOpCode, SpirVStorageClass,
+    SpirVType, SpirVVariableId,
+};
+
+pub mod items;
+pub use items::*;
+
+/// `GLSL.std.450` is the only extended instruction set airlines emits.
+const GLSL_STD_450: &str = "GLSL.std.450";
+
+/// Runs SPIR-V entry points on the CPU, one invocation at a time.
+///
+/// Inputs are matched by `Location` or `BuiltIn`, buffers by
+/// `(DescriptorSet, Binding)` and read/written with the module's
+/// `Offset`/`ArrayStride` layout.
+#[derive(Debug, Default)]
+pub struct SpirVInterpreter {
+    pub module: SpirVModule,
+    constants: HashMap<SpirVVariableId, SpirVValue>,
+    variables: HashMap<SpirVVariableId, SpirVAlloca>,
+    decorations: HashMap<SpirVVariableId, Vec<SpirVDecorateType>>,
+    member_decorations: HashMap<(SpirVVariableId, usize), Vec<SpirVDecorateType>>,
+    functions: HashMap<SpirVVariableId, SpirVProgramFunction>,
+    execution_modes: HashMap<SpirVVariableId, Vec<SpirVExecutionMode>>,
+    extended_imports: HashMap<SpirVVariableId, String>,
+    inputs: HashMap<SpirVInterface, SpirVValue>,
+    outputs: HashMap<SpirVInterface, SpirVValue>,
+    memory: SpirVMemory,
+    discarded: bool,
+}
+
+#[derive(Debug, Default, Clone)]
+struct SpirVProgramFunction {
+    parameters: Vec<SpirVVariableId>,
+    entry_block: SpirVVariableId,
+    blocks: HashMap<SpirVVariableId, Vec<SpirVOp>>,
+}
+
+/// Memory shared by every invocation of a run or dispatch.
+#[derive(Debug, Default)]
+struct SpirVMemory {
+    buffers: HashMap<(u32, u32), Vec<u8>>,
+    push_constants: Vec<u8>,
+    workgroup: HashMap<SpirVVariableId, SpirVValue>,
+}
+
+#[derive(Debug, Default)]
+struct SpirVInvocation {
+    frames: Vec<SpirVFrame>,
+    /// `Input`, `Output` and `Private` variables.
+    variables: HashMap<SpirVVariableId, SpirVValue>,
+    discarded: bool,
+}
+
+#[derive(Debug, Default)]
+struct SpirVFrame {
+    function: SpirVVariableId,
+    block: SpirVVariableId,
+    previous_block: Option<SpirVVariableId>,
+    position: usize,
+    values: HashMap<SpirVVariableId, SpirVValue>,
+    locals: HashMap<SpirVVariableId, SpirVValue>,
+    /// Caller id receiving the return value.
+    result: Option<SpirVVariableId>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SpirVStep {
+    Continue,
+    Barrier,
+    Done,
+}
+
+impl SpirVInterpreter {
+    pub fn new(module: SpirVModule) -> Result<Self> {
+        let mut interpreter = Self {
+            module,
+            ..Default::default()
+        };
+
+        let operands = interpreter.module.operands.clone();
+
+        for op in &operands {
+            match op {
+                SpirVOp::ExtendedInstructionImport(id, name) => {
+                    interpreter.extended_imports.insert(*id, name.clone());
+                }
+                SpirVOp::Decorate(id, decorate) => {
+                    interpreter
+                        .decorations
+                        .entry(*id)
+                        .or_default()
+                        .push(decorate.clone());
+                }
+                SpirVOp::MemberDecorate(id, member, decorate) => {
+                    interpreter
+                        .member_decorations
+                        .entry((*id, *member))
+                        .or_default()
+                        .push(decorate.clone());
+                }
+                SpirVOp::ExecutionMode(id, mode) => {
+                    interpreter
+                        .execution_modes
+                        .entry(*id)
+                        .or_default()
+                        .push(mode.clone());
+                }
+                SpirVOp::Constant(id, constant) => {
+                    let value = interpreter.constant_value(constant)?;
+                    interpreter.constants.insert(*id, value);
+                }
+                SpirVOp::ConstantComposite(id, composite) => {
+                    let mut values = vec![];
+                    for i in &composite.values {
+                        values.push(interpreter.constant(*i)?);
+                    }
+                    interpreter
+                        .constants
+                        .insert(*id, SpirVValue::Composite(values));
+                }
+                SpirVOp::Alloca(id, alloca) => {
+                    interpreter.variables.insert(*id, alloca.clone());
+                }
+                SpirVOp::Function(id, function) => {
+                    let mut program_function = SpirVProgramFunction::default();
+
+                    for i in &function.instructions {
+                        match i {
+                            SpirVOp::FunctionParameter(parameter, _) => {
+                                program_function.parameters.push(*parameter)
+                            }
+                            SpirVOp::Block(label, block) => {
+                                if program_function.blocks.is_empty() {
+                                    program_function.entry_block = *label;
+                                }
+                                program_function
+                                    .blocks
+                                    .insert(*label, block.instructions.clone());
+                            }
+                            _ => {}
+                        }
+                    }
+
+                    interpreter.functions.insert(*id, program_function);
+                }
+                _ => {}
+            }
+        }
+
+        Ok(interpreter)
+    }
+
+    pub fn set_input(&mut self, interface: SpirVInterface, value: impl Into<SpirVValue>) {
+        self.inputs.insert(interface, value.into());
+    }
+
+    pub fn output(&self, interface: SpirVInterface) -> Option<&SpirVValue> {
+        self.outputs.get(&interface)
+    }
+
+    pub fn outputs(&self) -> &HashMap<SpirVInterface, SpirVValue> {
+        &self.outputs
+    }
+
+    pub fn bind_buffer(&mut self, set: u32, binding: u32, data: Vec<u8>) {
+        self.memory.buffers.insert((set, binding), data);
+    }
+
+    pub fn buffer(&self, set: u32, binding: u32) -> Option<&Vec<u8>> {
+        self.memory.buffers.get(&(set, binding))
+    }
+
+    pub fn set_push_constants(&mut self, data: Vec<u8>) {
+        self.memory.push_constants = data;
+    }
+
+    /// Whether the last fragment invocation hit `OpKill`.
+    pub fn discarded(&self) -> bool {
+        self.discarded
+    }
+
+    pub fn entry_point(&self, name: &str) -> Result<SpirVEntryPoint> {
+        self.module
+            .operands
+            .iter()
+            .find_map(|i| match i {
+                SpirVOp::EntryPoint(entry_point) if entry_point.name == name => {
+                    Some(entry_point.clone())
+                }
+                _ => None,
+            })
+            .ok_or(anyhow!("Entry point \"{}\" not found.", name))
+    }
+
+    /// Runs a single invocation of a vertex or fragment entry point.
+    pub fn run(&mut self, entry_point: &str) -> Result<()> {
+        let entry_point = self.entry_point(entry_point)?;
+
+        let mut memory = std::mem::take(&mut self.memory);
+        memory.workgroup = self.new_workgroup()?;
+
+        let result = self
+            .new_invocation(entry_point.entry_point_id, &self.inputs)
+            .and_then(|mut invocation| {
+                while self.execute(&mut invocation, &mut memory)? != SpirVStep::Done {}
+                Ok(invocation)
+            });
+
+        self.memory = memory;
+
+        let invocation = result?;
+        self.outputs = self.collect_outputs(&invocation)?;
+        self.discarded = invocation.discarded;
+
+        Ok(())
+    }
+
+    /// Runs every invocation of a compute entry point. Invocations of a
+    /// workgroup are interleaved at each `OpControlBarrier`.
+    pub fn dispatch(&mut self, entry_point: &str, workgroups: [u32; 3]) -> Result<()> {
+        let entry_point = self.entry_point(entry_point)?;
+
+        let local_size = self
+            .execution_modes
+            .get(&entry_point.entry_point_id)
+            .and_then(|modes| {
+                modes.iter().find_map(|i| match i {
+                    SpirVExecutionMode::LocalSize(x, y, z) => Some([*x, *y, *z]),
+                    _ => None,
+                })
+            })
+            .unwrap_or([1, 1, 1]);
+
+        let mut memory = std::mem::take(&mut self.memory);
+        let result = self.dispatch_workgroups(
+            entry_point.entry_point_id,
+            workgroups,
+            local_size,
+            &mut memory,
+        );
+        self.memory = memory;
+
+        result
+    }
+
+    fn dispatch_workgroups(
+        &self,
+        function: SpirVVariableId,
+        workgroups: [u32; 3],
+        local_size: [u32; 3],
+        memory: &mut SpirVMemory,
+    ) -> Result<()> {
+        let uvec3 = |v: [u32; 3]| SpirVValue::from(v);
+
+        for group_z in 0..workgroups[2] {
+            for group_y in 0..workgroups[1] {
+                for group_x in 0..workgroups[0] {
+                    memory.workgroup = self.new_workgroup()?;
+
+                    let group = [group_x, group_y, group_z];
+                    let mut invocations = vec![];
+
+                    for local_z in 0..local_size[2] {
+                        for local_y in 0..local_size[1] {
+                            for local_x in 0..local_size[0] {
+                                let local = [local_x, local_y, local_z];
+                                let global = [
+                                    group_x * local_size[0] + local_x,
+                                    group_y * local_size[1] + local_y,
+                                    group_z * local_size[2] + local_z,
+                                ];
+                                let index = local_x
+                                    + local_y * local_size[0]
+                                    + local_z * local_size[0] * local_size[1];
+
+                                let mut inputs = self.inputs.clone();
+                                for (built_in, value) in [
+                                    (SpirVBuiltIn::NumWorkgroups, uvec3(workgroups)),
+                                    (SpirVBuiltIn::WorkgroupSize, uvec3(local_size)),
+                                    (SpirVBuiltIn::WorkgroupId, uvec3(group)),
+                                    (SpirVBuiltIn::LocalInvocationId, uvec3(local)),
+                                    (SpirVBuiltIn::GlobalInvocationId, uvec3(global)),
+                                    (SpirVBuiltIn::LocalInvocationIndex, index.into()),
+                                ] {
+                                    inputs.insert(SpirVInterface::BuiltIn(built_in), value);
+                                }
+
+                                invocations.push(self.new_invocation(function, &inputs)?);
+                            }
+                        }
+                    }
+
+                    let mut running = invocations.len();
+                    let mut done = vec![false; invocations.len()];
+
+                    while running > 0 {
+                        for (i, invocation) in invocations.iter_mut().enumerate() {
+                            if done[i] {
+                                continue;
+                            }
+
+                            if self.execute(invocation, memory)? == SpirVStep::Done {
+                                done[i] = true;
+                                running -= 1;
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn new_workgroup(&self) -> Result<HashMap<SpirVVariableId, SpirVValue>> {
+        let mut workgroup = HashMap::new();
+
+        for (id, variable) in &self.variables {
+            if variable.storage_class == SpirVStorageClass::Workgroup {
+                workgroup.insert(*id, self.variable_initial_value(variable)?);
+            }
+        }
+
+        Ok(workgroup)
+    }
+
+    fn new_invocation(
+        &self,
+        function: SpirVVariableId,
+        inputs: &HashMap<SpirVInterface, SpirVValue>,
+    ) -> Result<SpirVInvocation> {
+        let mut invocation = SpirVInvocation::default();
+
+        for (id, variable) in &self.variables {
+            let value = match variable.storage_class {
+                SpirVStorageClass::Input => self.interface_value(*id, variable, inputs)?,
+                SpirVStorageClass::Output | SpirVStorageClass::Private => {
+                    self.variable_initial_value(variable)?
+                }
+                _ => continue,
+            };
+
+            invocation.variables.insert(*id, value);
+        }
+
+        invocation
+            .frames
+            .push(self.new_frame(function, vec![], None)?);
+
+        Ok(invocation)
+    }
+
+    fn new_frame(
+        &self,
+        function: SpirVVariableId,
+        arguments: Vec<SpirVValue>,
+        result: Option<SpirVVariableId>,
+    ) -> Result<SpirVFrame> {
+        let program_function = self.function(function)?;
+
+        Ok(SpirVFrame {
+            function,
+            block: program_function.entry_block,
+            values: program_function
+                .parameters
+                .iter()
+                .copied()
+                .zip(arguments)
+                .collect(),
+            result,
+            ..Default::default()
+        })
+    }
+
+    fn function(&self, id: SpirVVariableId) -> Result<&SpirVProgramFunction> {
+        self.functions
+            .get(&id)
+            .ok_or(anyhow!("Function %{} not found.", id.0))
+    }
+
+    fn ty(&self, id: SpirVVariableId) -> Result<&SpirVType> {
+        self.module
+            .type_table
+            .get(&id)
+            .ok_or(anyhow!("Type %{} not found.", id.0))
+    }
+
+    fn pointee_type(&self, pointer_type: SpirVVariableId) -> Result<SpirVVariableId> {
+        match self.ty(pointer_type)? {
+            SpirVType::Pointer(_, ty) => Ok(*ty),
+            other => Err(anyhow!("Expected a pointer type, found {:?}.", other)),
+        }
+    }
+
+    /// Scalar type of a scalar or vector type.
+    fn scalar_type(&self, id: SpirVVariableId) -> Result<&SpirVType> {
+        match self.ty(id)? {
+            SpirVType::Vector(element, _) => self.ty(*element),
+            other => Ok(other),
+        }
+    }
+
+    fn constant(&self, id: SpirVVariableId) -> Result<SpirVValue> {
+        self.constants
+            .get(&id)
+            .cloned()
+            .ok_or(anyhow!("Constant %{} not found.", id.0))
+    }
+
+    fn constant_value(&self, constant: &SpirVConstant) -> Result<SpirVValue> {
+        let ty = self.ty(constant.type_id)?;
+
+        Ok(match (&constant.value, ty) {
+            (SpirVConstantValue::Null | SpirVConstantValue::Undefined, _) => {
+                self.zero_value(constant.type_id)?
+            }
+            (SpirVConstantValue::Bool(value), _) => SpirVValue::Bool(*value),
+            (SpirVConstantValue::UnsignedInteger(value), SpirVType::Int(width, _)) => {
+                SpirVValue::integer(*value, *width)
+            }
+            (SpirVConstantValue::SignedInteger(value), SpirVType::Int(width, _)) => {
+                SpirVValue::integer(*value as u64, *width)
+            }
+            (SpirVConstantValue::Float32(value), SpirVType::Float(64)) => {
+                SpirVValue::Float64(*value as f64)
+            }
+            (SpirVConstantValue::Float32(value), _) => SpirVValue::Float32(*value),
+            (SpirVConstantValue::Float64(value), SpirVType::Float(32)) => {
+                SpirVValue::Float32(*value as f32)
+            }
+            (SpirVConstantValue::Float64(value), _) => SpirVValue::Float64(*value),
+            (value, ty) => return Err(anyhow!("Invalid constant {:?} of type {:?}.", value, ty)),
+        })
+    }
+
+    fn zero_value(&self, ty: SpirVVariableId) -> Result<SpirVValue> {
+        Ok(match self.ty(ty)? {
+            SpirVType::Bool => SpirVValue::Bool(false),
+            SpirVType::Int(width, _) => SpirVValue::integer(0, *width),
+            SpirVType::Float(64) => SpirVValue::Float64(0.0),
+            SpirVType::Float(_) => SpirVValue::Float32(0.0),
+            SpirVType::Vector(element, count) | SpirVType::Matrix(element, count) => {
+                SpirVValue::Composite(vec![self.zero_value(*element)?; *count as usize])
+            }
+            SpirVType::Array(element, length) => {
+                let length = self.array_length(*length)?;
+                SpirVValue::Composite(vec![self.zero_value(*element)?; length])
+            }
+            SpirVType::RuntimeArray(_) => SpirVValue::Composite(vec![]),
+            SpirVType::Struct(members) => SpirVValue::Composite(
+                members
+                    .iter()
+                    .map(|i| self.zero_value(*i))
+                    .collect::<Result<_>>()?,
+            ),
+            SpirVType::Void
+            | SpirVType::Function(..)
+            | SpirVType::Pointer(..)
+            | SpirVType::Image(_)
+            | SpirVType::Sampler => SpirVValue::Undefined,
+        })
+    }
+
+    fn array_length(&self, length: SpirVVariableId) -> Result<usize> {
+        self.constant(length)?
+            .as_u64()
+            .map(|i| i as usize)
+            .ok_or(anyhow!("Array length %{} isn't an integer.", length.0))
+    }
+
+    fn variable_initial_value(&self, variable: &SpirVAlloca) -> Result<SpirVValue> {
+        match variable.initializer {
+            Some(initializer) => self.constant(initializer),
+            None => self.zero_value(self.pointee_type(variable.type_id)?),
+        }
+    }
+
+    fn decorations(&self, id: SpirVVariableId) -> &[SpirVDecorateType] {
+        self.decorations
+            .get(&id)
+            .map(|i| i.as_slice())
+            .unwrap_or(&[])
+    }
+
+    fn member_decorations(&self, id: SpirVVariableId, member: usize) -> &[SpirVDecorateType] {
+        self.member_decorations
+            .get(&(id, member))
+            .map(|i| i.as_slice())
+            .unwrap_or(&[])
+    }
+
+    fn interface(decorations: &[SpirVDecorateType]) -> Option<SpirVInterface> {
+        decorations.iter().find_map(|i| match i {
+            SpirVDecorateType::Location(location) => Some(SpirVInterface::Location(*location)),
+            SpirVDecorateType::BuiltIn(built_in) => Some(SpirVInterface::BuiltIn(*built_in)),
+            _ => None,
+        })
+    }
+
+    fn interface_value(
+        &self,
+        id: SpirVVariableId,
+        variable: &SpirVAlloca,
+        inputs: &HashMap<SpirVInterface, SpirVValue>,
+    ) -> Result<SpirVValue> {
+        let ty = self.pointee_type(variable.type_id)?;
+
+        if let Some(interface) = Self::interface(self.decorations(id)) {
+            return match inputs.get(&interface) {
+                Some(value) => Ok(value.clone()),
+                None => self.zero_value(ty),
+            };
+        }
+
+        // Built-in blocks (`gl_PerVertex` and friends).
+        if let SpirVType::Struct(members) = self.ty(ty)? {
+            let mut values = vec![];
+            for (index, member) in members.iter().enumerate() {
+                let interface = Self::interface(self.member_decorations(ty, index));
+                values.push(match interface.and_then(|i| inputs.get(&i)) {
+                    Some(value) => value.clone(),
+                    None => self.zero_value(*member)?,
+                });
+            }
+            return Ok(SpirVValue::Composite(values));
+        }
+
+        self.zero_value(ty)
+    }
+
+    fn collect_outputs(
+        &self,
+        invocation: &SpirVInvocation,
+    ) -> Result<HashMap<SpirVInterface, SpirVValue>> {
+        let mut outputs = HashMap::new();
+
+        for (id, variable) in &self.variables {
+            if variable.storage_class != SpirVStorageClass::Output {
+                continue;
+            }
+
+            let value = &invocation.variables[id];
+
+            if let Some(interface) = Self::interface(self.decorations(*id)) {
+                outputs.insert(interface, value.clone());
+                continue;
+            }
+
+            let ty = self.pointee_type(variable.type_id)?;
+            if let (SpirVType::Struct(_), SpirVValue::Composite(members)) = (self.ty(ty)?, value) {
+                for (index, member) in members.iter().enumerate() {
+                    if let Some(interface) = Self::interface(self.member_decorations(ty, index)) {
+                        outputs.insert(interface, member.clone());
+                    }
+                }
+            }
+        }
+
+        Ok(outputs)
+    }
+
+    fn value(&self, invocation: &SpirVInvocation, id: SpirVVariableId) -> Result<SpirVValue> {
+        let frame = invocation.frames.last().unwrap();
+
+        if let Some(value) = frame.values.get(&id) {
+            return Ok(value.clone());
+        }
+
+        if let Some(value) = self.constants.get(&id) {
+            return Ok(value.clone());
+        }
+
+        if self.variables.contains_key(&id) {
+            return Ok(SpirVValue::Pointer(SpirVPointer {
+                base: SpirVPointerBase::Global(id),
+                indices: vec![],
+            }));
+        }
+
+        Err(anyhow!("Value %{} not found.", id.0))
+    }
+
+    fn pointer(&self, invocation: &SpirVInvocation, id: SpirVVariableId) -> Result<SpirVPointer> {
+        match self.value(invocation, id)? {
+            SpirVValue::Pointer(pointer) => Ok(pointer),
+            other => Err(anyhow!("%{} isn't a pointer: {:?}.", id.0, other)),
+        }
+    }
+
+    /// Runs until the invocation finishes or reaches a barrier.
+    fn execute(
+        &self,
+        invocation: &mut SpirVInvocation,
+        memory: &mut SpirVMemory,
+    ) -> Result<SpirVStep> {
+        loop {
+            let Some(frame) = invocation.frames.last_mut() else {
+                return Ok(SpirVStep::Done);
+            };
+
+            let block = self
+                .function(frame.function)?
+                .blocks
+                .get(&frame.block)
+                .ok_or(anyhow!("Block %{} not found.", frame.block.0))?;
+
+            let op = block
+                .get(frame.position)
+                .ok_or(anyhow!("Block %{} has no terminator.", frame.block.0))?;
+
+            frame.position += 1;
+
+            match self.step(op, invocation, memory)? {
+                SpirVStep::Continue => {}
+                step => return Ok(step),
+            }
+        }
+    }
+
+    fn step(
+        &self,
+        op: &SpirVOp,
+        invocation: &mut SpirVInvocation,
+        memory: &mut SpirVMemory,
+    ) -> Result<SpirVStep> {
+        let (id, value) = match op {
+            SpirVOp::Name(..)
+            | SpirVOp::Line(..)
+            | SpirVOp::NoLine
+            | SpirVOp::SelectionMerge(..)
+            | SpirVOp::LoopMerge(..)
+            | SpirVOp::MemoryBarrier
+            | SpirVOp::FunctionEnd
+            | SpirVOp::Empty => return Ok(SpirVStep::Continue),
+            SpirVOp::ControlBarrier => return Ok(SpirVStep::Barrier),
+            SpirVOp::Constant(id, constant) => (*id, self.constant_value(constant)?),
+            SpirVOp::Alloca(id, alloca) => {
+                let value = self.variable_initial_value(alloca)?;
+                let depth = invocation.frames.len() - 1;
+                let frame = invocation.frames.last_mut().unwrap();
+                frame.locals.insert(*id, value);
+
+                (
+                    *id,
+                    SpirVValue::Pointer(SpirVPointer {
+                        base: SpirVPointerBase::Local(depth, *id),
+                        indices: vec![],
+                    }),
+                )
+            }
+            SpirVOp::Load(id, load) => {
+                let pointer = self.pointer(invocation, load.pointer_id)?;
+                (*id, self.load(invocation, memory, &pointer)?)
+            }
+            SpirVOp::Store(store) => {
+                let pointer = self.pointer(invocation, store.pointer_id)?;
+                let value = self.value(invocation, store.object_id)?;
+                self.store(invocation, memory, &pointer, value)?;
+                return Ok(SpirVStep::Continue);
+            }
+            SpirVOp::AccessChain(id, access_chain) => {
+                let mut pointer = self.pointer(invocation, access_chain.base_id)?;
+                for i in &access_chain.indices {
+                    let index = self
+                        .value(invocation, *i)?
+                        .as_u64()
+                        .ok_or(anyhow!("Access chain index %{} isn't an integer.", i.0))?;
+                    pointer.indices.push(index as u32);
+                }
+                (*id, SpirVValue::Pointer(pointer))
+            }
+            SpirVOp::ArrayLength(id, array_length) => {
+                let pointer = self.pointer(invocation, array_length.structure)?;
+                (
+                    *id,
+                    self.runtime_array_length(memory, &pointer, array_length.member)?,
+                )
+            }
+            SpirVOp::CompositeExtract(id, extract) => {
+                let composite = self.value(invocation, extract.composite_id)?;
+                (*id, element(&composite, &extract.indices)?.clone())
+            }
+            SpirVOp::CompositeInsert(id, insert) => {
+                let mut composite = self.value(invocation, insert.composite_id)?;
+                *element_mut(&mut composite, &insert.indices)? =
+                    self.value(invocation, insert.object_id)?;
+                (*id, composite)
+            }
+            SpirVOp::CompositeConstruct(id, construct) => {
+                let flatten = matches!(self.ty(construct.type_id)?, SpirVType::Vector(..));
+                let mut elements = vec![];
+                for i in &construct.elements {
+                    match self.value(invocation, *i)? {
+                        SpirVValue::Composite(values) if flatten => elements.extend(values),
+                        value => elements.push(value),
+                    }
+                }
+                (*id, SpirVValue::Composite(elements))
+            }
+            SpirVOp::VectorShuffle(id, shuffle) => {
+                let mut components = components(self.value(invocation, shuffle.vec1)?);
+                components.extend(self.components_of(invocation, shuffle.vec2)?);
+
+                let result = shuffle
+                    .mask
+                    .iter()
+                    .map(|i| match *i {
+                        0xFFFFFFFF => SpirVValue::Undefined,
+                        i => components.get(i as usize).cloned().unwrap_or_default(),
+                    })
+                    .collect();
+                (*id, SpirVValue::Composite(result))
+            }
+            SpirVOp::VectorInsertDynamic(id, insert) => {
+                let mut vector = self.value(invocation, insert.vector)?;
+                let index = self
+                    .value(invocation, insert.index)?
+                    .as_u64()
+                    .ok_or(anyhow!("Vector index isn't an integer."))?;
+                *element_mut(&mut vector, &[index as u32])? =
+                    self.value(invocation, insert.component)?;
+                (*id, vector)
+            }
+            SpirVOp::BitCast(id, bit_cast) => {
+                let value = self.value(invocation, bit_cast.variable)?;
+                let mut bytes = vec![];
+                value_to_bytes(&value, &mut bytes)?;
+                (*id, self.read_value(bit_cast.to_type, &bytes, 0)?)
+            }
+            SpirVOp::Unary(id, op_code, unary) => {
+                let value = self.value(invocation, unary.operand)?;
+                let result_type = self.scalar_type(unary.type_id)?;
+                (*id, self.unary(*op_code, result_type, &value)?)
+            }
+            SpirVOp::Binary(id, op_code, binary) => {
+                let a = self.value(invocation, binary.operand1)?;
+                let b = self.value(invocation, binary.operand2)?;
+                (*id, binary_op(*op_code, &a, &b)?)
+            }
+            SpirVOp::Select(id, select) => {
+                let condition = self.value(invocation, select.condition)?;
+                let a = self.value(invocation, select.object1)?;
+                let b = self.value(invocation, select.object2)?;
+                (*id, select_value(&condition, a, b)?)
+            }
+            SpirVOp::ExtendedInstruction(id, instruction) => {
+                let set = self
+                    .extended_imports
+                    .get(&instruction.set)
+                    .ok_or(anyhow!("Unknown extended instruction set."))?;
+                if set != GLSL_STD_450 {
+                    return Err(anyhow!("Extended instruction set {} is unsupported.", set));
+                }
+
+                let operands = instruction
+                    .operands
+                    .iter()
+                    .map(|i| self.value(invocation, *i))
+                    .collect::<Result<Vec<_>>>()?;
+                (*id, glsl_std_450(instruction.instruction, &operands)?)
+            }
+            SpirVOp::Phi(id, phi) => {
+                let frame = invocation.frames.last().unwrap();
+                let previous = frame
+                    .previous_block
+                    .ok_or(anyhow!("OpPhi in an entry block."))?;
+                let (value, _) = phi
+                    .incoming
+                    .iter()
+                    .find(|(_, parent)| *parent == previous)
+                    .ok_or(anyhow!("OpPhi %{} has no value for %{}.", id.0, previous.0))?;
+                (*id, self.value(invocation, *value)?)
+            }
+            SpirVOp::FunctionCall(id, call) => {
+                let arguments = call
+                    .arguments
+                    .iter()
+                    .map(|i| self.value(invocation, *i))
+                    .collect::<Result<Vec<_>>>()?;
+                let frame = self.new_frame(call.function, arguments, Some(*id))?;
+                invocation.frames.push(frame);
+                return Ok(SpirVStep::Continue);
+            }
+            SpirVOp::Return => return self.return_value(invocation, SpirVValue::Undefined),
+            SpirVOp::ReturnValue(value) => {
+                let value = self.value(invocation, *value)?;
+                return self.return_value(invocation, value);
+            }
+            SpirVOp::Branch(label) => {
+                jump(invocation, *label);
+                return Ok(SpirVStep::Continue);
+            }
+            SpirVOp::BranchConditional(branch) => {
+                let condition = self
+                    .value(invocation, branch.condition)?
+                    .as_bool()
+                    .ok_or(anyhow!("Branch condition isn't a bool."))?;
+                let label = match condition {
+                    true => branch.true_label,
+                    false => branch.false_label,
+                };
+                jump(invocation, label);
+                return Ok(SpirVStep::Continue);
+            }
+            SpirVOp::Switch(switch) => {
+                let selector = self
+                    .value(invocation, switch.selector)?
+                    .as_u64()
+                    .ok_or(anyhow!("Switch selector isn't an integer."))?;
+                let label = switch
+                    .targets
+                    .iter()
+                    .find(|(literal, _)| *literal == selector)
+                    .map(|(_, label)| *label)
+                    .unwrap_or(switch.default);
+                jump(invocation, label);
+                return Ok(SpirVStep::Continue);
+            }
+            SpirVOp::Kill => {
+                invocation.discarded = true;
+                invocation.frames.clear();
+                return Ok(SpirVStep::Done);
+            }
+            SpirVOp::Unreachable => return Err(anyhow!("Reached OpUnreachable.")),
+            other => return Err(anyhow!("{:?} isn't supported by the interpreter.", other)),
+        };
+
+        invocation
+            .frames
+            .last_mut()
+            .unwrap()
+            .values
+            .insert(id, value);
+
+        Ok(SpirVStep::Continue)
+    }
+
+    fn components_of(
+        &self,
+        invocation: &SpirVInvocation,
+        id: SpirVVariableId,
+    ) -> Result<Vec<SpirVValue>> {
+        Ok(components(self.value(invocation, id)?))
+    }
+
+    fn return_value(
+        &self,
+        invocation: &mut SpirVInvocation,
+        value: SpirVValue,
+    ) -> Result<SpirVStep> {
+        let frame = invocation.frames.pop().unwrap();
+
+        match invocation.frames.last_mut() {
+            Some(caller) => {
+                if let Some(result) = frame.result {
+                    caller.values.insert(result, value);
+                }
+                Ok(SpirVStep::Continue)
+            }
+            None => Ok(SpirVStep::Done),
+        }
+    }
+
+    fn buffer_key(&self, id: SpirVVariableId) -> (u32, u32) {
+        let mut key = (0, 0);
+        for i in self.decorations(id) {
+            match i {
+                SpirVDecorateType::DescriptorSet(set) => key.0 = *set,
+                SpirVDecorateType::Binding(binding) => key.1 = *binding,
+                _ => {}
+            }
+        }
+        key
+    }
+
+    /// Bytes backing a buffer variable, if it's one.
+    fn buffer_bytes<'a>(
+        &self,
+        memory: &'a mut SpirVMemory,
+        id: SpirVVariableId,
+    ) -> Result<Option<&'a mut Vec<u8>>> {
+        let variable = &self.variables[&id];
+
+        Ok(match variable.storage_class {
+            SpirVStorageClass::StorageBuffer | SpirVStorageClass::Uniform => {
+                let key = self.buffer_key(id);
+                Some(memory.buffers.get_mut(&key).ok_or(anyhow!(
+                    "No buffer bound at set {}, binding {}.",
+                    key.0,
+                    key.1
+                ))?)
+            }
+            SpirVStorageClass::PushConstant => Some(&mut memory.push_constants),
+            _ => None,
+        })
+    }
+
+    fn load(
+        &self,
+        invocation: &SpirVInvocation,
+        memory: &mut SpirVMemory,
+        pointer: &SpirVPointer,
+    ) -> Result<SpirVValue> {
+        let root = match pointer.base {
+            SpirVPointerBase::Local(depth, id) => &invocation.frames[depth].locals[&id],
+            SpirVPointerBase::Global(id) => {
+                let ty = self.pointee_type(self.variables[&id].type_id)?;
+
+                if let Some(bytes) = self.buffer_bytes(memory, id)? {
+                    let (offset, ty) = self.layout_offset(ty, &pointer.indices)?;
+                    return self.read_value(ty, bytes, offset);
+                }
+
+                match self.variables[&id].storage_class {
+                    SpirVStorageClass::Workgroup => &memory.workgroup[&id],
+                    _ => invocation
+                        .variables
+                        .get(&id)
+                        .ok_or(anyhow!("Variable %{} can't be loaded.", id.0))?,
+                }
+            }
+        };
+
+        Ok(element(root, &pointer.indices)?.clone())
+    }
+
+    fn store(
+        &self,
+        invocation: &mut SpirVInvocation,
+        memory: &mut SpirVMemory,
+        pointer: &SpirVPointer,
+        value: SpirVValue,
+    ) -> Result<()> {
+        let root = match pointer.base {
+            SpirVPointerBase::Local(depth, id) => invocation.frames[depth]
+                .locals
+                .get_mut(&id)
+                .ok_or(anyhow!("Local %{} not found.", id.0))?,
+            SpirVPointerBase::Global(id) => {
+                let ty = self.pointee_type(self.variables[&id].type_id)?;
+
+                if let Some(bytes) = self.buffer_bytes(memory, id)? {
+                    let (offset, ty) = self.layout_offset(ty, &pointer.indices)?;
+                    return self.write_value(ty, bytes, offset, &value);
+                }
+
+                match self.variables[&id].storage_class {
+                    SpirVStorageClass::Workgroup => memory.workgroup.get_mut(&id).unwrap(),
+                    _ => invocation
+                        .variables
+                        .get_mut(&id)
+                        .ok_or(anyhow!("Variable %{} can't be stored to.", id.0))?,
+                }
+            }
+        };
+
+        *element_mut(root, &pointer.indices)? = value;
+
+        Ok(())
+    }
+
+    fn runtime_array_length(
+        &self,
+        memory: &mut SpirVMemory,
+        pointer: &SpirVPointer,
+        member: u32,
+    ) -> Result<SpirVValue> {
+        let SpirVPointerBase::Global(id) = pointer.base else {
+            return Err(anyhow!("OpArrayLength on a function variable."));
+        };
+
+        let ty = self.pointee_type(self.variables[&id].type_id)?;
+        let (offset, _) = self.layout_offset(ty, &pointer.indices)?;
+        let (member_offset, array) = self.layout_offset(ty, &[member])?;
+        let stride = self.array_stride(array)?;
+
+        let length = match self.buffer_bytes(memory, id)? {
+            Some(bytes) => bytes.len().saturating_sub(offset + member_offset) / stride.max(1),
+            None => 0,
+        };
+
+        Ok(SpirVValue::integer(length as u64, 32))
+    }
+
+    /// Byte offset and type reached by walking `indices` into `ty`.
+    fn layout_offset(
+        &self,
+        ty: SpirVVariableId,
+        indices: &[u32],
+    ) -> Result<(usize, SpirVVariableId)> {
+        let mut offset = 0;
+        let mut ty = ty;
+
+        for index in indices {
+            let index = *index as usize;
+
+            match self.ty(ty)? {
+                SpirVType::Struct(members) => {
+                    offset += self.member_offset(ty, index)?;
+                    ty = *members
+                        .get(index)
+                        .ok_or(anyhow!("Struct member {} out of range.", index))?;
+                }
+                SpirVType::Array(element, _) | SpirVType::RuntimeArray(element) => {
+                    offset += index * self.array_stride(ty)?;
+                    ty = *element;
+                }
+                SpirVType::Vector(element, _) => {
+                    offset += index * self.size_of(*element)?;
+                    ty = *element;
+                }
+                SpirVType::Matrix(column, _) => {
+                    offset += index * self.matrix_stride(*column)?;
+                    ty = *column;
+                }
+                other => return Err(anyhow!("Can't index into {:?}.", other)),
+            }
+        }
+
+        Ok((offset, ty))
+    }
+
+    fn member_offset(&self, ty: SpirVVariableId, member: usize) -> Result<usize> {
+        let offset = self
+            .member_decorations(ty, member)
+            .iter()
+            .find_map(|i| match i {
+                SpirVDecorateType::Offset(offset) => Some(*offset as usize),
+                _ => None,
+            });
+
+        if let Some(offset) = offset {
+            return Ok(offset);
+        }
+
+        let SpirVType::Struct(members) = self.ty(ty)? else {
+            return Err(anyhow!("%{} isn't a struct.", ty.0));
+        };
+
+        let mut offset = 0;
+        for i in &members[..member] {
+            offset += self.size_of(*i)?;
+        }
+        Ok(offset)
+    }
+
+    fn array_stride(&self, ty: SpirVVariableId) -> Result<usize> {
+        let stride = self.decorations(ty).iter().find_map(|i| match i {
+            SpirVDecorateType::ArrayStride(stride) => Some(*stride as usize),
+            _ => None,
+        });
+
+        match (stride, self.ty(ty)?) {
+            (Some(stride), _) => Ok(stride),
+            (None, SpirVType::Array(element, _) | SpirVType::RuntimeArray(element)) => {
+                self.size_of(*element)
+            }
+            (None, other) => Err(anyhow!("{:?} isn't an array.", other)),
+        }
+    }
+
+    /// `MatrixStride` is decorated on the struct member holding the matrix,
+    /// columns are assumed to be aligned like a vector of their size the way
+    /// both Metal and std430 lay them out.
+    fn matrix_stride(&self, column: SpirVVariableId) -> Result<usize> {
+        Ok(self.size_of(column)?.next_power_of_two())
+    }
+
+    fn size_of(&self, ty: SpirVVariableId) -> Result<usize> {
+        Ok(match self.ty(ty)? {
+            SpirVType::Bool => 4,
+            SpirVType::Int(width, _) | SpirVType::Float(width) => *width as usize / 8,
+            SpirVType::Vector(element, count) => self.size_of(*element)? * *count as usize,
+            SpirVType::Matrix(column, count) => self.matrix_stride(*column)? * *count as usize,
+            SpirVType::Array(_, length) => self.array_length(*length)? * self.array_stride(ty)?,
+            SpirVType::RuntimeArray(_) => 0,
+            SpirVType::Struct(members) => match members.len() {
+                0 => 0,
+                count => self.member_offset(ty, count - 1)? + self.size_of(members[count - 1])?,
+            },
+            other => return Err(anyhow!("{:?} has no size.", other)),
+        })
+    }
+
+    fn read_value(&self, ty: SpirVVariableId, bytes: &[u8], offset: usize) -> Result<SpirVValue> {
+        let read = |size: usize| -> Result<u64> {
+            let slice = bytes
+                .get(offset..offset + size)
+                .ok_or(anyhow!("Out of bounds read at {}.", offset))?;
+            let mut word = [0u8; 8];
+            word[..size].copy_from_slice(slice);
+            Ok(u64::from_le_bytes(word))
+        };
+
+        Ok(match self.ty(ty)? {
+            SpirVType::Bool => SpirVValue::Bool(read(4)? != 0),
+            SpirVType::Int(width, _) => SpirVValue::integer(read(*width as usize / 8)?, *width),
+            SpirVType::Float(64) => SpirVValue::Float64(f64::from_bits(read(8)?)),
+            SpirVType::Float(_) => SpirVValue::Float32(f32::from_bits(read(4)? as u32)),
+            SpirVType::Vector(element, count) => {
+                let size = self.size_of(*element)?;
+                SpirVValue::Composite(
+                    (0..*count as usize)
+                        .map(|i| self.read_value(*element, bytes, offset + i * size))
+                        .collect::<Result<_>>()?,
+                )
+            }
+            SpirVType::Matrix(column, count) => {
+                let stride = self.matrix_stride(*column)?;
+                SpirVValue::Composite(
+                    (0..*count as usize)
+                        .map(|i| self.read_value(*column, bytes, offset + i * stride))
+                        .collect::<Result<_>>()?,
+                )
+            }
+            SpirVType::Array(element, length) => {
+                let stride = self.array_stride(ty)?;
+                SpirVValue::Composite(
+                    (0..self.array_length(*length)?)
+                        .map(|i| self.read_value(*element, bytes, offset + i * stride))
+                        .collect::<Result<_>>()?,
+                )
+            }
+            SpirVType::RuntimeArray(element) => {
+                let stride = self.array_stride(ty)?.max(1);
+                let length = bytes.len().saturating_sub(offset) / stride;
+                SpirVValue::Composite(
+                    (0..length)
+                        .map(|i| self.read_value(*element, bytes, offset + i * stride))
+                        .collect::<Result<_>>()?,
+                )
+            }
+            SpirVType::Struct(members) => {
+                let mut values = vec![];
+                for (index, member) in members.iter().enumerate() {
+                    let member_offset = offset + self.member_offset(ty, index)?;
+                    values.push(self.read_value(*member, bytes, member_offset)?);
+                }
+                SpirVValue::Composite(values)
+            }
+            other => return Err(anyhow!("Can't read {:?} from memory.", other)),
+        })
+    }
+
+    fn write_value(
+        &self,
+        ty: SpirVVariableId,
+        bytes: &mut [u8],
+        offset: usize,
+        value: &SpirVValue,
+    ) -> Result<()> {
+        let mut write = |size: usize, bits: u64| -> Result<()> {
+            let slice = bytes
+                .get_mut(offset..offset + size)
+                .ok_or(anyhow!("Out of bounds write at {}.", offset))?;
+            slice.copy_from_slice(&bits.to_le_bytes()[..size]);
+            Ok(())
+        };
+
+        match (self.ty(ty)?, value) {
+            (SpirVType::Bool, SpirVValue::Bool(value)) => write(4, *value as u64),
+            (SpirVType::Int(width, _), SpirVValue::Integer { value, .. }) => {
+                write(*width as usize / 8, *value)
+            }
+            (SpirVType::Float(64), value) => write(8, value.as_f64().unwrap_or(0.0).to_bits()),
+            (SpirVType::Float(_), value) => {
+                write(4, value.as_f32().unwrap_or(0.0).to_bits() as u64)
+            }
+            (SpirVType::Vector(element, _), SpirVValue::Composite(values)) => {
+                let size = self.size_of(*element)?;
+                for (i, value) in values.iter().enumerate() {
+                    self.write_value(*element, bytes, offset + i * size, value)?;
+                }
+                Ok(())
+            }
+            (SpirVType::Matrix(column, _), SpirVValue::Composite(values)) => {
+                let stride = self.matrix_stride(*column)?;
+                for (i, value) in values.iter().enumerate() {
+                    self.write_value(*column, bytes, offset + i * stride, value)?;
+                }
+                Ok(())
+            }
+            (
+                SpirVType::Array(element, _) | SpirVType::RuntimeArray(element),
+                SpirVValue::Composite(values),
+            ) => {
+                let stride = self.array_stride(ty)?;
+                for (i, value) in values.iter().enumerate() {
+                    self.write_value(*element, bytes, offset + i * stride, value)?;
+                }
+                Ok(())
+            }
+            (SpirVType::Struct(members), SpirVValue::Composite(values)) => {
+                for (index, (member, value)) in members.iter().zip(values).enumerate() {
+                    let member_offset = offset + self.member_offset(ty, index)?;
+                    self.write_value(*member, bytes, member_offset, value)?;
+                }
+                Ok(())
+            }
+            (_, SpirVValue::Undefined) => Ok(()),
+            (ty, value) => Err(anyhow!("Can't write {:?} as {:?}.", value, ty)),
+        }
+    }
+
+    fn unary(
+        &self,
+        op_code: SpirVOpCode,
+        result_type: &SpirVType,
+        value: &SpirVValue,
+    ) -> Result<SpirVValue> {
+        match op_code {
+            SpirVOpCode::CopyObject => return Ok(value.clone()),
+            SpirVOpCode::Any | SpirVOpCode::All => {
+                let values = components(value.clone());
+                let mut bools = values.iter().map(|i| i.as_bool().unwrap_or(false));
+                return Ok(SpirVValue::Bool(match op_code {
+                    SpirVOpCode::Any => bools.any(|i| i),
+                    _ => bools.all(|i| i),
+                }));
+            }
+            _ => {}
+        }
+
+        componentwise(value, &|value| {
+            Ok(match (op_code, value) {
+                (SpirVOpCode::SNegate, SpirVValue::Integer { value, width }) => {
+                    SpirVValue::integer(value.wrapping_neg(), *width)
+                }
+                (SpirVOpCode::Not, SpirVValue::Integer { value, width }) => {
+                    SpirVValue::integer(!value, *width)
+                }
+                (SpirVOpCode::FNegate, SpirVValue::Float32(value)) => SpirVValue::Float32(-value),
+                (SpirVOpCode::FNegate, SpirVValue::Float64(value)) => SpirVValue::Float64(-value),
+                (SpirVOpCode::LogicalNot, SpirVValue::Bool(value)) => SpirVValue::Bool(!value),
+                (SpirVOpCode::IsNan, value) => {
+                    SpirVValue::Bool(value.as_f64().is_some_and(|i| i.is_nan()))
+                }
+                (SpirVOpCode::IsInf, value) => {
+                    SpirVValue::Bool(value.as_f64().is_some_and(|i| i.is_infinite()))
+                }
+                (SpirVOpCode::ConvertFToU, value) => match result_type {
+                    SpirVType::Int(width, _) => {
+                        SpirVValue::integer(value.as_f64().unwrap_or(0.0) as u64, *width)
+                    }
+                    _ => return Err(anyhow!("ConvertFToU to {:?}.", result_type)),
+                },
+                (SpirVOpCode::ConvertFToS, value) => match result_type {
+                    SpirVType::Int(width, _) => {
+                        SpirVValue::integer(value.as_f64().unwrap_or(0.0) as i64 as u64, *width)
+                    }
+                    _ => return Err(anyhow!("ConvertFToS to {:?}.", result_type)),
+                },
+                (SpirVOpCode::ConvertUToF, SpirVValue::Integer { value, .. }) => {
+                    float(result_type, *value as f64)?
+                }
+                (SpirVOpCode::ConvertSToF, value @ SpirVValue::Integer { .. }) => {
+                    float(result_type, value.as_i64().unwrap() as f64)?
+                }
+                (SpirVOpCode::FConvert, value) => {
+                    float(result_type, value.as_f64().unwrap_or(0.0))?
+                }
+                (SpirVOpCode::UConvert, SpirVValue::Integer { value, .. }) => match result_type {
+                    SpirVType::Int(width, _) => SpirVValue::integer(*value, *width),
+                    _ => return Err(anyhow!("UConvert to {:?}.", result_type)),
+                },
+                (SpirVOpCode::SConvert, value @ SpirVValue::Integer { .. }) => match result_type {
+                    SpirVType::Int(width, _) => {
+                        SpirVValue::integer(value.as_i64().unwrap() as u64, *width)
+                    }
+                    _ => return Err(anyhow!("SConvert to {:?}.", result_type)),
+                },
+                (_, SpirVValue::Undefined) => SpirVValue::Undefined,
+                (op_code, value) => {
+                    return Err(anyhow!("Invalid operand {:?} for {:?}.", value, op_code));
+                }
+            })
+        })
+    }
+}
+
+fn jump(invocation: &mut SpirVInvocation, label: SpirVVariableId) {
+    let frame = invocation.frames.last_mut().unwrap();
+    frame.previous_block = Some(frame.block);
+    frame.block = label;
+    frame.position = 0;
+}
+
+fn components(value: SpirVValue) -> Vec<SpirVValue> {
+    match value {
+        SpirVValue::Composite(values) => values,
+        value => vec![value],
+    }
+}
+
+fn element<'a>(value: &'a SpirVValue, indices: &[u32]) -> Result<&'a SpirVValue> {
+    let mut value = value;
+    for index in indices {
+        value = value
+            .elements()
+            .and_then(|i| i.get(*index as usize))
+            .ok_or(anyhow!("Index {} out of range.", index))?;
+    }
+    Ok(value)
+}
+
+fn element_mut<'a>(value: &'a mut SpirVValue, indices: &[u32]) -> Result<&'a mut SpirVValue> {
+    let mut value = value;
+    for index in indices {
+        value = match value {
+            SpirVValue::Composite(values) => values
+                .get_mut(*index as usize)
+                .ok_or(anyhow!("Index {} out of range.", index))?,
+            _ => return Err(anyhow!("Can't index into a scalar.")),
+        };
+    }
+    Ok(value)
+}
+
+fn value_to_bytes(value: &SpirVValue, bytes: &mut Vec<u8>) -> Result<()> {
+    match value {
+        SpirVValue::Bool(value) => bytes.extend((*value as u32).to_le_bytes()),
+        SpirVValue::Integer { value, width } => {
+            bytes.extend(&value.to_le_bytes()[..*width as usize / 8])
+        }
+        SpirVValue::Float32(value) => bytes.extend(value.to_le_bytes()),
+        SpirVValue::Float64(value) => bytes.extend(value.to_le_bytes()),
+        SpirVValue::Composite(values) => {
+            for i in values {
+                value_to_bytes(i, bytes)?;
+            }
+        }
+        other => return Err(anyhow!("Can't bitcast {:?}.", other)),
+    }
+    Ok(())
+}
+
+fn float(ty: &SpirVType, value: f64) -> Result<SpirVValue> {
+    match ty {
+        SpirVType::Float(64) => Ok(SpirVValue::Float64(value)),
+        SpirVType::Float(_) => Ok(SpirVValue::Float32(value as f32)),
+        other => Err(anyhow!("{:?} isn't a float type.", other)),
+    }
+}
+
+/// Same float variant as `like`.
+fn float_like(like: &SpirVValue, value: f64) -> SpirVValue {
+    match like {
+        SpirVValue::Float64(_) => SpirVValue::Float64(value),
+        _ => SpirVValue::Float32(value as f32),
+    }
+}
+
+fn componentwise(
+    value: &SpirVValue,
+    f: &dyn Fn(&SpirVValue) -> Result<SpirVValue>,
+) -> Result<SpirVValue> {
+    match value {
+        SpirVValue::Composite(values) => Ok(SpirVValue::Composite(
+            values.iter().map(f).collect::<Result<_>>()?,
+        )),
+        value => f(value),
+    }
+}
+
+/// Applies `f` per component. Scalar operands are reused for every component.
+fn componentwise_n(
+    values: &[SpirVValue],
+    f: &dyn Fn(&[SpirVValue]) -> Result<SpirVValue>,
+) -> Result<SpirVValue> {
+    let count = values.iter().find_map(|i| i.elements().map(|i| i.len()));
+
+    match count {
+        None => f(values),
+        Some(count) => {
+            let mut result = vec![];
+            for index in 0..count {
+                let operands = values
+                    .iter()
+                    .map(|i| match i {
+                        SpirVValue::Composite(elements) => {
+                            elements.get(index).cloned().unwrap_or_default()
+                        }
+                        scalar => scalar.clone(),
+                    })
+                    .collect::<Vec<_>>();
+                result.push(f(&operands)?);
+            }
+            Ok(SpirVValue::Composite(result))
+        }
+    }
+}
+
+fn select_value(condition: &SpirVValue, a: SpirVValue, b: SpirVValue) -> Result<SpirVValue> {
+    match condition {
+        SpirVValue::Bool(true) => Ok(a),
+        SpirVValue::Bool(false) => Ok(b),
+        SpirVValue::Composite(conditions) => Ok(SpirVValue::Composite(
+            conditions
+                .iter()
+                .zip(components(a).into_iter().zip(components(b)))
+                .map(|(condition, (a, b))| select_value(condition, a, b))
+                .collect::<Result<_>>()?,
+        )),
+        other => Err(anyhow!("Select condition {:?} isn't a bool.", other)),
+    }
+}
+
+fn binary_op(op_code: SpirVOpCode, a: &SpirVValue, b: &SpirVValue) -> Result<SpirVValue> {
+    match op_code {
+        SpirVOpCode::VectorTimesScalar => {
+            return componentwise(a, &|a| binary_op(SpirVOpCode::FMul, a, b));
+        }
+        SpirVOpCode::MatrixTimesScalar => {
+            return componentwise(a, &|a| binary_op(SpirVOpCode::VectorTimesScalar, a, b));
+        }
+        // Matrices are lists of columns.
+        SpirVOpCode::MatrixTimesVector => {
+            let mut result: Option<SpirVValue> = None;
+            for (column, scalar) in components(a.clone()).iter().zip(components(b.clone())) {
+                let term = binary_op(SpirVOpCode::VectorTimesScalar, column, &scalar)?;
+                result = Some(match result {
+                    Some(sum) => binary_op(SpirVOpCode::FAdd, &sum, &term)?,
+                    None => term,
+                });
+            }
+            return result.ok_or(anyhow!("Matrix without columns."));
+        }
+        SpirVOpCode::VectorTimesMatrix => {
+            return Ok(SpirVValue::Composite(
+                components(b.clone())
+                    .iter()
+                    .map(|column| binary_op(SpirVOpCode::Dot, a, column))
+                    .collect::<Result<_>>()?,
+            ));
+        }
+        SpirVOpCode::MatrixTimesMatrix => {
+            return componentwise(b, &|column| {
+                binary_op(SpirVOpCode::MatrixTimesVector, a, column)
+            });
+        }
+        SpirVOpCode::Dot => {
+            let a = components(a.clone());
+            let b = components(b.clone());
+            let mut sum = 0.0;
+            for (a, b) in a.iter().zip(&b) {
+                sum += a.as_f64().unwrap_or(0.0) * b.as_f64().unwrap_or(0.0);
+            }
+            return Ok(float_like(&a[0], sum));
+        }
+        SpirVOpCode::VectorExtractDynamic => {
+            let index = b
+                .as_u64()
+                .ok_or(anyhow!("Vector index isn't an integer."))?;
+            return Ok(a
+                .elements()
+                .and_then(|i| i.get(index as usize))
+                .cloned()
+                .unwrap_or_default());
+        }
+        _ => {}
+    }
+
+    componentwise_n(&[a.clone(), b.clone()], &|operands| {
+        scalar_binary_op(op_code, &operands[0], &operands[1])
+    })
+}
+
+fn scalar_binary_op(op_code: SpirVOpCode, a: &SpirVValue, b: &SpirVValue) -> Result<SpirVValue> {
+    use SpirVOpCode as Op;
+
+    if let (SpirVValue::Bool(a), SpirVValue::Bool(b)) = (a, b) {
+        return Ok(SpirVValue::Bool(match op_code {
+            Op::LogicalEqual => a == b,
+            Op::LogicalNotEqual => a != b,
+            Op::LogicalOr => *a || *b,
+            Op::LogicalAnd => *a && *b,
+            op_code => return Err(anyhow!("{:?} on bools.", op_code)),
+        }));
+    }
+
+    if let (SpirVValue::Integer { value: x, width: w }, SpirVValue::Integer { value: y, .. }) =
+        (a, b)
+    {
+        let (x, y, w) = (*x, *y, *w);
+        let (sx, sy) = (sign_extend(x, w), sign_extend(y, w));
+        let integer = |value: u64| SpirVValue::integer(value, w);
+
+        return Ok(match op_code {
+            Op::IAdd => integer(x.wrapping_add(y)),
+            Op::ISub => integer(x.wrapping_sub(y)),
+            Op::IMul => integer(x.wrapping_mul(y)),
+            Op::UDiv => integer(x.checked_div(y).unwrap_or(0)),
+            Op::SDiv => integer(sx.checked_div(sy).unwrap_or(0) as u64),
+            Op::UMod => integer(x.checked_rem(y).unwrap_or(0)),
+            Op::SRem => integer(sx.checked_rem(sy).unwrap_or(0) as u64),
+            Op::SMod => {
+                let rem = sx.checked_rem(sy).unwrap_or(0);
+                let rem = match rem != 0 && (rem < 0) != (sy < 0) {
+                    true => rem + sy,
+                    false => rem,
+                };
+                integer(rem as u64)
+            }
+            Op::ShiftLeftLogical => integer(x.wrapping_shl(y as u32)),
+            Op::ShiftRightLogical => integer(x.wrapping_shr(y as u32)),
+            Op::ShiftRightArithmetic => integer(sx.wrapping_shr(y as u32) as u64),
+            Op::BitwiseOr => integer(x | y),
+            Op::BitwiseXor => integer(x ^ y),
+            Op::BitwiseAnd => integer(x & y),
+            Op::IEqual => SpirVValue::Bool(x == y),
+            Op::INotEqual => SpirVValue::Bool(x != y),
+            Op::UGreaterThan => SpirVValue::Bool(x > y),
+            Op::SGreaterThan => SpirVValue::Bool(sx > sy),
+            Op::UGreaterThanEqual => SpirVValue::Bool(x >= y),
+            Op::SGreaterThanEqual => SpirVValue::Bool(sx >= sy),
+            Op::ULessThan => SpirVValue::Bool(x < y),
+            Op::SLessThan => SpirVValue::Bool(sx < sy),
+            Op::ULessThanEqual => SpirVValue::Bool(x <= y),
+            Op::SLessThanEqual => SpirVValue::Bool(sx <= sy),
+            op_code => return Err(anyhow!("{:?} on integers.", op_code)),
+        });
+    }
+
+    let (Some(x), Some(y)) = (a.as_f64(), b.as_f64()) else {
+        return match (a, b) {
+            (SpirVValue::Undefined, _) | (_, SpirVValue::Undefined) => Ok(SpirVValue::Undefined),
+            _ => Err(anyhow!(
+                "Invalid operands {:?}, {:?} for {:?}.",
+                a,
+                b,
+                op_code
+            )),
+        };
+    };
+
+    let unordered = x.is_nan() || y.is_nan();
+
+    Ok(match op_code {
+        Op::FAdd => float_like(a, x + y),
+        Op::FSub => float_like(a, x - y),
+        Op::FMul => float_like(a, x * y),
+        Op::FDiv => float_like(a, x / y),
+        Op::FRem => float_like(a, x % y),
+        Op::FMod => float_like(a, x - y * (x / y).floor()),
+        Op::FOrdEqual => SpirVValue::Bool(x == y),
+        Op::FOrdNotEqual => SpirVValue::Bool(!unordered && x != y),
+        Op::FOrdLessThan => SpirVValue::Bool(x < y),
+        Op::FOrdGreaterThan => SpirVValue::Bool(x > y),
+        Op::FOrdLessThanEqual => SpirVValue::Bool(x <= y),
+        Op::FOrdGreaterThanEqual => SpirVValue::Bool(x >= y),
+        Op::FUnordEqual => SpirVValue::Bool(unordered || x == y),
+        Op::FUnordNotEqual => SpirVValue::Bool(x != y),
+        Op::FUnordLessThan => SpirVValue::Bool(unordered || x < y),
+        Op::FUnordGreaterThan => SpirVValue::Bool(unordered || x > y),
+        Op::FUnordLessThanEqual => SpirVValue::Bool(unordered || x <= y),
+        Op::FUnordGreaterThanEqual => SpirVValue::Bool(unordered || x >= y),
+        op_code => return Err(anyhow!("{:?} on floats.", op_code)),
+    })
+}
+
+/// Instruction numbers from the `GLSL.std.450` extended instruction set.
+fn glsl_std_450(instruction: u32, operands: &[SpirVValue]) -> Result<SpirVValue> {
+    let operand = |index: usize| {
+        operands
+            .get(index)
+            .ok_or(anyhow!("GLSL.std.450 {} is missing operands.", instruction))
+    };
+
+    // Geometric functions work on whole vectors.
+    match instruction {
+        66 => return Ok(float_like(&first(operand(0)?), length(operand(0)?))),
+        67 => {
+            let difference = binary_op(SpirVOpCode::FSub, operand(0)?, operand(1)?)?;
+            return Ok(float_like(&first(&difference), length(&difference)));
+        }
+        68 => {
+            let a = operand(0)?.as_f32_vec().unwrap_or_default();
+            let b = operand(1)?.as_f32_vec().unwrap_or_default();
+            if a.len() != 3 || b.len() != 3 {
+                return Err(anyhow!("Cross needs two 3 component vectors."));
+            }
+            return Ok(SpirVValue::from([
+                a[1] * b[2] - b[1] * a[2],
+                a[2] * b[0] - b[2] * a[0],
+                a[0] * b[1] - b[0] * a[1],
+            ]));
+        }
+        69 => {
+            let length = length(operand(0)?);
+            return componentwise(operand(0)?, &|i| {
+                Ok(float_like(i, i.as_f64().unwrap_or(0.0) / length))
+            });
+        }
+        71 => {
+            // I - 2 * dot(N, I) * N
+            let (i, n) = (operand(0)?, operand(1)?);
+            let dot = binary_op(SpirVOpCode::Dot, n, i)?.as_f64().unwrap_or(0.0);
+            return componentwise_n(&[i.clone(), n.clone()], &|v| {
+                let (i, n) = (v[0].as_f64().unwrap_or(0.0), v[1].as_f64().unwrap_or(0.0));
+                Ok(float_like(&v[0], i - 2.0 * dot * n))
+            });
+        }
+        _ => {}
+    }
+
+    componentwise_n(operands, &|v| {
+        let f = |index: usize| v.get(index).and_then(|i| i.as_f64()).unwrap_or(0.0);
+        let u = |index: usize| v.get(index).and_then(|i| i.as_u64()).unwrap_or(0);
+        let s = |index: usize| v.get(index).and_then(|i| i.as_i64()).unwrap_or(0);
+        let like = &v[0];
+        let integer = |value: u64| match like {
+            SpirVValue::Integer { width, .. } => SpirVValue::integer(value, *width),
+            _ => SpirVValue::integer(value, 32),
+        };
+
+        Ok(match instruction {
+            1 => float_like(like, f(0).round()),
+            2 => float_like(like, round_even(f(0))),
+            3 => float_like(like, f(0).trunc()),
+            4 => float_like(like, f(0).abs()),
+            5 => integer(s(0).wrapping_abs() as u64),
+            6 => float_like(like, sign(f(0))),
+            7 => integer(s(0).signum() as u64),
+            8 => float_like(like, f(0).floor()),
+            9 => float_like(like, f(0).ceil()),
+            10 => float_like(like, f(0) - f(0).floor()),
+            11 => float_like(like, f(0).to_radians()),
+            12 => float_like(like, f(0).to_degrees()),
+            13 => float_like(like, f(0).sin()),
+            14 => float_like(like, f(0).cos()),
+            15 => float_like(like, f(0).tan()),
+            16 => float_like(like, f(0).asin()),
+            17 => float_like(like, f(0).acos()),
+            18 => float_like(like, f(0).atan()),
+            19 => float_like(like, f(0).sinh()),
+            20 => float_like(like, f(0).cosh()),
+            21 => float_like(like, f(0).tanh()),
+            22 => float_like(like, f(0).asinh()),
+            23 => float_like(like, f(0).acosh()),
+            24 => float_like(like, f(0).atanh()),
+            25 => float_like(like, f(0).atan2(f(1))),
+            26 => float_like(like, f(0).powf(f(1))),
+            27 => float_like(like, f(0).exp()),
+            28 => float_like(like, f(0).ln()),
+            29 => float_like(like, f(0).exp2()),
+            30 => float_like(like, f(0).log2()),
+            31 => float_like(like, f(0).sqrt()),
+            32 => float_like(like, 1.0 / f(0).sqrt()),
+            37 | 79 => float_like(like, f(0).min(f(1))),
+            38 => integer(u(0).min(u(1))),
+            39 => integer(s(0).min(s(1)) as u64),
+            40 | 80 => float_like(like, f(0).max(f(1))),
+            41 => integer(u(0).max(u(1))),
+            42 => integer(s(0).max(s(1)) as u64),
+            43 | 81 => float_like(like, f(0).max(f(1)).min(f(2))),
+            44 => integer(u(0).max(u(1)).min(u(2))),
+            45 => integer(s(0).max(s(1)).min(s(2)) as u64),
+            46 => float_like(like, f(0) * (1.0 - f(2)) + f(1) * f(2)),
+            48 => float_like(like, if f(1) < f(0) { 0.0 } else { 1.0 }),
+            49 => {
+                let t = ((f(2) - f(0)) / (f(1) - f(0))).clamp(0.0, 1.0);
+                float_like(like, t * t * (3.0 - 2.0 * t))
+            }
+            50 => float_like(like, f(0).mul_add(f(1), f(2))),
+            53 => float_like(like, f(0) * 2f64.powi(s(1) as i32)),
+            instruction => {
+                return Err(anyhow!(
+                    "GLSL.std.450 instruction {} isn't supported.",
+                    instruction
+                ));
+            }
+        })
+    })
+}
+
+fn first(value: &SpirVValue) -> SpirVValue {
+    components(value.clone())
+        .into_iter()
+        .next()
+        .unwrap_or_default()
+}
+
+fn length(value: &SpirVValue) -> f64 {
+    components(value.clone())
+        .iter()
+        .map(|i| i.as_f64().unwrap_or(0.0).powi(2))
+        .sum::<f64>()
+        .sqrt()
+}
+
+fn sign(value: f64) -> f64 {
+    match value {
+        value if value > 0.0 => 1.0,
+        value if value < 0.0 => -1.0,
+        _ => 0.0,
+    }
+}
+
+fn round_even(value: f64) -> f64 {
+    let rounded = value.round();
+    match (value - value.trunc()).abs() == 0.5 && rounded % 2.0 != 0.0 {
+        true => rounded - value.signum(),
+        false => rounded,
+    }
+}
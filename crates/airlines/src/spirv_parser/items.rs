@@ -1,3 +1,5 @@
+use anyhow::{Result, anyhow};
+
 #[derive(Debug, Default, Clone)]
 pub struct SpirVSignature {
     pub magic_number: u32,
@@ -15,6 +17,7 @@ pub enum SpirVOp {
     ExtendedInstructionImport(SpirVVariableId, String),
     MemoryModel(SpirVAddressingModel, SpirVMemoryModel),
     EntryPoint(SpirVEntryPoint),
+    String(SpirVVariableId, String),
     Source(SpirVSource),
     SourceExtension(String),
     Name(SpirVVariableId, String),
@@ -37,8 +40,110 @@ pub enum SpirVOp {
     ReturnValue(SpirVVariableId),
     Function(SpirVVariableId, SpirVFunction),
     BitCast(SpirVVariableId, SpirVBitCast),
+    /// Single operand instructions (negation, conversions, ...), the
+    /// op code tells which one.
+    Unary(SpirVVariableId, SpirVOpCode, SpirVUnaryOp),
+    /// Two operand arithmetic, bitwise, logical and comparison instructions.
+    Binary(SpirVVariableId, SpirVOpCode, SpirVBinaryOp),
+    Select(SpirVVariableId, SpirVSelect),
     VectorShuffle(SpirVVariableId, SpirVVectorShuffle),
+    VectorInsertDynamic(SpirVVariableId, SpirVVectorInsertDynamic),
+    ExtendedInstruction(SpirVVariableId, SpirVExtendedInstruction),
+    ArrayLength(SpirVVariableId, SpirVArrayLength),
     Struct(SpirVVariableId, Vec<SpirVVariableId>),
+    ExecutionMode(SpirVVariableId, SpirVExecutionMode),
+    FunctionParameter(SpirVVariableId, SpirVVariableId),
+    FunctionCall(SpirVVariableId, SpirVFunctionCall),
+    Phi(SpirVVariableId, SpirVPhi),
+    SelectionMerge(SpirVVariableId),
+    LoopMerge(SpirVVariableId, SpirVVariableId),
+    Branch(SpirVVariableId),
+    BranchConditional(SpirVBranchConditional),
+    Switch(SpirVSwitch),
+    Kill,
+    Unreachable,
+    ControlBarrier,
+    MemoryBarrier,
+    Line(SpirVLine),
+    NoLine,
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct SpirVSelect {
+    pub type_id: SpirVVariableId,
+    pub condition: SpirVVariableId,
+    pub object1: SpirVVariableId,
+    pub object2: SpirVVariableId,
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct SpirVVectorInsertDynamic {
+    pub type_id: SpirVVariableId,
+    pub vector: SpirVVariableId,
+    pub component: SpirVVariableId,
+    pub index: SpirVVariableId,
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct SpirVExtendedInstruction {
+    pub type_id: SpirVVariableId,
+    pub set: SpirVVariableId,
+    pub instruction: u32,
+    pub operands: Vec<SpirVVariableId>,
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct SpirVArrayLength {
+    pub type_id: SpirVVariableId,
+    pub structure: SpirVVariableId,
+    pub member: u32,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub enum SpirVExecutionMode {
+    #[default]
+    OriginUpperLeft,
+    OriginLowerLeft,
+    DepthReplacing,
+    LocalSize(u32, u32, u32),
+    /// Modes that don't change how a shader is executed, kept as the raw
+    /// mode and literals.
+    Other(u32, Vec<u32>),
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct SpirVFunctionCall {
+    pub type_id: SpirVVariableId,
+    pub function: SpirVVariableId,
+    pub arguments: Vec<SpirVVariableId>,
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct SpirVPhi {
+    pub type_id: SpirVVariableId,
+    /// (value, parent block) pairs.
+    pub incoming: Vec<(SpirVVariableId, SpirVVariableId)>,
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct SpirVBranchConditional {
+    pub condition: SpirVVariableId,
+    pub true_label: SpirVVariableId,
+    pub false_label: SpirVVariableId,
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct SpirVSwitch {
+    pub selector: SpirVVariableId,
+    pub default: SpirVVariableId,
+    pub targets: Vec<(u64, SpirVVariableId)>,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct SpirVLine {
+    pub file: SpirVVariableId,
+    pub line: u32,
+    pub column: u32,
 }
 
 #[derive(Debug, Default, Clone)]
@@ -47,6 +152,19 @@ pub struct SpirVBitCast {
     pub to_type: SpirVVariableId,
 }
 
+#[derive(Debug, Default, Clone)]
+pub struct SpirVUnaryOp {
+    pub type_id: SpirVVariableId,
+    pub operand: SpirVVariableId,
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct SpirVBinaryOp {
+    pub type_id: SpirVVariableId,
+    pub operand1: SpirVVariableId,
+    pub operand2: SpirVVariableId,
+}
+
 #[derive(Debug, Default, Clone)]
 pub struct SpirVCompositeConstruct {
     pub type_id: SpirVVariableId,
@@ -105,18 +223,18 @@ pub enum SpirVMemoryOperands {
 }
 
 impl SpirVMemoryOperands {
-    pub fn from_u32(v: u32) -> Self {
+    pub fn from_u32(v: u32) -> Result<Self> {
         match v {
-            0x0 => Self::None,
-            0x1 => Self::Volatile,
-            0x2 => Self::Aligned,
-            0x4 => Self::NonTemporal,
-            0x8 => Self::MakePointerAvailable,
-            0x10 => Self::MakePointerVisible,
-            0x20 => Self::NonPrivatePointer,
-            0x10000 => Self::AliasScopeINTELMask,
-            0x20000 => Self::NoAliasINTELMask,
-            _ => unimplemented!("{:#X}", v),
+            0x0 => Ok(Self::None),
+            0x1 => Ok(Self::Volatile),
+            0x2 => Ok(Self::Aligned),
+            0x4 => Ok(Self::NonTemporal),
+            0x8 => Ok(Self::MakePointerAvailable),
+            0x10 => Ok(Self::MakePointerVisible),
+            0x20 => Ok(Self::NonPrivatePointer),
+            0x10000 => Ok(Self::AliasScopeINTELMask),
+            0x20000 => Ok(Self::NoAliasINTELMask),
+            _ => Err(anyhow!("{:#X} is not a valid memory operand.", v)),
         }
     }
 }
@@ -148,11 +266,59 @@ pub enum SpirVType {
     Void,
     Function(SpirVVariableId, Vec<SpirVVariableId>),
     Float(u32),
+    Bool,
     Int(u32, bool),
     Vector(SpirVVariableId, u32),
+    /// Column type and column count.
+    Matrix(SpirVVariableId, u32),
     Array(SpirVVariableId, SpirVVariableId),
+    RuntimeArray(SpirVVariableId),
     Pointer(SpirVStorageClass, SpirVVariableId),
     Struct(Vec<SpirVVariableId>),
+    Image(SpirVImageType),
+    Sampler,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct SpirVImageType {
+    pub sampled_type: SpirVVariableId,
+    pub dim: SpirVDim,
+    /// 0 for no depth, 1 for depth, 2 for unknown.
+    pub depth: u32,
+    pub arrayed: bool,
+    pub multisampled: bool,
+    /// 1 when used with a sampler, 2 for storage images.
+    pub sampled: u32,
+    /// `Unknown` is the only format that's emitted for now.
+    pub format: u32,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[repr(u32)]
+pub enum SpirVDim {
+    Dim1D = 0,
+    #[default]
+    Dim2D,
+    Dim3D,
+    Cube,
+    Rect,
+    Buffer,
+    SubpassData,
+}
+
+impl SpirVDim {
+    pub fn from_u32(v: u32) -> Result<Self> {
+        match v {
+            0 => Ok(Self::Dim1D),
+            1 => Ok(Self::Dim2D),
+            2 => Ok(Self::Dim3D),
+            3 => Ok(Self::Cube),
+            4 => Ok(Self::Rect),
+            5 => Ok(Self::Buffer),
+            6 => Ok(Self::SubpassData),
+            _ => Err(anyhow!("{:#X} is not a valid dimension.", v)),
+        }
+    }
 }
 
 #[derive(Debug, Default, Clone)]
@@ -196,6 +362,7 @@ pub struct SpirVConstantComposite {
 pub enum SpirVConstantValue {
     #[default]
     Undefined,
+    Bool(bool),
     SignedInteger(i64),
     UnsignedInteger(u64),
     Float32(f32),
@@ -213,11 +380,22 @@ pub struct SpirVDecorate {
 pub enum SpirVDecorateType {
     #[default]
     Block,
+    BufferBlock,
+    RowMajor,
+    ColMajor,
+    ArrayStride(u32),
+    MatrixStride(u32),
     BuiltIn(SpirVBuiltIn),
+    NoPerspective,
+    Flat,
+    NonWritable,
     Location(u32),
+    Binding(u32),
+    DescriptorSet(u32),
+    Offset(u32),
 }
 
-#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
 #[repr(u32)]
 pub enum SpirVBuiltIn {
     #[default]
@@ -261,6 +439,7 @@ pub enum SpirVBuiltIn {
     SubgroupId,
     SubgroupLocalInvocationId,
     VertexIndex,
+    InstanceIndex,
 }
 
 #[derive(Debug, Default, Clone, PartialEq)]
@@ -349,83 +528,281 @@ pub enum SpirVExecutionModel {
     MeshEXT,
 }
 
-#[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
 #[repr(u32)]
 pub enum SpirVOpCode {
     #[default]
     Empty = 0,
+    Undef = 1,
     Source = 3,
     SourceExtension = 4,
     Name = 5,
     MemberName = 6,
+    String = 7,
+    Line = 8,
     ExtInstImport = 11,
+    ExtInst = 12,
     MemoryModel = 14,
     EntryPoint = 15,
+    ExecutionMode = 16,
     Capability = 17,
     TypeVoid = 19,
+    TypeBool = 20,
     TypeInt = 21,
     TypeFloat = 22,
     TypeVector = 23,
+    TypeMatrix = 24,
+    TypeImage = 25,
+    TypeSampler = 26,
     TypeArray = 28,
+    TypeRuntimeArray = 29,
     TypeStruct = 30,
     TypePointer = 32,
     TypeFunction = 33,
+    ConstantTrue = 41,
+    ConstantFalse = 42,
     Constant = 43,
     ConstantComposite = 44,
+    ConstantNull = 46,
     Function = 54,
+    FunctionParameter = 55,
     FunctionEnd = 56,
+    FunctionCall = 57,
     Variable = 59,
     Load = 61,
     Store = 62,
     AccessChain = 65,
+    ArrayLength = 68,
     Decorate = 71,
     MemberDecorate = 72,
+    VectorExtractDynamic = 77,
+    VectorInsertDynamic = 78,
     VectorShuffle = 79,
     CompositeConstruct = 80,
     CompositeExtract = 81,
     CompositeInsert = 82,
+    CopyObject = 83,
+    ConvertFToU = 109,
+    ConvertFToS = 110,
+    ConvertSToF = 111,
+    ConvertUToF = 112,
+    UConvert = 113,
+    SConvert = 114,
+    FConvert = 115,
     BitCast = 124,
+    SNegate = 126,
+    FNegate = 127,
+    IAdd = 128,
+    FAdd = 129,
+    ISub = 130,
+    FSub = 131,
+    IMul = 132,
+    FMul = 133,
+    UDiv = 134,
+    SDiv = 135,
+    FDiv = 136,
+    UMod = 137,
+    SRem = 138,
+    SMod = 139,
+    FRem = 140,
+    FMod = 141,
+    VectorTimesScalar = 142,
+    MatrixTimesScalar = 143,
+    VectorTimesMatrix = 144,
+    MatrixTimesVector = 145,
+    MatrixTimesMatrix = 146,
+    Dot = 148,
+    Any = 154,
+    All = 155,
+    IsNan = 156,
+    IsInf = 157,
+    LogicalEqual = 164,
+    LogicalNotEqual = 165,
+    LogicalOr = 166,
+    LogicalAnd = 167,
+    LogicalNot = 168,
+    Select = 169,
+    IEqual = 170,
+    INotEqual = 171,
+    UGreaterThan = 172,
+    SGreaterThan = 173,
+    UGreaterThanEqual = 174,
+    SGreaterThanEqual = 175,
+    ULessThan = 176,
+    SLessThan = 177,
+    ULessThanEqual = 178,
+    SLessThanEqual = 179,
+    FOrdEqual = 180,
+    FUnordEqual = 181,
+    FOrdNotEqual = 182,
+    FUnordNotEqual = 183,
+    FOrdLessThan = 184,
+    FUnordLessThan = 185,
+    FOrdGreaterThan = 186,
+    FUnordGreaterThan = 187,
+    FOrdLessThanEqual = 188,
+    FUnordLessThanEqual = 189,
+    FOrdGreaterThanEqual = 190,
+    FUnordGreaterThanEqual = 191,
+    ShiftRightLogical = 194,
+    ShiftRightArithmetic = 195,
+    ShiftLeftLogical = 196,
+    BitwiseOr = 197,
+    BitwiseXor = 198,
+    BitwiseAnd = 199,
+    Not = 200,
+    ControlBarrier = 224,
+    MemoryBarrier = 225,
+    Phi = 245,
+    LoopMerge = 246,
+    SelectionMerge = 247,
     Label = 248,
+    Branch = 249,
+    BranchConditional = 250,
+    Switch = 251,
+    Kill = 252,
     Return = 253,
+    ReturnValue = 254,
+    Unreachable = 255,
+    NoLine = 317,
 }
 
 impl SpirVOpCode {
-    pub fn from_u32(v: u32) -> Self {
+    pub fn from_u32(v: u32) -> Result<Self> {
         match v {
-            3 => Self::Source,
-            4 => Self::SourceExtension,
-            5 => Self::Name,
-            6 => Self::MemberName,
-            11 => Self::ExtInstImport,
-            14 => Self::MemoryModel,
-            15 => Self::EntryPoint,
-            17 => Self::Capability,
-            19 => Self::TypeVoid,
-            21 => Self::TypeInt,
-            22 => Self::TypeFloat,
-            23 => Self::TypeVector,
-            28 => Self::TypeArray,
-            30 => Self::TypeStruct,
-            32 => Self::TypePointer,
-            33 => Self::TypeFunction,
-            43 => Self::Constant,
-            44 => Self::ConstantComposite,
-            54 => Self::Function,
-            56 => Self::FunctionEnd,
-            59 => Self::Variable,
-            61 => Self::Load,
-            62 => Self::Store,
-            65 => Self::AccessChain,
-            71 => Self::Decorate,
-            72 => Self::MemberDecorate,
-            79 => Self::VectorShuffle,
-            80 => Self::CompositeConstruct,
-            81 => Self::CompositeExtract,
-            82 => Self::CompositeInsert,
-            124 => Self::BitCast,
-            248 => Self::Label,
-            253 => Self::Return,
-            _ => todo!("{:?}", v),
+            1 => Ok(Self::Undef),
+            3 => Ok(Self::Source),
+            4 => Ok(Self::SourceExtension),
+            5 => Ok(Self::Name),
+            6 => Ok(Self::MemberName),
+            7 => Ok(Self::String),
+            8 => Ok(Self::Line),
+            11 => Ok(Self::ExtInstImport),
+            12 => Ok(Self::ExtInst),
+            14 => Ok(Self::MemoryModel),
+            15 => Ok(Self::EntryPoint),
+            16 => Ok(Self::ExecutionMode),
+            17 => Ok(Self::Capability),
+            19 => Ok(Self::TypeVoid),
+            20 => Ok(Self::TypeBool),
+            21 => Ok(Self::TypeInt),
+            22 => Ok(Self::TypeFloat),
+            23 => Ok(Self::TypeVector),
+            24 => Ok(Self::TypeMatrix),
+            25 => Ok(Self::TypeImage),
+            26 => Ok(Self::TypeSampler),
+            28 => Ok(Self::TypeArray),
+            29 => Ok(Self::TypeRuntimeArray),
+            30 => Ok(Self::TypeStruct),
+            32 => Ok(Self::TypePointer),
+            33 => Ok(Self::TypeFunction),
+            41 => Ok(Self::ConstantTrue),
+            42 => Ok(Self::ConstantFalse),
+            43 => Ok(Self::Constant),
+            44 => Ok(Self::ConstantComposite),
+            46 => Ok(Self::ConstantNull),
+            54 => Ok(Self::Function),
+            55 => Ok(Self::FunctionParameter),
+            56 => Ok(Self::FunctionEnd),
+            57 => Ok(Self::FunctionCall),
+            59 => Ok(Self::Variable),
+            61 => Ok(Self::Load),
+            62 => Ok(Self::Store),
+            65 => Ok(Self::AccessChain),
+            68 => Ok(Self::ArrayLength),
+            71 => Ok(Self::Decorate),
+            72 => Ok(Self::MemberDecorate),
+            77 => Ok(Self::VectorExtractDynamic),
+            78 => Ok(Self::VectorInsertDynamic),
+            79 => Ok(Self::VectorShuffle),
+            80 => Ok(Self::CompositeConstruct),
+            81 => Ok(Self::CompositeExtract),
+            82 => Ok(Self::CompositeInsert),
+            83 => Ok(Self::CopyObject),
+            109 => Ok(Self::ConvertFToU),
+            110 => Ok(Self::ConvertFToS),
+            111 => Ok(Self::ConvertSToF),
+            112 => Ok(Self::ConvertUToF),
+            113 => Ok(Self::UConvert),
+            114 => Ok(Self::SConvert),
+            115 => Ok(Self::FConvert),
+            124 => Ok(Self::BitCast),
+            126 => Ok(Self::SNegate),
+            127 => Ok(Self::FNegate),
+            128 => Ok(Self::IAdd),
+            129 => Ok(Self::FAdd),
+            130 => Ok(Self::ISub),
+            131 => Ok(Self::FSub),
+            132 => Ok(Self::IMul),
+            133 => Ok(Self::FMul),
+            134 => Ok(Self::UDiv),
+            135 => Ok(Self::SDiv),
+            136 => Ok(Self::FDiv),
+            137 => Ok(Self::UMod),
+            138 => Ok(Self::SRem),
+            139 => Ok(Self::SMod),
+            140 => Ok(Self::FRem),
+            141 => Ok(Self::FMod),
+            142 => Ok(Self::VectorTimesScalar),
+            143 => Ok(Self::MatrixTimesScalar),
+            144 => Ok(Self::VectorTimesMatrix),
+            145 => Ok(Self::MatrixTimesVector),
+            146 => Ok(Self::MatrixTimesMatrix),
+            148 => Ok(Self::Dot),
+            154 => Ok(Self::Any),
+            155 => Ok(Self::All),
+            156 => Ok(Self::IsNan),
+            157 => Ok(Self::IsInf),
+            164 => Ok(Self::LogicalEqual),
+            165 => Ok(Self::LogicalNotEqual),
+            166 => Ok(Self::LogicalOr),
+            167 => Ok(Self::LogicalAnd),
+            168 => Ok(Self::LogicalNot),
+            169 => Ok(Self::Select),
+            170 => Ok(Self::IEqual),
+            171 => Ok(Self::INotEqual),
+            172 => Ok(Self::UGreaterThan),
+            173 => Ok(Self::SGreaterThan),
+            174 => Ok(Self::UGreaterThanEqual),
+            175 => Ok(Self::SGreaterThanEqual),
+            176 => Ok(Self::ULessThan),
+            177 => Ok(Self::SLessThan),
+            178 => Ok(Self::ULessThanEqual),
+            179 => Ok(Self::SLessThanEqual),
+            180 => Ok(Self::FOrdEqual),
+            181 => Ok(Self::FUnordEqual),
+            182 => Ok(Self::FOrdNotEqual),
+            183 => Ok(Self::FUnordNotEqual),
+            184 => Ok(Self::FOrdLessThan),
+            185 => Ok(Self::FUnordLessThan),
+            186 => Ok(Self::FOrdGreaterThan),
+            187 => Ok(Self::FUnordGreaterThan),
+            188 => Ok(Self::FOrdLessThanEqual),
+            189 => Ok(Self::FUnordLessThanEqual),
+            190 => Ok(Self::FOrdGreaterThanEqual),
+            191 => Ok(Self::FUnordGreaterThanEqual),
+            194 => Ok(Self::ShiftRightLogical),
+            195 => Ok(Self::ShiftRightArithmetic),
+            196 => Ok(Self::ShiftLeftLogical),
+            197 => Ok(Self::BitwiseOr),
+            198 => Ok(Self::BitwiseXor),
+            199 => Ok(Self::BitwiseAnd),
+            200 => Ok(Self::Not),
+            224 => Ok(Self::ControlBarrier),
+            225 => Ok(Self::MemoryBarrier),
+            245 => Ok(Self::Phi),
+            246 => Ok(Self::LoopMerge),
+            247 => Ok(Self::SelectionMerge),
+            248 => Ok(Self::Label),
+            249 => Ok(Self::Branch),
+            250 => Ok(Self::BranchConditional),
+            251 => Ok(Self::Switch),
+            252 => Ok(Self::Kill),
+            253 => Ok(Self::Return),
+            254 => Ok(Self::ReturnValue),
+            255 => Ok(Self::Unreachable),
+            317 => Ok(Self::NoLine),
+            _ => Err(anyhow!("'{:?}' is not a valid opcode.", v)),
         }
     }
 }
@@ -443,15 +820,15 @@ pub enum FunctionControl {
 }
 
 impl FunctionControl {
-    pub fn from_u32(v: u32) -> Self {
+    pub fn from_u32(v: u32) -> Result<Self> {
         match v {
-            0x0 => Self::None,
-            0x1 => Self::Inline,
-            0x2 => Self::DontInline,
-            0x4 => Self::Pure,
-            0x8 => Self::Const,
-            0x10000 => Self::OptNoneEXT,
-            _ => unimplemented!(),
+            0x0 => Ok(Self::None),
+            0x1 => Ok(Self::Inline),
+            0x2 => Ok(Self::DontInline),
+            0x4 => Ok(Self::Pure),
+            0x8 => Ok(Self::Const),
+            0x10000 => Ok(Self::OptNoneEXT),
+            _ => Err(anyhow!("'{:?}' is not a valid function control.", v)),
         }
     }
 }
@@ -72,7 +72,8 @@ impl Parser {
 
         let version_hex = self.advance()?;
 
-        let version = (version_hex.to_le_bytes()[1], version_hex.to_le_bytes()[2]);
+        // 0x00MMmm00, kept as (major, minor) like `SpirVBuilder::set_version`.
+        let version = (version_hex.to_le_bytes()[2], version_hex.to_le_bytes()[1]);
 
         // TODO: Find a way to parse the tool that generated this.
         let generator_magic_number = self.advance()?;
@@ -150,7 +151,11 @@ impl Parser {
             55 => SpirVCapability::StorageImageReadWithoutFormat,
             56 => SpirVCapability::StorageImageWriteWithoutFormat,
             57 => SpirVCapability::MultiViewport,
-            58.. => todo!("Capabilities from 1.1 and above and Vendor Specific."),
+            58.. => {
+                return Err(anyhow!(
+                    "Capabilities from 1.1 and above and vendor specific ones aren't supported yet."
+                ));
+            }
             _ => return Err(anyhow!("Invalid Capability ID.")),
         })
     }
@@ -330,7 +335,7 @@ impl Parser {
         let op_code = u16::from_le_bytes([first_word[0], first_word[1]]);
         let word_count = u16::from_le_bytes([first_word[2], first_word[3]]);
 
-        Ok(match SpirVOpCode::from_u32(op_code as u32) {
+        Ok(match SpirVOpCode::from_u32(op_code as u32)? {
             SpirVOpCode::Source => {
                 let source_language = self.parse_source_language()?;
                 // word_count - source_language
@@ -338,7 +343,9 @@ impl Parser {
                 let version = self.advance()?;
 
                 if words_left != 0 {
-                    todo!("Handle optional <id> File and <Literal> Source.");
+                    return Err(anyhow!(
+                        "OpSource with a file or source isn't supported yet."
+                    ));
                 }
 
                 SpirVOp::Source(SpirVSource {
@@ -347,6 +354,16 @@ impl Parser {
                 })
             }
             SpirVOpCode::SourceExtension => SpirVOp::SourceExtension(self.parse_literal()?.1),
+            SpirVOpCode::String => {
+                let result_id = SpirVVariableId(self.advance()?);
+                SpirVOp::String(result_id, self.parse_literal()?.1)
+            }
+            SpirVOpCode::Line => SpirVOp::Line(SpirVLine {
+                file: SpirVVariableId(self.advance()?),
+                line: self.advance()?,
+                column: self.advance()?,
+            }),
+            SpirVOpCode::NoLine => SpirVOp::NoLine,
             SpirVOpCode::Name => {
                 let id = SpirVVariableId(self.advance()?);
                 let name = self.parse_literal()?.1;
@@ -424,6 +441,12 @@ impl Parser {
 
                 SpirVOp::Type(target_id, SpirVType::Void)
             }
+            SpirVOpCode::TypeBool => {
+                let target_id = SpirVVariableId(self.advance()?);
+                self.module.type_table.insert(target_id, SpirVType::Bool);
+
+                SpirVOp::Type(target_id, SpirVType::Bool)
+            }
             SpirVOpCode::TypeInt => {
                 let target_id = SpirVVariableId(self.advance()?);
                 let width = self.advance()?;
@@ -440,7 +463,7 @@ impl Parser {
                 let width = self.advance()?;
 
                 if words_left != 0 {
-                    todo!("Parsing the floating point encoding.");
+                    return Err(anyhow!("OpTypeFloat with an encoding isn't supported yet."));
                 }
 
                 self.module
@@ -459,6 +482,17 @@ impl Parser {
 
                 SpirVOp::Type(target_id, SpirVType::Vector(vector_type, size))
             }
+            SpirVOpCode::TypeMatrix => {
+                let target_id = SpirVVariableId(self.advance()?);
+                let column_type = SpirVVariableId(self.advance()?);
+                let column_count = self.advance()?;
+
+                self.module
+                    .type_table
+                    .insert(target_id, SpirVType::Matrix(column_type, column_count));
+
+                SpirVOp::Type(target_id, SpirVType::Matrix(column_type, column_count))
+            }
             SpirVOpCode::TypeArray => {
                 let target_id = SpirVVariableId(self.advance()?);
                 let array_type = SpirVVariableId(self.advance()?);
@@ -470,6 +504,46 @@ impl Parser {
 
                 SpirVOp::Type(target_id, SpirVType::Array(array_type, length))
             }
+            SpirVOpCode::TypeRuntimeArray => {
+                let target_id = SpirVVariableId(self.advance()?);
+                let array_type = SpirVVariableId(self.advance()?);
+
+                self.module
+                    .type_table
+                    .insert(target_id, SpirVType::RuntimeArray(array_type));
+
+                SpirVOp::Type(target_id, SpirVType::RuntimeArray(array_type))
+            }
+            SpirVOpCode::TypeImage => {
+                let target_id = SpirVVariableId(self.advance()?);
+                let image = SpirVImageType {
+                    sampled_type: SpirVVariableId(self.advance()?),
+                    dim: SpirVDim::from_u32(self.advance()?)?,
+                    depth: self.advance()?,
+                    arrayed: self.advance()? != 0,
+                    multisampled: self.advance()? != 0,
+                    sampled: self.advance()?,
+                    format: self.advance()?,
+                };
+
+                // The optional access qualifier is only used by kernels.
+                for _i in 0..word_count - 9 {
+                    self.advance()?;
+                }
+
+                self.module
+                    .type_table
+                    .insert(target_id, SpirVType::Image(image.clone()));
+
+                SpirVOp::Type(target_id, SpirVType::Image(image))
+            }
+            SpirVOpCode::TypeSampler => {
+                let target_id = SpirVVariableId(self.advance()?);
+
+                self.module.type_table.insert(target_id, SpirVType::Sampler);
+
+                SpirVOp::Type(target_id, SpirVType::Sampler)
+            }
             SpirVOpCode::TypePointer => {
                 let target_id = SpirVVariableId(self.advance()?);
                 let storage_class = self.parse_storage_class()?;
@@ -619,6 +693,48 @@ impl Parser {
                     .insert(target_id, constant.clone());
                 SpirVOp::Constant(target_id, constant)
             }
+            SpirVOpCode::ConstantTrue
+            | SpirVOpCode::ConstantFalse
+            | SpirVOpCode::ConstantNull
+            | SpirVOpCode::Undef => {
+                let type_id = SpirVVariableId(self.advance()?);
+                let target_id = SpirVVariableId(self.advance()?);
+
+                let constant = SpirVConstant {
+                    type_id,
+                    value: match SpirVOpCode::from_u32(op_code as u32)? {
+                        SpirVOpCode::ConstantTrue => SpirVConstantValue::Bool(true),
+                        SpirVOpCode::ConstantFalse => SpirVConstantValue::Bool(false),
+                        SpirVOpCode::ConstantNull => SpirVConstantValue::Null,
+                        _ => SpirVConstantValue::Undefined,
+                    },
+                };
+
+                self.module
+                    .constants_table
+                    .insert(target_id, constant.clone());
+                SpirVOp::Constant(target_id, constant)
+            }
+            SpirVOpCode::ExecutionMode => {
+                let entry_point = SpirVVariableId(self.advance()?);
+                let mode = self.advance()?;
+
+                let mut literals = vec![];
+                for _i in 0..word_count - 3 {
+                    literals.push(self.advance()?);
+                }
+
+                SpirVOp::ExecutionMode(
+                    entry_point,
+                    match mode {
+                        7 => SpirVExecutionMode::OriginUpperLeft,
+                        8 => SpirVExecutionMode::OriginLowerLeft,
+                        12 => SpirVExecutionMode::DepthReplacing,
+                        17 => SpirVExecutionMode::LocalSize(literals[0], literals[1], literals[2]),
+                        _ => SpirVExecutionMode::Other(mode, literals),
+                    },
+                )
+            }
             SpirVOpCode::ConstantComposite => {
                 let type_id = SpirVVariableId(self.advance()?);
                 let target_id = SpirVVariableId(self.advance()?);
@@ -679,11 +795,12 @@ impl Parser {
                 let struct_id = SpirVVariableId(self.advance()?);
                 let member_id = self.advance()? as usize;
                 let member_decorate = self.parse_decorate_type()?;
+                // Structs like matrix wrappers only have member decorations.
                 let member_decorates_vec = &mut self
                     .module
                     .decorate_table
-                    .get_mut(&struct_id)
-                    .unwrap()
+                    .entry(struct_id)
+                    .or_default()
                     .member_decorates;
 
                 if member_decorates_vec.len() <= member_id {
@@ -715,7 +832,7 @@ impl Parser {
             SpirVOpCode::Function => {
                 let return_type_id = SpirVVariableId(self.advance()?);
                 let result_id = SpirVVariableId(self.advance()?);
-                let function_control = FunctionControl::from_u32(self.advance()?);
+                let function_control = FunctionControl::from_u32(self.advance()?)?;
                 let function_type_id = SpirVVariableId(self.advance()?);
 
                 let mut instructions: Vec<SpirVOp> = vec![];
@@ -747,7 +864,16 @@ impl Parser {
                 let mut instructions: Vec<SpirVOp> = vec![];
                 let mut op = self.parse_op()?;
 
-                while !matches!(op, SpirVOp::Return) {
+                while !matches!(
+                    op,
+                    SpirVOp::Return
+                        | SpirVOp::ReturnValue(..)
+                        | SpirVOp::Branch(..)
+                        | SpirVOp::BranchConditional(..)
+                        | SpirVOp::Switch(..)
+                        | SpirVOp::Kill
+                        | SpirVOp::Unreachable
+                ) {
                     instructions.push(op.clone());
                     op = self.parse_op()?;
                 }
@@ -762,7 +888,7 @@ impl Parser {
                 let pointer_id = SpirVVariableId(self.advance()?);
                 let object_id = SpirVVariableId(self.advance()?);
                 let memory_operands = match word_count - 3 == 0 {
-                    false => SpirVMemoryOperands::from_u32(self.advance()?),
+                    false => SpirVMemoryOperands::from_u32(self.advance()?)?,
                     true => SpirVMemoryOperands::None,
                 };
 
@@ -777,7 +903,7 @@ impl Parser {
                 let result_id = SpirVVariableId(self.advance()?);
                 let pointer_id = SpirVVariableId(self.advance()?);
                 let memory_operands = match word_count - 4 == 0 {
-                    false => SpirVMemoryOperands::from_u32(self.advance()?),
+                    false => SpirVMemoryOperands::from_u32(self.advance()?)?,
                     true => SpirVMemoryOperands::None,
                 };
 
@@ -809,6 +935,311 @@ impl Parser {
                     },
                 )
             }
+            SpirVOpCode::SNegate
+            | SpirVOpCode::FNegate
+            | SpirVOpCode::Not
+            | SpirVOpCode::LogicalNot
+            | SpirVOpCode::CopyObject
+            | SpirVOpCode::ConvertFToU
+            | SpirVOpCode::ConvertFToS
+            | SpirVOpCode::ConvertSToF
+            | SpirVOpCode::ConvertUToF
+            | SpirVOpCode::UConvert
+            | SpirVOpCode::SConvert
+            | SpirVOpCode::FConvert
+            | SpirVOpCode::Any
+            | SpirVOpCode::All
+            | SpirVOpCode::IsNan
+            | SpirVOpCode::IsInf => {
+                let type_id = SpirVVariableId(self.advance()?);
+                let result_id = SpirVVariableId(self.advance()?);
+                let operand = SpirVVariableId(self.advance()?);
+
+                SpirVOp::Unary(
+                    result_id,
+                    SpirVOpCode::from_u32(op_code as u32)?,
+                    SpirVUnaryOp { type_id, operand },
+                )
+            }
+            SpirVOpCode::IAdd
+            | SpirVOpCode::FAdd
+            | SpirVOpCode::ISub
+            | SpirVOpCode::FSub
+            | SpirVOpCode::IMul
+            | SpirVOpCode::FMul
+            | SpirVOpCode::UDiv
+            | SpirVOpCode::SDiv
+            | SpirVOpCode::FDiv
+            | SpirVOpCode::UMod
+            | SpirVOpCode::SRem
+            | SpirVOpCode::SMod
+            | SpirVOpCode::FRem
+            | SpirVOpCode::FMod
+            | SpirVOpCode::VectorTimesScalar
+            | SpirVOpCode::MatrixTimesScalar
+            | SpirVOpCode::VectorTimesMatrix
+            | SpirVOpCode::MatrixTimesVector
+            | SpirVOpCode::MatrixTimesMatrix
+            | SpirVOpCode::Dot
+            | SpirVOpCode::VectorExtractDynamic
+            | SpirVOpCode::LogicalEqual
+            | SpirVOpCode::LogicalNotEqual
+            | SpirVOpCode::LogicalOr
+            | SpirVOpCode::LogicalAnd
+            | SpirVOpCode::IEqual
+            | SpirVOpCode::INotEqual
+            | SpirVOpCode::UGreaterThan
+            | SpirVOpCode::SGreaterThan
+            | SpirVOpCode::UGreaterThanEqual
+            | SpirVOpCode::SGreaterThanEqual
+            | SpirVOpCode::ULessThan
+            | SpirVOpCode::SLessThan
+            | SpirVOpCode::ULessThanEqual
+            | SpirVOpCode::SLessThanEqual
+            | SpirVOpCode::FOrdEqual
+            | SpirVOpCode::FUnordEqual
+            | SpirVOpCode::FOrdNotEqual
+            | SpirVOpCode::FUnordNotEqual
+            | SpirVOpCode::FOrdLessThan
+            | SpirVOpCode::FUnordLessThan
+            | SpirVOpCode::FOrdGreaterThan
+            | SpirVOpCode::FUnordGreaterThan
+            | SpirVOpCode::FOrdLessThanEqual
+            | SpirVOpCode::FUnordLessThanEqual
+            | SpirVOpCode::FOrdGreaterThanEqual
+            | SpirVOpCode::FUnordGreaterThanEqual
+            | SpirVOpCode::ShiftRightLogical
+            | SpirVOpCode::ShiftRightArithmetic
+            | SpirVOpCode::ShiftLeftLogical
+            | SpirVOpCode::BitwiseOr
+            | SpirVOpCode::BitwiseXor
+            | SpirVOpCode::BitwiseAnd => {
+                let type_id = SpirVVariableId(self.advance()?);
+                let result_id = SpirVVariableId(self.advance()?);
+                let operation = SpirVBinaryOp {
+                    type_id,
+                    operand1: SpirVVariableId(self.advance()?),
+                    operand2: SpirVVariableId(self.advance()?),
+                };
+
+                SpirVOp::Binary(result_id, SpirVOpCode::from_u32(op_code as u32)?, operation)
+            }
+            SpirVOpCode::Select => {
+                let type_id = SpirVVariableId(self.advance()?);
+                let result_id = SpirVVariableId(self.advance()?);
+
+                SpirVOp::Select(
+                    result_id,
+                    SpirVSelect {
+                        type_id,
+                        condition: SpirVVariableId(self.advance()?),
+                        object1: SpirVVariableId(self.advance()?),
+                        object2: SpirVVariableId(self.advance()?),
+                    },
+                )
+            }
+            SpirVOpCode::BitCast => {
+                let to_type = SpirVVariableId(self.advance()?);
+                let result_id = SpirVVariableId(self.advance()?);
+                let variable = SpirVVariableId(self.advance()?);
+
+                SpirVOp::BitCast(result_id, SpirVBitCast { variable, to_type })
+            }
+            SpirVOpCode::VectorShuffle => {
+                let vec_type = SpirVVariableId(self.advance()?);
+                let result_id = SpirVVariableId(self.advance()?);
+                let vec1 = SpirVVariableId(self.advance()?);
+                let vec2 = SpirVVariableId(self.advance()?);
+
+                let mut mask = vec![];
+                for _i in 0..word_count - 5 {
+                    mask.push(self.advance()?);
+                }
+
+                SpirVOp::VectorShuffle(
+                    result_id,
+                    SpirVVectorShuffle {
+                        vec_type,
+                        vec1,
+                        vec2,
+                        mask,
+                    },
+                )
+            }
+            SpirVOpCode::VectorInsertDynamic => {
+                let type_id = SpirVVariableId(self.advance()?);
+                let result_id = SpirVVariableId(self.advance()?);
+
+                SpirVOp::VectorInsertDynamic(
+                    result_id,
+                    SpirVVectorInsertDynamic {
+                        type_id,
+                        vector: SpirVVariableId(self.advance()?),
+                        component: SpirVVariableId(self.advance()?),
+                        index: SpirVVariableId(self.advance()?),
+                    },
+                )
+            }
+            SpirVOpCode::CompositeInsert => {
+                let type_id = SpirVVariableId(self.advance()?);
+                let result_id = SpirVVariableId(self.advance()?);
+                let object_id = SpirVVariableId(self.advance()?);
+                let composite_id = SpirVVariableId(self.advance()?);
+                let mut indices = vec![];
+                for _i in 0..word_count - 5 {
+                    indices.push(self.advance()?);
+                }
+
+                SpirVOp::CompositeInsert(
+                    result_id,
+                    SpirVCompositeInsert {
+                        type_id,
+                        object_id,
+                        composite_id,
+                        indices,
+                    },
+                )
+            }
+            SpirVOpCode::ExtInst => {
+                let type_id = SpirVVariableId(self.advance()?);
+                let result_id = SpirVVariableId(self.advance()?);
+                let set = SpirVVariableId(self.advance()?);
+                let instruction = self.advance()?;
+
+                let mut operands = vec![];
+                for _i in 0..word_count - 5 {
+                    operands.push(SpirVVariableId(self.advance()?));
+                }
+
+                SpirVOp::ExtendedInstruction(
+                    result_id,
+                    SpirVExtendedInstruction {
+                        type_id,
+                        set,
+                        instruction,
+                        operands,
+                    },
+                )
+            }
+            SpirVOpCode::ArrayLength => {
+                let type_id = SpirVVariableId(self.advance()?);
+                let result_id = SpirVVariableId(self.advance()?);
+
+                SpirVOp::ArrayLength(
+                    result_id,
+                    SpirVArrayLength {
+                        type_id,
+                        structure: SpirVVariableId(self.advance()?),
+                        member: self.advance()?,
+                    },
+                )
+            }
+            SpirVOpCode::FunctionParameter => {
+                let type_id = SpirVVariableId(self.advance()?);
+                let result_id = SpirVVariableId(self.advance()?);
+
+                SpirVOp::FunctionParameter(result_id, type_id)
+            }
+            SpirVOpCode::FunctionCall => {
+                let type_id = SpirVVariableId(self.advance()?);
+                let result_id = SpirVVariableId(self.advance()?);
+                let function = SpirVVariableId(self.advance()?);
+
+                let mut arguments = vec![];
+                for _i in 0..word_count - 4 {
+                    arguments.push(SpirVVariableId(self.advance()?));
+                }
+
+                SpirVOp::FunctionCall(
+                    result_id,
+                    SpirVFunctionCall {
+                        type_id,
+                        function,
+                        arguments,
+                    },
+                )
+            }
+            SpirVOpCode::Phi => {
+                let type_id = SpirVVariableId(self.advance()?);
+                let result_id = SpirVVariableId(self.advance()?);
+
+                let mut incoming = vec![];
+                for _i in 0..(word_count - 3) / 2 {
+                    let value = SpirVVariableId(self.advance()?);
+                    let parent = SpirVVariableId(self.advance()?);
+                    incoming.push((value, parent));
+                }
+
+                SpirVOp::Phi(result_id, SpirVPhi { type_id, incoming })
+            }
+            SpirVOpCode::SelectionMerge => {
+                let merge_block = SpirVVariableId(self.advance()?);
+                // Selection Control.
+                self.advance()?;
+
+                SpirVOp::SelectionMerge(merge_block)
+            }
+            SpirVOpCode::LoopMerge => {
+                let merge_block = SpirVVariableId(self.advance()?);
+                let continue_target = SpirVVariableId(self.advance()?);
+                // Loop Control and its parameters.
+                for _i in 0..word_count - 3 {
+                    self.advance()?;
+                }
+
+                SpirVOp::LoopMerge(merge_block, continue_target)
+            }
+            SpirVOpCode::Branch => SpirVOp::Branch(SpirVVariableId(self.advance()?)),
+            SpirVOpCode::BranchConditional => {
+                let branch = SpirVBranchConditional {
+                    condition: SpirVVariableId(self.advance()?),
+                    true_label: SpirVVariableId(self.advance()?),
+                    false_label: SpirVVariableId(self.advance()?),
+                };
+
+                // Branch weights.
+                for _i in 0..word_count - 4 {
+                    self.advance()?;
+                }
+
+                SpirVOp::BranchConditional(branch)
+            }
+            SpirVOpCode::Switch => {
+                let selector = SpirVVariableId(self.advance()?);
+                let default = SpirVVariableId(self.advance()?);
+
+                // Only 32-bit selectors, which take one word per literal.
+                let mut targets = vec![];
+                for _i in 0..(word_count - 3) / 2 {
+                    let literal = self.advance()? as u64;
+                    targets.push((literal, SpirVVariableId(self.advance()?)));
+                }
+
+                SpirVOp::Switch(SpirVSwitch {
+                    selector,
+                    default,
+                    targets,
+                })
+            }
+            SpirVOpCode::ControlBarrier => {
+                // Execution, Memory and Semantics.
+                for _i in 0..3 {
+                    self.advance()?;
+                }
+
+                SpirVOp::ControlBarrier
+            }
+            SpirVOpCode::MemoryBarrier => {
+                // Memory and Semantics.
+                for _i in 0..2 {
+                    self.advance()?;
+                }
+
+                SpirVOp::MemoryBarrier
+            }
+            SpirVOpCode::Kill => SpirVOp::Kill,
+            SpirVOpCode::Unreachable => SpirVOp::Unreachable,
+            SpirVOpCode::ReturnValue => SpirVOp::ReturnValue(SpirVVariableId(self.advance()?)),
             SpirVOpCode::CompositeExtract => {
                 let type_id = SpirVVariableId(self.advance()?);
                 let result_id = SpirVVariableId(self.advance()?);
@@ -842,7 +1273,7 @@ impl Parser {
             }
             SpirVOpCode::Return => SpirVOp::Return,
             SpirVOpCode::FunctionEnd => SpirVOp::FunctionEnd,
-            _ => todo!("{:?}", (op_code, word_count)),
+            code => return Err(anyhow!("Op{:?} isn't supported yet.", code)),
         })
     }
 
@@ -862,7 +1293,7 @@ impl Parser {
             10 => SpirVStorageClass::AtomicCounter,
             11 => SpirVStorageClass::Image,
             12 => SpirVStorageClass::StorageBuffer,
-            _ => todo!("{:?}", v),
+            _ => return Err(anyhow!("Storage class {} isn't supported yet.", v)),
         })
     }
 
@@ -895,7 +1326,24 @@ impl Parser {
                 Self::add_capability_to(SpirVCapability::Shader, &mut self.module.capabilities)?;
                 SpirVBuiltIn::VertexIndex
             }
-            _ => todo!(),
+            43 => {
+                Self::add_capability_to(SpirVCapability::Shader, &mut self.module.capabilities)?;
+                SpirVBuiltIn::InstanceIndex
+            }
+            5 => SpirVBuiltIn::VertexId,
+            6 => SpirVBuiltIn::InstanceId,
+            15 => SpirVBuiltIn::FragCoord,
+            16 => SpirVBuiltIn::PointCoord,
+            17 => SpirVBuiltIn::FrontFacing,
+            18 => SpirVBuiltIn::SampleId,
+            22 => SpirVBuiltIn::FragDepth,
+            24 => SpirVBuiltIn::NumWorkgroups,
+            25 => SpirVBuiltIn::WorkgroupSize,
+            26 => SpirVBuiltIn::WorkgroupId,
+            27 => SpirVBuiltIn::LocalInvocationId,
+            28 => SpirVBuiltIn::GlobalInvocationId,
+            29 => SpirVBuiltIn::LocalInvocationIndex,
+            _ => return Err(anyhow!("Built-in {} isn't supported yet.", v)),
         })
     }
 
@@ -907,9 +1355,20 @@ impl Parser {
                 Self::add_capability_to(SpirVCapability::Shader, &mut self.module.capabilities)?;
                 SpirVDecorateType::Block
             }
+            3 => SpirVDecorateType::BufferBlock,
+            4 => SpirVDecorateType::RowMajor,
+            5 => SpirVDecorateType::ColMajor,
+            6 => SpirVDecorateType::ArrayStride(self.advance()?),
+            7 => SpirVDecorateType::MatrixStride(self.advance()?),
             11 => SpirVDecorateType::BuiltIn(self.parse_built_in()?),
+            13 => SpirVDecorateType::NoPerspective,
+            14 => SpirVDecorateType::Flat,
+            24 => SpirVDecorateType::NonWritable,
             30 => SpirVDecorateType::Location(self.advance()?),
-            _ => todo!(),
+            33 => SpirVDecorateType::Binding(self.advance()?),
+            34 => SpirVDecorateType::DescriptorSet(self.advance()?),
+            35 => SpirVDecorateType::Offset(self.advance()?),
+            _ => return Err(anyhow!("Decoration {} isn't supported yet.", v)),
         })
     }
 
@@ -0,0 +1,31 @@
+use crate::spirv_parser::{SpirVBuiltIn, SpirVExecutionModel, SpirVStorageClass, SpirVVariableId};
+
+/// What a SPIR-V module exposes to the API side: its entry points with their
+/// interfaces, and every variable bound to a descriptor.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct SpirVReflection {
+    pub entry_points: Vec<ReflectedEntryPoint>,
+    /// Sorted by (set, binding).
+    pub resources: Vec<ReflectedVariable>,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ReflectedEntryPoint {
+    pub name: String,
+    pub execution_model: SpirVExecutionModel,
+    pub interface: Vec<ReflectedVariable>,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ReflectedVariable {
+    pub id: SpirVVariableId,
+    pub name: String,
+    pub storage_class: SpirVStorageClass,
+    /// Metal-style name of the pointee, `float4`, `texture2d<float>`, ...
+    pub type_name: String,
+    pub location: Option<u32>,
+    pub built_in: Option<SpirVBuiltIn>,
+    pub set: Option<u32>,
+    pub binding: Option<u32>,
+    pub read_only: bool,
+}
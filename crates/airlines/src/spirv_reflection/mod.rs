@@ -0,0 +1,163 @@
+use std::collections::HashMap;
+
+use crate::spirv_parser::{
+    SpirVAlloca, SpirVConstantValue, SpirVDecorateType, SpirVDim, SpirVModule, SpirVOp, SpirVType,
+    SpirVVariableId,
+};
+
+pub mod items;
+
+pub use items::*;
+
+impl SpirVReflection {
+    pub fn new(module: &SpirVModule) -> Self {
+        let mut names: HashMap<SpirVVariableId, &str> = HashMap::new();
+        let mut types: HashMap<SpirVVariableId, SpirVType> = HashMap::new();
+        let mut constants: HashMap<SpirVVariableId, &SpirVConstantValue> = HashMap::new();
+        let mut decorations: HashMap<SpirVVariableId, Vec<&SpirVDecorateType>> = HashMap::new();
+        let mut member_decorations: HashMap<SpirVVariableId, Vec<&SpirVDecorateType>> =
+            HashMap::new();
+        let mut variables: Vec<(SpirVVariableId, &SpirVAlloca)> = vec![];
+        let mut entry_points = vec![];
+
+        for op in &module.operands {
+            match op {
+                SpirVOp::Name(id, name) => {
+                    names.insert(*id, name);
+                }
+                SpirVOp::Type(id, ty) => {
+                    types.insert(*id, ty.clone());
+                }
+                // The builder emits structs as their own op.
+                SpirVOp::Struct(id, members) => {
+                    types.insert(*id, SpirVType::Struct(members.clone()));
+                }
+                SpirVOp::Constant(id, constant) => {
+                    constants.insert(*id, &constant.value);
+                }
+                SpirVOp::Decorate(id, decorate) => {
+                    decorations.entry(*id).or_default().push(decorate)
+                }
+                // Only the first member matters, that's where `Position` lives.
+                SpirVOp::MemberDecorate(id, 0, decorate) => {
+                    member_decorations.entry(*id).or_default().push(decorate)
+                }
+                SpirVOp::Alloca(id, alloca) => variables.push((*id, alloca)),
+                SpirVOp::EntryPoint(entry_point) => entry_points.push(entry_point),
+                _ => {}
+            }
+        }
+
+        let variable = |id: SpirVVariableId| -> Option<ReflectedVariable> {
+            let (_, alloca) = variables.iter().find(|(variable, _)| *variable == id)?;
+            let pointee = match types.get(&alloca.type_id) {
+                Some(SpirVType::Pointer(_, pointee)) => *pointee,
+                _ => alloca.type_id,
+            };
+
+            let mut result = ReflectedVariable {
+                id,
+                name: names.get(&id).unwrap_or(&"").to_string(),
+                storage_class: alloca.storage_class,
+                type_name: Self::type_name(pointee, &types, &names, &constants),
+                ..Default::default()
+            };
+
+            let decorations = decorations
+                .get(&id)
+                .into_iter()
+                .chain(member_decorations.get(&pointee))
+                .flatten();
+            for i in decorations {
+                match i {
+                    SpirVDecorateType::Location(location) => result.location = Some(*location),
+                    SpirVDecorateType::BuiltIn(built_in) => result.built_in = Some(*built_in),
+                    SpirVDecorateType::DescriptorSet(set) => result.set = Some(*set),
+                    SpirVDecorateType::Binding(binding) => result.binding = Some(*binding),
+                    SpirVDecorateType::NonWritable => result.read_only = true,
+                    _ => {}
+                }
+            }
+
+            Some(result)
+        };
+
+        let mut resources = variables
+            .iter()
+            .filter_map(|(id, _)| variable(*id))
+            .filter(|i| i.set.is_some() || i.binding.is_some())
+            .collect::<Vec<_>>();
+        resources.sort_by_key(|i| (i.set.unwrap_or(0), i.binding.unwrap_or(0)));
+
+        Self {
+            entry_points: entry_points
+                .into_iter()
+                .map(|i| ReflectedEntryPoint {
+                    name: i.name.clone(),
+                    execution_model: i.execution_model,
+                    interface: i.arguments.iter().filter_map(|id| variable(*id)).collect(),
+                })
+                .collect(),
+            resources,
+        }
+    }
+
+    fn type_name(
+        id: SpirVVariableId,
+        types: &HashMap<SpirVVariableId, SpirVType>,
+        names: &HashMap<SpirVVariableId, &str>,
+        constants: &HashMap<SpirVVariableId, &SpirVConstantValue>,
+    ) -> String {
+        let name = |id: &SpirVVariableId| Self::type_name(*id, types, names, constants);
+
+        match types.get(&id) {
+            Some(SpirVType::Void) => "void".to_string(),
+            Some(SpirVType::Bool) => "bool".to_string(),
+            Some(SpirVType::Float(32)) => "float".to_string(),
+            Some(SpirVType::Float(16)) => "half".to_string(),
+            Some(SpirVType::Float(width)) => format!("f{}", width),
+            Some(SpirVType::Int(32, true)) => "int".to_string(),
+            Some(SpirVType::Int(32, false)) => "uint".to_string(),
+            Some(SpirVType::Int(width, true)) => format!("i{}", width),
+            Some(SpirVType::Int(width, false)) => format!("u{}", width),
+            Some(SpirVType::Vector(element, size)) => format!("{}{}", name(element), size),
+            Some(SpirVType::Matrix(column, count)) => match types.get(column) {
+                Some(SpirVType::Vector(element, rows)) => {
+                    format!("{}{}x{}", name(element), count, rows)
+                }
+                _ => "matrix".to_string(),
+            },
+            Some(SpirVType::Array(element, length)) => {
+                let length = match constants.get(length) {
+                    Some(SpirVConstantValue::UnsignedInteger(length)) => length.to_string(),
+                    Some(SpirVConstantValue::SignedInteger(length)) => length.to_string(),
+                    _ => "?".to_string(),
+                };
+                format!("{}[{}]", name(element), length)
+            }
+            Some(SpirVType::RuntimeArray(element)) => format!("{}[]", name(element)),
+            Some(SpirVType::Pointer(_, pointee)) => format!("{}*", name(pointee)),
+            Some(SpirVType::Struct(_)) => match names.get(&id) {
+                Some(name) if !name.is_empty() => name.to_string(),
+                _ => "struct".to_string(),
+            },
+            Some(SpirVType::Function(..)) => "function".to_string(),
+            Some(SpirVType::Image(image)) => format!(
+                "{}{}{}{}<{}>",
+                if image.depth == 1 { "depth" } else { "texture" },
+                match image.dim {
+                    SpirVDim::Dim1D => "1d",
+                    SpirVDim::Dim3D => "3d",
+                    SpirVDim::Cube => "cube",
+                    SpirVDim::Buffer => "_buffer",
+                    _ => "2d",
+                },
+                if image.multisampled { "_ms" } else { "" },
+                if image.arrayed { "_array" } else { "" },
+                name(&image.sampled_type)
+            ),
+            Some(SpirVType::Sampler) => "sampler".to_string(),
+            None => "?".to_string(),
+        }
+    }
+}
@@ -0,0 +1,113 @@
+use crate::{
+    air_codegen::{AirToSpirVOptions, ArgumentBufferLayout},
+    metal_lib::{MTLLibraryFunction, MTLLibraryFunctionType, MTLLibraryParser},
+    spirv_parser::SpirVModule,
+    spirv_reflection::SpirVReflection,
+};
+use anyhow::Result;
+
+/// Part of every cache key, so translations cached by an older translator
+/// are never used.
+///
+/// Bump the `-N` suffix in the commit that changes the SPIR-V emitted for
+/// some input: new or reordered instructions, ids, names, decorations or
+/// layouts. Changes that leave every output word the same, like parser
+/// fixes or refactors, don't need one. The suffix goes back to 0 when the
+/// crate version is bumped.
+pub const TRANSLATOR_VERSION: &str = concat!(env!("CARGO_PKG_VERSION"), "-6");
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TranslationKey(pub u64);
+
+impl TranslationKey {
+    /// Uses the functions' `HASH` tags when every function has one, and the
+    /// bitcode itself otherwise.
+    pub fn new(library: &MTLLibraryParser, options: &AirToSpirVOptions) -> Result<Self> {
+        let mut hash = fnv1a(FNV_OFFSET_BASIS, TRANSLATOR_VERSION.as_bytes());
+        hash = fnv1a(hash, format!("{:?}", options).as_bytes());
+
+        let function_hashes = library
+            .functions
+            .iter()
+            .map(|function| function.hash)
+            .collect::<Option<Vec<_>>>();
+
+        match function_hashes {
+            Some(function_hashes) if !function_hashes.is_empty() => {
+                for function_hash in function_hashes {
+                    hash = fnv1a(hash, &function_hash);
+                }
+            }
+            _ => hash = fnv1a(hash, library.bitcode()?),
+        }
+
+        Ok(Self(hash))
+    }
+
+    pub fn file_name(&self) -> String {
+        format!("{:016x}.spvcache", self.0)
+    }
+}
+
+pub(crate) const FNV_OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+const FNV_PRIME: u64 = 0x100000001b3;
+
+/// FNV-1a, `std`'s hasher isn't guaranteed to be stable between releases.
+pub(crate) fn fnv1a(mut hash: u64, bytes: &[u8]) -> u64 {
+    for byte in bytes {
+        hash ^= *byte as u64;
+        hash = hash.wrapping_mul(FNV_PRIME);
+    }
+
+    hash
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct CachedTranslation {
+    pub words: Vec<u32>,
+    pub reflection: TranslationReflection,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct TranslationReflection {
+    pub functions: Vec<ReflectedFunction>,
+    pub resources: Vec<ReflectedResource>,
+    pub argument_buffers: Vec<ArgumentBufferLayout>,
+}
+
+impl TranslationReflection {
+    pub fn new(functions: &[MTLLibraryFunction], module: &SpirVModule) -> Self {
+        Self {
+            functions: functions
+                .iter()
+                .map(|function| ReflectedFunction {
+                    name: function.name.clone(),
+                    function_type: function.function_type,
+                })
+                .collect(),
+            resources: SpirVReflection::new(module)
+                .resources
+                .into_iter()
+                .map(|resource| ReflectedResource {
+                    name: resource.name,
+                    set: resource.set.unwrap_or(0),
+                    binding: resource.binding.unwrap_or(0),
+                })
+                .collect(),
+            argument_buffers: vec![],
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ReflectedFunction {
+    pub name: String,
+    pub function_type: MTLLibraryFunctionType,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ReflectedResource {
+    pub name: String,
+    pub set: u32,
+    pub binding: u32,
+}
@@ -0,0 +1,315 @@
+use std::{
+    fs::File,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use anyhow::{Result, anyhow};
+
+use crate::{
+    air_codegen::{ArgumentBufferArgument, ArgumentBufferLayout, ArgumentKind},
+    metal_lib::MTLLibraryFunctionType,
+};
+
+pub mod items;
+
+pub use items::*;
+
+const MAGIC: &[u8; 4] = b"ALSC";
+const FORMAT_VERSION: u32 = 2;
+
+/// Default limit for the whole cache directory.
+pub const DEFAULT_MAX_SIZE: u64 = 64 * 1024 * 1024;
+
+/// SPIR-V translations of Metal libraries kept on disk between launches,
+/// one file per `TranslationKey`. Once the directory grows past `max_size`
+/// the least recently used entries are removed.
+#[derive(Debug, Clone)]
+pub struct TranslationCache {
+    directory: PathBuf,
+    max_size: u64,
+}
+
+impl TranslationCache {
+    pub fn new(directory: impl Into<PathBuf>) -> Result<Self> {
+        let directory = directory.into();
+        std::fs::create_dir_all(&directory)?;
+
+        Ok(Self {
+            directory,
+            max_size: DEFAULT_MAX_SIZE,
+        })
+    }
+
+    pub fn with_max_size(mut self, max_size: u64) -> Self {
+        self.max_size = max_size;
+        self
+    }
+
+    pub fn directory(&self) -> &Path {
+        &self.directory
+    }
+
+    /// Entries written by another translator version, or that fail to
+    /// decode, are removed and count as a miss.
+    pub fn get(&self, key: &TranslationKey) -> Option<CachedTranslation> {
+        let path = self.directory.join(key.file_name());
+        let content = std::fs::read(&path).ok()?;
+
+        match Self::decode(key, &content) {
+            Ok(translation) => {
+                // Keeps recently used entries from being evicted first.
+                if let Ok(file) = File::options().append(true).open(&path) {
+                    let _ = file.set_modified(SystemTime::now());
+                }
+
+                Some(translation)
+            }
+            Err(_) => {
+                let _ = std::fs::remove_file(&path);
+                None
+            }
+        }
+    }
+
+    pub fn insert(&self, key: &TranslationKey, translation: &CachedTranslation) -> Result<()> {
+        let content = Self::encode(key, translation);
+        if content.len() as u64 > self.max_size {
+            return Ok(());
+        }
+
+        // Written next to the entry and renamed so readers never see half a file.
+        let path = self.directory.join(key.file_name());
+        let temporary = path.with_extension(format!("tmp{}", std::process::id()));
+        std::fs::write(&temporary, content)?;
+        std::fs::rename(&temporary, &path)?;
+
+        self.evict()
+    }
+
+    pub fn remove(&self, key: &TranslationKey) -> Result<()> {
+        match std::fs::remove_file(self.directory.join(key.file_name())) {
+            Err(error) if error.kind() != std::io::ErrorKind::NotFound => Err(error.into()),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn clear(&self) -> Result<()> {
+        for (path, _, _) in self.entries()? {
+            std::fs::remove_file(path)?;
+        }
+
+        Ok(())
+    }
+
+    /// Total size of the cached entries, in bytes.
+    pub fn size(&self) -> Result<u64> {
+        Ok(self.entries()?.iter().map(|(_, size, _)| size).sum())
+    }
+
+    fn evict(&self) -> Result<()> {
+        let mut entries = self.entries()?;
+        entries.sort_by_key(|(_, _, modified)| *modified);
+
+        let mut size: u64 = entries.iter().map(|(_, size, _)| size).sum();
+        for (path, entry_size, _) in entries {
+            if size <= self.max_size {
+                break;
+            }
+
+            std::fs::remove_file(path)?;
+            size -= entry_size;
+        }
+
+        Ok(())
+    }
+
+    fn entries(&self) -> Result<Vec<(PathBuf, u64, SystemTime)>> {
+        let mut result = vec![];
+        for entry in std::fs::read_dir(&self.directory)? {
+            let path = entry?.path();
+            if path.extension().is_none_or(|x| x != "spvcache") {
+                continue;
+            }
+
+            let metadata = std::fs::metadata(&path)?;
+            result.push((path, metadata.len(), metadata.modified()?));
+        }
+
+        Ok(result)
+    }
+
+    fn encode(key: &TranslationKey, translation: &CachedTranslation) -> Vec<u8> {
+        fn string(result: &mut Vec<u8>, value: &str) {
+            result.extend((value.len() as u32).to_le_bytes());
+            result.extend(value.as_bytes());
+        }
+
+        let mut result = MAGIC.to_vec();
+        result.extend(FORMAT_VERSION.to_le_bytes());
+        result.extend(key.0.to_le_bytes());
+        string(&mut result, TRANSLATOR_VERSION);
+
+        result.extend((translation.words.len() as u32).to_le_bytes());
+        for word in &translation.words {
+            result.extend(word.to_le_bytes());
+        }
+
+        let reflection = &translation.reflection;
+        result.extend((reflection.functions.len() as u32).to_le_bytes());
+        for function in &reflection.functions {
+            string(&mut result, &function.name);
+            result.push(function.function_type as u8);
+        }
+
+        result.extend((reflection.resources.len() as u32).to_le_bytes());
+        for resource in &reflection.resources {
+            string(&mut result, &resource.name);
+            result.extend(resource.set.to_le_bytes());
+            result.extend(resource.binding.to_le_bytes());
+        }
+
+        result.extend((reflection.argument_buffers.len() as u32).to_le_bytes());
+        for layout in &reflection.argument_buffers {
+            string(&mut result, &layout.function);
+            string(&mut result, &layout.name);
+            result.extend(layout.buffer_index.to_le_bytes());
+            result.extend(layout.set.to_le_bytes());
+            result.extend(layout.encoded_length.to_le_bytes());
+
+            result.extend((layout.arguments.len() as u32).to_le_bytes());
+            for argument in &layout.arguments {
+                string(&mut result, &argument.name);
+                string(&mut result, &argument.type_name);
+                result.push(argument.kind as u8);
+                result.push(argument.id.is_some() as u8);
+                result.extend(argument.id.unwrap_or(0).to_le_bytes());
+                result.extend(argument.offset.to_le_bytes());
+                result.extend(argument.size.to_le_bytes());
+                result.extend(argument.array_length.to_le_bytes());
+                result.push(argument.element_size.is_some() as u8);
+                result.extend(argument.element_size.unwrap_or(0).to_le_bytes());
+                result.push(argument.read_only as u8);
+            }
+        }
+
+        result
+    }
+
+    fn decode(key: &TranslationKey, content: &[u8]) -> Result<CachedTranslation> {
+        let mut reader = CacheReader {
+            content,
+            position: 0,
+        };
+
+        if reader.advance(4)? != MAGIC || reader.advance_u32()? != FORMAT_VERSION {
+            return Err(anyhow!("Not a translation cache entry."));
+        }
+
+        if reader.advance_u64()? != key.0 || reader.advance_string()? != TRANSLATOR_VERSION {
+            return Err(anyhow!("Stale translation cache entry."));
+        }
+
+        let mut result = CachedTranslation::default();
+
+        for _ in 0..reader.advance_u32()? {
+            result.words.push(reader.advance_u32()?);
+        }
+
+        for _ in 0..reader.advance_u32()? {
+            result.reflection.functions.push(ReflectedFunction {
+                name: reader.advance_string()?,
+                function_type: MTLLibraryFunctionType::from_u8(reader.advance_u8()?)?,
+            });
+        }
+
+        for _ in 0..reader.advance_u32()? {
+            result.reflection.resources.push(ReflectedResource {
+                name: reader.advance_string()?,
+                set: reader.advance_u32()?,
+                binding: reader.advance_u32()?,
+            });
+        }
+
+        for _ in 0..reader.advance_u32()? {
+            let mut layout = ArgumentBufferLayout {
+                function: reader.advance_string()?,
+                name: reader.advance_string()?,
+                buffer_index: reader.advance_u32()?,
+                set: reader.advance_u32()?,
+                encoded_length: reader.advance_u64()?,
+                arguments: vec![],
+            };
+
+            for _ in 0..reader.advance_u32()? {
+                layout.arguments.push(ArgumentBufferArgument {
+                    name: reader.advance_string()?,
+                    type_name: reader.advance_string()?,
+                    kind: ArgumentKind::from_u8(reader.advance_u8()?)?,
+                    id: reader.advance_option_u32()?,
+                    offset: reader.advance_u64()?,
+                    size: reader.advance_u64()?,
+                    array_length: reader.advance_u32()?,
+                    element_size: reader.advance_option_u64()?,
+                    read_only: reader.advance_u8()? != 0,
+                });
+            }
+
+            result.reflection.argument_buffers.push(layout);
+        }
+
+        if reader.position != content.len() {
+            return Err(anyhow!("Trailing data in translation cache entry."));
+        }
+
+        Ok(result)
+    }
+}
+
+struct CacheReader<'a> {
+    content: &'a [u8],
+    position: usize,
+}
+
+impl CacheReader<'_> {
+    fn advance(&mut self, count: usize) -> Result<&[u8]> {
+        let result = self
+            .content
+            .get(self.position..self.position + count)
+            .ok_or(anyhow!("Position out of bounds."))?;
+        self.position += count;
+
+        Ok(result)
+    }
+
+    fn advance_u8(&mut self) -> Result<u8> {
+        Ok(self.advance(1)?[0])
+    }
+
+    fn advance_u32(&mut self) -> Result<u32> {
+        Ok(u32::from_le_bytes(self.advance(4)?.try_into()?))
+    }
+
+    fn advance_u64(&mut self) -> Result<u64> {
+        Ok(u64::from_le_bytes(self.advance(8)?.try_into()?))
+    }
+
+    fn advance_option_u32(&mut self) -> Result<Option<u32>> {
+        let is_some = self.advance_u8()? != 0;
+        let value = self.advance_u32()?;
+
+        Ok(is_some.then_some(value))
+    }
+
+    fn advance_option_u64(&mut self) -> Result<Option<u64>> {
+        let is_some = self.advance_u8()? != 0;
+        let value = self.advance_u64()?;
+
+        Ok(is_some.then_some(value))
+    }
+
+    fn advance_string(&mut self) -> Result<String> {
+        let length = self.advance_u32()? as usize;
+        Ok(String::from_utf8(self.advance(length)?.to_vec())?)
+    }
+}
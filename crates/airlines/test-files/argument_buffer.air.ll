@@ -0,0 +1,57 @@
+; ModuleID = 'argument_buffer.air'
+source_filename = "argument_buffer.metal"
+target datalayout = "e-p:64:64:64-i1:8:8-i8:8:8-i16:16:16-i32:32:32-i64:64:64-f32:32:32-f64:64:64-v16:16:16-v24:32:32-v32:32:32-v48:64:64-v64:64:64-v96:128:128-v128:128:128-v192:256:256-v256:256:256-v512:512:512-v1024:1024:1024-n8:16:32"
+target triple = "air64-apple-macosx15.0.0"
+
+%struct.Material = type { ptr addrspace(1), ptr addrspace(2), [2 x ptr addrspace(1)], float }
+
+; Function Attrs: mustprogress nofree norecurse nosync nounwind willreturn memory(none)
+define <{ <4 x float> }> @vertexMain(i32 noundef %0, ptr addrspace(2) noundef "air-buffer-no-alias" %1) local_unnamed_addr #0 {
+  %3 = zext i32 %0 to i64
+  %4 = getelementptr inbounds %struct.Material, ptr addrspace(2) %1, i64 0, i32 2, i64 1
+  %5 = load ptr addrspace(1), ptr addrspace(2) %4, align 8
+  %6 = getelementptr inbounds <4 x float>, ptr addrspace(1) %5, i64 %3
+  %7 = load <4 x float>, ptr addrspace(1) %6, align 16
+  %8 = getelementptr inbounds %struct.Material, ptr addrspace(2) %1, i64 0, i32 3
+  %9 = load float, ptr addrspace(2) %8, align 4
+  %10 = insertelement <4 x float> %7, float %9, i64 3
+  %11 = insertvalue <{ <4 x float> }> undef, <4 x float> %10, 0
+  ret <{ <4 x float> }> %11
+}
+
+attributes #0 = { mustprogress nofree norecurse nosync nounwind willreturn memory(none) "approx-func-fp-math"="true" "frame-pointer"="all" "min-legal-vector-width"="0" "no-builtins" "no-infs-fp-math"="true" "no-nans-fp-math"="true" "no-signed-zeros-fp-math"="true" "no-trapping-math"="true" "stack-protector-buffer-size"="8" "unsafe-fp-math"="true" }
+
+!llvm.module.flags = !{!0, !1, !2, !3, !4, !5, !6, !7, !8}
+!air.vertex = !{!9}
+!air.compile_options = !{!21, !22, !23}
+!llvm.ident = !{!24}
+!air.version = !{!25}
+!air.language_version = !{!26}
+!air.source_file_name = !{!27}
+
+!0 = !{i32 2, !"SDK Version", [2 x i32] [i32 15, i32 5]}
+!1 = !{i32 1, !"wchar_size", i32 4}
+!2 = !{i32 7, !"frame-pointer", i32 2}
+!3 = !{i32 7, !"air.max_device_buffers", i32 31}
+!4 = !{i32 7, !"air.max_constant_buffers", i32 31}
+!5 = !{i32 7, !"air.max_threadgroup_buffers", i32 31}
+!6 = !{i32 7, !"air.max_textures", i32 128}
+!7 = !{i32 7, !"air.max_read_write_textures", i32 8}
+!8 = !{i32 7, !"air.max_samplers", i32 16}
+!9 = !{ptr @vertexMain, !10, !12}
+!10 = !{!11}
+!11 = !{!"air.position", !"air.arg_type_name", !"float4", !"air.arg_name", !"position"}
+!12 = !{!13, !14}
+!13 = !{i32 0, !"air.vertex_id", !"air.arg_type_name", !"uint", !"air.arg_name", !"vertexID"}
+!14 = !{i32 1, !"air.indirect_buffer", !"air.buffer_size", i32 40, !"air.location_index", i32 0, i32 1, !"air.read", !"air.address_space", i32 2, !"air.struct_type_info", !15, !"air.arg_type_size", i32 40, !"air.arg_type_align_size", i32 8, !"air.arg_type_name", !"Material", !"air.arg_name", !"material"}
+!15 = !{i32 0, i32 8, i32 0, !"texture2d<float, sample>", !"albedo", !"air.indirect_argument", !16, i32 8, i32 8, i32 0, !"sampler", !"albedoSampler", !"air.indirect_argument", !17, i32 16, i32 16, i32 2, !"const device float4*", !"colors", !"air.indirect_argument", !18, i32 32, i32 4, i32 0, !"float", !"scale"}
+!16 = !{i32 0, !"air.texture", !"air.location_index", i32 0, i32 1, !"air.sample", !"air.arg_type_name", !"texture2d<float, sample>", !"air.arg_name", !"albedo"}
+!17 = !{i32 1, !"air.sampler", !"air.location_index", i32 1, i32 1, !"air.arg_type_name", !"sampler", !"air.arg_name", !"albedoSampler"}
+!18 = !{i32 2, !"air.buffer", !"air.location_index", i32 2, i32 2, !"air.read", !"air.address_space", i32 1, !"air.arg_type_size", i32 16, !"air.arg_type_align_size", i32 16, !"air.arg_type_name", !"float4", !"air.arg_name", !"colors"}
+!21 = !{!"air.compile.denorms_disable"}
+!22 = !{!"air.compile.fast_math_enable"}
+!23 = !{!"air.compile.framebuffer_fetch_enable"}
+!24 = !{!"Apple metal version 32023.620 (metalfe-32023.620)"}
+!25 = !{i32 2, i32 7, i32 0}
+!26 = !{!"Metal", i32 3, i32 2, i32 0}
+!27 = !{!"argument_buffer.metal"}
@@ -0,0 +1,29 @@
+; ModuleID = 'intrinsics.air'
+source_filename = "intrinsics.metal"
+target datalayout = "e-p:64:64:64-i1:8:8-i8:8:8-i16:16:16-i32:32:32-i64:64:64-f32:32:32-f64:64:64-v16:16:16-v24:32:32-v32:32:32-v48:64:64-v64:64:64-v96:128:128-v128:128:128-v192:256:256-v256:256:256-v512:512:512-v1024:1024:1024-n8:16:32"
+target triple = "air64-apple-macosx15.0.0"
+
+; Function Attrs: mustprogress nofree nosync nounwind willreturn memory(none)
+define float @lighting(<3 x float> noundef %0, <3 x float> noundef %1) local_unnamed_addr #0 {
+  %3 = tail call fast <3 x float> @air.fast_normalize.v3f32(<3 x float> %0)
+  %4 = tail call fast float @air.dot.v3f32(<3 x float> %3, <3 x float> %1)
+  %5 = tail call fast float @air.fast_saturate.f32(float %4)
+  %6 = tail call fast float @air.fast_sqrt.f32(float %5)
+  %7 = call float @square(float %6)
+  ret float %7
+}
+
+; Function Attrs: mustprogress nofree nosync nounwind willreturn memory(none)
+define internal float @square(float noundef %0) unnamed_addr #0 {
+  %2 = tail call fast float @llvm.fma.f32(float %0, float %0, float 0.000000e+00)
+  ret float %2
+}
+
+declare <3 x float> @air.fast_normalize.v3f32(<3 x float>) local_unnamed_addr #1
+declare float @air.dot.v3f32(<3 x float>, <3 x float>) local_unnamed_addr #1
+declare float @air.fast_saturate.f32(float) local_unnamed_addr #1
+declare float @air.fast_sqrt.f32(float) local_unnamed_addr #1
+declare float @llvm.fma.f32(float, float, float) #1
+
+attributes #0 = { mustprogress nofree nosync nounwind willreturn memory(none) "approx-func-fp-math"="true" "frame-pointer"="all" "no-infs-fp-math"="true" "no-nans-fp-math"="true" "no-signed-zeros-fp-math"="true" "unsafe-fp-math"="true" }
+attributes #1 = { mustprogress nocallback nofree nosync nounwind willreturn memory(none) }
@@ -0,0 +1,68 @@
+; ModuleID = 'layout.air'
+source_filename = "layout.metal"
+target datalayout = "e-p:64:64:64-i1:8:8-i8:8:8-i16:16:16-i32:32:32-i64:64:64-f32:32:32-f64:64:64-v16:16:16-v24:32:32-v32:32:32-v48:64:64-v64:64:64-v96:128:128-v128:128:128-v192:256:256-v256:256:256-v512:512:512-v1024:1024:1024-n8:16:32"
+target triple = "air64-apple-macosx15.0.0"
+
+%struct.Vertex = type { [3 x float], float, <3 x float>, <2 x float> }
+
+; Function Attrs: mustprogress nofree norecurse nosync nounwind willreturn memory(argmem: read)
+define <{ <4 x float> }> @vertexMain(i32 noundef %0, %struct.Vertex addrspace(1)* noundef "air-buffer-no-alias" %1, [3 x float] addrspace(2)* noundef "air-buffer-no-alias" %2) local_unnamed_addr #0 {
+  %4 = zext i32 %0 to i64
+  %5 = getelementptr inbounds %struct.Vertex, %struct.Vertex addrspace(1)* %1, i64 %4, i32 0
+  %6 = load [3 x float], [3 x float] addrspace(1)* %5, align 4
+  %7 = extractvalue [3 x float] %6, 0
+  %8 = getelementptr inbounds %struct.Vertex, %struct.Vertex addrspace(1)* %1, i64 %4, i32 1
+  %9 = load float, float addrspace(1)* %8, align 4
+  %10 = getelementptr inbounds %struct.Vertex, %struct.Vertex addrspace(1)* %1, i64 %4, i32 2
+  %11 = load <3 x float>, <3 x float> addrspace(1)* %10, align 16
+  %12 = getelementptr inbounds %struct.Vertex, %struct.Vertex addrspace(1)* %1, i64 %4, i32 3
+  %13 = load <2 x float>, <2 x float> addrspace(1)* %12, align 8
+  %14 = getelementptr inbounds [3 x float], [3 x float] addrspace(2)* %2, i64 %4
+  %15 = load [3 x float], [3 x float] addrspace(2)* %14, align 4
+  %16 = extractvalue [3 x float] %15, 2
+  %17 = fmul fast float %7, %9
+  %18 = fadd fast float %17, %16
+  %19 = extractelement <3 x float> %11, i64 1
+  %20 = extractelement <2 x float> %13, i64 1
+  %21 = insertelement <4 x float> undef, float %18, i64 0
+  %22 = insertelement <4 x float> %21, float %19, i64 1
+  %23 = insertelement <4 x float> %22, float %20, i64 2
+  %24 = insertelement <4 x float> %23, float 1.000000e+00, i64 3
+  %25 = insertvalue <{ <4 x float> }> undef, <4 x float> %24, 0
+  ret <{ <4 x float> }> %25
+}
+
+attributes #0 = { mustprogress nofree norecurse nosync nounwind willreturn memory(argmem: read) "approx-func-fp-math"="true" "frame-pointer"="all" "min-legal-vector-width"="0" "no-builtins" "no-infs-fp-math"="true" "no-nans-fp-math"="true" "no-signed-zeros-fp-math"="true" "no-trapping-math"="true" "stack-protector-buffer-size"="8" "unsafe-fp-math"="true" }
+
+!llvm.module.flags = !{!0, !1, !2, !3, !4, !5, !6, !7, !8}
+!air.vertex = !{!9}
+!air.compile_options = !{!17, !18, !19}
+!llvm.ident = !{!20}
+!air.version = !{!21}
+!air.language_version = !{!22}
+!air.source_file_name = !{!23}
+
+!0 = !{i32 2, !"SDK Version", [2 x i32] [i32 15, i32 5]}
+!1 = !{i32 1, !"wchar_size", i32 4}
+!2 = !{i32 7, !"frame-pointer", i32 2}
+!3 = !{i32 7, !"air.max_device_buffers", i32 31}
+!4 = !{i32 7, !"air.max_constant_buffers", i32 31}
+!5 = !{i32 7, !"air.max_threadgroup_buffers", i32 31}
+!6 = !{i32 7, !"air.max_textures", i32 128}
+!7 = !{i32 7, !"air.max_read_write_textures", i32 8}
+!8 = !{i32 7, !"air.max_samplers", i32 16}
+!9 = !{ptr @vertexMain, !10, !12}
+!10 = !{!11}
+!11 = !{!"air.position", !"air.arg_type_name", !"float4", !"air.arg_name", !"position"}
+!12 = !{!13, !14, !16}
+!13 = !{i32 0, !"air.vertex_id", !"air.arg_type_name", !"uint", !"air.arg_name", !"vertexID"}
+!14 = !{i32 1, !"air.buffer", !"air.location_index", i32 0, i32 1, !"air.read", !"air.address_space", i32 1, !"air.struct_type_info", !15, !"air.arg_type_size", i32 48, !"air.arg_type_align_size", i32 16, !"air.arg_type_name", !"Vertex", !"air.arg_name", !"vertices"}
+!15 = !{i32 0, i32 12, i32 0, !"packed_float3", !"position", i32 12, i32 4, i32 0, !"float", !"scale", i32 16, i32 16, i32 0, !"float3", !"color", i32 32, i32 8, i32 0, !"float2", !"uv"}
+!16 = !{i32 2, !"air.buffer", !"air.location_index", i32 1, i32 1, !"air.read", !"air.address_space", i32 2, !"air.arg_type_size", i32 12, !"air.arg_type_align_size", i32 4, !"air.arg_type_name", !"packed_float3", !"air.arg_name", !"offsets"}
+!17 = !{!"air.compile.denorms_disable"}
+!18 = !{!"air.compile.fast_math_enable"}
+!19 = !{!"air.compile.framebuffer_fetch_enable"}
+!20 = !{!"Apple metal version 32023.620 (metalfe-32023.620)"}
+!21 = !{i32 2, i32 7, i32 0}
+!22 = !{!"Metal", i32 3, i32 2, i32 0}
+!23 = !{!"layout.metal"}
@@ -0,0 +1,25 @@
+; ModuleID = 'lazy.air'
+source_filename = "lazy.metal"
+target datalayout = "e-p:64:64:64-i1:8:8-i8:8:8-i16:16:16-i32:32:32-i64:64:64-f32:32:32-f64:64:64-v16:16:16-v24:32:32-v32:32:32-v48:64:64-v64:64:64-v96:128:128-v128:128:128-v192:256:256-v256:256:256-v512:512:512-v1024:1024:1024-n8:16:32"
+target triple = "air64-apple-macosx15.0.0"
+
+declare float @air.fast_sqrt.f32(float) local_unnamed_addr #1
+
+; Function Attrs: nounwind readnone
+define float @length(<2 x float> noundef %0) local_unnamed_addr #0 {
+  %2 = tail call fast float @air.dot.v2f32(<2 x float> %0, <2 x float> %0)
+  %3 = tail call fast float @air.fast_sqrt.f32(float %2)
+  ret float %3
+}
+
+; Function Attrs: nounwind readnone
+define <2 x float> @splat(float noundef %0) local_unnamed_addr #0 {
+  %2 = insertelement <2 x float> undef, float %0, i64 0
+  %3 = insertelement <2 x float> %2, float %0, i64 1
+  ret <2 x float> %3
+}
+
+declare float @air.dot.v2f32(<2 x float>, <2 x float>) local_unnamed_addr #1
+
+attributes #0 = { nounwind readnone "frame-pointer"="all" }
+attributes #1 = { nounwind readnone }
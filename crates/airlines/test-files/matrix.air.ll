@@ -0,0 +1,133 @@
+; ModuleID = 'matrix.air'
+source_filename = "matrix.metal"
+target datalayout = "e-p:64:64:64-i1:8:8-i8:8:8-i16:16:16-i32:32:32-i64:64:64-f32:32:32-f64:64:64-v16:16:16-v24:32:32-v32:32:32-v48:64:64-v64:64:64-v96:128:128-v128:128:128-v192:256:256-v256:256:256-v512:512:512-v1024:1024:1024-n8:16:32"
+target triple = "air64-apple-macosx15.0.0"
+
+%"struct.metal::matrix<float, 4, 4, void>" = type { [4 x <4 x float>] }
+%struct.Uniforms = type { %"struct.metal::matrix<float, 4, 4, void>", %"struct.metal::matrix<float, 4, 4, void>" }
+
+; Function Attrs: mustprogress nofree norecurse nosync nounwind willreturn memory(argmem: read)
+define <{ <4 x float> }> @vertexMain(i32 noundef %0, <4 x float> addrspace(1)* noundef "air-buffer-no-alias" %1, %struct.Uniforms addrspace(2)* noundef "air-buffer-no-alias" %2) local_unnamed_addr #0 {
+  %4 = zext i32 %0 to i64
+  %5 = getelementptr inbounds <4 x float>, <4 x float> addrspace(1)* %1, i64 %4
+  %6 = load <4 x float>, <4 x float> addrspace(1)* %5, align 16
+  %7 = getelementptr inbounds %struct.Uniforms, %struct.Uniforms addrspace(2)* %2, i64 0, i32 0, i32 0, i64 0
+  %8 = load <4 x float>, <4 x float> addrspace(2)* %7, align 16
+  %9 = getelementptr inbounds %struct.Uniforms, %struct.Uniforms addrspace(2)* %2, i64 0, i32 0, i32 0, i64 1
+  %10 = load <4 x float>, <4 x float> addrspace(2)* %9, align 16
+  %11 = getelementptr inbounds %struct.Uniforms, %struct.Uniforms addrspace(2)* %2, i64 0, i32 0, i32 0, i64 2
+  %12 = load <4 x float>, <4 x float> addrspace(2)* %11, align 16
+  %13 = getelementptr inbounds %struct.Uniforms, %struct.Uniforms addrspace(2)* %2, i64 0, i32 0, i32 0, i64 3
+  %14 = load <4 x float>, <4 x float> addrspace(2)* %13, align 16
+  %15 = getelementptr inbounds %struct.Uniforms, %struct.Uniforms addrspace(2)* %2, i64 0, i32 1
+  %16 = load %"struct.metal::matrix<float, 4, 4, void>", %"struct.metal::matrix<float, 4, 4, void>" addrspace(2)* %15, align 16
+  %17 = extractvalue %"struct.metal::matrix<float, 4, 4, void>" %16, 0, 0
+  %18 = shufflevector <4 x float> %17, <4 x float> poison, <4 x i32> zeroinitializer
+  %19 = fmul fast <4 x float> %8, %18
+  %20 = shufflevector <4 x float> %17, <4 x float> poison, <4 x i32> <i32 1, i32 1, i32 1, i32 1>
+  %21 = tail call fast <4 x float> @air.fma.v4f32(<4 x float> %10, <4 x float> %20, <4 x float> %19)
+  %22 = shufflevector <4 x float> %17, <4 x float> poison, <4 x i32> <i32 2, i32 2, i32 2, i32 2>
+  %23 = tail call fast <4 x float> @air.fma.v4f32(<4 x float> %12, <4 x float> %22, <4 x float> %21)
+  %24 = shufflevector <4 x float> %17, <4 x float> poison, <4 x i32> <i32 3, i32 3, i32 3, i32 3>
+  %25 = tail call fast <4 x float> @air.fma.v4f32(<4 x float> %14, <4 x float> %24, <4 x float> %23)
+  %26 = extractvalue %"struct.metal::matrix<float, 4, 4, void>" %16, 0, 1
+  %27 = shufflevector <4 x float> %26, <4 x float> poison, <4 x i32> zeroinitializer
+  %28 = fmul fast <4 x float> %8, %27
+  %29 = shufflevector <4 x float> %26, <4 x float> poison, <4 x i32> <i32 1, i32 1, i32 1, i32 1>
+  %30 = fmul fast <4 x float> %29, %10
+  %31 = shufflevector <4 x float> %26, <4 x float> poison, <4 x i32> <i32 2, i32 2, i32 2, i32 2>
+  %32 = fmul fast <4 x float> %12, %31
+  %33 = shufflevector <4 x float> %26, <4 x float> poison, <4 x i32> <i32 3, i32 3, i32 3, i32 3>
+  %34 = fmul fast <4 x float> %33, %14
+  %35 = fadd fast <4 x float> %28, %30
+  %36 = fadd fast <4 x float> %35, %32
+  %37 = fadd fast <4 x float> %36, %34
+  %38 = extractvalue %"struct.metal::matrix<float, 4, 4, void>" %16, 0, 2
+  %39 = shufflevector <4 x float> %38, <4 x float> poison, <4 x i32> zeroinitializer
+  %40 = fmul fast <4 x float> %8, %39
+  %41 = shufflevector <4 x float> %38, <4 x float> poison, <4 x i32> <i32 1, i32 1, i32 1, i32 1>
+  %42 = tail call fast <4 x float> @air.fma.v4f32(<4 x float> %10, <4 x float> %41, <4 x float> %40)
+  %43 = shufflevector <4 x float> %38, <4 x float> poison, <4 x i32> <i32 2, i32 2, i32 2, i32 2>
+  %44 = tail call fast <4 x float> @air.fma.v4f32(<4 x float> %12, <4 x float> %43, <4 x float> %42)
+  %45 = shufflevector <4 x float> %38, <4 x float> poison, <4 x i32> <i32 3, i32 3, i32 3, i32 3>
+  %46 = tail call fast <4 x float> @air.fma.v4f32(<4 x float> %14, <4 x float> %45, <4 x float> %44)
+  %47 = extractvalue %"struct.metal::matrix<float, 4, 4, void>" %16, 0, 3
+  %48 = shufflevector <4 x float> %47, <4 x float> poison, <4 x i32> zeroinitializer
+  %49 = fmul fast <4 x float> %8, %48
+  %50 = shufflevector <4 x float> %47, <4 x float> poison, <4 x i32> <i32 1, i32 1, i32 1, i32 1>
+  %51 = fmul fast <4 x float> %50, %10
+  %52 = shufflevector <4 x float> %47, <4 x float> poison, <4 x i32> <i32 2, i32 2, i32 2, i32 2>
+  %53 = fmul fast <4 x float> %12, %52
+  %54 = shufflevector <4 x float> %47, <4 x float> poison, <4 x i32> <i32 3, i32 3, i32 3, i32 3>
+  %55 = fmul fast <4 x float> %54, %14
+  %56 = fadd fast <4 x float> %49, %51
+  %57 = fadd fast <4 x float> %56, %53
+  %58 = fadd fast <4 x float> %57, %55
+  %59 = insertvalue [4 x <4 x float>] undef, <4 x float> %25, 0
+  %60 = insertvalue [4 x <4 x float>] %59, <4 x float> %37, 1
+  %61 = insertvalue [4 x <4 x float>] %60, <4 x float> %46, 2
+  %62 = insertvalue [4 x <4 x float>] %61, <4 x float> %58, 3
+  %63 = insertvalue %"struct.metal::matrix<float, 4, 4, void>" undef, [4 x <4 x float>] %62, 0
+  %64 = extractvalue %"struct.metal::matrix<float, 4, 4, void>" %63, 0, 0
+  %65 = extractvalue %"struct.metal::matrix<float, 4, 4, void>" %63, 0, 1
+  %66 = extractvalue %"struct.metal::matrix<float, 4, 4, void>" %63, 0, 2
+  %67 = extractvalue %"struct.metal::matrix<float, 4, 4, void>" %63, 0, 3
+  %68 = extractelement <4 x float> %6, i64 0
+  %69 = insertelement <4 x float> undef, float %68, i64 0
+  %70 = shufflevector <4 x float> %69, <4 x float> undef, <4 x i32> zeroinitializer
+  %71 = fmul fast <4 x float> %64, %70
+  %72 = extractelement <4 x float> %6, i64 1
+  %73 = insertelement <4 x float> undef, float %72, i64 0
+  %74 = shufflevector <4 x float> %73, <4 x float> undef, <4 x i32> zeroinitializer
+  %75 = fmul fast <4 x float> %74, %65
+  %76 = extractelement <4 x float> %6, i64 2
+  %77 = insertelement <4 x float> undef, float %76, i64 0
+  %78 = shufflevector <4 x float> %77, <4 x float> undef, <4 x i32> zeroinitializer
+  %79 = fmul fast <4 x float> %66, %78
+  %80 = extractelement <4 x float> %6, i64 3
+  %81 = insertelement <4 x float> undef, float %80, i64 0
+  %82 = shufflevector <4 x float> %81, <4 x float> undef, <4 x i32> zeroinitializer
+  %83 = fmul fast <4 x float> %82, %67
+  %84 = fadd fast <4 x float> %71, %75
+  %85 = fadd fast <4 x float> %84, %79
+  %86 = fadd fast <4 x float> %85, %83
+  %87 = insertvalue <{ <4 x float> }> undef, <4 x float> %86, 0
+  ret <{ <4 x float> }> %87
+}
+
+declare <4 x float> @air.fma.v4f32(<4 x float>, <4 x float>, <4 x float>) local_unnamed_addr #1
+
+attributes #0 = { mustprogress nofree norecurse nosync nounwind willreturn memory(argmem: read) "approx-func-fp-math"="true" "frame-pointer"="all" "min-legal-vector-width"="0" "no-builtins" "no-infs-fp-math"="true" "no-nans-fp-math"="true" "no-signed-zeros-fp-math"="true" "no-trapping-math"="true" "stack-protector-buffer-size"="8" "unsafe-fp-math"="true" }
+attributes #1 = { mustprogress nocallback nofree nosync nounwind willreturn memory(none) }
+
+!llvm.module.flags = !{!0, !1, !2, !3, !4, !5, !6, !7, !8}
+!air.vertex = !{!9}
+!air.compile_options = !{!16, !17, !18}
+!llvm.ident = !{!19}
+!air.version = !{!20}
+!air.language_version = !{!21}
+!air.source_file_name = !{!22}
+
+!0 = !{i32 2, !"SDK Version", [2 x i32] [i32 15, i32 5]}
+!1 = !{i32 1, !"wchar_size", i32 4}
+!2 = !{i32 7, !"frame-pointer", i32 2}
+!3 = !{i32 7, !"air.max_device_buffers", i32 31}
+!4 = !{i32 7, !"air.max_constant_buffers", i32 31}
+!5 = !{i32 7, !"air.max_threadgroup_buffers", i32 31}
+!6 = !{i32 7, !"air.max_textures", i32 128}
+!7 = !{i32 7, !"air.max_read_write_textures", i32 8}
+!8 = !{i32 7, !"air.max_samplers", i32 16}
+!9 = !{ptr @vertexMain, !10, !12}
+!10 = !{!11}
+!11 = !{!"air.position", !"air.arg_type_name", !"float4", !"air.arg_name", !"position"}
+!12 = !{!13, !14, !15}
+!13 = !{i32 0, !"air.vertex_id", !"air.arg_type_name", !"uint", !"air.arg_name", !"vertexID"}
+!14 = !{i32 1, !"air.buffer", !"air.location_index", i32 0, i32 1, !"air.read", !"air.address_space", i32 1, !"air.arg_type_size", i32 16, !"air.arg_type_align_size", i32 16, !"air.arg_type_name", !"float4", !"air.arg_name", !"positions"}
+!15 = !{i32 2, !"air.buffer", !"air.buffer_size", i32 128, !"air.location_index", i32 1, i32 1, !"air.read", !"air.address_space", i32 2, !"air.arg_type_size", i32 128, !"air.arg_type_align_size", i32 16, !"air.arg_type_name", !"Uniforms", !"air.arg_name", !"uniforms"}
+!16 = !{!"air.compile.denorms_disable"}
+!17 = !{!"air.compile.fast_math_enable"}
+!18 = !{!"air.compile.framebuffer_fetch_enable"}
+!19 = !{!"Apple metal version 32023.620 (metalfe-32023.620)"}
+!20 = !{i32 2, i32 7, i32 0}
+!21 = !{!"Metal", i32 3, i32 2, i32 0}
+!22 = !{!"matrix.metal"}
@@ -0,0 +1,25 @@
+; ModuleID = 'names.air'
+source_filename = "names.metal"
+target datalayout = "e-p:64:64:64-i1:8:8-i8:8:8-i16:16:16-i32:32:32-i64:64:64-f32:32:32-f64:64:64-v16:16:16-v24:32:32-v32:32:32-v48:64:64-v64:64:64-v96:128:128-v128:128:128-v192:256:256-v256:256:256-v512:512:512-v1024:1024:1024-n8:16:32"
+target triple = "air64-apple-macosx15.0.0"
+
+; Function Attrs: nounwind readnone
+define float @scale(float noundef %value, i1 noundef zeroext %flip) local_unnamed_addr #0 {
+entry:
+  br label %body
+
+exit:
+  %picked = phi float [ %doubled, %body ], [ %negated, %negate ]
+  %sum = fadd fast float %picked, %doubled
+  ret float %sum
+
+body:
+  %doubled = fmul fast float %value, 2.000000e+00
+  br i1 %flip, label %negate, label %exit
+
+negate:
+  %negated = fsub fast float 0.000000e+00, %doubled
+  br label %exit
+}
+
+attributes #0 = { nounwind readnone "frame-pointer"="all" }
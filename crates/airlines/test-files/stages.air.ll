@@ -0,0 +1,54 @@
+; ModuleID = 'stages.air'
+source_filename = "stages.metal"
+target datalayout = "e-p:64:64:64-i1:8:8-i8:8:8-i16:16:16-i32:32:32-i64:64:64-f32:32:32-f64:64:64-v16:16:16-v24:32:32-v32:32:32-v48:64:64-v64:64:64-v96:128:128-v128:128:128-v192:256:256-v256:256:256-v512:512:512-v1024:1024:1024-n8:16:32"
+target triple = "air64-apple-macosx15.0.0"
+
+; Function Attrs: mustprogress nofree norecurse nosync nounwind willreturn memory(none)
+define <4 x float> @fragmentMain(<4 x float> noundef %0, <3 x float> noundef %1) local_unnamed_addr #0 {
+  %3 = shufflevector <3 x float> %1, <3 x float> poison, <4 x i32> <i32 0, i32 1, i32 2, i32 poison>
+  %4 = shufflevector <4 x float> %3, <4 x float> %0, <4 x i32> <i32 0, i32 1, i32 2, i32 6>
+  ret <4 x float> %4
+}
+
+; Function Attrs: mustprogress nofree norecurse nosync nounwind willreturn memory(none)
+define void @kernelMain(<2 x i32> noundef %0, i32 noundef %1) local_unnamed_addr #0 {
+  ret void
+}
+attributes #0 = { mustprogress nofree norecurse nosync nounwind willreturn memory(none) "approx-func-fp-math"="true" "frame-pointer"="all" "min-legal-vector-width"="0" "no-builtins" "no-infs-fp-math"="true" "no-nans-fp-math"="true" "no-signed-zeros-fp-math"="true" "no-trapping-math"="true" "stack-protector-buffer-size"="8" "unsafe-fp-math"="true" }
+
+!llvm.module.flags = !{!0, !1, !2, !3, !4, !5, !6, !7, !8}
+!air.fragment = !{!9}
+!air.kernel = !{!15}
+!air.compile_options = !{!20, !21, !22}
+!llvm.ident = !{!23}
+!air.version = !{!24}
+!air.language_version = !{!25}
+!air.source_file_name = !{!26}
+
+!0 = !{i32 2, !"SDK Version", [2 x i32] [i32 15, i32 5]}
+!1 = !{i32 1, !"wchar_size", i32 4}
+!2 = !{i32 7, !"frame-pointer", i32 2}
+!3 = !{i32 7, !"air.max_device_buffers", i32 31}
+!4 = !{i32 7, !"air.max_constant_buffers", i32 31}
+!5 = !{i32 7, !"air.max_threadgroup_buffers", i32 31}
+!6 = !{i32 7, !"air.max_textures", i32 128}
+!7 = !{i32 7, !"air.max_read_write_textures", i32 8}
+!8 = !{i32 7, !"air.max_samplers", i32 16}
+!9 = !{ptr @fragmentMain, !10, !12}
+!10 = !{!11}
+!11 = !{!"air.render_target", i32 0, i32 0, !"air.arg_type_name", !"float4"}
+!12 = !{!13, !14}
+!13 = !{i32 0, !"air.position", !"air.center", !"air.no_perspective", !"air.arg_type_name", !"float4", !"air.arg_name", !"position"}
+!14 = !{i32 1, !"air.fragment_input", !"user(locn1)", !"air.center", !"air.flat", !"air.arg_type_name", !"float3", !"air.arg_name", !"color"}
+!15 = !{ptr @kernelMain, !16, !17}
+!16 = !{}
+!17 = !{!18, !19}
+!18 = !{i32 0, !"air.thread_position_in_grid", !"air.arg_type_name", !"uint2", !"air.arg_name", !"position"}
+!19 = !{i32 1, !"air.thread_index_in_threadgroup", !"air.arg_type_name", !"uint", !"air.arg_name", !"index"}
+!20 = !{!"air.compile.denorms_disable"}
+!21 = !{!"air.compile.fast_math_enable"}
+!22 = !{!"air.compile.framebuffer_fetch_enable"}
+!23 = !{!"Apple metal version 32023.620 (metalfe-32023.620)"}
+!24 = !{i32 2, i32 7, i32 0}
+!25 = !{!"Metal", i32 3, i32 2, i32 0}
+!26 = !{!"stages.metal"}
@@ -0,0 +1,46 @@
+; ModuleID = 'vertex_input.air'
+source_filename = "vertex_input.metal"
+target datalayout = "e-p:64:64:64-i1:8:8-i8:8:8-i16:16:16-i32:32:32-i64:64:64-f32:32:32-f64:64:64-v16:16:16-v24:32:32-v32:32:32-v48:64:64-v64:64:64-v96:128:128-v128:128:128-v192:256:256-v256:256:256-v512:512:512-v1024:1024:1024-n8:16:32"
+target triple = "air64-apple-macosx15.0.0"
+
+; Function Attrs: mustprogress nofree norecurse nosync nounwind willreturn memory(none)
+define <{ <3 x float>, <4 x float> }> @vertexMain(<3 x float> noundef %0, <2 x float> noundef %1) local_unnamed_addr #0 {
+  %3 = shufflevector <2 x float> %1, <2 x float> poison, <4 x i32> <i32 0, i32 1, i32 poison, i32 poison>
+  %4 = shufflevector <4 x float> %3, <4 x float> <float poison, float poison, float 0.000000e+00, float 1.000000e+00>, <4 x i32> <i32 0, i32 1, i32 6, i32 7>
+  %5 = insertvalue <{ <3 x float>, <4 x float> }> undef, <3 x float> %0, 0
+  %6 = insertvalue <{ <3 x float>, <4 x float> }> %5, <4 x float> %4, 1
+  ret <{ <3 x float>, <4 x float> }> %6
+}
+attributes #0 = { mustprogress nofree norecurse nosync nounwind willreturn memory(none) "approx-func-fp-math"="true" "frame-pointer"="all" "min-legal-vector-width"="0" "no-builtins" "no-infs-fp-math"="true" "no-nans-fp-math"="true" "no-signed-zeros-fp-math"="true" "no-trapping-math"="true" "stack-protector-buffer-size"="8" "unsafe-fp-math"="true" }
+
+!llvm.module.flags = !{!0, !1, !2, !3, !4, !5, !6, !7, !8}
+!air.vertex = !{!9}
+!air.compile_options = !{!16, !17, !18}
+!llvm.ident = !{!19}
+!air.version = !{!20}
+!air.language_version = !{!21}
+!air.source_file_name = !{!22}
+
+!0 = !{i32 2, !"SDK Version", [2 x i32] [i32 15, i32 5]}
+!1 = !{i32 1, !"wchar_size", i32 4}
+!2 = !{i32 7, !"frame-pointer", i32 2}
+!3 = !{i32 7, !"air.max_device_buffers", i32 31}
+!4 = !{i32 7, !"air.max_constant_buffers", i32 31}
+!5 = !{i32 7, !"air.max_threadgroup_buffers", i32 31}
+!6 = !{i32 7, !"air.max_textures", i32 128}
+!7 = !{i32 7, !"air.max_read_write_textures", i32 8}
+!8 = !{i32 7, !"air.max_samplers", i32 16}
+!9 = !{ptr @vertexMain, !10, !13}
+!10 = !{!11, !12}
+!11 = !{!"air.vertex_output", !"user(locn0)", !"air.arg_type_name", !"float3", !"air.arg_name", !"fragColor"}
+!12 = !{!"air.position", !"air.arg_type_name", !"float4", !"air.arg_name", !"mtlPosition"}
+!13 = !{!14, !15}
+!14 = !{i32 0, !"air.vertex_input", !"air.location_index", i32 2, i32 1, !"air.arg_type_name", !"float3", !"air.arg_name", !"color"}
+!15 = !{i32 1, !"air.vertex_input", !"air.location_index", i32 0, i32 1, !"air.arg_type_name", !"float2", !"air.arg_name", !"position"}
+!16 = !{!"air.compile.denorms_disable"}
+!17 = !{!"air.compile.fast_math_enable"}
+!18 = !{!"air.compile.framebuffer_fetch_enable"}
+!19 = !{!"Apple metal version 32023.620 (metalfe-32023.620)"}
+!20 = !{i32 2, i32 7, i32 0}
+!21 = !{!"Metal", i32 3, i32 2, i32 0}
+!22 = !{!"vertex_input.metal"}
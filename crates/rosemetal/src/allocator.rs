@@ -0,0 +1,433 @@
+use anyhow::{Result, anyhow};
+use ash::vk;
+use std::{ffi::c_void, sync::Mutex};
+
+/// Size of the blocks small resources are sub-allocated from.
+const BLOCK_SIZE: vk::DeviceSize = 64 * 1024 * 1024;
+
+/// Resources of at least this fraction of a block get their own
+/// `vkDeviceMemory`.
+const DEDICATED_DIVISOR: vk::DeviceSize = 2;
+
+/// Hands out `vkDeviceMemory` ranges from a few large blocks per memory type,
+/// instead of doing one `vkAllocateMemory` per resource.
+///
+/// Buffers and images live in separate blocks, so `bufferImageGranularity`
+/// never has to be considered.
+pub struct VulkanAllocator {
+    memory_properties: vk::PhysicalDeviceMemoryProperties,
+    pools: Mutex<Vec<VulkanMemoryPool>>,
+}
+
+/// Where a resource's memory lives. Give it back with
+/// [`VulkanAllocator::free`].
+#[derive(Debug)]
+pub struct VulkanAllocation {
+    memory: vk::DeviceMemory,
+    offset: vk::DeviceSize,
+    size: vk::DeviceSize,
+    memory_size: vk::DeviceSize,
+    memory_type: u32,
+    mapped: *mut c_void,
+    kind: VulkanAllocationKind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum VulkanAllocationKind {
+    Dedicated,
+    Block { linear: bool },
+}
+
+// The mapping belongs to the block or dedicated memory the allocation
+// points into, it's only freed through the allocator.
+unsafe impl Send for VulkanAllocation {}
+unsafe impl Sync for VulkanAllocation {}
+
+impl VulkanAllocation {
+    pub fn memory(&self) -> vk::DeviceMemory {
+        self.memory
+    }
+
+    pub fn offset(&self) -> vk::DeviceSize {
+        self.offset
+    }
+
+    pub fn size(&self) -> vk::DeviceSize {
+        self.size
+    }
+
+    /// Size of the `vkDeviceMemory` the allocation is part of.
+    pub fn memory_size(&self) -> vk::DeviceSize {
+        self.memory_size
+    }
+
+    pub fn memory_type(&self) -> u32 {
+        self.memory_type
+    }
+
+    /// CPU address of the allocation, null if its memory isn't host visible.
+    pub fn mapped(&self) -> *mut c_void {
+        self.mapped
+    }
+
+    pub fn is_dedicated(&self) -> bool {
+        self.kind == VulkanAllocationKind::Dedicated
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct VulkanAllocatorStatistics {
+    /// Blocks shared by sub-allocations.
+    pub block_count: usize,
+    /// Resources with a `vkDeviceMemory` of their own.
+    pub dedicated_allocation_count: usize,
+    /// Live sub-allocations.
+    pub allocation_count: usize,
+    /// Bytes allocated from the driver, blocks and dedicated memory.
+    pub allocated_bytes: vk::DeviceSize,
+    /// Bytes resources are actually using.
+    pub used_bytes: vk::DeviceSize,
+}
+
+struct VulkanMemoryPool {
+    memory_type: u32,
+    linear: bool,
+    blocks: Vec<VulkanMemoryBlock>,
+    dedicated: Vec<(vk::DeviceMemory, vk::DeviceSize)>,
+}
+
+pub(crate) struct VulkanMemoryBlock {
+    memory: vk::DeviceMemory,
+    size: vk::DeviceSize,
+    mapped: *mut c_void,
+    /// Free ranges as `(offset, size)`, sorted by offset and never adjacent,
+    /// neighbours are merged on free.
+    free: Vec<(vk::DeviceSize, vk::DeviceSize)>,
+    allocation_count: usize,
+}
+
+unsafe impl Send for VulkanMemoryBlock {}
+
+impl VulkanMemoryBlock {
+    pub(crate) fn new(memory: vk::DeviceMemory, size: vk::DeviceSize, mapped: *mut c_void) -> Self {
+        Self {
+            memory,
+            size,
+            mapped,
+            free: vec![(0, size)],
+            allocation_count: 0,
+        }
+    }
+
+    /// Best fit: the smallest free range that holds `size` at `alignment`.
+    pub(crate) fn allocate(
+        &mut self,
+        size: vk::DeviceSize,
+        alignment: vk::DeviceSize,
+    ) -> Option<vk::DeviceSize> {
+        let (index, offset) = self
+            .free
+            .iter()
+            .enumerate()
+            .filter_map(|(index, (start, length))| {
+                let offset = start.next_multiple_of(alignment);
+                (offset + size <= start + length).then_some((index, offset, *length))
+            })
+            .min_by_key(|(_, _, length)| *length)
+            .map(|(index, offset, _)| (index, offset))?;
+
+        let (start, length) = self.free.remove(index);
+        let end = start + length;
+
+        // Keep what's left on either side of the allocation.
+        if offset + size < end {
+            self.free
+                .insert(index, (offset + size, end - offset - size));
+        }
+        if start < offset {
+            self.free.insert(index, (start, offset - start));
+        }
+
+        self.allocation_count += 1;
+        Some(offset)
+    }
+
+    pub(crate) fn free(&mut self, offset: vk::DeviceSize, size: vk::DeviceSize) {
+        let index = self.free.partition_point(|(start, _)| *start < offset);
+        self.free.insert(index, (offset, size));
+
+        if index + 1 < self.free.len() && offset + size == self.free[index + 1].0 {
+            self.free[index].1 += self.free.remove(index + 1).1;
+        }
+        if index > 0 && self.free[index - 1].0 + self.free[index - 1].1 == offset {
+            self.free[index - 1].1 += self.free.remove(index).1;
+        }
+
+        self.allocation_count -= 1;
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.allocation_count == 0
+    }
+}
+
+impl VulkanAllocator {
+    pub fn new(memory_properties: vk::PhysicalDeviceMemoryProperties) -> Self {
+        Self {
+            memory_properties,
+            pools: Mutex::new(vec![]),
+        }
+    }
+
+    pub fn memory_properties(&self) -> &vk::PhysicalDeviceMemoryProperties {
+        &self.memory_properties
+    }
+
+    /// First memory type allowed by `type_bits` that has all of `properties`.
+    pub fn find_memory_type(
+        &self,
+        type_bits: u32,
+        properties: vk::MemoryPropertyFlags,
+    ) -> Option<u32> {
+        self.memory_properties
+            .memory_types_as_slice()
+            .iter()
+            .enumerate()
+            .find(|(index, memory_type)| {
+                type_bits & (1 << index) != 0 && memory_type.property_flags.contains(properties)
+            })
+            .map(|(index, _)| index as u32)
+    }
+
+    pub fn memory_type_flags(&self, memory_type: u32) -> vk::MemoryPropertyFlags {
+        self.memory_properties.memory_types[memory_type as usize].property_flags
+    }
+
+    /// Allocates memory for a resource with `requirements`, from a memory
+    /// type with all of `required` and, if there's one, all of `preferred`.
+    /// `linear` is true for buffers and linear images.
+    pub fn allocate(
+        &self,
+        device: &ash::Device,
+        requirements: vk::MemoryRequirements,
+        required: vk::MemoryPropertyFlags,
+        preferred: vk::MemoryPropertyFlags,
+        linear: bool,
+    ) -> Result<VulkanAllocation> {
+        let memory_type = self
+            .find_memory_type(requirements.memory_type_bits, required | preferred)
+            .or_else(|| self.find_memory_type(requirements.memory_type_bits, required))
+            .ok_or_else(|| anyhow!("No memory type with {:?} found.", required))?;
+
+        let block_size = self.block_size(memory_type);
+        let host_visible = self
+            .memory_type_flags(memory_type)
+            .contains(vk::MemoryPropertyFlags::HOST_VISIBLE);
+
+        let mut pools = self.pools.lock().unwrap();
+        let pool = match pools
+            .iter()
+            .position(|x| x.memory_type == memory_type && x.linear == linear)
+        {
+            Some(index) => &mut pools[index],
+            None => {
+                pools.push(VulkanMemoryPool {
+                    memory_type,
+                    linear,
+                    blocks: vec![],
+                    dedicated: vec![],
+                });
+                pools.last_mut().unwrap()
+            }
+        };
+
+        if requirements.size >= block_size / DEDICATED_DIVISOR {
+            let (memory, mapped) =
+                Self::allocate_memory(device, requirements.size, memory_type, host_visible)?;
+            pool.dedicated.push((memory, requirements.size));
+
+            return Ok(VulkanAllocation {
+                memory,
+                offset: 0,
+                size: requirements.size,
+                memory_size: requirements.size,
+                memory_type,
+                mapped,
+                kind: VulkanAllocationKind::Dedicated,
+            });
+        }
+
+        let existing = pool.blocks.iter_mut().find_map(|block| {
+            block
+                .allocate(requirements.size, requirements.alignment)
+                .map(|offset| (block, offset))
+        });
+
+        let (block, offset) = match existing {
+            Some(x) => x,
+            None => {
+                let (memory, mapped) =
+                    Self::allocate_memory(device, block_size, memory_type, host_visible)?;
+                pool.blocks
+                    .push(VulkanMemoryBlock::new(memory, block_size, mapped));
+
+                let block = pool.blocks.last_mut().unwrap();
+                let offset = block
+                    .allocate(requirements.size, requirements.alignment)
+                    .unwrap();
+                (block, offset)
+            }
+        };
+
+        let mapped = match block.mapped.is_null() {
+            true => std::ptr::null_mut(),
+            false => unsafe { block.mapped.byte_add(offset as usize) },
+        };
+
+        Ok(VulkanAllocation {
+            memory: block.memory,
+            offset,
+            size: requirements.size,
+            memory_size: block.size,
+            memory_type,
+            mapped,
+            kind: VulkanAllocationKind::Block { linear },
+        })
+    }
+
+    /// Gives `allocation` back. Empty blocks are released, except for the
+    /// last one of a pool, which is kept around for the next resource.
+    pub fn free(&self, device: &ash::Device, allocation: &VulkanAllocation) {
+        let mut pools = self.pools.lock().unwrap();
+
+        let linear = match allocation.kind {
+            VulkanAllocationKind::Block { linear } => linear,
+            VulkanAllocationKind::Dedicated => {
+                for pool in pools.iter_mut() {
+                    pool.dedicated
+                        .retain(|(memory, _)| *memory != allocation.memory);
+                }
+
+                unsafe { device.free_memory(allocation.memory, None) };
+                return;
+            }
+        };
+
+        let Some(pool) = pools
+            .iter_mut()
+            .find(|x| x.memory_type == allocation.memory_type && x.linear == linear)
+        else {
+            return;
+        };
+
+        let Some(index) = pool
+            .blocks
+            .iter()
+            .position(|x| x.memory == allocation.memory)
+        else {
+            return;
+        };
+
+        pool.blocks[index].free(allocation.offset, allocation.size);
+
+        if pool.blocks[index].is_empty() && pool.blocks.iter().filter(|x| x.is_empty()).count() > 1
+        {
+            let block = pool.blocks.remove(index);
+            unsafe { device.free_memory(block.memory, None) };
+        }
+    }
+
+    pub fn statistics(&self) -> VulkanAllocatorStatistics {
+        let pools = self.pools.lock().unwrap();
+
+        let mut statistics = VulkanAllocatorStatistics::default();
+        for pool in pools.iter() {
+            statistics.block_count += pool.blocks.len();
+            statistics.dedicated_allocation_count += pool.dedicated.len();
+
+            for block in pool.blocks.iter() {
+                statistics.allocation_count += block.allocation_count;
+                statistics.allocated_bytes += block.size;
+                statistics.used_bytes += block.size
+                    - block
+                        .free
+                        .iter()
+                        .map(|(_, size)| size)
+                        .sum::<vk::DeviceSize>();
+            }
+
+            for (_, size) in pool.dedicated.iter() {
+                statistics.allocated_bytes += size;
+                statistics.used_bytes += size;
+            }
+        }
+
+        statistics
+    }
+
+    /// Total size of the device local heaps.
+    pub fn device_local_size(&self) -> vk::DeviceSize {
+        self.memory_properties
+            .memory_heaps_as_slice()
+            .iter()
+            .filter(|x| x.flags.contains(vk::MemoryHeapFlags::DEVICE_LOCAL))
+            .map(|x| x.size)
+            .sum()
+    }
+
+    /// Releases every block and dedicated allocation.
+    pub fn destroy(&self, device: &ash::Device) {
+        let mut pools = self.pools.lock().unwrap();
+
+        for pool in pools.drain(..) {
+            for block in pool.blocks {
+                unsafe { device.free_memory(block.memory, None) };
+            }
+            for (memory, _) in pool.dedicated {
+                unsafe { device.free_memory(memory, None) };
+            }
+        }
+    }
+
+    /// Blocks are an eighth of small heaps, like integrated GPUs' host
+    /// visible ones.
+    fn block_size(&self, memory_type: u32) -> vk::DeviceSize {
+        let heap = self.memory_properties.memory_types[memory_type as usize].heap_index;
+        let heap_size = self.memory_properties.memory_heaps[heap as usize].size;
+
+        match heap_size <= 1024 * 1024 * 1024 {
+            true => (heap_size / 8).min(BLOCK_SIZE),
+            false => BLOCK_SIZE,
+        }
+    }
+
+    fn allocate_memory(
+        device: &ash::Device,
+        size: vk::DeviceSize,
+        memory_type: u32,
+        host_visible: bool,
+    ) -> Result<(vk::DeviceMemory, *mut c_void)> {
+        let memory = unsafe {
+            device.allocate_memory(
+                &vk::MemoryAllocateInfo::default()
+                    .allocation_size(size)
+                    .memory_type_index(memory_type),
+                None,
+            )?
+        };
+
+        if !host_visible {
+            return Ok((memory, std::ptr::null_mut()));
+        }
+
+        // Host visible memory stays mapped, a `vkDeviceMemory` can only be
+        // mapped once and its allocations share it.
+        match unsafe { device.map_memory(memory, 0, vk::WHOLE_SIZE, vk::MemoryMapFlags::empty()) } {
+            Ok(mapped) => Ok((memory, mapped)),
+            Err(error) => {
+                unsafe { device.free_memory(memory, None) };
+                Err(error.into())
+            }
+        }
+    }
+}
@@ -0,0 +1,341 @@
+#[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+use airlines::air_codegen::ArgumentBufferArgument;
+use airlines::air_codegen::{ArgumentBufferLayout, ArgumentKind};
+use anyhow::{Result, anyhow};
+
+#[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+use ash::vk;
+
+#[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+use crate::{MTLBuffer, MTLDevice, MTLSamplerState, MTLTexture, VulkanMTLBuffer};
+#[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+use std::sync::Arc;
+
+/// Fills the descriptor set an argument buffer was translated to. Resources
+/// are set by their `[[id(n)]]`, array members take consecutive ids the same
+/// way they do in Metal. Plain data stays in the argument buffer, which is
+/// bound next to the resources, see `set_argument_buffer`.
+pub struct MTLArgumentEncoder {
+    layout: ArgumentBufferLayout,
+    #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+    vulkan_arguments: Vec<VulkanArgument>,
+    /// Where plain data members are written, set by `set_argument_buffer`.
+    #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+    vulkan_constant_data: Option<*mut u8>,
+}
+
+#[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+struct VulkanArgument {
+    binding: u32,
+    array_element: u32,
+    descriptor_type: vk::DescriptorType,
+    buffer_info: Option<vk::DescriptorBufferInfo>,
+    image_info: Option<vk::DescriptorImageInfo>,
+    /// Kept alive by command buffers the descriptor is bound in.
+    buffer: Option<Arc<VulkanMTLBuffer>>,
+}
+
+impl MTLArgumentEncoder {
+    pub fn new(layout: ArgumentBufferLayout) -> Self {
+        Self {
+            layout,
+
+            #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+            vulkan_arguments: vec![],
+            #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+            vulkan_constant_data: None,
+        }
+    }
+
+    pub fn layout(&self) -> &ArgumentBufferLayout {
+        &self.layout
+    }
+
+    /// Size of the argument buffer's struct in Metal.
+    pub fn encoded_length(&self) -> u64 {
+        self.layout.encoded_length
+    }
+
+    /// Sets the buffer the arguments are encoded into. It holds the plain
+    /// data members, at the offsets Metal gives them.
+    #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+    pub fn set_argument_buffer<T>(&mut self, buffer: &MTLBuffer<T>, offset: u64) -> Result<()> {
+        if offset + self.layout.encoded_length > buffer.length() as u64 {
+            return Err(anyhow!(
+                "`{}` needs {} bytes at offset {}, the buffer has {}.",
+                self.layout.name,
+                self.layout.encoded_length,
+                offset,
+                buffer.length()
+            ));
+        }
+
+        Self::vulkan_check_usage(buffer)?;
+
+        let contents = buffer.contents() as *mut u8;
+        self.vulkan_constant_data = match contents.is_null() {
+            true => None,
+            false => Some(unsafe { contents.add(offset as usize) }),
+        };
+
+        if !self.layout.has_data() {
+            return Ok(());
+        }
+
+        self.vulkan_set(VulkanArgument {
+            binding: self.layout.data_binding(),
+            array_element: 0,
+            descriptor_type: vk::DescriptorType::STORAGE_BUFFER,
+            buffer_info: Some(
+                vk::DescriptorBufferInfo::default()
+                    .buffer(*buffer.vulkan_buffer())
+                    .offset(offset)
+                    .range(self.layout.encoded_length),
+            ),
+            image_info: None,
+            buffer: Some(buffer.vulkan_resource().clone()),
+        });
+
+        Ok(())
+    }
+
+    /// CPU address of the plain data member `name` in the argument buffer,
+    /// which has to be host visible.
+    #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+    pub fn constant_data(&self, name: &str) -> Result<*mut u8> {
+        let argument = self
+            .layout
+            .arguments
+            .iter()
+            .find(|x| x.kind == ArgumentKind::Data && x.name == name)
+            .ok_or(anyhow!(
+                "`{}` has no plain data member `{}`.",
+                self.layout.name,
+                name
+            ))?;
+        let contents = self.vulkan_constant_data.ok_or(anyhow!(
+            "`{}` needs a host visible argument buffer first.",
+            self.layout.name
+        ))?;
+
+        Ok(unsafe { contents.add(argument.offset as usize) })
+    }
+
+    /// Binding and array element of the member at `[[id(index)]]`.
+    fn resolve(&self, index: u32, kind: ArgumentKind) -> Result<(u32, u32)> {
+        let argument = self
+            .layout
+            .arguments
+            .iter()
+            .find(|x| {
+                x.id.is_some_and(|id| (id..id + x.array_length).contains(&index))
+            })
+            .ok_or(anyhow!(
+                "`{}` has no argument at index {}.",
+                self.layout.name,
+                index
+            ))?;
+
+        if argument.kind != kind {
+            return Err(anyhow!(
+                "`{}.{}` is a {:?}, not a {:?}.",
+                self.layout.name,
+                argument.name,
+                argument.kind,
+                kind
+            ));
+        }
+
+        let id = argument.id.unwrap();
+        Ok((id, index - id))
+    }
+
+    /// Buffers are bound as storage buffers.
+    #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+    fn vulkan_check_usage<T>(buffer: &MTLBuffer<T>) -> Result<()> {
+        match buffer.vulkan_usage().is_argument() {
+            true => Ok(()),
+            false => Err(anyhow!(
+                "Buffers without `STORAGE` usage can't be bound as arguments."
+            )),
+        }
+    }
+
+    #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+    fn vulkan_set(&mut self, argument: VulkanArgument) {
+        self.vulkan_arguments
+            .retain(|x| (x.binding, x.array_element) != (argument.binding, argument.array_element));
+        self.vulkan_arguments.push(argument);
+    }
+
+    #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+    pub fn set_buffer<T>(&mut self, buffer: &MTLBuffer<T>, offset: u64, index: u32) -> Result<()> {
+        let (binding, array_element) = self.resolve(index, ArgumentKind::Buffer)?;
+        Self::vulkan_check_usage(buffer)?;
+
+        self.vulkan_set(VulkanArgument {
+            binding,
+            array_element,
+            descriptor_type: vk::DescriptorType::STORAGE_BUFFER,
+            buffer_info: Some(
+                vk::DescriptorBufferInfo::default()
+                    .buffer(*buffer.vulkan_buffer())
+                    .offset(offset)
+                    .range(vk::WHOLE_SIZE),
+            ),
+            image_info: None,
+            buffer: Some(buffer.vulkan_resource().clone()),
+        });
+
+        Ok(())
+    }
+
+    #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+    pub fn set_texture(&mut self, texture: &MTLTexture, index: u32) -> Result<()> {
+        let (binding, array_element) = self.resolve(index, ArgumentKind::Texture)?;
+        let descriptor_type = self
+            .layout
+            .argument(binding)
+            .map_or(vk::DescriptorType::SAMPLED_IMAGE, |x| {
+                Self::vulkan_descriptor_type(x)
+            });
+
+        self.vulkan_set(VulkanArgument {
+            binding,
+            array_element,
+            descriptor_type,
+            buffer_info: None,
+            image_info: Some(
+                vk::DescriptorImageInfo::default()
+                    .image_view(*texture.vulkan_sampled_image_view())
+                    .image_layout(match descriptor_type {
+                        vk::DescriptorType::STORAGE_IMAGE => vk::ImageLayout::GENERAL,
+                        _ => vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+                    }),
+            ),
+            buffer: None,
+        });
+
+        Ok(())
+    }
+
+    #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+    pub fn set_sampler_state(&mut self, sampler: &MTLSamplerState, index: u32) -> Result<()> {
+        let (binding, array_element) = self.resolve(index, ArgumentKind::Sampler)?;
+
+        if !sampler.descriptor().support_argument_buffers {
+            return Err(anyhow!(
+                "Samplers in argument buffers need `support_argument_buffers`."
+            ));
+        }
+
+        self.vulkan_set(VulkanArgument {
+            binding,
+            array_element,
+            descriptor_type: vk::DescriptorType::SAMPLER,
+            buffer_info: None,
+            image_info: Some(vk::DescriptorImageInfo::default().sampler(*sampler.vulkan_sampler())),
+            buffer: None,
+        });
+
+        Ok(())
+    }
+
+    /// Textures the shader writes to are storage images, the plain data
+    /// block is a storage buffer like every other buffer.
+    #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+    fn vulkan_descriptor_type(argument: &ArgumentBufferArgument) -> vk::DescriptorType {
+        match argument.kind {
+            ArgumentKind::Buffer | ArgumentKind::Data => vk::DescriptorType::STORAGE_BUFFER,
+            ArgumentKind::Texture => {
+                let writable = argument
+                    .type_name
+                    .trim_end_matches('>')
+                    .split(',')
+                    .skip(1)
+                    .any(|x| matches!(x.trim(), "write" | "read_write"));
+
+                match writable {
+                    true => vk::DescriptorType::STORAGE_IMAGE,
+                    false => vk::DescriptorType::SAMPLED_IMAGE,
+                }
+            }
+            ArgumentKind::Sampler => vk::DescriptorType::SAMPLER,
+        }
+    }
+
+    /// Bindings of an argument buffer's set, for its
+    /// `VkDescriptorSetLayout`.
+    #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+    pub fn vulkan_descriptor_set_layout_bindings(
+        layout: &ArgumentBufferLayout,
+        stage_flags: vk::ShaderStageFlags,
+    ) -> Vec<vk::DescriptorSetLayoutBinding<'static>> {
+        let mut result = layout
+            .arguments
+            .iter()
+            .filter_map(|argument| {
+                Some(
+                    vk::DescriptorSetLayoutBinding::default()
+                        .binding(argument.id?)
+                        .descriptor_type(Self::vulkan_descriptor_type(argument))
+                        .descriptor_count(argument.array_length.max(1))
+                        .stage_flags(stage_flags),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        if layout.has_data() {
+            result.push(
+                vk::DescriptorSetLayoutBinding::default()
+                    .binding(layout.data_binding())
+                    .descriptor_type(vk::DescriptorType::STORAGE_BUFFER)
+                    .descriptor_count(1)
+                    .stage_flags(stage_flags),
+            );
+        }
+
+        result
+    }
+
+    /// Buffers the descriptors set so far point to.
+    #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+    pub fn vulkan_buffers(&self) -> impl Iterator<Item = &Arc<VulkanMTLBuffer>> {
+        self.vulkan_arguments
+            .iter()
+            .filter_map(|x| x.buffer.as_ref())
+    }
+
+    /// Writes everything set so far into `descriptor_set`.
+    #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+    pub fn vulkan_update_descriptor_set(
+        &self,
+        device: &MTLDevice,
+        descriptor_set: vk::DescriptorSet,
+    ) {
+        let writes = self
+            .vulkan_arguments
+            .iter()
+            .map(|argument| {
+                let write = vk::WriteDescriptorSet::default()
+                    .dst_set(descriptor_set)
+                    .dst_binding(argument.binding)
+                    .dst_array_element(argument.array_element)
+                    .descriptor_type(argument.descriptor_type);
+
+                match (&argument.buffer_info, &argument.image_info) {
+                    (Some(buffer_info), _) => write.buffer_info(std::slice::from_ref(buffer_info)),
+                    (_, Some(image_info)) => write.image_info(std::slice::from_ref(image_info)),
+                    _ => write,
+                }
+            })
+            .collect::<Vec<_>>();
+
+        unsafe {
+            device
+                .vulkan_device()
+                .logical()
+                .update_descriptor_sets(&writes, &[]);
+        }
+    }
+}
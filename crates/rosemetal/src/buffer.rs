@@ -1,4 +1,4 @@
-use std::{marker::PhantomData, sync::Arc};
+use std::{marker::PhantomData, ops::Range, sync::Arc};
 
 #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
 use ash::vk;
@@ -6,49 +6,265 @@ use ash::vk;
 use objc2::{rc::Retained, runtime::ProtocolObject};
 pub use objc2_metal::MTLBuffer as MetalMTLBuffer;
 
-use crate::MTLDevice;
+#[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+use crate::VulkanAllocation;
+use crate::{
+    MTLCPUCacheMode, MTLDevice, MTLHazardTrackingMode, MTLResourceOptions, MTLStorageMode,
+};
+use anyhow::{Result, anyhow};
+use bitflags::bitflags;
 
+/// A buffer's Vulkan objects. Command buffers that use the buffer keep them
+/// alive, they're destroyed once the last one is done with them.
 #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
 pub struct VulkanMTLBuffer {
+    device: Arc<MTLDevice>,
     buffer: vk::Buffer,
-    device_memory: vk::DeviceMemory,
+    allocation: VulkanAllocation,
+    length: usize,
+    usage: MTLBufferUsage,
+}
+
+#[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+impl VulkanMTLBuffer {
+    pub fn new(
+        device: Arc<MTLDevice>,
+        buffer: vk::Buffer,
+        allocation: VulkanAllocation,
+        length: usize,
+        usage: MTLBufferUsage,
+    ) -> Self {
+        Self {
+            device,
+            buffer,
+            allocation,
+            length,
+            usage,
+        }
+    }
+}
+
+#[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+impl VulkanMTLBuffer {
+    pub fn vulkan_buffer(&self) -> vk::Buffer {
+        self.buffer
+    }
+
+    /// Makes GPU writes to the whole buffer visible to the CPU.
+    pub fn vulkan_invalidate(&self) -> Result<()> {
+        self.device
+            .vulkan_device()
+            .invalidate(&self.allocation, 0..self.length as u64)
+    }
+}
+
+#[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+impl Drop for VulkanMTLBuffer {
+    fn drop(&mut self) {
+        let vulkan_device = self.device.vulkan_device();
+
+        unsafe {
+            vulkan_device.logical().destroy_buffer(self.buffer, None);
+        }
+
+        vulkan_device
+            .allocator()
+            .free(vulkan_device.logical(), &self.allocation);
+    }
+}
+
+bitflags! {
+    /// What a buffer is created for. Metal buffers can be used for
+    /// anything, Vulkan ones only for what they're created with, so buffers
+    /// bound as shader arguments need `STORAGE`. The default is every usage.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct MTLBufferUsage: u32 {
+        const VERTEX = (1 << 0);
+        const INDEX = (1 << 1);
+        const UNIFORM = (1 << 2);
+        const STORAGE = (1 << 3);
+        const INDIRECT = (1 << 4);
+        const TRANSFER_SOURCE = (1 << 5);
+        const TRANSFER_DESTINATION = (1 << 6);
+    }
 }
 
-pub enum MTLBufferUsage {
-    Vertex,
+impl Default for MTLBufferUsage {
+    fn default() -> Self {
+        Self::all()
+    }
 }
 
 impl MTLBufferUsage {
     #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
-    pub fn to_vulkan(&self) -> vk::BufferUsageFlags {
-        match self {
-            Self::Vertex => vk::BufferUsageFlags::VERTEX_BUFFER,
+    pub fn to_vulkan(self) -> vk::BufferUsageFlags {
+        let mut flags = vk::BufferUsageFlags::empty();
+
+        for (usage, flag) in [
+            (Self::VERTEX, vk::BufferUsageFlags::VERTEX_BUFFER),
+            (Self::INDEX, vk::BufferUsageFlags::INDEX_BUFFER),
+            (Self::UNIFORM, vk::BufferUsageFlags::UNIFORM_BUFFER),
+            (Self::STORAGE, vk::BufferUsageFlags::STORAGE_BUFFER),
+            (Self::INDIRECT, vk::BufferUsageFlags::INDIRECT_BUFFER),
+            (Self::TRANSFER_SOURCE, vk::BufferUsageFlags::TRANSFER_SRC),
+            (
+                Self::TRANSFER_DESTINATION,
+                vk::BufferUsageFlags::TRANSFER_DST,
+            ),
+        ] {
+            if self.contains(usage) {
+                flags |= flag;
+            }
         }
+
+        flags
+    }
+
+    /// Whether the buffer can be bound as a shader argument, which is a
+    /// storage buffer on Vulkan.
+    pub fn is_argument(self) -> bool {
+        self.contains(Self::STORAGE)
     }
 }
 
 pub struct MTLBuffer<T> {
     device: Arc<MTLDevice>,
+    options: MTLResourceOptions,
     phantom: PhantomData<T>,
     #[cfg(all(any(target_os = "macos", target_os = "ios"), not(feature = "moltenvk")))]
     metal_buffer: Retained<ProtocolObject<dyn MetalMTLBuffer>>,
 
     #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
-    vulkan_buffer: VulkanMTLBuffer,
+    vulkan_buffer: Arc<VulkanMTLBuffer>,
 }
 
 impl<T> MTLBuffer<T> {
     #[cfg(all(any(target_os = "macos", target_os = "ios"), not(feature = "moltenvk")))]
-    pub fn from_metal<T>(
+    pub fn from_metal(
         device: Arc<MTLDevice>,
         metal_buffer: Retained<ProtocolObject<dyn MetalMTLBuffer>>,
+        options: MTLResourceOptions,
     ) -> Self {
         Self {
             device,
+            options,
+            phantom: PhantomData,
             metal_buffer,
         }
     }
 
+    #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+    pub fn from_vulkan(
+        device: Arc<MTLDevice>,
+        vulkan_buffer: VulkanMTLBuffer,
+        options: MTLResourceOptions,
+    ) -> Self {
+        Self {
+            device,
+            options,
+            phantom: PhantomData,
+            vulkan_buffer: Arc::new(vulkan_buffer),
+        }
+    }
+
+    pub fn resource_options(&self) -> MTLResourceOptions {
+        self.options
+    }
+
+    pub fn storage_mode(&self) -> MTLStorageMode {
+        self.options.storage_mode
+    }
+
+    pub fn cpu_cache_mode(&self) -> MTLCPUCacheMode {
+        self.options.cpu_cache_mode
+    }
+
+    pub fn hazard_tracking_mode(&self) -> MTLHazardTrackingMode {
+        self.options.hazard_tracking_mode
+    }
+
+    /// CPU address of the buffer's storage, null for private buffers.
+    pub fn contents(&self) -> *mut T {
+        if !self.options.is_host_visible() {
+            return std::ptr::null_mut();
+        }
+
+        #[cfg(all(any(target_os = "macos", target_os = "ios"), not(feature = "moltenvk")))]
+        return self.metal_buffer.contents().as_ptr() as *mut T;
+
+        #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+        return self.vulkan_buffer.allocation.mapped() as *mut T;
+    }
+
+    /// Size of the buffer in bytes.
+    pub fn length(&self) -> usize {
+        #[cfg(all(any(target_os = "macos", target_os = "ios"), not(feature = "moltenvk")))]
+        return self.metal_buffer.length();
+
+        #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+        return self.vulkan_buffer.length;
+    }
+
+    /// Makes CPU writes to the bytes in `range` visible to the GPU. Like on
+    /// Metal, only managed buffers have a copy to synchronize.
+    pub fn did_modify_range(&self, range: Range<usize>) -> Result<()> {
+        if self.storage_mode() != MTLStorageMode::Managed {
+            return Err(anyhow!(
+                "did_modify_range needs a managed buffer, this one is {:?}.",
+                self.storage_mode()
+            ));
+        }
+
+        #[cfg(all(any(target_os = "macos", target_os = "ios"), not(feature = "moltenvk")))]
+        {
+            use objc2_foundation::NSRange;
+
+            self.metal_buffer
+                .didModifyRange(NSRange::new(range.start, range.len()));
+            return Ok(());
+        }
+
+        #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+        return self.vulkan_did_modify_range(range);
+    }
+
+    #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+    fn vulkan_did_modify_range(&self, range: Range<usize>) -> Result<()> {
+        if range.end > self.vulkan_buffer.length {
+            return Err(anyhow!(
+                "Range {:?} is out of bounds for a buffer of {} bytes.",
+                range,
+                self.vulkan_buffer.length
+            ));
+        }
+
+        self.device.vulkan_device().flush(
+            &self.vulkan_buffer.allocation,
+            range.start as u64..range.end as u64,
+        )
+    }
+
+    #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+    pub fn vulkan_buffer(&self) -> &vk::Buffer {
+        &self.vulkan_buffer.buffer
+    }
+
+    #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+    pub fn vulkan_usage(&self) -> MTLBufferUsage {
+        self.vulkan_buffer.usage
+    }
+
+    #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+    pub fn vulkan_allocation(&self) -> &VulkanAllocation {
+        &self.vulkan_buffer.allocation
+    }
+
+    /// What a command buffer holds on to while it uses this buffer.
+    #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+    pub fn vulkan_resource(&self) -> &Arc<VulkanMTLBuffer> {
+        &self.vulkan_buffer
+    }
+
     #[cfg(all(any(target_os = "macos", target_os = "ios"), not(feature = "moltenvk")))]
     pub fn metal_buffer(&self) -> &Retained<ProtocolObject<dyn MetalMTLBuffer>> {
         &self.metal_buffer
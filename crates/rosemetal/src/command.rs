@@ -1,15 +1,25 @@
 use crate::{
-    MTLBeginRenderPassDescriptor, MTLBuffer, MTLDevice, MTLPrimitiveType, MTLRenderPass,
-    MTLRenderPassDescriptor, MTLRenderPipelineState, MTLTexture,
+    MTLBeginRenderPassDescriptor, MTLBuffer, MTLComputePipelineState, MTLDepthStencilState,
+    MTLDevice, MTLPrimitiveType, MTLRenderPass, MTLRenderPassDescriptor, MTLRenderPipelineState,
+    MTLSamplerState, MTLSize, MTLStorageMode, MTLTexture,
 };
+use airlines::air_codegen::{MAX_BUFFER_BINDINGS, MAX_SAMPLER_BINDINGS};
 use anyhow::{Result, anyhow};
 use crossbeam::queue::SegQueue;
 use std::sync::Arc;
 
+#[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+use crate::{
+    MTLArgumentEncoder, MTLBufferUsage, MTLDepthStencilDescriptor, VulkanAttachmentKey,
+    VulkanMTLBuffer, VulkanPipelineLayout,
+};
 #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
 use ash::vk::Device;
 #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
-use std::sync::RwLock;
+use std::sync::{
+    Mutex, RwLock,
+    atomic::{AtomicBool, Ordering},
+};
 
 #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
 use ash::vk;
@@ -19,7 +29,8 @@ use objc2::{rc::Retained, runtime::ProtocolObject};
 #[cfg(all(any(target_os = "macos", target_os = "ios"), not(feature = "moltenvk")))]
 use objc2_metal::{
     MTLCommandBuffer as MetalMTLCommandBuffer, MTLCommandEncoder as MetalMTLCommandEncoder,
-    MTLCommandQueue as MetalMTLCommandQueue, MTLDevice as MetalMTLDevice,
+    MTLCommandQueue as MetalMTLCommandQueue,
+    MTLComputeCommandEncoder as MetalMTLComputeCommandEncoder, MTLDevice as MetalMTLDevice,
     MTLRenderCommandEncoder as MetalMTLRenderCommandEncoder,
 };
 
@@ -87,6 +98,19 @@ impl MTLCommandQueue {
     }
 }
 
+#[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+impl Drop for MTLCommandQueue {
+    fn drop(&mut self) {
+        // Command buffers hold the queue, they're all back in the pool.
+        unsafe {
+            self.device
+                .vulkan_device()
+                .logical()
+                .destroy_command_pool(self.vulkan_command_queue.command_pool, None);
+        }
+    }
+}
+
 pub struct MTLCommandBuffer {
     queue: Arc<MTLCommandQueue>,
     schedule_handler_queue: SegQueue<MTLCommandBufferHandler>,
@@ -96,6 +120,20 @@ pub struct MTLCommandBuffer {
 
     #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
     vulkan_command_buffer: vk::CommandBuffer,
+    /// Pools for the descriptor sets this command buffer binds, they live
+    /// as long as it does.
+    #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+    vulkan_descriptor_pools: Mutex<Vec<vk::DescriptorPool>>,
+    /// Buffers the recorded commands use. They aren't destroyed before the
+    /// command buffer is done with them, even when dropped by their owner.
+    #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+    vulkan_retained_buffers: Mutex<Vec<Arc<VulkanMTLBuffer>>>,
+    /// Managed buffers the CPU reads back once the commands are done.
+    #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+    vulkan_synchronized_buffers: Mutex<Vec<Arc<VulkanMTLBuffer>>>,
+    /// Commands were recorded and have to be submitted on `commit`.
+    #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+    vulkan_encoded: AtomicBool,
 }
 
 impl MTLCommandBuffer {
@@ -135,9 +173,111 @@ impl MTLCommandBuffer {
 
                 queue.vulkan_command_queue.command_buffers().pop().unwrap()
             },
+            vulkan_descriptor_pools: Mutex::new(vec![]),
+            vulkan_retained_buffers: Mutex::new(vec![]),
+            vulkan_synchronized_buffers: Mutex::new(vec![]),
+            vulkan_encoded: AtomicBool::new(false),
         }))
     }
 
+    /// Ends the commands an encoder recorded, they're submitted on `commit`.
+    #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+    fn vulkan_end_encoding(&self) -> Result<()> {
+        let device = self.queue.device.vulkan_device().logical();
+
+        // Host reads after `commit` have to see what the commands wrote, a
+        // finished submission only makes it available to the device.
+        let memory_barrier = vk::MemoryBarrier::default()
+            .src_access_mask(vk::AccessFlags::MEMORY_WRITE)
+            .dst_access_mask(vk::AccessFlags::HOST_READ);
+
+        unsafe {
+            device.cmd_pipeline_barrier(
+                self.vulkan_command_buffer,
+                vk::PipelineStageFlags::ALL_COMMANDS,
+                vk::PipelineStageFlags::HOST,
+                vk::DependencyFlags::empty(),
+                &[memory_barrier],
+                &[],
+                &[],
+            );
+            device.end_command_buffer(self.vulkan_command_buffer)?;
+        }
+
+        self.vulkan_encoded.store(true, Ordering::Release);
+
+        Ok(())
+    }
+
+    /// Keeps `buffer` alive until the command buffer is dropped.
+    #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+    pub fn vulkan_retain_buffer(&self, buffer: &Arc<VulkanMTLBuffer>) {
+        let mut buffers = self.vulkan_retained_buffers.lock().unwrap();
+
+        if !buffers.iter().any(|x| Arc::ptr_eq(x, buffer)) {
+            buffers.push(buffer.clone());
+        }
+    }
+
+    /// Allocates a descriptor set that stays valid until the command buffer
+    /// is dropped, starting a new pool when the current one is full.
+    #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+    pub fn vulkan_allocate_descriptor_set(
+        &self,
+        layout: vk::DescriptorSetLayout,
+    ) -> Result<vk::DescriptorSet> {
+        let device = self.queue.device.vulkan_device().logical();
+        let mut pools = self.vulkan_descriptor_pools.lock().unwrap();
+        let layouts = [layout];
+
+        if let Some(pool) = pools.last() {
+            let result = unsafe {
+                device.allocate_descriptor_sets(
+                    &vk::DescriptorSetAllocateInfo::default()
+                        .descriptor_pool(*pool)
+                        .set_layouts(&layouts),
+                )
+            };
+
+            match result {
+                Ok(sets) => return Ok(sets[0]),
+                Err(vk::Result::ERROR_OUT_OF_POOL_MEMORY | vk::Result::ERROR_FRAGMENTED_POOL) => {}
+                Err(error) => return Err(error.into()),
+            }
+        }
+
+        let pool_sizes = [
+            vk::DescriptorType::STORAGE_BUFFER,
+            vk::DescriptorType::SAMPLED_IMAGE,
+            vk::DescriptorType::STORAGE_IMAGE,
+            vk::DescriptorType::SAMPLER,
+        ]
+        .map(|ty| {
+            vk::DescriptorPoolSize::default()
+                .ty(ty)
+                .descriptor_count(DESCRIPTORS_PER_POOL)
+        });
+        let pool = unsafe {
+            device.create_descriptor_pool(
+                &vk::DescriptorPoolCreateInfo::default()
+                    .max_sets(DESCRIPTOR_SETS_PER_POOL)
+                    .pool_sizes(&pool_sizes),
+                None,
+            )?
+        };
+        pools.push(pool);
+
+        let sets = unsafe {
+            device.allocate_descriptor_sets(
+                &vk::DescriptorSetAllocateInfo::default()
+                    .descriptor_pool(pool)
+                    .set_layouts(&layouts),
+            )?
+        };
+
+        Ok(sets[0])
+    }
+
     #[cfg(all(any(target_os = "macos", target_os = "ios"), not(feature = "moltenvk")))]
     pub fn metal_new(queue: Arc<MTLCommandQueue>) -> Result<Arc<Self>> {
         let metal_command_buffer = queue.metal_command_queue.commandBuffer();
@@ -162,6 +302,37 @@ impl MTLCommandBuffer {
         self.schedule_handler_queue.push(handler);
     }
 
+    /// Makes what the GPU writes to a managed `buffer` visible to the CPU
+    /// once the command buffer completes. Other storage modes need nothing.
+    pub fn synchronize_resource<T>(&self, buffer: &MTLBuffer<T>) -> Result<()> {
+        if buffer.storage_mode() != MTLStorageMode::Managed {
+            return Ok(());
+        }
+
+        #[cfg(all(any(target_os = "macos", target_os = "ios"), not(feature = "moltenvk")))]
+        {
+            use objc2_metal::{MTLBlitCommandEncoder, MTLCommandEncoder};
+
+            let encoder = self
+                .metal_command_buffer
+                .blitCommandEncoder()
+                .ok_or(anyhow!("Blit Command Encoder creation failed."))?;
+            encoder.synchronizeResource(ProtocolObject::from_ref(&**buffer.metal_buffer()));
+            encoder.endEncoding();
+        }
+
+        #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+        {
+            self.vulkan_retain_buffer(buffer.vulkan_resource());
+            self.vulkan_synchronized_buffers
+                .lock()
+                .unwrap()
+                .push(buffer.vulkan_resource().clone());
+        }
+
+        Ok(())
+    }
+
     pub fn commit(&self) -> Result<()> {
         #[cfg(all(any(target_os = "macos", target_os = "ios"), not(feature = "moltenvk")))]
         return self.metal_commit();
@@ -198,8 +369,6 @@ impl MTLCommandBuffer {
 
             match handle {
                 MTLCommandBufferHandler::Present(d) => {
-                    use std::sync::atomic::Ordering;
-
                     let sync_object = d.vulkan_sync_object().read().unwrap();
                     let sync_object = sync_object.as_ref().unwrap();
 
@@ -223,6 +392,7 @@ impl MTLCommandBuffer {
                             *sync_object.fence().vulkan_fence(),
                         )?
                     };
+                    self.vulkan_encoded.store(false, Ordering::Release);
 
                     let (swapchain_instance, swapchain_khr) =
                         d.vulkan_swapchain().as_ref().unwrap();
@@ -259,6 +429,26 @@ impl MTLCommandBuffer {
             }
         }
 
+        // Nothing was presented, the commands still have to run. Waiting
+        // for them is what keeps resources tracked: the next command buffer
+        // can't start before this one is done with them.
+        if self.vulkan_encoded.swap(false, Ordering::AcqRel) {
+            let command_buffers = [self.vulkan_command_buffer];
+
+            unsafe {
+                device.queue_submit(
+                    self.queue.vulkan_command_queue.graphics_queue,
+                    &[vk::SubmitInfo::default().command_buffers(&command_buffers)],
+                    vk::Fence::null(),
+                )?;
+                device.queue_wait_idle(self.queue.vulkan_command_queue.graphics_queue)?;
+            }
+        }
+
+        for buffer in self.vulkan_synchronized_buffers.lock().unwrap().drain(..) {
+            buffer.vulkan_invalidate()?;
+        }
+
         Ok(())
     }
 }
@@ -277,6 +467,19 @@ impl Drop for MTLCommandBuffer {
                 .unwrap();
         }
 
+        for pool in self.vulkan_descriptor_pools.get_mut().unwrap().drain(..) {
+            unsafe {
+                self.queue
+                    .device
+                    .vulkan_device()
+                    .logical()
+                    .destroy_descriptor_pool(pool, None);
+            }
+        }
+
+        // The commands are done, so are the buffers they used.
+        self.vulkan_retained_buffers.get_mut().unwrap().clear();
+
         let buffer_queue = self.queue.vulkan_command_queue.command_buffers();
 
         buffer_queue.push(buffer);
@@ -293,6 +496,32 @@ pub struct MTLRenderCommandEncoder {
     metal_render_command_encoder: Retained<ProtocolObject<dyn MetalMTLRenderCommandEncoder>>,
 
     command_buffer: Arc<MTLCommandBuffer>,
+
+    /// Pipelines are made for the pass's render pass and attachments.
+    #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+    vulkan_render_pass: vk::RenderPass,
+    #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+    vulkan_attachment_key: VulkanAttachmentKey,
+
+    /// Buffers by their `[[buffer(n)]]` index, with the offset they're bound
+    /// at. airlines gives both stages the same bindings in set 0, so the
+    /// vertex and fragment stages share this table.
+    #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+    vulkan_buffers: Mutex<Vec<Option<VulkanBufferBinding>>>,
+
+    /// Samplers by their `[[sampler(n)]]` index, written to the fragment
+    /// stage's descriptor set when drawing.
+    #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+    vulkan_fragment_samplers: Mutex<Vec<Option<Arc<MTLSamplerState>>>>,
+
+    /// Pipelines without extended dynamic state have to be created with it.
+    #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+    vulkan_depth_stencil_state: Mutex<Option<Arc<MTLDepthStencilState>>>,
+
+    /// The current pipeline, rebound when a new depth stencil state needs
+    /// another pipeline. Argument buffers are bound with its layout.
+    #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+    vulkan_render_pipeline_state: Mutex<Option<Arc<MTLRenderPipelineState>>>,
 }
 
 impl MTLRenderCommandEncoder {
@@ -314,12 +543,12 @@ impl MTLRenderCommandEncoder {
         render_pass: Arc<MTLRenderPass>,
         begin_descriptor: MTLBeginRenderPassDescriptor,
     ) -> Result<Self> {
-        let vk_render_pass = render_pass
-            .vulkan_render_pass(&begin_descriptor)?
-            .borrow()
-            .unwrap();
+        render_pass.descriptor().validate_begin(&begin_descriptor)?;
 
-        begin_descriptor.vulkan_framebuffer_check(&vk_render_pass, render_pass.device().clone())?;
+        let attachment_key = begin_descriptor.vulkan_attachment_key()?;
+        let vk_render_pass = render_pass.vulkan_render_pass(&begin_descriptor, &attachment_key)?;
+        let (framebuffer, extent) =
+            begin_descriptor.vulkan_framebuffer(vk_render_pass, &attachment_key)?;
 
         let device = command_buffer.queue.device.vulkan_device().logical();
 
@@ -331,31 +560,60 @@ impl MTLRenderCommandEncoder {
             )?;
         }
 
-        let clear_color_values = begin_descriptor.vulkan_clear_color_values();
+        let clear_color_values = begin_descriptor.vulkan_clear_values();
 
-        let texture = begin_descriptor.color_attachments[0].texture.clone();
+        let render_area = vk::Rect2D {
+            offset: vk::Offset2D { x: 0, y: 0 },
+            extent,
+        };
 
         let begin_render_pass_info = vk::RenderPassBeginInfo::default()
             .render_pass(vk_render_pass)
-            .framebuffer(texture.vulkan_framebuffer().read().unwrap().unwrap())
-            .render_area(vk::Rect2D {
-                offset: vk::Offset2D { x: 0, y: 0 },
-                extent: vk::Extent2D {
-                    width: texture.width(),
-                    height: texture.height(),
-                },
-            })
+            .framebuffer(framebuffer)
+            .render_area(render_area)
             .clear_values(&clear_color_values);
 
+        // Metal's default viewport and scissor cover the whole pass.
+        let viewport = vk::Viewport::default()
+            .width(extent.width as f32)
+            .height(extent.height as f32)
+            .max_depth(1.0);
+
         unsafe {
             device.cmd_begin_render_pass(
                 command_buffer.vulkan_command_buffer,
                 &begin_render_pass_info,
                 vk::SubpassContents::INLINE,
             );
+            device.cmd_set_viewport(command_buffer.vulkan_command_buffer, 0, &[viewport]);
+            device.cmd_set_scissor(command_buffer.vulkan_command_buffer, 0, &[render_area]);
+            device.cmd_set_stencil_reference(
+                command_buffer.vulkan_command_buffer,
+                vk::StencilFaceFlags::FRONT_AND_BACK,
+                0,
+            );
         }
 
-        Ok(Self { command_buffer })
+        // Metal's default depth stencil state tests nothing. What's dynamic
+        // state has to be recorded before the first draw.
+        MTLDepthStencilState::vulkan_new(
+            &command_buffer.queue.device,
+            MTLDepthStencilDescriptor::default(),
+        )?
+        .vulkan_record(
+            &command_buffer.queue.device,
+            command_buffer.vulkan_command_buffer,
+        );
+
+        Ok(Self {
+            command_buffer,
+            vulkan_render_pass: vk_render_pass,
+            vulkan_attachment_key: attachment_key,
+            vulkan_buffers: Mutex::new(vec![None; MAX_BUFFER_BINDINGS as usize]),
+            vulkan_fragment_samplers: Mutex::new(vec![None; MAX_SAMPLER_BINDINGS as usize]),
+            vulkan_depth_stencil_state: Mutex::new(None),
+            vulkan_render_pipeline_state: Mutex::new(None),
+        })
     }
 
     #[cfg(all(any(target_os = "macos", target_os = "ios"), not(feature = "moltenvk")))]
@@ -364,6 +622,8 @@ impl MTLRenderCommandEncoder {
         render_pass: Arc<MTLRenderPass>,
         begin_descriptor: MTLBeginRenderPassDescriptor,
     ) -> Result<Self> {
+        render_pass.descriptor().validate_begin(&begin_descriptor)?;
+
         let metal_render_command_encoder = command_buffer
             .metal_command_buffer
             .renderCommandEncoderWithDescriptor(
@@ -394,14 +654,16 @@ impl MTLRenderCommandEncoder {
 
     #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
     pub fn vulkan_end_encoding(&self) -> Result<()> {
-        let device = self.command_buffer.queue.device.vulkan_device().logical();
-
         unsafe {
-            device.cmd_end_render_pass(self.command_buffer.vulkan_command_buffer);
-            device.end_command_buffer(self.command_buffer.vulkan_command_buffer)?;
+            self.command_buffer
+                .queue
+                .device
+                .vulkan_device()
+                .logical()
+                .cmd_end_render_pass(self.command_buffer.vulkan_command_buffer);
         }
 
-        Ok(())
+        self.command_buffer.vulkan_end_encoding()
     }
 
     #[cfg(all(any(target_os = "macos", target_os = "ios"), not(feature = "moltenvk")))]
@@ -411,7 +673,10 @@ impl MTLRenderCommandEncoder {
         Ok(())
     }
 
-    pub fn set_render_pipeline_state(&self, pipeline_state: &MTLRenderPipelineState) -> Result<()> {
+    pub fn set_render_pipeline_state(
+        &self,
+        pipeline_state: &Arc<MTLRenderPipelineState>,
+    ) -> Result<()> {
         #[cfg(all(any(target_os = "macos", target_os = "ios"), not(feature = "moltenvk")))]
         {
             self.metal_render_command_encoder
@@ -420,21 +685,374 @@ impl MTLRenderCommandEncoder {
 
         #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
         {
-            todo!()
+            self.vulkan_bind_pipeline(pipeline_state)?;
+            self.vulkan_render_pipeline_state
+                .lock()
+                .unwrap()
+                .replace(pipeline_state.clone());
+            self.vulkan_bind_resources()?;
         }
 
         Ok(())
     }
 
-    pub fn set_vertex_buffer<T>(&self, buffer: &Arc<MTLBuffer<T>>) -> Result<()> {
+    /// Without extended dynamic state, the depth stencil state set so far
+    /// is part of the pipeline.
+    #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+    fn vulkan_bind_pipeline(&self, pipeline_state: &MTLRenderPipelineState) -> Result<()> {
+        let depth_stencil_state = self.vulkan_depth_stencil_state();
+        let pipeline = pipeline_state.vulkan_pipeline(
+            self.vulkan_render_pass,
+            &self.vulkan_attachment_key,
+            depth_stencil_state.as_ref().map(|x| x.descriptor()),
+        )?;
+
+        unsafe {
+            self.command_buffer
+                .queue
+                .device
+                .vulkan_device()
+                .logical()
+                .cmd_bind_pipeline(
+                    self.command_buffer.vulkan_command_buffer,
+                    vk::PipelineBindPoint::GRAPHICS,
+                    pipeline,
+                );
+        }
+
+        Ok(())
+    }
+
+    /// Writes the buffers and samplers set so far to a new set 0 and binds
+    /// it, once there's a pipeline to take them.
+    #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+    fn vulkan_bind_resources(&self) -> Result<()> {
+        let (pipeline_layout, descriptor_set_layout, fragment_samplers) = {
+            let pipeline_state = self.vulkan_render_pipeline_state.lock().unwrap();
+
+            match pipeline_state.as_ref().map(|x| x.vulkan_pipeline_layout()) {
+                Some(layout) => (
+                    layout.pipeline_layout(),
+                    layout.descriptor_set_layout(0).unwrap(),
+                    layout.fragment_samplers(),
+                ),
+                None => return Ok(()),
+            }
+        };
+
+        let buffers = self.vulkan_buffers.lock().unwrap();
+        let samplers = self.vulkan_fragment_samplers.lock().unwrap();
+        let samplers = match fragment_samplers {
+            true => samplers.as_slice(),
+            false => &[],
+        };
+        if buffers.iter().all(Option::is_none) && samplers.iter().all(Option::is_none) {
+            return Ok(());
+        }
+
+        let descriptor_set = self
+            .command_buffer
+            .vulkan_allocate_descriptor_set(descriptor_set_layout)?;
+
+        let buffer_infos = vulkan_buffer_infos(&buffers);
+        let image_infos = samplers
+            .iter()
+            .enumerate()
+            .filter_map(|(index, sampler)| {
+                let info =
+                    vk::DescriptorImageInfo::default().sampler(*sampler.as_ref()?.vulkan_sampler());
+                Some((index as u32, [info]))
+            })
+            .collect::<Vec<_>>();
+        let writes = vulkan_buffer_writes(descriptor_set, &buffer_infos)
+            .chain(image_infos.iter().map(|(index, info)| {
+                vk::WriteDescriptorSet::default()
+                    .dst_set(descriptor_set)
+                    .dst_binding(VulkanPipelineLayout::sampler_binding(*index))
+                    .descriptor_type(vk::DescriptorType::SAMPLER)
+                    .image_info(info)
+            }))
+            .collect::<Vec<_>>();
+
+        let device = self.command_buffer.queue.device.vulkan_device().logical();
+
+        unsafe {
+            device.update_descriptor_sets(&writes, &[]);
+            device.cmd_bind_descriptor_sets(
+                self.command_buffer.vulkan_command_buffer,
+                vk::PipelineBindPoint::GRAPHICS,
+                pipeline_layout,
+                0,
+                &[descriptor_set],
+                &[],
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Binds what `encoder` holds to its argument buffer's set, for every
+    /// stage of the current pipeline that takes it.
+    #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+    pub fn set_argument_buffer(&self, encoder: &MTLArgumentEncoder) -> Result<()> {
+        let set = encoder.layout().set;
+        let (pipeline_layout, descriptor_set_layout) = {
+            let pipeline_state = self.vulkan_render_pipeline_state.lock().unwrap();
+            let layout = pipeline_state
+                .as_ref()
+                .map(|x| x.vulkan_pipeline_layout())
+                .ok_or(anyhow!(
+                    "Argument buffers are bound after the render pipeline state."
+                ))?;
+
+            (
+                layout.pipeline_layout(),
+                layout.descriptor_set_layout(set).ok_or(anyhow!(
+                    "The render pipeline doesn't take `{}`.",
+                    encoder.layout().name
+                ))?,
+            )
+        };
+
+        let device = &self.command_buffer.queue.device;
+        let descriptor_set = self
+            .command_buffer
+            .vulkan_allocate_descriptor_set(descriptor_set_layout)?;
+        encoder.vulkan_update_descriptor_set(device, descriptor_set);
+        for buffer in encoder.vulkan_buffers() {
+            self.command_buffer.vulkan_retain_buffer(buffer);
+        }
+
+        unsafe {
+            device.vulkan_device().logical().cmd_bind_descriptor_sets(
+                self.command_buffer.vulkan_command_buffer,
+                vk::PipelineBindPoint::GRAPHICS,
+                pipeline_layout,
+                set,
+                &[descriptor_set],
+                &[],
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Binds `buffer` at `index` of the vertex stage's buffer table, which
+    /// both the vertex descriptor's layouts and the vertex function's
+    /// `[[buffer(index)]]` read from.
+    pub fn set_vertex_buffer<T>(
+        &self,
+        buffer: &Arc<MTLBuffer<T>>,
+        offset: u64,
+        index: usize,
+    ) -> Result<()> {
+        if index >= MAX_BUFFER_BINDINGS as usize {
+            return Err(anyhow!("No vertex buffer at index {}.", index));
+        }
+
         #[cfg(all(any(target_os = "macos", target_os = "ios"), not(feature = "moltenvk")))]
         unsafe {
-            // TODO: Add Params for `offset` and `index`.
+            use std::ops::Deref;
+            self.metal_render_command_encoder
+                .setVertexBuffer_offset_atIndex(
+                    Some(buffer.metal_buffer().deref()),
+                    offset as usize,
+                    index,
+                );
+        }
+
+        // Vulkan fetches vertices from vertex buffers, and the shader reads
+        // its arguments from storage buffers.
+        #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+        {
+            let usage = buffer.vulkan_usage();
+            if !usage.intersects(MTLBufferUsage::VERTEX | MTLBufferUsage::STORAGE) {
+                return Err(anyhow!("Vertex buffers need `VERTEX` or `STORAGE` usage."));
+            }
+
+            if usage.contains(MTLBufferUsage::VERTEX) {
+                let device = self.command_buffer.queue.device.vulkan_device();
+                let max_vertex_input_bindings = device.features().max_vertex_input_bindings;
+                if index >= max_vertex_input_bindings as usize {
+                    return Err(anyhow!(
+                        "Buffer {} is past the {} vertex buffers the device binds.",
+                        index,
+                        max_vertex_input_bindings
+                    ));
+                }
+
+                self.command_buffer
+                    .vulkan_retain_buffer(buffer.vulkan_resource());
+
+                unsafe {
+                    device.logical().cmd_bind_vertex_buffers(
+                        self.command_buffer.vulkan_command_buffer,
+                        index as u32,
+                        &[*buffer.vulkan_buffer()],
+                        &[offset],
+                    );
+                }
+            }
+
+            if usage.is_argument() {
+                self.vulkan_set_buffer(buffer, offset, index)?;
+            }
+        }
+
+        Ok(())
+    }
 
+    /// Binds `buffer` to the fragment function's `[[buffer(index)]]`.
+    pub fn set_fragment_buffer<T>(
+        &self,
+        buffer: &Arc<MTLBuffer<T>>,
+        offset: u64,
+        index: usize,
+    ) -> Result<()> {
+        if index >= MAX_BUFFER_BINDINGS as usize {
+            return Err(anyhow!("No fragment buffer at index {}.", index));
+        }
+
+        #[cfg(all(any(target_os = "macos", target_os = "ios"), not(feature = "moltenvk")))]
+        unsafe {
             use std::ops::Deref;
             self.metal_render_command_encoder
-                .setVertexBuffer_offset_atIndex(Some(buffer.metal_buffer().deref()), 0, 0);
+                .setFragmentBuffer_offset_atIndex(
+                    Some(buffer.metal_buffer().deref()),
+                    offset as usize,
+                    index,
+                );
         }
+
+        #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+        {
+            if !buffer.vulkan_usage().is_argument() {
+                return Err(anyhow!(
+                    "Buffers without `STORAGE` usage can't be bound as arguments."
+                ));
+            }
+
+            self.vulkan_set_buffer(buffer, offset, index)?;
+        }
+
+        Ok(())
+    }
+
+    #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+    fn vulkan_set_buffer<T>(
+        &self,
+        buffer: &Arc<MTLBuffer<T>>,
+        offset: u64,
+        index: usize,
+    ) -> Result<()> {
+        self.command_buffer
+            .vulkan_retain_buffer(buffer.vulkan_resource());
+        self.vulkan_buffers.lock().unwrap()[index] =
+            Some((buffer.vulkan_resource().clone(), offset));
+
+        self.vulkan_bind_resources()
+    }
+
+    pub fn set_fragment_sampler_state(
+        &self,
+        sampler: &Arc<MTLSamplerState>,
+        index: usize,
+    ) -> Result<()> {
+        if index >= MAX_SAMPLER_BINDINGS as usize {
+            return Err(anyhow!("No fragment sampler at index {}.", index));
+        }
+
+        #[cfg(all(any(target_os = "macos", target_os = "ios"), not(feature = "moltenvk")))]
+        unsafe {
+            self.metal_render_command_encoder
+                .setFragmentSamplerState_atIndex(Some(sampler.metal_sampler_state()), index);
+        }
+
+        #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+        {
+            self.vulkan_fragment_samplers.lock().unwrap()[index] = Some(sampler.clone());
+            self.vulkan_bind_resources()?;
+        }
+
+        Ok(())
+    }
+
+    #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+    pub fn vulkan_fragment_sampler(&self, index: usize) -> Option<vk::Sampler> {
+        self.vulkan_fragment_samplers
+            .lock()
+            .unwrap()
+            .get(index)?
+            .as_ref()
+            .map(|x| *x.vulkan_sampler())
+    }
+
+    pub fn set_depth_stencil_state(&self, state: &Arc<MTLDepthStencilState>) -> Result<()> {
+        #[cfg(all(any(target_os = "macos", target_os = "ios"), not(feature = "moltenvk")))]
+        {
+            self.metal_render_command_encoder
+                .setDepthStencilState(Some(state.metal_depth_stencil_state()));
+        }
+
+        // Metal usually sets the pipeline first, without extended dynamic
+        // state it's rebound with the new tests.
+        #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+        {
+            state.vulkan_record(
+                &self.command_buffer.queue.device,
+                self.command_buffer.vulkan_command_buffer,
+            );
+            self.vulkan_depth_stencil_state
+                .lock()
+                .unwrap()
+                .replace(state.clone());
+
+            let extended_dynamic_state = self
+                .command_buffer
+                .queue
+                .device
+                .vulkan_device()
+                .features()
+                .extended_dynamic_state;
+            let pipeline_state = match extended_dynamic_state {
+                true => None,
+                false => self.vulkan_render_pipeline_state.lock().unwrap().clone(),
+            };
+
+            if let Some(pipeline_state) = pipeline_state {
+                self.vulkan_bind_pipeline(&pipeline_state)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+    pub fn vulkan_depth_stencil_state(&self) -> Option<Arc<MTLDepthStencilState>> {
+        self.vulkan_depth_stencil_state.lock().unwrap().clone()
+    }
+
+    pub fn set_stencil_reference_value(&self, value: u32) -> Result<()> {
+        #[cfg(all(any(target_os = "macos", target_os = "ios"), not(feature = "moltenvk")))]
+        {
+            self.metal_render_command_encoder
+                .setStencilReferenceValue(value);
+        }
+
+        #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+        unsafe {
+            self.command_buffer
+                .queue
+                .device
+                .vulkan_device()
+                .logical()
+                .cmd_set_stencil_reference(
+                    self.command_buffer.vulkan_command_buffer,
+                    vk::StencilFaceFlags::FRONT_AND_BACK,
+                    value,
+                );
+        }
+
         Ok(())
     }
 
@@ -454,6 +1072,301 @@ impl MTLRenderCommandEncoder {
     }
 }
 
+/// A buffer bound at a `[[buffer(n)]]` index, and the offset it's bound at.
+#[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+type VulkanBufferBinding = (Arc<VulkanMTLBuffer>, u64);
+
+/// Infos of the buffers bound so far, by their binding in set 0.
+#[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+fn vulkan_buffer_infos(
+    buffers: &[Option<VulkanBufferBinding>],
+) -> Vec<(u32, [vk::DescriptorBufferInfo; 1])> {
+    buffers
+        .iter()
+        .enumerate()
+        .filter_map(|(index, buffer)| {
+            let (buffer, offset) = buffer.as_ref()?;
+            let info = vk::DescriptorBufferInfo::default()
+                .buffer(buffer.vulkan_buffer())
+                .offset(*offset)
+                .range(vk::WHOLE_SIZE);
+            Some((index as u32, [info]))
+        })
+        .collect()
+}
+
+#[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+fn vulkan_buffer_writes(
+    descriptor_set: vk::DescriptorSet,
+    buffer_infos: &[(u32, [vk::DescriptorBufferInfo; 1])],
+) -> impl Iterator<Item = vk::WriteDescriptorSet<'_>> {
+    buffer_infos.iter().map(move |(index, info)| {
+        vk::WriteDescriptorSet::default()
+            .dst_set(descriptor_set)
+            .dst_binding(*index)
+            .descriptor_type(vk::DescriptorType::STORAGE_BUFFER)
+            .buffer_info(info)
+    })
+}
+
+pub struct MTLComputeCommandEncoder {
+    #[cfg(all(any(target_os = "macos", target_os = "ios"), not(feature = "moltenvk")))]
+    metal_compute_command_encoder: Retained<ProtocolObject<dyn MetalMTLComputeCommandEncoder>>,
+
+    command_buffer: Arc<MTLCommandBuffer>,
+
+    /// Buffers by their `[[buffer(n)]]` index, with the offset they're bound
+    /// at.
+    #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+    vulkan_buffers: Mutex<Vec<Option<VulkanBufferBinding>>>,
+
+    /// Layout of the current pipeline, buffers are bound with it.
+    #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+    vulkan_pipeline_layout: Mutex<Option<VulkanPipelineLayout>>,
+    /// Threadgroup size the current kernel was translated for.
+    #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+    vulkan_threads_per_threadgroup: Mutex<Option<MTLSize>>,
+}
+
+impl MTLComputeCommandEncoder {
+    pub fn new(command_buffer: Arc<MTLCommandBuffer>) -> Result<Self> {
+        #[cfg(all(any(target_os = "macos", target_os = "ios"), not(feature = "moltenvk")))]
+        return Self::metal_new(command_buffer);
+
+        #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+        return Self::vulkan_new(command_buffer);
+    }
+
+    #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+    pub fn vulkan_new(command_buffer: Arc<MTLCommandBuffer>) -> Result<Self> {
+        unsafe {
+            command_buffer
+                .queue
+                .device
+                .vulkan_device()
+                .logical()
+                .begin_command_buffer(
+                    command_buffer.vulkan_command_buffer,
+                    &vk::CommandBufferBeginInfo::default()
+                        .flags(vk::CommandBufferUsageFlags::SIMULTANEOUS_USE),
+                )?;
+        }
+
+        Ok(Self {
+            command_buffer,
+            vulkan_buffers: Mutex::new(vec![None; MAX_BUFFER_BINDINGS as usize]),
+            vulkan_pipeline_layout: Mutex::new(None),
+            vulkan_threads_per_threadgroup: Mutex::new(None),
+        })
+    }
+
+    #[cfg(all(any(target_os = "macos", target_os = "ios"), not(feature = "moltenvk")))]
+    pub fn metal_new(command_buffer: Arc<MTLCommandBuffer>) -> Result<Self> {
+        let metal_compute_command_encoder =
+            match command_buffer.metal_command_buffer.computeCommandEncoder() {
+                Some(c) => c,
+                None => return Err(anyhow!("Compute Command Encoder creation failed.")),
+            };
+
+        Ok(Self {
+            command_buffer,
+            metal_compute_command_encoder,
+        })
+    }
+
+    pub fn end_encoding(&self) -> Result<()> {
+        #[cfg(all(any(target_os = "macos", target_os = "ios"), not(feature = "moltenvk")))]
+        {
+            self.metal_compute_command_encoder.endEncoding();
+            return Ok(());
+        }
+
+        #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+        return self.command_buffer.vulkan_end_encoding();
+    }
+
+    pub fn set_compute_pipeline_state(
+        &self,
+        pipeline_state: &MTLComputePipelineState,
+    ) -> Result<()> {
+        #[cfg(all(any(target_os = "macos", target_os = "ios"), not(feature = "moltenvk")))]
+        {
+            self.metal_compute_command_encoder
+                .setComputePipelineState(pipeline_state.metal_state());
+        }
+
+        #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+        {
+            unsafe {
+                self.command_buffer
+                    .queue
+                    .device
+                    .vulkan_device()
+                    .logical()
+                    .cmd_bind_pipeline(
+                        self.command_buffer.vulkan_command_buffer,
+                        vk::PipelineBindPoint::COMPUTE,
+                        pipeline_state.vulkan_pipeline(),
+                    );
+            }
+
+            self.vulkan_pipeline_layout
+                .lock()
+                .unwrap()
+                .replace(pipeline_state.vulkan_pipeline_layout().clone());
+            self.vulkan_threads_per_threadgroup
+                .lock()
+                .unwrap()
+                .replace(pipeline_state.vulkan_threads_per_threadgroup());
+            self.vulkan_bind_buffers()?;
+        }
+
+        Ok(())
+    }
+
+    /// Binds `buffer` to the kernel's `[[buffer(index)]]`.
+    pub fn set_buffer<T>(
+        &self,
+        buffer: &Arc<MTLBuffer<T>>,
+        offset: u64,
+        index: usize,
+    ) -> Result<()> {
+        if index >= MAX_BUFFER_BINDINGS as usize {
+            return Err(anyhow!("No kernel buffer at index {}.", index));
+        }
+
+        #[cfg(all(any(target_os = "macos", target_os = "ios"), not(feature = "moltenvk")))]
+        unsafe {
+            use std::ops::Deref;
+            self.metal_compute_command_encoder.setBuffer_offset_atIndex(
+                Some(buffer.metal_buffer().deref()),
+                offset as usize,
+                index,
+            );
+        }
+
+        #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+        {
+            if !buffer.vulkan_usage().is_argument() {
+                return Err(anyhow!(
+                    "Buffers without `STORAGE` usage can't be bound as arguments."
+                ));
+            }
+
+            self.command_buffer
+                .vulkan_retain_buffer(buffer.vulkan_resource());
+            self.vulkan_buffers.lock().unwrap()[index] =
+                Some((buffer.vulkan_resource().clone(), offset));
+            self.vulkan_bind_buffers()?;
+        }
+
+        Ok(())
+    }
+
+    /// Writes the buffers set so far to a new set 0 and binds it, once
+    /// there's a pipeline to take them.
+    #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+    fn vulkan_bind_buffers(&self) -> Result<()> {
+        let (pipeline_layout, descriptor_set_layout) = {
+            let layout = self.vulkan_pipeline_layout.lock().unwrap();
+
+            match layout.as_ref() {
+                Some(layout) => (
+                    layout.pipeline_layout(),
+                    layout.descriptor_set_layout(0).unwrap(),
+                ),
+                None => return Ok(()),
+            }
+        };
+
+        let buffers = self.vulkan_buffers.lock().unwrap();
+        if buffers.iter().all(Option::is_none) {
+            return Ok(());
+        }
+
+        let descriptor_set = self
+            .command_buffer
+            .vulkan_allocate_descriptor_set(descriptor_set_layout)?;
+
+        let buffer_infos = vulkan_buffer_infos(&buffers);
+        let writes = vulkan_buffer_writes(descriptor_set, &buffer_infos).collect::<Vec<_>>();
+
+        let device = self.command_buffer.queue.device.vulkan_device().logical();
+
+        unsafe {
+            device.update_descriptor_sets(&writes, &[]);
+            device.cmd_bind_descriptor_sets(
+                self.command_buffer.vulkan_command_buffer,
+                vk::PipelineBindPoint::COMPUTE,
+                pipeline_layout,
+                0,
+                &[descriptor_set],
+                &[],
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Vulkan kernels only run with the threadgroup size they were
+    /// translated for, see `MTLComputePipelineState`.
+    pub fn dispatch_threadgroups(
+        &self,
+        threadgroups: MTLSize,
+        threads_per_threadgroup: MTLSize,
+    ) -> Result<()> {
+        #[cfg(all(any(target_os = "macos", target_os = "ios"), not(feature = "moltenvk")))]
+        {
+            self.metal_compute_command_encoder
+                .dispatchThreadgroups_threadsPerThreadgroup(
+                    threadgroups.to_metal(),
+                    threads_per_threadgroup.to_metal(),
+                );
+        }
+
+        #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+        {
+            let translated = self
+                .vulkan_threads_per_threadgroup
+                .lock()
+                .unwrap()
+                .ok_or(anyhow!(
+                    "Threadgroups are dispatched after the compute pipeline state."
+                ))?;
+
+            if threads_per_threadgroup != translated {
+                return Err(anyhow!(
+                    "The kernel was translated for {:?} threads per threadgroup, not {:?}.",
+                    translated,
+                    threads_per_threadgroup
+                ));
+            }
+
+            unsafe {
+                self.command_buffer
+                    .queue
+                    .device
+                    .vulkan_device()
+                    .logical()
+                    .cmd_dispatch(
+                        self.command_buffer.vulkan_command_buffer,
+                        threadgroups.width,
+                        threadgroups.height,
+                        threadgroups.depth,
+                    );
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Size of the pools `vulkan_allocate_descriptor_set` makes.
+#[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+const DESCRIPTOR_SETS_PER_POOL: u32 = 64;
+#[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+const DESCRIPTORS_PER_POOL: u32 = 256;
+
 #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
 pub struct VulkanMTLCommandQueue {
     graphics_queue: vk::Queue,
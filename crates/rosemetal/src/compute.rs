@@ -0,0 +1,131 @@
+use std::sync::Arc;
+
+use crate::{MTLDevice, shader::MTLFunction};
+
+#[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+use crate::{MTLFunctionType, MTLLibrary, MTLSize, VulkanPipelineLayout};
+#[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+use anyhow::{Result, anyhow};
+#[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+use ash::vk;
+#[cfg(all(any(target_os = "macos", target_os = "ios"), not(feature = "moltenvk")))]
+use objc2::{rc::Retained, runtime::ProtocolObject};
+#[cfg(all(any(target_os = "macos", target_os = "ios"), not(feature = "moltenvk")))]
+use objc2_metal::MTLComputePipelineState as MetalMTLComputePipelineState;
+
+pub struct MTLComputePipelineState {
+    device: Arc<MTLDevice>,
+    function: MTLFunction,
+
+    #[cfg(all(any(target_os = "macos", target_os = "ios"), not(feature = "moltenvk")))]
+    native_mtl_state: Retained<ProtocolObject<dyn MetalMTLComputePipelineState>>,
+
+    #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+    vulkan_pipeline_layout: VulkanPipelineLayout,
+    #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+    vulkan_pipeline: vk::Pipeline,
+    /// Vulkan bakes the threadgroup size into the kernel, see
+    /// `AirToSpirVOptions::threads_per_threadgroup`.
+    #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+    vulkan_threads_per_threadgroup: MTLSize,
+}
+
+impl MTLComputePipelineState {
+    #[cfg(all(any(target_os = "macos", target_os = "ios"), not(feature = "moltenvk")))]
+    pub fn from_metal(
+        device: Arc<MTLDevice>,
+        function: MTLFunction,
+        native_mtl_state: Retained<ProtocolObject<dyn MetalMTLComputePipelineState>>,
+    ) -> Self {
+        Self {
+            device,
+            function,
+            native_mtl_state,
+        }
+    }
+
+    #[cfg(all(any(target_os = "macos", target_os = "ios"), not(feature = "moltenvk")))]
+    pub fn metal_state(&self) -> &Retained<ProtocolObject<dyn MetalMTLComputePipelineState>> {
+        &self.native_mtl_state
+    }
+
+    #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+    pub fn from_vulkan(device: Arc<MTLDevice>, function: MTLFunction) -> Result<Self> {
+        if !matches!(function.function_type(), MTLFunctionType::Kernel) {
+            return Err(anyhow!("`{}` isn't a kernel.", function.name()));
+        }
+
+        let vulkan_pipeline_layout = VulkanPipelineLayout::new(
+            &device,
+            [(&function, vk::ShaderStageFlags::COMPUTE)].into_iter(),
+        )?;
+
+        let create_info = vk::ComputePipelineCreateInfo::default()
+            .stage(function.vulkan_pipeline_stage_create_info())
+            .layout(vulkan_pipeline_layout.pipeline_layout());
+
+        let pipeline = unsafe {
+            device.vulkan_device().logical().create_compute_pipelines(
+                vk::PipelineCache::null(),
+                &[create_info],
+                None,
+            )
+        };
+
+        let vulkan_pipeline = match pipeline {
+            Ok(x) => x[0],
+            Err((_, error)) => {
+                vulkan_pipeline_layout.destroy(&device);
+                return Err(error.into());
+            }
+        };
+
+        let [width, height, depth] =
+            MTLLibrary::vulkan_translation_options(&device).threads_per_threadgroup;
+
+        Ok(Self {
+            device,
+            function,
+            vulkan_pipeline_layout,
+            vulkan_pipeline,
+            vulkan_threads_per_threadgroup: MTLSize::new(width, height, depth),
+        })
+    }
+
+    #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+    pub fn vulkan_pipeline(&self) -> vk::Pipeline {
+        self.vulkan_pipeline
+    }
+
+    #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+    pub fn vulkan_pipeline_layout(&self) -> &VulkanPipelineLayout {
+        &self.vulkan_pipeline_layout
+    }
+
+    #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+    pub fn vulkan_threads_per_threadgroup(&self) -> MTLSize {
+        self.vulkan_threads_per_threadgroup
+    }
+
+    pub fn device(&self) -> &Arc<MTLDevice> {
+        &self.device
+    }
+
+    pub fn function(&self) -> &MTLFunction {
+        &self.function
+    }
+}
+
+#[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+impl Drop for MTLComputePipelineState {
+    fn drop(&mut self) {
+        unsafe {
+            self.device
+                .vulkan_device()
+                .logical()
+                .destroy_pipeline(self.vulkan_pipeline, None);
+        }
+
+        self.vulkan_pipeline_layout.destroy(&self.device);
+    }
+}
@@ -0,0 +1,300 @@
+use crate::{MTLCompareFunction, MTLDevice};
+use anyhow::Result;
+
+#[cfg(all(any(target_os = "macos", target_os = "ios"), not(feature = "moltenvk")))]
+use anyhow::anyhow;
+#[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+use ash::vk;
+#[cfg(all(any(target_os = "macos", target_os = "ios"), not(feature = "moltenvk")))]
+use objc2::{rc::Retained, runtime::ProtocolObject};
+#[cfg(all(any(target_os = "macos", target_os = "ios"), not(feature = "moltenvk")))]
+use objc2_metal::{
+    MTLDepthStencilDescriptor as MetalMTLDepthStencilDescriptor,
+    MTLDepthStencilState as MetalMTLDepthStencilState, MTLDevice as MetalMTLDevice,
+    MTLStencilDescriptor as MetalMTLStencilDescriptor,
+    MTLStencilOperation as MetalMTLStencilOperation,
+};
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum MTLStencilOperation {
+    #[default]
+    Keep,
+    Zero,
+    Replace,
+    IncrementClamp,
+    DecrementClamp,
+    Invert,
+    IncrementWrap,
+    DecrementWrap,
+}
+
+impl MTLStencilOperation {
+    #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+    pub fn to_vulkan(self) -> vk::StencilOp {
+        match self {
+            Self::Keep => vk::StencilOp::KEEP,
+            Self::Zero => vk::StencilOp::ZERO,
+            Self::Replace => vk::StencilOp::REPLACE,
+            Self::IncrementClamp => vk::StencilOp::INCREMENT_AND_CLAMP,
+            Self::DecrementClamp => vk::StencilOp::DECREMENT_AND_CLAMP,
+            Self::Invert => vk::StencilOp::INVERT,
+            Self::IncrementWrap => vk::StencilOp::INCREMENT_AND_WRAP,
+            Self::DecrementWrap => vk::StencilOp::DECREMENT_AND_WRAP,
+        }
+    }
+
+    #[cfg(all(any(target_os = "macos", target_os = "ios"), not(feature = "moltenvk")))]
+    pub fn to_metal(self) -> MetalMTLStencilOperation {
+        match self {
+            Self::Keep => MetalMTLStencilOperation::Keep,
+            Self::Zero => MetalMTLStencilOperation::Zero,
+            Self::Replace => MetalMTLStencilOperation::Replace,
+            Self::IncrementClamp => MetalMTLStencilOperation::IncrementClamp,
+            Self::DecrementClamp => MetalMTLStencilOperation::DecrementClamp,
+            Self::Invert => MetalMTLStencilOperation::Invert,
+            Self::IncrementWrap => MetalMTLStencilOperation::IncrementWrap,
+            Self::DecrementWrap => MetalMTLStencilOperation::DecrementWrap,
+        }
+    }
+}
+
+/// Stencil test of one face.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MTLStencilDescriptor {
+    pub stencil_compare_function: MTLCompareFunction,
+    /// Stencil test failed.
+    pub stencil_failure_operation: MTLStencilOperation,
+    /// Stencil test passed, depth test failed.
+    pub depth_failure_operation: MTLStencilOperation,
+    /// Both tests passed.
+    pub depth_stencil_pass_operation: MTLStencilOperation,
+    pub read_mask: u32,
+    pub write_mask: u32,
+}
+
+impl Default for MTLStencilDescriptor {
+    fn default() -> Self {
+        Self {
+            stencil_compare_function: MTLCompareFunction::Always,
+            stencil_failure_operation: MTLStencilOperation::Keep,
+            depth_failure_operation: MTLStencilOperation::Keep,
+            depth_stencil_pass_operation: MTLStencilOperation::Keep,
+            read_mask: u32::MAX,
+            write_mask: u32::MAX,
+        }
+    }
+}
+
+impl MTLStencilDescriptor {
+    /// The reference value is set on the encoder, it's dynamic state in
+    /// every pipeline.
+    #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+    pub fn to_vulkan(&self) -> vk::StencilOpState {
+        vk::StencilOpState::default()
+            .fail_op(self.stencil_failure_operation.to_vulkan())
+            .pass_op(self.depth_stencil_pass_operation.to_vulkan())
+            .depth_fail_op(self.depth_failure_operation.to_vulkan())
+            .compare_op(self.stencil_compare_function.to_vulkan())
+            .compare_mask(self.read_mask)
+            .write_mask(self.write_mask)
+    }
+
+    #[cfg(all(any(target_os = "macos", target_os = "ios"), not(feature = "moltenvk")))]
+    pub fn to_metal(&self) -> Retained<MetalMTLStencilDescriptor> {
+        let descriptor = MetalMTLStencilDescriptor::new();
+
+        descriptor.setStencilCompareFunction(self.stencil_compare_function.to_metal());
+        descriptor.setStencilFailureOperation(self.stencil_failure_operation.to_metal());
+        descriptor.setDepthFailureOperation(self.depth_failure_operation.to_metal());
+        descriptor.setDepthStencilPassOperation(self.depth_stencil_pass_operation.to_metal());
+        descriptor.setReadMask(self.read_mask);
+        descriptor.setWriteMask(self.write_mask);
+
+        descriptor
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MTLDepthStencilDescriptor {
+    /// `Always` with writes disabled turns the depth test off.
+    pub depth_compare_function: MTLCompareFunction,
+    pub depth_write_enabled: bool,
+    /// `None` on both faces turns the stencil test off.
+    pub front_face_stencil: Option<MTLStencilDescriptor>,
+    pub back_face_stencil: Option<MTLStencilDescriptor>,
+}
+
+impl Default for MTLDepthStencilDescriptor {
+    fn default() -> Self {
+        Self {
+            depth_compare_function: MTLCompareFunction::Always,
+            depth_write_enabled: false,
+            front_face_stencil: None,
+            back_face_stencil: None,
+        }
+    }
+}
+
+impl MTLDepthStencilDescriptor {
+    pub fn is_depth_test_enabled(&self) -> bool {
+        self.depth_compare_function != MTLCompareFunction::Always || self.depth_write_enabled
+    }
+
+    pub fn is_stencil_test_enabled(&self) -> bool {
+        self.front_face_stencil.is_some() || self.back_face_stencil.is_some()
+    }
+
+    /// A face without a stencil descriptor keeps whatever is in the
+    /// attachment, like Metal does.
+    #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+    pub fn to_vulkan(&self) -> vk::PipelineDepthStencilStateCreateInfo<'static> {
+        let front = self.front_face_stencil.unwrap_or_default();
+        let back = self.back_face_stencil.unwrap_or_default();
+
+        vk::PipelineDepthStencilStateCreateInfo::default()
+            .depth_test_enable(self.is_depth_test_enabled())
+            .depth_write_enable(self.depth_write_enabled)
+            .depth_compare_op(self.depth_compare_function.to_vulkan())
+            .stencil_test_enable(self.is_stencil_test_enabled())
+            .front(front.to_vulkan())
+            .back(back.to_vulkan())
+    }
+
+    #[cfg(all(any(target_os = "macos", target_os = "ios"), not(feature = "moltenvk")))]
+    pub fn to_metal(&self) -> Retained<MetalMTLDepthStencilDescriptor> {
+        let descriptor = MetalMTLDepthStencilDescriptor::new();
+
+        descriptor.setDepthCompareFunction(self.depth_compare_function.to_metal());
+        descriptor.setDepthWriteEnabled(self.depth_write_enabled);
+        descriptor.setFrontFaceStencil(self.front_face_stencil.map(|x| x.to_metal()).as_deref());
+        descriptor.setBackFaceStencil(self.back_face_stencil.map(|x| x.to_metal()).as_deref());
+
+        descriptor
+    }
+}
+
+/// Depth and stencil tests for draws. On Vulkan it's applied with dynamic
+/// state when the device has `VK_EXT_extended_dynamic_state`, otherwise
+/// pipelines have to be created with `to_vulkan` of its descriptor.
+pub struct MTLDepthStencilState {
+    descriptor: MTLDepthStencilDescriptor,
+
+    #[cfg(all(any(target_os = "macos", target_os = "ios"), not(feature = "moltenvk")))]
+    metal_depth_stencil_state: Retained<ProtocolObject<dyn MetalMTLDepthStencilState>>,
+}
+
+impl MTLDepthStencilState {
+    pub fn new(device: &MTLDevice, descriptor: MTLDepthStencilDescriptor) -> Result<Self> {
+        #[cfg(all(any(target_os = "macos", target_os = "ios"), not(feature = "moltenvk")))]
+        return Self::metal_new(device, descriptor);
+
+        #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+        return Self::vulkan_new(device, descriptor);
+    }
+
+    #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+    pub fn vulkan_new(_device: &MTLDevice, descriptor: MTLDepthStencilDescriptor) -> Result<Self> {
+        Ok(Self { descriptor })
+    }
+
+    #[cfg(all(any(target_os = "macos", target_os = "ios"), not(feature = "moltenvk")))]
+    pub fn metal_new(device: &MTLDevice, descriptor: MTLDepthStencilDescriptor) -> Result<Self> {
+        let metal_depth_stencil_state = device
+            .metal_device()
+            .newDepthStencilStateWithDescriptor(&descriptor.to_metal())
+            .ok_or(anyhow!("Depth Stencil State creation failed."))?;
+
+        Ok(Self {
+            descriptor,
+            metal_depth_stencil_state,
+        })
+    }
+
+    pub fn descriptor(&self) -> &MTLDepthStencilDescriptor {
+        &self.descriptor
+    }
+
+    #[cfg(all(any(target_os = "macos", target_os = "ios"), not(feature = "moltenvk")))]
+    pub fn metal_depth_stencil_state(
+        &self,
+    ) -> &Retained<ProtocolObject<dyn MetalMTLDepthStencilState>> {
+        &self.metal_depth_stencil_state
+    }
+
+    /// States pipelines leave to the encoder. The stencil reference and
+    /// masks are core Vulkan, the rest needs `VK_EXT_extended_dynamic_state`.
+    #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+    pub fn vulkan_dynamic_states(extended_dynamic_state: bool) -> Vec<vk::DynamicState> {
+        let mut result = vec![
+            vk::DynamicState::STENCIL_REFERENCE,
+            vk::DynamicState::STENCIL_COMPARE_MASK,
+            vk::DynamicState::STENCIL_WRITE_MASK,
+        ];
+
+        if extended_dynamic_state {
+            result.extend_from_slice(&[
+                vk::DynamicState::DEPTH_TEST_ENABLE_EXT,
+                vk::DynamicState::DEPTH_WRITE_ENABLE_EXT,
+                vk::DynamicState::DEPTH_COMPARE_OP_EXT,
+                vk::DynamicState::STENCIL_TEST_ENABLE_EXT,
+                vk::DynamicState::STENCIL_OP_EXT,
+            ]);
+        }
+
+        result
+    }
+
+    /// Records the state into `command_buffer`. Without extended dynamic
+    /// state only the masks can change, the rest comes from the pipeline.
+    #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+    pub fn vulkan_record(&self, device: &MTLDevice, command_buffer: vk::CommandBuffer) {
+        let vulkan_device = device.vulkan_device();
+        let logical = vulkan_device.logical();
+
+        let front = self.descriptor.front_face_stencil.unwrap_or_default();
+        let back = self.descriptor.back_face_stencil.unwrap_or_default();
+
+        unsafe {
+            for (face, stencil) in [
+                (vk::StencilFaceFlags::FRONT, front),
+                (vk::StencilFaceFlags::BACK, back),
+            ] {
+                logical.cmd_set_stencil_compare_mask(command_buffer, face, stencil.read_mask);
+                logical.cmd_set_stencil_write_mask(command_buffer, face, stencil.write_mask);
+            }
+        }
+
+        let Some(extended_dynamic_state) = vulkan_device.extended_dynamic_state() else {
+            return;
+        };
+
+        let descriptor = &self.descriptor;
+
+        unsafe {
+            extended_dynamic_state
+                .cmd_set_depth_test_enable(command_buffer, descriptor.is_depth_test_enabled());
+            extended_dynamic_state
+                .cmd_set_depth_write_enable(command_buffer, descriptor.depth_write_enabled);
+            extended_dynamic_state.cmd_set_depth_compare_op(
+                command_buffer,
+                descriptor.depth_compare_function.to_vulkan(),
+            );
+            extended_dynamic_state
+                .cmd_set_stencil_test_enable(command_buffer, descriptor.is_stencil_test_enabled());
+
+            for (face, stencil) in [
+                (vk::StencilFaceFlags::FRONT, front),
+                (vk::StencilFaceFlags::BACK, back),
+            ] {
+                extended_dynamic_state.cmd_set_stencil_op(
+                    command_buffer,
+                    face,
+                    stencil.stencil_failure_operation.to_vulkan(),
+                    stencil.depth_stencil_pass_operation.to_vulkan(),
+                    stencil.depth_failure_operation.to_vulkan(),
+                    stencil.stencil_compare_function.to_vulkan(),
+                );
+            }
+        }
+    }
+}
@@ -1,7 +1,12 @@
 use crate::{
-    MTLBufferUsage, MTLRenderPass, MTLRenderPipelineDescriptor, MTLRenderPipelineState, MTLView,
-    RMLInstance, buffer::MTLBuffer, shader::MTLLibrary,
+    MTLBufferUsage, MTLComputePipelineState, MTLDepthStencilDescriptor, MTLDepthStencilState,
+    MTLRenderPass, MTLRenderPipelineDescriptor, MTLRenderPipelineState, MTLResourceOptions,
+    MTLSamplerDescriptor, MTLSamplerState, MTLTexture, MTLTextureDescriptor, MTLView, RMLInstance,
+    buffer::MTLBuffer,
+    shader::{MTLFunction, MTLLibrary},
 };
+#[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+use crate::{MTLStorageMode, VulkanAllocation, VulkanAllocator, buffer::VulkanMTLBuffer};
 use anyhow::{Result, anyhow};
 #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
 use ash::vk;
@@ -9,9 +14,9 @@ use ash::vk;
 use std::{
     cell::RefCell,
     collections::BTreeMap,
-    sync::{RwLock, atomic::AtomicU32},
+    sync::{Mutex, RwLock, atomic::AtomicU32},
 };
-use std::{ffi::CStr, ptr::NonNull, sync::atomic::Ordering};
+use std::{ffi::CStr, ops::Range, ptr::NonNull, sync::atomic::Ordering};
 use std::{ffi::c_void, sync::Arc};
 
 #[cfg(all(any(target_os = "macos", target_os = "ios"), not(feature = "moltenvk")))]
@@ -32,28 +37,48 @@ pub struct MTLDevice {
 }
 
 pub trait ArcMTLDevice {
-    fn make_buffer<T>(&self, data: &[T], usage: MTLBufferUsage) -> Result<Arc<MTLBuffer<T>>>;
+    fn make_buffer<T>(
+        &self,
+        data: &[T],
+        usage: MTLBufferUsage,
+        options: MTLResourceOptions,
+    ) -> Result<Arc<MTLBuffer<T>>>;
+    fn new_texture(&self, descriptor: MTLTextureDescriptor) -> Result<Arc<MTLTexture>>;
     fn new_render_pipeline_state(
         &self,
         render_pipeline_descriptor: MTLRenderPipelineDescriptor,
-    ) -> Result<MTLRenderPipelineState>;
+    ) -> Result<Arc<MTLRenderPipelineState>>;
+    fn new_compute_pipeline_state(&self, function: MTLFunction) -> Result<MTLComputePipelineState>;
 
     #[cfg(all(any(target_os = "macos", target_os = "ios"), not(feature = "moltenvk")))]
-    fn metal_make_buffer<T>(&self, data: &[T]) -> Result<Arc<MTLBuffer>>;
+    fn metal_make_buffer<T>(
+        &self,
+        data: &[T],
+        options: MTLResourceOptions,
+    ) -> Result<Arc<MTLBuffer<T>>>;
 
     #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
-    fn vulkan_make_buffer<T>(&self, data: &[T], usage: MTLBufferUsage)
-    -> Result<Arc<MTLBuffer<T>>>;
+    fn vulkan_make_buffer<T>(
+        &self,
+        data: &[T],
+        usage: MTLBufferUsage,
+        options: MTLResourceOptions,
+    ) -> Result<Arc<MTLBuffer<T>>>;
 }
 
 impl ArcMTLDevice for Arc<MTLDevice> {
     #[allow(unused_variables)]
-    fn make_buffer<T>(&self, data: &[T], usage: MTLBufferUsage) -> Result<Arc<MTLBuffer<T>>> {
+    fn make_buffer<T>(
+        &self,
+        data: &[T],
+        usage: MTLBufferUsage,
+        options: MTLResourceOptions,
+    ) -> Result<Arc<MTLBuffer<T>>> {
         #[cfg(all(any(target_os = "macos", target_os = "ios"), not(feature = "moltenvk")))]
-        return self.metal_make_buffer(data);
+        return self.metal_make_buffer(data, options);
 
         #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
-        return self.vulkan_make_buffer(data, usage);
+        return self.vulkan_make_buffer(data, usage, options);
     }
 
     #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
@@ -61,26 +86,57 @@ impl ArcMTLDevice for Arc<MTLDevice> {
         &self,
         data: &[T],
         usage: MTLBufferUsage,
+        options: MTLResourceOptions,
     ) -> Result<Arc<MTLBuffer<T>>> {
-        let device = self.vulkan_device().logical();
+        if options.storage_mode == MTLStorageMode::Memoryless {
+            return Err(anyhow!("Buffers can't use memoryless storage."));
+        }
 
-        let buffer = unsafe {
-            device.create_buffer(
-                &vk::BufferCreateInfo::default()
-                    .usage(usage.to_vulkan())
-                    .size((size_of::<T>() * data.len()) as u64)
-                    .sharing_mode(vk::SharingMode::EXCLUSIVE),
-                None,
-            )?
+        let length = size_of_val(data);
+
+        // Memory the CPU can't see is filled from a staging buffer.
+        let upload_usage = match options.is_host_visible() {
+            true => vk::BufferUsageFlags::empty(),
+            false => vk::BufferUsageFlags::TRANSFER_DST,
         };
 
-        todo!()
+        let (buffer, allocation) = self.vulkan_device().create_buffer(
+            length as u64,
+            usage.to_vulkan() | upload_usage,
+            options,
+        )?;
+        let mapped = allocation.mapped();
+
+        let buffer = MTLBuffer::from_vulkan(
+            self.clone(),
+            VulkanMTLBuffer::new(self.clone(), buffer, allocation, length, usage),
+            options,
+        );
+
+        let bytes = unsafe { std::slice::from_raw_parts(data.as_ptr() as *const u8, length) };
+
+        match mapped.is_null() {
+            true => self
+                .vulkan_device()
+                .upload_to_buffer(*buffer.vulkan_buffer(), bytes)?,
+            false => {
+                unsafe {
+                    std::ptr::copy_nonoverlapping(bytes.as_ptr(), mapped as *mut u8, length);
+                }
+                self.vulkan_device()
+                    .flush(buffer.vulkan_allocation(), 0..length as u64)?;
+            }
+        }
+
+        Ok(Arc::new(buffer))
     }
 
     #[cfg(all(any(target_os = "macos", target_os = "ios"), not(feature = "moltenvk")))]
-    fn metal_make_buffer<T>(&self, data: &[T]) -> Result<Arc<MTLBuffer>> {
-        use objc2_metal::MTLResourceOptions;
-
+    fn metal_make_buffer<T>(
+        &self,
+        data: &[T],
+        options: MTLResourceOptions,
+    ) -> Result<Arc<MTLBuffer<T>>> {
         let data_ptr = data.as_ptr();
         let data_non_null = unsafe { NonNull::new_unchecked(data_ptr as *mut c_void) };
 
@@ -88,37 +144,67 @@ impl ArcMTLDevice for Arc<MTLDevice> {
             self.metal_device().newBufferWithBytes_length_options(
                 data_non_null,
                 size_of::<T>() * data.len(),
-                MTLResourceOptions::StorageModeShared,
+                options.to_metal(),
             )
         }
         .unwrap();
 
-        Ok(Arc::new(MTLBuffer::from_metal(self.clone(), metal_buffer)))
+        Ok(Arc::new(MTLBuffer::from_metal(
+            self.clone(),
+            metal_buffer,
+            options,
+        )))
+    }
+
+    fn new_texture(&self, descriptor: MTLTextureDescriptor) -> Result<Arc<MTLTexture>> {
+        MTLTexture::new(self.clone(), descriptor)
     }
 
     fn new_render_pipeline_state(
         &self,
         render_pipeline_descriptor: MTLRenderPipelineDescriptor,
-    ) -> Result<MTLRenderPipelineState> {
+    ) -> Result<Arc<MTLRenderPipelineState>> {
+        if let Some(vertex_descriptor) = &render_pipeline_descriptor.vertex_descriptor {
+            vertex_descriptor.validate()?;
+        }
+
         #[cfg(all(any(target_os = "macos", target_os = "ios"), not(feature = "moltenvk")))]
         {
-            return Ok(MTLRenderPipelineState::from_metal(
+            return Ok(Arc::new(MTLRenderPipelineState::from_metal(
                 self.clone(),
                 render_pipeline_descriptor,
                 self.metal_device()
                     .newRenderPipelineStateWithDescriptor_error(
                         &render_pipeline_descriptor.to_metal(),
                     )?,
-            ));
+            )));
         }
 
         #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
         {
-            return Ok(MTLRenderPipelineState::from_vulkan(
+            return Ok(Arc::new(MTLRenderPipelineState::from_vulkan(
                 self.clone(),
                 render_pipeline_descriptor,
+            )?));
+        }
+    }
+
+    fn new_compute_pipeline_state(&self, function: MTLFunction) -> Result<MTLComputePipelineState> {
+        #[cfg(all(any(target_os = "macos", target_os = "ios"), not(feature = "moltenvk")))]
+        {
+            let native_mtl_state = self
+                .metal_device()
+                .newComputePipelineStateWithFunction_error(function.to_metal())?;
+
+            return Ok(MTLComputePipelineState::from_metal(
+                self.clone(),
+                function,
+                native_mtl_state,
             ));
         }
+
+        #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+        return MTLComputePipelineState::from_vulkan(self.clone(), function);
     }
 }
 
@@ -154,8 +240,31 @@ impl MTLDevice {
                 .to_string()
         };
 
-        let logical_device =
-            Self::vulkan_create_logical_device(&instance, &physical_device, &queue_families)?;
+        let memory_properties = unsafe {
+            instance
+                .vulkan_instance()
+                .get_physical_device_memory_properties(physical_device)
+        };
+
+        let features = Self::vulkan_device_features(&instance, &physical_device, &properties)?;
+
+        let logical_device = Self::vulkan_create_logical_device(
+            &instance,
+            &physical_device,
+            &queue_families,
+            &features,
+        )?;
+
+        let transfer_queue =
+            unsafe { logical_device.get_device_queue(queue_families.graphics_queue, 0) };
+
+        let extended_dynamic_state = match features.extended_dynamic_state {
+            true => Some(ash::ext::extended_dynamic_state::Device::new(
+                instance.vulkan_instance(),
+                &logical_device,
+            )),
+            false => None,
+        };
 
         Ok(Arc::new(Self {
             name,
@@ -164,6 +273,12 @@ impl MTLDevice {
                 physical_device,
                 logical_device,
                 queue_families,
+                allocator: VulkanAllocator::new(memory_properties),
+                non_coherent_atom_size: properties.limits.non_coherent_atom_size,
+                transfer_queue: Mutex::new(transfer_queue),
+                features,
+                extended_dynamic_state,
+                samplers: Mutex::new(vec![]),
             },
         }))
     }
@@ -173,6 +288,7 @@ impl MTLDevice {
         instance: &Arc<RMLInstance>,
         device: &vk::PhysicalDevice,
         queue_families: &VulkanQueueFamilies,
+        features: &VulkanDeviceFeatures,
     ) -> Result<ash::Device> {
         let mut indices = vec![queue_families.graphics_queue, queue_families.present_queue];
         indices.dedup();
@@ -186,14 +302,50 @@ impl MTLDevice {
             })
             .collect::<Vec<_>>();
 
-        let device_extensions = Self::vulkan_required_extensions()
+        let mut device_extensions = Self::vulkan_required_extensions()
             .iter()
             .map(|ext| ext.as_ptr())
             .collect::<Vec<_>>();
 
-        let device_create_info = vk::DeviceCreateInfo::default()
+        if features.sampler_mirror_clamp_to_edge {
+            device_extensions.push(ash::khr::sampler_mirror_clamp_to_edge::NAME.as_ptr());
+        }
+        if features.extended_dynamic_state {
+            device_extensions.push(ash::ext::extended_dynamic_state::NAME.as_ptr());
+        }
+        if features.vertex_attribute_divisor {
+            device_extensions.push(ash::ext::vertex_attribute_divisor::NAME.as_ptr());
+        }
+        if features.scalar_block_layout {
+            device_extensions.push(ash::ext::scalar_block_layout::NAME.as_ptr());
+        }
+
+        let enabled_features = vk::PhysicalDeviceFeatures::default()
+            .sampler_anisotropy(features.max_sampler_anisotropy > 1.0);
+        let mut extended_dynamic_state_features =
+            vk::PhysicalDeviceExtendedDynamicStateFeaturesEXT::default()
+                .extended_dynamic_state(true);
+        let mut vertex_attribute_divisor_features =
+            vk::PhysicalDeviceVertexAttributeDivisorFeaturesEXT::default()
+                .vertex_attribute_instance_rate_divisor(true);
+        let mut scalar_block_layout_features =
+            vk::PhysicalDeviceScalarBlockLayoutFeaturesEXT::default().scalar_block_layout(true);
+
+        let mut device_create_info = vk::DeviceCreateInfo::default()
             .queue_create_infos(&queue_info)
-            .enabled_extension_names(&device_extensions);
+            .enabled_extension_names(&device_extensions)
+            .enabled_features(&enabled_features);
+
+        if features.extended_dynamic_state {
+            device_create_info = device_create_info.push_next(&mut extended_dynamic_state_features);
+        }
+        if features.vertex_attribute_divisor {
+            device_create_info =
+                device_create_info.push_next(&mut vertex_attribute_divisor_features);
+        }
+        if features.scalar_block_layout {
+            device_create_info = device_create_info.push_next(&mut scalar_block_layout_features);
+        }
 
         Ok(unsafe {
             instance
@@ -202,6 +354,101 @@ impl MTLDevice {
         })
     }
 
+    /// Optional features rosemetal turns on when the device has them.
+    #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+    pub fn vulkan_device_features(
+        instance: &Arc<RMLInstance>,
+        device: &vk::PhysicalDevice,
+        properties: &vk::PhysicalDeviceProperties,
+    ) -> Result<VulkanDeviceFeatures> {
+        let supported = unsafe {
+            instance
+                .vulkan_instance()
+                .get_physical_device_features(*device)
+        };
+
+        let extension_properties = unsafe {
+            instance
+                .vulkan_instance()
+                .enumerate_device_extension_properties(*device)?
+        };
+
+        let has_extension = |extension: &CStr| {
+            extension_properties.iter().any(|ext| {
+                let name = unsafe { CStr::from_ptr(ext.extension_name.as_ptr()) };
+                name == extension
+            })
+        };
+
+        // The extension only exposes the feature, the device may still not
+        // support instance rate divisors.
+        let (vertex_attribute_divisor, max_vertex_attrib_divisor) = match instance
+            .vulkan_properties2()
+            .filter(|_| has_extension(ash::ext::vertex_attribute_divisor::NAME))
+        {
+            Some(properties2) => {
+                let mut divisor_features =
+                    vk::PhysicalDeviceVertexAttributeDivisorFeaturesEXT::default();
+                let mut divisor_properties =
+                    vk::PhysicalDeviceVertexAttributeDivisorPropertiesEXT::default();
+
+                unsafe {
+                    properties2.get_physical_device_features2(
+                        *device,
+                        &mut vk::PhysicalDeviceFeatures2::default()
+                            .push_next(&mut divisor_features),
+                    );
+                    properties2.get_physical_device_properties2(
+                        *device,
+                        &mut vk::PhysicalDeviceProperties2::default()
+                            .push_next(&mut divisor_properties),
+                    );
+                }
+
+                (
+                    divisor_features.vertex_attribute_instance_rate_divisor == vk::TRUE,
+                    divisor_properties.max_vertex_attrib_divisor,
+                )
+            }
+            None => (false, 1),
+        };
+
+        Ok(VulkanDeviceFeatures {
+            max_sampler_anisotropy: match supported.sampler_anisotropy == vk::TRUE {
+                true => properties.limits.max_sampler_anisotropy,
+                false => 1.0,
+            },
+            sampler_mirror_clamp_to_edge: has_extension(
+                ash::khr::sampler_mirror_clamp_to_edge::NAME,
+            ),
+            // The extension requires the feature, there's no need to query
+            // it through `vkGetPhysicalDeviceFeatures2`.
+            extended_dynamic_state: has_extension(ash::ext::extended_dynamic_state::NAME),
+            vertex_attribute_divisor,
+            max_vertex_attrib_divisor,
+            max_vertex_input_bindings: properties.limits.max_vertex_input_bindings,
+            // Same for scalar block layout.
+            scalar_block_layout: has_extension(ash::ext::scalar_block_layout::NAME),
+            // Every device renders to one of the combined formats.
+            stencil8_format: [
+                vk::Format::S8_UINT,
+                vk::Format::D24_UNORM_S8_UINT,
+                vk::Format::D32_SFLOAT_S8_UINT,
+            ]
+            .into_iter()
+            .find(|format| {
+                unsafe {
+                    instance
+                        .vulkan_instance()
+                        .get_physical_device_format_properties(*device, *format)
+                }
+                .optimal_tiling_features
+                .contains(vk::FormatFeatureFlags::DEPTH_STENCIL_ATTACHMENT)
+            })
+            .unwrap_or(vk::Format::D32_SFLOAT_S8_UINT),
+        })
+    }
+
     #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
     pub fn vulkan_device_check(
         instance: &Arc<RMLInstance>,
@@ -341,18 +588,57 @@ impl MTLDevice {
         &self.name
     }
 
+    /// Bytes of GPU memory allocated for the device's resources.
+    pub fn current_allocated_size(&self) -> usize {
+        #[cfg(all(any(target_os = "macos", target_os = "ios"), not(feature = "moltenvk")))]
+        return self.metal_device.currentAllocatedSize();
+
+        #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+        return self.vulkan_device.allocator.statistics().allocated_bytes as usize;
+    }
+
+    /// How much memory the device can use without hurting performance.
+    pub fn recommended_max_working_set_size(&self) -> u64 {
+        #[cfg(all(any(target_os = "macos", target_os = "ios"), not(feature = "moltenvk")))]
+        return self.metal_device.recommendedMaxWorkingSetSize();
+
+        #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+        return self.vulkan_device.allocator.device_local_size();
+    }
+
     pub fn new_library(&self, content: &[u8]) -> Result<Arc<MTLLibrary>> {
         #[cfg(all(any(target_os = "macos", target_os = "ios"), not(feature = "moltenvk")))]
         return self.metal_new_library(content);
 
         #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
-        todo!("Vulkan Support.")
+        return self.vulkan_new_library(content);
     }
 
     #[cfg(all(any(target_os = "macos", target_os = "ios"), not(feature = "moltenvk")))]
     pub fn metal_new_library(&self, content: &[u8]) -> Result<Arc<MTLLibrary>> {
         Ok(Arc::new(MTLLibrary::from_metal_lib(content, self)?))
     }
+
+    /// The library's AIR is translated to SPIR-V, input the translator
+    /// doesn't support yet is an error.
+    #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+    pub fn vulkan_new_library(&self, content: &[u8]) -> Result<Arc<MTLLibrary>> {
+        Ok(Arc::new(MTLLibrary::from_metal_lib(content, self)?))
+    }
+
+    pub fn new_sampler_state(
+        &self,
+        descriptor: MTLSamplerDescriptor,
+    ) -> Result<Arc<MTLSamplerState>> {
+        Ok(Arc::new(MTLSamplerState::new(self, descriptor)?))
+    }
+
+    pub fn new_depth_stencil_state(
+        &self,
+        descriptor: MTLDepthStencilDescriptor,
+    ) -> Result<Arc<MTLDepthStencilState>> {
+        Ok(Arc::new(MTLDepthStencilState::new(self, descriptor)?))
+    }
 }
 
 #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
@@ -360,6 +646,58 @@ pub struct VulkanMTLDevice {
     physical_device: vk::PhysicalDevice,
     logical_device: ash::Device,
     queue_families: VulkanQueueFamilies,
+    allocator: VulkanAllocator,
+    non_coherent_atom_size: vk::DeviceSize,
+    /// Internal queue for uploads and readbacks. It's from the graphics
+    /// family, so resources never have to change owners.
+    transfer_queue: Mutex<vk::Queue>,
+    features: VulkanDeviceFeatures,
+    extended_dynamic_state: Option<ash::ext::extended_dynamic_state::Device>,
+    /// Every sampler created so far, by the descriptor it was created from.
+    samplers: Mutex<Vec<(MTLSamplerDescriptor, vk::Sampler)>>,
+}
+
+/// Everything made from the device holds an `Arc` to it, so nothing is
+/// left using it by the time it's dropped.
+#[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+impl Drop for VulkanMTLDevice {
+    fn drop(&mut self) {
+        unsafe {
+            let _ = self.logical_device.device_wait_idle();
+
+            for (_, sampler) in self.samplers.get_mut().unwrap().drain(..) {
+                self.logical_device.destroy_sampler(sampler, None);
+            }
+        }
+
+        self.allocator.destroy(&self.logical_device);
+
+        unsafe {
+            self.logical_device.destroy_device(None);
+        }
+    }
+}
+
+#[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+#[derive(Debug, Clone, Copy)]
+pub struct VulkanDeviceFeatures {
+    /// 1 when anisotropic filtering isn't supported.
+    pub max_sampler_anisotropy: f32,
+    pub sampler_mirror_clamp_to_edge: bool,
+    /// Depth and stencil state can be set on the encoder.
+    pub extended_dynamic_state: bool,
+    /// Instanced vertex buffers can step at rates other than 1.
+    pub vertex_attribute_divisor: bool,
+    /// Largest step rate of an instanced vertex buffer, 1 without
+    /// `vertex_attribute_divisor`.
+    pub max_vertex_attrib_divisor: u32,
+    /// Vertex buffers are bound at indices below this.
+    pub max_vertex_input_bindings: u32,
+    /// Buffers can use Metal's packed layouts as they are.
+    pub scalar_block_layout: bool,
+    /// `S8_UINT` where the device can render to it, a combined depth
+    /// stencil format otherwise.
+    pub stencil8_format: vk::Format,
 }
 
 #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
@@ -375,6 +713,256 @@ impl VulkanMTLDevice {
     pub fn queue_families(&self) -> &VulkanQueueFamilies {
         &self.queue_families
     }
+
+    pub fn allocator(&self) -> &VulkanAllocator {
+        &self.allocator
+    }
+
+    pub fn non_coherent_atom_size(&self) -> vk::DeviceSize {
+        self.non_coherent_atom_size
+    }
+
+    pub fn features(&self) -> &VulkanDeviceFeatures {
+        &self.features
+    }
+
+    pub fn extended_dynamic_state(&self) -> Option<&ash::ext::extended_dynamic_state::Device> {
+        self.extended_dynamic_state.as_ref()
+    }
+
+    /// The sampler for `descriptor`, created the first time it's asked for.
+    pub fn sampler(&self, descriptor: &MTLSamplerDescriptor) -> Result<vk::Sampler> {
+        let mut samplers = self.samplers.lock().unwrap();
+
+        if let Some((_, sampler)) = samplers.iter().find(|(x, _)| x == descriptor) {
+            return Ok(*sampler);
+        }
+
+        if !self.features.sampler_mirror_clamp_to_edge
+            && [
+                descriptor.s_address_mode,
+                descriptor.t_address_mode,
+                descriptor.r_address_mode,
+            ]
+            .contains(&crate::MTLSamplerAddressMode::MirrorClampToEdge)
+        {
+            return Err(anyhow!(
+                "The device doesn't support `MirrorClampToEdge` samplers."
+            ));
+        }
+
+        let sampler = unsafe {
+            self.logical_device.create_sampler(
+                &descriptor.to_vulkan(self.features.max_sampler_anisotropy),
+                None,
+            )?
+        };
+        samplers.push((*descriptor, sampler));
+
+        Ok(sampler)
+    }
+
+    /// Creates a buffer of `size` bytes bound to memory picked for `options`.
+    pub fn create_buffer(
+        &self,
+        size: vk::DeviceSize,
+        usage: vk::BufferUsageFlags,
+        options: MTLResourceOptions,
+    ) -> Result<(vk::Buffer, VulkanAllocation)> {
+        let device = &self.logical_device;
+
+        let buffer = unsafe {
+            device.create_buffer(
+                &vk::BufferCreateInfo::default()
+                    .usage(usage)
+                    .size(size.max(1))
+                    .sharing_mode(vk::SharingMode::EXCLUSIVE),
+                None,
+            )?
+        };
+
+        let requirements = unsafe { device.get_buffer_memory_requirements(buffer) };
+        let (required, preferred) = options.to_vulkan();
+
+        let allocation =
+            match self
+                .allocator
+                .allocate(device, requirements, required, preferred, true)
+            {
+                Ok(allocation) => allocation,
+                Err(error) => {
+                    unsafe { device.destroy_buffer(buffer, None) };
+                    return Err(error);
+                }
+            };
+
+        if let Err(error) =
+            unsafe { device.bind_buffer_memory(buffer, allocation.memory(), allocation.offset()) }
+        {
+            unsafe { device.destroy_buffer(buffer, None) };
+            self.allocator.free(device, &allocation);
+            return Err(error.into());
+        }
+
+        Ok((buffer, allocation))
+    }
+
+    /// Makes CPU writes to `range` of `allocation` visible to the GPU, if
+    /// its memory isn't coherent.
+    pub fn flush(&self, allocation: &VulkanAllocation, range: Range<vk::DeviceSize>) -> Result<()> {
+        if let Some(range) = self.mapped_range(allocation, range) {
+            unsafe { self.logical_device.flush_mapped_memory_ranges(&[range])? };
+        }
+
+        Ok(())
+    }
+
+    /// Makes GPU writes to `range` of `allocation` visible to the CPU, if
+    /// its memory isn't coherent.
+    pub fn invalidate(
+        &self,
+        allocation: &VulkanAllocation,
+        range: Range<vk::DeviceSize>,
+    ) -> Result<()> {
+        if let Some(range) = self.mapped_range(allocation, range) {
+            unsafe {
+                self.logical_device
+                    .invalidate_mapped_memory_ranges(&[range])?
+            };
+        }
+
+        Ok(())
+    }
+
+    /// `range` of a non coherent `allocation`, aligned to
+    /// `nonCoherentAtomSize` unless it reaches the end of the memory.
+    fn mapped_range(
+        &self,
+        allocation: &VulkanAllocation,
+        range: Range<vk::DeviceSize>,
+    ) -> Option<vk::MappedMemoryRange<'static>> {
+        let coherent = self
+            .allocator
+            .memory_type_flags(allocation.memory_type())
+            .contains(vk::MemoryPropertyFlags::HOST_COHERENT);
+
+        if coherent || range.is_empty() {
+            return None;
+        }
+
+        let atom_size = self.non_coherent_atom_size;
+        let offset = (allocation.offset() + range.start) / atom_size * atom_size;
+        let end = (allocation.offset() + range.end).next_multiple_of(atom_size);
+        let size = match end >= allocation.memory_size() {
+            true => vk::WHOLE_SIZE,
+            false => end - offset,
+        };
+
+        Some(
+            vk::MappedMemoryRange::default()
+                .memory(allocation.memory())
+                .offset(offset)
+                .size(size),
+        )
+    }
+
+    /// Records commands with `record` and waits for the transfer queue to
+    /// run them.
+    pub fn submit_and_wait(&self, record: impl FnOnce(vk::CommandBuffer)) -> Result<()> {
+        let device = &self.logical_device;
+
+        let command_pool = unsafe {
+            device.create_command_pool(
+                &vk::CommandPoolCreateInfo::default()
+                    .flags(vk::CommandPoolCreateFlags::TRANSIENT)
+                    .queue_family_index(self.queue_families.graphics_queue),
+                None,
+            )?
+        };
+
+        let result = (|| -> Result<()> {
+            let command_buffer = unsafe {
+                device.allocate_command_buffers(
+                    &vk::CommandBufferAllocateInfo::default()
+                        .command_pool(command_pool)
+                        .level(vk::CommandBufferLevel::PRIMARY)
+                        .command_buffer_count(1),
+                )?[0]
+            };
+
+            unsafe {
+                device.begin_command_buffer(
+                    command_buffer,
+                    &vk::CommandBufferBeginInfo::default()
+                        .flags(vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT),
+                )?;
+            }
+
+            record(command_buffer);
+
+            unsafe {
+                device.end_command_buffer(command_buffer)?;
+
+                let fence = device.create_fence(&vk::FenceCreateInfo::default(), None)?;
+                let queue = self.transfer_queue.lock().unwrap();
+
+                let submitted = device
+                    .queue_submit(
+                        *queue,
+                        &[vk::SubmitInfo::default().command_buffers(&[command_buffer])],
+                        fence,
+                    )
+                    .and_then(|_| device.wait_for_fences(&[fence], true, u64::MAX));
+
+                device.destroy_fence(fence, None);
+                submitted?;
+            }
+
+            Ok(())
+        })();
+
+        unsafe { device.destroy_command_pool(command_pool, None) };
+
+        result
+    }
+
+    /// Copies `data` into the start of `buffer` through a staging buffer.
+    pub fn upload_to_buffer(&self, buffer: vk::Buffer, data: &[u8]) -> Result<()> {
+        if data.is_empty() {
+            return Ok(());
+        }
+
+        let (staging, allocation) = self.create_buffer(
+            data.len() as u64,
+            vk::BufferUsageFlags::TRANSFER_SRC,
+            MTLResourceOptions::default(),
+        )?;
+
+        let result = (|| -> Result<()> {
+            unsafe {
+                std::ptr::copy_nonoverlapping(
+                    data.as_ptr(),
+                    allocation.mapped() as *mut u8,
+                    data.len(),
+                );
+            }
+            self.flush(&allocation, 0..data.len() as u64)?;
+
+            self.submit_and_wait(|command_buffer| unsafe {
+                self.logical_device.cmd_copy_buffer(
+                    command_buffer,
+                    staging,
+                    buffer,
+                    &[vk::BufferCopy::default().size(data.len() as u64)],
+                );
+            })
+        })();
+
+        unsafe { self.logical_device.destroy_buffer(staging, None) };
+        self.allocator.free(&self.logical_device, &allocation);
+
+        result
+    }
 }
 
 pub struct VulkanQueueFamilies {
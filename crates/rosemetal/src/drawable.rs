@@ -1,4 +1,7 @@
-use crate::{MTLDevice, RMLLayer, device};
+use crate::{
+    MTLDevice, MTLPixelFormat, MTLRegion, MTLStorageMode, MTLTextureDescriptor, MTLTextureType,
+    MTLTextureUsage, RMLLayer, device,
+};
 use crate::{MTLEvent, MTLFence};
 use anyhow::{Result, anyhow};
 use crossbeam::queue::SegQueue;
@@ -12,7 +15,12 @@ use std::sync::{Arc, Mutex, RwLock};
 use std::{cell::RefCell, sync::atomic::AtomicU32};
 
 #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
-use crate::{MTLRenderPassDescriptor, VulkanSurface};
+use crate::texture::copy_rows;
+#[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+use crate::{
+    MTLRenderPassDescriptor, MTLResourceOptions, VulkanAllocation, VulkanAttachmentKey,
+    VulkanSurface,
+};
 
 #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
 use ash::vk;
@@ -25,14 +33,11 @@ use objc2_quartz_core::{CAMetalDrawable, CAMetalLayer};
 use raw_window_metal::Layer;
 
 #[cfg(all(any(target_os = "macos", target_os = "ios"), not(feature = "moltenvk")))]
-use objc2_metal::{MTLPixelFormat as MetalMTLPixelFormat, MTLTexture as MetalMTLTexture};
+use objc2_metal::MTLTexture as MetalMTLTexture;
 
 pub struct MTLTexture {
     device: Arc<MTLDevice>,
-    pixel_format: MTLPixelFormat,
-    width: u32,
-    height: u32,
-    depth: u32,
+    descriptor: MTLTextureDescriptor,
 
     #[cfg(all(any(target_os = "macos", target_os = "ios"), not(feature = "moltenvk")))]
     metal_texture: Option<Retained<ProtocolObject<dyn MetalMTLTexture>>>,
@@ -43,14 +48,23 @@ pub struct MTLTexture {
     vulkan_image: vk::Image,
     #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
     vulkan_image_view: vk::ImageView,
+    /// What shaders sample, only the depth of combined depth and stencil
+    /// formats. `vulkan_image_view` for every other format.
+    #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+    vulkan_sampled_image_view: vk::ImageView,
+    /// Made for the attachments it's keyed on.
     #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
-    vulkan_framebuffer: RwLock<Option<vk::Framebuffer>>,
+    vulkan_framebuffer: RwLock<Option<(VulkanAttachmentKey, Vec<vk::ImageView>, vk::Framebuffer)>>,
     #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
     vulkan_swapchain: Option<(Arc<ash::khr::swapchain::Device>, Arc<vk::SwapchainKHR>)>,
     #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
     vulkan_sync_object: RwLock<Option<VulkanSyncObject>>,
     #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
     vulkan_image_index: AtomicU32,
+    /// Memory of textures created by `MTLDevice::new_texture`, swapchain
+    /// images belong to the swapchain.
+    #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+    vulkan_allocation: Option<VulkanAllocation>,
 }
 
 impl MTLTexture {
@@ -84,37 +98,522 @@ impl MTLTexture {
 
         Ok(Arc::new(Self {
             device,
-            pixel_format,
-            width,
-            height,
-            depth,
+            descriptor: MTLTextureDescriptor {
+                depth,
+                usage: MTLTextureUsage::render_target(),
+                ..MTLTextureDescriptor::texture_2d(pixel_format, width, height, false)
+            },
             vulkan_image,
             vulkan_image_view,
+            vulkan_sampled_image_view: vulkan_image_view,
             vulkan_swapchain: Some((vulkan_swapchain_instance, vulkan_swapchain_khr)),
             vulkan_image_index: AtomicU32::new(0),
             vulkan_framebuffer: RwLock::new(None),
             vulkan_sync_object: RwLock::new(None),
+            vulkan_allocation: None,
+        }))
+    }
+
+    pub fn new(device: Arc<MTLDevice>, descriptor: MTLTextureDescriptor) -> Result<Arc<Self>> {
+        descriptor.validate()?;
+
+        #[cfg(all(any(target_os = "macos", target_os = "ios"), not(feature = "moltenvk")))]
+        return Self::metal_new(device, descriptor);
+
+        #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+        return Self::vulkan_new(device, descriptor);
+    }
+
+    /// Creates an optimally tiled image whatever the storage mode, so the
+    /// CPU goes through staging copies. The image is left in the `GENERAL`
+    /// layout, where it can be sampled, written, rendered to and copied.
+    #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+    pub fn vulkan_new(
+        device: Arc<MTLDevice>,
+        descriptor: MTLTextureDescriptor,
+    ) -> Result<Arc<Self>> {
+        let vulkan_device = device.vulkan_device();
+        let logical = vulkan_device.logical();
+
+        let vulkan_image = unsafe {
+            logical.create_image(
+                &vk::ImageCreateInfo::default()
+                    .flags(descriptor.vulkan_flags())
+                    .image_type(descriptor.texture_type.to_vulkan_image_type())
+                    .format(descriptor.pixel_format.vulkan_format(&device))
+                    .extent(descriptor.vulkan_extent())
+                    .mip_levels(descriptor.mipmap_level_count)
+                    .array_layers(descriptor.layer_count())
+                    .samples(vk::SampleCountFlags::from_raw(descriptor.sample_count))
+                    .tiling(vk::ImageTiling::OPTIMAL)
+                    .usage(descriptor.vulkan_usage())
+                    .sharing_mode(vk::SharingMode::EXCLUSIVE)
+                    .initial_layout(vk::ImageLayout::UNDEFINED),
+                None,
+            )?
+        };
+
+        let requirements = unsafe { logical.get_image_memory_requirements(vulkan_image) };
+        let (required, preferred) = match descriptor.resource_options.storage_mode {
+            MTLStorageMode::Memoryless => MTLStorageMode::Memoryless,
+            _ => MTLStorageMode::Private,
+        }
+        .to_vulkan(descriptor.resource_options.cpu_cache_mode);
+
+        let allocation = match vulkan_device.allocator().allocate(
+            logical,
+            requirements,
+            required,
+            preferred,
+            false,
+        ) {
+            Ok(allocation) => allocation,
+            Err(error) => {
+                unsafe { logical.destroy_image(vulkan_image, None) };
+                return Err(error);
+            }
+        };
+
+        let subresource_range = vk::ImageSubresourceRange {
+            aspect_mask: descriptor.pixel_format.vulkan_aspect_mask(),
+            base_mip_level: 0,
+            level_count: descriptor.mipmap_level_count,
+            base_array_layer: 0,
+            layer_count: descriptor.layer_count(),
+        };
+
+        let vulkan_image_view = unsafe {
+            logical
+                .bind_image_memory(vulkan_image, allocation.memory(), allocation.offset())
+                .and_then(|_| {
+                    logical.create_image_view(
+                        &vk::ImageViewCreateInfo::default()
+                            .image(vulkan_image)
+                            .view_type(descriptor.texture_type.to_vulkan_view_type())
+                            .format(descriptor.pixel_format.vulkan_format(&device))
+                            .components(descriptor.pixel_format.vulkan_component_mapping())
+                            .subresource_range(subresource_range),
+                        None,
+                    )
+                })
+        };
+
+        let vulkan_image_view = match vulkan_image_view {
+            Ok(view) => view,
+            Err(error) => {
+                unsafe { logical.destroy_image(vulkan_image, None) };
+                vulkan_device.allocator().free(logical, &allocation);
+                return Err(error.into());
+            }
+        };
+
+        // Sampled image descriptors can only have one aspect.
+        let vulkan_sampled_image_view = match subresource_range.aspect_mask {
+            aspect if aspect == vk::ImageAspectFlags::DEPTH | vk::ImageAspectFlags::STENCIL => {
+                let view = unsafe {
+                    logical.create_image_view(
+                        &vk::ImageViewCreateInfo::default()
+                            .image(vulkan_image)
+                            .view_type(descriptor.texture_type.to_vulkan_view_type())
+                            .format(descriptor.pixel_format.vulkan_format(&device))
+                            .subresource_range(vk::ImageSubresourceRange {
+                                aspect_mask: vk::ImageAspectFlags::DEPTH,
+                                ..subresource_range
+                            }),
+                        None,
+                    )
+                };
+
+                match view {
+                    Ok(view) => view,
+                    Err(error) => {
+                        unsafe {
+                            logical.destroy_image_view(vulkan_image_view, None);
+                            logical.destroy_image(vulkan_image, None);
+                        }
+                        vulkan_device.allocator().free(logical, &allocation);
+                        return Err(error.into());
+                    }
+                }
+            }
+            _ => vulkan_image_view,
+        };
+
+        // Dropped on failure from here on, which releases everything above.
+        let texture = Arc::new(Self {
+            device: device.clone(),
+            descriptor,
+            vulkan_image,
+            vulkan_image_view,
+            vulkan_sampled_image_view,
+            vulkan_swapchain: None,
+            vulkan_image_index: AtomicU32::new(0),
+            vulkan_framebuffer: RwLock::new(None),
+            vulkan_sync_object: RwLock::new(None),
+            vulkan_allocation: Some(allocation),
+        });
+
+        vulkan_device.submit_and_wait(|command_buffer| unsafe {
+            logical.cmd_pipeline_barrier(
+                command_buffer,
+                vk::PipelineStageFlags::TOP_OF_PIPE,
+                vk::PipelineStageFlags::ALL_COMMANDS,
+                vk::DependencyFlags::empty(),
+                &[],
+                &[],
+                &[vk::ImageMemoryBarrier::default()
+                    .image(vulkan_image)
+                    .old_layout(vk::ImageLayout::UNDEFINED)
+                    .new_layout(vk::ImageLayout::GENERAL)
+                    .src_access_mask(vk::AccessFlags::empty())
+                    .dst_access_mask(vk::AccessFlags::MEMORY_READ | vk::AccessFlags::MEMORY_WRITE)
+                    .src_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+                    .dst_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+                    .subresource_range(vk::ImageSubresourceRange {
+                        aspect_mask: descriptor.pixel_format.vulkan_image_aspect_mask(&device),
+                        ..subresource_range
+                    })],
+            );
+        })?;
+
+        Ok(texture)
+    }
+
+    #[cfg(all(any(target_os = "macos", target_os = "ios"), not(feature = "moltenvk")))]
+    pub fn metal_new(
+        device: Arc<MTLDevice>,
+        descriptor: MTLTextureDescriptor,
+    ) -> Result<Arc<Self>> {
+        let metal_texture = device
+            .metal_device()
+            .newTextureWithDescriptor(&descriptor.to_metal());
+
+        let metal_texture = match metal_texture {
+            Some(t) => t,
+            None => return Err(anyhow!("Texture creation failed.")),
+        };
+
+        Ok(Arc::new(Self {
+            device,
+            descriptor,
+            metal_texture: Some(metal_texture),
+            ca_metal_drawable: None,
         }))
     }
 
+    pub fn descriptor(&self) -> &MTLTextureDescriptor {
+        &self.descriptor
+    }
+
+    pub fn texture_type(&self) -> MTLTextureType {
+        self.descriptor.texture_type
+    }
+
+    pub fn mipmap_level_count(&self) -> u32 {
+        self.descriptor.mipmap_level_count
+    }
+
+    pub fn array_length(&self) -> u32 {
+        self.descriptor.array_length
+    }
+
+    pub fn sample_count(&self) -> u32 {
+        self.descriptor.sample_count
+    }
+
+    pub fn usage(&self) -> MTLTextureUsage {
+        self.descriptor.usage
+    }
+
+    pub fn storage_mode(&self) -> MTLStorageMode {
+        self.descriptor.resource_options.storage_mode
+    }
+
+    /// Copies `bytes`, rows `bytes_per_row` apart, into `region` of a mipmap
+    /// level and slice. 3D regions take their depth slices one after the
+    /// other, each `bytes_per_row * height` bytes long.
+    pub fn replace_region(
+        &self,
+        region: MTLRegion,
+        mipmap_level: u32,
+        slice: u32,
+        bytes: &[u8],
+        bytes_per_row: usize,
+    ) -> Result<()> {
+        self.descriptor.check_cpu_access(
+            region,
+            mipmap_level,
+            slice,
+            bytes.len(),
+            bytes_per_row,
+        )?;
+
+        #[cfg(all(any(target_os = "macos", target_os = "ios"), not(feature = "moltenvk")))]
+        unsafe {
+            use std::ffi::c_void;
+            use std::ptr::NonNull;
+
+            self.to_metal()?
+                .replaceRegion_mipmapLevel_slice_withBytes_bytesPerRow_bytesPerImage(
+                    region.to_metal(),
+                    mipmap_level as usize,
+                    slice as usize,
+                    NonNull::new_unchecked(bytes.as_ptr() as *mut c_void),
+                    bytes_per_row,
+                    bytes_per_row * region.size.height as usize,
+                );
+            return Ok(());
+        }
+
+        #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+        return self.vulkan_replace_region(region, mipmap_level, slice, bytes, bytes_per_row);
+    }
+
+    /// Copies `region` of a mipmap level and slice into `bytes`, laid out
+    /// like `replace_region` expects them.
+    pub fn get_bytes(
+        &self,
+        bytes: &mut [u8],
+        bytes_per_row: usize,
+        region: MTLRegion,
+        mipmap_level: u32,
+        slice: u32,
+    ) -> Result<()> {
+        self.descriptor.check_cpu_access(
+            region,
+            mipmap_level,
+            slice,
+            bytes.len(),
+            bytes_per_row,
+        )?;
+
+        #[cfg(all(any(target_os = "macos", target_os = "ios"), not(feature = "moltenvk")))]
+        unsafe {
+            use std::ffi::c_void;
+            use std::ptr::NonNull;
+
+            self.to_metal()?
+                .getBytes_bytesPerRow_bytesPerImage_fromRegion_mipmapLevel_slice(
+                    NonNull::new_unchecked(bytes.as_mut_ptr() as *mut c_void),
+                    bytes_per_row,
+                    bytes_per_row * region.size.height as usize,
+                    region.to_metal(),
+                    mipmap_level as usize,
+                    slice as usize,
+                );
+            return Ok(());
+        }
+
+        #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+        return self.vulkan_get_bytes(bytes, bytes_per_row, region, mipmap_level, slice);
+    }
+
+    #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+    fn vulkan_replace_region(
+        &self,
+        region: MTLRegion,
+        mipmap_level: u32,
+        slice: u32,
+        bytes: &[u8],
+        bytes_per_row: usize,
+    ) -> Result<()> {
+        let (row_size, rows) = self.vulkan_staging_rows(region);
+
+        self.vulkan_staged_copy(region, mipmap_level, slice, false, |staging| {
+            // The staging buffer is tightly packed, which takes care of
+            // Vulkan's row length being in texels rather than bytes.
+            let staging = unsafe { std::slice::from_raw_parts_mut(staging, row_size * rows) };
+            copy_rows(bytes, bytes_per_row, staging, row_size, row_size, rows);
+        })
+    }
+
+    #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+    fn vulkan_get_bytes(
+        &self,
+        bytes: &mut [u8],
+        bytes_per_row: usize,
+        region: MTLRegion,
+        mipmap_level: u32,
+        slice: u32,
+    ) -> Result<()> {
+        let (row_size, rows) = self.vulkan_staging_rows(region);
+
+        self.vulkan_staged_copy(region, mipmap_level, slice, true, |staging| {
+            let staging = unsafe { std::slice::from_raw_parts(staging, row_size * rows) };
+            copy_rows(staging, row_size, bytes, bytes_per_row, row_size, rows);
+        })
+    }
+
+    /// Row size and row count of `region` in a tightly packed buffer, rows
+    /// of compressed formats being rows of blocks.
+    #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+    fn vulkan_staging_rows(&self, region: MTLRegion) -> (usize, usize) {
+        let pixel_format = self.descriptor.pixel_format;
+
+        (
+            pixel_format.bytes_per_row(region.size.width),
+            pixel_format.block_rows(region.size.height) as usize * region.size.depth as usize,
+        )
+    }
+
+    /// Copies between `region` and a staging buffer on the device's
+    /// transfer queue. `access` fills the staging buffer before uploads and
+    /// reads it after readbacks.
+    #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+    fn vulkan_staged_copy(
+        &self,
+        region: MTLRegion,
+        mipmap_level: u32,
+        slice: u32,
+        readback: bool,
+        mut access: impl FnMut(*mut u8),
+    ) -> Result<()> {
+        if self.vulkan_allocation.is_none() {
+            return Err(anyhow!("Drawable textures can't be accessed by the CPU."));
+        }
+
+        let vulkan_device = self.device.vulkan_device();
+        let logical = vulkan_device.logical();
+
+        let (row_size, rows) = self.vulkan_staging_rows(region);
+        let size = (row_size * rows) as vk::DeviceSize;
+
+        let (staging, allocation) = vulkan_device.create_buffer(
+            size,
+            vk::BufferUsageFlags::TRANSFER_SRC | vk::BufferUsageFlags::TRANSFER_DST,
+            MTLResourceOptions::default(),
+        )?;
+        let mapped = allocation.mapped() as *mut u8;
+
+        // Textures stay in the `GENERAL` layout, the barriers only order the
+        // copy with whatever used the texture before and after.
+        let subresource_range = vk::ImageSubresourceRange {
+            aspect_mask: self.descriptor.pixel_format.vulkan_aspect_mask(),
+            base_mip_level: mipmap_level,
+            level_count: 1,
+            base_array_layer: slice,
+            layer_count: 1,
+        };
+        let barrier = vk::ImageMemoryBarrier::default()
+            .image(self.vulkan_image)
+            .old_layout(vk::ImageLayout::GENERAL)
+            .new_layout(vk::ImageLayout::GENERAL)
+            .src_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+            .dst_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+            .subresource_range(vk::ImageSubresourceRange {
+                aspect_mask: self
+                    .descriptor
+                    .pixel_format
+                    .vulkan_image_aspect_mask(&self.device),
+                ..subresource_range
+            });
+
+        let copy = vk::BufferImageCopy::default()
+            .image_subresource(vk::ImageSubresourceLayers {
+                aspect_mask: subresource_range.aspect_mask,
+                mip_level: mipmap_level,
+                base_array_layer: slice,
+                layer_count: 1,
+            })
+            .image_offset(vk::Offset3D {
+                x: region.origin.x as i32,
+                y: region.origin.y as i32,
+                z: region.origin.z as i32,
+            })
+            .image_extent(vk::Extent3D {
+                width: region.size.width,
+                height: region.size.height,
+                depth: region.size.depth,
+            });
+
+        let result = (|| -> Result<()> {
+            if !readback {
+                access(mapped);
+                vulkan_device.flush(&allocation, 0..size)?;
+            }
+
+            vulkan_device.submit_and_wait(|command_buffer| unsafe {
+                logical.cmd_pipeline_barrier(
+                    command_buffer,
+                    vk::PipelineStageFlags::ALL_COMMANDS,
+                    vk::PipelineStageFlags::TRANSFER,
+                    vk::DependencyFlags::empty(),
+                    &[],
+                    &[],
+                    &[barrier
+                        .src_access_mask(vk::AccessFlags::MEMORY_WRITE)
+                        .dst_access_mask(match readback {
+                            true => vk::AccessFlags::TRANSFER_READ,
+                            false => vk::AccessFlags::TRANSFER_WRITE,
+                        })],
+                );
+
+                match readback {
+                    true => logical.cmd_copy_image_to_buffer(
+                        command_buffer,
+                        self.vulkan_image,
+                        vk::ImageLayout::GENERAL,
+                        staging,
+                        &[copy],
+                    ),
+                    false => logical.cmd_copy_buffer_to_image(
+                        command_buffer,
+                        staging,
+                        self.vulkan_image,
+                        vk::ImageLayout::GENERAL,
+                        &[copy],
+                    ),
+                }
+
+                logical.cmd_pipeline_barrier(
+                    command_buffer,
+                    vk::PipelineStageFlags::TRANSFER,
+                    vk::PipelineStageFlags::ALL_COMMANDS | vk::PipelineStageFlags::HOST,
+                    vk::DependencyFlags::empty(),
+                    &[vk::MemoryBarrier::default()
+                        .src_access_mask(vk::AccessFlags::TRANSFER_WRITE)
+                        .dst_access_mask(
+                            vk::AccessFlags::MEMORY_READ
+                                | vk::AccessFlags::MEMORY_WRITE
+                                | vk::AccessFlags::HOST_READ,
+                        )],
+                    &[],
+                    &[],
+                );
+            })?;
+
+            if readback {
+                vulkan_device.invalidate(&allocation, 0..size)?;
+                access(mapped);
+            }
+
+            Ok(())
+        })();
+
+        unsafe { logical.destroy_buffer(staging, None) };
+        vulkan_device.allocator().free(logical, &allocation);
+
+        result
+    }
+
     pub fn device(&self) -> &Arc<MTLDevice> {
         &self.device
     }
 
     pub fn width(&self) -> u32 {
-        self.width
+        self.descriptor.width
     }
 
     pub fn height(&self) -> u32 {
-        self.height
+        self.descriptor.height
     }
 
     pub fn depth(&self) -> u32 {
-        self.depth
+        self.descriptor.depth
     }
 
     pub fn pixel_format(&self) -> &MTLPixelFormat {
-        &self.pixel_format
+        &self.descriptor.pixel_format
     }
 
     #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
@@ -128,13 +627,8 @@ impl MTLTexture {
     }
 
     #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
-    pub fn vulkan_framebuffer(&self) -> &RwLock<Option<vk::Framebuffer>> {
-        &self.vulkan_framebuffer
-    }
-
-    #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
-    pub fn vulkan_is_framebuffer(&self) -> bool {
-        self.vulkan_framebuffer.read().unwrap().is_some()
+    pub fn vulkan_sampled_image_view(&self) -> &vk::ImageView {
+        &self.vulkan_sampled_image_view
     }
 
     #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
@@ -144,27 +638,49 @@ impl MTLTexture {
         &self.vulkan_swapchain
     }
 
+    /// The framebuffer of a pass with `attachments`, this texture being
+    /// the first. It's rebuilt when the attachments change.
     #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
-    pub unsafe fn vulkan_create_framebuffer(
+    pub fn vulkan_framebuffer(
         &self,
-        render_pass: &vk::RenderPass,
-        device: Arc<MTLDevice>,
-    ) -> Result<()> {
-        let mut framebuffer = self.vulkan_framebuffer().write().unwrap();
+        render_pass: vk::RenderPass,
+        key: &VulkanAttachmentKey,
+        attachments: &[vk::ImageView],
+    ) -> Result<vk::Framebuffer> {
+        let mut framebuffer = self.vulkan_framebuffer.write().unwrap();
+
+        if let Some((_, _, framebuffer)) = framebuffer
+            .as_ref()
+            .filter(|(x, y, _)| x == key && y == attachments)
+        {
+            return Ok(*framebuffer);
+        }
+
+        let logical = self.device.vulkan_device().logical();
 
-        framebuffer.replace(unsafe {
-            device.vulkan_device().logical().create_framebuffer(
+        if let Some((_, _, old)) = framebuffer.take() {
+            // Attachments rarely change, waiting beats tracking which
+            // submissions still use the old one.
+            unsafe {
+                logical.device_wait_idle()?;
+                logical.destroy_framebuffer(old, None);
+            }
+        }
+
+        let new = unsafe {
+            logical.create_framebuffer(
                 &vk::FramebufferCreateInfo::default()
-                    .render_pass(*render_pass)
-                    .attachments(&[*self.vulkan_image_view()])
-                    .width(self.width)
-                    .height(self.height)
+                    .render_pass(render_pass)
+                    .attachments(attachments)
+                    .width(self.descriptor.width)
+                    .height(self.descriptor.height)
                     .layers(1),
                 None,
             )?
-        });
+        };
+        framebuffer.replace((key.clone(), attachments.to_vec(), new));
 
-        Ok(())
+        Ok(new)
     }
 
     #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
@@ -214,10 +730,11 @@ impl MTLTexture {
             device,
             ca_metal_drawable,
             metal_texture,
-            width,
-            height,
-            depth,
-            pixel_format,
+            descriptor: MTLTextureDescriptor {
+                depth,
+                usage: MTLTextureUsage::render_target(),
+                ..MTLTextureDescriptor::texture_2d(pixel_format, width, height, false)
+            },
         })
     }
 
@@ -240,6 +757,33 @@ impl MTLTexture {
     }
 }
 
+#[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+impl Drop for MTLTexture {
+    fn drop(&mut self) {
+        let vulkan_device = self.device.vulkan_device();
+        let logical = vulkan_device.logical();
+
+        // Swapchain images have framebuffers too.
+        if let Some((_, _, framebuffer)) = self.vulkan_framebuffer.get_mut().unwrap().take() {
+            unsafe { logical.destroy_framebuffer(framebuffer, None) };
+        }
+
+        let Some(allocation) = &self.vulkan_allocation else {
+            return;
+        };
+
+        unsafe {
+            if self.vulkan_sampled_image_view != self.vulkan_image_view {
+                logical.destroy_image_view(self.vulkan_sampled_image_view, None);
+            }
+            logical.destroy_image_view(self.vulkan_image_view, None);
+            logical.destroy_image(self.vulkan_image, None);
+        }
+
+        vulkan_device.allocator().free(logical, allocation);
+    }
+}
+
 #[derive(Default)]
 pub struct MTLViewSettings {
     pub vsync: AtomicBool,
@@ -747,42 +1291,3 @@ impl VulkanInFlightFrames {
         &self.sync_objects[current_frame]
     }
 }
-
-#[derive(Debug, Clone, Copy)]
-pub enum MTLPixelFormat {
-    Bgra8Unorm,
-}
-
-impl MTLPixelFormat {
-    #[cfg(all(any(target_os = "macos", target_os = "ios"), not(feature = "moltenvk")))]
-    pub fn from_metal(metal_format: MetalMTLPixelFormat) -> Self {
-        match metal_format {
-            MetalMTLPixelFormat::BGRA8Unorm => MTLPixelFormat::Bgra8Unorm,
-            _ => todo!("Format not yet handled."),
-        }
-    }
-
-    #[cfg(all(any(target_os = "macos", target_os = "ios"), not(feature = "moltenvk")))]
-    pub fn to_metal(&self) -> MetalMTLPixelFormat {
-        match self {
-            Self::Bgra8Unorm => MetalMTLPixelFormat::BGRA8Unorm,
-            _ => todo!("Format not yet handled."),
-        }
-    }
-
-    #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
-    pub fn from_vulkan(vulkan_format: vk::Format) -> Self {
-        match vulkan_format {
-            vk::Format::B8G8R8A8_UNORM => Self::Bgra8Unorm,
-            _ => todo!("Format not yet handled."),
-        }
-    }
-
-    #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
-    pub fn to_vulkan(&self) -> vk::Format {
-        match self {
-            Self::Bgra8Unorm => vk::Format::B8G8R8A8_UNORM,
-            _ => todo!("Format not yet handled."),
-        }
-    }
-}
@@ -1,7 +1,11 @@
 use anyhow::Result;
 
 #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
-use ash::{Entry, Instance, khr::surface, vk::SurfaceKHR};
+use ash::{
+    Entry, Instance,
+    khr::{get_physical_device_properties2, surface},
+    vk::SurfaceKHR,
+};
 
 use raw_window_handle::{RawDisplayHandle, RawWindowHandle};
 use std::sync::Arc;
@@ -14,6 +18,11 @@ pub struct RMLInstance {
     #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
     vulkan_instance: ash::Instance,
 
+    /// Queries optional device features and limits, when the loader has
+    /// `VK_KHR_get_physical_device_properties2`.
+    #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+    vulkan_properties2: Option<get_physical_device_properties2::Instance>,
+
     #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
     vulkan_surface: Option<VulkanSurface>,
 }
@@ -41,6 +50,14 @@ impl RMLInstance {
 
         let vulkan_instance = Self::vulkan_create_instance(&layer)?;
 
+        let vulkan_properties2 = match Self::vulkan_has_properties2() {
+            true => Some(get_physical_device_properties2::Instance::new(
+                &VULKAN_ENTRY,
+                &vulkan_instance,
+            )),
+            false => None,
+        };
+
         let vulkan_surface = unsafe {
             match &layer {
                 Some(l) => Some(VulkanSurface {
@@ -60,6 +77,7 @@ impl RMLInstance {
         Ok(Arc::new(Self {
             layer,
             vulkan_instance,
+            vulkan_properties2,
             vulkan_surface,
         }))
     }
@@ -93,7 +111,10 @@ impl RMLInstance {
         #[cfg(any(target_os = "macos", target_os = "ios"))]
         {
             extension_names.push(ash::khr::portability_enumeration::NAME.as_ptr());
-            extension_names.push(ash::khr::get_physical_device_properties2::NAME.as_ptr());
+        }
+
+        if Self::vulkan_has_properties2() {
+            extension_names.push(get_physical_device_properties2::NAME.as_ptr());
         }
 
         let (_layer_names, layer_names_ptrs) = Self::vulkan_get_layer_names_and_pointers();
@@ -115,6 +136,24 @@ impl RMLInstance {
         Ok(unsafe { VULKAN_ENTRY.create_instance(&instance_create_info, None)? })
     }
 
+    /// (Vulkan) Whether the loader has `VK_KHR_get_physical_device_properties2`.
+    /// It's core from Vulkan 1.1, but the instance is created for 1.0.
+    #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+    pub fn vulkan_has_properties2() -> bool {
+        // Portability enumeration requires it.
+        if cfg!(any(target_os = "macos", target_os = "ios")) {
+            return true;
+        }
+
+        unsafe { VULKAN_ENTRY.enumerate_instance_extension_properties(None) }.is_ok_and(
+            |extensions| {
+                extensions.iter().any(|ext| {
+                    ext.extension_name_as_c_str() == Ok(get_physical_device_properties2::NAME)
+                })
+            },
+        )
+    }
+
     #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
     pub const REQUIRED_LAYERS: [&str; 1] = ["VK_LAYER_KHRONOS_validation"];
 
@@ -138,6 +177,11 @@ impl RMLInstance {
         &self.vulkan_instance
     }
 
+    #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+    pub fn vulkan_properties2(&self) -> Option<&get_physical_device_properties2::Instance> {
+        self.vulkan_properties2.as_ref()
+    }
+
     #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
     pub fn vulkan_surface(&self) -> &Option<VulkanSurface> {
         &self.vulkan_surface
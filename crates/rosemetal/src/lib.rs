@@ -1,22 +1,42 @@
+#[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+pub mod allocator;
+pub mod argument;
 pub mod buffer;
 pub mod command;
+pub mod compute;
+pub mod depth_stencil;
 pub mod device;
 pub mod drawable;
 pub mod instance;
 pub mod math;
+pub mod pixel_format;
 pub mod render;
+pub mod resource;
+pub mod sampler;
 pub mod shader;
 pub mod sync;
+pub mod texture;
+pub mod vertex;
 
+#[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+pub use allocator::*;
+pub use argument::*;
 pub use buffer::*;
 pub use command::*;
+pub use compute::*;
+pub use depth_stencil::*;
 pub use device::*;
 pub use drawable::*;
 pub use instance::*;
 pub use math::*;
+pub use pixel_format::*;
 pub use render::*;
+pub use resource::*;
+pub use sampler::*;
 pub use shader::*;
 pub use sync::*;
+pub use texture::*;
+pub use vertex::*;
 
 #[cfg(test)]
 mod tests {
@@ -32,4 +52,615 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn texture_descriptor() {
+        assert_eq!(MTLTextureDescriptor::full_mipmap_level_count(0), 1);
+        assert_eq!(MTLTextureDescriptor::full_mipmap_level_count(1), 1);
+        assert_eq!(MTLTextureDescriptor::full_mipmap_level_count(255), 8);
+        assert_eq!(MTLTextureDescriptor::full_mipmap_level_count(256), 9);
+
+        let descriptor =
+            MTLTextureDescriptor::texture_2d(MTLPixelFormat::Rgba8Unorm, 256, 64, true);
+        assert_eq!(descriptor.mipmap_level_count, 9);
+        assert_eq!(descriptor.layer_count(), 1);
+        assert!(descriptor.validate().is_ok());
+
+        let cube = MTLTextureDescriptor::texture_cube(MTLPixelFormat::Rgba8Unorm, 16, false);
+        assert_eq!(cube.layer_count(), 6);
+        assert!(cube.validate().is_ok());
+
+        let cube_array = MTLTextureDescriptor {
+            texture_type: MTLTextureType::TypeCubeArray,
+            array_length: 3,
+            ..cube
+        };
+        assert_eq!(cube_array.layer_count(), 18);
+        assert!(cube_array.validate().is_ok());
+
+        let one_dimensional = MTLTextureDescriptor {
+            texture_type: MTLTextureType::Type1D,
+            ..MTLTextureDescriptor::texture_2d(MTLPixelFormat::Rgba8Unorm, 64, 1, false)
+        };
+        assert!(one_dimensional.validate().is_ok());
+
+        let volume = MTLTextureDescriptor {
+            texture_type: MTLTextureType::Type3D,
+            depth: 32,
+            ..MTLTextureDescriptor::texture_2d(MTLPixelFormat::Rgba8Unorm, 16, 16, false)
+        };
+        assert!(volume.validate().is_ok());
+
+        let multisample = MTLTextureDescriptor {
+            texture_type: MTLTextureType::Type2DMultisample,
+            sample_count: 4,
+            ..MTLTextureDescriptor::texture_2d(MTLPixelFormat::Rgba8Unorm, 16, 16, false)
+        };
+        assert!(multisample.validate().is_ok());
+
+        for invalid in [
+            MTLTextureDescriptor {
+                width: 0,
+                ..descriptor
+            },
+            MTLTextureDescriptor {
+                mipmap_level_count: 0,
+                ..descriptor
+            },
+            MTLTextureDescriptor {
+                mipmap_level_count: 10,
+                ..descriptor
+            },
+            MTLTextureDescriptor {
+                array_length: 2,
+                ..descriptor
+            },
+            MTLTextureDescriptor {
+                depth: 2,
+                ..descriptor
+            },
+            MTLTextureDescriptor {
+                height: 2,
+                ..one_dimensional
+            },
+            MTLTextureDescriptor {
+                sample_count: 3,
+                ..multisample
+            },
+            MTLTextureDescriptor {
+                sample_count: 4,
+                mipmap_level_count: 1,
+                ..descriptor
+            },
+            MTLTextureDescriptor {
+                mipmap_level_count: 2,
+                ..multisample
+            },
+            MTLTextureDescriptor { height: 8, ..cube },
+        ] {
+            assert!(invalid.validate().is_err(), "{:?}", invalid);
+        }
+    }
+
+    #[test]
+    fn texture_cpu_access() {
+        let descriptor = MTLTextureDescriptor::texture_2d(MTLPixelFormat::Rgba8Unorm, 16, 8, true);
+        let region = MTLRegion::new_2d(4, 2, 8, 4);
+
+        // The last row doesn't need its padding.
+        assert!(
+            descriptor
+                .check_cpu_access(region, 0, 0, 32 * 4, 32)
+                .is_ok()
+        );
+        assert!(
+            descriptor
+                .check_cpu_access(region, 0, 0, 32 * 3 + 32, 40)
+                .is_err()
+        );
+        assert!(
+            descriptor
+                .check_cpu_access(region, 0, 0, 40 * 3 + 32, 40)
+                .is_ok()
+        );
+        assert!(
+            descriptor
+                .check_cpu_access(region, 0, 0, 32 * 4 - 1, 32)
+                .is_err()
+        );
+        assert!(descriptor.check_cpu_access(region, 0, 0, 1024, 28).is_err());
+
+        // Level 1 is 8x4, level 4 a single pixel.
+        assert!(descriptor.check_cpu_access(region, 1, 0, 1024, 32).is_err());
+        assert!(
+            descriptor
+                .check_cpu_access(MTLRegion::new_2d(0, 0, 8, 4), 1, 0, 1024, 32)
+                .is_ok()
+        );
+        assert!(
+            descriptor
+                .check_cpu_access(MTLRegion::new_2d(0, 0, 1, 1), 4, 0, 4, 4)
+                .is_ok()
+        );
+        assert!(
+            descriptor
+                .check_cpu_access(MTLRegion::new_2d(0, 0, 1, 1), 5, 0, 4, 4)
+                .is_err()
+        );
+        assert!(descriptor.check_cpu_access(region, 0, 1, 1024, 32).is_err());
+        assert!(
+            descriptor
+                .check_cpu_access(MTLRegion::new_2d(0, 0, 0, 4), 0, 0, 1024, 32)
+                .is_err()
+        );
+        assert!(
+            descriptor
+                .check_cpu_access(MTLRegion::new_2d(u32::MAX, 0, 2, 4), 0, 0, 1024, 32)
+                .is_err()
+        );
+
+        let private = MTLTextureDescriptor {
+            resource_options: MTLResourceOptions {
+                storage_mode: MTLStorageMode::Private,
+                ..Default::default()
+            },
+            ..descriptor
+        };
+        assert!(private.check_cpu_access(region, 0, 0, 1024, 32).is_err());
+
+        let depth_stencil =
+            MTLTextureDescriptor::texture_2d(MTLPixelFormat::Depth32FloatStencil8, 16, 8, false);
+        assert!(
+            depth_stencil
+                .check_cpu_access(region, 0, 0, 1024, 64)
+                .is_err()
+        );
+
+        // BC1 takes 8 bytes per 4x4 block, and regions reaching the edge of
+        // a 10x10 level may end halfway through a block.
+        let compressed = MTLTextureDescriptor::texture_2d(MTLPixelFormat::Bc1Rgba, 10, 10, false);
+        assert!(
+            compressed
+                .check_cpu_access(MTLRegion::new_2d(4, 4, 6, 6), 0, 0, 32, 16)
+                .is_ok()
+        );
+        assert!(
+            compressed
+                .check_cpu_access(MTLRegion::new_2d(4, 4, 6, 6), 0, 0, 31, 16)
+                .is_err()
+        );
+        assert!(
+            compressed
+                .check_cpu_access(MTLRegion::new_2d(4, 4, 2, 4), 0, 0, 1024, 16)
+                .is_err()
+        );
+        assert!(
+            compressed
+                .check_cpu_access(MTLRegion::new_2d(2, 0, 4, 4), 0, 0, 1024, 16)
+                .is_err()
+        );
+
+        let volume = MTLTextureDescriptor {
+            texture_type: MTLTextureType::Type3D,
+            depth: 4,
+            ..MTLTextureDescriptor::texture_2d(MTLPixelFormat::R8Unorm, 4, 4, false)
+        };
+        let slab = MTLRegion::new_3d(0, 0, 1, 4, 4, 3);
+        assert!(volume.check_cpu_access(slab, 0, 0, 4 * 12, 4).is_ok());
+        assert!(volume.check_cpu_access(slab, 0, 0, 4 * 12 - 1, 4).is_err());
+        assert!(
+            volume
+                .check_cpu_access(MTLRegion::new_3d(0, 0, 2, 4, 4, 3), 0, 0, 1024, 4)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn copy_rows() {
+        // Two rows of 3 bytes, packed 5 bytes apart into a tight buffer and
+        // back.
+        let padded = [1, 2, 3, 0, 0, 4, 5, 6];
+        let mut packed = [0; 6];
+        texture::copy_rows(&padded, 5, &mut packed, 3, 3, 2);
+        assert_eq!(packed, [1, 2, 3, 4, 5, 6]);
+
+        let mut unpacked = [9; 8];
+        texture::copy_rows(&packed, 3, &mut unpacked, 5, 3, 2);
+        assert_eq!(unpacked, [1, 2, 3, 9, 9, 4, 5, 6]);
+    }
+
+    #[test]
+    fn pixel_format_blocks() {
+        assert_eq!(MTLPixelFormat::Invalid.bytes_per_row(16), 0);
+        assert_eq!(MTLPixelFormat::R8Unorm.bytes_per_row(7), 7);
+        assert_eq!(MTLPixelFormat::R8Unorm.block_rows(7), 7);
+        assert_eq!(MTLPixelFormat::Rgba32Float.bytes_per_row(3), 48);
+        assert_eq!(MTLPixelFormat::Depth32FloatStencil8.bytes_per_row(3), 24);
+
+        // Partial blocks at the edges take up whole ones.
+        assert_eq!(MTLPixelFormat::Bc1Rgba.bytes_per_row(10), 24);
+        assert_eq!(MTLPixelFormat::Bc1Rgba.block_rows(10), 3);
+        assert_eq!(MTLPixelFormat::Bc7RgbaUnorm.bytes_per_row(1), 16);
+        assert_eq!(MTLPixelFormat::Bc7RgbaUnorm.block_rows(1), 1);
+        assert_eq!(MTLPixelFormat::Astc12x10Ldr.bytes_per_row(25), 48);
+        assert_eq!(MTLPixelFormat::Astc12x10Ldr.block_rows(25), 3);
+
+        assert!(MTLPixelFormat::Astc12x10Ldr.is_compressed());
+        assert!(!MTLPixelFormat::Rgba8Unorm.is_compressed());
+        assert!(MTLPixelFormat::Depth32FloatStencil8.is_depth());
+        assert!(MTLPixelFormat::Depth32FloatStencil8.is_stencil());
+        assert!(!MTLPixelFormat::Stencil8.is_depth());
+    }
+
+    #[test]
+    fn render_pass_begin_descriptor() {
+        let begin = MTLBeginRenderPassDescriptor::default();
+        assert!(
+            MTLRenderPassDescriptor::default()
+                .validate_begin(&begin)
+                .is_ok()
+        );
+
+        // Attachments without a texture.
+        let color = MTLRenderPassDescriptor {
+            color_attachments: vec![MTLRenderPassColorAttachment {
+                load_action: MTLLoadAction::Clear,
+                store_action: MTLStoreAction::Store,
+            }],
+            ..Default::default()
+        };
+        assert!(color.validate_begin(&begin).is_err());
+
+        let depth = MTLRenderPassDescriptor {
+            depth_attachment: Some(MTLRenderPassDepthAttachment {
+                load_action: MTLLoadAction::Clear,
+                store_action: MTLStoreAction::DontCare,
+            }),
+            ..Default::default()
+        };
+        assert!(depth.validate_begin(&begin).is_err());
+    }
+
+    #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+    #[test]
+    fn pixel_format_to_vulkan() {
+        use ash::vk::{ComponentSwizzle, Format, ImageAspectFlags};
+
+        // Core formats and the ASTC HDR ones, every format rosemetal maps
+        // back has to map there again, with a block matching its kind.
+        let mut mapped = 0;
+        for raw in (0..=184).chain(1000066000..=1000066013) {
+            let format = Format::from_raw(raw);
+            let pixel_format = MTLPixelFormat::from_vulkan(format);
+
+            if pixel_format == MTLPixelFormat::Invalid {
+                continue;
+            }
+
+            mapped += 1;
+            assert_eq!(pixel_format.to_vulkan(), format, "{:?}", pixel_format);
+            assert_eq!(
+                pixel_format.is_compressed(),
+                pixel_format.block_width() > 1,
+                "{:?}",
+                pixel_format
+            );
+            assert_ne!(pixel_format.bytes_per_block(), 0, "{:?}", pixel_format);
+        }
+        // Every format but `Invalid`, `A8Unorm` and the stencil views.
+        assert_eq!(mapped, 121);
+
+        // Formats that aren't a Vulkan format of their own.
+        assert_eq!(MTLPixelFormat::A8Unorm.to_vulkan(), Format::R8_UNORM);
+        assert_eq!(
+            MTLPixelFormat::A8Unorm.vulkan_component_mapping().a,
+            ComponentSwizzle::R
+        );
+        assert_eq!(
+            MTLPixelFormat::X24Stencil8.to_vulkan(),
+            Format::D24_UNORM_S8_UINT
+        );
+        assert_eq!(
+            MTLPixelFormat::X32Stencil8.to_vulkan(),
+            Format::D32_SFLOAT_S8_UINT
+        );
+        assert_eq!(
+            MTLPixelFormat::X32Stencil8.vulkan_aspect_mask(),
+            ImageAspectFlags::STENCIL
+        );
+        assert_eq!(
+            MTLPixelFormat::Depth24UnormStencil8.vulkan_aspect_mask(),
+            ImageAspectFlags::DEPTH | ImageAspectFlags::STENCIL
+        );
+        assert_eq!(
+            MTLPixelFormat::Rgba8Unorm.vulkan_aspect_mask(),
+            ImageAspectFlags::COLOR
+        );
+    }
+
+    #[test]
+    fn sampler_descriptor() {
+        let descriptor = MTLSamplerDescriptor::address_mode(MTLSamplerAddressMode::Repeat);
+        assert!(descriptor.validate().is_ok());
+
+        for invalid in [
+            MTLSamplerDescriptor {
+                max_anisotropy: 0,
+                ..descriptor
+            },
+            MTLSamplerDescriptor {
+                lod_min_clamp: -1.0,
+                ..descriptor
+            },
+            MTLSamplerDescriptor {
+                lod_min_clamp: 4.0,
+                lod_max_clamp: 2.0,
+                ..descriptor
+            },
+            MTLSamplerDescriptor {
+                lod_min_clamp: f32::NAN,
+                ..descriptor
+            },
+            MTLSamplerDescriptor {
+                lod_max_clamp: f32::NAN,
+                ..descriptor
+            },
+            MTLSamplerDescriptor {
+                normalized_coordinates: false,
+                ..descriptor
+            },
+        ] {
+            assert!(invalid.validate().is_err(), "{:?}", invalid);
+        }
+
+        assert!(
+            MTLSamplerDescriptor {
+                normalized_coordinates: false,
+                ..Default::default()
+            }
+            .validate()
+            .is_ok()
+        );
+    }
+
+    #[test]
+    fn vertex_format_size() {
+        assert_eq!(MTLVertexFormat::Invalid.size(), 0);
+        assert_eq!(MTLVertexFormat::UChar3Normalized.size(), 3);
+        assert_eq!(MTLVertexFormat::Half.size(), 2);
+        assert_eq!(MTLVertexFormat::Short3.size(), 6);
+        assert_eq!(MTLVertexFormat::UChar4NormalizedBgra.size(), 4);
+        assert_eq!(MTLVertexFormat::FloatRgb9e5.size(), 4);
+        assert_eq!(MTLVertexFormat::Float3.size(), 12);
+        assert_eq!(MTLVertexFormat::UInt4.size(), 16);
+    }
+
+    #[test]
+    fn vertex_descriptor() {
+        use std::collections::BTreeMap;
+
+        let descriptor = MTLVertexDescriptor {
+            attributes: BTreeMap::from([
+                (
+                    0,
+                    MTLVertexAttributeDescriptor {
+                        format: MTLVertexFormat::Float3,
+                        offset: 0,
+                        buffer_index: 1,
+                    },
+                ),
+                (
+                    1,
+                    MTLVertexAttributeDescriptor {
+                        format: MTLVertexFormat::UChar4Normalized,
+                        offset: 12,
+                        buffer_index: 1,
+                    },
+                ),
+            ]),
+            layouts: BTreeMap::from([(
+                1,
+                MTLVertexBufferLayoutDescriptor {
+                    stride: 16,
+                    ..Default::default()
+                },
+            )]),
+        };
+        assert!(descriptor.validate().is_ok());
+        assert!(MTLVertexDescriptor::default().validate().is_ok());
+
+        let with_layout = |layout: MTLVertexBufferLayoutDescriptor| {
+            let mut descriptor = descriptor.clone();
+            descriptor.layouts.insert(1, layout);
+            descriptor
+        };
+        let with_attribute = |attribute: MTLVertexAttributeDescriptor| {
+            let mut descriptor = descriptor.clone();
+            descriptor.attributes.insert(1, attribute);
+            descriptor
+        };
+
+        for invalid in [
+            with_layout(MTLVertexBufferLayoutDescriptor {
+                stride: 18,
+                ..Default::default()
+            }),
+            with_layout(MTLVertexBufferLayoutDescriptor {
+                stride: 16,
+                step_function: MTLVertexStepFunction::PerInstance,
+                step_rate: 0,
+            }),
+            // The attribute ends past the stride.
+            with_attribute(MTLVertexAttributeDescriptor {
+                format: MTLVertexFormat::Float2,
+                offset: 12,
+                buffer_index: 1,
+            }),
+            with_attribute(MTLVertexAttributeDescriptor {
+                format: MTLVertexFormat::Invalid,
+                offset: 12,
+                buffer_index: 1,
+            }),
+            // Buffer 2 has no layout.
+            with_attribute(MTLVertexAttributeDescriptor {
+                format: MTLVertexFormat::UChar4Normalized,
+                offset: 0,
+                buffer_index: 2,
+            }),
+        ] {
+            assert!(invalid.validate().is_err(), "{:?}", invalid);
+        }
+
+        // Constant buffers are read with a stride of 0, so nothing overflows.
+        assert!(
+            with_layout(MTLVertexBufferLayoutDescriptor {
+                stride: 0,
+                step_function: MTLVertexStepFunction::Constant,
+                step_rate: 0,
+            })
+            .validate()
+            .is_ok()
+        );
+    }
+
+    #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+    #[test]
+    fn sampler_bindings() {
+        use airlines::air_codegen::{AirToSpirVOptions, ShaderResourceKind};
+
+        // Set 0 has to agree with where airlines puts samplers.
+        for index in [0, 15] {
+            assert_eq!(
+                AirToSpirVOptions::default().descriptor_binding(ShaderResourceKind::Sampler, index),
+                (0, VulkanPipelineLayout::sampler_binding(index))
+            );
+        }
+    }
+
+    #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+    #[test]
+    fn depth_stencil_descriptor_to_vulkan() {
+        use ash::vk::{CompareOp, FALSE, StencilOp, TRUE};
+
+        // The default keeps both tests off.
+        let off = MTLDepthStencilDescriptor::default().to_vulkan();
+        assert_eq!(off.depth_test_enable, FALSE);
+        assert_eq!(off.depth_write_enable, FALSE);
+        assert_eq!(off.depth_compare_op, CompareOp::ALWAYS);
+        assert_eq!(off.stencil_test_enable, FALSE);
+
+        // Writing with `Always` still needs the test on.
+        let write_only = MTLDepthStencilDescriptor {
+            depth_write_enabled: true,
+            ..Default::default()
+        }
+        .to_vulkan();
+        assert_eq!(write_only.depth_test_enable, TRUE);
+        assert_eq!(write_only.depth_write_enable, TRUE);
+
+        let stencil = MTLStencilDescriptor {
+            stencil_compare_function: MTLCompareFunction::Equal,
+            stencil_failure_operation: MTLStencilOperation::Zero,
+            depth_failure_operation: MTLStencilOperation::DecrementWrap,
+            depth_stencil_pass_operation: MTLStencilOperation::IncrementClamp,
+            read_mask: 0x0f,
+            write_mask: 0xf0,
+        };
+        let state = MTLDepthStencilDescriptor {
+            depth_compare_function: MTLCompareFunction::Less,
+            depth_write_enabled: false,
+            front_face_stencil: Some(stencil),
+            back_face_stencil: None,
+        }
+        .to_vulkan();
+        assert_eq!(state.depth_test_enable, TRUE);
+        assert_eq!(state.depth_write_enable, FALSE);
+        assert_eq!(state.depth_compare_op, CompareOp::LESS);
+        assert_eq!(state.stencil_test_enable, TRUE);
+
+        assert_eq!(state.front.compare_op, CompareOp::EQUAL);
+        assert_eq!(state.front.fail_op, StencilOp::ZERO);
+        assert_eq!(state.front.depth_fail_op, StencilOp::DECREMENT_AND_WRAP);
+        assert_eq!(state.front.pass_op, StencilOp::INCREMENT_AND_CLAMP);
+        assert_eq!(state.front.compare_mask, 0x0f);
+        assert_eq!(state.front.write_mask, 0xf0);
+
+        // The back face keeps what's in the attachment.
+        assert_eq!(state.back.compare_op, CompareOp::ALWAYS);
+        assert_eq!(state.back.fail_op, StencilOp::KEEP);
+        assert_eq!(state.back.depth_fail_op, StencilOp::KEEP);
+        assert_eq!(state.back.pass_op, StencilOp::KEEP);
+    }
+
+    #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+    #[test]
+    fn memory_block() {
+        use crate::allocator::VulkanMemoryBlock;
+
+        let mut block =
+            VulkanMemoryBlock::new(ash::vk::DeviceMemory::null(), 1024, std::ptr::null_mut());
+        assert!(block.is_empty());
+
+        // Offsets are aligned, the padding before them stays free.
+        assert_eq!(block.allocate(100, 1), Some(0));
+        assert_eq!(block.allocate(64, 256), Some(256));
+        assert!(!block.is_empty());
+
+        // Best fit takes the padding, 156 bytes, over the 704 at the end.
+        assert_eq!(block.allocate(50, 4), Some(100));
+        assert_eq!(block.allocate(704, 1), Some(320));
+
+        // 106 bytes are left, after the third allocation.
+        assert_eq!(block.allocate(200, 1), None);
+        assert_eq!(block.allocate(8, 16), Some(160));
+        block.free(160, 8);
+
+        // Neighbouring free ranges merge back into the whole block.
+        block.free(256, 64);
+        block.free(320, 704);
+        block.free(100, 50);
+        assert!(!block.is_empty());
+        block.free(0, 100);
+        assert!(block.is_empty());
+
+        assert_eq!(block.allocate(1024, 1), Some(0));
+        assert_eq!(block.allocate(1, 1), None);
+    }
+
+    #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+    #[test]
+    fn buffer_usage_to_vulkan() {
+        use ash::vk::BufferUsageFlags;
+
+        for (usage, flags) in [
+            (MTLBufferUsage::VERTEX, BufferUsageFlags::VERTEX_BUFFER),
+            (MTLBufferUsage::INDEX, BufferUsageFlags::INDEX_BUFFER),
+            (MTLBufferUsage::UNIFORM, BufferUsageFlags::UNIFORM_BUFFER),
+            (MTLBufferUsage::STORAGE, BufferUsageFlags::STORAGE_BUFFER),
+            (MTLBufferUsage::INDIRECT, BufferUsageFlags::INDIRECT_BUFFER),
+            (
+                MTLBufferUsage::TRANSFER_SOURCE,
+                BufferUsageFlags::TRANSFER_SRC,
+            ),
+            (
+                MTLBufferUsage::TRANSFER_DESTINATION,
+                BufferUsageFlags::TRANSFER_DST,
+            ),
+        ] {
+            assert_eq!(usage.to_vulkan(), flags);
+            assert!(MTLBufferUsage::default().to_vulkan().contains(flags));
+        }
+
+        // A buffer can be fetched from and read by shaders at once.
+        assert_eq!(
+            (MTLBufferUsage::VERTEX | MTLBufferUsage::STORAGE).to_vulkan(),
+            BufferUsageFlags::VERTEX_BUFFER | BufferUsageFlags::STORAGE_BUFFER
+        );
+
+        assert!(MTLBufferUsage::default().is_argument());
+        assert!(MTLBufferUsage::STORAGE.is_argument());
+        assert!((MTLBufferUsage::VERTEX | MTLBufferUsage::STORAGE).is_argument());
+        assert!(!MTLBufferUsage::VERTEX.is_argument());
+        assert!(!MTLBufferUsage::UNIFORM.is_argument());
+    }
 }
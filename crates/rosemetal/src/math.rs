@@ -1,7 +1,12 @@
 use objc2_metal::MTLPrimitiveType as MetalMTLPrimitiveType;
+#[cfg(all(any(target_os = "macos", target_os = "ios"), not(feature = "moltenvk")))]
+use objc2_metal::{
+    MTLOrigin as MetalMTLOrigin, MTLRegion as MetalMTLRegion, MTLSize as MetalMTLSize,
+};
 
+/// Metal's `float3`, 16 bytes like `float4`.
 #[derive(Debug, Default, Clone, Copy)]
-#[repr(C)]
+#[repr(C, align(16))]
 pub struct MTLFloat3 {
     pub x: f32,
     pub y: f32,
@@ -14,6 +19,100 @@ impl MTLFloat3 {
     }
 }
 
+/// Metal's `packed_float3`, three tightly packed floats.
+#[derive(Debug, Default, Clone, Copy)]
+#[repr(C)]
+pub struct MTLPackedFloat3 {
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+}
+
+impl MTLPackedFloat3 {
+    pub fn new(x: f32, y: f32, z: f32) -> Self {
+        Self { x, y, z }
+    }
+}
+
+/// A texel position in a texture.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct MTLOrigin {
+    pub x: u32,
+    pub y: u32,
+    pub z: u32,
+}
+
+impl MTLOrigin {
+    pub fn new(x: u32, y: u32, z: u32) -> Self {
+        Self { x, y, z }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct MTLSize {
+    pub width: u32,
+    pub height: u32,
+    pub depth: u32,
+}
+
+impl MTLSize {
+    pub fn new(width: u32, height: u32, depth: u32) -> Self {
+        Self {
+            width,
+            height,
+            depth,
+        }
+    }
+
+    #[cfg(all(any(target_os = "macos", target_os = "ios"), not(feature = "moltenvk")))]
+    pub fn to_metal(&self) -> MetalMTLSize {
+        MetalMTLSize {
+            width: self.width as usize,
+            height: self.height as usize,
+            depth: self.depth as usize,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct MTLRegion {
+    pub origin: MTLOrigin,
+    pub size: MTLSize,
+}
+
+impl MTLRegion {
+    pub fn new_1d(x: u32, width: u32) -> Self {
+        Self::new_3d(x, 0, 0, width, 1, 1)
+    }
+
+    pub fn new_2d(x: u32, y: u32, width: u32, height: u32) -> Self {
+        Self::new_3d(x, y, 0, width, height, 1)
+    }
+
+    pub fn new_3d(x: u32, y: u32, z: u32, width: u32, height: u32, depth: u32) -> Self {
+        Self {
+            origin: MTLOrigin::new(x, y, z),
+            size: MTLSize::new(width, height, depth),
+        }
+    }
+
+    #[cfg(all(any(target_os = "macos", target_os = "ios"), not(feature = "moltenvk")))]
+    pub fn to_metal(&self) -> MetalMTLRegion {
+        MetalMTLRegion {
+            origin: MetalMTLOrigin {
+                x: self.origin.x as usize,
+                y: self.origin.y as usize,
+                z: self.origin.z as usize,
+            },
+            size: MetalMTLSize {
+                width: self.size.width as usize,
+                height: self.size.height as usize,
+                depth: self.size.depth as usize,
+            },
+        }
+    }
+}
+
 pub enum MTLPrimitiveType {
     Triangle,
 }
@@ -0,0 +1,1080 @@
+use crate::MTLDevice;
+
+#[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+use ash::vk;
+#[cfg(all(any(target_os = "macos", target_os = "ios"), not(feature = "moltenvk")))]
+use objc2_metal::MTLPixelFormat as MetalMTLPixelFormat;
+
+/// Formats of texture pixels. Variants follow Metal's names, `_sRGB`
+/// becomes `Srgb`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MTLPixelFormat {
+    #[default]
+    Invalid,
+
+    // Ordinary 8 bit formats.
+    A8Unorm,
+    R8Unorm,
+    R8UnormSrgb,
+    R8Snorm,
+    R8Uint,
+    R8Sint,
+
+    // Ordinary 16 bit formats.
+    R16Unorm,
+    R16Snorm,
+    R16Uint,
+    R16Sint,
+    R16Float,
+    Rg8Unorm,
+    Rg8UnormSrgb,
+    Rg8Snorm,
+    Rg8Uint,
+    Rg8Sint,
+
+    // Packed 16 bit formats.
+    B5g6r5Unorm,
+    A1bgr5Unorm,
+    Abgr4Unorm,
+    Bgr5a1Unorm,
+
+    // Ordinary 32 bit formats.
+    R32Uint,
+    R32Sint,
+    R32Float,
+    Rg16Unorm,
+    Rg16Snorm,
+    Rg16Uint,
+    Rg16Sint,
+    Rg16Float,
+    Rgba8Unorm,
+    Rgba8UnormSrgb,
+    Rgba8Snorm,
+    Rgba8Uint,
+    Rgba8Sint,
+    Bgra8Unorm,
+    Bgra8UnormSrgb,
+
+    // Packed 32 bit formats.
+    Rgb10a2Unorm,
+    Rgb10a2Uint,
+    Rg11b10Float,
+    Rgb9e5Float,
+    Bgr10a2Unorm,
+
+    // Ordinary 64 bit formats.
+    Rg32Uint,
+    Rg32Sint,
+    Rg32Float,
+    Rgba16Unorm,
+    Rgba16Snorm,
+    Rgba16Uint,
+    Rgba16Sint,
+    Rgba16Float,
+
+    // Ordinary 128 bit formats.
+    Rgba32Uint,
+    Rgba32Sint,
+    Rgba32Float,
+
+    // BC compressed formats.
+    Bc1Rgba,
+    Bc1RgbaSrgb,
+    Bc2Rgba,
+    Bc2RgbaSrgb,
+    Bc3Rgba,
+    Bc3RgbaSrgb,
+    Bc4RUnorm,
+    Bc4RSnorm,
+    Bc5RgUnorm,
+    Bc5RgSnorm,
+    Bc6hRgbFloat,
+    Bc6hRgbUfloat,
+    Bc7RgbaUnorm,
+    Bc7RgbaUnormSrgb,
+
+    // EAC and ETC2 compressed formats.
+    EacR11Unorm,
+    EacR11Snorm,
+    EacRg11Unorm,
+    EacRg11Snorm,
+    EacRgba8,
+    EacRgba8Srgb,
+    Etc2Rgb8,
+    Etc2Rgb8Srgb,
+    Etc2Rgb8a1,
+    Etc2Rgb8a1Srgb,
+
+    // ASTC compressed formats.
+    Astc4x4Srgb,
+    Astc4x4Ldr,
+    Astc4x4Hdr,
+    Astc5x4Srgb,
+    Astc5x4Ldr,
+    Astc5x4Hdr,
+    Astc5x5Srgb,
+    Astc5x5Ldr,
+    Astc5x5Hdr,
+    Astc6x5Srgb,
+    Astc6x5Ldr,
+    Astc6x5Hdr,
+    Astc6x6Srgb,
+    Astc6x6Ldr,
+    Astc6x6Hdr,
+    Astc8x5Srgb,
+    Astc8x5Ldr,
+    Astc8x5Hdr,
+    Astc8x6Srgb,
+    Astc8x6Ldr,
+    Astc8x6Hdr,
+    Astc8x8Srgb,
+    Astc8x8Ldr,
+    Astc8x8Hdr,
+    Astc10x5Srgb,
+    Astc10x5Ldr,
+    Astc10x5Hdr,
+    Astc10x6Srgb,
+    Astc10x6Ldr,
+    Astc10x6Hdr,
+    Astc10x8Srgb,
+    Astc10x8Ldr,
+    Astc10x8Hdr,
+    Astc10x10Srgb,
+    Astc10x10Ldr,
+    Astc10x10Hdr,
+    Astc12x10Srgb,
+    Astc12x10Ldr,
+    Astc12x10Hdr,
+    Astc12x12Srgb,
+    Astc12x12Ldr,
+    Astc12x12Hdr,
+
+    // Depth and stencil formats.
+    Depth16Unorm,
+    Depth32Float,
+    Stencil8,
+    Depth24UnormStencil8,
+    Depth32FloatStencil8,
+    X32Stencil8,
+    X24Stencil8,
+}
+
+impl MTLPixelFormat {
+    /// Bytes, width and height of a block, a single pixel for uncompressed
+    /// formats.
+    fn block(&self) -> (u32, u32, u32) {
+        match self {
+            Self::Invalid => (0, 1, 1),
+            Self::A8Unorm
+            | Self::R8Unorm
+            | Self::R8UnormSrgb
+            | Self::R8Snorm
+            | Self::R8Uint
+            | Self::R8Sint
+            | Self::Stencil8 => (1, 1, 1),
+            Self::R16Unorm
+            | Self::R16Snorm
+            | Self::R16Uint
+            | Self::R16Sint
+            | Self::R16Float
+            | Self::Rg8Unorm
+            | Self::Rg8UnormSrgb
+            | Self::Rg8Snorm
+            | Self::Rg8Uint
+            | Self::Rg8Sint
+            | Self::B5g6r5Unorm
+            | Self::A1bgr5Unorm
+            | Self::Abgr4Unorm
+            | Self::Bgr5a1Unorm
+            | Self::Depth16Unorm => (2, 1, 1),
+            Self::R32Uint
+            | Self::R32Sint
+            | Self::R32Float
+            | Self::Rg16Unorm
+            | Self::Rg16Snorm
+            | Self::Rg16Uint
+            | Self::Rg16Sint
+            | Self::Rg16Float
+            | Self::Rgba8Unorm
+            | Self::Rgba8UnormSrgb
+            | Self::Rgba8Snorm
+            | Self::Rgba8Uint
+            | Self::Rgba8Sint
+            | Self::Bgra8Unorm
+            | Self::Bgra8UnormSrgb
+            | Self::Rgb10a2Unorm
+            | Self::Rgb10a2Uint
+            | Self::Rg11b10Float
+            | Self::Rgb9e5Float
+            | Self::Bgr10a2Unorm
+            | Self::Depth32Float
+            | Self::Depth24UnormStencil8
+            | Self::X24Stencil8 => (4, 1, 1),
+            Self::Rg32Uint
+            | Self::Rg32Sint
+            | Self::Rg32Float
+            | Self::Rgba16Unorm
+            | Self::Rgba16Snorm
+            | Self::Rgba16Uint
+            | Self::Rgba16Sint
+            | Self::Rgba16Float
+            | Self::Depth32FloatStencil8
+            | Self::X32Stencil8 => (8, 1, 1),
+            Self::Rgba32Uint | Self::Rgba32Sint | Self::Rgba32Float => (16, 1, 1),
+            Self::Bc1Rgba
+            | Self::Bc1RgbaSrgb
+            | Self::Bc4RUnorm
+            | Self::Bc4RSnorm
+            | Self::EacR11Unorm
+            | Self::EacR11Snorm
+            | Self::Etc2Rgb8
+            | Self::Etc2Rgb8Srgb
+            | Self::Etc2Rgb8a1
+            | Self::Etc2Rgb8a1Srgb => (8, 4, 4),
+            Self::Bc2Rgba
+            | Self::Bc2RgbaSrgb
+            | Self::Bc3Rgba
+            | Self::Bc3RgbaSrgb
+            | Self::Bc5RgUnorm
+            | Self::Bc5RgSnorm
+            | Self::Bc6hRgbFloat
+            | Self::Bc6hRgbUfloat
+            | Self::Bc7RgbaUnorm
+            | Self::Bc7RgbaUnormSrgb
+            | Self::EacRg11Unorm
+            | Self::EacRg11Snorm
+            | Self::EacRgba8
+            | Self::EacRgba8Srgb
+            | Self::Astc4x4Srgb
+            | Self::Astc4x4Ldr
+            | Self::Astc4x4Hdr => (16, 4, 4),
+            Self::Astc5x4Srgb | Self::Astc5x4Ldr | Self::Astc5x4Hdr => (16, 5, 4),
+            Self::Astc5x5Srgb | Self::Astc5x5Ldr | Self::Astc5x5Hdr => (16, 5, 5),
+            Self::Astc6x5Srgb | Self::Astc6x5Ldr | Self::Astc6x5Hdr => (16, 6, 5),
+            Self::Astc6x6Srgb | Self::Astc6x6Ldr | Self::Astc6x6Hdr => (16, 6, 6),
+            Self::Astc8x5Srgb | Self::Astc8x5Ldr | Self::Astc8x5Hdr => (16, 8, 5),
+            Self::Astc8x6Srgb | Self::Astc8x6Ldr | Self::Astc8x6Hdr => (16, 8, 6),
+            Self::Astc8x8Srgb | Self::Astc8x8Ldr | Self::Astc8x8Hdr => (16, 8, 8),
+            Self::Astc10x5Srgb | Self::Astc10x5Ldr | Self::Astc10x5Hdr => (16, 10, 5),
+            Self::Astc10x6Srgb | Self::Astc10x6Ldr | Self::Astc10x6Hdr => (16, 10, 6),
+            Self::Astc10x8Srgb | Self::Astc10x8Ldr | Self::Astc10x8Hdr => (16, 10, 8),
+            Self::Astc10x10Srgb | Self::Astc10x10Ldr | Self::Astc10x10Hdr => (16, 10, 10),
+            Self::Astc12x10Srgb | Self::Astc12x10Ldr | Self::Astc12x10Hdr => (16, 12, 10),
+            Self::Astc12x12Srgb | Self::Astc12x12Ldr | Self::Astc12x12Hdr => (16, 12, 12),
+        }
+    }
+
+    pub fn bytes_per_block(&self) -> u32 {
+        self.block().0
+    }
+
+    pub fn block_width(&self) -> u32 {
+        self.block().1
+    }
+
+    pub fn block_height(&self) -> u32 {
+        self.block().2
+    }
+
+    /// Bytes in a row of blocks `width` pixels wide.
+    pub fn bytes_per_row(&self, width: u32) -> usize {
+        width.div_ceil(self.block_width()) as usize * self.bytes_per_block() as usize
+    }
+
+    /// Rows of blocks in `height` pixels.
+    pub fn block_rows(&self, height: u32) -> u32 {
+        height.div_ceil(self.block_height())
+    }
+
+    pub fn is_compressed(&self) -> bool {
+        matches!(
+            self,
+            Self::Bc1Rgba
+                | Self::Bc1RgbaSrgb
+                | Self::Bc2Rgba
+                | Self::Bc2RgbaSrgb
+                | Self::Bc3Rgba
+                | Self::Bc3RgbaSrgb
+                | Self::Bc4RUnorm
+                | Self::Bc4RSnorm
+                | Self::Bc5RgUnorm
+                | Self::Bc5RgSnorm
+                | Self::Bc6hRgbFloat
+                | Self::Bc6hRgbUfloat
+                | Self::Bc7RgbaUnorm
+                | Self::Bc7RgbaUnormSrgb
+                | Self::EacR11Unorm
+                | Self::EacR11Snorm
+                | Self::EacRg11Unorm
+                | Self::EacRg11Snorm
+                | Self::EacRgba8
+                | Self::EacRgba8Srgb
+                | Self::Etc2Rgb8
+                | Self::Etc2Rgb8Srgb
+                | Self::Etc2Rgb8a1
+                | Self::Etc2Rgb8a1Srgb
+                | Self::Astc4x4Srgb
+                | Self::Astc4x4Ldr
+                | Self::Astc4x4Hdr
+                | Self::Astc5x4Srgb
+                | Self::Astc5x4Ldr
+                | Self::Astc5x4Hdr
+                | Self::Astc5x5Srgb
+                | Self::Astc5x5Ldr
+                | Self::Astc5x5Hdr
+                | Self::Astc6x5Srgb
+                | Self::Astc6x5Ldr
+                | Self::Astc6x5Hdr
+                | Self::Astc6x6Srgb
+                | Self::Astc6x6Ldr
+                | Self::Astc6x6Hdr
+                | Self::Astc8x5Srgb
+                | Self::Astc8x5Ldr
+                | Self::Astc8x5Hdr
+                | Self::Astc8x6Srgb
+                | Self::Astc8x6Ldr
+                | Self::Astc8x6Hdr
+                | Self::Astc8x8Srgb
+                | Self::Astc8x8Ldr
+                | Self::Astc8x8Hdr
+                | Self::Astc10x5Srgb
+                | Self::Astc10x5Ldr
+                | Self::Astc10x5Hdr
+                | Self::Astc10x6Srgb
+                | Self::Astc10x6Ldr
+                | Self::Astc10x6Hdr
+                | Self::Astc10x8Srgb
+                | Self::Astc10x8Ldr
+                | Self::Astc10x8Hdr
+                | Self::Astc10x10Srgb
+                | Self::Astc10x10Ldr
+                | Self::Astc10x10Hdr
+                | Self::Astc12x10Srgb
+                | Self::Astc12x10Ldr
+                | Self::Astc12x10Hdr
+                | Self::Astc12x12Srgb
+                | Self::Astc12x12Ldr
+                | Self::Astc12x12Hdr
+        )
+    }
+
+    pub fn is_srgb(&self) -> bool {
+        matches!(
+            self,
+            Self::R8UnormSrgb
+                | Self::Rg8UnormSrgb
+                | Self::Rgba8UnormSrgb
+                | Self::Bgra8UnormSrgb
+                | Self::Bc1RgbaSrgb
+                | Self::Bc2RgbaSrgb
+                | Self::Bc3RgbaSrgb
+                | Self::Bc7RgbaUnormSrgb
+                | Self::EacRgba8Srgb
+                | Self::Etc2Rgb8Srgb
+                | Self::Etc2Rgb8a1Srgb
+                | Self::Astc4x4Srgb
+                | Self::Astc5x4Srgb
+                | Self::Astc5x5Srgb
+                | Self::Astc6x5Srgb
+                | Self::Astc6x6Srgb
+                | Self::Astc8x5Srgb
+                | Self::Astc8x6Srgb
+                | Self::Astc8x8Srgb
+                | Self::Astc10x5Srgb
+                | Self::Astc10x6Srgb
+                | Self::Astc10x8Srgb
+                | Self::Astc10x10Srgb
+                | Self::Astc12x10Srgb
+                | Self::Astc12x12Srgb
+        )
+    }
+
+    /// Formats shaders read as integers rather than floats.
+    pub fn is_integer(&self) -> bool {
+        matches!(
+            self,
+            Self::R8Uint
+                | Self::R8Sint
+                | Self::R16Uint
+                | Self::R16Sint
+                | Self::Rg8Uint
+                | Self::Rg8Sint
+                | Self::R32Uint
+                | Self::R32Sint
+                | Self::Rg16Uint
+                | Self::Rg16Sint
+                | Self::Rgba8Uint
+                | Self::Rgba8Sint
+                | Self::Rgb10a2Uint
+                | Self::Rg32Uint
+                | Self::Rg32Sint
+                | Self::Rgba16Uint
+                | Self::Rgba16Sint
+                | Self::Rgba32Uint
+                | Self::Rgba32Sint
+                | Self::Stencil8
+                | Self::X32Stencil8
+                | Self::X24Stencil8
+        )
+    }
+
+    pub fn is_depth(&self) -> bool {
+        matches!(
+            self,
+            Self::Depth16Unorm
+                | Self::Depth32Float
+                | Self::Depth24UnormStencil8
+                | Self::Depth32FloatStencil8
+        )
+    }
+
+    pub fn is_stencil(&self) -> bool {
+        matches!(
+            self,
+            Self::Stencil8
+                | Self::Depth24UnormStencil8
+                | Self::Depth32FloatStencil8
+                | Self::X32Stencil8
+                | Self::X24Stencil8
+        )
+    }
+
+    /// Whether the device can render to textures of this format.
+    pub fn is_renderable(&self, device: &MTLDevice) -> bool {
+        #[cfg(all(any(target_os = "macos", target_os = "ios"), not(feature = "moltenvk")))]
+        {
+            let _ = device;
+            return *self != Self::Invalid && !self.is_compressed() && *self != Self::Rgb9e5Float;
+        }
+
+        // `A8Unorm` is an `R8Unorm` image on Vulkan, writes would land in
+        // red.
+        #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+        return *self != Self::A8Unorm
+            && self.vulkan_format_features(device).intersects(
+                vk::FormatFeatureFlags::COLOR_ATTACHMENT
+                    | vk::FormatFeatureFlags::DEPTH_STENCIL_ATTACHMENT,
+            );
+    }
+
+    /// Whether the device can sample textures of this format with linear
+    /// filtering.
+    pub fn is_filterable(&self, device: &MTLDevice) -> bool {
+        // Metal has no query for this, these are the formats its feature
+        // tables don't list as filterable on every GPU.
+        #[cfg(all(any(target_os = "macos", target_os = "ios"), not(feature = "moltenvk")))]
+        {
+            let _ = device;
+            return *self != Self::Invalid
+                && !self.is_integer()
+                && !matches!(
+                    self,
+                    Self::R32Float
+                        | Self::Rg32Float
+                        | Self::Rgba32Float
+                        | Self::Depth32FloatStencil8
+                );
+        }
+
+        #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+        return self
+            .vulkan_format_features(device)
+            .contains(vk::FormatFeatureFlags::SAMPLED_IMAGE_FILTER_LINEAR);
+    }
+
+    /// What optimally tiled images of this format support on the device.
+    #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+    pub fn vulkan_format_features(&self, device: &MTLDevice) -> vk::FormatFeatureFlags {
+        if *self == Self::Invalid {
+            return vk::FormatFeatureFlags::empty();
+        }
+
+        unsafe {
+            device
+                .instance
+                .vulkan_instance()
+                .get_physical_device_format_properties(
+                    *device.vulkan_device().physical(),
+                    self.vulkan_format(device),
+                )
+                .optimal_tiling_features
+        }
+    }
+
+    /// The format images are created with. Unlike `to_vulkan`, `Stencil8`
+    /// is a combined format on devices that can't render to `S8_UINT`.
+    #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+    pub fn vulkan_format(&self, device: &MTLDevice) -> vk::Format {
+        match self {
+            Self::Stencil8 => device.vulkan_device().features().stencil8_format,
+            _ => self.to_vulkan(),
+        }
+    }
+
+    /// `A8Unorm` reads its red channel as alpha.
+    #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+    pub fn vulkan_component_mapping(&self) -> vk::ComponentMapping {
+        match self {
+            Self::A8Unorm => vk::ComponentMapping {
+                r: vk::ComponentSwizzle::ZERO,
+                g: vk::ComponentSwizzle::ZERO,
+                b: vk::ComponentSwizzle::ZERO,
+                a: vk::ComponentSwizzle::R,
+            },
+            _ => vk::ComponentMapping::default(),
+        }
+    }
+
+    /// Every aspect of the image, which barriers need when depth and
+    /// stencil share an image.
+    #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+    pub fn vulkan_image_aspect_mask(&self, device: &MTLDevice) -> vk::ImageAspectFlags {
+        match self.vulkan_format(device) {
+            vk::Format::D24_UNORM_S8_UINT | vk::Format::D32_SFLOAT_S8_UINT => {
+                vk::ImageAspectFlags::DEPTH | vk::ImageAspectFlags::STENCIL
+            }
+            _ => self.vulkan_aspect_mask(),
+        }
+    }
+
+    #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+    pub fn vulkan_aspect_mask(&self) -> vk::ImageAspectFlags {
+        let mut aspect = vk::ImageAspectFlags::empty();
+
+        if self.is_depth() {
+            aspect |= vk::ImageAspectFlags::DEPTH;
+        }
+        if self.is_stencil() {
+            aspect |= vk::ImageAspectFlags::STENCIL;
+        }
+
+        match aspect.is_empty() {
+            true => vk::ImageAspectFlags::COLOR,
+            false => aspect,
+        }
+    }
+
+    #[cfg(all(any(target_os = "macos", target_os = "ios"), not(feature = "moltenvk")))]
+    pub fn from_metal(metal_format: MetalMTLPixelFormat) -> Self {
+        match metal_format {
+            MetalMTLPixelFormat::A8Unorm => Self::A8Unorm,
+            MetalMTLPixelFormat::R8Unorm => Self::R8Unorm,
+            MetalMTLPixelFormat::R8Unorm_sRGB => Self::R8UnormSrgb,
+            MetalMTLPixelFormat::R8Snorm => Self::R8Snorm,
+            MetalMTLPixelFormat::R8Uint => Self::R8Uint,
+            MetalMTLPixelFormat::R8Sint => Self::R8Sint,
+            MetalMTLPixelFormat::R16Unorm => Self::R16Unorm,
+            MetalMTLPixelFormat::R16Snorm => Self::R16Snorm,
+            MetalMTLPixelFormat::R16Uint => Self::R16Uint,
+            MetalMTLPixelFormat::R16Sint => Self::R16Sint,
+            MetalMTLPixelFormat::R16Float => Self::R16Float,
+            MetalMTLPixelFormat::RG8Unorm => Self::Rg8Unorm,
+            MetalMTLPixelFormat::RG8Unorm_sRGB => Self::Rg8UnormSrgb,
+            MetalMTLPixelFormat::RG8Snorm => Self::Rg8Snorm,
+            MetalMTLPixelFormat::RG8Uint => Self::Rg8Uint,
+            MetalMTLPixelFormat::RG8Sint => Self::Rg8Sint,
+            MetalMTLPixelFormat::B5G6R5Unorm => Self::B5g6r5Unorm,
+            MetalMTLPixelFormat::A1BGR5Unorm => Self::A1bgr5Unorm,
+            MetalMTLPixelFormat::ABGR4Unorm => Self::Abgr4Unorm,
+            MetalMTLPixelFormat::BGR5A1Unorm => Self::Bgr5a1Unorm,
+            MetalMTLPixelFormat::R32Uint => Self::R32Uint,
+            MetalMTLPixelFormat::R32Sint => Self::R32Sint,
+            MetalMTLPixelFormat::R32Float => Self::R32Float,
+            MetalMTLPixelFormat::RG16Unorm => Self::Rg16Unorm,
+            MetalMTLPixelFormat::RG16Snorm => Self::Rg16Snorm,
+            MetalMTLPixelFormat::RG16Uint => Self::Rg16Uint,
+            MetalMTLPixelFormat::RG16Sint => Self::Rg16Sint,
+            MetalMTLPixelFormat::RG16Float => Self::Rg16Float,
+            MetalMTLPixelFormat::RGBA8Unorm => Self::Rgba8Unorm,
+            MetalMTLPixelFormat::RGBA8Unorm_sRGB => Self::Rgba8UnormSrgb,
+            MetalMTLPixelFormat::RGBA8Snorm => Self::Rgba8Snorm,
+            MetalMTLPixelFormat::RGBA8Uint => Self::Rgba8Uint,
+            MetalMTLPixelFormat::RGBA8Sint => Self::Rgba8Sint,
+            MetalMTLPixelFormat::BGRA8Unorm => Self::Bgra8Unorm,
+            MetalMTLPixelFormat::BGRA8Unorm_sRGB => Self::Bgra8UnormSrgb,
+            MetalMTLPixelFormat::RGB10A2Unorm => Self::Rgb10a2Unorm,
+            MetalMTLPixelFormat::RGB10A2Uint => Self::Rgb10a2Uint,
+            MetalMTLPixelFormat::RG11B10Float => Self::Rg11b10Float,
+            MetalMTLPixelFormat::RGB9E5Float => Self::Rgb9e5Float,
+            MetalMTLPixelFormat::BGR10A2Unorm => Self::Bgr10a2Unorm,
+            MetalMTLPixelFormat::RG32Uint => Self::Rg32Uint,
+            MetalMTLPixelFormat::RG32Sint => Self::Rg32Sint,
+            MetalMTLPixelFormat::RG32Float => Self::Rg32Float,
+            MetalMTLPixelFormat::RGBA16Unorm => Self::Rgba16Unorm,
+            MetalMTLPixelFormat::RGBA16Snorm => Self::Rgba16Snorm,
+            MetalMTLPixelFormat::RGBA16Uint => Self::Rgba16Uint,
+            MetalMTLPixelFormat::RGBA16Sint => Self::Rgba16Sint,
+            MetalMTLPixelFormat::RGBA16Float => Self::Rgba16Float,
+            MetalMTLPixelFormat::RGBA32Uint => Self::Rgba32Uint,
+            MetalMTLPixelFormat::RGBA32Sint => Self::Rgba32Sint,
+            MetalMTLPixelFormat::RGBA32Float => Self::Rgba32Float,
+            MetalMTLPixelFormat::BC1_RGBA => Self::Bc1Rgba,
+            MetalMTLPixelFormat::BC1_RGBA_sRGB => Self::Bc1RgbaSrgb,
+            MetalMTLPixelFormat::BC2_RGBA => Self::Bc2Rgba,
+            MetalMTLPixelFormat::BC2_RGBA_sRGB => Self::Bc2RgbaSrgb,
+            MetalMTLPixelFormat::BC3_RGBA => Self::Bc3Rgba,
+            MetalMTLPixelFormat::BC3_RGBA_sRGB => Self::Bc3RgbaSrgb,
+            MetalMTLPixelFormat::BC4_RUnorm => Self::Bc4RUnorm,
+            MetalMTLPixelFormat::BC4_RSnorm => Self::Bc4RSnorm,
+            MetalMTLPixelFormat::BC5_RGUnorm => Self::Bc5RgUnorm,
+            MetalMTLPixelFormat::BC5_RGSnorm => Self::Bc5RgSnorm,
+            MetalMTLPixelFormat::BC6H_RGBFloat => Self::Bc6hRgbFloat,
+            MetalMTLPixelFormat::BC6H_RGBUfloat => Self::Bc6hRgbUfloat,
+            MetalMTLPixelFormat::BC7_RGBAUnorm => Self::Bc7RgbaUnorm,
+            MetalMTLPixelFormat::BC7_RGBAUnorm_sRGB => Self::Bc7RgbaUnormSrgb,
+            MetalMTLPixelFormat::EAC_R11Unorm => Self::EacR11Unorm,
+            MetalMTLPixelFormat::EAC_R11Snorm => Self::EacR11Snorm,
+            MetalMTLPixelFormat::EAC_RG11Unorm => Self::EacRg11Unorm,
+            MetalMTLPixelFormat::EAC_RG11Snorm => Self::EacRg11Snorm,
+            MetalMTLPixelFormat::EAC_RGBA8 => Self::EacRgba8,
+            MetalMTLPixelFormat::EAC_RGBA8_sRGB => Self::EacRgba8Srgb,
+            MetalMTLPixelFormat::ETC2_RGB8 => Self::Etc2Rgb8,
+            MetalMTLPixelFormat::ETC2_RGB8_sRGB => Self::Etc2Rgb8Srgb,
+            MetalMTLPixelFormat::ETC2_RGB8A1 => Self::Etc2Rgb8a1,
+            MetalMTLPixelFormat::ETC2_RGB8A1_sRGB => Self::Etc2Rgb8a1Srgb,
+            MetalMTLPixelFormat::ASTC_4x4_sRGB => Self::Astc4x4Srgb,
+            MetalMTLPixelFormat::ASTC_4x4_LDR => Self::Astc4x4Ldr,
+            MetalMTLPixelFormat::ASTC_4x4_HDR => Self::Astc4x4Hdr,
+            MetalMTLPixelFormat::ASTC_5x4_sRGB => Self::Astc5x4Srgb,
+            MetalMTLPixelFormat::ASTC_5x4_LDR => Self::Astc5x4Ldr,
+            MetalMTLPixelFormat::ASTC_5x4_HDR => Self::Astc5x4Hdr,
+            MetalMTLPixelFormat::ASTC_5x5_sRGB => Self::Astc5x5Srgb,
+            MetalMTLPixelFormat::ASTC_5x5_LDR => Self::Astc5x5Ldr,
+            MetalMTLPixelFormat::ASTC_5x5_HDR => Self::Astc5x5Hdr,
+            MetalMTLPixelFormat::ASTC_6x5_sRGB => Self::Astc6x5Srgb,
+            MetalMTLPixelFormat::ASTC_6x5_LDR => Self::Astc6x5Ldr,
+            MetalMTLPixelFormat::ASTC_6x5_HDR => Self::Astc6x5Hdr,
+            MetalMTLPixelFormat::ASTC_6x6_sRGB => Self::Astc6x6Srgb,
+            MetalMTLPixelFormat::ASTC_6x6_LDR => Self::Astc6x6Ldr,
+            MetalMTLPixelFormat::ASTC_6x6_HDR => Self::Astc6x6Hdr,
+            MetalMTLPixelFormat::ASTC_8x5_sRGB => Self::Astc8x5Srgb,
+            MetalMTLPixelFormat::ASTC_8x5_LDR => Self::Astc8x5Ldr,
+            MetalMTLPixelFormat::ASTC_8x5_HDR => Self::Astc8x5Hdr,
+            MetalMTLPixelFormat::ASTC_8x6_sRGB => Self::Astc8x6Srgb,
+            MetalMTLPixelFormat::ASTC_8x6_LDR => Self::Astc8x6Ldr,
+            MetalMTLPixelFormat::ASTC_8x6_HDR => Self::Astc8x6Hdr,
+            MetalMTLPixelFormat::ASTC_8x8_sRGB => Self::Astc8x8Srgb,
+            MetalMTLPixelFormat::ASTC_8x8_LDR => Self::Astc8x8Ldr,
+            MetalMTLPixelFormat::ASTC_8x8_HDR => Self::Astc8x8Hdr,
+            MetalMTLPixelFormat::ASTC_10x5_sRGB => Self::Astc10x5Srgb,
+            MetalMTLPixelFormat::ASTC_10x5_LDR => Self::Astc10x5Ldr,
+            MetalMTLPixelFormat::ASTC_10x5_HDR => Self::Astc10x5Hdr,
+            MetalMTLPixelFormat::ASTC_10x6_sRGB => Self::Astc10x6Srgb,
+            MetalMTLPixelFormat::ASTC_10x6_LDR => Self::Astc10x6Ldr,
+            MetalMTLPixelFormat::ASTC_10x6_HDR => Self::Astc10x6Hdr,
+            MetalMTLPixelFormat::ASTC_10x8_sRGB => Self::Astc10x8Srgb,
+            MetalMTLPixelFormat::ASTC_10x8_LDR => Self::Astc10x8Ldr,
+            MetalMTLPixelFormat::ASTC_10x8_HDR => Self::Astc10x8Hdr,
+            MetalMTLPixelFormat::ASTC_10x10_sRGB => Self::Astc10x10Srgb,
+            MetalMTLPixelFormat::ASTC_10x10_LDR => Self::Astc10x10Ldr,
+            MetalMTLPixelFormat::ASTC_10x10_HDR => Self::Astc10x10Hdr,
+            MetalMTLPixelFormat::ASTC_12x10_sRGB => Self::Astc12x10Srgb,
+            MetalMTLPixelFormat::ASTC_12x10_LDR => Self::Astc12x10Ldr,
+            MetalMTLPixelFormat::ASTC_12x10_HDR => Self::Astc12x10Hdr,
+            MetalMTLPixelFormat::ASTC_12x12_sRGB => Self::Astc12x12Srgb,
+            MetalMTLPixelFormat::ASTC_12x12_LDR => Self::Astc12x12Ldr,
+            MetalMTLPixelFormat::ASTC_12x12_HDR => Self::Astc12x12Hdr,
+            MetalMTLPixelFormat::Depth16Unorm => Self::Depth16Unorm,
+            MetalMTLPixelFormat::Depth32Float => Self::Depth32Float,
+            MetalMTLPixelFormat::Stencil8 => Self::Stencil8,
+            MetalMTLPixelFormat::Depth24Unorm_Stencil8 => Self::Depth24UnormStencil8,
+            MetalMTLPixelFormat::Depth32Float_Stencil8 => Self::Depth32FloatStencil8,
+            MetalMTLPixelFormat::X32_Stencil8 => Self::X32Stencil8,
+            MetalMTLPixelFormat::X24_Stencil8 => Self::X24Stencil8,
+            _ => Self::Invalid,
+        }
+    }
+
+    #[cfg(all(any(target_os = "macos", target_os = "ios"), not(feature = "moltenvk")))]
+    pub fn to_metal(&self) -> MetalMTLPixelFormat {
+        match self {
+            Self::Invalid => MetalMTLPixelFormat::Invalid,
+            Self::A8Unorm => MetalMTLPixelFormat::A8Unorm,
+            Self::R8Unorm => MetalMTLPixelFormat::R8Unorm,
+            Self::R8UnormSrgb => MetalMTLPixelFormat::R8Unorm_sRGB,
+            Self::R8Snorm => MetalMTLPixelFormat::R8Snorm,
+            Self::R8Uint => MetalMTLPixelFormat::R8Uint,
+            Self::R8Sint => MetalMTLPixelFormat::R8Sint,
+            Self::R16Unorm => MetalMTLPixelFormat::R16Unorm,
+            Self::R16Snorm => MetalMTLPixelFormat::R16Snorm,
+            Self::R16Uint => MetalMTLPixelFormat::R16Uint,
+            Self::R16Sint => MetalMTLPixelFormat::R16Sint,
+            Self::R16Float => MetalMTLPixelFormat::R16Float,
+            Self::Rg8Unorm => MetalMTLPixelFormat::RG8Unorm,
+            Self::Rg8UnormSrgb => MetalMTLPixelFormat::RG8Unorm_sRGB,
+            Self::Rg8Snorm => MetalMTLPixelFormat::RG8Snorm,
+            Self::Rg8Uint => MetalMTLPixelFormat::RG8Uint,
+            Self::Rg8Sint => MetalMTLPixelFormat::RG8Sint,
+            Self::B5g6r5Unorm => MetalMTLPixelFormat::B5G6R5Unorm,
+            Self::A1bgr5Unorm => MetalMTLPixelFormat::A1BGR5Unorm,
+            Self::Abgr4Unorm => MetalMTLPixelFormat::ABGR4Unorm,
+            Self::Bgr5a1Unorm => MetalMTLPixelFormat::BGR5A1Unorm,
+            Self::R32Uint => MetalMTLPixelFormat::R32Uint,
+            Self::R32Sint => MetalMTLPixelFormat::R32Sint,
+            Self::R32Float => MetalMTLPixelFormat::R32Float,
+            Self::Rg16Unorm => MetalMTLPixelFormat::RG16Unorm,
+            Self::Rg16Snorm => MetalMTLPixelFormat::RG16Snorm,
+            Self::Rg16Uint => MetalMTLPixelFormat::RG16Uint,
+            Self::Rg16Sint => MetalMTLPixelFormat::RG16Sint,
+            Self::Rg16Float => MetalMTLPixelFormat::RG16Float,
+            Self::Rgba8Unorm => MetalMTLPixelFormat::RGBA8Unorm,
+            Self::Rgba8UnormSrgb => MetalMTLPixelFormat::RGBA8Unorm_sRGB,
+            Self::Rgba8Snorm => MetalMTLPixelFormat::RGBA8Snorm,
+            Self::Rgba8Uint => MetalMTLPixelFormat::RGBA8Uint,
+            Self::Rgba8Sint => MetalMTLPixelFormat::RGBA8Sint,
+            Self::Bgra8Unorm => MetalMTLPixelFormat::BGRA8Unorm,
+            Self::Bgra8UnormSrgb => MetalMTLPixelFormat::BGRA8Unorm_sRGB,
+            Self::Rgb10a2Unorm => MetalMTLPixelFormat::RGB10A2Unorm,
+            Self::Rgb10a2Uint => MetalMTLPixelFormat::RGB10A2Uint,
+            Self::Rg11b10Float => MetalMTLPixelFormat::RG11B10Float,
+            Self::Rgb9e5Float => MetalMTLPixelFormat::RGB9E5Float,
+            Self::Bgr10a2Unorm => MetalMTLPixelFormat::BGR10A2Unorm,
+            Self::Rg32Uint => MetalMTLPixelFormat::RG32Uint,
+            Self::Rg32Sint => MetalMTLPixelFormat::RG32Sint,
+            Self::Rg32Float => MetalMTLPixelFormat::RG32Float,
+            Self::Rgba16Unorm => MetalMTLPixelFormat::RGBA16Unorm,
+            Self::Rgba16Snorm => MetalMTLPixelFormat::RGBA16Snorm,
+            Self::Rgba16Uint => MetalMTLPixelFormat::RGBA16Uint,
+            Self::Rgba16Sint => MetalMTLPixelFormat::RGBA16Sint,
+            Self::Rgba16Float => MetalMTLPixelFormat::RGBA16Float,
+            Self::Rgba32Uint => MetalMTLPixelFormat::RGBA32Uint,
+            Self::Rgba32Sint => MetalMTLPixelFormat::RGBA32Sint,
+            Self::Rgba32Float => MetalMTLPixelFormat::RGBA32Float,
+            Self::Bc1Rgba => MetalMTLPixelFormat::BC1_RGBA,
+            Self::Bc1RgbaSrgb => MetalMTLPixelFormat::BC1_RGBA_sRGB,
+            Self::Bc2Rgba => MetalMTLPixelFormat::BC2_RGBA,
+            Self::Bc2RgbaSrgb => MetalMTLPixelFormat::BC2_RGBA_sRGB,
+            Self::Bc3Rgba => MetalMTLPixelFormat::BC3_RGBA,
+            Self::Bc3RgbaSrgb => MetalMTLPixelFormat::BC3_RGBA_sRGB,
+            Self::Bc4RUnorm => MetalMTLPixelFormat::BC4_RUnorm,
+            Self::Bc4RSnorm => MetalMTLPixelFormat::BC4_RSnorm,
+            Self::Bc5RgUnorm => MetalMTLPixelFormat::BC5_RGUnorm,
+            Self::Bc5RgSnorm => MetalMTLPixelFormat::BC5_RGSnorm,
+            Self::Bc6hRgbFloat => MetalMTLPixelFormat::BC6H_RGBFloat,
+            Self::Bc6hRgbUfloat => MetalMTLPixelFormat::BC6H_RGBUfloat,
+            Self::Bc7RgbaUnorm => MetalMTLPixelFormat::BC7_RGBAUnorm,
+            Self::Bc7RgbaUnormSrgb => MetalMTLPixelFormat::BC7_RGBAUnorm_sRGB,
+            Self::EacR11Unorm => MetalMTLPixelFormat::EAC_R11Unorm,
+            Self::EacR11Snorm => MetalMTLPixelFormat::EAC_R11Snorm,
+            Self::EacRg11Unorm => MetalMTLPixelFormat::EAC_RG11Unorm,
+            Self::EacRg11Snorm => MetalMTLPixelFormat::EAC_RG11Snorm,
+            Self::EacRgba8 => MetalMTLPixelFormat::EAC_RGBA8,
+            Self::EacRgba8Srgb => MetalMTLPixelFormat::EAC_RGBA8_sRGB,
+            Self::Etc2Rgb8 => MetalMTLPixelFormat::ETC2_RGB8,
+            Self::Etc2Rgb8Srgb => MetalMTLPixelFormat::ETC2_RGB8_sRGB,
+            Self::Etc2Rgb8a1 => MetalMTLPixelFormat::ETC2_RGB8A1,
+            Self::Etc2Rgb8a1Srgb => MetalMTLPixelFormat::ETC2_RGB8A1_sRGB,
+            Self::Astc4x4Srgb => MetalMTLPixelFormat::ASTC_4x4_sRGB,
+            Self::Astc4x4Ldr => MetalMTLPixelFormat::ASTC_4x4_LDR,
+            Self::Astc4x4Hdr => MetalMTLPixelFormat::ASTC_4x4_HDR,
+            Self::Astc5x4Srgb => MetalMTLPixelFormat::ASTC_5x4_sRGB,
+            Self::Astc5x4Ldr => MetalMTLPixelFormat::ASTC_5x4_LDR,
+            Self::Astc5x4Hdr => MetalMTLPixelFormat::ASTC_5x4_HDR,
+            Self::Astc5x5Srgb => MetalMTLPixelFormat::ASTC_5x5_sRGB,
+            Self::Astc5x5Ldr => MetalMTLPixelFormat::ASTC_5x5_LDR,
+            Self::Astc5x5Hdr => MetalMTLPixelFormat::ASTC_5x5_HDR,
+            Self::Astc6x5Srgb => MetalMTLPixelFormat::ASTC_6x5_sRGB,
+            Self::Astc6x5Ldr => MetalMTLPixelFormat::ASTC_6x5_LDR,
+            Self::Astc6x5Hdr => MetalMTLPixelFormat::ASTC_6x5_HDR,
+            Self::Astc6x6Srgb => MetalMTLPixelFormat::ASTC_6x6_sRGB,
+            Self::Astc6x6Ldr => MetalMTLPixelFormat::ASTC_6x6_LDR,
+            Self::Astc6x6Hdr => MetalMTLPixelFormat::ASTC_6x6_HDR,
+            Self::Astc8x5Srgb => MetalMTLPixelFormat::ASTC_8x5_sRGB,
+            Self::Astc8x5Ldr => MetalMTLPixelFormat::ASTC_8x5_LDR,
+            Self::Astc8x5Hdr => MetalMTLPixelFormat::ASTC_8x5_HDR,
+            Self::Astc8x6Srgb => MetalMTLPixelFormat::ASTC_8x6_sRGB,
+            Self::Astc8x6Ldr => MetalMTLPixelFormat::ASTC_8x6_LDR,
+            Self::Astc8x6Hdr => MetalMTLPixelFormat::ASTC_8x6_HDR,
+            Self::Astc8x8Srgb => MetalMTLPixelFormat::ASTC_8x8_sRGB,
+            Self::Astc8x8Ldr => MetalMTLPixelFormat::ASTC_8x8_LDR,
+            Self::Astc8x8Hdr => MetalMTLPixelFormat::ASTC_8x8_HDR,
+            Self::Astc10x5Srgb => MetalMTLPixelFormat::ASTC_10x5_sRGB,
+            Self::Astc10x5Ldr => MetalMTLPixelFormat::ASTC_10x5_LDR,
+            Self::Astc10x5Hdr => MetalMTLPixelFormat::ASTC_10x5_HDR,
+            Self::Astc10x6Srgb => MetalMTLPixelFormat::ASTC_10x6_sRGB,
+            Self::Astc10x6Ldr => MetalMTLPixelFormat::ASTC_10x6_LDR,
+            Self::Astc10x6Hdr => MetalMTLPixelFormat::ASTC_10x6_HDR,
+            Self::Astc10x8Srgb => MetalMTLPixelFormat::ASTC_10x8_sRGB,
+            Self::Astc10x8Ldr => MetalMTLPixelFormat::ASTC_10x8_LDR,
+            Self::Astc10x8Hdr => MetalMTLPixelFormat::ASTC_10x8_HDR,
+            Self::Astc10x10Srgb => MetalMTLPixelFormat::ASTC_10x10_sRGB,
+            Self::Astc10x10Ldr => MetalMTLPixelFormat::ASTC_10x10_LDR,
+            Self::Astc10x10Hdr => MetalMTLPixelFormat::ASTC_10x10_HDR,
+            Self::Astc12x10Srgb => MetalMTLPixelFormat::ASTC_12x10_sRGB,
+            Self::Astc12x10Ldr => MetalMTLPixelFormat::ASTC_12x10_LDR,
+            Self::Astc12x10Hdr => MetalMTLPixelFormat::ASTC_12x10_HDR,
+            Self::Astc12x12Srgb => MetalMTLPixelFormat::ASTC_12x12_sRGB,
+            Self::Astc12x12Ldr => MetalMTLPixelFormat::ASTC_12x12_LDR,
+            Self::Astc12x12Hdr => MetalMTLPixelFormat::ASTC_12x12_HDR,
+            Self::Depth16Unorm => MetalMTLPixelFormat::Depth16Unorm,
+            Self::Depth32Float => MetalMTLPixelFormat::Depth32Float,
+            Self::Stencil8 => MetalMTLPixelFormat::Stencil8,
+            Self::Depth24UnormStencil8 => MetalMTLPixelFormat::Depth24Unorm_Stencil8,
+            Self::Depth32FloatStencil8 => MetalMTLPixelFormat::Depth32Float_Stencil8,
+            Self::X32Stencil8 => MetalMTLPixelFormat::X32_Stencil8,
+            Self::X24Stencil8 => MetalMTLPixelFormat::X24_Stencil8,
+        }
+    }
+
+    /// `X32Stencil8` and `X24Stencil8` come back as the combined formats
+    /// they're views of.
+    #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+    pub fn from_vulkan(vulkan_format: vk::Format) -> Self {
+        match vulkan_format {
+            vk::Format::R8_UNORM => Self::R8Unorm,
+            vk::Format::R8_SRGB => Self::R8UnormSrgb,
+            vk::Format::R8_SNORM => Self::R8Snorm,
+            vk::Format::R8_UINT => Self::R8Uint,
+            vk::Format::R8_SINT => Self::R8Sint,
+            vk::Format::R16_UNORM => Self::R16Unorm,
+            vk::Format::R16_SNORM => Self::R16Snorm,
+            vk::Format::R16_UINT => Self::R16Uint,
+            vk::Format::R16_SINT => Self::R16Sint,
+            vk::Format::R16_SFLOAT => Self::R16Float,
+            vk::Format::R8G8_UNORM => Self::Rg8Unorm,
+            vk::Format::R8G8_SRGB => Self::Rg8UnormSrgb,
+            vk::Format::R8G8_SNORM => Self::Rg8Snorm,
+            vk::Format::R8G8_UINT => Self::Rg8Uint,
+            vk::Format::R8G8_SINT => Self::Rg8Sint,
+            vk::Format::R5G6B5_UNORM_PACK16 => Self::B5g6r5Unorm,
+            vk::Format::R5G5B5A1_UNORM_PACK16 => Self::A1bgr5Unorm,
+            vk::Format::R4G4B4A4_UNORM_PACK16 => Self::Abgr4Unorm,
+            vk::Format::A1R5G5B5_UNORM_PACK16 => Self::Bgr5a1Unorm,
+            vk::Format::R32_UINT => Self::R32Uint,
+            vk::Format::R32_SINT => Self::R32Sint,
+            vk::Format::R32_SFLOAT => Self::R32Float,
+            vk::Format::R16G16_UNORM => Self::Rg16Unorm,
+            vk::Format::R16G16_SNORM => Self::Rg16Snorm,
+            vk::Format::R16G16_UINT => Self::Rg16Uint,
+            vk::Format::R16G16_SINT => Self::Rg16Sint,
+            vk::Format::R16G16_SFLOAT => Self::Rg16Float,
+            vk::Format::R8G8B8A8_UNORM => Self::Rgba8Unorm,
+            vk::Format::R8G8B8A8_SRGB => Self::Rgba8UnormSrgb,
+            vk::Format::R8G8B8A8_SNORM => Self::Rgba8Snorm,
+            vk::Format::R8G8B8A8_UINT => Self::Rgba8Uint,
+            vk::Format::R8G8B8A8_SINT => Self::Rgba8Sint,
+            vk::Format::B8G8R8A8_UNORM => Self::Bgra8Unorm,
+            vk::Format::B8G8R8A8_SRGB => Self::Bgra8UnormSrgb,
+            vk::Format::A2B10G10R10_UNORM_PACK32 => Self::Rgb10a2Unorm,
+            vk::Format::A2B10G10R10_UINT_PACK32 => Self::Rgb10a2Uint,
+            vk::Format::B10G11R11_UFLOAT_PACK32 => Self::Rg11b10Float,
+            vk::Format::E5B9G9R9_UFLOAT_PACK32 => Self::Rgb9e5Float,
+            vk::Format::A2R10G10B10_UNORM_PACK32 => Self::Bgr10a2Unorm,
+            vk::Format::R32G32_UINT => Self::Rg32Uint,
+            vk::Format::R32G32_SINT => Self::Rg32Sint,
+            vk::Format::R32G32_SFLOAT => Self::Rg32Float,
+            vk::Format::R16G16B16A16_UNORM => Self::Rgba16Unorm,
+            vk::Format::R16G16B16A16_SNORM => Self::Rgba16Snorm,
+            vk::Format::R16G16B16A16_UINT => Self::Rgba16Uint,
+            vk::Format::R16G16B16A16_SINT => Self::Rgba16Sint,
+            vk::Format::R16G16B16A16_SFLOAT => Self::Rgba16Float,
+            vk::Format::R32G32B32A32_UINT => Self::Rgba32Uint,
+            vk::Format::R32G32B32A32_SINT => Self::Rgba32Sint,
+            vk::Format::R32G32B32A32_SFLOAT => Self::Rgba32Float,
+            vk::Format::BC1_RGBA_UNORM_BLOCK => Self::Bc1Rgba,
+            vk::Format::BC1_RGBA_SRGB_BLOCK => Self::Bc1RgbaSrgb,
+            vk::Format::BC2_UNORM_BLOCK => Self::Bc2Rgba,
+            vk::Format::BC2_SRGB_BLOCK => Self::Bc2RgbaSrgb,
+            vk::Format::BC3_UNORM_BLOCK => Self::Bc3Rgba,
+            vk::Format::BC3_SRGB_BLOCK => Self::Bc3RgbaSrgb,
+            vk::Format::BC4_UNORM_BLOCK => Self::Bc4RUnorm,
+            vk::Format::BC4_SNORM_BLOCK => Self::Bc4RSnorm,
+            vk::Format::BC5_UNORM_BLOCK => Self::Bc5RgUnorm,
+            vk::Format::BC5_SNORM_BLOCK => Self::Bc5RgSnorm,
+            vk::Format::BC6H_SFLOAT_BLOCK => Self::Bc6hRgbFloat,
+            vk::Format::BC6H_UFLOAT_BLOCK => Self::Bc6hRgbUfloat,
+            vk::Format::BC7_UNORM_BLOCK => Self::Bc7RgbaUnorm,
+            vk::Format::BC7_SRGB_BLOCK => Self::Bc7RgbaUnormSrgb,
+            vk::Format::EAC_R11_UNORM_BLOCK => Self::EacR11Unorm,
+            vk::Format::EAC_R11_SNORM_BLOCK => Self::EacR11Snorm,
+            vk::Format::EAC_R11G11_UNORM_BLOCK => Self::EacRg11Unorm,
+            vk::Format::EAC_R11G11_SNORM_BLOCK => Self::EacRg11Snorm,
+            vk::Format::ETC2_R8G8B8A8_UNORM_BLOCK => Self::EacRgba8,
+            vk::Format::ETC2_R8G8B8A8_SRGB_BLOCK => Self::EacRgba8Srgb,
+            vk::Format::ETC2_R8G8B8_UNORM_BLOCK => Self::Etc2Rgb8,
+            vk::Format::ETC2_R8G8B8_SRGB_BLOCK => Self::Etc2Rgb8Srgb,
+            vk::Format::ETC2_R8G8B8A1_UNORM_BLOCK => Self::Etc2Rgb8a1,
+            vk::Format::ETC2_R8G8B8A1_SRGB_BLOCK => Self::Etc2Rgb8a1Srgb,
+            vk::Format::ASTC_4X4_SRGB_BLOCK => Self::Astc4x4Srgb,
+            vk::Format::ASTC_4X4_UNORM_BLOCK => Self::Astc4x4Ldr,
+            vk::Format::ASTC_4X4_SFLOAT_BLOCK => Self::Astc4x4Hdr,
+            vk::Format::ASTC_5X4_SRGB_BLOCK => Self::Astc5x4Srgb,
+            vk::Format::ASTC_5X4_UNORM_BLOCK => Self::Astc5x4Ldr,
+            vk::Format::ASTC_5X4_SFLOAT_BLOCK => Self::Astc5x4Hdr,
+            vk::Format::ASTC_5X5_SRGB_BLOCK => Self::Astc5x5Srgb,
+            vk::Format::ASTC_5X5_UNORM_BLOCK => Self::Astc5x5Ldr,
+            vk::Format::ASTC_5X5_SFLOAT_BLOCK => Self::Astc5x5Hdr,
+            vk::Format::ASTC_6X5_SRGB_BLOCK => Self::Astc6x5Srgb,
+            vk::Format::ASTC_6X5_UNORM_BLOCK => Self::Astc6x5Ldr,
+            vk::Format::ASTC_6X5_SFLOAT_BLOCK => Self::Astc6x5Hdr,
+            vk::Format::ASTC_6X6_SRGB_BLOCK => Self::Astc6x6Srgb,
+            vk::Format::ASTC_6X6_UNORM_BLOCK => Self::Astc6x6Ldr,
+            vk::Format::ASTC_6X6_SFLOAT_BLOCK => Self::Astc6x6Hdr,
+            vk::Format::ASTC_8X5_SRGB_BLOCK => Self::Astc8x5Srgb,
+            vk::Format::ASTC_8X5_UNORM_BLOCK => Self::Astc8x5Ldr,
+            vk::Format::ASTC_8X5_SFLOAT_BLOCK => Self::Astc8x5Hdr,
+            vk::Format::ASTC_8X6_SRGB_BLOCK => Self::Astc8x6Srgb,
+            vk::Format::ASTC_8X6_UNORM_BLOCK => Self::Astc8x6Ldr,
+            vk::Format::ASTC_8X6_SFLOAT_BLOCK => Self::Astc8x6Hdr,
+            vk::Format::ASTC_8X8_SRGB_BLOCK => Self::Astc8x8Srgb,
+            vk::Format::ASTC_8X8_UNORM_BLOCK => Self::Astc8x8Ldr,
+            vk::Format::ASTC_8X8_SFLOAT_BLOCK => Self::Astc8x8Hdr,
+            vk::Format::ASTC_10X5_SRGB_BLOCK => Self::Astc10x5Srgb,
+            vk::Format::ASTC_10X5_UNORM_BLOCK => Self::Astc10x5Ldr,
+            vk::Format::ASTC_10X5_SFLOAT_BLOCK => Self::Astc10x5Hdr,
+            vk::Format::ASTC_10X6_SRGB_BLOCK => Self::Astc10x6Srgb,
+            vk::Format::ASTC_10X6_UNORM_BLOCK => Self::Astc10x6Ldr,
+            vk::Format::ASTC_10X6_SFLOAT_BLOCK => Self::Astc10x6Hdr,
+            vk::Format::ASTC_10X8_SRGB_BLOCK => Self::Astc10x8Srgb,
+            vk::Format::ASTC_10X8_UNORM_BLOCK => Self::Astc10x8Ldr,
+            vk::Format::ASTC_10X8_SFLOAT_BLOCK => Self::Astc10x8Hdr,
+            vk::Format::ASTC_10X10_SRGB_BLOCK => Self::Astc10x10Srgb,
+            vk::Format::ASTC_10X10_UNORM_BLOCK => Self::Astc10x10Ldr,
+            vk::Format::ASTC_10X10_SFLOAT_BLOCK => Self::Astc10x10Hdr,
+            vk::Format::ASTC_12X10_SRGB_BLOCK => Self::Astc12x10Srgb,
+            vk::Format::ASTC_12X10_UNORM_BLOCK => Self::Astc12x10Ldr,
+            vk::Format::ASTC_12X10_SFLOAT_BLOCK => Self::Astc12x10Hdr,
+            vk::Format::ASTC_12X12_SRGB_BLOCK => Self::Astc12x12Srgb,
+            vk::Format::ASTC_12X12_UNORM_BLOCK => Self::Astc12x12Ldr,
+            vk::Format::ASTC_12X12_SFLOAT_BLOCK => Self::Astc12x12Hdr,
+            vk::Format::D16_UNORM => Self::Depth16Unorm,
+            vk::Format::D32_SFLOAT => Self::Depth32Float,
+            vk::Format::S8_UINT => Self::Stencil8,
+            vk::Format::D24_UNORM_S8_UINT => Self::Depth24UnormStencil8,
+            vk::Format::D32_SFLOAT_S8_UINT => Self::Depth32FloatStencil8,
+            _ => Self::Invalid,
+        }
+    }
+
+    /// Stencil only formats are the combined format, viewed through the
+    /// stencil aspect. `A8Unorm` is `R8_UNORM` swizzled to alpha, as
+    /// `A8_UNORM_KHR` needs `VK_KHR_maintenance5`.
+    #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+    pub fn to_vulkan(&self) -> vk::Format {
+        match self {
+            Self::Invalid => vk::Format::UNDEFINED,
+            Self::A8Unorm => vk::Format::R8_UNORM,
+            Self::R8Unorm => vk::Format::R8_UNORM,
+            Self::R8UnormSrgb => vk::Format::R8_SRGB,
+            Self::R8Snorm => vk::Format::R8_SNORM,
+            Self::R8Uint => vk::Format::R8_UINT,
+            Self::R8Sint => vk::Format::R8_SINT,
+            Self::R16Unorm => vk::Format::R16_UNORM,
+            Self::R16Snorm => vk::Format::R16_SNORM,
+            Self::R16Uint => vk::Format::R16_UINT,
+            Self::R16Sint => vk::Format::R16_SINT,
+            Self::R16Float => vk::Format::R16_SFLOAT,
+            Self::Rg8Unorm => vk::Format::R8G8_UNORM,
+            Self::Rg8UnormSrgb => vk::Format::R8G8_SRGB,
+            Self::Rg8Snorm => vk::Format::R8G8_SNORM,
+            Self::Rg8Uint => vk::Format::R8G8_UINT,
+            Self::Rg8Sint => vk::Format::R8G8_SINT,
+            Self::B5g6r5Unorm => vk::Format::R5G6B5_UNORM_PACK16,
+            Self::A1bgr5Unorm => vk::Format::R5G5B5A1_UNORM_PACK16,
+            Self::Abgr4Unorm => vk::Format::R4G4B4A4_UNORM_PACK16,
+            Self::Bgr5a1Unorm => vk::Format::A1R5G5B5_UNORM_PACK16,
+            Self::R32Uint => vk::Format::R32_UINT,
+            Self::R32Sint => vk::Format::R32_SINT,
+            Self::R32Float => vk::Format::R32_SFLOAT,
+            Self::Rg16Unorm => vk::Format::R16G16_UNORM,
+            Self::Rg16Snorm => vk::Format::R16G16_SNORM,
+            Self::Rg16Uint => vk::Format::R16G16_UINT,
+            Self::Rg16Sint => vk::Format::R16G16_SINT,
+            Self::Rg16Float => vk::Format::R16G16_SFLOAT,
+            Self::Rgba8Unorm => vk::Format::R8G8B8A8_UNORM,
+            Self::Rgba8UnormSrgb => vk::Format::R8G8B8A8_SRGB,
+            Self::Rgba8Snorm => vk::Format::R8G8B8A8_SNORM,
+            Self::Rgba8Uint => vk::Format::R8G8B8A8_UINT,
+            Self::Rgba8Sint => vk::Format::R8G8B8A8_SINT,
+            Self::Bgra8Unorm => vk::Format::B8G8R8A8_UNORM,
+            Self::Bgra8UnormSrgb => vk::Format::B8G8R8A8_SRGB,
+            Self::Rgb10a2Unorm => vk::Format::A2B10G10R10_UNORM_PACK32,
+            Self::Rgb10a2Uint => vk::Format::A2B10G10R10_UINT_PACK32,
+            Self::Rg11b10Float => vk::Format::B10G11R11_UFLOAT_PACK32,
+            Self::Rgb9e5Float => vk::Format::E5B9G9R9_UFLOAT_PACK32,
+            Self::Bgr10a2Unorm => vk::Format::A2R10G10B10_UNORM_PACK32,
+            Self::Rg32Uint => vk::Format::R32G32_UINT,
+            Self::Rg32Sint => vk::Format::R32G32_SINT,
+            Self::Rg32Float => vk::Format::R32G32_SFLOAT,
+            Self::Rgba16Unorm => vk::Format::R16G16B16A16_UNORM,
+            Self::Rgba16Snorm => vk::Format::R16G16B16A16_SNORM,
+            Self::Rgba16Uint => vk::Format::R16G16B16A16_UINT,
+            Self::Rgba16Sint => vk::Format::R16G16B16A16_SINT,
+            Self::Rgba16Float => vk::Format::R16G16B16A16_SFLOAT,
+            Self::Rgba32Uint => vk::Format::R32G32B32A32_UINT,
+            Self::Rgba32Sint => vk::Format::R32G32B32A32_SINT,
+            Self::Rgba32Float => vk::Format::R32G32B32A32_SFLOAT,
+            Self::Bc1Rgba => vk::Format::BC1_RGBA_UNORM_BLOCK,
+            Self::Bc1RgbaSrgb => vk::Format::BC1_RGBA_SRGB_BLOCK,
+            Self::Bc2Rgba => vk::Format::BC2_UNORM_BLOCK,
+            Self::Bc2RgbaSrgb => vk::Format::BC2_SRGB_BLOCK,
+            Self::Bc3Rgba => vk::Format::BC3_UNORM_BLOCK,
+            Self::Bc3RgbaSrgb => vk::Format::BC3_SRGB_BLOCK,
+            Self::Bc4RUnorm => vk::Format::BC4_UNORM_BLOCK,
+            Self::Bc4RSnorm => vk::Format::BC4_SNORM_BLOCK,
+            Self::Bc5RgUnorm => vk::Format::BC5_UNORM_BLOCK,
+            Self::Bc5RgSnorm => vk::Format::BC5_SNORM_BLOCK,
+            Self::Bc6hRgbFloat => vk::Format::BC6H_SFLOAT_BLOCK,
+            Self::Bc6hRgbUfloat => vk::Format::BC6H_UFLOAT_BLOCK,
+            Self::Bc7RgbaUnorm => vk::Format::BC7_UNORM_BLOCK,
+            Self::Bc7RgbaUnormSrgb => vk::Format::BC7_SRGB_BLOCK,
+            Self::EacR11Unorm => vk::Format::EAC_R11_UNORM_BLOCK,
+            Self::EacR11Snorm => vk::Format::EAC_R11_SNORM_BLOCK,
+            Self::EacRg11Unorm => vk::Format::EAC_R11G11_UNORM_BLOCK,
+            Self::EacRg11Snorm => vk::Format::EAC_R11G11_SNORM_BLOCK,
+            Self::EacRgba8 => vk::Format::ETC2_R8G8B8A8_UNORM_BLOCK,
+            Self::EacRgba8Srgb => vk::Format::ETC2_R8G8B8A8_SRGB_BLOCK,
+            Self::Etc2Rgb8 => vk::Format::ETC2_R8G8B8_UNORM_BLOCK,
+            Self::Etc2Rgb8Srgb => vk::Format::ETC2_R8G8B8_SRGB_BLOCK,
+            Self::Etc2Rgb8a1 => vk::Format::ETC2_R8G8B8A1_UNORM_BLOCK,
+            Self::Etc2Rgb8a1Srgb => vk::Format::ETC2_R8G8B8A1_SRGB_BLOCK,
+            Self::Astc4x4Srgb => vk::Format::ASTC_4X4_SRGB_BLOCK,
+            Self::Astc4x4Ldr => vk::Format::ASTC_4X4_UNORM_BLOCK,
+            Self::Astc4x4Hdr => vk::Format::ASTC_4X4_SFLOAT_BLOCK,
+            Self::Astc5x4Srgb => vk::Format::ASTC_5X4_SRGB_BLOCK,
+            Self::Astc5x4Ldr => vk::Format::ASTC_5X4_UNORM_BLOCK,
+            Self::Astc5x4Hdr => vk::Format::ASTC_5X4_SFLOAT_BLOCK,
+            Self::Astc5x5Srgb => vk::Format::ASTC_5X5_SRGB_BLOCK,
+            Self::Astc5x5Ldr => vk::Format::ASTC_5X5_UNORM_BLOCK,
+            Self::Astc5x5Hdr => vk::Format::ASTC_5X5_SFLOAT_BLOCK,
+            Self::Astc6x5Srgb => vk::Format::ASTC_6X5_SRGB_BLOCK,
+            Self::Astc6x5Ldr => vk::Format::ASTC_6X5_UNORM_BLOCK,
+            Self::Astc6x5Hdr => vk::Format::ASTC_6X5_SFLOAT_BLOCK,
+            Self::Astc6x6Srgb => vk::Format::ASTC_6X6_SRGB_BLOCK,
+            Self::Astc6x6Ldr => vk::Format::ASTC_6X6_UNORM_BLOCK,
+            Self::Astc6x6Hdr => vk::Format::ASTC_6X6_SFLOAT_BLOCK,
+            Self::Astc8x5Srgb => vk::Format::ASTC_8X5_SRGB_BLOCK,
+            Self::Astc8x5Ldr => vk::Format::ASTC_8X5_UNORM_BLOCK,
+            Self::Astc8x5Hdr => vk::Format::ASTC_8X5_SFLOAT_BLOCK,
+            Self::Astc8x6Srgb => vk::Format::ASTC_8X6_SRGB_BLOCK,
+            Self::Astc8x6Ldr => vk::Format::ASTC_8X6_UNORM_BLOCK,
+            Self::Astc8x6Hdr => vk::Format::ASTC_8X6_SFLOAT_BLOCK,
+            Self::Astc8x8Srgb => vk::Format::ASTC_8X8_SRGB_BLOCK,
+            Self::Astc8x8Ldr => vk::Format::ASTC_8X8_UNORM_BLOCK,
+            Self::Astc8x8Hdr => vk::Format::ASTC_8X8_SFLOAT_BLOCK,
+            Self::Astc10x5Srgb => vk::Format::ASTC_10X5_SRGB_BLOCK,
+            Self::Astc10x5Ldr => vk::Format::ASTC_10X5_UNORM_BLOCK,
+            Self::Astc10x5Hdr => vk::Format::ASTC_10X5_SFLOAT_BLOCK,
+            Self::Astc10x6Srgb => vk::Format::ASTC_10X6_SRGB_BLOCK,
+            Self::Astc10x6Ldr => vk::Format::ASTC_10X6_UNORM_BLOCK,
+            Self::Astc10x6Hdr => vk::Format::ASTC_10X6_SFLOAT_BLOCK,
+            Self::Astc10x8Srgb => vk::Format::ASTC_10X8_SRGB_BLOCK,
+            Self::Astc10x8Ldr => vk::Format::ASTC_10X8_UNORM_BLOCK,
+            Self::Astc10x8Hdr => vk::Format::ASTC_10X8_SFLOAT_BLOCK,
+            Self::Astc10x10Srgb => vk::Format::ASTC_10X10_SRGB_BLOCK,
+            Self::Astc10x10Ldr => vk::Format::ASTC_10X10_UNORM_BLOCK,
+            Self::Astc10x10Hdr => vk::Format::ASTC_10X10_SFLOAT_BLOCK,
+            Self::Astc12x10Srgb => vk::Format::ASTC_12X10_SRGB_BLOCK,
+            Self::Astc12x10Ldr => vk::Format::ASTC_12X10_UNORM_BLOCK,
+            Self::Astc12x10Hdr => vk::Format::ASTC_12X10_SFLOAT_BLOCK,
+            Self::Astc12x12Srgb => vk::Format::ASTC_12X12_SRGB_BLOCK,
+            Self::Astc12x12Ldr => vk::Format::ASTC_12X12_UNORM_BLOCK,
+            Self::Astc12x12Hdr => vk::Format::ASTC_12X12_SFLOAT_BLOCK,
+            Self::Depth16Unorm => vk::Format::D16_UNORM,
+            Self::Depth32Float => vk::Format::D32_SFLOAT,
+            Self::Stencil8 => vk::Format::S8_UINT,
+            Self::Depth24UnormStencil8 | Self::X24Stencil8 => vk::Format::D24_UNORM_S8_UINT,
+            Self::Depth32FloatStencil8 | Self::X32Stencil8 => vk::Format::D32_SFLOAT_S8_UINT,
+        }
+    }
+}
@@ -1,13 +1,18 @@
 #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
 use std::cell::RefCell;
+#[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+use std::sync::Mutex;
 use std::{ops::Deref, sync::Arc};
 
-use crate::{MTLDevice, MTLPixelFormat, MTLTexture, shader::MTLFunction};
+use crate::{MTLDevice, MTLPixelFormat, MTLTexture, MTLVertexDescriptor, shader::MTLFunction};
 
-#[cfg(all(any(target_os = "macos", target_os = "ios"), not(feature = "moltenvk")))]
-use anyhow::Result;
 #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
-use anyhow::Result;
+use crate::{
+    MTLArgumentEncoder, MTLDepthStencilDescriptor, MTLDepthStencilState, VulkanVertexInputState,
+};
+#[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+use airlines::air_codegen::{MAX_BUFFER_BINDINGS, MAX_SAMPLER_BINDINGS, MAX_TEXTURE_BINDINGS};
+use anyhow::{Result, anyhow};
 #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
 use ash::vk;
 #[cfg(all(any(target_os = "macos", target_os = "ios"), not(feature = "moltenvk")))]
@@ -27,8 +32,9 @@ pub struct MTLRenderPass {
     device: Arc<MTLDevice>,
     descriptor: MTLRenderPassDescriptor,
 
+    /// Render passes by the attachments they were made for.
     #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
-    vulkan_render_pass: RefCell<Option<vk::RenderPass>>,
+    vulkan_render_passes: RefCell<Vec<(VulkanAttachmentKey, vk::RenderPass)>>,
 }
 
 impl MTLRenderPass {
@@ -37,7 +43,7 @@ impl MTLRenderPass {
             device,
             descriptor,
             #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
-            vulkan_render_pass: RefCell::new(None),
+            vulkan_render_passes: RefCell::new(vec![]),
         })
     }
 
@@ -45,15 +51,21 @@ impl MTLRenderPass {
         &self.descriptor
     }
 
+    /// The render pass for `begin`'s attachments, made the first time
+    /// they're used.
     #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
     pub fn vulkan_render_pass(
         &self,
         begin: &MTLBeginRenderPassDescriptor,
-    ) -> Result<&RefCell<Option<vk::RenderPass>>> {
-        if self.vulkan_render_pass.borrow().is_some() {
-            // TODO: Handle more cases in the future other than
-            // just checking if the render pass exists.
-            return Ok(&self.vulkan_render_pass);
+        key: &VulkanAttachmentKey,
+    ) -> Result<vk::RenderPass> {
+        if let Some((_, render_pass)) = self
+            .vulkan_render_passes
+            .borrow()
+            .iter()
+            .find(|(x, _)| x == key)
+        {
+            return Ok(*render_pass);
         }
 
         // I fucking hate this, this is a terrible, TERRIBLE solution,
@@ -66,16 +78,19 @@ impl MTLRenderPass {
         // This is a great example that Rust doesn't free you from having
         // to write shitty code.
         let mut handle = VulkanRenderPassHandler::default();
-        let new_handle = self.descriptor.to_vulkan(begin, &mut handle);
+        let new_handle = self.descriptor.to_vulkan(begin, &mut handle)?;
 
-        self.vulkan_render_pass.replace(Some(unsafe {
+        let render_pass = unsafe {
             self.device
                 .vulkan_device()
                 .logical()
                 .create_render_pass(&new_handle.final_render_pass_create_info, None)?
-        }));
+        };
+        self.vulkan_render_passes
+            .borrow_mut()
+            .push((key.clone(), render_pass));
 
-        Ok(&self.vulkan_render_pass)
+        Ok(render_pass)
     }
 
     pub fn device(&self) -> &Arc<MTLDevice> {
@@ -83,9 +98,47 @@ impl MTLRenderPass {
     }
 }
 
+#[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+impl Drop for MTLRenderPass {
+    fn drop(&mut self) {
+        let logical = self.device.vulkan_device().logical();
+
+        for (_, render_pass) in self.vulkan_render_passes.get_mut().drain(..) {
+            unsafe { logical.destroy_render_pass(render_pass, None) };
+        }
+    }
+}
+
+/// What render passes and the pipelines used in them have to agree on, the
+/// format and sample count of each attachment.
+#[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VulkanAttachmentKey {
+    pub color_attachments: Vec<(vk::Format, vk::SampleCountFlags)>,
+    pub depth_stencil_attachment: Option<(vk::Format, vk::SampleCountFlags)>,
+    /// Which color attachments are drawables, their render pass leaves them
+    /// ready to present.
+    pub drawables: Vec<bool>,
+}
+
+#[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+impl VulkanAttachmentKey {
+    /// Metal has every attachment of a pass use the same sample count.
+    pub fn sample_count(&self) -> vk::SampleCountFlags {
+        self.color_attachments
+            .iter()
+            .chain(&self.depth_stencil_attachment)
+            .map(|(_, samples)| *samples)
+            .next()
+            .unwrap_or(vk::SampleCountFlags::TYPE_1)
+    }
+}
+
 #[derive(Default)]
 pub struct MTLRenderPassDescriptor {
     pub color_attachments: Vec<MTLRenderPassColorAttachment>,
+    pub depth_attachment: Option<MTLRenderPassDepthAttachment>,
+    pub stencil_attachment: Option<MTLRenderPassStencilAttachment>,
 }
 
 impl<'a> MTLRenderPassDescriptor {
@@ -94,13 +147,15 @@ impl<'a> MTLRenderPassDescriptor {
         &self,
         begin: &MTLBeginRenderPassDescriptor,
         handle: &'a mut VulkanRenderPassHandler<'a>,
-    ) -> VulkanRenderPassHandler<'a> {
+    ) -> Result<VulkanRenderPassHandler<'a>> {
         handle.color_attachments = self.vulkan_color_attachments(begin);
 
         handle
             .attachment_descriptions
             .extend_from_slice(&handle.color_attachments);
 
+        let depth_stencil_attachment = self.vulkan_depth_stencil_attachment(begin)?;
+
         let mut ref_count = 0_u32;
 
         for _i in &handle.color_attachments {
@@ -112,23 +167,50 @@ impl<'a> MTLRenderPassDescriptor {
             ref_count += 1;
         }
 
-        handle.subpass_descriptions = vec![
-            vk::SubpassDescription::default()
-                .pipeline_bind_point(vk::PipelineBindPoint::GRAPHICS)
-                .color_attachments(&handle.color_attachment_refs),
-        ];
+        // The depth and stencil attachment comes after the colors.
+        if let Some(attachment) = depth_stencil_attachment {
+            handle.attachment_descriptions.push(attachment);
+            handle.depth_stencil_attachment_refs.push(
+                vk::AttachmentReference::default()
+                    .attachment(ref_count)
+                    .layout(vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL),
+            );
+        }
+
+        let mut subpass_description = vk::SubpassDescription::default()
+            .pipeline_bind_point(vk::PipelineBindPoint::GRAPHICS)
+            .color_attachments(&handle.color_attachment_refs);
+
+        if let Some(attachment_ref) = handle.depth_stencil_attachment_refs.first() {
+            subpass_description = subpass_description.depth_stencil_attachment(attachment_ref);
+        }
+
+        handle.subpass_descriptions = vec![subpass_description];
+
+        let (stage_mask, access_mask) = match depth_stencil_attachment {
+            Some(_) => (
+                vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT
+                    | vk::PipelineStageFlags::EARLY_FRAGMENT_TESTS
+                    | vk::PipelineStageFlags::LATE_FRAGMENT_TESTS,
+                vk::AccessFlags::COLOR_ATTACHMENT_READ
+                    | vk::AccessFlags::COLOR_ATTACHMENT_WRITE
+                    | vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_READ
+                    | vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_WRITE,
+            ),
+            None => (
+                vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT,
+                vk::AccessFlags::COLOR_ATTACHMENT_READ | vk::AccessFlags::COLOR_ATTACHMENT_WRITE,
+            ),
+        };
 
         handle.subpass_dependencies = vec![
             vk::SubpassDependency::default()
                 .src_subpass(vk::SUBPASS_EXTERNAL)
                 .dst_subpass(0)
-                .src_stage_mask(vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT)
+                .src_stage_mask(stage_mask)
                 .src_access_mask(vk::AccessFlags::empty())
-                .dst_stage_mask(vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT)
-                .dst_access_mask(
-                    vk::AccessFlags::COLOR_ATTACHMENT_READ
-                        | vk::AccessFlags::COLOR_ATTACHMENT_WRITE,
-                ),
+                .dst_stage_mask(stage_mask)
+                .dst_access_mask(access_mask),
         ];
 
         handle.final_render_pass_create_info = vk::RenderPassCreateInfo::default()
@@ -136,11 +218,52 @@ impl<'a> MTLRenderPassDescriptor {
             .subpasses(&handle.subpass_descriptions)
             .dependencies(&handle.subpass_dependencies);
 
-        handle.clone()
+        Ok(handle.clone())
     }
 }
 
 impl MTLRenderPassDescriptor {
+    /// `begin` has to give a texture to every attachment of the pass, and
+    /// none to attachments it doesn't have. The render pass, framebuffer
+    /// and pipelines are made from both and have to agree.
+    pub fn validate_begin(&self, begin: &MTLBeginRenderPassDescriptor) -> Result<()> {
+        if begin.color_attachments.len() != self.color_attachments.len() {
+            return Err(anyhow!(
+                "The render pass has {} color attachments, not {}.",
+                self.color_attachments.len(),
+                begin.color_attachments.len()
+            ));
+        }
+
+        for (name, in_pass, in_begin) in [
+            (
+                "depth",
+                self.depth_attachment.is_some(),
+                begin.depth_attachment.is_some(),
+            ),
+            (
+                "stencil",
+                self.stencil_attachment.is_some(),
+                begin.stencil_attachment.is_some(),
+            ),
+        ] {
+            match (in_pass, in_begin) {
+                (true, false) => {
+                    return Err(anyhow!(
+                        "The render pass's {} attachment has no texture.",
+                        name
+                    ));
+                }
+                (false, true) => {
+                    return Err(anyhow!("The render pass has no {} attachment.", name));
+                }
+                _ => {}
+            }
+        }
+
+        Ok(())
+    }
+
     #[cfg(all(any(target_os = "macos", target_os = "ios"), not(feature = "moltenvk")))]
     pub fn to_metal(
         &self,
@@ -154,6 +277,34 @@ impl MTLRenderPassDescriptor {
             count += 1;
         }
 
+        if let (Some(attachment), Some(begin_attachment)) =
+            (&self.depth_attachment, &begin.depth_attachment)
+        {
+            let depth_result = result.depthAttachment();
+
+            depth_result.setLoadAction(attachment.load_action.to_metal());
+            depth_result.setStoreAction(attachment.store_action.to_metal());
+            depth_result.setClearDepth(begin_attachment.clear_depth);
+            unsafe {
+                depth_result
+                    .setTexture(Some(begin_attachment.texture.to_metal().unwrap().as_ref()));
+            }
+        }
+
+        if let (Some(attachment), Some(begin_attachment)) =
+            (&self.stencil_attachment, &begin.stencil_attachment)
+        {
+            let stencil_result = result.stencilAttachment();
+
+            stencil_result.setLoadAction(attachment.load_action.to_metal());
+            stencil_result.setStoreAction(attachment.store_action.to_metal());
+            stencil_result.setClearStencil(begin_attachment.clear_stencil);
+            unsafe {
+                stencil_result
+                    .setTexture(Some(begin_attachment.texture.to_metal().unwrap().as_ref()));
+            }
+        }
+
         result
     }
 
@@ -171,6 +322,51 @@ impl MTLRenderPassDescriptor {
 
         result
     }
+
+    /// Vulkan has a single attachment for both depth and stencil, loaded
+    /// and stored separately for each aspect. Attachments stay in the
+    /// `GENERAL` layout between passes, like every other texture.
+    #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+    pub fn vulkan_depth_stencil_attachment(
+        &self,
+        begin: &MTLBeginRenderPassDescriptor,
+    ) -> Result<Option<vk::AttachmentDescription>> {
+        if self.depth_attachment.is_none() && self.stencil_attachment.is_none() {
+            return Ok(None);
+        }
+
+        let texture = begin.depth_stencil_texture()?.ok_or(anyhow!(
+            "The render pass has a depth or stencil attachment without a texture."
+        ))?;
+        let format = texture.pixel_format();
+
+        let (load_op, store_op) = match &self.depth_attachment {
+            Some(x) => (x.load_action.to_vulkan(), x.store_action.to_vulkan()),
+            None => (
+                vk::AttachmentLoadOp::DONT_CARE,
+                vk::AttachmentStoreOp::DONT_CARE,
+            ),
+        };
+        let (stencil_load_op, stencil_store_op) = match &self.stencil_attachment {
+            Some(x) => (x.load_action.to_vulkan(), x.store_action.to_vulkan()),
+            None => (
+                vk::AttachmentLoadOp::DONT_CARE,
+                vk::AttachmentStoreOp::DONT_CARE,
+            ),
+        };
+
+        Ok(Some(
+            vk::AttachmentDescription::default()
+                .format(format.vulkan_format(texture.device()))
+                .samples(vk::SampleCountFlags::from_raw(texture.sample_count()))
+                .load_op(load_op)
+                .store_op(store_op)
+                .stencil_load_op(stencil_load_op)
+                .stencil_store_op(stencil_store_op)
+                .initial_layout(vk::ImageLayout::GENERAL)
+                .final_layout(vk::ImageLayout::GENERAL),
+        ))
+    }
 }
 
 #[derive(Default, Clone)]
@@ -179,6 +375,7 @@ pub struct VulkanRenderPassHandler<'a> {
     color_attachments: Vec<vk::AttachmentDescription>,
     attachment_descriptions: Vec<vk::AttachmentDescription>,
     color_attachment_refs: Vec<vk::AttachmentReference>,
+    depth_stencil_attachment_refs: Vec<vk::AttachmentReference>,
     subpass_descriptions: Vec<vk::SubpassDescription<'a>>,
     subpass_dependencies: Vec<vk::SubpassDependency>,
     final_render_pass_create_info: vk::RenderPassCreateInfo<'a>,
@@ -187,35 +384,125 @@ pub struct VulkanRenderPassHandler<'a> {
 #[derive(Default)]
 pub struct MTLBeginRenderPassDescriptor {
     pub color_attachments: Vec<MTLBeginRenderPassColorAttachment>,
+    pub depth_attachment: Option<MTLBeginRenderPassDepthAttachment>,
+    pub stencil_attachment: Option<MTLBeginRenderPassStencilAttachment>,
 }
 
 impl MTLBeginRenderPassDescriptor {
-    #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
-    pub fn vulkan_framebuffer_check(
-        &self,
-        render_pass: &vk::RenderPass,
-        device: Arc<MTLDevice>,
-    ) -> Result<()> {
-        for i in &self.color_attachments {
-            if !i.texture.vulkan_is_framebuffer() {
-                unsafe {
-                    i.texture
-                        .vulkan_create_framebuffer(render_pass, device.clone())?;
-                }
-            }
+    /// The texture behind the depth and stencil attachments, which have to
+    /// share it when both are set.
+    pub fn depth_stencil_texture(&self) -> Result<Option<&Arc<MTLTexture>>> {
+        let depth_format = self
+            .depth_attachment
+            .as_ref()
+            .map(|x| *x.texture.pixel_format());
+        if let Some(format) = depth_format.filter(|x| !x.is_depth()) {
+            return Err(anyhow!("{:?} textures can't be depth attachments.", format));
         }
 
-        Ok(())
+        let stencil_format = self
+            .stencil_attachment
+            .as_ref()
+            .map(|x| *x.texture.pixel_format());
+        if let Some(format) = stencil_format.filter(|x| !x.is_stencil()) {
+            return Err(anyhow!(
+                "{:?} textures can't be stencil attachments.",
+                format
+            ));
+        }
+
+        match (&self.depth_attachment, &self.stencil_attachment) {
+            (Some(depth), Some(stencil)) if !Arc::ptr_eq(&depth.texture, &stencil.texture) => Err(
+                anyhow!("Depth and stencil attachments have to use the same texture."),
+            ),
+            (Some(depth), _) => Ok(Some(&depth.texture)),
+            (None, Some(stencil)) => Ok(Some(&stencil.texture)),
+            (None, None) => Ok(None),
+        }
+    }
+
+    #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+    pub fn vulkan_attachment_key(&self) -> Result<VulkanAttachmentKey> {
+        let attachment = |texture: &MTLTexture| {
+            (
+                texture.pixel_format().vulkan_format(texture.device()),
+                vk::SampleCountFlags::from_raw(texture.sample_count()),
+            )
+        };
+
+        Ok(VulkanAttachmentKey {
+            color_attachments: self
+                .color_attachments
+                .iter()
+                .map(|x| attachment(&x.texture))
+                .collect(),
+            depth_stencil_attachment: self.depth_stencil_texture()?.map(|x| attachment(x)),
+            drawables: self
+                .color_attachments
+                .iter()
+                .map(|x| x.texture.vulkan_swapchain().is_some())
+                .collect(),
+        })
+    }
+
+    /// The framebuffer with every attachment, kept by the first color
+    /// attachment or else by the depth and stencil one. Returns its size
+    /// too, which is the render area.
+    #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+    pub fn vulkan_framebuffer(
+        &self,
+        render_pass: vk::RenderPass,
+        key: &VulkanAttachmentKey,
+    ) -> Result<(vk::Framebuffer, vk::Extent2D)> {
+        let depth_stencil = self.depth_stencil_texture()?;
+
+        let mut attachments = self
+            .color_attachments
+            .iter()
+            .map(|x| *x.texture.vulkan_image_view())
+            .collect::<Vec<_>>();
+        attachments.extend(depth_stencil.map(|x| *x.vulkan_image_view()));
+
+        let texture = self
+            .color_attachments
+            .first()
+            .map(|x| &x.texture)
+            .or(depth_stencil)
+            .ok_or(anyhow!("The render pass has no attachments."))?;
+
+        Ok((
+            texture.vulkan_framebuffer(render_pass, key, &attachments)?,
+            vk::Extent2D {
+                width: texture.width(),
+                height: texture.height(),
+            },
+        ))
     }
 
+    /// Clear values in attachment order, colors then depth and stencil.
     #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
-    pub fn vulkan_clear_color_values(&self) -> Vec<vk::ClearValue> {
+    pub fn vulkan_clear_values(&self) -> Vec<vk::ClearValue> {
         let mut result: Vec<vk::ClearValue> = vec![];
 
         for i in &self.color_attachments {
             result.push(i.clear_color.to_vulkan());
         }
 
+        if self.depth_attachment.is_some() || self.stencil_attachment.is_some() {
+            result.push(vk::ClearValue {
+                depth_stencil: vk::ClearDepthStencilValue {
+                    depth: self
+                        .depth_attachment
+                        .as_ref()
+                        .map_or(1.0, |x| x.clear_depth as f32),
+                    stencil: self
+                        .stencil_attachment
+                        .as_ref()
+                        .map_or(0, |x| x.clear_stencil),
+                },
+            });
+        }
+
         result
     }
 }
@@ -225,6 +512,16 @@ pub struct MTLBeginRenderPassColorAttachment {
     pub texture: Arc<MTLTexture>,
 }
 
+pub struct MTLBeginRenderPassDepthAttachment {
+    pub clear_depth: f64,
+    pub texture: Arc<MTLTexture>,
+}
+
+pub struct MTLBeginRenderPassStencilAttachment {
+    pub clear_stencil: u32,
+    pub texture: Arc<MTLTexture>,
+}
+
 pub struct MTLRenderPassColorAttachment {
     pub load_action: MTLLoadAction,
     pub store_action: MTLStoreAction,
@@ -265,28 +562,41 @@ impl MTLRenderPassColorAttachment {
         Ok(())
     }
 
+    /// Drawables are presented after the pass, other textures stay in the
+    /// `GENERAL` layout like everywhere else.
     #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
     pub fn to_vulkan(
         &self,
         begin: &MTLBeginRenderPassDescriptor,
         count: usize,
     ) -> vk::AttachmentDescription {
-        let format = begin.color_attachments[count]
-            .texture
-            .pixel_format()
-            .to_vulkan();
+        let texture = &begin.color_attachments[count].texture;
+
+        let (initial_layout, final_layout) = match texture.vulkan_swapchain() {
+            Some(_) => (vk::ImageLayout::UNDEFINED, vk::ImageLayout::PRESENT_SRC_KHR),
+            None => (vk::ImageLayout::GENERAL, vk::ImageLayout::GENERAL),
+        };
 
         vk::AttachmentDescription::default()
-            .format(format)
-            .samples(vk::SampleCountFlags::TYPE_1)
+            .format(texture.pixel_format().vulkan_format(texture.device()))
+            .samples(vk::SampleCountFlags::from_raw(texture.sample_count()))
             .load_op(self.load_action.to_vulkan())
             .store_op(self.store_action.to_vulkan())
-            .initial_layout(vk::ImageLayout::UNDEFINED)
-            .final_layout(vk::ImageLayout::PRESENT_SRC_KHR)
-        //.final_layout(vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL)
+            .initial_layout(initial_layout)
+            .final_layout(final_layout)
     }
 }
 
+pub struct MTLRenderPassDepthAttachment {
+    pub load_action: MTLLoadAction,
+    pub store_action: MTLStoreAction,
+}
+
+pub struct MTLRenderPassStencilAttachment {
+    pub load_action: MTLLoadAction,
+    pub store_action: MTLStoreAction,
+}
+
 pub struct MTLClearColor {
     pub red: f64,
     pub green: f64,
@@ -378,6 +688,8 @@ pub struct MTLRenderPipelineDescriptor {
     pub vertex_function: Option<MTLFunction>,
     pub fragment_function: Option<MTLFunction>,
     pub color_attachments: Vec<MTLRenderPipelineColorAttachment>,
+    /// Needed when the vertex function takes a `[[stage_in]]`.
+    pub vertex_descriptor: Option<MTLVertexDescriptor>,
 }
 
 impl MTLRenderPipelineDescriptor {
@@ -393,6 +705,10 @@ impl MTLRenderPipelineDescriptor {
             Some(self.vertex_function.to_metal().deref());
         native.setVertexFunction(vertex);
 
+        if let Some(vertex_descriptor) = &self.vertex_descriptor {
+            native.setVertexDescriptor(Some(&vertex_descriptor.to_metal()));
+        }
+
         let mut count = 0;
         for i in &self.color_attachments {
             unsafe {
@@ -429,6 +745,21 @@ pub struct MTLRenderPipelineState {
 
     #[cfg(all(any(target_os = "macos", target_os = "ios"), not(feature = "moltenvk")))]
     native_mtl_state: Retained<ProtocolObject<dyn MetalMTLRenderPipelineState>>,
+
+    #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+    vulkan_vertex_input: VulkanVertexInputState,
+    #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+    vulkan_pipeline_layout: VulkanPipelineLayout,
+    /// Pipelines by the attachments they render to and, without extended
+    /// dynamic state, the depth and stencil state they test with.
+    #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+    vulkan_pipelines: Mutex<
+        Vec<(
+            VulkanAttachmentKey,
+            Option<MTLDepthStencilDescriptor>,
+            vk::Pipeline,
+        )>,
+    >,
 }
 
 impl MTLRenderPipelineState {
@@ -451,11 +782,149 @@ impl MTLRenderPipelineState {
     }
 
     #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
-    pub fn from_vulkan(device: Arc<MTLDevice>, descriptor: MTLRenderPipelineDescriptor) -> Self {
-        Self {
+    pub fn from_vulkan(
+        device: Arc<MTLDevice>,
+        descriptor: MTLRenderPipelineDescriptor,
+    ) -> Result<Self> {
+        let vulkan_vertex_input = match &descriptor.vertex_descriptor {
+            Some(vertex_descriptor) => vertex_descriptor.to_vulkan(&device)?,
+            None => VulkanVertexInputState::default(),
+        };
+
+        let functions = [
+            (&descriptor.vertex_function, vk::ShaderStageFlags::VERTEX),
+            (
+                &descriptor.fragment_function,
+                vk::ShaderStageFlags::FRAGMENT,
+            ),
+        ];
+        let vulkan_pipeline_layout = VulkanPipelineLayout::new(
+            &device,
+            functions
+                .iter()
+                .filter_map(|(function, stage)| Some((function.as_ref()?, *stage))),
+        )?;
+
+        Ok(Self {
             device,
             description: descriptor,
+            vulkan_vertex_input,
+            vulkan_pipeline_layout,
+            vulkan_pipelines: Mutex::new(vec![]),
+        })
+    }
+
+    /// The pipeline for a render pass with `key`'s attachments, made the
+    /// first time it's used. `depth_stencil` only matters without extended
+    /// dynamic state, a missing one turns both tests off.
+    #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+    pub fn vulkan_pipeline(
+        &self,
+        render_pass: vk::RenderPass,
+        key: &VulkanAttachmentKey,
+        depth_stencil: Option<&MTLDepthStencilDescriptor>,
+    ) -> Result<vk::Pipeline> {
+        let vulkan_device = self.device.vulkan_device();
+        let extended_dynamic_state = vulkan_device.features().extended_dynamic_state;
+        let depth_stencil = match extended_dynamic_state {
+            true => None,
+            false => Some(depth_stencil.copied().unwrap_or_default()),
+        };
+
+        let mut pipelines = self.vulkan_pipelines.lock().unwrap();
+
+        if let Some((_, _, pipeline)) = pipelines
+            .iter()
+            .find(|(x, y, _)| x == key && *y == depth_stencil)
+        {
+            return Ok(*pipeline);
+        }
+
+        let stages = [
+            &self.description.vertex_function,
+            &self.description.fragment_function,
+        ]
+        .into_iter()
+        .flatten()
+        .map(|x| x.vulkan_pipeline_stage_create_info())
+        .collect::<Vec<_>>();
+
+        let mut divisor_state = self.vulkan_vertex_input.divisor_state();
+        let mut vertex_input_state = self.vulkan_vertex_input.create_info();
+        if let Some(divisor_state) = &mut divisor_state {
+            vertex_input_state = vertex_input_state.push_next(divisor_state);
         }
+
+        let input_assembly_state = vk::PipelineInputAssemblyStateCreateInfo::default()
+            .topology(vk::PrimitiveTopology::TRIANGLE_LIST);
+
+        // The encoder sets both to the whole render area.
+        let viewport_state = vk::PipelineViewportStateCreateInfo::default()
+            .viewport_count(1)
+            .scissor_count(1);
+
+        let rasterization_state = vk::PipelineRasterizationStateCreateInfo::default()
+            .polygon_mode(vk::PolygonMode::FILL)
+            .cull_mode(vk::CullModeFlags::NONE)
+            .front_face(vk::FrontFace::CLOCKWISE)
+            .line_width(1.0);
+
+        let multisample_state = vk::PipelineMultisampleStateCreateInfo::default()
+            .rasterization_samples(key.sample_count());
+
+        let depth_stencil_state = depth_stencil.unwrap_or_default().to_vulkan();
+
+        let color_blend_attachments = key
+            .color_attachments
+            .iter()
+            .map(|_| {
+                vk::PipelineColorBlendAttachmentState::default()
+                    .color_write_mask(vk::ColorComponentFlags::RGBA)
+            })
+            .collect::<Vec<_>>();
+        let color_blend_state =
+            vk::PipelineColorBlendStateCreateInfo::default().attachments(&color_blend_attachments);
+
+        let mut dynamic_states = vec![vk::DynamicState::VIEWPORT, vk::DynamicState::SCISSOR];
+        dynamic_states.extend(MTLDepthStencilState::vulkan_dynamic_states(
+            extended_dynamic_state,
+        ));
+        let dynamic_state =
+            vk::PipelineDynamicStateCreateInfo::default().dynamic_states(&dynamic_states);
+
+        let create_info = vk::GraphicsPipelineCreateInfo::default()
+            .stages(&stages)
+            .vertex_input_state(&vertex_input_state)
+            .input_assembly_state(&input_assembly_state)
+            .viewport_state(&viewport_state)
+            .rasterization_state(&rasterization_state)
+            .multisample_state(&multisample_state)
+            .depth_stencil_state(&depth_stencil_state)
+            .color_blend_state(&color_blend_state)
+            .dynamic_state(&dynamic_state)
+            .layout(self.vulkan_pipeline_layout.pipeline_layout())
+            .render_pass(render_pass)
+            .subpass(0);
+
+        let pipeline = unsafe {
+            vulkan_device
+                .logical()
+                .create_graphics_pipelines(vk::PipelineCache::null(), &[create_info], None)
+                .map_err(|(_, error)| error)?[0]
+        };
+        pipelines.push((key.clone(), depth_stencil, pipeline));
+
+        Ok(pipeline)
+    }
+
+    #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+    pub fn vulkan_vertex_input(&self) -> &VulkanVertexInputState {
+        &self.vulkan_vertex_input
+    }
+
+    #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+    pub fn vulkan_pipeline_layout(&self) -> &VulkanPipelineLayout {
+        &self.vulkan_pipeline_layout
     }
 
     pub fn device(&self) -> &Arc<MTLDevice> {
@@ -466,3 +935,168 @@ impl MTLRenderPipelineState {
         &self.description
     }
 }
+
+#[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+impl Drop for MTLRenderPipelineState {
+    fn drop(&mut self) {
+        let logical = self.device.vulkan_device().logical();
+
+        for (_, _, pipeline) in self.vulkan_pipelines.get_mut().unwrap().drain(..) {
+            unsafe { logical.destroy_pipeline(pipeline, None) };
+        }
+
+        self.vulkan_pipeline_layout.destroy(&self.device);
+    }
+}
+
+/// Descriptor set layouts of the argument buffers a pipeline's functions
+/// take, indexed by set, and the pipeline layout made of them. Set 0 holds
+/// the stages' `[[buffer(n)]]` buffers and the fragment samplers, other sets
+/// no argument buffer uses are left empty.
+#[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+#[derive(Debug, Clone, Default)]
+pub struct VulkanPipelineLayout {
+    pipeline_layout: vk::PipelineLayout,
+    descriptor_set_layouts: Vec<vk::DescriptorSetLayout>,
+    fragment_samplers: bool,
+}
+
+#[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+impl VulkanPipelineLayout {
+    pub fn new<'a>(
+        device: &MTLDevice,
+        functions: impl Iterator<Item = (&'a MTLFunction, vk::ShaderStageFlags)>,
+    ) -> Result<Self> {
+        let mut sets: Vec<Vec<vk::DescriptorSetLayoutBinding>> = vec![vec![]];
+        let mut fragment_samplers = false;
+
+        for (function, stage_flags) in functions {
+            // airlines gives every stage the same buffer bindings.
+            for index in 0..MAX_BUFFER_BINDINGS {
+                match sets[0].iter_mut().find(|x| x.binding == index) {
+                    Some(x) => x.stage_flags |= stage_flags,
+                    None => sets[0].push(
+                        vk::DescriptorSetLayoutBinding::default()
+                            .binding(index)
+                            .descriptor_type(vk::DescriptorType::STORAGE_BUFFER)
+                            .descriptor_count(1)
+                            .stage_flags(stage_flags),
+                    ),
+                }
+            }
+
+            if stage_flags == vk::ShaderStageFlags::FRAGMENT {
+                fragment_samplers = true;
+                sets[0].extend((0..MAX_SAMPLER_BINDINGS).map(|index| {
+                    vk::DescriptorSetLayoutBinding::default()
+                        .binding(Self::sampler_binding(index))
+                        .descriptor_type(vk::DescriptorType::SAMPLER)
+                        .descriptor_count(1)
+                        .stage_flags(stage_flags)
+                }));
+            }
+
+            for layout in function.argument_buffers() {
+                let set = layout.set as usize;
+                if sets.len() <= set {
+                    sets.resize(set + 1, vec![]);
+                }
+
+                // Both stages can take the same argument buffer.
+                for binding in
+                    MTLArgumentEncoder::vulkan_descriptor_set_layout_bindings(layout, stage_flags)
+                {
+                    match sets[set].iter_mut().find(|x| x.binding == binding.binding) {
+                        Some(x)
+                            if x.descriptor_type == binding.descriptor_type
+                                && x.descriptor_count == binding.descriptor_count =>
+                        {
+                            x.stage_flags |= binding.stage_flags
+                        }
+                        Some(_) => {
+                            return Err(anyhow!(
+                                "`{}` disagrees with another stage about binding {} of set {}.",
+                                function.name(),
+                                binding.binding,
+                                set
+                            ));
+                        }
+                        None => sets[set].push(binding),
+                    }
+                }
+            }
+        }
+
+        let logical = device.vulkan_device().logical();
+        let mut result = Self {
+            fragment_samplers,
+            ..Self::default()
+        };
+
+        for bindings in &sets {
+            let descriptor_set_layout = unsafe {
+                logical.create_descriptor_set_layout(
+                    &vk::DescriptorSetLayoutCreateInfo::default().bindings(bindings),
+                    None,
+                )
+            };
+
+            match descriptor_set_layout {
+                Ok(x) => result.descriptor_set_layouts.push(x),
+                Err(error) => {
+                    result.destroy(device);
+                    return Err(error.into());
+                }
+            }
+        }
+
+        let pipeline_layout = unsafe {
+            logical.create_pipeline_layout(
+                &vk::PipelineLayoutCreateInfo::default()
+                    .set_layouts(&result.descriptor_set_layouts),
+                None,
+            )
+        };
+
+        match pipeline_layout {
+            Ok(x) => result.pipeline_layout = x,
+            Err(error) => {
+                result.destroy(device);
+                return Err(error.into());
+            }
+        }
+
+        Ok(result)
+    }
+
+    pub fn pipeline_layout(&self) -> vk::PipelineLayout {
+        self.pipeline_layout
+    }
+
+    pub fn descriptor_set_layout(&self, set: u32) -> Option<vk::DescriptorSetLayout> {
+        self.descriptor_set_layouts.get(set as usize).copied()
+    }
+
+    /// Whether set 0 has the fragment stage's samplers.
+    pub fn fragment_samplers(&self) -> bool {
+        self.fragment_samplers
+    }
+
+    /// Binding of `[[sampler(n)]]` in set 0, airlines puts samplers after
+    /// the buffers and textures.
+    pub fn sampler_binding(index: u32) -> u32 {
+        MAX_BUFFER_BINDINGS + MAX_TEXTURE_BINDINGS + index
+    }
+
+    pub fn destroy(&self, device: &MTLDevice) {
+        let logical = device.vulkan_device().logical();
+
+        unsafe {
+            logical.destroy_pipeline_layout(self.pipeline_layout, None);
+
+            for descriptor_set_layout in &self.descriptor_set_layouts {
+                logical.destroy_descriptor_set_layout(*descriptor_set_layout, None);
+            }
+        }
+    }
+}
@@ -0,0 +1,163 @@
+#[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+use ash::vk;
+#[cfg(all(any(target_os = "macos", target_os = "ios"), not(feature = "moltenvk")))]
+use objc2_metal::{
+    MTLCPUCacheMode as MetalMTLCPUCacheMode, MTLHazardTrackingMode as MetalMTLHazardTrackingMode,
+    MTLResourceOptions as MetalMTLResourceOptions, MTLStorageMode as MetalMTLStorageMode,
+};
+
+/// Where a resource's memory lives and who can access it.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum MTLStorageMode {
+    /// CPU and GPU share the memory.
+    #[default]
+    Shared,
+    /// GPU only memory, filled through a staging upload.
+    Private,
+    /// CPU and GPU copies synchronized with `did_modify_range` and
+    /// `MTLCommandBuffer::synchronize_resource`.
+    Managed,
+    /// Transient render targets that never leave tile memory.
+    Memoryless,
+}
+
+impl MTLStorageMode {
+    /// Memory properties a resource needs and the ones it would like to have.
+    #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+    pub fn to_vulkan(
+        self,
+        cpu_cache_mode: MTLCPUCacheMode,
+    ) -> (vk::MemoryPropertyFlags, vk::MemoryPropertyFlags) {
+        match (self, cpu_cache_mode) {
+            (Self::Shared, MTLCPUCacheMode::DefaultCache) => (
+                vk::MemoryPropertyFlags::HOST_VISIBLE,
+                vk::MemoryPropertyFlags::HOST_COHERENT,
+            ),
+            // Write combined memory is the uncached, device local window
+            // into VRAM when there is one.
+            (Self::Shared, MTLCPUCacheMode::WriteCombined) => (
+                vk::MemoryPropertyFlags::HOST_VISIBLE,
+                vk::MemoryPropertyFlags::HOST_COHERENT | vk::MemoryPropertyFlags::DEVICE_LOCAL,
+            ),
+            (Self::Private, _) => (
+                vk::MemoryPropertyFlags::DEVICE_LOCAL,
+                vk::MemoryPropertyFlags::empty(),
+            ),
+            (Self::Managed, _) => (
+                vk::MemoryPropertyFlags::HOST_VISIBLE,
+                vk::MemoryPropertyFlags::HOST_CACHED,
+            ),
+            (Self::Memoryless, _) => (
+                vk::MemoryPropertyFlags::DEVICE_LOCAL,
+                vk::MemoryPropertyFlags::LAZILY_ALLOCATED,
+            ),
+        }
+    }
+
+    #[cfg(all(any(target_os = "macos", target_os = "ios"), not(feature = "moltenvk")))]
+    pub fn to_metal(self) -> MetalMTLStorageMode {
+        match self {
+            Self::Shared => MetalMTLStorageMode::Shared,
+            Self::Private => MetalMTLStorageMode::Private,
+            Self::Managed => MetalMTLStorageMode::Managed,
+            Self::Memoryless => MetalMTLStorageMode::Memoryless,
+        }
+    }
+}
+
+/// How the CPU caches a resource's memory.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum MTLCPUCacheMode {
+    #[default]
+    DefaultCache,
+    /// Faster CPU writes, very slow CPU reads.
+    WriteCombined,
+}
+
+impl MTLCPUCacheMode {
+    #[cfg(all(any(target_os = "macos", target_os = "ios"), not(feature = "moltenvk")))]
+    pub fn to_metal(self) -> MetalMTLCPUCacheMode {
+        match self {
+            Self::DefaultCache => MetalMTLCPUCacheMode::DefaultCache,
+            Self::WriteCombined => MetalMTLCPUCacheMode::WriteCombined,
+        }
+    }
+}
+
+/// Whether the device tracks hazards between commands using a resource.
+///
+/// Vulkan command buffers run one after the other, `commit` waits for each,
+/// so every resource is tracked whatever its mode. Untracked resources only
+/// give up the guarantee, they're never less synchronized.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum MTLHazardTrackingMode {
+    #[default]
+    Default,
+    Untracked,
+    Tracked,
+}
+
+impl MTLHazardTrackingMode {
+    #[cfg(all(any(target_os = "macos", target_os = "ios"), not(feature = "moltenvk")))]
+    pub fn to_metal(self) -> MetalMTLHazardTrackingMode {
+        match self {
+            Self::Default => MetalMTLHazardTrackingMode::Default,
+            Self::Untracked => MetalMTLHazardTrackingMode::Untracked,
+            Self::Tracked => MetalMTLHazardTrackingMode::Tracked,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct MTLResourceOptions {
+    pub storage_mode: MTLStorageMode,
+    pub cpu_cache_mode: MTLCPUCacheMode,
+    pub hazard_tracking_mode: MTLHazardTrackingMode,
+}
+
+impl MTLResourceOptions {
+    pub fn storage_mode(storage_mode: MTLStorageMode) -> Self {
+        Self {
+            storage_mode,
+            ..Default::default()
+        }
+    }
+
+    /// Whether the CPU can write the resource's memory directly.
+    pub fn is_host_visible(&self) -> bool {
+        matches!(
+            self.storage_mode,
+            MTLStorageMode::Shared | MTLStorageMode::Managed
+        )
+    }
+
+    #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+    pub fn to_vulkan(self) -> (vk::MemoryPropertyFlags, vk::MemoryPropertyFlags) {
+        self.storage_mode.to_vulkan(self.cpu_cache_mode)
+    }
+
+    #[cfg(all(any(target_os = "macos", target_os = "ios"), not(feature = "moltenvk")))]
+    pub fn to_metal(self) -> MetalMTLResourceOptions {
+        let storage_mode = match self.storage_mode {
+            MTLStorageMode::Shared => MetalMTLResourceOptions::StorageModeShared,
+            MTLStorageMode::Private => MetalMTLResourceOptions::StorageModePrivate,
+            MTLStorageMode::Managed => MetalMTLResourceOptions::StorageModeManaged,
+            MTLStorageMode::Memoryless => MetalMTLResourceOptions::StorageModeMemoryless,
+        };
+
+        let cpu_cache_mode = match self.cpu_cache_mode {
+            MTLCPUCacheMode::DefaultCache => MetalMTLResourceOptions::CPUCacheModeDefaultCache,
+            MTLCPUCacheMode::WriteCombined => MetalMTLResourceOptions::CPUCacheModeWriteCombined,
+        };
+
+        let hazard_tracking_mode = match self.hazard_tracking_mode {
+            MTLHazardTrackingMode::Default => MetalMTLResourceOptions::HazardTrackingModeDefault,
+            MTLHazardTrackingMode::Untracked => {
+                MetalMTLResourceOptions::HazardTrackingModeUntracked
+            }
+            MTLHazardTrackingMode::Tracked => MetalMTLResourceOptions::HazardTrackingModeTracked,
+        };
+
+        storage_mode | cpu_cache_mode | hazard_tracking_mode
+    }
+}
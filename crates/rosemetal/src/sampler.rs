@@ -0,0 +1,399 @@
+use crate::MTLDevice;
+use anyhow::{Result, anyhow};
+
+#[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+use ash::vk;
+#[cfg(all(any(target_os = "macos", target_os = "ios"), not(feature = "moltenvk")))]
+use objc2::{rc::Retained, runtime::ProtocolObject};
+#[cfg(all(any(target_os = "macos", target_os = "ios"), not(feature = "moltenvk")))]
+use objc2_metal::{
+    MTLCompareFunction as MetalMTLCompareFunction, MTLDevice as MetalMTLDevice,
+    MTLSamplerAddressMode as MetalMTLSamplerAddressMode,
+    MTLSamplerBorderColor as MetalMTLSamplerBorderColor,
+    MTLSamplerDescriptor as MetalMTLSamplerDescriptor,
+    MTLSamplerMinMagFilter as MetalMTLSamplerMinMagFilter,
+    MTLSamplerMipFilter as MetalMTLSamplerMipFilter, MTLSamplerState as MetalMTLSamplerState,
+};
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum MTLSamplerMinMagFilter {
+    #[default]
+    Nearest,
+    Linear,
+}
+
+impl MTLSamplerMinMagFilter {
+    #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+    pub fn to_vulkan(self) -> vk::Filter {
+        match self {
+            Self::Nearest => vk::Filter::NEAREST,
+            Self::Linear => vk::Filter::LINEAR,
+        }
+    }
+
+    #[cfg(all(any(target_os = "macos", target_os = "ios"), not(feature = "moltenvk")))]
+    pub fn to_metal(self) -> MetalMTLSamplerMinMagFilter {
+        match self {
+            Self::Nearest => MetalMTLSamplerMinMagFilter::Nearest,
+            Self::Linear => MetalMTLSamplerMinMagFilter::Linear,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum MTLSamplerMipFilter {
+    /// Always samples the base level.
+    #[default]
+    NotMipmapped,
+    Nearest,
+    Linear,
+}
+
+impl MTLSamplerMipFilter {
+    #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+    pub fn to_vulkan(self) -> vk::SamplerMipmapMode {
+        match self {
+            Self::NotMipmapped | Self::Nearest => vk::SamplerMipmapMode::NEAREST,
+            Self::Linear => vk::SamplerMipmapMode::LINEAR,
+        }
+    }
+
+    #[cfg(all(any(target_os = "macos", target_os = "ios"), not(feature = "moltenvk")))]
+    pub fn to_metal(self) -> MetalMTLSamplerMipFilter {
+        match self {
+            Self::NotMipmapped => MetalMTLSamplerMipFilter::NotMipmapped,
+            Self::Nearest => MetalMTLSamplerMipFilter::Nearest,
+            Self::Linear => MetalMTLSamplerMipFilter::Linear,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum MTLSamplerAddressMode {
+    #[default]
+    ClampToEdge,
+    MirrorClampToEdge,
+    Repeat,
+    MirrorRepeat,
+    /// Reads outside of the texture return zero.
+    ClampToZero,
+    ClampToBorderColor,
+}
+
+impl MTLSamplerAddressMode {
+    #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+    pub fn to_vulkan(self) -> vk::SamplerAddressMode {
+        match self {
+            Self::ClampToEdge => vk::SamplerAddressMode::CLAMP_TO_EDGE,
+            Self::MirrorClampToEdge => vk::SamplerAddressMode::MIRROR_CLAMP_TO_EDGE,
+            Self::Repeat => vk::SamplerAddressMode::REPEAT,
+            Self::MirrorRepeat => vk::SamplerAddressMode::MIRRORED_REPEAT,
+            Self::ClampToZero | Self::ClampToBorderColor => vk::SamplerAddressMode::CLAMP_TO_BORDER,
+        }
+    }
+
+    #[cfg(all(any(target_os = "macos", target_os = "ios"), not(feature = "moltenvk")))]
+    pub fn to_metal(self) -> MetalMTLSamplerAddressMode {
+        match self {
+            Self::ClampToEdge => MetalMTLSamplerAddressMode::ClampToEdge,
+            Self::MirrorClampToEdge => MetalMTLSamplerAddressMode::MirrorClampToEdge,
+            Self::Repeat => MetalMTLSamplerAddressMode::Repeat,
+            Self::MirrorRepeat => MetalMTLSamplerAddressMode::MirrorRepeat,
+            Self::ClampToZero => MetalMTLSamplerAddressMode::ClampToZero,
+            Self::ClampToBorderColor => MetalMTLSamplerAddressMode::ClampToBorderColor,
+        }
+    }
+}
+
+/// Color of reads outside of the texture with `ClampToBorderColor`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum MTLSamplerBorderColor {
+    #[default]
+    TransparentBlack,
+    OpaqueBlack,
+    OpaqueWhite,
+}
+
+impl MTLSamplerBorderColor {
+    #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+    pub fn to_vulkan(self) -> vk::BorderColor {
+        match self {
+            Self::TransparentBlack => vk::BorderColor::FLOAT_TRANSPARENT_BLACK,
+            Self::OpaqueBlack => vk::BorderColor::FLOAT_OPAQUE_BLACK,
+            Self::OpaqueWhite => vk::BorderColor::FLOAT_OPAQUE_WHITE,
+        }
+    }
+
+    #[cfg(all(any(target_os = "macos", target_os = "ios"), not(feature = "moltenvk")))]
+    pub fn to_metal(self) -> MetalMTLSamplerBorderColor {
+        match self {
+            Self::TransparentBlack => MetalMTLSamplerBorderColor::TransparentBlack,
+            Self::OpaqueBlack => MetalMTLSamplerBorderColor::OpaqueBlack,
+            Self::OpaqueWhite => MetalMTLSamplerBorderColor::OpaqueWhite,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum MTLCompareFunction {
+    #[default]
+    Never,
+    Less,
+    Equal,
+    LessEqual,
+    Greater,
+    NotEqual,
+    GreaterEqual,
+    Always,
+}
+
+impl MTLCompareFunction {
+    #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+    pub fn to_vulkan(self) -> vk::CompareOp {
+        match self {
+            Self::Never => vk::CompareOp::NEVER,
+            Self::Less => vk::CompareOp::LESS,
+            Self::Equal => vk::CompareOp::EQUAL,
+            Self::LessEqual => vk::CompareOp::LESS_OR_EQUAL,
+            Self::Greater => vk::CompareOp::GREATER,
+            Self::NotEqual => vk::CompareOp::NOT_EQUAL,
+            Self::GreaterEqual => vk::CompareOp::GREATER_OR_EQUAL,
+            Self::Always => vk::CompareOp::ALWAYS,
+        }
+    }
+
+    #[cfg(all(any(target_os = "macos", target_os = "ios"), not(feature = "moltenvk")))]
+    pub fn to_metal(self) -> MetalMTLCompareFunction {
+        match self {
+            Self::Never => MetalMTLCompareFunction::Never,
+            Self::Less => MetalMTLCompareFunction::Less,
+            Self::Equal => MetalMTLCompareFunction::Equal,
+            Self::LessEqual => MetalMTLCompareFunction::LessEqual,
+            Self::Greater => MetalMTLCompareFunction::Greater,
+            Self::NotEqual => MetalMTLCompareFunction::NotEqual,
+            Self::GreaterEqual => MetalMTLCompareFunction::GreaterEqual,
+            Self::Always => MetalMTLCompareFunction::Always,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MTLSamplerDescriptor {
+    pub min_filter: MTLSamplerMinMagFilter,
+    pub mag_filter: MTLSamplerMinMagFilter,
+    pub mip_filter: MTLSamplerMipFilter,
+    pub s_address_mode: MTLSamplerAddressMode,
+    pub t_address_mode: MTLSamplerAddressMode,
+    pub r_address_mode: MTLSamplerAddressMode,
+    pub border_color: MTLSamplerBorderColor,
+    /// 1 turns anisotropic filtering off.
+    pub max_anisotropy: u32,
+    /// Only used by depth textures sampled with `sample_compare`.
+    pub compare_function: MTLCompareFunction,
+    pub lod_min_clamp: f32,
+    pub lod_max_clamp: f32,
+    /// Coordinates go from 0 to 1 rather than 0 to the texture's size.
+    pub normalized_coordinates: bool,
+    pub support_argument_buffers: bool,
+}
+
+impl Default for MTLSamplerDescriptor {
+    fn default() -> Self {
+        Self {
+            min_filter: MTLSamplerMinMagFilter::Nearest,
+            mag_filter: MTLSamplerMinMagFilter::Nearest,
+            mip_filter: MTLSamplerMipFilter::NotMipmapped,
+            s_address_mode: MTLSamplerAddressMode::ClampToEdge,
+            t_address_mode: MTLSamplerAddressMode::ClampToEdge,
+            r_address_mode: MTLSamplerAddressMode::ClampToEdge,
+            border_color: MTLSamplerBorderColor::TransparentBlack,
+            max_anisotropy: 1,
+            compare_function: MTLCompareFunction::Never,
+            lod_min_clamp: 0.0,
+            lod_max_clamp: f32::MAX,
+            normalized_coordinates: true,
+            support_argument_buffers: false,
+        }
+    }
+}
+
+impl MTLSamplerDescriptor {
+    pub fn address_mode(address_mode: MTLSamplerAddressMode) -> Self {
+        Self {
+            s_address_mode: address_mode,
+            t_address_mode: address_mode,
+            r_address_mode: address_mode,
+            ..Default::default()
+        }
+    }
+
+    fn address_modes(&self) -> [MTLSamplerAddressMode; 3] {
+        [
+            self.s_address_mode,
+            self.t_address_mode,
+            self.r_address_mode,
+        ]
+    }
+
+    /// Rejects descriptors Metal wouldn't create a sampler for.
+    pub fn validate(&self) -> Result<()> {
+        if !(1..=16).contains(&self.max_anisotropy) {
+            return Err(anyhow!("Invalid max anisotropy {}.", self.max_anisotropy));
+        }
+
+        if self.lod_min_clamp.is_nan()
+            || self.lod_max_clamp.is_nan()
+            || self.lod_min_clamp < 0.0
+            || self.lod_min_clamp > self.lod_max_clamp
+        {
+            return Err(anyhow!(
+                "Invalid LOD clamp {}..{}.",
+                self.lod_min_clamp,
+                self.lod_max_clamp
+            ));
+        }
+
+        if self.normalized_coordinates {
+            return Ok(());
+        }
+
+        // Pixel coordinates only work on a single level, without wrapping.
+        if self.min_filter != self.mag_filter
+            || self.mip_filter != MTLSamplerMipFilter::NotMipmapped
+            || self.max_anisotropy > 1
+            || self.compare_function != MTLCompareFunction::Never
+            || self.address_modes().iter().any(|x| {
+                !matches!(
+                    x,
+                    MTLSamplerAddressMode::ClampToEdge | MTLSamplerAddressMode::ClampToZero
+                )
+            })
+        {
+            return Err(anyhow!(
+                "Samplers with pixel coordinates have to use the same min and mag filter, no mipmaps, no anisotropy, no compare function and clamp to edge or zero."
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// `ClampToZero` is the transparent black border, the descriptor's own
+    /// border color only applies to `ClampToBorderColor`.
+    #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+    pub fn to_vulkan(&self, max_sampler_anisotropy: f32) -> vk::SamplerCreateInfo<'static> {
+        let border_color = match self
+            .address_modes()
+            .contains(&MTLSamplerAddressMode::ClampToZero)
+        {
+            true => MTLSamplerBorderColor::TransparentBlack,
+            false => self.border_color,
+        };
+
+        // Vulkan has no "not mipmapped", clamping to the base level does the
+        // same.
+        let (min_lod, max_lod) = match self.mip_filter {
+            MTLSamplerMipFilter::NotMipmapped => (0.0, 0.0),
+            _ => (self.lod_min_clamp, self.lod_max_clamp),
+        };
+
+        let anisotropy = (self.max_anisotropy as f32).min(max_sampler_anisotropy);
+
+        vk::SamplerCreateInfo::default()
+            .min_filter(self.min_filter.to_vulkan())
+            .mag_filter(self.mag_filter.to_vulkan())
+            .mipmap_mode(self.mip_filter.to_vulkan())
+            .address_mode_u(self.s_address_mode.to_vulkan())
+            .address_mode_v(self.t_address_mode.to_vulkan())
+            .address_mode_w(self.r_address_mode.to_vulkan())
+            .border_color(border_color.to_vulkan())
+            .anisotropy_enable(anisotropy > 1.0)
+            .max_anisotropy(anisotropy.max(1.0))
+            .compare_enable(self.compare_function != MTLCompareFunction::Never)
+            .compare_op(self.compare_function.to_vulkan())
+            .min_lod(min_lod)
+            .max_lod(max_lod)
+            .unnormalized_coordinates(!self.normalized_coordinates)
+    }
+
+    #[cfg(all(any(target_os = "macos", target_os = "ios"), not(feature = "moltenvk")))]
+    pub fn to_metal(&self) -> Retained<MetalMTLSamplerDescriptor> {
+        let descriptor = MetalMTLSamplerDescriptor::new();
+
+        unsafe {
+            descriptor.setMinFilter(self.min_filter.to_metal());
+            descriptor.setMagFilter(self.mag_filter.to_metal());
+            descriptor.setMipFilter(self.mip_filter.to_metal());
+            descriptor.setSAddressMode(self.s_address_mode.to_metal());
+            descriptor.setTAddressMode(self.t_address_mode.to_metal());
+            descriptor.setRAddressMode(self.r_address_mode.to_metal());
+            descriptor.setBorderColor(self.border_color.to_metal());
+            descriptor.setMaxAnisotropy(self.max_anisotropy as usize);
+            descriptor.setCompareFunction(self.compare_function.to_metal());
+            descriptor.setLodMinClamp(self.lod_min_clamp);
+            descriptor.setLodMaxClamp(self.lod_max_clamp);
+            descriptor.setNormalizedCoordinates(self.normalized_coordinates);
+            descriptor.setSupportArgumentBuffers(self.support_argument_buffers);
+        }
+
+        descriptor
+    }
+}
+
+/// An immutable sampler. On Vulkan identical descriptors share the same
+/// `VkSampler`, which lives as long as the device.
+pub struct MTLSamplerState {
+    descriptor: MTLSamplerDescriptor,
+
+    #[cfg(all(any(target_os = "macos", target_os = "ios"), not(feature = "moltenvk")))]
+    metal_sampler_state: Retained<ProtocolObject<dyn MetalMTLSamplerState>>,
+
+    #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+    vulkan_sampler: vk::Sampler,
+}
+
+impl MTLSamplerState {
+    pub fn new(device: &MTLDevice, descriptor: MTLSamplerDescriptor) -> Result<Self> {
+        descriptor.validate()?;
+
+        #[cfg(all(any(target_os = "macos", target_os = "ios"), not(feature = "moltenvk")))]
+        return Self::metal_new(device, descriptor);
+
+        #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+        return Self::vulkan_new(device, descriptor);
+    }
+
+    #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+    pub fn vulkan_new(device: &MTLDevice, descriptor: MTLSamplerDescriptor) -> Result<Self> {
+        Ok(Self {
+            descriptor,
+            vulkan_sampler: device.vulkan_device().sampler(&descriptor)?,
+        })
+    }
+
+    #[cfg(all(any(target_os = "macos", target_os = "ios"), not(feature = "moltenvk")))]
+    pub fn metal_new(device: &MTLDevice, descriptor: MTLSamplerDescriptor) -> Result<Self> {
+        let metal_sampler_state = device
+            .metal_device()
+            .newSamplerStateWithDescriptor(&descriptor.to_metal())
+            .ok_or(anyhow!("Sampler State creation failed."))?;
+
+        Ok(Self {
+            descriptor,
+            metal_sampler_state,
+        })
+    }
+
+    pub fn descriptor(&self) -> &MTLSamplerDescriptor {
+        &self.descriptor
+    }
+
+    #[cfg(all(any(target_os = "macos", target_os = "ios"), not(feature = "moltenvk")))]
+    pub fn metal_sampler_state(&self) -> &Retained<ProtocolObject<dyn MetalMTLSamplerState>> {
+        &self.metal_sampler_state
+    }
+
+    #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+    pub fn vulkan_sampler(&self) -> &vk::Sampler {
+        &self.vulkan_sampler
+    }
+}
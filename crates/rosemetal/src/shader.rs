@@ -1,9 +1,16 @@
 use std::ffi::{CStr, CString};
 
 use airlines::metal_lib::MTLLibraryParser;
+#[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+use airlines::{
+    air_codegen::{AirToSpirVOptions, ArgumentBufferLayout},
+    translation_cache::TranslationCache,
+};
 use anyhow::Result;
+#[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+use anyhow::anyhow;
 
-#[cfg(all(any(target_os = "macos", target_os = "ios"), feature = "moltenvk"))]
+#[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
 use ash::vk;
 
 #[cfg(all(any(target_os = "macos", target_os = "ios"), not(feature = "moltenvk")))]
@@ -12,6 +19,8 @@ use objc2::{rc::Retained, runtime::ProtocolObject};
 #[cfg(all(any(target_os = "macos", target_os = "ios"), not(feature = "moltenvk")))]
 use objc2_metal::{MTLFunction as MetalMTLFunction, MTLLibrary as MetalMTLLibrary};
 
+#[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+use crate::MTLArgumentEncoder;
 use crate::MTLDevice;
 
 pub struct MTLLibrary {
@@ -20,8 +29,10 @@ pub struct MTLLibrary {
     #[cfg(all(any(target_os = "macos", target_os = "ios"), not(feature = "moltenvk")))]
     native_mtl_library: Retained<ProtocolObject<dyn MetalMTLLibrary>>,
 
-    #[cfg(all(any(target_os = "macos", target_os = "ios"), feature = "moltenvk"))]
+    #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
     vulkan_shader_module: vk::ShaderModule,
+    #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+    argument_buffers: Vec<ArgumentBufferLayout>,
 }
 
 impl MTLLibrary {
@@ -46,9 +57,12 @@ impl MTLLibrary {
             });
         }
 
-        #[cfg(all(any(target_os = "macos", target_os = "ios"), feature = "moltenvk"))]
+        #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
         {
-            let spirv_result = parser.to_spirv_binary();
+            let mut parser = parser;
+            let options = Self::vulkan_translation_options(device);
+            let argument_buffers = parser.argument_buffers(&options)?;
+            let spirv_result = parser.to_spirv_binary(&options)?;
 
             let vulkan_shader_module = unsafe {
                 device.vulkan_device().logical().create_shader_module(
@@ -60,10 +74,48 @@ impl MTLLibrary {
             return Ok(Self {
                 parser,
                 vulkan_shader_module,
+                argument_buffers,
             });
         }
     }
 
+    /// Like `from_metal_lib`, but reuses the SPIR-V from `cache` when this
+    /// library was translated on a previous launch.
+    #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+    pub fn from_metal_lib_cached(
+        content: &[u8],
+        device: &MTLDevice,
+        cache: &TranslationCache,
+    ) -> Result<Self> {
+        let mut parser = MTLLibraryParser::default();
+        parser.read_header(content)?;
+
+        let translation =
+            parser.to_spirv_binary_cached(cache, &Self::vulkan_translation_options(device))?;
+
+        let vulkan_shader_module = unsafe {
+            device.vulkan_device().logical().create_shader_module(
+                &vk::ShaderModuleCreateInfo::default().code(&translation.words),
+                None,
+            )?
+        };
+
+        Ok(Self {
+            parser,
+            vulkan_shader_module,
+            argument_buffers: translation.reflection.argument_buffers,
+        })
+    }
+
+    /// Libraries are translated for what `device` supports.
+    #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+    pub fn vulkan_translation_options(device: &MTLDevice) -> AirToSpirVOptions {
+        AirToSpirVOptions {
+            scalar_block_layout: device.vulkan_device().features().scalar_block_layout,
+            ..Default::default()
+        }
+    }
+
     pub fn get_function(&self, name: &str, function_type: MTLFunctionType) -> Result<MTLFunction> {
         #[cfg(all(any(target_os = "macos", target_os = "ios"), not(feature = "moltenvk")))]
         {
@@ -82,10 +134,16 @@ impl MTLLibrary {
             ));
         }
 
+        #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
         return Ok(MTLFunction::from_vulkan(
             function_type,
             self.vulkan_shader_module,
             name.to_string(),
+            self.argument_buffers
+                .iter()
+                .filter(|x| x.function == name)
+                .cloned()
+                .collect(),
         ));
     }
 }
@@ -94,6 +152,7 @@ impl MTLLibrary {
 pub enum MTLFunctionType {
     Vertex,
     Fragment,
+    Kernel,
 }
 
 #[derive(Clone)]
@@ -101,24 +160,29 @@ pub struct MTLFunction {
     name: String,
     c_string_name: CString,
     function_type: MTLFunctionType,
+    #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
     vulkan_shader_module: vk::ShaderModule,
+    #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+    argument_buffers: Vec<ArgumentBufferLayout>,
 
     #[cfg(all(any(target_os = "macos", target_os = "ios"), not(feature = "moltenvk")))]
     native_mtl_function: Retained<ProtocolObject<dyn MetalMTLFunction>>,
 }
 
 impl MTLFunction {
-    #[cfg(all(any(target_os = "macos", target_os = "ios"), feature = "moltenvk"))]
+    #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
     pub fn from_vulkan(
         function_type: MTLFunctionType,
         vulkan_shader_module: vk::ShaderModule,
         name: String,
+        argument_buffers: Vec<ArgumentBufferLayout>,
     ) -> Self {
         Self {
             name: name.clone(),
             c_string_name: CString::new(name).unwrap(),
             vulkan_shader_module,
             function_type,
+            argument_buffers,
         }
     }
 
@@ -153,17 +217,40 @@ impl MTLFunction {
         &self.function_type
     }
 
-    #[cfg(all(any(target_os = "macos", target_os = "ios"), feature = "moltenvk"))]
+    /// Encoder for the argument buffer at `[[buffer(buffer_index)]]`.
+    #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+    pub fn new_argument_encoder(&self, buffer_index: u32) -> Result<MTLArgumentEncoder> {
+        let layout = self
+            .argument_buffers
+            .iter()
+            .find(|x| x.buffer_index == buffer_index)
+            .ok_or(anyhow!(
+                "`{}` has no argument buffer at index {}.",
+                self.name,
+                buffer_index
+            ))?;
+
+        Ok(MTLArgumentEncoder::new(layout.clone()))
+    }
+
+    /// Layouts of the argument buffers the function takes.
+    #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+    pub fn argument_buffers(&self) -> &[ArgumentBufferLayout] {
+        &self.argument_buffers
+    }
+
+    #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
     pub fn vulkan_shader_module(&self) -> &vk::ShaderModule {
         &self.vulkan_shader_module
     }
 
-    #[cfg(all(any(target_os = "macos", target_os = "ios"), feature = "moltenvk"))]
+    #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
     pub fn vulkan_pipeline_stage_create_info(&self) -> vk::PipelineShaderStageCreateInfo {
         vk::PipelineShaderStageCreateInfo::default()
             .stage(match self.function_type {
                 MTLFunctionType::Vertex => vk::ShaderStageFlags::VERTEX,
                 MTLFunctionType::Fragment => vk::ShaderStageFlags::FRAGMENT,
+                MTLFunctionType::Kernel => vk::ShaderStageFlags::COMPUTE,
             })
             .module(*self.vulkan_shader_module())
             .name(self.c_string_name())
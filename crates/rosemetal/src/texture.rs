@@ -0,0 +1,458 @@
+use crate::{MTLPixelFormat, MTLRegion, MTLResourceOptions};
+use anyhow::{Result, anyhow};
+
+#[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+use ash::vk;
+#[cfg(all(any(target_os = "macos", target_os = "ios"), not(feature = "moltenvk")))]
+use objc2::rc::Retained;
+#[cfg(all(any(target_os = "macos", target_os = "ios"), not(feature = "moltenvk")))]
+use objc2_metal::{
+    MTLTextureDescriptor as MetalMTLTextureDescriptor, MTLTextureType as MetalMTLTextureType,
+    MTLTextureUsage as MetalMTLTextureUsage,
+};
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum MTLTextureType {
+    Type1D,
+    Type1DArray,
+    #[default]
+    Type2D,
+    Type2DArray,
+    Type2DMultisample,
+    Type2DMultisampleArray,
+    TypeCube,
+    TypeCubeArray,
+    Type3D,
+}
+
+impl MTLTextureType {
+    pub fn is_array(self) -> bool {
+        matches!(
+            self,
+            Self::Type1DArray
+                | Self::Type2DArray
+                | Self::Type2DMultisampleArray
+                | Self::TypeCubeArray
+        )
+    }
+
+    pub fn is_cube(self) -> bool {
+        matches!(self, Self::TypeCube | Self::TypeCubeArray)
+    }
+
+    pub fn is_multisample(self) -> bool {
+        matches!(self, Self::Type2DMultisample | Self::Type2DMultisampleArray)
+    }
+
+    #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+    pub fn to_vulkan_image_type(self) -> vk::ImageType {
+        match self {
+            Self::Type1D | Self::Type1DArray => vk::ImageType::TYPE_1D,
+            Self::Type3D => vk::ImageType::TYPE_3D,
+            _ => vk::ImageType::TYPE_2D,
+        }
+    }
+
+    #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+    pub fn to_vulkan_view_type(self) -> vk::ImageViewType {
+        match self {
+            Self::Type1D => vk::ImageViewType::TYPE_1D,
+            Self::Type1DArray => vk::ImageViewType::TYPE_1D_ARRAY,
+            Self::Type2D | Self::Type2DMultisample => vk::ImageViewType::TYPE_2D,
+            Self::Type2DArray | Self::Type2DMultisampleArray => vk::ImageViewType::TYPE_2D_ARRAY,
+            Self::TypeCube => vk::ImageViewType::CUBE,
+            Self::TypeCubeArray => vk::ImageViewType::CUBE_ARRAY,
+            Self::Type3D => vk::ImageViewType::TYPE_3D,
+        }
+    }
+
+    #[cfg(all(any(target_os = "macos", target_os = "ios"), not(feature = "moltenvk")))]
+    pub fn to_metal(self) -> MetalMTLTextureType {
+        match self {
+            Self::Type1D => MetalMTLTextureType::Type1D,
+            Self::Type1DArray => MetalMTLTextureType::Type1DArray,
+            Self::Type2D => MetalMTLTextureType::Type2D,
+            Self::Type2DArray => MetalMTLTextureType::Type2DArray,
+            Self::Type2DMultisample => MetalMTLTextureType::Type2DMultisample,
+            Self::Type2DMultisampleArray => MetalMTLTextureType::Type2DMultisampleArray,
+            Self::TypeCube => MetalMTLTextureType::TypeCube,
+            Self::TypeCubeArray => MetalMTLTextureType::TypeCubeArray,
+            Self::Type3D => MetalMTLTextureType::Type3D,
+        }
+    }
+}
+
+/// How shaders and render passes are going to use a texture.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MTLTextureUsage {
+    pub shader_read: bool,
+    pub shader_write: bool,
+    pub render_target: bool,
+    /// Views of the texture can reinterpret its pixel format.
+    pub pixel_format_view: bool,
+}
+
+impl Default for MTLTextureUsage {
+    fn default() -> Self {
+        Self {
+            shader_read: true,
+            shader_write: false,
+            render_target: false,
+            pixel_format_view: false,
+        }
+    }
+}
+
+impl MTLTextureUsage {
+    pub fn render_target() -> Self {
+        Self {
+            shader_read: false,
+            render_target: true,
+            ..Default::default()
+        }
+    }
+
+    #[cfg(all(any(target_os = "macos", target_os = "ios"), not(feature = "moltenvk")))]
+    pub fn to_metal(self) -> MetalMTLTextureUsage {
+        let mut usage = MetalMTLTextureUsage::Unknown;
+
+        if self.shader_read {
+            usage |= MetalMTLTextureUsage::ShaderRead;
+        }
+        if self.shader_write {
+            usage |= MetalMTLTextureUsage::ShaderWrite;
+        }
+        if self.render_target {
+            usage |= MetalMTLTextureUsage::RenderTarget;
+        }
+        if self.pixel_format_view {
+            usage |= MetalMTLTextureUsage::PixelFormatView;
+        }
+
+        usage
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MTLTextureDescriptor {
+    pub texture_type: MTLTextureType,
+    pub pixel_format: MTLPixelFormat,
+    pub width: u32,
+    pub height: u32,
+    pub depth: u32,
+    pub mipmap_level_count: u32,
+    pub array_length: u32,
+    pub sample_count: u32,
+    pub usage: MTLTextureUsage,
+    pub resource_options: MTLResourceOptions,
+}
+
+impl MTLTextureDescriptor {
+    pub fn texture_2d(
+        pixel_format: MTLPixelFormat,
+        width: u32,
+        height: u32,
+        mipmapped: bool,
+    ) -> Self {
+        Self {
+            texture_type: MTLTextureType::Type2D,
+            pixel_format,
+            width,
+            height,
+            depth: 1,
+            mipmap_level_count: match mipmapped {
+                true => Self::full_mipmap_level_count(width.max(height)),
+                false => 1,
+            },
+            array_length: 1,
+            sample_count: 1,
+            usage: MTLTextureUsage::default(),
+            resource_options: MTLResourceOptions::default(),
+        }
+    }
+
+    pub fn texture_cube(pixel_format: MTLPixelFormat, size: u32, mipmapped: bool) -> Self {
+        Self {
+            texture_type: MTLTextureType::TypeCube,
+            ..Self::texture_2d(pixel_format, size, size, mipmapped)
+        }
+    }
+
+    /// Levels down to a single pixel.
+    pub fn full_mipmap_level_count(size: u32) -> u32 {
+        u32::BITS - size.max(1).leading_zeros()
+    }
+
+    /// Slices the texture has, counting each cube face.
+    pub fn layer_count(&self) -> u32 {
+        let faces = match self.texture_type.is_cube() {
+            true => 6,
+            false => 1,
+        };
+
+        self.array_length * faces
+    }
+
+    /// Rejects descriptors Metal wouldn't create a texture for.
+    pub fn validate(&self) -> Result<()> {
+        if self.width == 0 || self.height == 0 || self.depth == 0 {
+            return Err(anyhow!("Texture dimensions can't be zero."));
+        }
+
+        let one_dimensional = matches!(
+            self.texture_type,
+            MTLTextureType::Type1D | MTLTextureType::Type1DArray
+        );
+        if one_dimensional && self.height > 1 {
+            return Err(anyhow!(
+                "{:?} can't be {} pixels high.",
+                self.texture_type,
+                self.height
+            ));
+        }
+
+        if self.texture_type != MTLTextureType::Type3D && self.depth > 1 {
+            return Err(anyhow!(
+                "{:?} can't be {} pixels deep.",
+                self.texture_type,
+                self.depth
+            ));
+        }
+
+        if self.mipmap_level_count == 0
+            || self.mipmap_level_count
+                > Self::full_mipmap_level_count(self.width.max(self.height).max(self.depth))
+        {
+            return Err(anyhow!(
+                "Invalid mipmap level count {}.",
+                self.mipmap_level_count
+            ));
+        }
+
+        if self.array_length == 0 || (self.array_length > 1 && !self.texture_type.is_array()) {
+            return Err(anyhow!(
+                "Invalid array length {} for {:?}.",
+                self.array_length,
+                self.texture_type
+            ));
+        }
+
+        if !self.sample_count.is_power_of_two() || self.sample_count > 64 {
+            return Err(anyhow!("Invalid sample count {}.", self.sample_count));
+        }
+
+        if self.sample_count > 1 && !self.texture_type.is_multisample() {
+            return Err(anyhow!(
+                "{:?} can't have {} samples.",
+                self.texture_type,
+                self.sample_count
+            ));
+        }
+
+        if self.sample_count > 1 && self.mipmap_level_count > 1 {
+            return Err(anyhow!("Multisample textures can't have mipmaps."));
+        }
+
+        if self.texture_type.is_cube() && self.width != self.height {
+            return Err(anyhow!("Cube textures have to be square."));
+        }
+
+        Ok(())
+    }
+
+    /// Rejects CPU copies Metal doesn't allow, or that don't fit in the
+    /// texture or in `bytes_len`.
+    pub fn check_cpu_access(
+        &self,
+        region: MTLRegion,
+        mipmap_level: u32,
+        slice: u32,
+        bytes_len: usize,
+        bytes_per_row: usize,
+    ) -> Result<()> {
+        if !self.resource_options.is_host_visible() {
+            return Err(anyhow!(
+                "{:?} textures can't be accessed by the CPU.",
+                self.resource_options.storage_mode
+            ));
+        }
+
+        if self.pixel_format.is_stencil() && self.pixel_format != MTLPixelFormat::Stencil8 {
+            return Err(anyhow!(
+                "Combined depth and stencil textures can't be copied."
+            ));
+        }
+
+        if mipmap_level >= self.mipmap_level_count || slice >= self.layer_count() {
+            return Err(anyhow!(
+                "No mipmap level {} or slice {}.",
+                mipmap_level,
+                slice
+            ));
+        }
+
+        let level_size = |size: u32| (size >> mipmap_level).max(1);
+        let fits = |origin: u32, size: u32, limit: u32| {
+            size > 0
+                && origin
+                    .checked_add(size)
+                    .is_some_and(|end| end <= level_size(limit))
+        };
+
+        let depth = match self.texture_type {
+            MTLTextureType::Type3D => self.depth,
+            _ => 1,
+        };
+
+        if !fits(region.origin.x, region.size.width, self.width)
+            || !fits(region.origin.y, region.size.height, self.height)
+            || !fits(region.origin.z, region.size.depth, depth)
+        {
+            return Err(anyhow!(
+                "{:?} is outside of mipmap level {}.",
+                region,
+                mipmap_level
+            ));
+        }
+
+        // Compressed regions cover whole blocks, except where they reach
+        // the edge of the level.
+        let pixel_format = self.pixel_format;
+        let aligned = |origin: u32, size: u32, limit: u32, block: u32| {
+            origin.is_multiple_of(block)
+                && (size.is_multiple_of(block) || origin + size == level_size(limit))
+        };
+
+        if !aligned(
+            region.origin.x,
+            region.size.width,
+            self.width,
+            pixel_format.block_width(),
+        ) || !aligned(
+            region.origin.y,
+            region.size.height,
+            self.height,
+            pixel_format.block_height(),
+        ) {
+            return Err(anyhow!(
+                "{:?} isn't aligned to {:?} blocks.",
+                region,
+                pixel_format
+            ));
+        }
+
+        let row_size = pixel_format.bytes_per_row(region.size.width);
+        let rows =
+            pixel_format.block_rows(region.size.height) as usize * region.size.depth as usize;
+
+        if bytes_per_row < row_size || bytes_len < bytes_per_row * (rows - 1) + row_size {
+            return Err(anyhow!(
+                "{} bytes with {} bytes per row don't hold {:?}.",
+                bytes_len,
+                bytes_per_row,
+                region
+            ));
+        }
+
+        Ok(())
+    }
+
+    #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+    pub fn vulkan_extent(&self) -> vk::Extent3D {
+        match self.texture_type {
+            MTLTextureType::Type1D | MTLTextureType::Type1DArray => vk::Extent3D {
+                width: self.width,
+                height: 1,
+                depth: 1,
+            },
+            MTLTextureType::Type3D => vk::Extent3D {
+                width: self.width,
+                height: self.height,
+                depth: self.depth,
+            },
+            _ => vk::Extent3D {
+                width: self.width,
+                height: self.height,
+                depth: 1,
+            },
+        }
+    }
+
+    /// Every texture can be copied from and to. Memoryless textures can
+    /// only be transient attachments.
+    #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+    pub fn vulkan_usage(&self) -> vk::ImageUsageFlags {
+        let attachment = match self.pixel_format.is_depth() || self.pixel_format.is_stencil() {
+            true => vk::ImageUsageFlags::DEPTH_STENCIL_ATTACHMENT,
+            false => vk::ImageUsageFlags::COLOR_ATTACHMENT,
+        };
+
+        if self.resource_options.storage_mode == crate::MTLStorageMode::Memoryless {
+            return attachment | vk::ImageUsageFlags::TRANSIENT_ATTACHMENT;
+        }
+
+        let mut usage = vk::ImageUsageFlags::TRANSFER_SRC | vk::ImageUsageFlags::TRANSFER_DST;
+
+        if self.usage.shader_read {
+            usage |= vk::ImageUsageFlags::SAMPLED;
+        }
+        if self.usage.shader_write {
+            usage |= vk::ImageUsageFlags::STORAGE;
+        }
+        if self.usage.render_target {
+            usage |= attachment;
+        }
+
+        usage
+    }
+
+    #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+    pub fn vulkan_flags(&self) -> vk::ImageCreateFlags {
+        let mut flags = vk::ImageCreateFlags::empty();
+
+        if self.texture_type.is_cube() {
+            flags |= vk::ImageCreateFlags::CUBE_COMPATIBLE;
+        }
+        if self.usage.pixel_format_view {
+            flags |= vk::ImageCreateFlags::MUTABLE_FORMAT;
+        }
+
+        flags
+    }
+
+    #[cfg(all(any(target_os = "macos", target_os = "ios"), not(feature = "moltenvk")))]
+    pub fn to_metal(&self) -> Retained<MetalMTLTextureDescriptor> {
+        let descriptor = MetalMTLTextureDescriptor::new();
+
+        unsafe {
+            descriptor.setTextureType(self.texture_type.to_metal());
+            descriptor.setPixelFormat(self.pixel_format.to_metal());
+            descriptor.setWidth(self.width as usize);
+            descriptor.setHeight(self.height as usize);
+            descriptor.setDepth(self.depth as usize);
+            descriptor.setMipmapLevelCount(self.mipmap_level_count as usize);
+            descriptor.setArrayLength(self.array_length as usize);
+            descriptor.setSampleCount(self.sample_count as usize);
+            descriptor.setUsage(self.usage.to_metal());
+            descriptor.setResourceOptions(self.resource_options.to_metal());
+        }
+
+        descriptor
+    }
+}
+
+/// Copies `rows` rows of `row_size` bytes between buffers with different
+/// row pitches.
+pub(crate) fn copy_rows(
+    source: &[u8],
+    source_bytes_per_row: usize,
+    destination: &mut [u8],
+    destination_bytes_per_row: usize,
+    row_size: usize,
+    rows: usize,
+) {
+    for row in 0..rows {
+        let source = &source[row * source_bytes_per_row..][..row_size];
+        destination[row * destination_bytes_per_row..][..row_size].copy_from_slice(source);
+    }
+}
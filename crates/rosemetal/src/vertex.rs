@@ -0,0 +1,517 @@
+use std::collections::BTreeMap;
+
+#[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+use crate::MTLDevice;
+use anyhow::{Result, anyhow};
+
+#[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+use ash::vk;
+#[cfg(all(any(target_os = "macos", target_os = "ios"), not(feature = "moltenvk")))]
+use objc2::rc::Retained;
+#[cfg(all(any(target_os = "macos", target_os = "ios"), not(feature = "moltenvk")))]
+use objc2_metal::{
+    MTLVertexDescriptor as MetalMTLVertexDescriptor, MTLVertexFormat as MetalMTLVertexFormat,
+    MTLVertexStepFunction as MetalMTLVertexStepFunction,
+};
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MTLVertexFormat {
+    #[default]
+    Invalid,
+    UChar,
+    UChar2,
+    UChar3,
+    UChar4,
+    Char,
+    Char2,
+    Char3,
+    Char4,
+    UCharNormalized,
+    UChar2Normalized,
+    UChar3Normalized,
+    UChar4Normalized,
+    CharNormalized,
+    Char2Normalized,
+    Char3Normalized,
+    Char4Normalized,
+    UShort,
+    UShort2,
+    UShort3,
+    UShort4,
+    Short,
+    Short2,
+    Short3,
+    Short4,
+    UShortNormalized,
+    UShort2Normalized,
+    UShort3Normalized,
+    UShort4Normalized,
+    ShortNormalized,
+    Short2Normalized,
+    Short3Normalized,
+    Short4Normalized,
+    Half,
+    Half2,
+    Half3,
+    Half4,
+    Float,
+    Float2,
+    Float3,
+    Float4,
+    Int,
+    Int2,
+    Int3,
+    Int4,
+    UInt,
+    UInt2,
+    UInt3,
+    UInt4,
+    Int1010102Normalized,
+    UInt1010102Normalized,
+    UChar4NormalizedBgra,
+    FloatRg11b10,
+    FloatRgb9e5,
+}
+
+impl MTLVertexFormat {
+    /// Bytes one attribute of the format takes in a vertex buffer.
+    pub fn size(self) -> u32 {
+        match self {
+            Self::Invalid => 0,
+            Self::UChar | Self::Char | Self::UCharNormalized | Self::CharNormalized => 1,
+            Self::UChar2
+            | Self::Char2
+            | Self::UChar2Normalized
+            | Self::Char2Normalized
+            | Self::UShort
+            | Self::Short
+            | Self::UShortNormalized
+            | Self::ShortNormalized
+            | Self::Half => 2,
+            Self::UChar3 | Self::Char3 | Self::UChar3Normalized | Self::Char3Normalized => 3,
+            Self::UChar4
+            | Self::Char4
+            | Self::UChar4Normalized
+            | Self::Char4Normalized
+            | Self::UShort2
+            | Self::Short2
+            | Self::UShort2Normalized
+            | Self::Short2Normalized
+            | Self::Half2
+            | Self::Float
+            | Self::Int
+            | Self::UInt
+            | Self::Int1010102Normalized
+            | Self::UInt1010102Normalized
+            | Self::UChar4NormalizedBgra
+            | Self::FloatRg11b10
+            | Self::FloatRgb9e5 => 4,
+            Self::UShort3
+            | Self::Short3
+            | Self::UShort3Normalized
+            | Self::Short3Normalized
+            | Self::Half3 => 6,
+            Self::UShort4
+            | Self::Short4
+            | Self::UShort4Normalized
+            | Self::Short4Normalized
+            | Self::Half4
+            | Self::Float2
+            | Self::Int2
+            | Self::UInt2 => 8,
+            Self::Float3 | Self::Int3 | Self::UInt3 => 12,
+            Self::Float4 | Self::Int4 | Self::UInt4 => 16,
+        }
+    }
+
+    #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+    pub fn to_vulkan(self) -> vk::Format {
+        match self {
+            Self::Invalid => vk::Format::UNDEFINED,
+            Self::UChar => vk::Format::R8_UINT,
+            Self::UChar2 => vk::Format::R8G8_UINT,
+            Self::UChar3 => vk::Format::R8G8B8_UINT,
+            Self::UChar4 => vk::Format::R8G8B8A8_UINT,
+            Self::Char => vk::Format::R8_SINT,
+            Self::Char2 => vk::Format::R8G8_SINT,
+            Self::Char3 => vk::Format::R8G8B8_SINT,
+            Self::Char4 => vk::Format::R8G8B8A8_SINT,
+            Self::UCharNormalized => vk::Format::R8_UNORM,
+            Self::UChar2Normalized => vk::Format::R8G8_UNORM,
+            Self::UChar3Normalized => vk::Format::R8G8B8_UNORM,
+            Self::UChar4Normalized => vk::Format::R8G8B8A8_UNORM,
+            Self::CharNormalized => vk::Format::R8_SNORM,
+            Self::Char2Normalized => vk::Format::R8G8_SNORM,
+            Self::Char3Normalized => vk::Format::R8G8B8_SNORM,
+            Self::Char4Normalized => vk::Format::R8G8B8A8_SNORM,
+            Self::UShort => vk::Format::R16_UINT,
+            Self::UShort2 => vk::Format::R16G16_UINT,
+            Self::UShort3 => vk::Format::R16G16B16_UINT,
+            Self::UShort4 => vk::Format::R16G16B16A16_UINT,
+            Self::Short => vk::Format::R16_SINT,
+            Self::Short2 => vk::Format::R16G16_SINT,
+            Self::Short3 => vk::Format::R16G16B16_SINT,
+            Self::Short4 => vk::Format::R16G16B16A16_SINT,
+            Self::UShortNormalized => vk::Format::R16_UNORM,
+            Self::UShort2Normalized => vk::Format::R16G16_UNORM,
+            Self::UShort3Normalized => vk::Format::R16G16B16_UNORM,
+            Self::UShort4Normalized => vk::Format::R16G16B16A16_UNORM,
+            Self::ShortNormalized => vk::Format::R16_SNORM,
+            Self::Short2Normalized => vk::Format::R16G16_SNORM,
+            Self::Short3Normalized => vk::Format::R16G16B16_SNORM,
+            Self::Short4Normalized => vk::Format::R16G16B16A16_SNORM,
+            Self::Half => vk::Format::R16_SFLOAT,
+            Self::Half2 => vk::Format::R16G16_SFLOAT,
+            Self::Half3 => vk::Format::R16G16B16_SFLOAT,
+            Self::Half4 => vk::Format::R16G16B16A16_SFLOAT,
+            Self::Float => vk::Format::R32_SFLOAT,
+            Self::Float2 => vk::Format::R32G32_SFLOAT,
+            Self::Float3 => vk::Format::R32G32B32_SFLOAT,
+            Self::Float4 => vk::Format::R32G32B32A32_SFLOAT,
+            Self::Int => vk::Format::R32_SINT,
+            Self::Int2 => vk::Format::R32G32_SINT,
+            Self::Int3 => vk::Format::R32G32B32_SINT,
+            Self::Int4 => vk::Format::R32G32B32A32_SINT,
+            Self::UInt => vk::Format::R32_UINT,
+            Self::UInt2 => vk::Format::R32G32_UINT,
+            Self::UInt3 => vk::Format::R32G32B32_UINT,
+            Self::UInt4 => vk::Format::R32G32B32A32_UINT,
+            Self::Int1010102Normalized => vk::Format::A2B10G10R10_SNORM_PACK32,
+            Self::UInt1010102Normalized => vk::Format::A2B10G10R10_UNORM_PACK32,
+            Self::UChar4NormalizedBgra => vk::Format::B8G8R8A8_UNORM,
+            Self::FloatRg11b10 => vk::Format::B10G11R11_UFLOAT_PACK32,
+            Self::FloatRgb9e5 => vk::Format::E5B9G9R9_UFLOAT_PACK32,
+        }
+    }
+
+    /// What buffers of this format support on the device.
+    #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+    pub fn vulkan_buffer_features(self, device: &MTLDevice) -> vk::FormatFeatureFlags {
+        if self == Self::Invalid {
+            return vk::FormatFeatureFlags::empty();
+        }
+
+        unsafe {
+            device
+                .instance
+                .vulkan_instance()
+                .get_physical_device_format_properties(
+                    *device.vulkan_device().physical(),
+                    self.to_vulkan(),
+                )
+                .buffer_features
+        }
+    }
+
+    #[cfg(all(any(target_os = "macos", target_os = "ios"), not(feature = "moltenvk")))]
+    pub fn to_metal(self) -> MetalMTLVertexFormat {
+        match self {
+            Self::Invalid => MetalMTLVertexFormat::Invalid,
+            Self::UChar => MetalMTLVertexFormat::UChar,
+            Self::UChar2 => MetalMTLVertexFormat::UChar2,
+            Self::UChar3 => MetalMTLVertexFormat::UChar3,
+            Self::UChar4 => MetalMTLVertexFormat::UChar4,
+            Self::Char => MetalMTLVertexFormat::Char,
+            Self::Char2 => MetalMTLVertexFormat::Char2,
+            Self::Char3 => MetalMTLVertexFormat::Char3,
+            Self::Char4 => MetalMTLVertexFormat::Char4,
+            Self::UCharNormalized => MetalMTLVertexFormat::UCharNormalized,
+            Self::UChar2Normalized => MetalMTLVertexFormat::UChar2Normalized,
+            Self::UChar3Normalized => MetalMTLVertexFormat::UChar3Normalized,
+            Self::UChar4Normalized => MetalMTLVertexFormat::UChar4Normalized,
+            Self::CharNormalized => MetalMTLVertexFormat::CharNormalized,
+            Self::Char2Normalized => MetalMTLVertexFormat::Char2Normalized,
+            Self::Char3Normalized => MetalMTLVertexFormat::Char3Normalized,
+            Self::Char4Normalized => MetalMTLVertexFormat::Char4Normalized,
+            Self::UShort => MetalMTLVertexFormat::UShort,
+            Self::UShort2 => MetalMTLVertexFormat::UShort2,
+            Self::UShort3 => MetalMTLVertexFormat::UShort3,
+            Self::UShort4 => MetalMTLVertexFormat::UShort4,
+            Self::Short => MetalMTLVertexFormat::Short,
+            Self::Short2 => MetalMTLVertexFormat::Short2,
+            Self::Short3 => MetalMTLVertexFormat::Short3,
+            Self::Short4 => MetalMTLVertexFormat::Short4,
+            Self::UShortNormalized => MetalMTLVertexFormat::UShortNormalized,
+            Self::UShort2Normalized => MetalMTLVertexFormat::UShort2Normalized,
+            Self::UShort3Normalized => MetalMTLVertexFormat::UShort3Normalized,
+            Self::UShort4Normalized => MetalMTLVertexFormat::UShort4Normalized,
+            Self::ShortNormalized => MetalMTLVertexFormat::ShortNormalized,
+            Self::Short2Normalized => MetalMTLVertexFormat::Short2Normalized,
+            Self::Short3Normalized => MetalMTLVertexFormat::Short3Normalized,
+            Self::Short4Normalized => MetalMTLVertexFormat::Short4Normalized,
+            Self::Half => MetalMTLVertexFormat::Half,
+            Self::Half2 => MetalMTLVertexFormat::Half2,
+            Self::Half3 => MetalMTLVertexFormat::Half3,
+            Self::Half4 => MetalMTLVertexFormat::Half4,
+            Self::Float => MetalMTLVertexFormat::Float,
+            Self::Float2 => MetalMTLVertexFormat::Float2,
+            Self::Float3 => MetalMTLVertexFormat::Float3,
+            Self::Float4 => MetalMTLVertexFormat::Float4,
+            Self::Int => MetalMTLVertexFormat::Int,
+            Self::Int2 => MetalMTLVertexFormat::Int2,
+            Self::Int3 => MetalMTLVertexFormat::Int3,
+            Self::Int4 => MetalMTLVertexFormat::Int4,
+            Self::UInt => MetalMTLVertexFormat::UInt,
+            Self::UInt2 => MetalMTLVertexFormat::UInt2,
+            Self::UInt3 => MetalMTLVertexFormat::UInt3,
+            Self::UInt4 => MetalMTLVertexFormat::UInt4,
+            Self::Int1010102Normalized => MetalMTLVertexFormat::Int1010102Normalized,
+            Self::UInt1010102Normalized => MetalMTLVertexFormat::UInt1010102Normalized,
+            Self::UChar4NormalizedBgra => MetalMTLVertexFormat::UChar4Normalized_BGRA,
+            Self::FloatRg11b10 => MetalMTLVertexFormat::FloatRG11B10,
+            Self::FloatRgb9e5 => MetalMTLVertexFormat::FloatRGB9E5,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum MTLVertexStepFunction {
+    /// Every vertex and instance reads the same element.
+    Constant,
+    #[default]
+    PerVertex,
+    PerInstance,
+}
+
+impl MTLVertexStepFunction {
+    #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+    pub fn to_vulkan(self) -> vk::VertexInputRate {
+        match self {
+            // A constant buffer is read with a stride of 0.
+            Self::Constant | Self::PerVertex => vk::VertexInputRate::VERTEX,
+            Self::PerInstance => vk::VertexInputRate::INSTANCE,
+        }
+    }
+
+    #[cfg(all(any(target_os = "macos", target_os = "ios"), not(feature = "moltenvk")))]
+    pub fn to_metal(self) -> MetalMTLVertexStepFunction {
+        match self {
+            Self::Constant => MetalMTLVertexStepFunction::Constant,
+            Self::PerVertex => MetalMTLVertexStepFunction::PerVertex,
+            Self::PerInstance => MetalMTLVertexStepFunction::PerInstance,
+        }
+    }
+}
+
+/// Where a `[[stage_in]]` attribute is read from.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct MTLVertexAttributeDescriptor {
+    pub format: MTLVertexFormat,
+    pub offset: u32,
+    pub buffer_index: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MTLVertexBufferLayoutDescriptor {
+    pub stride: u32,
+    pub step_function: MTLVertexStepFunction,
+    /// Instances drawn before a `PerInstance` buffer moves to its next
+    /// element.
+    pub step_rate: u32,
+}
+
+impl Default for MTLVertexBufferLayoutDescriptor {
+    fn default() -> Self {
+        Self {
+            stride: 0,
+            step_function: MTLVertexStepFunction::PerVertex,
+            step_rate: 1,
+        }
+    }
+}
+
+/// Layout of the vertex buffers a pipeline reads its `[[stage_in]]` from.
+/// Attributes are keyed by their `[[attribute(n)]]`, layouts by the buffer
+/// index vertex buffers are bound at.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct MTLVertexDescriptor {
+    pub attributes: BTreeMap<u32, MTLVertexAttributeDescriptor>,
+    pub layouts: BTreeMap<u32, MTLVertexBufferLayoutDescriptor>,
+}
+
+impl MTLVertexDescriptor {
+    /// Rejects descriptors Metal wouldn't create a pipeline with.
+    pub fn validate(&self) -> Result<()> {
+        for (index, layout) in &self.layouts {
+            if !layout.stride.is_multiple_of(4) {
+                return Err(anyhow!(
+                    "Stride {} of buffer {} isn't a multiple of 4.",
+                    layout.stride,
+                    index
+                ));
+            }
+
+            if layout.step_function == MTLVertexStepFunction::PerInstance && layout.step_rate == 0 {
+                return Err(anyhow!(
+                    "Buffer {} steps per instance at a rate of 0.",
+                    index
+                ));
+            }
+        }
+
+        for (index, attribute) in &self.attributes {
+            if attribute.format == MTLVertexFormat::Invalid {
+                return Err(anyhow!("Attribute {} has no format.", index));
+            }
+
+            let layout = self.layouts.get(&attribute.buffer_index).ok_or(anyhow!(
+                "Attribute {} reads from buffer {}, which has no layout.",
+                index,
+                attribute.buffer_index
+            ))?;
+
+            if layout.step_function != MTLVertexStepFunction::Constant
+                && attribute.offset + attribute.format.size() > layout.stride
+            {
+                return Err(anyhow!(
+                    "Attribute {} doesn't fit in the stride of buffer {}.",
+                    index,
+                    attribute.buffer_index
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Binds each layout at its buffer index and each attribute at the
+    /// location its `[[attribute(n)]]` was translated to.
+    #[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+    pub fn to_vulkan(&self, device: &MTLDevice) -> Result<VulkanVertexInputState> {
+        self.validate()?;
+
+        let features = device.vulkan_device().features();
+        let mut result = VulkanVertexInputState::default();
+
+        for (index, layout) in &self.layouts {
+            if *index >= features.max_vertex_input_bindings {
+                return Err(anyhow!(
+                    "Buffer {} is past the {} vertex buffers the device binds.",
+                    index,
+                    features.max_vertex_input_bindings
+                ));
+            }
+
+            result.bindings.push(
+                vk::VertexInputBindingDescription::default()
+                    .binding(*index)
+                    .stride(match layout.step_function {
+                        MTLVertexStepFunction::Constant => 0,
+                        _ => layout.stride,
+                    })
+                    .input_rate(layout.step_function.to_vulkan()),
+            );
+
+            if layout.step_function != MTLVertexStepFunction::PerInstance || layout.step_rate == 1 {
+                continue;
+            }
+
+            if !features.vertex_attribute_divisor {
+                return Err(anyhow!(
+                    "Buffer {} steps every {} instances, which needs VK_EXT_vertex_attribute_divisor.",
+                    index,
+                    layout.step_rate
+                ));
+            }
+
+            if layout.step_rate > features.max_vertex_attrib_divisor {
+                return Err(anyhow!(
+                    "Buffer {} steps every {} instances, the device steps at most every {}.",
+                    index,
+                    layout.step_rate,
+                    features.max_vertex_attrib_divisor
+                ));
+            }
+
+            result.divisors.push(
+                vk::VertexInputBindingDivisorDescriptionEXT::default()
+                    .binding(*index)
+                    .divisor(layout.step_rate),
+            );
+        }
+
+        for (index, attribute) in &self.attributes {
+            // Most devices can't read the 3 component 8 and 16-bit formats.
+            if !attribute
+                .format
+                .vulkan_buffer_features(device)
+                .contains(vk::FormatFeatureFlags::VERTEX_BUFFER)
+            {
+                return Err(anyhow!(
+                    "Attribute {} is {:?}, which the device can't read from vertex buffers.",
+                    index,
+                    attribute.format
+                ));
+            }
+
+            result.attributes.push(
+                vk::VertexInputAttributeDescription::default()
+                    .location(*index)
+                    .binding(attribute.buffer_index)
+                    .format(attribute.format.to_vulkan())
+                    .offset(attribute.offset),
+            );
+        }
+
+        Ok(result)
+    }
+
+    #[cfg(all(any(target_os = "macos", target_os = "ios"), not(feature = "moltenvk")))]
+    pub fn to_metal(&self) -> Retained<MetalMTLVertexDescriptor> {
+        let descriptor = unsafe { MetalMTLVertexDescriptor::new() };
+
+        unsafe {
+            for (index, attribute) in &self.attributes {
+                let native = descriptor
+                    .attributes()
+                    .objectAtIndexedSubscript(*index as usize);
+
+                native.setFormat(attribute.format.to_metal());
+                native.setOffset(attribute.offset as usize);
+                native.setBufferIndex(attribute.buffer_index as usize);
+            }
+
+            for (index, layout) in &self.layouts {
+                let native = descriptor
+                    .layouts()
+                    .objectAtIndexedSubscript(*index as usize);
+
+                native.setStride(layout.stride as usize);
+                native.setStepFunction(layout.step_function.to_metal());
+                native.setStepRate(layout.step_rate as usize);
+            }
+        }
+
+        descriptor
+    }
+}
+
+/// Descriptions a `VkPipelineVertexInputStateCreateInfo` points to.
+#[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+#[derive(Debug, Default, Clone)]
+pub struct VulkanVertexInputState {
+    pub bindings: Vec<vk::VertexInputBindingDescription>,
+    pub attributes: Vec<vk::VertexInputAttributeDescription>,
+    /// Step rates of instanced bindings that don't step every instance.
+    pub divisors: Vec<vk::VertexInputBindingDivisorDescriptionEXT>,
+}
+
+#[cfg(any(not(any(target_os = "macos", target_os = "ios")), feature = "moltenvk"))]
+impl VulkanVertexInputState {
+    pub fn create_info(&self) -> vk::PipelineVertexInputStateCreateInfo<'_> {
+        vk::PipelineVertexInputStateCreateInfo::default()
+            .vertex_binding_descriptions(&self.bindings)
+            .vertex_attribute_descriptions(&self.attributes)
+    }
+
+    /// Has to be chained onto `create_info` when there is one.
+    pub fn divisor_state(&self) -> Option<vk::PipelineVertexInputDivisorStateCreateInfoEXT<'_>> {
+        match self.divisors.is_empty() {
+            true => None,
+            false => Some(
+                vk::PipelineVertexInputDivisorStateCreateInfoEXT::default()
+                    .vertex_binding_divisors(&self.divisors),
+            ),
+        }
+    }
+}
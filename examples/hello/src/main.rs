@@ -17,7 +17,7 @@ pub struct State {
     view: Arc<MTLView>,
     queue: Arc<MTLCommandQueue>,
     render_pass: Arc<MTLRenderPass>,
-    render_pipeline_state: MTLRenderPipelineState,
+    render_pipeline_state: Arc<MTLRenderPipelineState>,
 
     triangle_buffer: Arc<MTLBuffer<MTLFloat3>>,
 }
@@ -47,7 +47,8 @@ impl State {
                 MTLFloat3::new(position, -position, 0.0),
                 MTLFloat3::new(0.0, position, 0.0),
             ],
-            MTLBufferUsage::Vertex,
+            MTLBufferUsage::STORAGE,
+            MTLResourceOptions::default(),
         )?;
 
         let library = device.new_library(&std::fs::read("Shaders.metallib")?)?;
@@ -120,7 +121,9 @@ impl State {
             .set_render_pipeline_state(&self.render_pipeline_state)
             .unwrap();
 
-        encoder.set_vertex_buffer(&self.triangle_buffer).unwrap();
+        encoder
+            .set_vertex_buffer(&self.triangle_buffer, 0, 0)
+            .unwrap();
         encoder
             .draw_primitives(MTLPrimitiveType::Triangle, 0, 3)
             .unwrap();